@@ -0,0 +1,35 @@
+#![allow(dead_code)]
+
+use scribe_backend::seed::{SeedOptions, seed};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Corpus sizes every benchmark runs against.
+pub const SIZES: [usize; 2] = [1_000, 5_000];
+
+/// A generated site, removed when dropped.
+pub struct Corpus {
+    pub dir: TempDir,
+    pub files: Vec<PathBuf>,
+}
+
+impl Corpus {
+    /// Writes `articles` articles with `scribe seed`'s generator, always
+    /// with the same seed so runs compare like with like.
+    pub fn generate(articles: usize) -> Self {
+        let dir = TempDir::new().unwrap();
+        let options = SeedOptions {
+            articles,
+            notes: 0,
+            seed: 1,
+            article_dir: dir.path().join("article"),
+            notes_dir: dir.path().join("notes"),
+        };
+        let files = seed(&options).unwrap();
+        Corpus { dir, files }
+    }
+
+    pub fn article_dir(&self) -> PathBuf {
+        self.dir.path().join("article")
+    }
+}
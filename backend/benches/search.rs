@@ -0,0 +1,66 @@
+//! Benchmarks of full-text search: indexing a site and answering queries.
+
+mod common;
+
+use common::{Corpus, SIZES};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use scribe_backend::services::search::SearchService;
+use scribe_backend::services::service::ArticleStore;
+use std::hint::black_box;
+
+/// The heap the server indexes with by default.
+const HEAP_SIZE: usize = 50_000_000;
+
+const QUERIES: [(&str, &str); 3] = [
+    ("term", "tokio"),
+    ("terms", "async runtime cache"),
+    ("phrase", "\"the server\""),
+];
+
+fn index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search/index_articles");
+    group.sample_size(10);
+    for size in SIZES {
+        let corpus = Corpus::generate(size);
+        let articles = ArticleStore::new(corpus.article_dir(), true)
+            .unwrap()
+            .load_full_articles();
+        let service = SearchService::new(corpus.dir.path().join("index")).unwrap();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &articles,
+            |b, articles| b.iter(|| service.index_articles(articles, HEAP_SIZE).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("search/search");
+    for size in SIZES {
+        let corpus = Corpus::generate(size);
+        let articles = ArticleStore::new(corpus.article_dir(), true)
+            .unwrap()
+            .load_full_articles();
+        let service = SearchService::new(corpus.dir.path().join("index")).unwrap();
+        service.index_articles(&articles, HEAP_SIZE).unwrap();
+        for (name, query) in QUERIES {
+            for highlights in [false, true] {
+                let id = if highlights {
+                    format!("{}_highlighted", name)
+                } else {
+                    name.to_string()
+                };
+                group.bench_function(BenchmarkId::new(id, size), |b| {
+                    b.to_async(&runtime)
+                        .iter(|| service.search(black_box(query), 10, highlights))
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, index, search);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Benchmarks of the article store: loading a site, filtered listings and
+//! picking up an edited file.
+
+mod common;
+
+use common::{Corpus, SIZES};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use scribe_backend::services::service::ArticleStore;
+use std::fs;
+use std::hint::black_box;
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("store/new");
+    group.sample_size(10);
+    for size in SIZES {
+        let corpus = Corpus::generate(size);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &corpus, |b, corpus| {
+            b.iter(|| ArticleStore::new(corpus.article_dir(), true).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("store/query");
+    for size in SIZES {
+        let corpus = Corpus::generate(size);
+        let store = ArticleStore::new(corpus.article_dir(), true).unwrap();
+        group.bench_with_input(BenchmarkId::new("tag", size), &store, |b, store| {
+            b.iter(|| {
+                store.query_paginated(
+                    |a| a.metadata.is_published() && a.metadata.tags.iter().any(|t| t == "rust"),
+                    black_box(20),
+                    10,
+                )
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("category_and_tag", size),
+            &store,
+            |b, store| {
+                b.iter(|| {
+                    store
+                        .query(
+                            |a| {
+                                a.metadata.is_published()
+                                    && a.metadata.category.as_deref() == Some("rust/async")
+                                    && a.metadata.tags.iter().any(|t| t == "tokio")
+                            },
+                            black_box(0),
+                            10,
+                        )
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn incremental_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("store/incremental_update");
+    group.sample_size(20);
+    for size in SIZES {
+        let corpus = Corpus::generate(size);
+        let mut store = ArticleStore::new(corpus.article_dir(), true).unwrap();
+        group.bench_function(BenchmarkId::new("unchanged", size), |b| {
+            b.iter(|| store.incremental_update().unwrap())
+        });
+
+        let edited = &corpus.files[size / 2];
+        let original = fs::read_to_string(edited).unwrap();
+        let mut edits = 0;
+        group.bench_function(BenchmarkId::new("one_edit", size), |b| {
+            b.iter_batched(
+                || {
+                    edits += 1;
+                    fs::write(edited, format!("{}\nEdit {}.\n", original, edits)).unwrap();
+                },
+                |()| assert!(store.incremental_update().unwrap()),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, load, query, incremental_update);
+criterion_main!(benches);
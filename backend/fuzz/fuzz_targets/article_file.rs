@@ -0,0 +1,22 @@
+//! Loads a store whose only article holds the input, so arbitrary bytes go
+//! through the same reading, front matter parsing and indexing as files
+//! on disk. Malformed files must be reported, never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scribe_backend::services::service::ArticleStore;
+use std::fs;
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+/// The content directory, reused across runs.
+static DIR: OnceLock<TempDir> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let dir = DIR.get_or_init(|| TempDir::new().unwrap());
+    let category = dir.path().join("rust");
+    fs::create_dir_all(&category).unwrap();
+    fs::write(category.join("fuzzed.md"), data).unwrap();
+    let _ = ArticleStore::new(dir.path(), true);
+});
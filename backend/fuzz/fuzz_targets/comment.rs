@@ -0,0 +1,18 @@
+//! Runs comment bodies through the markdown rendering and sanitization
+//! they get before being stored. Whatever the input, no script tag
+//! may survive.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scribe_backend::services::comment_service::{sanitize_content, sanitize_html};
+
+fuzz_target!(|text: &str| {
+    for html in [sanitize_content(text), sanitize_html(text)] {
+        assert!(
+            !html.to_ascii_lowercase().contains("<script"),
+            "script kept in {}",
+            html
+        );
+    }
+});
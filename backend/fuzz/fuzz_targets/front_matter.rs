@@ -0,0 +1,27 @@
+//! Extracts and rewrites front matter. When the input holds valid article
+//! metadata, patching it with a changed title must still give YAML that
+//! reads back as the new metadata.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scribe_backend::models::article::Metadata;
+use scribe_backend::services::front_matter::{front_matter_yaml, patch_front_matter};
+
+fuzz_target!(|text: &str| {
+    let Some(yaml) = front_matter_yaml(text) else {
+        return;
+    };
+    let Ok(mut metadata) = serde_yaml::from_str::<Metadata>(yaml) else {
+        return;
+    };
+    metadata.title.push_str(" (edited)");
+    let patched = patch_front_matter(yaml, &metadata).unwrap();
+    let reread: Metadata = serde_yaml::from_str(&patched).unwrap();
+    assert_eq!(
+        serde_yaml::to_value(&reread).unwrap(),
+        serde_yaml::to_value(&metadata).unwrap(),
+        "patched front matter:\n{}",
+        patched
+    );
+});
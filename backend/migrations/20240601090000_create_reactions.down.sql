@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_reactions_article_slug;
+DROP TABLE IF EXISTS reactions;
@@ -0,0 +1,10 @@
+CREATE TABLE reactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_slug TEXT NOT NULL,
+    user_github_id INTEGER NOT NULL,
+    emoji TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (article_slug, user_github_id, emoji)
+);
+
+CREATE INDEX idx_reactions_article_slug ON reactions(article_slug);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS poll_votes;
+DROP INDEX IF EXISTS idx_polls_article_slug;
+DROP TABLE IF EXISTS polls;
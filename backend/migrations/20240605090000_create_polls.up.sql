@@ -0,0 +1,18 @@
+CREATE TABLE polls (
+    id TEXT PRIMARY KEY,
+    article_slug TEXT,
+    question TEXT NOT NULL,
+    options TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_polls_article_slug ON polls(article_slug);
+
+CREATE TABLE poll_votes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    poll_id TEXT NOT NULL,
+    option_index INTEGER NOT NULL,
+    voter_key TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (poll_id, voter_key)
+);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_guestbook_entries_approved;
+DROP TABLE IF EXISTS guestbook_entries;
@@ -0,0 +1,10 @@
+CREATE TABLE guestbook_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    author_github_id INTEGER NOT NULL,
+    author_login TEXT NOT NULL,
+    content TEXT NOT NULL,
+    approved INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_guestbook_entries_approved ON guestbook_entries(approved);
@@ -0,0 +1,7 @@
+CREATE TABLE redirects (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    from_path TEXT NOT NULL UNIQUE,
+    to_url TEXT NOT NULL,
+    status INTEGER NOT NULL DEFAULT 301,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
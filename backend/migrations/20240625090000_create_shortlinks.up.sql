@@ -0,0 +1,6 @@
+CREATE TABLE shortlinks (
+    code TEXT PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    clicks INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_article_views_slug;
+DROP TABLE IF EXISTS article_views;
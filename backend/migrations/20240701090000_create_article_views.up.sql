@@ -0,0 +1,11 @@
+CREATE TABLE article_views (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    slug TEXT NOT NULL,
+    viewed_on TEXT NOT NULL DEFAULT (date('now')),
+    referrer_host TEXT,
+    utm_source TEXT,
+    utm_medium TEXT,
+    utm_campaign TEXT
+);
+
+CREATE INDEX idx_article_views_slug ON article_views(slug, viewed_on);
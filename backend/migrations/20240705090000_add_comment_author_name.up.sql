@@ -0,0 +1,2 @@
+-- Authors of imported comments that have no GitHub account.
+ALTER TABLE comments ADD COLUMN author_name TEXT;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_article_suggestions_status;
+DROP TABLE IF EXISTS article_suggestions;
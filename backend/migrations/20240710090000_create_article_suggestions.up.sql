@@ -0,0 +1,13 @@
+CREATE TABLE article_suggestions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_slug TEXT NOT NULL,
+    author_github_id INTEGER NOT NULL,
+    author_login TEXT NOT NULL,
+    original TEXT NOT NULL,
+    replacement TEXT NOT NULL,
+    note TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_article_suggestions_status ON article_suggestions(status);
@@ -0,0 +1,9 @@
+-- Per-key override of the configured daily quota; 0 lifts the limit.
+ALTER TABLE api_keys ADD COLUMN daily_quota INTEGER;
+
+CREATE TABLE api_key_usage (
+    key_id INTEGER NOT NULL REFERENCES api_keys(id),
+    day TEXT NOT NULL,
+    requests INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day)
+);
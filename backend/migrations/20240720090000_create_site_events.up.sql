@@ -0,0 +1,4 @@
+CREATE TABLE site_events (
+    name TEXT PRIMARY KEY,
+    occurred_at TEXT NOT NULL
+);
@@ -0,0 +1,22 @@
+CREATE TABLE preview_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_slug TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    reviewer TEXT,
+    expires_at TEXT NOT NULL,
+    revoked BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_preview_links_article_slug ON preview_links(article_slug);
+
+CREATE TABLE review_comments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preview_id INTEGER NOT NULL,
+    article_slug TEXT NOT NULL,
+    author_name TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_review_comments_article_slug ON review_comments(article_slug);
@@ -0,0 +1,6 @@
+CREATE TABLE article_locks (
+    article_slug TEXT PRIMARY KEY,
+    locked_by TEXT NOT NULL,
+    locked_until TEXT NOT NULL,
+    locked_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,14 @@
+CREATE TABLE saved_searches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    notify INTEGER NOT NULL DEFAULT 0,
+    last_comment_id INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE saved_search_hits (
+    saved_search_id INTEGER NOT NULL,
+    slug TEXT NOT NULL,
+    PRIMARY KEY (saved_search_id, slug)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE moderation_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pattern TEXT NOT NULL,
+    regex INTEGER NOT NULL DEFAULT 0,
+    action TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
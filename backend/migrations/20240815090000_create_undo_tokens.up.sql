@@ -0,0 +1,5 @@
+CREATE TABLE undo_tokens (
+    token_hash TEXT PRIMARY KEY,
+    snapshot TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
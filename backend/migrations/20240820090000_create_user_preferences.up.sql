@@ -0,0 +1,10 @@
+CREATE TABLE user_preferences (
+    github_id INTEGER PRIMARY KEY,
+    display_name TEXT,
+    bio TEXT,
+    avatar TEXT,
+    website TEXT,
+    theme TEXT,
+    language TEXT,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS fediverse_threads;
+DROP TABLE IF EXISTS fediverse_replies;
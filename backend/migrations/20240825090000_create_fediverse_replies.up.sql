@@ -0,0 +1,19 @@
+CREATE TABLE fediverse_replies (
+    article_slug TEXT NOT NULL,
+    id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    in_reply_to_id TEXT,
+    author_acct TEXT NOT NULL,
+    author_name TEXT NOT NULL,
+    author_url TEXT NOT NULL,
+    author_avatar TEXT,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (article_slug, id)
+);
+
+CREATE TABLE fediverse_threads (
+    article_slug TEXT PRIMARY KEY,
+    post_url TEXT NOT NULL,
+    fetched_at TEXT NOT NULL
+);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS evergreen_reminders;
@@ -0,0 +1,10 @@
+CREATE TABLE evergreen_reminders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    article_slug TEXT NOT NULL,
+    views INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    decided_at TEXT
+);
+
+CREATE INDEX idx_evergreen_reminders_slug ON evergreen_reminders(article_slug, created_at);
@@ -0,0 +1,13 @@
+CREATE TABLE content_ledger (
+    collection TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    -- NULL once the entry is no longer public.
+    fingerprint TEXT,
+    revision INTEGER NOT NULL,
+    -- Milliseconds since the epoch.
+    changed_at INTEGER NOT NULL,
+    PRIMARY KEY (collection, slug)
+);
+
+CREATE INDEX idx_content_ledger_revision ON content_ledger(revision);
+CREATE INDEX idx_content_ledger_changed_at ON content_ledger(changed_at);
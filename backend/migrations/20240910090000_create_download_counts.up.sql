@@ -0,0 +1,7 @@
+CREATE TABLE download_counts (
+    slug TEXT NOT NULL,
+    -- The `name` of the download in the article's front matter.
+    name TEXT NOT NULL,
+    downloads INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (slug, name)
+);
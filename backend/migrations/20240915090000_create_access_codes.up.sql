@@ -0,0 +1,10 @@
+CREATE TABLE access_codes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    code_hash TEXT NOT NULL UNIQUE,
+    -- NULL for codes that can be redeemed any number of times.
+    max_redemptions INTEGER,
+    redemptions INTEGER NOT NULL DEFAULT 0,
+    revoked INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
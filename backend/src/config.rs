@@ -1,7 +1,15 @@
+use crate::models::user::User;
+use crate::server::body_limit::BodyGroup;
+use crate::server::client_ip::TrustedProxies;
+use crate::services::error_reporter::Dsn;
+use crate::services::taxonomy::TaxonomyRules;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path};
 use std::sync::Arc;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::layer::SubscriberExt;
@@ -9,10 +17,20 @@ use tracing_subscriber::util::SubscriberInitExt;
 
 pub const ARTICLE_DIR: &str = "article";
 pub const NOTES_DIR: &str = "notes";
+pub const PAGES_DIR: &str = "pages";
+pub const DATA_DIR: &str = "data";
+pub const TAXONOMY_DIR: &str = "taxonomy";
+pub const COLLECTIONS_FILE: &str = "collections.yaml";
+pub const GLOSSARY_DIR: &str = "glossary";
+pub const DATABASE_URL: &str = "sqlite://comments.db";
 pub const SERVER_ADDR: &str = "127.0.0.1:3000";
 pub const ENABLE_NESTED_CATEGORIES: bool = true;
 pub const CACHE_MAX_CAPACITY: u64 = 1_000;
 pub const CACHE_TTL_SECONDS: u64 = 60;
+/// Lifetime of cached article counters, short so they stay close to live.
+pub const META_CACHE_TTL_SECONDS: u64 = 5;
+/// Lifetime of unfurled link previews; pages rarely change their cards.
+pub const UNFURL_CACHE_TTL_SECONDS: u64 = 60 * 60;
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -20,35 +38,807 @@ pub struct Config {
     #[serde(default = "default_hostname")]
     pub hostname: String,
     pub latest_articles_count: usize,
+    /// Sections of `GET /api/home`, in order, e.g. `[[home]]` tables with
+    /// `kind = "latest"`. Without any, the home page lists the latest
+    /// articles.
+    #[serde(default = "default_home")]
+    pub home: Vec<HomeSection>,
+    /// Menu of `GET /api/navigation`, as `[[navigation]]` tables. Pages
+    /// with a `menu` entry are added to it.
+    #[serde(default)]
+    pub navigation: Vec<NavItem>,
+    /// Switches for frontend features that are not live yet, keyed by
+    /// name, e.g. `[flags.comments_ui]`. `GET /api/flags` lists the ones on
+    /// for the requester.
+    #[serde(default)]
+    pub flags: BTreeMap<String, FeatureFlag>,
     #[serde(default)]
     pub comments: bool,
     pub github_redirect_url: String,
+    /// Base URL of the GitHub REST API; GitHub Enterprise Server serves it
+    /// at `https://<host>/api/v3`.
+    #[serde(default = "default_github_api_url")]
+    pub github_api_url: String,
     #[serde(default = "default_search_index_dir")]
     pub search_index_dir: String,
     #[serde(default)]
     pub enable_full_text_search: bool,
     #[serde(default = "default_search_index_heap_size")]
     pub search_index_heap_size: usize,
+    /// Minimum seconds between search index commits. Edits made in between
+    /// are committed together; 0 commits as soon as the last commit is done.
+    #[serde(default)]
+    pub search_commit_debounce_secs: u64,
     #[serde(default = "default_content_search_limit")]
     pub content_search_limit: usize,
+    /// Number of latest articles whose content is loaded into memory at
+    /// startup; 0 disables the warmup.
+    #[serde(default)]
+    pub warmup_articles: usize,
+    /// Saves the parsed articles and notes on shutdown and restores them at
+    /// the next start, re-reading only the files changed since.
+    #[serde(default = "default_store_snapshots")]
+    pub store_snapshots: bool,
+    #[serde(default = "default_reaction_emojis")]
+    pub reaction_emojis: Vec<String>,
+    #[serde(default)]
+    pub guestbook_moderation: bool,
+    /// Refuses new comments and guestbook entries while serving the ones
+    /// already stored, e.g. during a spam wave. The admin can also pause
+    /// and resume them at runtime.
+    #[serde(default)]
+    pub comments_paused: bool,
+    /// Seconds an admin deletion can be undone for with the `undo_token`
+    /// returned with it.
+    #[serde(default = "default_undo_window_secs")]
+    pub undo_window_secs: u64,
+    /// Days articles deleted through the API stay in the trash before the
+    /// `purge_trash` job removes them for good.
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    /// Refuses writes through the API, for deployments where content only
+    /// changes through the repository.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Creates the article and notes directories, with a sample article,
+    /// when they are missing at startup instead of refusing to start.
+    #[serde(default)]
+    pub bootstrap_content: bool,
+    /// Addresses or CIDR ranges of reverse proxies whose
+    /// `X-Forwarded-For` is believed. Loopback by default.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<String>,
+    /// URL that receives a JSON `POST` when readers leave something for the
+    /// author to review, such as a suggested correction.
+    #[serde(default)]
+    pub notify_webhook: Option<String>,
+    /// Downstream builds the author can trigger, keyed by name, e.g.
+    /// `[deploy_hooks.netlify]`.
+    #[serde(default)]
+    pub deploy_hooks: HashMap<String, DeployHookConfig>,
+    /// Categories, subcategories included, that are hidden from anonymous
+    /// visitors, e.g. `["work"]`.
+    #[serde(default)]
+    pub restricted_categories: Vec<String>,
+    /// Folder under the notes directory holding daily notes, named by
+    /// date.
+    #[serde(default = "default_journal_dir")]
+    pub journal_dir: String,
+    /// Cron schedules for background jobs, keyed by job name.
+    #[serde(default)]
+    pub jobs: HashMap<String, String>,
+    /// HTTP-date announced in the `Sunset` header of legacy `/api` responses.
+    #[serde(default)]
+    pub legacy_api_sunset: Option<String>,
+    /// IANA timezone of the site, e.g. `Asia/Shanghai`. Front matter dates
+    /// without an offset are read in it, and archives count days in it.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default)]
+    pub goals: GoalsConfig,
+    /// OpenAI-compatible endpoint used to draft missing descriptions. The
+    /// integration is off unless this table is present.
+    #[serde(default)]
+    pub summarizer: Option<SummarizerConfig>,
+    /// External HTML-to-PDF converter behind `?format=pdf` downloads. PDF
+    /// export is off unless this table is present.
+    #[serde(default)]
+    pub pdf: Option<PdfConfig>,
+    /// Repository the content is kept in, for "suggest an edit" links. API
+    /// responses carry no `edit_url` unless this table is present.
+    #[serde(default)]
+    pub repository: Option<RepositoryConfig>,
+    /// Primary instance a replica copies content from with the
+    /// `sync_from_primary` job. Replicas must be `read_only`.
+    #[serde(default)]
+    pub sync: Option<SyncConfig>,
+    /// Git repository whose branches the author can check out and review
+    /// under `/preview/{branch}/api`. Unavailable unless this table is
+    /// present.
+    #[serde(default)]
+    pub preview_branches: Option<PreviewBranchesConfig>,
+    /// Search engines and WebSub hub told when an article is published.
+    /// Nothing is pinged unless this table is present.
+    #[serde(default)]
+    pub pings: Option<PingConfig>,
+    /// Social accounts newly published articles are posted to. Nothing is
+    /// cross-posted unless this table is present.
+    #[serde(default)]
+    pub crosspost: Option<CrosspostConfig>,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub body_limits: BodyLimitsConfig,
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+    #[serde(default)]
+    pub pagination: PaginationConfig,
+    #[serde(default)]
+    pub api_keys: ApiKeysConfig,
+    #[serde(default)]
+    pub public_api: PublicApiConfig,
+    #[serde(default)]
+    pub admin_lockout: AdminLockoutConfig,
+    #[serde(default)]
+    pub taxonomy: TaxonomyConfig,
+    /// Hunspell dictionaries the content lint checks spelling against.
+    /// Spelling is not checked unless this table is present.
+    #[serde(default)]
+    pub spellcheck: Option<SpellcheckConfig>,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub podcast: PodcastConfig,
+    #[serde(default)]
+    pub glossary: GlossaryConfig,
+    #[serde(default)]
+    pub members: MembersConfig,
+    /// Supporters listed by `GET /api/sponsors`, which is off without
+    /// this table.
+    #[serde(default)]
+    pub sponsors: Option<SponsorsConfig>,
+    /// Where panics and internal server errors are reported, if anywhere.
+    #[serde(default)]
+    pub error_reporting: Option<ErrorReportingConfig>,
+    /// Cache, compression, rate limit and CORS switched on or off for
+    /// groups of routes, e.g. `[[route_layers]]` with `prefix =
+    /// "/api/search"` and `cache = false`.
+    #[serde(default)]
+    pub route_layers: Vec<RouteLayersConfig>,
 }
 
-impl Config {
-    pub fn validate(&self) -> Result<(), String> {
-        if !Path::new(ARTICLE_DIR).exists() {
-            return Err(format!(
-                "Article directory does not exist: {}",
-                ARTICLE_DIR
-            ));
+/// Writing targets reported by `GET /api/admin/goals`. Unset goals are not
+/// tracked.
+#[derive(Deserialize, Debug, Default)]
+pub struct GoalsConfig {
+    pub words_per_week: Option<usize>,
+    pub posts_per_month: Option<usize>,
+}
+
+/// A section of the home page. Unset limits fall back to
+/// `latest_articles_count`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HomeSection {
+    /// Articles picked by slug, in the order given.
+    Featured { slugs: Vec<String> },
+    Latest {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Notes picked by path under the notes directory, in the order given.
+    PinnedNotes { paths: Vec<String> },
+    /// Tags with the most published articles.
+    PopularTags {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    RecentComments {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+}
+
+/// A menu link, to a frontend route such as `/archive` or to another site.
+/// Items are shown by ascending `order`, then in the order given.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct NavItem {
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub children: Vec<NavItem>,
+}
+
+/// Who a feature flag is on for. A flag with none of these set is off.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FeatureFlag {
+    /// On for everyone.
+    #[serde(default)]
+    pub enabled: bool,
+    /// On for signed-in users and admin token holders.
+    #[serde(default)]
+    pub signed_in: bool,
+    /// GitHub logins it is on for.
+    #[serde(default)]
+    pub users: Vec<String>,
+}
+
+impl FeatureFlag {
+    pub fn is_on(&self, signed_in: bool, login: Option<&str>) -> bool {
+        self.enabled
+            || (self.signed_in && signed_in)
+            || login.is_some_and(|login| self.users.iter().any(|u| u.eq_ignore_ascii_case(login)))
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SummarizerConfig {
+    /// Base URL, e.g. `https://api.openai.com/v1`; `/chat/completions` is
+    /// appended.
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default = "default_summary_max_words")]
+    pub max_words: usize,
+}
+
+/// A command such as `["weasyprint", "-", "-"]` that reads an HTML document
+/// on stdin and writes the PDF to stdout.
+#[derive(Deserialize, Debug)]
+pub struct PdfConfig {
+    pub command: Vec<String>,
+    #[serde(default = "default_pdf_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// Where the content directories live in a GitHub-style repository.
+#[derive(Deserialize, Debug)]
+pub struct RepositoryConfig {
+    /// Web URL of the repository, e.g. `https://github.com/user/blog`.
+    pub url: String,
+    #[serde(default = "default_repository_branch")]
+    pub branch: String,
+    /// Directory in the repository holding `article/` and `notes/`; the
+    /// repository root by default.
+    #[serde(default)]
+    pub path: String,
+}
+
+/// Where branches are checked out from for review.
+#[derive(Deserialize, Debug)]
+pub struct PreviewBranchesConfig {
+    /// Anything `git clone` accepts, e.g. `/srv/blog` or
+    /// `https://github.com/user/blog.git`.
+    pub remote: String,
+    /// Directory in the repository holding `article/` and `notes/`; the
+    /// repository root by default.
+    #[serde(default)]
+    pub path: String,
+    /// The checkout is abandoned if it takes longer.
+    #[serde(default = "default_checkout_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// A build hook of a hosting provider such as Netlify or Vercel, or any
+/// endpoint that accepts a JSON `POST`.
+#[derive(Deserialize, Debug)]
+pub struct DeployHookConfig {
+    pub url: String,
+    /// Environment variable holding the secret deliveries are signed with.
+    /// Deliveries are unsigned when unset.
+    #[serde(default)]
+    pub secret_env: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SyncConfig {
+    /// Base URL of the primary, e.g. `https://blog.example`.
+    pub primary: String,
+}
+
+/// Where to announce newly published articles. Each ping runs from the job
+/// queue and its outcome is kept in the audit log.
+#[derive(Deserialize, Debug)]
+pub struct PingConfig {
+    /// Sitemap ping endpoints; `?sitemap=<hostname>/sitemap.xml` is
+    /// appended to each.
+    #[serde(default = "default_sitemap_endpoints")]
+    pub sitemap_endpoints: Vec<String>,
+    /// WebSub hub notified with `hub.mode=publish` for each of
+    /// `websub_topics`, the feed URLs it distributes.
+    #[serde(default)]
+    pub websub_hub: Option<String>,
+    #[serde(default)]
+    pub websub_topics: Vec<String>,
+}
+
+/// Accounts to post a status linking each newly published article to.
+/// Credentials are read from the environment, never from the config.
+#[derive(Deserialize, Debug)]
+pub struct CrosspostConfig {
+    #[serde(default)]
+    pub mastodon: Option<MastodonConfig>,
+    #[serde(default)]
+    pub bluesky: Option<BlueskyConfig>,
+    #[serde(default)]
+    pub evergreen: EvergreenConfig,
+}
+
+/// How the `suggest_evergreen_posts` job picks older articles to share
+/// again. Picks wait for the admin's approval before anything is posted.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct EvergreenConfig {
+    /// Articles published more recently are not picked.
+    pub min_age_days: u32,
+    /// Days of views an article is ranked by.
+    pub lookback_days: u32,
+    /// Articles picked per run.
+    pub picks: usize,
+    /// Days before an article can be picked again, approved or not.
+    pub cooldown_days: u32,
+}
+
+impl Default for EvergreenConfig {
+    fn default() -> Self {
+        Self {
+            min_age_days: 180,
+            lookback_days: 90,
+            picks: 1,
+            cooldown_days: 365,
         }
+    }
+}
 
-        if !Path::new(NOTES_DIR).exists() {
-            return Err(format!(
-                "Notes directory does not exist: {}",
-                NOTES_DIR
-            ));
+#[derive(Deserialize, Debug)]
+pub struct MastodonConfig {
+    /// Base URL of the instance, e.g. `https://mastodon.social`.
+    pub instance: String,
+    /// Environment variable holding an access token with `write:statuses`.
+    #[serde(default = "default_mastodon_token_env")]
+    pub token_env: String,
+    /// `public`, `unlisted`, `private` or `direct`.
+    #[serde(default = "default_mastodon_visibility")]
+    pub visibility: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlueskyConfig {
+    /// Handle of the account, e.g. `blog.example`.
+    pub handle: String,
+    /// Environment variable holding an app password of the account.
+    #[serde(default = "default_bluesky_password_env")]
+    pub app_password_env: String,
+    /// PDS the account lives on.
+    #[serde(default = "default_bluesky_service")]
+    pub service: String,
+}
+
+/// Connection settings of the HTTP listener, for deployments where scribe
+/// faces clients directly rather than sitting behind a reverse proxy.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Accept HTTP/2 with prior knowledge (h2c) alongside HTTP/1.1.
+    pub http2: bool,
+    pub http2_max_concurrent_streams: u32,
+    /// Seconds between HTTP/2 keep-alive pings; 0 sends none.
+    pub http2_keep_alive_interval_secs: u64,
+    /// Seconds to wait for a ping to be acknowledged before the connection
+    /// is closed.
+    pub http2_keep_alive_timeout_secs: u64,
+    /// Keep HTTP/1.1 connections open between requests.
+    pub http1_keep_alive: bool,
+    /// Seconds a client has to send the request headers; 0 waits forever.
+    pub header_read_timeout_secs: u64,
+    pub tcp_nodelay: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            http2: true,
+            http2_max_concurrent_streams: 200,
+            http2_keep_alive_interval_secs: 0,
+            http2_keep_alive_timeout_secs: 20,
+            http1_keep_alive: true,
+            header_read_timeout_secs: 30,
+            tcp_nodelay: true,
         }
+    }
+}
+
+/// Largest request bodies accepted, in bytes, per group of routes.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct BodyLimitsConfig {
+    /// Routes outside the groups below.
+    pub default: usize,
+    pub articles: usize,
+    pub comments: usize,
+    pub imports: usize,
+    pub assets: usize,
+}
 
+impl BodyLimitsConfig {
+    pub fn limit(&self, group: BodyGroup) -> usize {
+        match group {
+            BodyGroup::Articles => self.articles,
+            BodyGroup::Comments => self.comments,
+            BodyGroup::Imports => self.imports,
+            BodyGroup::Assets => self.assets,
+        }
+    }
+}
+
+impl Default for BodyLimitsConfig {
+    fn default() -> Self {
+        Self {
+            default: 2 * 1024 * 1024,
+            articles: 16 * 1024 * 1024,
+            comments: 16 * 1024,
+            imports: 64 * 1024 * 1024,
+            assets: 32 * 1024 * 1024,
+        }
+    }
+}
+
+/// Lifetime and size of the response cache, for all routes and per class
+/// of routes.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ResponseCacheConfig {
+    /// Seconds a response stays cached, for routes outside every class.
+    pub ttl_secs: u64,
+    /// Responses kept for routes outside every class.
+    pub capacity: u64,
+    /// Classes of routes with their own lifetime and size, keyed by name,
+    /// e.g. `[response_cache.classes.search]`.
+    pub classes: BTreeMap<String, CacheClassConfig>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: CACHE_TTL_SECONDS,
+            capacity: CACHE_MAX_CAPACITY,
+            classes: BTreeMap::new(),
+        }
+    }
+}
+
+/// Routes whose path starts with `prefix`. A path matching several classes
+/// belongs to the one with the longest prefix.
+#[derive(Deserialize, Debug)]
+pub struct CacheClassConfig {
+    pub prefix: String,
+    pub ttl_secs: u64,
+    pub capacity: u64,
+}
+
+/// Requests of each expensive kind served at once; 0 removes the limit.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    pub search: usize,
+    pub content_listings: usize,
+    pub exports: usize,
+    /// Seconds suggested in `Retry-After` when a request is turned away.
+    pub retry_after_secs: u64,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            search: 16,
+            content_listings: 8,
+            exports: 4,
+            retry_after_secs: 1,
+        }
+    }
+}
+
+/// The client behind every outbound request: GitHub sign-in, webhooks,
+/// deploy hooks, pings, the summarizer and replica syncs.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Proxy for all outbound requests, e.g. `http://proxy.internal:3128`.
+    /// Without one, the `HTTP_PROXY` and `HTTPS_PROXY` variables apply.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts reached directly rather than through `proxy`.
+    pub no_proxy: Option<String>,
+    pub connect_timeout_secs: u64,
+    /// Longest wait for the next bytes of a response.
+    pub read_timeout_secs: u64,
+    /// Further attempts for a request that fails to connect, times out or
+    /// is answered with 429 or a 5xx status.
+    pub retries: u32,
+    /// Wait before the first retry, doubled before each further one.
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: None,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 60,
+            retries: 2,
+            retry_backoff_ms: 500,
+        }
+    }
+}
+
+/// Page sizes of article, note, guestbook and search listings.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PaginationConfig {
+    /// Page size when a request gives no `limit`.
+    pub default_limit: usize,
+    /// Largest `limit` honoured; larger requests are cut down to it.
+    pub max_limit: usize,
+}
+
+impl PaginationConfig {
+    /// Page size for a requested `limit`: the default when it is missing or
+    /// 0, and at most `max_limit`.
+    pub fn limit(&self, requested: Option<usize>) -> usize {
+        match requested {
+            Some(limit) if limit > 0 => limit.min(self.max_limit),
+            _ => self.default_limit,
+        }
+    }
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 10,
+            max_limit: 100,
+        }
+    }
+}
+
+/// Limits on requests made with read-only API keys.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ApiKeysConfig {
+    /// Requests a key may make per UTC day, unless the key sets its own
+    /// quota; 0 lifts the limit.
+    pub daily_quota: u64,
+}
+
+impl Default for ApiKeysConfig {
+    fn default() -> Self {
+        Self {
+            daily_quota: 10_000,
+        }
+    }
+}
+
+/// Limits and caching of the `/api/public` surface for third-party
+/// consumers.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct PublicApiConfig {
+    /// Requests each client address may make per minute; 0 lifts the
+    /// limit.
+    pub requests_per_minute: u32,
+    /// `max-age` of successful responses for browsers and CDNs.
+    pub cache_max_age_secs: u64,
+    /// How long browsers may reuse a CORS preflight answer.
+    pub cors_max_age_secs: u64,
+}
+
+impl Default for PublicApiConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 60,
+            cache_max_age_secs: 300,
+            cors_max_age_secs: 86_400,
+        }
+    }
+}
+
+/// Lockout of client addresses that keep sending a wrong admin token. Each
+/// failure past `max_failures` doubles the lockout, starting at
+/// `lockout_secs` and capped at `max_lockout_secs`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct AdminLockoutConfig {
+    /// Wrong tokens allowed before a client is locked out; 0 turns the
+    /// lockout off.
+    pub max_failures: u32,
+    pub lockout_secs: u64,
+    pub max_lockout_secs: u64,
+}
+
+impl Default for AdminLockoutConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            lockout_secs: 60,
+            max_lockout_secs: 86_400,
+        }
+    }
+}
+
+/// How client addresses are stored and how long visitor data is kept.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    pub ip_storage: IpStorage,
+    /// Days after which the `purge_expired_data` job deletes article views
+    /// and API key usage, and drops the addresses kept with anonymous poll
+    /// votes. Nothing is purged when unset.
+    pub retention_days: Option<u32>,
+}
+
+/// What is kept of a client address, for instance to count an anonymous
+/// poll vote once.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpStorage {
+    /// A SHA-256 hash of the address.
+    #[default]
+    Hash,
+    /// The network only: the first three bytes of an IPv4 address or the
+    /// first six of an IPv6 one. Clients on the same network are counted
+    /// as one.
+    Truncate,
+}
+
+/// Channel details of `/feed/podcast.xml`, the feed of articles with
+/// `audio`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct PodcastConfig {
+    /// `Podcast` when unset.
+    pub title: Option<String>,
+    pub description: String,
+    pub author: Option<String>,
+    /// Cover art URL, at least 1400×1400 for most directories.
+    pub image: Option<String>,
+    /// An Apple Podcasts category, e.g. `Technology`.
+    pub category: Option<String>,
+    pub explicit: bool,
+    pub language: Option<String>,
+    /// Contact address directories use to verify the owner.
+    pub owner_email: Option<String>,
+}
+
+/// Links from rendered articles to the definitions under `glossary/`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct GlossaryConfig {
+    /// Link the first occurrence of each term in print pages, PDFs and
+    /// bundles.
+    pub link_terms: bool,
+    /// Frontend route of the glossary page. Terms link to `{page}#{slug}`
+    /// on the site.
+    pub page: String,
+}
+
+impl Default for GlossaryConfig {
+    fn default() -> Self {
+        Self {
+            link_terms: true,
+            page: "/glossary".to_string(),
+        }
+    }
+}
+
+/// Who may read `members_only` articles without an access code: the
+/// author, and GitHub users whose login is on the `allowlist`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct MembersConfig {
+    pub allowlist: Vec<String>,
+}
+
+impl MembersConfig {
+    pub fn allows(&self, user: &User) -> bool {
+        user.is_author()
+            || self
+                .allowlist
+                .iter()
+                .any(|login| login.eq_ignore_ascii_case(&user.github_login))
+    }
+}
+
+/// Where supporters come from: the public GitHub sponsors of
+/// `github_login`, then the entries of the YAML `file`. At least one is
+/// needed. The list is refreshed by the `refresh_sponsors` job, or when it
+/// is older than six hours.
+#[derive(Deserialize, Debug)]
+pub struct SponsorsConfig {
+    #[serde(default)]
+    pub github_login: Option<String>,
+    /// Environment variable holding a GitHub token of `github_login`, which
+    /// the GraphQL API asks for.
+    #[serde(default = "default_sponsors_token_env")]
+    pub token_env: String,
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// A Sentry-compatible service that panics and `500` responses are sent
+/// to, with the method, path and request id they happened in.
+#[derive(Deserialize, Debug)]
+pub struct ErrorReportingConfig {
+    /// e.g. `https://<public key>@errors.example.com/<project id>`.
+    pub dsn: String,
+    /// Reported with every event, e.g. `production`.
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// The layers of the routes under `prefix`; unset ones are left to the
+/// next shorter matching prefix, then to the defaults.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RouteLayersConfig {
+    /// Path prefix, matched segment by segment, e.g. `/api/search`.
+    /// `/api/v1` paths match as their `/api` path.
+    pub prefix: String,
+    /// Whether responses may be kept in the response cache. On by default.
+    #[serde(default)]
+    pub cache: Option<bool>,
+    /// Whether responses are gzipped for clients that accept it. Off by
+    /// default.
+    #[serde(default)]
+    pub compression: Option<bool>,
+    /// `false` lifts the public API quota and the comment limit; `true`
+    /// holds other routes to the public API quota.
+    #[serde(default)]
+    pub rate_limit: Option<bool>,
+    /// `false` drops the public API's CORS headers; `true` allows any
+    /// origin on other routes.
+    #[serde(default)]
+    pub cors: Option<bool>,
+}
+
+/// The tags and categories articles may use. A tag is allowed when it is
+/// listed in `tags` or fully matches one of `tag_patterns`, and likewise
+/// for categories; with neither set, any value is allowed.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct TaxonomyConfig {
+    pub tags: Vec<String>,
+    pub tag_patterns: Vec<String>,
+    pub categories: Vec<String>,
+    pub category_patterns: Vec<String>,
+    /// Refuse to save articles that break the rules instead of saving them
+    /// with a warning.
+    pub strict: bool,
+}
+
+/// Hunspell dictionaries by language, each given as the path of its `.aff`
+/// and `.dic` files without the extension, e.g.
+/// `en = "/usr/share/hunspell/en_US"`. Entries without a `lang` in their
+/// front matter are checked as `default_lang`; `words` are accepted in
+/// every language.
+#[derive(Deserialize, Debug)]
+pub struct SpellcheckConfig {
+    pub dictionaries: BTreeMap<String, String>,
+    #[serde(default = "default_spellcheck_lang")]
+    pub default_lang: String,
+    #[serde(default)]
+    pub words: Vec<String>,
+}
+
+fn default_spellcheck_lang() -> String {
+    "en".to_string()
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), String> {
         if EnvFilter::try_new(&self.log_level).is_err() {
             return Err(format!("Invalid log level: {}", self.log_level));
         }
@@ -70,8 +860,306 @@ impl Config {
             );
         }
 
+        if let Some(item) = nav_items(&self.navigation)
+            .find(|item| item.title.trim().is_empty() || item.url.trim().is_empty())
+        {
+            return Err(format!(
+                "Navigation items need a title and a url: {:?}",
+                item
+            ));
+        }
+
+        if self.jobs.contains_key("purge_expired_data") && self.privacy.retention_days.is_none() {
+            return Err(
+                "The purge_expired_data job needs privacy.retention_days to be set".to_string(),
+            );
+        }
+
+        if self.jobs.contains_key("suggest_evergreen_posts") && self.crosspost.is_none() {
+            return Err("The suggest_evergreen_posts job needs a [crosspost] table".to_string());
+        }
+
+        if let Some(sponsors) = &self.sponsors
+            && sponsors.github_login.is_none()
+            && sponsors.file.is_none()
+        {
+            return Err("[sponsors] needs a github_login or a file".to_string());
+        }
+
+        if self.jobs.contains_key("refresh_sponsors") && self.sponsors.is_none() {
+            return Err("The refresh_sponsors job needs a [sponsors] table".to_string());
+        }
+
+        if let Some(reporting) = &self.error_reporting {
+            Dsn::parse(&reporting.dsn).map_err(|e| format!("[error_reporting] {}", e))?;
+        }
+
+        if self.reaction_emojis.iter().any(|e| e.trim().is_empty()) {
+            return Err("Reaction emojis cannot be empty".to_string());
+        }
+
+        if let Some(summarizer) = &self.summarizer
+            && reqwest::Url::parse(&summarizer.endpoint).is_err()
+        {
+            return Err(format!(
+                "Invalid summarizer endpoint: {}",
+                summarizer.endpoint
+            ));
+        }
+
+        if self
+            .restricted_categories
+            .iter()
+            .any(|c| c.trim_matches('/').trim().is_empty())
+        {
+            return Err("Restricted categories cannot be empty".to_string());
+        }
+
+        let journal_dir = Path::new(&self.journal_dir);
+        if self.journal_dir.trim().is_empty()
+            || !journal_dir
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(format!(
+                "Journal folder must be a relative path inside the notes directory: {}",
+                self.journal_dir
+            ));
+        }
+
+        if self.timezone.parse::<Tz>().is_err() {
+            return Err(format!("Invalid timezone: {}", self.timezone));
+        }
+
+        if reqwest::Url::parse(&self.github_api_url).is_err() {
+            return Err(format!("Invalid GitHub API URL: {}", self.github_api_url));
+        }
+
+        if let Some(webhook) = &self.notify_webhook
+            && reqwest::Url::parse(webhook).is_err()
+        {
+            return Err(format!("Invalid notification webhook: {}", webhook));
+        }
+
+        for (name, hook) in &self.deploy_hooks {
+            if reqwest::Url::parse(&hook.url).is_err() {
+                return Err(format!(
+                    "Invalid URL for deploy hook {}: {}",
+                    name, hook.url
+                ));
+            }
+        }
+
+        if let Some(repository) = &self.repository {
+            if reqwest::Url::parse(&repository.url).is_err() {
+                return Err(format!("Invalid repository URL: {}", repository.url));
+            }
+            if repository.branch.trim().is_empty() {
+                return Err("Repository branch cannot be empty".to_string());
+            }
+        }
+
+        if let Some(sync) = &self.sync {
+            if reqwest::Url::parse(&sync.primary).is_err() {
+                return Err(format!("Invalid sync primary URL: {}", sync.primary));
+            }
+            if !self.read_only {
+                return Err("Replicas syncing from a primary must be read_only".to_string());
+            }
+        }
+
+        if let Some(pings) = &self.pings {
+            let urls = pings
+                .sitemap_endpoints
+                .iter()
+                .chain(&pings.websub_hub)
+                .chain(&pings.websub_topics);
+            for url in urls {
+                if reqwest::Url::parse(url).is_err() {
+                    return Err(format!("Invalid ping URL: {}", url));
+                }
+            }
+            if pings.websub_hub.is_some() && pings.websub_topics.is_empty() {
+                return Err("A WebSub hub needs at least one topic".to_string());
+            }
+        }
+
+        if let Some(crosspost) = &self.crosspost {
+            let urls = crosspost
+                .mastodon
+                .iter()
+                .map(|m| &m.instance)
+                .chain(crosspost.bluesky.iter().map(|b| &b.service));
+            for url in urls {
+                if reqwest::Url::parse(url).is_err() {
+                    return Err(format!("Invalid cross-posting URL: {}", url));
+                }
+            }
+            if let Some(mastodon) = &crosspost.mastodon
+                && !["public", "unlisted", "private", "direct"]
+                    .contains(&mastodon.visibility.as_str())
+            {
+                return Err(format!(
+                    "Invalid Mastodon visibility: {}",
+                    mastodon.visibility
+                ));
+            }
+        }
+
+        if let Some(proxy) = &self.http.proxy
+            && reqwest::Proxy::all(proxy).is_err()
+        {
+            return Err(format!("Invalid HTTP proxy: {}", proxy));
+        }
+
+        if self.http.connect_timeout_secs == 0 || self.http.read_timeout_secs == 0 {
+            return Err("HTTP timeouts must be greater than 0".to_string());
+        }
+
+        if let Some(previews) = &self.preview_branches
+            && (previews.remote.trim().is_empty() || previews.timeout_secs == 0)
+        {
+            return Err("Branch previews need a remote and a positive timeout_secs".to_string());
+        }
+
+        if let Some(spellcheck) = &self.spellcheck
+            && spellcheck.dictionaries.is_empty()
+        {
+            return Err("Spellcheck needs at least one dictionary".to_string());
+        }
+
+        TrustedProxies::new(&self.trusted_proxies)
+            .map_err(|e| format!("Invalid trusted_proxies: {}", e))?;
+
+        let mut prefixes = HashSet::new();
+        for group in &self.route_layers {
+            if !group.prefix.starts_with('/') {
+                return Err(format!(
+                    "Route layer prefix '{}' must start with '/'",
+                    group.prefix
+                ));
+            }
+            if !prefixes.insert(group.prefix.as_str()) {
+                return Err(format!(
+                    "Route layer prefix '{}' is given twice",
+                    group.prefix
+                ));
+            }
+        }
+
+        if let Err(e) = TaxonomyRules::new(&self.taxonomy) {
+            return Err(format!("Invalid taxonomy pattern: {}", e));
+        }
+
+        if let Some(pdf) = &self.pdf
+            && (pdf.command.is_empty() || pdf.command[0].trim().is_empty())
+        {
+            return Err("PDF command cannot be empty".to_string());
+        }
+
+        if self.goals.words_per_week == Some(0) || self.goals.posts_per_month == Some(0) {
+            return Err("Goal targets must be greater than 0".to_string());
+        }
+
+        if self.server.http2 && self.server.http2_max_concurrent_streams == 0 {
+            return Err("HTTP/2 max concurrent streams must be greater than 0".to_string());
+        }
+
+        if self.server.http2_keep_alive_interval_secs > 0
+            && self.server.http2_keep_alive_timeout_secs == 0
+        {
+            return Err("HTTP/2 keep-alive timeout must be greater than 0".to_string());
+        }
+
+        let limits = &self.body_limits;
+        if [
+            limits.default,
+            limits.articles,
+            limits.comments,
+            limits.imports,
+        ]
+        .contains(&0)
+        {
+            return Err("Body limits must be greater than 0".to_string());
+        }
+
+        let cache = &self.response_cache;
+        if cache.ttl_secs == 0 || cache.capacity == 0 {
+            return Err("Response cache TTL and capacity must be greater than 0".to_string());
+        }
+        for (name, class) in &cache.classes {
+            if name == "default" {
+                return Err("Response cache class name 'default' is reserved".to_string());
+            }
+            if !class.prefix.starts_with('/') {
+                return Err(format!(
+                    "Response cache class '{}' needs a prefix starting with '/'",
+                    name
+                ));
+            }
+            if class.ttl_secs == 0 || class.capacity == 0 {
+                return Err(format!(
+                    "Response cache class '{}' needs a TTL and capacity greater than 0",
+                    name
+                ));
+            }
+        }
+
+        if self.pagination.default_limit == 0
+            || self.pagination.default_limit > self.pagination.max_limit
+        {
+            return Err(
+                "Default page size must be between 1 and the maximum page size".to_string(),
+            );
+        }
+
         Ok(())
     }
+
+    /// Parsed `timezone`, UTC when it is not a known zone.
+    pub fn site_timezone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// Public URL of an article, used wherever a link must leave the API.
+    pub fn article_url(&self, slug: &str) -> String {
+        format!("{}/articles/{}", self.hostname.trim_end_matches('/'), slug)
+    }
+
+    /// Path of the daily note for `date` under `/api/notes`, which is also
+    /// its file path under the notes directory without the extension.
+    pub fn daily_note_path(&self, date: NaiveDate) -> String {
+        format!("{}/{}", self.journal_dir.trim_matches('/'), date)
+    }
+
+    /// Link to edit `file_path`, a file under the content directory `root`,
+    /// in the configured repository. `None` without a `[repository]` table
+    /// or for files outside `root`.
+    pub fn edit_url(&self, root: &Path, file_path: impl AsRef<Path>) -> Option<String> {
+        let repository = self.repository.as_ref()?;
+        let relative = file_path.as_ref().strip_prefix(root).ok()?;
+        let segments: Vec<String> = repository
+            .path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .chain(
+                root.file_name()
+                    .map(|dir| dir.to_string_lossy().into_owned()),
+            )
+            .chain(
+                relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy().into_owned()),
+            )
+            .collect();
+        Some(format!(
+            "{}/edit/{}/{}",
+            repository.url.trim_end_matches('/'),
+            repository.branch,
+            segments.join("/")
+        ))
+    }
 }
 
 fn default_search_index_dir() -> String {
@@ -86,10 +1174,101 @@ fn default_content_search_limit() -> usize {
     10_000
 }
 
+fn default_reaction_emojis() -> Vec<String> {
+    ["👍", "❤️", "🎉", "🤔"]
+        .iter()
+        .map(|e| e.to_string())
+        .collect()
+}
+
+fn default_summary_max_words() -> usize {
+    60
+}
+
+fn default_pdf_timeout_secs() -> u64 {
+    60
+}
+
+fn default_checkout_timeout_secs() -> u64 {
+    120
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_store_snapshots() -> bool {
+    true
+}
+
+fn default_undo_window_secs() -> u64 {
+    300
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+/// Every navigation item, nested ones included.
+fn nav_items(items: &[NavItem]) -> Box<dyn Iterator<Item = &NavItem> + '_> {
+    Box::new(
+        items
+            .iter()
+            .flat_map(|item| std::iter::once(item).chain(nav_items(&item.children))),
+    )
+}
+
+fn default_home() -> Vec<HomeSection> {
+    vec![HomeSection::Latest { limit: None }]
+}
+
+fn default_journal_dir() -> String {
+    "journal".to_string()
+}
+
+fn default_repository_branch() -> String {
+    "main".to_string()
+}
+
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1".to_string()]
+}
+
+fn default_sponsors_token_env() -> String {
+    "GITHUB_SPONSORS_TOKEN".to_string()
+}
+
+fn default_mastodon_token_env() -> String {
+    "MASTODON_ACCESS_TOKEN".to_string()
+}
+
+fn default_mastodon_visibility() -> String {
+    "public".to_string()
+}
+
+fn default_bluesky_password_env() -> String {
+    "BLUESKY_APP_PASSWORD".to_string()
+}
+
+fn default_bluesky_service() -> String {
+    "https://bsky.social".to_string()
+}
+
+fn default_sitemap_endpoints() -> Vec<String> {
+    vec![
+        "https://www.google.com/ping".to_string(),
+        "https://www.bing.com/ping".to_string(),
+    ]
+}
+
 fn default_hostname() -> String {
     "http://localhost:3000".to_string()
 }
 
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
 pub fn load_config() -> Result<Config, Box<dyn std::error::Error>> {
     let config_content = fs::read_to_string("config.toml")?;
     let mut config: Config = toml::from_str(&config_content)?;
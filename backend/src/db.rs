@@ -16,3 +16,20 @@ pub async fn init_db(database_url: &str) -> Result<DbPool, sqlx::Error> {
 
     Ok(pool)
 }
+
+/// Opens a migrated in-memory database for tests and embedders.
+///
+/// The pool is limited to a single connection because every SQLite
+/// `:memory:` connection gets its own private database.
+pub async fn memory_db() -> DbPool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("migrations");
+    pool
+}
@@ -0,0 +1,259 @@
+//! Entry point for running scribe inside another axum application.
+//!
+//! ```no_run
+//! # async fn run(config: scribe_backend::config::Config) -> Result<(), Box<dyn std::error::Error>> {
+//! use axum::{Router, routing::get};
+//! use scribe_backend::ScribeApp;
+//!
+//! let blog = ScribeApp::builder(config)
+//!     .article_dir("content/posts")
+//!     .build()
+//!     .await?;
+//! blog.spawn_background_tasks();
+//!
+//! let app: Router = Router::new()
+//!     .route("/health", get(|| async { "ok" }))
+//!     .nest_service("/blog", blog.router());
+//! # let _ = app;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::{Config, DATABASE_URL};
+use crate::db::{self, DbPool};
+use crate::server::app::{
+    AppState, AppStateBuilder, build_router_with, save_store_snapshots, start_file_watcher,
+};
+use crate::server::job_queue::start_job_worker;
+use crate::server::scheduler::start_scheduler;
+use crate::server::snapshots::start_snapshot_worker;
+use crate::services::field_crypto::FieldCipher;
+use crate::services::note_crypto::NoteCipher;
+use crate::services::search::SearchService;
+use crate::services::service::ArticleStore;
+use axum::Router;
+use cookie::Key;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A fully assembled scribe instance: the router to mount plus handles to
+/// the state behind it.
+pub struct ScribeApp {
+    router: Router,
+    state: Arc<AppState>,
+}
+
+impl ScribeApp {
+    pub fn builder(config: impl Into<Arc<Config>>) -> ScribeAppBuilder {
+        ScribeAppBuilder {
+            config: config.into(),
+            database_url: DATABASE_URL.to_string(),
+            db: None,
+            article_dir: None,
+            notes_dir: None,
+            pages_dir: None,
+            data_dir: None,
+            taxonomy_dir: None,
+            collections_file: None,
+            glossary_dir: None,
+            search_index_dir: None,
+            cookie_key: None,
+            note_cipher: None,
+            field_cipher: None,
+            routes: Router::new(),
+        }
+    }
+
+    /// The complete application. Cloning is cheap, so this can be called
+    /// once per mount point.
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+
+    pub fn into_router(self) -> Router {
+        self.router
+    }
+
+    pub fn state(&self) -> Arc<AppState> {
+        Arc::clone(&self.state)
+    }
+
+    pub fn article_store(&self) -> Arc<RwLock<ArticleStore>> {
+        Arc::clone(&self.state.store)
+    }
+
+    pub fn note_store(&self) -> Arc<RwLock<ArticleStore>> {
+        Arc::clone(&self.state.note_store)
+    }
+
+    /// `None` unless full-text search is enabled and the index opened.
+    pub fn search_service(&self) -> Option<Arc<SearchService>> {
+        self.state.search_service.clone()
+    }
+
+    /// Starts the content watchers, the cron scheduler, the job queue
+    /// worker and the feed and sitemap snapshots. Left to the caller so tests
+    /// can run without them.
+    pub fn spawn_background_tasks(&self) {
+        start_file_watcher(self.state());
+        start_scheduler(self.state());
+        start_job_worker(self.state());
+        start_snapshot_worker(self.state());
+    }
+
+    /// Commits search index updates still waiting for the commit debounce
+    /// window and saves the store snapshots. Call after the server has
+    /// stopped accepting requests.
+    pub async fn shutdown(&self) {
+        if let Some(queue) = &self.state.index_tx {
+            queue.flush().await;
+        }
+        save_store_snapshots(&self.state).await;
+    }
+}
+
+pub struct ScribeAppBuilder {
+    config: Arc<Config>,
+    database_url: String,
+    db: Option<DbPool>,
+    article_dir: Option<PathBuf>,
+    notes_dir: Option<PathBuf>,
+    pages_dir: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    taxonomy_dir: Option<PathBuf>,
+    collections_file: Option<PathBuf>,
+    glossary_dir: Option<PathBuf>,
+    search_index_dir: Option<PathBuf>,
+    cookie_key: Option<Key>,
+    note_cipher: Option<NoteCipher>,
+    field_cipher: Option<FieldCipher>,
+    routes: Router<Arc<AppState>>,
+}
+
+impl ScribeAppBuilder {
+    /// SQLite database to open and migrate. Ignored when a pool is given
+    /// with [`ScribeAppBuilder::db`].
+    pub fn database_url(mut self, url: impl Into<String>) -> Self {
+        self.database_url = url.into();
+        self
+    }
+
+    pub fn db(mut self, db: DbPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub fn article_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.article_dir = Some(dir.into());
+        self
+    }
+
+    pub fn notes_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.notes_dir = Some(dir.into());
+        self
+    }
+
+    pub fn pages_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.pages_dir = Some(dir.into());
+        self
+    }
+
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(dir.into());
+        self
+    }
+
+    pub fn taxonomy_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.taxonomy_dir = Some(dir.into());
+        self
+    }
+
+    pub fn collections_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.collections_file = Some(path.into());
+        self
+    }
+
+    pub fn glossary_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.glossary_dir = Some(dir.into());
+        self
+    }
+
+    pub fn search_index_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_index_dir = Some(dir.into());
+        self
+    }
+
+    pub fn cookie_key(mut self, key: Key) -> Self {
+        self.cookie_key = Some(key);
+        self
+    }
+
+    /// Key for private notes. Read from `NOTES_ENCRYPTION_KEY` when not set.
+    pub fn note_cipher(mut self, cipher: NoteCipher) -> Self {
+        self.note_cipher = Some(cipher);
+        self
+    }
+
+    /// Key for personal data in the database. Read from
+    /// `DATABASE_ENCRYPTION_KEY` when not set.
+    pub fn field_cipher(mut self, cipher: FieldCipher) -> Self {
+        self.field_cipher = Some(cipher);
+        self
+    }
+
+    /// Adds custom routes. They are served alongside the built-in API, can
+    /// extract `State<Arc<AppState>>`, and go through the same middleware.
+    pub fn routes(mut self, routes: Router<Arc<AppState>>) -> Self {
+        self.routes = self.routes.merge(routes);
+        self
+    }
+
+    pub async fn build(self) -> Result<ScribeApp, Box<dyn std::error::Error>> {
+        let db = match self.db {
+            Some(db) => db,
+            None => db::init_db(&self.database_url).await?,
+        };
+
+        let mut state = AppStateBuilder::new(&self.config, db);
+        if let Some(dir) = self.article_dir {
+            state = state.article_dir(dir);
+        }
+        if let Some(dir) = self.notes_dir {
+            state = state.notes_dir(dir);
+        }
+        if let Some(dir) = self.pages_dir {
+            state = state.pages_dir(dir);
+        }
+        if let Some(dir) = self.data_dir {
+            state = state.data_dir(dir);
+        }
+        if let Some(dir) = self.taxonomy_dir {
+            state = state.taxonomy_dir(dir);
+        }
+        if let Some(path) = self.collections_file {
+            state = state.collections_file(path);
+        }
+        if let Some(dir) = self.glossary_dir {
+            state = state.glossary_dir(dir);
+        }
+        if let Some(dir) = self.search_index_dir {
+            state = state.search_index_dir(dir);
+        }
+        if let Some(key) = self.cookie_key {
+            state = state.cookie_key(key);
+        }
+        if let Some(cipher) = self.note_cipher {
+            state = state.note_cipher(cipher);
+        }
+        if let Some(cipher) = self.field_cipher {
+            state = state.field_cipher(cipher);
+        }
+        let state = state.build().await?;
+
+        Ok(ScribeApp {
+            router: build_router_with(Arc::clone(&state), self.routes),
+            state,
+        })
+    }
+}
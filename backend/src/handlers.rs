@@ -1,12 +1,59 @@
+pub mod access_codes;
+pub mod admin;
+pub mod analytics;
+pub mod api_keys;
 pub mod article_versions;
 pub mod articles;
+pub mod assets;
+pub mod attachments;
 pub mod auth;
 pub mod categories;
+pub mod changes;
+pub mod collab;
+pub mod collections;
 pub mod comments;
+pub mod deploy_hooks;
+pub mod downloads;
 pub mod error;
+pub mod error_codes;
+pub mod evergreen;
+pub mod fediverse;
+pub mod feeds;
+pub mod flags;
+pub mod glossary;
+pub mod guestbook;
+pub mod health;
+pub mod home;
+pub mod locks;
+pub mod metrics;
+pub mod moderation;
+pub mod navigation;
 pub mod notes;
+pub mod pages;
+pub mod polls;
+pub mod presence;
+pub mod preview_branches;
+pub mod previews;
+pub mod public;
+pub mod reactions;
+pub mod redirects;
+pub mod response;
 pub mod root;
+pub mod saved_searches;
+pub mod schema;
 pub mod search;
+pub mod shortlinks;
 pub mod sitemap;
+pub mod sponsors;
+pub mod stats;
+pub mod suggestions;
+pub mod summaries;
+pub mod sync;
 pub mod tags;
+pub mod translations;
+pub mod trash;
+pub mod undo;
+pub mod unfurl;
 pub mod users;
+pub mod validation;
+pub mod version;
@@ -0,0 +1,128 @@
+use crate::handlers::auth::SignedJar;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::access_code::{AccessCode, IssuedAccessCode};
+use crate::server::access::MEMBER_COOKIE;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::access_code_service::{issue_code, list_codes, redeem_code, revoke_code};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const MAX_NAME_LENGTH: usize = 100;
+/// How long a redeemed code keeps the visitor a member, unless it is
+/// revoked first.
+const MEMBERSHIP_DAYS: i64 = 365;
+
+/// `name` says what the code is for. `max_redemptions` caps how often it
+/// can be redeemed; without it there is no limit.
+#[derive(Deserialize, Debug)]
+pub struct IssueCodeRequest {
+    pub name: String,
+    pub max_redemptions: Option<u64>,
+}
+
+impl Validate for IssueCodeRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("name", &self.name);
+        errors.max_chars("name", &self.name, MAX_NAME_LENGTH);
+        errors.into_result()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RedeemRequest {
+    pub code: String,
+}
+
+impl Validate for RedeemRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("code", &self.code);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/access-codes",
+            get(list)
+                .post(issue)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/access-codes/{id}",
+            delete(revoke).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route("/api/access-codes/redeem", post(redeem))
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<AccessCode>>, AppError> {
+    Ok(Json(list_codes(&state.db).await.map_err(db_error)?))
+}
+
+async fn issue(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<IssueCodeRequest>,
+) -> Result<Json<IssuedAccessCode>, AppError> {
+    let issued = issue_code(&state.db, payload.name.trim(), payload.max_redemptions)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(issued))
+}
+
+async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    if !revoke_code(&state.db, id).await.map_err(db_error)? {
+        return Err(AppError::NotFound {
+            code: ErrorCode::AccessCodeNotFound,
+            message: format!("Access code {} not found", id),
+        });
+    }
+    Ok(Json(json!({ "id": id, "message": "Access code revoked" })))
+}
+
+/// Makes the visitor a member with a signed cookie naming the code, so
+/// revoking the code ends their membership.
+async fn redeem(
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+    ValidJson(payload): ValidJson<RedeemRequest>,
+) -> Result<(SignedJar, Json<Value>), AppError> {
+    let redeemed = redeem_code(&state.db, &payload.code)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::BadRequest {
+            code: ErrorCode::InvalidAccessCode,
+            message: "Access code is unknown, revoked or used up".to_string(),
+        })?;
+
+    let is_secure_cookie = state.config.github_redirect_url.starts_with("https://");
+    let jar = jar.add(
+        Cookie::build((MEMBER_COOKIE, redeemed.id.to_string()))
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(is_secure_cookie)
+            .path("/")
+            .max_age(cookie::time::Duration::days(MEMBERSHIP_DAYS))
+            .build(),
+    );
+    Ok((jar, Json(json!({ "member": true }))))
+}
@@ -0,0 +1,713 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::{Article, ArticleStatus, ArticleTeaser};
+use crate::models::audit::AuditEntry;
+use crate::models::content_diff::ContentDiff;
+use crate::models::diagnostics::Diagnostics;
+use crate::models::duplicate::DuplicatePair;
+use crate::models::github::GitHubRateLimitStatus;
+use crate::models::goal::{GoalKind, GoalProgress};
+use crate::models::job::QueuedJob;
+use crate::models::lock::ArticleLock;
+use crate::models::seo::SeoReport;
+use crate::models::store_stats::StoreStats;
+use crate::models::taxonomy::ContentLint;
+use crate::server::app::{AppState, rebuild_store};
+use crate::server::auth::require_admin;
+use crate::server::error_log::ErrorRecord;
+use crate::server::scheduler::JobStatus;
+use crate::services::article_service::saved_versions;
+use crate::services::asset_service::ASSET_DIR;
+use crate::services::audit_service::{ARTICLE_DELETED, audit_between, recent_audit};
+use crate::services::content_diff_service::content_diff;
+use crate::services::diagnostics_service::run_diagnostics;
+use crate::services::duplicate_service::find_duplicates;
+use crate::services::goal_service::{Publication, goal_progress};
+use crate::services::guestbook_service::count_entries;
+use crate::services::image_service::image_file;
+use crate::services::job_queue_service::dead_letter_jobs;
+use crate::services::lock_service::live_locks;
+use crate::services::search::IndexCollection;
+use crate::services::seo_service::audit;
+use crate::services::service::ArticleStore;
+use crate::services::site_event_service::{CONTENT_CHANGED, DEPLOYED, last_occurred};
+use crate::services::site_time;
+use crate::services::suggestion_service::count_pending;
+use axum::extract::{Path, Query, State};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Serialize, Debug)]
+pub struct ModerationQueue {
+    pub guestbook: usize,
+    pub suggestions: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PendingContentChanges {
+    pub articles: usize,
+    pub notes: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct IndexStatus {
+    pub enabled: bool,
+    pub last_indexed_at: Option<DateTime<Utc>>,
+    pub articles_indexed_at: Option<DateTime<Utc>>,
+    pub notes_indexed_at: Option<DateTime<Utc>>,
+    /// Index jobs queued but not yet committed.
+    pub pending_jobs: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledPost {
+    pub slug: String,
+    pub title: String,
+    pub date: DateTime<Utc>,
+}
+
+/// When content last changed against when a downstream deploy last
+/// reported back, to show whether the public site is behind.
+#[derive(Serialize, Debug)]
+pub struct PublishStatus {
+    pub content_changed_at: Option<DateTime<Utc>>,
+    pub deployed_at: Option<DateTime<Utc>>,
+    /// Content changed after the last deploy, or was never deployed.
+    pub deploy_pending: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AdminOverview {
+    pub pending_moderation: ModerationQueue,
+    pub pending_content_changes: PendingContentChanges,
+    pub recent_errors: Vec<ErrorRecord>,
+    pub search_index: IndexStatus,
+    pub scheduled_posts: Vec<ScheduledPost>,
+    pub publishing: PublishStatus,
+    pub github_rate_limit: GitHubRateLimitStatus,
+}
+
+/// The editing session holding a live lock on an article, if any.
+#[derive(Serialize, Debug, Default)]
+pub struct LockStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<String>,
+}
+
+impl From<Option<&ArticleLock>> for LockStatus {
+    fn from(lock: Option<&ArticleLock>) -> Self {
+        LockStatus {
+            locked_by: lock.map(|l| l.locked_by.clone()),
+            locked_until: lock.map(|l| l.locked_until.clone()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct AdminArticle {
+    #[serde(flatten)]
+    pub article: ArticleTeaser,
+    #[serde(flatten)]
+    pub lock: LockStatus,
+}
+
+/// An article or note and when it was last edited; notes are listed under
+/// `notes/`.
+#[derive(Serialize, Debug)]
+pub struct RecentEdit {
+    pub slug: String,
+    pub title: String,
+    pub status: ArticleStatus,
+    pub edited_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub lock: LockStatus,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StoreRebuild {
+    pub collection: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StoresStats {
+    pub articles: StoreStats,
+    pub notes: StoreStats,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SeoParams {
+    keyword: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AdminArticleParams {
+    status: Option<ArticleStatus>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecentEditsParams {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AuditParams {
+    action: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DuplicateParams {
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ContentDiffParams {
+    from: String,
+    to: Option<String>,
+}
+
+fn default_threshold() -> f64 {
+    0.8
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/overview",
+            get(get_overview).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/diagnostics",
+            get(get_diagnostics).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/jobs",
+            get(list_jobs).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/jobs/dead-letter",
+            get(list_dead_letter).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/audit",
+            get(list_audit).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/goals",
+            get(get_goals).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles",
+            get(list_articles).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles/recent-edits",
+            get(list_recent_edits).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles/{slug}/seo",
+            get(get_seo_report).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/content/duplicates",
+            get(list_duplicates).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/content/lint",
+            get(lint_content).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/content/diff",
+            get(get_content_diff).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/stores/stats",
+            get(get_store_stats).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/stores/{collection}/rebuild",
+            post(rebuild).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+/// Number of files on disk that differ from what the store has loaded, i.e.
+/// edits the file watcher has not picked up yet.
+fn pending_changes(store: &ArticleStore) -> usize {
+    store
+        .detect_file_changes()
+        .map(|changes| changes.len())
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to detect pending changes in '{}': {:?}",
+                store.root().display(),
+                e
+            );
+            0
+        })
+}
+
+/// Quick self-tests of the disks, database, file watchers and clock. The
+/// response is `200` either way; `healthy` tells whether any check failed.
+async fn get_diagnostics(State(state): State<Arc<AppState>>) -> Json<Diagnostics> {
+    Json(run_diagnostics(&state).await)
+}
+
+async fn get_overview(State(state): State<Arc<AppState>>) -> Result<Json<AdminOverview>, AppError> {
+    let guestbook =
+        count_entries(&state.db, false)
+            .await
+            .map_err(|e| AppError::InternalServerError {
+                code: ErrorCode::InternalServer,
+                message: e.to_string(),
+            })?;
+
+    let suggestions =
+        count_pending(&state.db)
+            .await
+            .map_err(|e| AppError::InternalServerError {
+                code: ErrorCode::InternalServer,
+                message: e.to_string(),
+            })?;
+
+    let internal = |e: sqlx::Error| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    };
+    let content_changed_at = last_occurred(&state.db, CONTENT_CHANGED)
+        .await
+        .map_err(internal)?;
+    let deployed_at = last_occurred(&state.db, DEPLOYED).await.map_err(internal)?;
+
+    let now = Utc::now();
+    let (article_changes, scheduled_posts) = {
+        let store = state.store.read().await;
+        let changes = pending_changes(&store);
+        let mut scheduled: Vec<ScheduledPost> = store
+            .query(
+                |a| {
+                    a.metadata.status == ArticleStatus::Scheduled
+                        || (a.metadata.is_published() && a.metadata.date > now)
+                },
+                0,
+                usize::MAX,
+            )
+            .map(|a| ScheduledPost {
+                slug: a.slug.clone(),
+                title: a.metadata.title.clone(),
+                date: a.metadata.date,
+            })
+            .collect();
+        scheduled.sort_by_key(|p| p.date);
+        (changes, scheduled)
+    };
+    let note_changes = {
+        let store = state.note_store.read().await;
+        pending_changes(&store)
+    };
+
+    let search = state.search_service.as_deref();
+    Ok(Json(AdminOverview {
+        pending_moderation: ModerationQueue {
+            guestbook,
+            suggestions,
+        },
+        pending_content_changes: PendingContentChanges {
+            articles: article_changes,
+            notes: note_changes,
+        },
+        recent_errors: state.error_log.recent(),
+        search_index: IndexStatus {
+            enabled: search.is_some(),
+            last_indexed_at: search.and_then(|s| s.last_commit_at()),
+            articles_indexed_at: search.and_then(|s| s.last_commit_for(IndexCollection::Articles)),
+            notes_indexed_at: search.and_then(|s| s.last_commit_for(IndexCollection::Notes)),
+            pending_jobs: state.index_tx.as_ref().map_or(0, |q| q.pending()),
+        },
+        scheduled_posts,
+        publishing: PublishStatus {
+            content_changed_at,
+            deployed_at,
+            deploy_pending: content_changed_at
+                .is_some_and(|changed| deployed_at.is_none_or(|deployed| deployed < changed)),
+        },
+        github_rate_limit: state.github_rate_limit.status(),
+    }))
+}
+
+async fn list_jobs(State(state): State<Arc<AppState>>) -> Json<Vec<JobStatus>> {
+    Json(state.scheduler.statuses())
+}
+
+async fn list_dead_letter(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<QueuedJob>>, AppError> {
+    let jobs = dead_letter_jobs(&state.db)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(jobs))
+}
+
+/// The latest audit log entries, newest first, optionally of one action.
+async fn list_audit(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AuditParams>,
+) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    let limit = state.config.pagination.limit(params.limit);
+    let entries = recent_audit(&state.db, params.action.as_deref(), limit)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(entries))
+}
+
+/// Live editing locks by article slug.
+async fn locks_by_slug(state: &AppState) -> Result<HashMap<String, ArticleLock>, AppError> {
+    let locks = live_locks(&state.db)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(locks
+        .into_iter()
+        .map(|lock| (lock.article_slug.clone(), lock))
+        .collect())
+}
+
+/// Every article whatever its status, newest first, optionally only those
+/// with `status`, with the session editing it.
+async fn list_articles(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminArticleParams>,
+) -> Result<Json<Vec<AdminArticle>>, AppError> {
+    let locks = locks_by_slug(&state).await?;
+    let store = state.store.read().await;
+    Ok(Json(
+        store
+            .query(
+                |a| {
+                    params
+                        .status
+                        .is_none_or(|status| a.metadata.status == status)
+                },
+                0,
+                usize::MAX,
+            )
+            .map(|a| AdminArticle {
+                article: ArticleTeaser {
+                    slug: a.slug_with_category(),
+                    metadata: a.metadata.clone(),
+                    edit_url: state.config.edit_url(store.root(), &a.file_path),
+                },
+                lock: locks.get(&a.slug).into(),
+            })
+            .collect(),
+    ))
+}
+
+/// Articles and notes, drafts and private notes included, most recently
+/// edited first. An edit is a change to the file or a version saved through
+/// the API, whichever is later.
+async fn list_recent_edits(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<RecentEditsParams>,
+) -> Result<Json<Vec<RecentEdit>>, AppError> {
+    let locks = locks_by_slug(&state).await?;
+    let edit = |slug: String, article: &Article, is_article: bool| {
+        let saved = is_article
+            .then(|| saved_versions(&state.data_dir, &article.slug_with_category()).pop())
+            .flatten()
+            .and_then(|version| DateTime::from_timestamp_millis(version as i64));
+        RecentEdit {
+            title: article.metadata.title.clone(),
+            status: article.metadata.status,
+            edited_at: saved.map_or(article.updated_at, |saved| saved.max(article.updated_at)),
+            lock: is_article
+                .then(|| locks.get(&article.slug))
+                .flatten()
+                .into(),
+            slug,
+        }
+    };
+
+    let mut edits: Vec<RecentEdit> = {
+        let store = state.store.read().await;
+        store
+            .query(|_| true, 0, usize::MAX)
+            .map(|a| edit(a.slug.clone(), a, true))
+            .collect()
+    };
+    let notes = state.note_store.read().await;
+    edits.extend(
+        notes
+            .query(|_| true, 0, usize::MAX)
+            .map(|n| edit(format!("notes/{}", n.slug_with_category()), n, false)),
+    );
+    edits.sort_by_key(|e| std::cmp::Reverse(e.edited_at));
+    edits.truncate(state.config.pagination.limit(params.limit));
+    Ok(Json(edits))
+}
+
+async fn get_goals(State(state): State<Arc<AppState>>) -> Json<Vec<GoalProgress>> {
+    let goals = &state.config.goals;
+    let mut targets = Vec::new();
+    if let Some(target) = goals.words_per_week {
+        targets.push((GoalKind::WordsPerWeek, target));
+    }
+    if let Some(target) = goals.posts_per_month {
+        targets.push((GoalKind::PostsPerMonth, target));
+    }
+    if targets.is_empty() {
+        return Json(Vec::new());
+    }
+
+    let count_words = goals.words_per_week.is_some();
+    let publications: Vec<Publication> = {
+        let store = state.store.read().await;
+        store
+            .query(|a| a.metadata.is_published(), 0, usize::MAX)
+            .map(|a| Publication {
+                date: a.metadata.day(),
+                words: if count_words {
+                    store
+                        .load_content_for(a)
+                        .map(|c| c.split_whitespace().count())
+                        .unwrap_or(0)
+                } else {
+                    0
+                },
+            })
+            .collect()
+    };
+
+    let today = site_time::today();
+    Json(
+        targets
+            .into_iter()
+            .map(|(kind, target)| goal_progress(kind, target, &publications, today))
+            .collect(),
+    )
+}
+
+async fn get_seo_report(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Query(params): Query<SeoParams>,
+) -> Result<Json<SeoReport>, AppError> {
+    let store = state.store.read().await;
+    let article = store.get_by_slug(&slug).ok_or_else(|| AppError::NotFound {
+        code: ErrorCode::ArticleNotFound,
+        message: format!("Article with slug {} not found", slug),
+    })?;
+    let content = store
+        .load_content_for(article)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(audit(
+        &article.slug,
+        &article.metadata,
+        &content,
+        params.keyword.as_deref(),
+        &state.config.hostname,
+    )))
+}
+
+async fn list_duplicates(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DuplicateParams>,
+) -> Result<Json<Vec<DuplicatePair>>, AppError> {
+    if !(0.0..=1.0).contains(&params.threshold) {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "threshold must be between 0 and 1".to_string(),
+        });
+    }
+
+    let mut documents: Vec<(String, String)> = state
+        .store
+        .read()
+        .await
+        .load_full_articles()
+        .into_iter()
+        .map(|a| (a.slug, a.content))
+        .collect();
+    documents.extend(
+        state
+            .note_store
+            .read()
+            .await
+            .load_full_articles()
+            .into_iter()
+            .map(|n| (format!("notes/{}", n.slug), n.content)),
+    );
+
+    let pairs = tokio::task::spawn_blocking(move || find_duplicates(&documents, params.threshold))
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(pairs))
+}
+
+/// New, edited and deleted articles from `from` up to `to` (now when
+/// unset), for writing "what's new" posts. Times are taken as front matter
+/// dates are, in the site timezone unless they carry an offset.
+async fn get_content_diff(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ContentDiffParams>,
+) -> Result<Json<ContentDiff>, AppError> {
+    let parse = |value: &str| {
+        site_time::parse_date(value, site_time::site_timezone()).ok_or_else(|| {
+            AppError::BadRequest {
+                code: ErrorCode::BadRequest,
+                message: format!("'{}' is not a date or timestamp", value),
+            }
+        })
+    };
+    let from = parse(&params.from)?;
+    let to = params
+        .to
+        .as_deref()
+        .map(parse)
+        .transpose()?
+        .unwrap_or_else(Utc::now);
+    if from >= to {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "from must be before to".to_string(),
+        });
+    }
+
+    let deletions = audit_between(&state.db, ARTICLE_DELETED, from, to)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    let store = state.store.read().await;
+    Ok(Json(content_diff(
+        &state.data_dir,
+        &store,
+        deletions,
+        from,
+        to,
+    )))
+}
+
+/// Gallery photos of `article` that should be on this server but are not.
+/// Remote photos are not fetched.
+fn missing_gallery_images(article: &Article, asset_dir: &std::path::Path) -> Vec<String> {
+    let article_dir = std::path::Path::new(&article.file_path)
+        .parent()
+        .unwrap_or(std::path::Path::new("."));
+    article
+        .metadata
+        .gallery
+        .iter()
+        .filter(|image| {
+            image_file(&image.src, article_dir, asset_dir).is_some_and(|path| !path.is_file())
+        })
+        .map(|image| image.src.clone())
+        .collect()
+}
+
+/// Articles and notes, drafts included, whose tags or category break the
+/// `[taxonomy]` rules, whose gallery names missing photos, that rely on a
+/// generated description or, with `[spellcheck]`, whose text has
+/// misspellings, by slug. Private notes are encrypted on disk and not spell-checked.
+async fn lint_content(State(state): State<Arc<AppState>>) -> Json<Vec<ContentLint>> {
+    let rules = &state.taxonomy_rules;
+    let asset_dir = state.data_dir.join(ASSET_DIR);
+    let lint = |slug: String, article: &Article| {
+        let violations =
+            rules.violations(&article.metadata.tags, article.metadata.category.as_deref());
+        let misspellings = match &state.spell_checker {
+            Some(checker) if !article.metadata.private => {
+                match std::fs::read_to_string(&article.file_path) {
+                    Ok(text) => checker.check(article.metadata.lang.as_deref(), &text),
+                    Err(e) => {
+                        tracing::warn!("Failed to read {} for spell checking: {}", slug, e);
+                        Vec::new()
+                    }
+                }
+            }
+            _ => Vec::new(),
+        };
+        let missing_gallery_images = missing_gallery_images(article, &asset_dir);
+        let auto_description = article.metadata.description_generated;
+        (!violations.is_empty()
+            || !misspellings.is_empty()
+            || !missing_gallery_images.is_empty()
+            || auto_description)
+            .then_some(ContentLint {
+                slug,
+                violations,
+                misspellings,
+                missing_gallery_images,
+                auto_description,
+            })
+    };
+
+    let mut report: Vec<ContentLint> = {
+        let store = state.store.read().await;
+        store
+            .query(|_| true, 0, usize::MAX)
+            .filter_map(|a| lint(a.slug.clone(), a))
+            .collect()
+    };
+    let notes = state.note_store.read().await;
+    report.extend(
+        notes
+            .query(|_| true, 0, usize::MAX)
+            .filter_map(|n| lint(format!("notes/{}", n.slug_with_category()), n)),
+    );
+    report.sort_by(|a, b| a.slug.cmp(&b.slug));
+    Json(report)
+}
+
+async fn get_store_stats(State(state): State<Arc<AppState>>) -> Json<StoresStats> {
+    Json(StoresStats {
+        articles: state.store.read().await.stats(),
+        notes: state.note_store.read().await.stats(),
+    })
+}
+
+/// Reloads the article or note collection from disk in full, for edits made
+/// while the server was not watching or too large for incremental updates.
+async fn rebuild(
+    State(state): State<Arc<AppState>>,
+    Path(collection): Path<String>,
+) -> Result<Json<StoreRebuild>, AppError> {
+    let Some(target) = IndexCollection::ALL
+        .into_iter()
+        .find(|c| c.as_str() == collection)
+    else {
+        return Err(AppError::NotFound {
+            code: ErrorCode::NotFound,
+            message: format!("Unknown collection '{}'", collection),
+        });
+    };
+    let count = rebuild_store(&state, target)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::StoreRebuildFailed,
+            message: e.to_string(),
+        })?;
+    Ok(Json(StoreRebuild { collection, count }))
+}
@@ -0,0 +1,335 @@
+use crate::handlers::comments::{ExportFormat, export_rows, parse_rows};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::search::enabled_search;
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::analytics::{ReferrerReport, ViewRecord};
+use crate::models::comment::ImportSummary;
+use crate::models::reaction::ReactionRecord;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::server::visibility::Visibility;
+use crate::services::analytics_service::{
+    ViewSource, export_views, import_views, record_view, referrer_counts, referrer_host, scrub_utm,
+};
+use crate::services::comment_service::{current_timestamp, normalize_timestamp};
+use crate::services::reaction_service::{export_reactions, import_reactions};
+use crate::services::search::SearchStats;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use reqwest::Url;
+use serde::Deserialize;
+use std::sync::Arc;
+
+const MAX_FIELD_LENGTH: usize = 2_000;
+
+/// Sent by the page when an article is read. `referrer` is the page's
+/// `document.referrer`; the API request's own `Referer` is the blog itself.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ViewRequest {
+    pub referrer: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+}
+
+impl Validate for ViewRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        for (field, value) in [
+            ("referrer", &self.referrer),
+            ("utm_source", &self.utm_source),
+            ("utm_medium", &self.utm_medium),
+            ("utm_campaign", &self.utm_campaign),
+        ] {
+            if let Some(value) = value {
+                errors.max_chars(field, value, MAX_FIELD_LENGTH);
+            }
+        }
+        errors.into_result()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReferrerParams {
+    slug: Option<String>,
+}
+
+/// The analytics that can be exported and imported: page views, reactions
+/// and the recent searches held by the search service.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsDataset {
+    Views,
+    Reactions,
+    Searches,
+}
+
+impl AnalyticsDataset {
+    fn as_str(self) -> &'static str {
+        match self {
+            AnalyticsDataset::Views => "views",
+            AnalyticsDataset::Reactions => "reactions",
+            AnalyticsDataset::Searches => "searches",
+        }
+    }
+}
+
+/// `from` and `to` are inclusive `YYYY-MM-DD` dates in UTC.
+#[derive(Deserialize, Debug)]
+pub struct AnalyticsExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/articles/{slug}/views", post(count_view))
+        .route(
+            "/api/admin/stats/referrers",
+            get(get_referrers).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/analytics/{dataset}/export",
+            with_load_limit(get(export), LoadClass::Exports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/analytics/{dataset}/import",
+            with_body_limit(post(import), BodyGroup::Imports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn count_view(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<ViewRequest>,
+) -> Result<StatusCode, AppError> {
+    // Views are counted under `category/slug`, whichever form the path used.
+    let slug = {
+        let store = state.store.read().await;
+        store
+            .get_by_slug(&slug)
+            .filter(|a| visibility.allows_direct(&store, a))
+            .map(|a| a.slug_with_category())
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?
+    };
+
+    let own_host = Url::parse(&state.config.hostname).ok();
+    let source = ViewSource {
+        referrer_host: payload
+            .referrer
+            .as_deref()
+            .and_then(|r| referrer_host(r, own_host.as_ref().and_then(|u| u.host_str()))),
+        utm_source: payload.utm_source.as_deref().and_then(scrub_utm),
+        utm_medium: payload.utm_medium.as_deref().and_then(scrub_utm),
+        utm_campaign: payload.utm_campaign.as_deref().and_then(scrub_utm),
+    };
+    record_view(&state.db, &slug, &source)
+        .await
+        .map_err(db_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_referrers(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReferrerParams>,
+) -> Result<Json<ReferrerReport>, AppError> {
+    let sources = referrer_counts(&state.db, params.slug.as_deref())
+        .await
+        .map_err(db_error)?;
+    Ok(Json(ReferrerReport {
+        slug: params.slug,
+        total_views: sources.iter().map(|s| s.views).sum(),
+        sources,
+    }))
+}
+
+/// One analytics dataset from `from` to `to`, as JSON or as CSV with a
+/// header row.
+async fn export(
+    State(state): State<Arc<AppState>>,
+    Path(dataset): Path<AnalyticsDataset>,
+    Query(params): Query<AnalyticsExportParams>,
+) -> Result<Response, AppError> {
+    let (from, to) = (params.from, params.to);
+    if let (Some(from), Some(to)) = (from, to)
+        && from > to
+    {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "from must not be after to".to_string(),
+        });
+    }
+    let filename = format!("{}.csv", dataset.as_str());
+    match dataset {
+        AnalyticsDataset::Views => {
+            let views = export_views(&state.db, from, to).await.map_err(db_error)?;
+            export_rows(&views, params.format, &filename)
+        }
+        AnalyticsDataset::Reactions => {
+            let reactions = export_reactions(&state.db, from, to)
+                .await
+                .map_err(db_error)?;
+            export_rows(&reactions, params.format, &filename)
+        }
+        AnalyticsDataset::Searches => {
+            let searches = enabled_search(&state)?.recent_searches(from, to).await;
+            export_rows(&searches, params.format, &filename)
+        }
+    }
+}
+
+/// Imports one analytics dataset in its export format: a JSON array, or CSV
+/// when the request is sent as `text/csv`. Nothing is stored unless every
+/// row is valid.
+async fn import(
+    State(state): State<Arc<AppState>>,
+    Path(dataset): Path<AnalyticsDataset>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportSummary>, AppError> {
+    let summary = match dataset {
+        AnalyticsDataset::Views => {
+            let views =
+                validate_views(parse_rows(&headers, &body)?).map_err(AppError::Validation)?;
+            let imported = import_views(&state.db, &views).await.map_err(db_error)?;
+            ImportSummary {
+                imported,
+                skipped: 0,
+            }
+        }
+        AnalyticsDataset::Reactions => {
+            let reactions =
+                validate_reactions(parse_rows(&headers, &body)?).map_err(AppError::Validation)?;
+            import_reactions(&state.db, &reactions)
+                .await
+                .map_err(db_error)?
+        }
+        AnalyticsDataset::Searches => {
+            let search = enabled_search(&state)?;
+            let searches =
+                validate_searches(parse_rows(&headers, &body)?).map_err(AppError::Validation)?;
+            let imported = searches.len();
+            search.import_searches(searches).await;
+            ImportSummary {
+                imported,
+                skipped: 0,
+            }
+        }
+    };
+    state.cache.invalidate_all();
+    Ok(Json(summary))
+}
+
+fn blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Checks exported views and scrubs their sources as [`count_view`] does.
+/// Errors are reported per row under `views`, numbered from 1.
+fn validate_views(rows: Vec<ViewRecord>) -> Result<Vec<ViewRecord>, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    let mut views = Vec::with_capacity(rows.len());
+    for (i, row) in rows.into_iter().enumerate() {
+        let n = i + 1;
+        let slug = row.slug.trim().to_string();
+        if slug.is_empty() {
+            errors.add("views", format!("row {}: slug must not be empty", n));
+        }
+        let Ok(day) = NaiveDate::parse_from_str(row.viewed_on.trim(), "%Y-%m-%d") else {
+            errors.add("views", format!("row {}: invalid viewed_on", n));
+            continue;
+        };
+        views.push(ViewRecord {
+            slug,
+            viewed_on: day.format("%Y-%m-%d").to_string(),
+            referrer_host: blank(row.referrer_host).map(|host| host.to_lowercase()),
+            utm_source: row.utm_source.as_deref().and_then(scrub_utm),
+            utm_medium: row.utm_medium.as_deref().and_then(scrub_utm),
+            utm_campaign: row.utm_campaign.as_deref().and_then(scrub_utm),
+        });
+    }
+    errors.into_result().map(|()| views)
+}
+
+/// Checks exported reactions. Errors are reported per row under
+/// `reactions`, numbered from 1.
+fn validate_reactions(rows: Vec<ReactionRecord>) -> Result<Vec<ReactionRecord>, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    let mut reactions = Vec::with_capacity(rows.len());
+    for (i, row) in rows.into_iter().enumerate() {
+        let n = i + 1;
+        let article_slug = row.article_slug.trim().to_string();
+        if article_slug.is_empty() {
+            errors.add(
+                "reactions",
+                format!("row {}: article_slug must not be empty", n),
+            );
+        }
+        if row.user_github_id <= 0 {
+            errors.add(
+                "reactions",
+                format!("row {}: user_github_id must be positive", n),
+            );
+        }
+        let emoji = row.emoji.trim().to_string();
+        if emoji.is_empty() {
+            errors.add("reactions", format!("row {}: emoji must not be empty", n));
+        }
+        let created_at = match row.created_at.trim() {
+            "" => Some(current_timestamp()),
+            value => normalize_timestamp(value),
+        };
+        let Some(created_at) = created_at else {
+            errors.add("reactions", format!("row {}: invalid created_at", n));
+            continue;
+        };
+        reactions.push(ReactionRecord {
+            article_slug,
+            user_github_id: row.user_github_id,
+            emoji,
+            created_at,
+        });
+    }
+    errors.into_result().map(|()| reactions)
+}
+
+/// Checks exported searches. Errors are reported per row under `searches`,
+/// numbered from 1.
+fn validate_searches(rows: Vec<SearchStats>) -> Result<Vec<SearchStats>, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    for (i, row) in rows.iter().enumerate() {
+        let n = i + 1;
+        if row.query.trim().is_empty() {
+            errors.add("searches", format!("row {}: query must not be empty", n));
+        }
+        if row.count == 0 {
+            errors.add("searches", format!("row {}: count must be positive", n));
+        }
+    }
+    errors.into_result().map(|()| rows)
+}
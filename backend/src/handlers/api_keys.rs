@@ -0,0 +1,126 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::api_key::{ApiKey, ApiKeyUsage, IssuedApiKey};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::api_key_service::{
+    daily_usage, get_key, issue_key, list_keys, quota_for, revoke_key,
+};
+use axum::extract::{Path, Query, State};
+use axum::middleware;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const MAX_NAME_LENGTH: usize = 100;
+const DEFAULT_USAGE_DAYS: usize = 30;
+const MAX_USAGE_DAYS: usize = 366;
+
+/// `name` says which app the key is for. `daily_quota` overrides the
+/// configured quota for this key; 0 lifts the limit.
+#[derive(Deserialize, Debug)]
+pub struct IssueKeyRequest {
+    pub name: String,
+    pub daily_quota: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UsageParams {
+    pub days: Option<usize>,
+}
+
+impl Validate for IssueKeyRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("name", &self.name);
+        errors.max_chars("name", &self.name, MAX_NAME_LENGTH);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/keys",
+            get(list)
+                .post(issue)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/keys/{id}",
+            delete(revoke).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/keys/{id}/usage",
+            get(usage).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<ApiKey>>, AppError> {
+    Ok(Json(list_keys(&state.db).await.map_err(db_error)?))
+}
+
+async fn issue(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<IssueKeyRequest>,
+) -> Result<Json<IssuedApiKey>, AppError> {
+    let issued = issue_key(&state.db, payload.name.trim(), payload.daily_quota)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(issued))
+}
+
+/// Requests made with a key over the last `days` UTC days, 30 by default.
+/// Revoked keys keep their history.
+async fn usage(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Query(params): Query<UsageParams>,
+) -> Result<Json<ApiKeyUsage>, AppError> {
+    let key = get_key(&state.db, id)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ApiKeyNotFound,
+            message: format!("API key {} not found", id),
+        })?;
+    let days = params
+        .days
+        .unwrap_or(DEFAULT_USAGE_DAYS)
+        .clamp(1, MAX_USAGE_DAYS);
+    let days = daily_usage(&state.db, id, days).await.map_err(db_error)?;
+    let today = Utc::now().date_naive().to_string();
+    Ok(Json(ApiKeyUsage {
+        id: key.id,
+        name: key.name.clone(),
+        daily_quota: quota_for(&key, state.config.api_keys.daily_quota),
+        today: days
+            .iter()
+            .find(|usage| usage.day == today)
+            .map_or(0, |usage| usage.requests),
+        days,
+    }))
+}
+
+async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    if !revoke_key(&state.db, id).await.map_err(db_error)? {
+        return Err(AppError::NotFound {
+            code: ErrorCode::ApiKeyNotFound,
+            message: format!("API key {} not found", id),
+        });
+    }
+    Ok(Json(json!({ "id": id, "message": "API key revoked" })))
+}
@@ -1,22 +1,24 @@
-use crate::handlers::error::{
-    AppError, ERR_ARTICLE_NOT_FOUND, ERR_INTERNAL_SERVER, ERR_VERSION_NOT_FOUND,
-};
-use crate::models::version::VersionRecord;
-use crate::server::app::AppState;
+use crate::handlers::articles::{members_only, rolled_back};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::version::{Changelog, VersionRecord};
+use crate::server::app::{AppState, record_content_change};
 use crate::server::auth::require_author;
-use crate::services::article_service::save_version;
+use crate::server::visibility::Visibility;
+use crate::services::article_service::{ArticleWrite, version_dir};
+use crate::services::atomic_file::write_atomic;
+use crate::services::changelog;
 use axum::extract::{Path, State};
 use axum::middleware;
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use chrono::{DateTime, Utc};
 use std::fs;
-use std::path::Path as StdPath;
 use std::sync::Arc;
 use std::time::SystemTime;
 
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
+        .route("/api/articles/{id}/changelog", get(changelog))
         .route("/api/articles/{id}/versions", get(list_versions))
         .route("/api/articles/{id}/versions/{version}", get(get_version))
         .route(
@@ -25,34 +27,53 @@ pub fn create_router() -> Router<Arc<AppState>> {
         )
 }
 
+/// When and how much a published article changed, for readers to see that it
+/// is kept up to date.
+async fn changelog(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(id): Path<String>,
+) -> Result<Json<Changelog>, AppError> {
+    let store = state.store.read().await;
+    let article = store
+        .get_by_slug(&id)
+        .filter(|a| visibility.allows_direct(&store, a))
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: "Article not found".to_string(),
+        })?;
+    Ok(Json(changelog::changelog(&state.data_dir, article)))
+}
+
 async fn list_versions(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
     Path(id): Path<String>,
 ) -> Result<Json<Vec<VersionRecord>>, AppError> {
     let store = state.store.read().await;
-    let article = store.get_by_slug(&id).ok_or_else(|| AppError::NotFound {
-        code: ERR_ARTICLE_NOT_FOUND,
-        message: "Article not found".to_string(),
-    })?;
-    if article.metadata.draft {
-        return Err(AppError::NotFound {
-            code: ERR_ARTICLE_NOT_FOUND,
+    let article = store
+        .get_by_slug(&id)
+        .filter(|a| visibility.allows_direct(&store, a))
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
             message: "Article not found".to_string(),
-        });
+        })?;
+    if !visibility.unlocks(article) {
+        return Err(members_only(article));
     }
-    let slug = article.slug.clone();
-    let version_dir = format!("data/articles/{}/versions", slug);
-    if !StdPath::new(&version_dir).exists() {
+    let slug = article.slug_with_category();
+    let version_dir = version_dir(&state.data_dir, &slug);
+    if !version_dir.exists() {
         return Ok(Json(vec![]));
     }
     let mut records = Vec::new();
     let entries = fs::read_dir(&version_dir).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
+        code: ErrorCode::InternalServer,
         message: e.to_string(),
     })?;
     for entry in entries {
         let entry = entry.map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
+            code: ErrorCode::InternalServer,
             message: e.to_string(),
         })?;
         let file_name = entry.file_name();
@@ -82,33 +103,34 @@ async fn list_versions(
 
 async fn get_version(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
     Path((id, version)): Path<(String, u64)>,
 ) -> Result<Json<VersionRecord>, AppError> {
     let store = state.store.read().await;
-    let article = store.get_by_slug(&id).ok_or_else(|| AppError::NotFound {
-        code: ERR_ARTICLE_NOT_FOUND,
-        message: "Article not found".to_string(),
-    })?;
-    if article.metadata.draft {
-        return Err(AppError::NotFound {
-            code: ERR_ARTICLE_NOT_FOUND,
+    let article = store
+        .get_by_slug(&id)
+        .filter(|a| visibility.allows_direct(&store, a))
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
             message: "Article not found".to_string(),
-        });
+        })?;
+    if !visibility.unlocks(article) {
+        return Err(members_only(article));
     }
-    let slug = article.slug.clone();
-    let path = format!("data/articles/{}/versions/{}.md", slug, version);
+    let slug = article.slug_with_category();
+    let path = version_dir(&state.data_dir, &slug).join(format!("{}.md", version));
     let content = fs::read_to_string(&path).map_err(|_| AppError::NotFound {
-        code: ERR_VERSION_NOT_FOUND,
+        code: ErrorCode::VersionNotFound,
         message: "Version not found".to_string(),
     })?;
     let metadata = fs::metadata(&path).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
+        code: ErrorCode::InternalServer,
         message: e.to_string(),
     })?;
     let modified = metadata
         .modified()
         .map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
+            code: ErrorCode::InternalServer,
             message: e.to_string(),
         })?;
     let timestamp: DateTime<Utc> = modified.into();
@@ -127,22 +149,26 @@ async fn restore_version(
 ) -> Result<Json<VersionRecord>, AppError> {
     let store = state.store.read().await;
     let article = store.get_by_slug(&id).ok_or_else(|| AppError::NotFound {
-        code: ERR_ARTICLE_NOT_FOUND,
+        code: ErrorCode::ArticleNotFound,
         message: "Article not found".to_string(),
     })?;
-    let version_path = format!("data/articles/{}/versions/{}.md", id, version);
+    let version_path =
+        version_dir(&state.data_dir, &article.slug_with_category()).join(format!("{}.md", version));
     let content = fs::read_to_string(&version_path).map_err(|_| AppError::NotFound {
-        code: ERR_VERSION_NOT_FOUND,
+        code: ErrorCode::VersionNotFound,
         message: "Version not found".to_string(),
     })?;
-    fs::write(&article.file_path, &content).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
+    let internal = |e: std::io::Error| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
         message: e.to_string(),
-    })?;
-    save_version(article).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
-        message: e.to_string(),
-    })?;
+    };
+    let mut write = ArticleWrite::begin(&article.file_path).map_err(internal)?;
+    write_atomic(&article.file_path, &content).map_err(internal)?;
+    let message = format!("Restored version {}", version);
+    if let Err(e) = write.save_version(&state.data_dir, article, Some(&message)) {
+        return Err(rolled_back(write, "save a version", e));
+    }
+    record_content_change(&state).await;
     let timestamp = Utc::now();
     Ok(Json(VersionRecord {
         article_id: id,
@@ -1,49 +1,123 @@
-use crate::config::{ARTICLE_DIR, ENABLE_NESTED_CATEGORIES};
-use crate::handlers::error::{
-    AppError, ERR_ARTICLE_NOT_FOUND, ERR_BAD_REQUEST, ERR_INTERNAL_SERVER,
-};
+use crate::config::META_CACHE_TTL_SECONDS;
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::polls::article_poll_results;
+use crate::handlers::response::{Pagination, page_offset, requested_page, total_pages};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
 use crate::models::article::{
-    Article, ArticleContent, ArticleRepresentation, ArticleTeaser, Metadata, PaginatedArticles,
+    Article, ArticleContent, ArticleDetail, ArticleMeta, ArticleRepresentation, ArticleStatus,
+    ArticleTeaser, LinkPost, Metadata, PaginatedArticles,
 };
-use crate::server::app::{AppState, IndexJob};
+use crate::models::merge::MergeResult;
+use crate::server::app::{AppState, IndexJob, record_content_change, record_deletions};
 use crate::server::auth::require_author;
-use crate::services::article_service::save_version;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::server::visibility::Visibility;
+use crate::services::analytics_service::{article_views, coarsen};
+use crate::services::article_service::{ArticleWrite, remove_empty_dirs, revised_at};
+use crate::services::asset_service::ASSET_DIR;
+use crate::services::atomic_file::write_atomic;
+use crate::services::citation_service::article_references;
+use crate::services::comment_service::count_comments;
+use crate::services::crosspost_service::queue_crosspost;
+use crate::services::duplicate_service::similar_titles;
+use crate::services::front_matter::{category_defaults, front_matter_yaml, patch_front_matter};
+use crate::services::link_preview::fetch_link_preview;
+use crate::services::merge_service::merge;
+use crate::services::notification_service::notify_author;
+use crate::services::ping_service::announce_publication;
+use crate::services::presence_service::announce_live;
+use crate::services::reaction_service::reaction_counts;
+use crate::services::render_service::image_sources;
 use crate::services::service::ArticleStore;
-use axum::extract::{Path, Query, State};
+use crate::services::trash_service::trash_article;
+use crate::services::undo_service::undo_trashing;
+use crate::services::user_preferences_service::get_preferences;
+use axum::body::Body;
+use axum::extract::{Extension, Path, Query, State};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
 use axum::middleware;
-use axum::response::IntoResponse;
-use axum::routing::{get, post, put};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
+use bytes::Bytes;
 use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
+use reqwest::Url;
 use serde::Deserialize;
 use serde_json::{Value, json};
+use serde_yaml::Mapping;
 use slug::slugify;
+use std::cmp::Reverse;
 use std::fs;
 use std::path::{Path as StdPath, PathBuf};
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::RwLock;
 
+/// Order of article listings.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArticleSort {
+    /// Newest publish date first.
+    #[default]
+    Published,
+    /// Most recently revised first; articles never revised after
+    /// publication fall back to their publish date.
+    Updated,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ArticleParams {
     tag: Option<String>,
     category: Option<String>,
     q: Option<String>,
     include_content: Option<bool>,
+    /// Send the page as newline-delimited JSON, one article per line.
+    stream: Option<bool>,
     #[serde(default = "default_page")]
     page: usize,
-    #[serde(default = "default_limit")]
-    limit: usize,
+    limit: Option<usize>,
+    #[serde(default)]
+    sort: ArticleSort,
+    /// List archived articles too; only honored with the admin token.
+    #[serde(default)]
+    include_archived: bool,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct CreateArticleRequest {
+    #[serde(default)]
     pub title: String,
+    #[serde(default)]
     pub content: String,
     pub tags: Option<Vec<String>>,
     pub category: Option<String>,
     pub description: Option<String>,
+    pub status: Option<ArticleStatus>,
+    /// Older form of `status`, used when `status` is not given.
     pub draft: Option<bool>,
+    /// Makes the article a link post; its body may then be empty.
+    pub link: Option<LinkPost>,
+    /// Fill the title, description and image the request leaves out from
+    /// the linked page.
+    #[serde(default)]
+    pub fetch_link: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct LanguageParams {
+    /// Language to serve the article in, e.g. `zh`. Overrides the saved
+    /// preference of a signed-in user.
+    pub lang: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CreateArticleParams {
+    /// Create the article even if another has a nearly identical title.
+    pub force: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -53,53 +127,283 @@ pub struct UpdateArticleRequest {
     pub tags: Option<Vec<String>>,
     pub category: Option<String>,
     pub description: Option<String>,
+    pub status: Option<ArticleStatus>,
+    /// Older form of `status`, used when `status` is not given.
     pub draft: Option<bool>,
+    /// Describes the change in the article's public changelog.
+    pub message: Option<String>,
 }
 
-fn default_page() -> usize {
-    1
+const MAX_TITLE_LENGTH: usize = 200;
+const MAX_MESSAGE_LENGTH: usize = 500;
+
+/// The status asked for by a write, from `status` or the older `draft`
+/// flag.
+fn requested_status(status: Option<ArticleStatus>, draft: Option<bool>) -> Option<ArticleStatus> {
+    status.or(draft.map(|draft| {
+        if draft {
+            ArticleStatus::Draft
+        } else {
+            ArticleStatus::Published
+        }
+    }))
+}
+
+fn validate_article_fields(
+    title: &str,
+    content: &str,
+    tags: Option<&[String]>,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    errors.require("title", title);
+    errors.max_chars("title", title, MAX_TITLE_LENGTH);
+    errors.require("content", content);
+    if tags.is_some_and(|tags| tags.iter().any(|t| t.trim().is_empty())) {
+        errors.add("tags", "must not contain empty tags");
+    }
+    errors.into_result()
+}
+
+impl Validate for CreateArticleRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let Some(link) = &self.link else {
+            return validate_article_fields(&self.title, &self.content, self.tags.as_deref());
+        };
+        let mut errors = ValidationErrors::default();
+        if !self.fetch_link {
+            errors.require("title", &self.title);
+        }
+        errors.max_chars("title", &self.title, MAX_TITLE_LENGTH);
+        if !Url::parse(&link.url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+            errors.add("link", "must be an http or https URL");
+        }
+        if self
+            .tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t.trim().is_empty()))
+        {
+            errors.add("tags", "must not contain empty tags");
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for UpdateArticleRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let fields = validate_article_fields(&self.title, &self.content, self.tags.as_deref());
+        let mut errors = fields.err().unwrap_or_default();
+        if let Some(message) = &self.message {
+            errors.max_chars("message", message, MAX_MESSAGE_LENGTH);
+        }
+        errors.into_result()
+    }
+}
+
+/// A client's edit to merge into the article: `base` is the body it was
+/// made from and `content` the edited body.
+#[derive(Deserialize, Debug)]
+pub struct MergeRequest {
+    pub base: String,
+    pub content: String,
+}
+
+impl Validate for MergeRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("content", &self.content);
+        errors.into_result()
+    }
 }
 
-fn default_limit() -> usize {
-    10
+fn default_page() -> usize {
+    1
 }
 
-fn write_article_to_file(
+/// Writes `metadata` and `content` to `file_path`. When the article already
+/// has a file at `previous`, its front matter is patched rather than
+/// regenerated, so hand-written formatting and comments survive API edits,
+/// and values it inherits from the category defaults given along with it
+/// stay inherited.
+pub(crate) fn write_article_to_file(
     metadata: &Metadata,
     content: &str,
     file_path: &StdPath,
+    previous: Option<(&StdPath, &Mapping)>,
 ) -> Result<(), AppError> {
     if let Some(parent) = file_path.parent() {
         fs::create_dir_all(parent).map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
+            code: ErrorCode::InternalServer,
             message: e.to_string(),
         })?;
     }
 
-    let front_matter =
-        serde_yaml::to_string(metadata).map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
-            message: e.to_string(),
-        })?;
+    let metadata = &*metadata.for_front_matter();
+    let previous =
+        previous.and_then(|(path, defaults)| Some((fs::read_to_string(path).ok()?, defaults)));
+    let front_matter = match &previous {
+        Some((text, defaults)) if let Some(original) = front_matter_yaml(text) => {
+            patch_front_matter(original, metadata, defaults)
+        }
+        _ => serde_yaml::to_string(metadata),
+    }
+    .map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    })?;
     let file_content = format!("---\n{}---\n\n{}", front_matter, content);
-    fs::write(file_path, file_content).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
+    write_atomic(file_path, file_content).map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
         message: e.to_string(),
     })?;
     Ok(())
 }
 
+fn start_write(file_path: &StdPath) -> Result<ArticleWrite, AppError> {
+    ArticleWrite::begin(file_path).map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    })
+}
+
+/// Undoes `write` after `step` failed, reporting the failure together with
+/// what was rolled back.
+pub(crate) fn rolled_back(
+    write: ArticleWrite,
+    step: &str,
+    cause: impl std::fmt::Display,
+) -> AppError {
+    let undone = write.rollback();
+    AppError::InternalServerError {
+        code: ErrorCode::PersistenceRolledBack,
+        message: format!("Failed to {}: {}; {}", step, cause, undone.join(", ")),
+    }
+}
+
+/// Writes an existing article back to its file with new metadata, then
+/// records a version, described by `message` when given, and refreshes the
+/// store, search index and cache.
+pub(crate) async fn rewrite_article(
+    state: &AppState,
+    article: &Article,
+    content: &str,
+    message: Option<&str>,
+) -> Result<(), AppError> {
+    let file_path = StdPath::new(&article.file_path);
+    let defaults = category_defaults(state.store.read().await.root(), file_path);
+    let mut write = start_write(file_path)?;
+    write_article_to_file(
+        &article.metadata,
+        content,
+        file_path,
+        Some((file_path, &defaults)),
+    )?;
+    if let Err(e) = write.save_version(&state.data_dir, article, message) {
+        return Err(rolled_back(write, "save a version", e));
+    }
+    if let Err(e) = state
+        .store
+        .write()
+        .await
+        .update_single_article(&article.file_path)
+    {
+        return Err(rolled_back(write, "update the article store", e));
+    }
+
+    if let Some(tx) = &state.index_tx {
+        let _ = tx.send(IndexJob::for_article(ArticleContent {
+            slug: article.slug_with_category(),
+            metadata: article.metadata.clone(),
+            content: content.to_string(),
+            edit_url: None,
+        }));
+    }
+    state.cache.invalidate_all();
+    record_content_change(state).await;
+    Ok(())
+}
+
+/// Tells the author about an article moving from `from` to `to` in the
+/// editorial workflow.
+async fn status_changed(state: &AppState, slug: &str, from: ArticleStatus, to: ArticleStatus) {
+    notify_author(
+        state,
+        &json!({
+            "event": "status_changed",
+            "slug": slug,
+            "url": state.config.article_url(slug),
+            "from": from,
+            "to": to,
+        }),
+    )
+    .await;
+}
+
+/// Publishes the scheduled articles whose date has passed, for the
+/// `publish_scheduled` job. Returns how many were published.
+pub async fn publish_scheduled(state: &AppState) -> Result<usize, String> {
+    let now = Utc::now();
+    let due: Vec<Article> = state
+        .store
+        .read()
+        .await
+        .query(
+            |a| a.metadata.status == ArticleStatus::Scheduled && a.metadata.date <= now,
+            0,
+            usize::MAX,
+        )
+        .cloned()
+        .collect();
+    for mut article in due.iter().cloned() {
+        let content = state
+            .store
+            .read()
+            .await
+            .load_source_for(&article)
+            .map_err(|e| format!("Failed to load {}: {}", article.slug, e))?;
+        article.metadata.status = ArticleStatus::Published;
+        rewrite_article(state, &article, &content, Some("Published as scheduled"))
+            .await
+            .map_err(|e| format!("Failed to publish {}: {:?}", article.slug, e))?;
+        status_changed(
+            state,
+            &article.slug,
+            ArticleStatus::Scheduled,
+            ArticleStatus::Published,
+        )
+        .await;
+        announce_publication(state, &article.slug).await;
+        queue_crosspost(state, &article.slug).await;
+        announce_live(state, &article.slug).await;
+    }
+    Ok(due.len())
+}
+
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/api/articles", get(get_articles_list))
         .route(
             "/api/articles",
-            post(create_article).route_layer(middleware::from_fn(require_author)),
+            with_load_limit(get(get_articles_list), LoadClass::ContentListings),
+        )
+        .route(
+            "/api/articles",
+            with_body_limit(post(create_article), BodyGroup::Articles)
+                .route_layer(middleware::from_fn(require_author)),
         )
         .route("/api/articles/{slug}", get(get_article_by_slug))
+        .route("/api/articles/{slug}/meta", get(get_article_meta))
         .route(
             "/api/articles/{slug}",
-            put(update_article).route_layer(middleware::from_fn(require_author)),
+            with_body_limit(put(update_article), BodyGroup::Articles)
+                .route_layer(middleware::from_fn(require_author)),
+        )
+        .route(
+            "/api/articles/{slug}",
+            delete(delete_article).route_layer(middleware::from_fn(require_author)),
+        )
+        .route(
+            "/api/articles/{slug}/merge",
+            with_body_limit(post(merge_article), BodyGroup::Articles)
+                .route_layer(middleware::from_fn(require_author)),
         )
 }
 
@@ -110,10 +414,6 @@ fn matches_filters(
     search_slugs: &Option<std::collections::HashSet<String>>,
     query_lower: &Option<String>,
 ) -> bool {
-    if article.metadata.draft {
-        return false;
-    }
-
     if let Some(t) = tag {
         if !article.metadata.tags.contains(t) {
             return false;
@@ -140,6 +440,7 @@ async fn filter_articles<'a>(
     store: &'a ArticleStore,
     params: &ArticleParams,
     state: &AppState,
+    visibility: Visibility,
     offset: usize,
     limit: usize,
 ) -> (Vec<&'a Article>, usize) {
@@ -167,61 +468,84 @@ async fn filter_articles<'a>(
 
     let query_lower = query.map(|q| q.to_lowercase());
 
-    let tag_filter = tag.clone();
-    let category_filter = category.clone();
-    let search_slugs_filter = search_slugs.clone();
-    let query_lower_filter = query_lower.clone();
-
-    let articles: Vec<&Article> = store
-        .query(
-            move |a| {
-                matches_filters(
-                    a,
-                    &tag_filter,
-                    &category_filter,
-                    &search_slugs_filter,
-                    &query_lower_filter,
-                )
-            },
-            offset,
-            limit,
-        )
-        .collect();
-
-    let total = store
-        .query(
-            move |a| matches_filters(a, &tag, &category, &search_slugs, &query_lower),
-            0,
-            usize::MAX,
-        )
-        .count();
-
-    (articles, total)
+    let filter = |a: &Article| {
+        visibility.allows(store, a)
+            && matches_filters(a, &tag, &category, &search_slugs, &query_lower)
+    };
+    match params.sort {
+        ArticleSort::Published => store.query_paginated(filter, offset, limit),
+        ArticleSort::Updated => {
+            let (matching, total) = store.query_paginated(filter, 0, usize::MAX);
+            let mut matching: Vec<_> = matching
+                .into_iter()
+                .map(|a| {
+                    let updated = revised_at(&state.data_dir, a).unwrap_or(a.metadata.date);
+                    (updated, a)
+                })
+                .collect();
+            matching.sort_by_key(|&(updated, _)| Reverse(updated));
+            let page = matching
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .map(|(_, a)| a)
+                .collect();
+            (page, total)
+        }
+    }
 }
 
-async fn get_articles_list(
+pub(crate) async fn get_articles_list(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
     Query(params): Query<ArticleParams>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<Response, AppError> {
     let store = state.store.read().await;
-    let limit = if params.limit > 0 { params.limit } else { 10 };
-    let page = if params.page > 0 { params.page } else { 1 };
-    let offset = (page - 1) * limit;
+    let limit = state.config.pagination.limit(params.limit);
+    let page = requested_page(params.page);
+    let offset = page_offset(page, limit);
+    let visibility = visibility.with_archived(params.include_archived);
     let (paginated_articles_vec, total_articles) =
-        filter_articles(&store, &params, &state, offset, limit).await;
-    let total_pages = (total_articles as f64 / limit as f64).ceil() as usize;
-    let paginated_articles = paginated_articles_vec.into_iter();
+        filter_articles(&store, &params, &state, visibility, offset, limit).await;
+    let total_pages = total_pages(total_articles, limit);
+    let pagination = Pagination {
+        page,
+        limit,
+        total_pages,
+        max_limit: state.config.pagination.max_limit,
+    };
 
+    if params.stream.unwrap_or(false) {
+        let articles: Vec<Article> = paginated_articles_vec.into_iter().cloned().collect();
+        let root = store.root().to_path_buf();
+        drop(store);
+        let include_content = params.include_content.unwrap_or(false);
+        return Ok(stream_articles(
+            &state,
+            root,
+            articles,
+            include_content.then_some(visibility),
+            pagination,
+        ));
+    }
+
+    let paginated_articles = paginated_articles_vec.into_iter();
     let result = if params.include_content.unwrap_or(false) {
         let articles_with_content = paginated_articles
             .map(|article| {
-                let content = store
-                    .load_content_for(article)
-                    .unwrap_or_else(|_| String::new());
+                // Members-only bodies are left out for non-members.
+                let content = if visibility.unlocks(article) {
+                    store
+                        .load_content_for(article)
+                        .unwrap_or_else(|_| String::new())
+                } else {
+                    String::new()
+                };
                 ArticleRepresentation::Full(ArticleContent {
-                    slug: article.slug.clone(),
+                    slug: article.slug_with_category(),
                     metadata: article.metadata.clone(),
                     content,
+                    edit_url: state.config.edit_url(store.root(), &article.file_path),
                 })
             })
             .collect::<Vec<_>>();
@@ -234,8 +558,9 @@ async fn get_articles_list(
         let teasers = paginated_articles
             .map(|article| {
                 ArticleRepresentation::Teaser(ArticleTeaser {
-                    slug: article.slug.clone(),
+                    slug: article.slug_with_category(),
                     metadata: article.metadata.clone(),
+                    edit_url: state.config.edit_url(store.root(), &article.file_path),
                 })
             })
             .collect::<Vec<_>>();
@@ -246,59 +571,136 @@ async fn get_articles_list(
         })
     };
 
-    Ok(result)
+    Ok((Extension(pagination), result).into_response())
 }
 
-async fn prepare_metadata(
-    store: Arc<RwLock<ArticleStore>>,
-    payload: &CreateArticleRequest,
-) -> Result<(String, Metadata, PathBuf), AppError> {
-    if payload.title.trim().is_empty() || payload.content.trim().is_empty() {
-        return Err(AppError::BadRequest {
-            code: ERR_BAD_REQUEST,
-            message: "Title and content cannot be empty".to_string(),
-        });
-    }
+/// Streams a page of articles as NDJSON. Bodies are loaded one at a time
+/// while the response is written, so a large page is never buffered whole;
+/// the store is only locked while each body is read. Bodies are included
+/// when `content_for` is given, except those it does not unlock.
+fn stream_articles(
+    state: &AppState,
+    root: PathBuf,
+    articles: Vec<Article>,
+    content_for: Option<Visibility>,
+    pagination: Pagination,
+) -> Response {
+    let store = Arc::clone(&state.store);
+    let config = Arc::clone(&state.config);
+    let lines = stream::iter(articles).then(move |article| {
+        let store = Arc::clone(&store);
+        let edit_url = config.edit_url(&root, &article.file_path);
+        async move {
+            let representation = if let Some(visibility) = content_for {
+                let content = if visibility.unlocks(&article) {
+                    store
+                        .read()
+                        .await
+                        .load_content_for(&article)
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                };
+                ArticleRepresentation::Full(ArticleContent {
+                    slug: article.slug_with_category(),
+                    metadata: article.metadata,
+                    content,
+                    edit_url,
+                })
+            } else {
+                ArticleRepresentation::Teaser(ArticleTeaser {
+                    slug: article.slug_with_category(),
+                    metadata: article.metadata,
+                    edit_url,
+                })
+            };
+            let mut line = serde_json::to_vec(&representation)?;
+            line.push(b'\n');
+            Ok::<_, serde_json::Error>(Bytes::from(line))
+        }
+    });
 
-    let base_slug = slugify(&payload.title);
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (
+                header::HeaderName::from_static("x-total-pages"),
+                pagination.total_pages.to_string(),
+            ),
+        ],
+        Extension(pagination),
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// The slug of a new article titled `title`: the slugified title, with
+/// the first free `-N` suffix when `taken` says it is in use.
+fn unique_slug(title: &str, taken: impl Fn(&str) -> bool) -> Result<String, AppError> {
+    let base_slug = slugify(title);
     if base_slug.is_empty() {
         return Err(AppError::BadRequest {
-            code: ERR_BAD_REQUEST,
+            code: ErrorCode::BadRequest,
             message: "Invalid title for slug generation".to_string(),
         });
     }
 
     let mut slug_candidate = base_slug.clone();
     let mut counter = 1;
-    while store.read().await.get_by_slug(&slug_candidate).is_some() {
+    while taken(&slug_candidate) {
         if counter > 100 {
             return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
+                code: ErrorCode::BadRequest,
                 message: "Exceeded maximum slug generation attempts".to_string(),
             });
         }
         slug_candidate = format!("{}-{}", base_slug, counter);
         counter += 1;
     }
-    let slug = slug_candidate;
+    Ok(slug_candidate)
+}
+
+async fn prepare_metadata(
+    store: Arc<RwLock<ArticleStore>>,
+    payload: &CreateArticleRequest,
+) -> Result<(String, Metadata, PathBuf), AppError> {
+    let slug = {
+        let store = store.read().await;
+        unique_slug(&payload.title, |slug| store.get_by_slug(slug).is_some())?
+    };
 
     let metadata = Metadata {
         title: payload.title.clone(),
         author: "system".to_string(),
         date: Utc::now(),
+        display_date: None,
         tags: payload.tags.clone().unwrap_or_default(),
         description: payload.description.clone().unwrap_or_default(),
-        draft: payload.draft.unwrap_or(false),
+        status: requested_status(payload.status, payload.draft).unwrap_or_default(),
         last_updated: None,
         category: payload.category.clone(),
+        poll: None,
+        lang: None,
+        translations: Default::default(),
+        private: false,
+        http_headers: Default::default(),
+        comments: Default::default(),
+        menu: None,
+        audio: None,
+        gallery: Vec::new(),
+        link: payload.link.clone(),
+        syndication: Default::default(),
+        references: Vec::new(),
+        downloads: Vec::new(),
+        description_generated: false,
+        members_only: false,
     };
 
+    let root = store.read().await.root().to_path_buf();
     let file_path = if let Some(ref cat) = payload.category {
-        StdPath::new(ARTICLE_DIR)
-            .join(cat)
-            .join(format!("{}.md", slug))
+        root.join(cat).join(format!("{}.md", slug))
     } else {
-        StdPath::new(ARTICLE_DIR).join(format!("{}.md", slug))
+        root.join(format!("{}.md", slug))
     };
 
     Ok((slug, metadata, file_path))
@@ -306,12 +708,14 @@ async fn prepare_metadata(
 
 async fn persist_article(
     store: Arc<RwLock<ArticleStore>>,
+    data_dir: &StdPath,
     slug: &str,
     metadata: &Metadata,
     content: &str,
     file_path: &StdPath,
 ) -> Result<Article, AppError> {
-    write_article_to_file(metadata, content, file_path)?;
+    let mut write = start_write(file_path)?;
+    write_article_to_file(metadata, content, file_path, None)?;
 
     let last_modified = fs::metadata(file_path)
         .and_then(|m| m.modified())
@@ -324,37 +728,146 @@ async fn persist_article(
         updated_at: Utc::now(),
         file_path: file_path.to_string_lossy().to_string(),
         last_modified,
+        includes: Vec::new(),
         deleted: false,
     };
-    save_version(&article).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
-        message: e.to_string(),
-    })?;
+    if let Err(e) = write.save_version(data_dir, &article, None) {
+        return Err(rolled_back(write, "save a version", e));
+    }
 
     {
         let mut store = store.write().await;
-        if let Err(e) = store.incremental_update(ARTICLE_DIR, ENABLE_NESTED_CATEGORIES) {
-            return Err(AppError::InternalServerError {
-                code: ERR_INTERNAL_SERVER,
-                message: e.to_string(),
-            });
+        if let Err(e) = store.incremental_update() {
+            return Err(rolled_back(write, "update the article store", e));
         }
     }
 
     Ok(article)
 }
 
+/// Headers the server sets itself, which front matter may not override.
+const RESERVED_HEADERS: &[&str] = &[
+    "connection",
+    "content-disposition",
+    "content-encoding",
+    "content-length",
+    "content-type",
+    "etag",
+    "last-modified",
+    "location",
+    "set-cookie",
+    "transfer-encoding",
+    "vary",
+];
+
+/// Refusal to serve the body of a members-only article to a non-member, by
+/// the endpoints that have nothing to show without it.
+pub fn members_only(article: &Article) -> AppError {
+    AppError::Forbidden {
+        code: ErrorCode::MembersOnly,
+        message: format!("Article {} is for members only", article.slug),
+    }
+}
+
+/// The `http_headers` of an article's front matter, to send with every
+/// response serving it. Invalid or reserved headers are left out.
+pub fn article_headers(metadata: &Metadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &metadata.http_headers {
+        let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.trim().as_bytes()),
+            HeaderValue::from_str(value.trim()),
+        ) else {
+            continue;
+        };
+        if !RESERVED_HEADERS.contains(&name.as_str()) {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
 fn build_response(slug: &str) -> Json<Value> {
     Json(json!({ "slug": slug, "message": "Article created" }))
 }
 
+/// Checks tags and a category against the `[taxonomy]` rules. In strict
+/// mode a violation refuses the write; otherwise violations are returned
+/// as warnings to report with the saved article.
+fn check_taxonomy(
+    state: &AppState,
+    tags: &[String],
+    category: Option<&str>,
+) -> Result<Vec<String>, AppError> {
+    let rules = &state.taxonomy_rules;
+    let violations = rules.violations(tags, category);
+    if rules.strict && !violations.is_empty() {
+        let mut errors = ValidationErrors::default();
+        for violation in &violations {
+            errors.add(violation.field, violation.to_string());
+        }
+        return Err(AppError::Validation(errors));
+    }
+    Ok(violations.iter().map(ToString::to_string).collect())
+}
+
+fn with_warnings(mut response: Json<Value>, warnings: Vec<String>) -> Json<Value> {
+    if !warnings.is_empty() {
+        response.0["warnings"] = json!(warnings);
+    }
+    response
+}
+
 async fn create_article(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<CreateArticleRequest>,
+    Query(params): Query<CreateArticleParams>,
+    ValidJson(mut payload): ValidJson<CreateArticleRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    let mut warnings = check_taxonomy(
+        &state,
+        payload.tags.as_deref().unwrap_or_default(),
+        payload.category.as_deref(),
+    )?;
+    if payload.fetch_link
+        && let Some(link) = payload.link.as_mut()
+    {
+        match fetch_link_preview(&state.config.http, &link.url).await {
+            Ok(preview) => {
+                if payload.title.trim().is_empty() {
+                    let title = preview.title.unwrap_or_else(|| link.url.clone());
+                    payload.title = title.chars().take(MAX_TITLE_LENGTH).collect();
+                }
+                if payload.description.is_none() {
+                    payload.description = preview.description;
+                }
+                if link.image.is_none() {
+                    link.image = preview.image;
+                }
+            }
+            // Without a title there is nothing to name the article after.
+            Err(e) if payload.title.trim().is_empty() => {
+                return Err(AppError::BadGateway {
+                    code: ErrorCode::LinkPreviewFailed,
+                    message: e.to_string(),
+                });
+            }
+            Err(e) => warnings.push(format!("Could not preview {}: {}", link.url, e)),
+        }
+    }
+    if !params.force {
+        let store = state.store.read().await;
+        let titles = store
+            .query(|_| true, 0, usize::MAX)
+            .map(|a| (a.slug.as_str(), a.metadata.title.as_str()));
+        let similar = similar_titles(&payload.title, titles);
+        if !similar.is_empty() {
+            return Err(AppError::SimilarTitles(similar));
+        }
+    }
     let (slug, metadata, file_path) = prepare_metadata(Arc::clone(&state.store), &payload).await?;
     persist_article(
         Arc::clone(&state.store),
+        &state.data_dir,
         &slug,
         &metadata,
         &payload.content,
@@ -362,43 +875,65 @@ async fn create_article(
     )
     .await?;
     if let Some(tx) = &state.index_tx {
-        let article_content = ArticleContent {
+        let mut article_content = ArticleContent {
             slug: slug.clone(),
             metadata: metadata.clone(),
             content: payload.content.clone(),
+            edit_url: None,
         };
-        let _ = tx.send(IndexJob::Index(article_content));
+        article_content.slug = article_content.slug_with_category();
+        let _ = tx.send(IndexJob::for_article(article_content));
     }
     state.cache.invalidate_all();
-    Ok(build_response(&slug))
+    record_content_change(&state).await;
+    if metadata.is_published() {
+        announce_publication(&state, &slug).await;
+        queue_crosspost(&state, &slug).await;
+        announce_live(&state, &slug).await;
+    }
+    Ok(with_warnings(build_response(&slug), warnings))
 }
 
 async fn update_article(
     State(state): State<Arc<AppState>>,
     Path(slug): Path<String>,
-    Json(payload): Json<UpdateArticleRequest>,
+    ValidJson(payload): ValidJson<UpdateArticleRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    if payload.title.trim().is_empty() || payload.content.trim().is_empty() {
-        return Err(AppError::BadRequest {
-            code: ERR_BAD_REQUEST,
-            message: "Title and content cannot be empty".to_string(),
-        });
-    }
-
-    let existing = {
+    // Only the terms being set are checked, so an article that predates the
+    // rules can still be edited.
+    let warnings = check_taxonomy(
+        &state,
+        payload.tags.as_deref().unwrap_or_default(),
+        payload.category.as_deref(),
+    )?;
+    let (root, existing) = {
         let store = state.store.read().await;
-        store.get_by_slug(&slug).cloned()
+        (
+            store.root().to_path_buf(),
+            store.get_by_slug(&slug).cloned(),
+        )
     };
 
     let mut existing_article = existing.ok_or_else(|| AppError::NotFound {
-        code: ERR_ARTICLE_NOT_FOUND,
+        code: ErrorCode::ArticleNotFound,
         message: format!("Article with slug {} not found", slug),
     })?;
+    let previous_status = existing_article.metadata.status;
+    let status = requested_status(payload.status, payload.draft).unwrap_or(previous_status);
+    if !previous_status.can_become(status) {
+        let mut errors = ValidationErrors::default();
+        errors.add(
+            "status",
+            format!("cannot change from {} to {}", previous_status, status),
+        );
+        return Err(AppError::Validation(errors));
+    }
 
     let metadata = Metadata {
         title: payload.title.clone(),
         author: existing_article.metadata.author.clone(),
         date: existing_article.metadata.date,
+        display_date: existing_article.metadata.display_date,
         tags: payload
             .tags
             .clone()
@@ -407,155 +942,491 @@ async fn update_article(
             .description
             .clone()
             .unwrap_or(existing_article.metadata.description.clone()),
-        draft: payload.draft.unwrap_or(existing_article.metadata.draft),
+        description_generated: payload.description.is_none()
+            && existing_article.metadata.description_generated,
+        status,
         last_updated: Some(Utc::now().to_rfc3339()),
         category: payload
             .category
             .clone()
             .or(existing_article.metadata.category.clone()),
+        poll: existing_article.metadata.poll.clone(),
+        lang: existing_article.metadata.lang.clone(),
+        translations: existing_article.metadata.translations.clone(),
+        private: existing_article.metadata.private,
+        http_headers: existing_article.metadata.http_headers.clone(),
+        comments: existing_article.metadata.comments,
+        menu: existing_article.metadata.menu.clone(),
+        audio: existing_article.metadata.audio.clone(),
+        gallery: existing_article.metadata.gallery.clone(),
+        link: existing_article.metadata.link.clone(),
+        syndication: existing_article.metadata.syndication.clone(),
+        references: existing_article.metadata.references.clone(),
+        downloads: existing_article.metadata.downloads.clone(),
+        members_only: existing_article.metadata.members_only,
     };
 
+    let file_name = format!("{}.md", existing_article.slug);
     let file_path = if let Some(ref cat) = metadata.category {
-        StdPath::new(ARTICLE_DIR)
-            .join(cat)
-            .join(format!("{}.md", slug))
+        root.join(cat).join(file_name)
     } else {
-        StdPath::new(ARTICLE_DIR).join(format!("{}.md", slug))
+        root.join(file_name)
     };
+    let old_path = PathBuf::from(&existing_article.file_path);
+    let moved = file_path != old_path;
+    if moved && file_path.exists() {
+        return Err(AppError::Conflict {
+            code: ErrorCode::ArticleExists,
+            message: format!(
+                "An article already exists at {}",
+                file_path
+                    .strip_prefix(&root)
+                    .unwrap_or(&file_path)
+                    .display()
+            ),
+        });
+    }
 
-    write_article_to_file(&metadata, &payload.content, &file_path)?;
+    let defaults = category_defaults(&root, &file_path);
+    let mut write = start_write(&file_path)?;
+    write_article_to_file(
+        &metadata,
+        &payload.content,
+        &file_path,
+        Some((&old_path, &defaults)),
+    )?;
 
     let last_modified = fs::metadata(&file_path)
         .and_then(|m| m.modified())
         .unwrap_or(SystemTime::now());
 
+    let was_published = existing_article.metadata.is_published();
+    let old_key = existing_article.slug_with_category();
     existing_article.metadata = metadata.clone();
     existing_article.file_path = file_path.to_string_lossy().to_string();
     existing_article.updated_at = Utc::now();
     existing_article.last_modified = last_modified;
 
-    save_version(&existing_article).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
-        message: e.to_string(),
-    })?;
+    let message = payload
+        .message
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty());
+    let new_key = existing_article.slug_with_category();
+    if let Err(e) = write.move_versions(&state.data_dir, &old_key, &new_key) {
+        return Err(rolled_back(write, "move the saved versions", e));
+    }
+    if let Err(e) = write.save_version(&state.data_dir, &existing_article, message) {
+        return Err(rolled_back(write, "save a version", e));
+    }
+
+    if moved && let Err(e) = write.remove_moved(&old_path) {
+        return Err(rolled_back(write, "remove the old article file", e));
+    }
 
     {
         let mut store = state.store.write().await;
-        if let Err(e) = store.update_single_article(
-            &existing_article.file_path,
-            ARTICLE_DIR,
-            ENABLE_NESTED_CATEGORIES,
-        ) {
-            return Err(AppError::InternalServerError {
-                code: ERR_INTERNAL_SERVER,
-                message: e.to_string(),
-            });
+        let updated = if moved {
+            store.move_article(&old_path.to_string_lossy(), &existing_article.file_path)
+        } else {
+            store.update_single_article(&existing_article.file_path)
+        };
+        if let Err(e) = updated {
+            return Err(rolled_back(write, "update the article store", e));
         }
     }
+    if moved && let Some(old_dir) = old_path.parent() {
+        remove_empty_dirs(old_dir, &root);
+    }
 
     if let Some(tx) = &state.index_tx {
         let article_content = ArticleContent {
-            slug: slug.clone(),
+            slug: new_key.clone(),
             metadata: metadata.clone(),
             content: payload.content.clone(),
+            edit_url: None,
         };
-        let _ = tx.send(IndexJob::Index(article_content));
+        // A move to another category indexes it under a new key.
+        if old_key != new_key {
+            let _ = tx.send(IndexJob::Remove(old_key));
+        }
+        let _ = tx.send(IndexJob::for_article(article_content));
     }
     state.cache.invalidate_all();
+    record_content_change(&state).await;
+    if status != previous_status {
+        status_changed(&state, &slug, previous_status, status).await;
+    }
+    if metadata.is_published() && !was_published {
+        announce_publication(&state, &slug).await;
+        queue_crosspost(&state, &slug).await;
+        announce_live(&state, &slug).await;
+    }
 
-    Ok(Json(json!({ "slug": slug, "message": "Article updated" })))
+    Ok(with_warnings(
+        Json(json!({ "slug": slug, "message": "Article updated" })),
+        warnings,
+    ))
 }
 
-async fn get_article_by_slug(
+/// Moves an article to the trash, from where an admin can restore it
+/// until it is purged. The response carries a token that undoes the
+/// deletion within the undo window.
+async fn delete_article(
     State(state): State<Arc<AppState>>,
+    jar: SignedJar,
     Path(slug): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let user = session_user(&jar)?;
+    let mut store = state.store.write().await;
+    let article = store
+        .get_by_slug(&slug)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })?;
+    let root = store.root().to_path_buf();
+    let item =
+        trash_article(&state.data_dir, &root, &article, &user.github_login).map_err(|e| {
+            AppError::InternalServerError {
+                code: ErrorCode::InternalServer,
+                message: format!("Failed to move {} to the trash: {}", slug, e),
+            }
+        })?;
+    store.remove_article(&article.file_path);
+    if let Some(dir) = StdPath::new(&article.file_path).parent() {
+        remove_empty_dirs(dir, &root);
+    }
+    if let Some(tx) = &state.index_tx {
+        let _ = tx.send(IndexJob::Remove(article.slug_with_category()));
+    }
+    record_deletions(
+        &state,
+        &store,
+        std::iter::once((article.slug_with_category(), article.metadata.title)),
+    )
+    .await;
+    drop(store);
+    state.cache.invalidate_all();
+    record_content_change(&state).await;
+    let token = undo_trashing(&state.db, &item.id, state.config.undo_window_secs)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(json!({
+        "slug": item.slug,
+        "trash_id": item.id,
+        "message": "Article moved to the trash",
+        "undo_token": token,
+        "undo_expires_in": state.config.undo_window_secs,
+    })))
+}
+
+/// Whether `lang` names the language `tag`, or its primary subtag.
+fn same_language(tag: &str, lang: &str) -> bool {
+    tag.eq_ignore_ascii_case(lang)
+        || tag
+            .split('-')
+            .next()
+            .is_some_and(|primary| primary.eq_ignore_ascii_case(lang))
+}
+
+/// The translation of `article` into `lang`, unless it is written in it.
+fn translation_into<'a>(
+    store: &'a ArticleStore,
+    article: &Article,
+    lang: &str,
+) -> Option<&'a Article> {
+    if article
+        .metadata
+        .lang
+        .as_deref()
+        .is_some_and(|own| same_language(own, lang))
+    {
+        return None;
+    }
+    article
+        .metadata
+        .translations
+        .iter()
+        .find(|(tag, _)| same_language(tag, lang))
+        .and_then(|(_, slug)| store.get_by_slug(slug))
+}
+
+/// The language to serve articles in: the `lang` parameter, or the saved
+/// preference of a signed-in user.
+async fn preferred_language(
+    state: &AppState,
+    jar: &SignedJar,
+    params: LanguageParams,
+) -> Result<Option<String>, AppError> {
+    if params.lang.is_some() {
+        return Ok(params.lang);
+    }
+    let Ok(user) = session_user(jar) else {
+        return Ok(None);
+    };
+    let prefs = get_preferences(&state.db, state.field_cipher.as_ref(), user.github_id)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(prefs.and_then(|p| p.language))
+}
+
+/// Serves the translation in the preferred language instead when there is
+/// one the requester may see; `slug` and `metadata.lang` of the response
+/// tell which variant was served. `slug` is `category/slug` for articles in
+/// categories, as listings give it.
+pub(crate) async fn get_article_by_slug(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    jar: SignedJar,
+    Path(slug): Path<String>,
+    Query(params): Query<LanguageParams>,
 ) -> Result<impl IntoResponse, AppError> {
-    let store = state.store.read().await;
-    let article = store.get_by_slug(&slug);
+    let lang = preferred_language(&state, &jar, params).await?;
+    let article = {
+        let store = state.store.read().await;
+        let found = store
+            .get_by_slug(&slug)
+            .filter(|article| visibility.allows_direct(&store, article))
+            .map(|article| {
+                lang.as_deref()
+                    .and_then(|lang| translation_into(&store, article, lang))
+                    .filter(|translated| visibility.allows_direct(&store, translated))
+                    .unwrap_or(article)
+            });
+        match found {
+            Some(article) => {
+                // Non-members get the metadata of a members-only article.
+                let locked = !visibility.unlocks(article);
+                let content = if locked {
+                    String::new()
+                } else {
+                    store
+                        .load_content_for(article)
+                        .map_err(|e| AppError::BadRequest {
+                            code: ErrorCode::BadRequest,
+                            message: e.to_string(),
+                        })?
+                };
+                let article_dir = std::path::Path::new(&article.file_path)
+                    .parent()
+                    .map(|dir| dir.to_path_buf())
+                    .unwrap_or_default();
+                let mut metadata = article.metadata.clone();
+                metadata.references =
+                    article_references(&article.metadata, std::path::Path::new(&article.file_path));
+                Some((
+                    ArticleContent {
+                        slug: article.slug_with_category(),
+                        metadata,
+                        content,
+                        edit_url: state.config.edit_url(store.root(), &article.file_path),
+                    },
+                    article_dir,
+                    locked,
+                ))
+            }
+            _ => None,
+        }
+    };
 
     match article {
-        Some(article) if !article.metadata.draft => {
-            let content = store
-                .load_content_for(article)
-                .map_err(|e| AppError::BadRequest {
-                    code: ERR_BAD_REQUEST,
+        Some((article, article_dir, locked)) => {
+            let images = {
+                let cache = state.image_info.clone();
+                let mut sources = image_sources(&article.content);
+                sources.extend(article.metadata.gallery.iter().map(|g| g.src.clone()));
+                let asset_dir = state.data_dir.join(ASSET_DIR);
+                tokio::task::spawn_blocking(move || {
+                    cache.for_sources(sources, &article_dir, &asset_dir)
+                })
+                .await
+                .map_err(|e| AppError::InternalServerError {
+                    code: ErrorCode::InternalServer,
                     message: e.to_string(),
-                })?;
-            Ok(Json(ArticleContent {
-                slug: article.slug.clone(),
-                metadata: article.metadata.clone(),
-                content,
-            }))
+                })?
+            };
+            let reactions =
+                reaction_counts(&state.db, &article.slug, &state.config.reaction_emojis)
+                    .await
+                    .map_err(|e| AppError::InternalServerError {
+                        code: ErrorCode::InternalServer,
+                        message: e.to_string(),
+                    })?;
+            let poll = article_poll_results(&state, &article).await?;
+            let mut headers = article_headers(&article.metadata);
+            if let Some(value) = article
+                .metadata
+                .lang
+                .as_deref()
+                .and_then(|lang| HeaderValue::from_str(lang).ok())
+            {
+                headers.insert(header::CONTENT_LANGUAGE, value);
+            }
+            Ok((
+                headers,
+                Json(ArticleDetail {
+                    archived: article.metadata.is_archived(),
+                    locked,
+                    article,
+                    reactions,
+                    poll,
+                    images,
+                }),
+            ))
         }
-        Some(_) => Err(AppError::NotFound {
-            code: ERR_ARTICLE_NOT_FOUND,
-            message: format!("Article with slug {} not found", slug),
-        }),
         None => Err(AppError::NotFound {
-            code: ERR_ARTICLE_NOT_FOUND,
+            code: ErrorCode::ArticleNotFound,
             message: format!("Article with slug {} not found", slug),
         }),
     }
 }
 
+/// Merges an edit made from an older body of the article into the body it
+/// has now, word by word. Nothing is saved: the client sends the merged
+/// content as an update, or resolves the conflicts first.
+async fn merge_article(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<MergeRequest>,
+) -> Result<Json<MergeResult>, AppError> {
+    let current = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| !a.deleted)
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        store
+            .load_content_for(article)
+            .map_err(|e| AppError::InternalServerError {
+                code: ErrorCode::InternalServer,
+                message: e.to_string(),
+            })?
+    };
+    Ok(Json(merge(&payload.base, &payload.content, &current)))
+}
+
+/// Comment, reaction and view counts and the revision time of an article,
+/// for pages to refresh without fetching the full response. Counters are
+/// shared between requests for a few seconds.
+async fn get_article_meta(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    // Counters are kept under `category/slug`, whichever form the path used.
+    let (slug, updated_at) = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| visibility.allows_direct(&store, a))
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        (
+            article.slug_with_category(),
+            revised_at(&state.data_dir, article).unwrap_or(article.metadata.date),
+        )
+    };
+    let cache_control = (
+        header::CACHE_CONTROL,
+        format!("public, max-age={}", META_CACHE_TTL_SECONDS),
+    );
+    if let Some(meta) = state.meta_cache.get(&slug).await {
+        return Ok(([cache_control], Json(meta)));
+    }
+
+    let internal = |e: sqlx::Error| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    };
+    let meta = ArticleMeta {
+        comment_count: count_comments(&state.db, &slug).await.map_err(internal)?,
+        reactions: reaction_counts(&state.db, &slug, &state.config.reaction_emojis)
+            .await
+            .map_err(internal)?,
+        views: coarsen(article_views(&state.db, &slug).await.map_err(internal)?),
+        updated_at,
+        slug,
+    };
+    state
+        .meta_cache
+        .insert(meta.slug.clone(), meta.clone())
+        .await;
+    Ok(([cache_control], Json(meta)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::ENABLE_NESTED_CATEGORIES;
     use chrono::Utc;
+    use proptest::prelude::*;
     use std::collections::HashSet;
     use std::time::SystemTime;
     use tempfile::tempdir;
 
-    async fn setup_store() -> (
-        tempfile::TempDir,
-        Arc<RwLock<ArticleStore>>,
-        PathBuf,
-    ) {
+    async fn setup_store() -> (tempfile::TempDir, Arc<RwLock<ArticleStore>>) {
         let dir = tempdir().unwrap();
-        let original = std::env::current_dir().unwrap();
-        std::env::set_current_dir(dir.path()).unwrap();
-        fs::create_dir("article").unwrap();
+        let article_dir = dir.path().join("article");
+        fs::create_dir(&article_dir).unwrap();
         let store = Arc::new(RwLock::new(
-            ArticleStore::new("article", ENABLE_NESTED_CATEGORIES).unwrap(),
+            ArticleStore::new(&article_dir, ENABLE_NESTED_CATEGORIES).unwrap(),
         ));
-        (dir, store, original)
+        (dir, store)
     }
 
     #[tokio::test]
     async fn test_prepare_metadata() {
-        let (_dir, store, original) = setup_store().await;
+        let (_dir, store) = setup_store().await;
         let payload = CreateArticleRequest {
             title: "Test Title".to_string(),
             content: "Content".to_string(),
             tags: None,
             category: None,
             description: None,
+            status: None,
             draft: Some(false),
+            link: None,
+            fetch_link: false,
         };
         let (slug, metadata, path) = prepare_metadata(store, &payload).await.unwrap();
         assert_eq!(slug, "test-title");
         assert!(path.ends_with("article/test-title.md"));
         assert_eq!(metadata.title, "Test Title");
-        std::env::set_current_dir(original).unwrap();
     }
 
     #[tokio::test]
     async fn test_persist_article() {
-        let (_dir, store, original) = setup_store().await;
+        let (dir, store) = setup_store().await;
         let payload = CreateArticleRequest {
             title: "Persist".to_string(),
             content: "Hello".to_string(),
             tags: None,
             category: None,
             description: None,
+            status: None,
             draft: Some(false),
+            link: None,
+            fetch_link: false,
         };
         let (slug, metadata, path) = prepare_metadata(Arc::clone(&store), &payload)
             .await
             .unwrap();
         persist_article(
             Arc::clone(&store),
+            &dir.path().join("data"),
             &slug,
             &metadata,
             &payload.content,
@@ -565,7 +1436,14 @@ mod tests {
         .unwrap();
         let guard = store.read().await;
         assert!(guard.get_by_slug(&slug).is_some());
-        std::env::set_current_dir(original).unwrap();
+        assert!(
+            dir.path()
+                .join("data/articles/persist/versions")
+                .read_dir()
+                .unwrap()
+                .next()
+                .is_some()
+        );
     }
 
     #[test]
@@ -583,11 +1461,27 @@ mod tests {
             title: "Rust Article".to_string(),
             author: "Author".to_string(),
             date: Utc::now(),
+            display_date: None,
             tags: vec!["rust".to_string()],
             description: "An article about Rust".to_string(),
-            draft: false,
+            status: ArticleStatus::Published,
             last_updated: None,
             category: Some("programming".to_string()),
+            poll: None,
+            lang: None,
+            translations: Default::default(),
+            private: false,
+            http_headers: Default::default(),
+            comments: Default::default(),
+            menu: None,
+            audio: None,
+            gallery: Vec::new(),
+            link: None,
+            syndication: Default::default(),
+            references: Vec::new(),
+            downloads: Vec::new(),
+            description_generated: false,
+            members_only: false,
         };
 
         let article = Article {
@@ -597,6 +1491,7 @@ mod tests {
             updated_at: Utc::now(),
             file_path: String::new(),
             last_modified: SystemTime::now(),
+            includes: Vec::new(),
             deleted: false,
         };
 
@@ -638,4 +1533,99 @@ mod tests {
             &Some("nomatch".to_string())
         ));
     }
+
+    const TAGS: [&str; 3] = ["rust", "web", "notes"];
+    const CATEGORIES: [&str; 2] = ["programming", "life"];
+
+    /// An article with the filtered fields drawn from small sets, so
+    /// filters match some articles and miss others.
+    fn arbitrary_article() -> impl Strategy<Value = Article> {
+        (
+            "[a-z]{1,8}",
+            "[A-Za-z ]{0,12}",
+            proptest::sample::subsequence(TAGS.to_vec(), 0..=TAGS.len()),
+            proptest::option::of(proptest::sample::select(CATEGORIES.to_vec())),
+            0i64..1_000_000,
+            proptest::bool::weighted(0.1),
+        )
+            .prop_map(|(slug, title, tags, category, seconds, deleted)| {
+                let metadata: Metadata = serde_json::from_value(json!({
+                    "title": title,
+                    "author": "Author",
+                    "date": chrono::DateTime::from_timestamp(seconds, 0).unwrap(),
+                    "tags": tags,
+                    "description": "",
+                    "category": category,
+                }))
+                .unwrap();
+                Article {
+                    slug,
+                    metadata,
+                    version: 0,
+                    updated_at: Utc::now(),
+                    file_path: String::new(),
+                    last_modified: SystemTime::now(),
+                    includes: Vec::new(),
+                    deleted,
+                }
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn slugs_are_idempotent_and_never_reused(
+            titles in proptest::collection::vec("\\PC{0,24}", 1..20),
+        ) {
+            let mut taken = HashSet::new();
+            for title in &titles {
+                let Ok(slug) = unique_slug(title, |slug| taken.contains(slug)) else {
+                    prop_assert!(slugify(title).is_empty());
+                    continue;
+                };
+                prop_assert_eq!(slugify(&slug), slug.clone());
+                prop_assert!(
+                    slug.chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                );
+                prop_assert!(taken.insert(slug));
+            }
+        }
+
+        #[test]
+        fn pages_cover_every_match_once(
+            articles in proptest::collection::vec(arbitrary_article(), 0..40),
+            tag in proptest::option::of(proptest::sample::select(TAGS.to_vec())),
+            category in proptest::option::of(proptest::sample::select(CATEGORIES.to_vec())),
+            query in proptest::option::of("[a-z]{1,2}"),
+            limit in 1usize..15,
+            page in any::<usize>(),
+        ) {
+            let store = ArticleStore::with_articles(articles);
+            let tag = tag.map(str::to_string);
+            let category = category.map(str::to_string);
+            let filter = |a: &Article| matches_filters(a, &tag, &category, &None, &query);
+            let expected: Vec<&str> = store
+                .query(filter, 0, usize::MAX)
+                .map(|a| a.slug.as_str())
+                .collect();
+
+            let pages = total_pages(expected.len(), limit);
+            let mut listed = Vec::new();
+            for page in 1..=pages {
+                let (items, total) = store.query_paginated(filter, page_offset(page, limit), limit);
+                prop_assert_eq!(total, expected.len());
+                prop_assert!(!items.is_empty() && items.len() <= limit);
+                listed.extend(items.into_iter().map(|a| a.slug.as_str()));
+            }
+            prop_assert_eq!(&listed, &expected);
+
+            // Any other page number, however large, gives a page without
+            // panicking, empty past the end.
+            let (items, total) = store.query_paginated(filter, page_offset(page, limit), limit);
+            prop_assert_eq!(total, expected.len());
+            if requested_page(page) > pages {
+                prop_assert!(items.is_empty());
+            }
+        }
+    }
 }
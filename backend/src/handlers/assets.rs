@@ -0,0 +1,184 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::asset::{AssetVerification, StoredAsset};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::services::asset_service::{
+    ASSET_DIR, asset_path, content_type_for, extension_for, is_compressible, store_asset,
+    verify_assets,
+};
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::middleware;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::sync::Arc;
+use tower::{Layer, ServiceExt};
+use tower_http::compression::CompressionLayer;
+use tower_http::services::ServeFile;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/assets",
+            with_body_limit(post(upload_asset), BodyGroup::Assets)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/assets/verify",
+            get(verify).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route("/api/assets/{name}", get(get_asset))
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Stores the request body under the hash of its content, with the
+/// extension of its `Content-Type`. Uploading the same bytes again returns
+/// the stored asset with `duplicate` set. Images are measured right away,
+/// so articles showing them are answered without decoding them again.
+async fn upload_asset(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, Json<StoredAsset>), AppError> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let extension = extension_for(content_type).ok_or_else(|| AppError::BadRequest {
+        code: ErrorCode::UnsupportedAssetType,
+        message: format!("Assets of type '{}' are not accepted", content_type),
+    })?;
+    if body.is_empty() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "Asset is empty".to_string(),
+        });
+    }
+    let dir = state.data_dir.join(ASSET_DIR);
+    let images = state.image_info.clone();
+    let asset = tokio::task::spawn_blocking(move || {
+        store_asset(&dir, &body, extension).map(|mut asset| {
+            asset.image = images.get(&dir.join(&asset.name));
+            asset
+        })
+    })
+    .await
+    .map_err(internal)?
+    .map_err(internal)?;
+    let status = if asset.duplicate {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(asset)))
+}
+
+async fn verify(State(state): State<Arc<AppState>>) -> Result<Json<AssetVerification>, AppError> {
+    let dir = state.data_dir.join(ASSET_DIR);
+    let report = tokio::task::spawn_blocking(move || verify_assets(&dir))
+        .await
+        .map_err(internal)?
+        .map_err(internal)?;
+    if !report.mismatches.is_empty() {
+        tracing::warn!(
+            "{} stored assets no longer match their hash",
+            report.mismatches.len()
+        );
+    }
+    Ok(Json(report))
+}
+
+/// Assets never change under a name, so they are cached for good and the
+/// name serves as their `ETag`. The file is served through [`ServeFile`],
+/// which answers `Range` and `If-Range` with partial content and
+/// `If-Modified-Since` with 304; a matching `If-None-Match` is answered
+/// with 304 here. SVGs are gzipped for clients that accept it, unless a
+/// range is asked for; the gzipped body gets a weak `ETag` as it differs
+/// from the file. The sandbox keeps scripts in uploaded SVGs from running
+/// on the site.
+async fn get_asset(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    request: Request,
+) -> Result<Response, AppError> {
+    let not_found = || AppError::NotFound {
+        code: ErrorCode::AssetNotFound,
+        message: format!("Asset {} not found", name),
+    };
+    let path = asset_path(&state.data_dir.join(ASSET_DIR), &name).ok_or_else(not_found)?;
+    if !tokio::fs::metadata(&path)
+        .await
+        .is_ok_and(|meta| meta.is_file())
+    {
+        return Err(not_found());
+    }
+    let etag = HeaderValue::from_str(&format!("\"{}\"", name)).map_err(|_| not_found())?;
+    let unchanged = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag.as_bytes() == etag.as_bytes())
+        });
+
+    let extension = name.rsplit('.').next().unwrap_or_default();
+    let mut response = if unchanged {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else if is_compressible(extension) {
+        let response = CompressionLayer::new()
+            .layer(ServeFile::new(&path))
+            .oneshot(request)
+            .await
+            .map_err(internal)?;
+        response.map(Body::new)
+    } else {
+        let response = ServeFile::new(&path)
+            .oneshot(request)
+            .await
+            .map_err(internal)?;
+        response.map(Body::new)
+    };
+    let has_body = response_has_body(response.status());
+    let headers = response.headers_mut();
+    if has_body {
+        let content_type = content_type_for(extension).unwrap_or("application/octet-stream");
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+    if headers.contains_key(header::CONTENT_ENCODING) {
+        let weak = HeaderValue::from_str(&format!("W/{}", etag.to_str().unwrap_or_default()))
+            .map_err(internal)?;
+        headers.insert(header::ETAG, weak);
+    } else {
+        headers.insert(header::ETAG, etag);
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=31536000, immutable"),
+    );
+    headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("sandbox"),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    Ok(response)
+}
+
+fn response_has_body(status: StatusCode) -> bool {
+    status == StatusCode::OK || status == StatusCode::PARTIAL_CONTENT
+}
@@ -0,0 +1,148 @@
+use crate::handlers::articles::{article_headers, members_only};
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::{Article, ArticleDownload};
+use crate::models::download::DownloadListing;
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::asset_service::{ASSET_DIR, asset_path, content_type_for};
+use crate::services::download_service::{download_counts, record_download};
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/articles/{slug}/downloads", get(list_downloads))
+        .route("/api/articles/{slug}/downloads/{name}", get(download_file))
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// An article the caller may see.
+async fn visible_article(
+    state: &AppState,
+    visibility: Visibility,
+    slug: &str,
+) -> Result<Article, AppError> {
+    let store = state.store.read().await;
+    store
+        .get_by_slug(slug)
+        .filter(|a| visibility.allows_direct(&store, a))
+        .cloned()
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })
+}
+
+/// The stored asset behind a download. Only uploaded assets can be
+/// offered, so front matter cannot point at other files.
+fn download_path(state: &AppState, download: &ArticleDownload) -> Option<PathBuf> {
+    download
+        .file
+        .strip_prefix("/api/assets/")
+        .and_then(|name| asset_path(&state.data_dir.join(ASSET_DIR), name))
+}
+
+fn download_type(download: &ArticleDownload) -> &'static str {
+    let extension = download.file.rsplit('.').next().unwrap_or_default();
+    content_type_for(extension).unwrap_or("application/octet-stream")
+}
+
+/// The files attached to an article with how often each was downloaded.
+/// Files that need a sign-in are listed for everyone.
+async fn list_downloads(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<DownloadListing>>, AppError> {
+    let article = visible_article(&state, visibility, &slug).await?;
+    let counts = download_counts(&state.db, &article.slug_with_category())
+        .await
+        .map_err(internal)?;
+    let listings = article
+        .metadata
+        .downloads
+        .iter()
+        .map(|download| DownloadListing {
+            name: download.name.clone(),
+            title: download.title.clone(),
+            url: format!("/api/articles/{}/downloads/{}", slug, download.name),
+            content_type: download_type(download),
+            size: download_path(&state, download)
+                .and_then(|path| std::fs::metadata(path).ok())
+                .map(|m| m.len()),
+            login_required: download.login_required,
+            downloads: counts.get(&download.name).copied().unwrap_or(0),
+        })
+        .collect();
+    Ok(Json(listings))
+}
+
+/// Serves a file attached to an article under its download name and
+/// counts the download. Files with `login_required` need a signed-in
+/// reader.
+async fn download_file(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    jar: SignedJar,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let not_found = || AppError::NotFound {
+        code: ErrorCode::AttachmentNotFound,
+        message: format!("Download {} of {} not found", name, slug),
+    };
+    let article = visible_article(&state, visibility, &slug).await?;
+    let download = article
+        .metadata
+        .downloads
+        .iter()
+        .find(|download| download.name == name)
+        .ok_or_else(not_found)?;
+    if !visibility.unlocks(&article) {
+        return Err(members_only(&article));
+    }
+    if download.login_required {
+        session_user(&jar)?;
+    }
+    let path = download_path(&state, download).ok_or_else(not_found)?;
+    let bytes = tokio::fs::read(&path).await.map_err(|e| {
+        tracing::warn!("Download {} of {} is missing: {}", name, slug, e);
+        not_found()
+    })?;
+    record_download(&state.db, &article.slug_with_category(), &name)
+        .await
+        .map_err(internal)?;
+    let cache_control = if download.login_required || article.metadata.members_only {
+        "private, no-store"
+    } else {
+        "no-cache"
+    };
+    Ok((
+        article_headers(&article.metadata),
+        [
+            (header::CONTENT_TYPE, download_type(download).to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{}\"",
+                    name.replace(['"', '\\'], "_")
+                ),
+            ),
+            (header::CACHE_CONTROL, cache_control.to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        bytes,
+    )
+        .into_response())
+}
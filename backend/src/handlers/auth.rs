@@ -1,26 +1,28 @@
 use crate::config::{get_author_github_username, get_github_client_id, get_github_client_secret};
-use crate::handlers::error::{AppError, ERR_INTERNAL_SERVER, ERR_UNAUTHORIZED};
-use crate::handlers::users::{apply_github_profile, fetch_github_profile};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::users::{apply_github_profile, fetch_github_profile, negotiate_language};
 use crate::models::user::{User, UserInfo};
-use crate::models::user_preferences::UserPreferences;
+use crate::models::user_preferences::ResolvedPreferences;
 use crate::server::app::AppState;
+use crate::server::http_client::HttpClient;
+use crate::services::user_preferences_service::get_preferences;
 use axum::extract::{FromRef, Query, State};
+use axum::http::HeaderMap;
 use axum::response::Redirect;
 use axum::routing::get;
 use axum::{Json, Router};
 use axum_extra::extract::cookie::{Cookie, SameSite, SignedCookieJar};
 use cookie::Key;
 use oauth2::basic::BasicClient;
-use oauth2::reqwest::async_http_client;
 use oauth2::{
-    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
-    TokenResponse, TokenUrl,
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, HttpRequest, HttpResponse,
+    RedirectUrl, Scope, TokenResponse, TokenUrl,
 };
 use serde::Deserialize;
 use std::sync::Arc;
 
 #[derive(Clone)]
-struct CookieKey(Key);
+pub struct CookieKey(Key);
 
 impl FromRef<Arc<AppState>> for CookieKey {
     fn from_ref(app: &Arc<AppState>) -> Self {
@@ -34,7 +36,20 @@ impl Into<Key> for CookieKey {
     }
 }
 
-type SignedJar = SignedCookieJar<CookieKey>;
+pub type SignedJar = SignedCookieJar<CookieKey>;
+
+/// Reads the signed `user_session` cookie and returns the logged-in user.
+pub fn session_user<K>(jar: &SignedCookieJar<K>) -> Result<User, AppError> {
+    let user_cookie = jar.get("user_session").ok_or(AppError::Unauthorized {
+        code: ErrorCode::Unauthorized,
+        message: "Not authenticated".to_string(),
+    })?;
+
+    serde_json::from_str(user_cookie.value()).map_err(|_| AppError::Unauthorized {
+        code: ErrorCode::Unauthorized,
+        message: "Invalid session".to_string(),
+    })
+}
 
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
@@ -55,6 +70,41 @@ fn oauth_client(state: &AppState) -> BasicClient {
     .set_redirect_uri(RedirectUrl::new(state.config.github_redirect_url.clone()).unwrap())
 }
 
+/// Sends a request of the OAuth flow through the shared client, so the
+/// token exchange honors the `[http]` settings like every other outbound
+/// call. `oauth2` speaks an older `http` crate, hence the conversions.
+async fn oauth_request(
+    http: &HttpClient,
+    request: HttpRequest,
+) -> Result<HttpResponse, reqwest::Error> {
+    // Both crates accept the same method tokens, so this cannot fail.
+    let method =
+        reqwest::Method::from_bytes(request.method.as_str().as_bytes()).expect("valid HTTP method");
+    let mut builder = http.request(method, request.url.as_str());
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    let response = http.send(builder.body(request.body)).await?;
+
+    let status_code = oauth2::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(oauth2::http::StatusCode::BAD_GATEWAY);
+    let mut headers = oauth2::http::HeaderMap::new();
+    for (name, value) in response.headers() {
+        if let (Ok(name), Ok(value)) = (
+            oauth2::http::HeaderName::from_bytes(name.as_str().as_bytes()),
+            oauth2::http::HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    let body = response.bytes().await?.to_vec();
+    Ok(HttpResponse {
+        status_code,
+        headers,
+        body,
+    })
+}
+
 async fn github_login(State(state): State<Arc<AppState>>, jar: SignedJar) -> (SignedJar, Redirect) {
     let client = oauth_client(&state);
     let (auth_url, csrf_token) = client
@@ -87,13 +137,13 @@ async fn github_callback(
     Query(query): Query<AuthRequest>,
 ) -> Result<(SignedJar, Redirect), AppError> {
     let state_cookie = jar.get("oauth_state").ok_or(AppError::Unauthorized {
-        code: ERR_UNAUTHORIZED,
+        code: ErrorCode::Unauthorized,
         message: "missing oauth state".to_string(),
     })?;
 
     if state_cookie.value() != query.state {
         return Err(AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
+            code: ErrorCode::Unauthorized,
             message: "invalid oauth state".to_string(),
         });
     }
@@ -103,14 +153,14 @@ async fn github_callback(
     let client = oauth_client(&state);
     let token = client
         .exchange_code(AuthorizationCode::new(query.code))
-        .request_async(async_http_client)
+        .request_async(|request| oauth_request(&state.http, request))
         .await
         .map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
+            code: ErrorCode::InternalServer,
             message: e.to_string(),
         })?;
 
-    let profile = fetch_github_profile(token.access_token().secret()).await?;
+    let profile = fetch_github_profile(&state, token.access_token().secret()).await?;
 
     // Determine if user is the author
     let author_username = get_author_github_username().unwrap_or_default();
@@ -118,13 +168,17 @@ async fn github_callback(
 
     let mut user = User::new(profile.id, profile.login.clone(), is_author);
 
-    // In a real application, preferences would be loaded from storage
-    let prefs = UserPreferences::default();
-    apply_github_profile(&mut user, &profile, Some(&prefs));
+    let prefs = get_preferences(&state.db, state.field_cipher.as_ref(), profile.id)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    apply_github_profile(&mut user, &profile, prefs.as_ref());
 
     // Create signed cookie with user info
     let user_json = serde_json::to_string(&user).map_err(|e| AppError::InternalServerError {
-        code: ERR_INTERNAL_SERVER,
+        code: ErrorCode::InternalServer,
         message: e.to_string(),
     })?;
 
@@ -142,17 +196,34 @@ async fn github_callback(
     Ok((jar, Redirect::to("http://localhost:8080/author")))
 }
 
-async fn get_current_user(jar: SignedJar) -> Result<Json<UserInfo>, AppError> {
-    let user_cookie = jar.get("user_session").ok_or(AppError::Unauthorized {
-        code: ERR_UNAUTHORIZED,
-        message: "Not authenticated".to_string(),
-    })?;
-
-    let user: User =
-        serde_json::from_str(user_cookie.value()).map_err(|_| AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
-            message: "Invalid session".to_string(),
-        })?;
-
-    Ok(Json(UserInfo::from(user)))
+/// The signed-in user with their saved profile overrides and the
+/// preferences the frontend should apply.
+async fn get_current_user(
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+    headers: HeaderMap,
+) -> Result<Json<UserInfo>, AppError> {
+    let mut user = session_user(&jar)?;
+    let prefs = get_preferences(&state.db, state.field_cipher.as_ref(), user.github_id)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?
+        .unwrap_or_default();
+    if prefs.display_name.is_some() {
+        user.display_name = prefs.display_name;
+    }
+    if prefs.bio.is_some() {
+        user.bio = prefs.bio;
+    }
+    if prefs.avatar.is_some() {
+        user.avatar = prefs.avatar;
+    }
+    let mut info = UserInfo::from(user);
+    info.preferences = ResolvedPreferences {
+        theme: prefs.theme,
+        language: prefs.language.or_else(|| negotiate_language(&headers)),
+    };
+    Ok(Json(info))
 }
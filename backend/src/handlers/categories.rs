@@ -1,19 +1,77 @@
-use crate::handlers::error::AppError;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::Article;
+use crate::models::taxonomy::TermDetail;
 use crate::server::app::AppState;
-use axum::extract::State;
+use crate::server::visibility::Visibility;
+use crate::services::taxonomy::{TermKind, term_detail};
+use axum::extract::{Path, State};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Json, Router};
 use std::sync::Arc;
 
 pub fn create_router() -> Router<Arc<AppState>> {
-    Router::new().route("/api/categories", get(get_all_categories))
+    Router::new()
+        .route("/api/categories", get(get_all_categories))
+        .route("/api/categories/counts", get(get_category_counts))
+        .route("/api/categories/{*category}", get(get_category))
 }
 
-async fn get_all_categories(
+pub(crate) async fn get_all_categories(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
 ) -> Result<impl IntoResponse, AppError> {
     let store = state.store.read().await;
-    let categories = store.get_all_categories();
+    let categories = store.get_all_categories(visibility.includes_restricted());
     Ok(Json(categories))
 }
+
+async fn get_category_counts(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.store.read().await;
+    Ok(Json(
+        store.category_counts(visibility.includes_restricted()),
+    ))
+}
+
+/// Landing page data for a category, from its `taxonomy/categories/` page
+/// and the articles filed under it. Nested categories are addressed by
+/// their full path, e.g. `/api/categories/guides/axum`.
+async fn get_category(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(category): Path<String>,
+) -> Result<Json<TermDetail>, AppError> {
+    let store = state.store.read().await;
+    let articles: Vec<&Article> = store
+        .query(
+            |a| {
+                visibility.allows(&store, a)
+                    && a.metadata.category.as_deref() == Some(category.as_str())
+            },
+            0,
+            usize::MAX,
+        )
+        .collect();
+    if articles.is_empty() {
+        return Err(AppError::NotFound {
+            code: ErrorCode::CategoryNotFound,
+            message: format!("Category {} not found", category),
+        });
+    }
+    let page_size = state.config.pagination.default_limit;
+    let detail = term_detail(
+        &state.taxonomy_dir,
+        TermKind::Category,
+        &category,
+        &articles,
+        page_size,
+    )
+    .map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    })?;
+    Ok(Json(detail))
+}
@@ -0,0 +1,61 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use crate::services::change_service::{ChangeMarker, changes_since, marker_of, reconcile};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct ChangesParams {
+    /// A `marker` from an earlier response or an RFC 3339 timestamp;
+    /// every public entry when absent.
+    pub since: Option<String>,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/changes", get(get_changes))
+}
+
+/// Slugs of the articles and notes that changed for anonymous visitors
+/// since `since`, so frontends can revalidate only those pages. The new
+/// marker is also the `ETag`; a request whose `If-None-Match` holds it is
+/// answered with 304 while nothing changed.
+async fn get_changes(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ChangesParams>,
+) -> Result<Response, AppError> {
+    let since = params
+        .since
+        .as_deref()
+        .map(|value| {
+            ChangeMarker::parse(value).ok_or_else(|| AppError::BadRequest {
+                code: ErrorCode::BadRequest,
+                message: format!("'{}' is neither a change marker nor a timestamp", value),
+            })
+        })
+        .transpose()?;
+    let internal = |e: sqlx::Error| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    };
+    let revision = reconcile(&state).await.map_err(internal)?;
+    let etag = format!("\"{}\"", marker_of(revision));
+
+    let unchanged = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(ChangeMarker::parse)
+        == Some(ChangeMarker::Revision(revision));
+    if unchanged {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    let changes = changes_since(&state.db, since, revision)
+        .await
+        .map_err(internal)?;
+    Ok(([(header::ETAG, etag)], Json(changes)).into_response())
+}
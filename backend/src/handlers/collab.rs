@@ -0,0 +1,156 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::locks::{LOCK_TTL_SECS, LockRequest};
+use crate::handlers::validation::Validate;
+use crate::server::app::AppState;
+use crate::server::auth::{ADMIN_TOKEN_PROTOCOL, require_admin};
+use crate::services::collab_service::{COLLAB_EDITOR, ClientMessage, ServerMessage};
+use crate::services::lock_service::{acquire_lock, release_lock};
+use crate::services::presence_service::editing_changed;
+use axum::Router;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::middleware;
+use axum::response::Response;
+use axum::routing::get;
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/admin/articles/{slug}/ws",
+        get(collaborate).route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Takes the article lock for live editing. A session holding the lock
+/// through the lock API hands it over when it joins; anyone else holding
+/// it keeps the article.
+async fn lock_for_collaboration(
+    state: &AppState,
+    slug: &str,
+    editor: &str,
+) -> Result<(), AppError> {
+    let mut lock = acquire_lock(&state.db, slug, COLLAB_EDITOR, LOCK_TTL_SECS)
+        .await
+        .map_err(internal)?;
+    if lock.locked_by == editor {
+        release_lock(&state.db, slug, editor)
+            .await
+            .map_err(internal)?;
+        lock = acquire_lock(&state.db, slug, COLLAB_EDITOR, LOCK_TTL_SECS)
+            .await
+            .map_err(internal)?;
+    }
+    if lock.locked_by != COLLAB_EDITOR {
+        return Err(AppError::Locked(lock));
+    }
+    Ok(())
+}
+
+/// Opens a live editing session of an article's draft for `editor`. The
+/// session starts from a snapshot of the body, then patches of every
+/// session and presence updates are pushed as they happen. The article is
+/// locked while anyone edits it live. A browser passing the admin token as
+/// a subprotocol gets `ADMIN_TOKEN_PROTOCOL` back, as it requires one of
+/// its offers to be accepted.
+async fn collaborate(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Query(params): Query<LockRequest>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    params.validate().map_err(AppError::Validation)?;
+    let editor = params.editor.trim().to_string();
+    let source = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| !a.deleted)
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        store.load_source_for(article).map_err(internal)?
+    };
+
+    lock_for_collaboration(&state, &slug, &editor).await?;
+    editing_changed(&state).await;
+    Ok(ws
+        .protocols([ADMIN_TOKEN_PROTOCOL])
+        .on_upgrade(move |socket| session(state, slug, editor, source, socket)))
+}
+
+async fn session(
+    state: Arc<AppState>,
+    slug: String,
+    editor: String,
+    source: String,
+    socket: WebSocket,
+) {
+    let (room, mut events, snapshot) = state.drafts.join(&slug, &editor, || source);
+    let (mut sender, mut receiver) = socket.split();
+    let send = |message: &ServerMessage| {
+        Message::Text(serde_json::to_string(message).unwrap_or_default().into())
+    };
+
+    if sender.send(send(&snapshot)).await.is_ok() {
+        loop {
+            let reply = tokio::select! {
+                incoming = receiver.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(ClientMessage::Patch { from, to, text }) => {
+                                // Sent back to this session with the others.
+                                room.apply(&editor, from, to, text);
+                                None
+                            }
+                            Ok(ClientMessage::Heartbeat) => {
+                                match lock_for_collaboration(&state, &slug, &editor).await {
+                                    Ok(()) => None,
+                                    Err(_) => Some(ServerMessage::Error {
+                                        message: "The article lock was lost".to_string(),
+                                    }),
+                                }
+                            }
+                            Ok(ClientMessage::Sync) => Some(room.snapshot()),
+                            Err(e) => Some(ServerMessage::Error {
+                                message: format!("Invalid message: {}", e),
+                            }),
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => None,
+                },
+                event = events.recv() => match event {
+                    Ok(event) => Some(event),
+                    Err(RecvError::Lagged(_)) => Some(room.snapshot()),
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if let Some(reply) = reply
+                && sender.send(send(&reply)).await.is_err()
+            {
+                break;
+            }
+        }
+    }
+
+    if state.drafts.leave(&slug, &editor) {
+        if let Err(e) = release_lock(&state.db, &slug, COLLAB_EDITOR).await {
+            tracing::warn!(
+                "Failed to release the live editing lock on {}: {:?}",
+                slug,
+                e
+            );
+        }
+        editing_changed(&state).await;
+    }
+}
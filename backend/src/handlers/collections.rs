@@ -0,0 +1,79 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::ArticleTeaser;
+use crate::models::collection::{Collection, CollectionDetail, CollectionSummary};
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::service::ArticleStore;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/collections", get(get_collections))
+        .route("/api/collections/{name}", get(get_collection))
+}
+
+/// The articles of `collection` the requester may see, in list order.
+/// Slugs of missing or hidden articles are skipped.
+fn teasers(
+    state: &AppState,
+    store: &ArticleStore,
+    visibility: Visibility,
+    collection: &Collection,
+) -> Vec<ArticleTeaser> {
+    collection
+        .articles
+        .iter()
+        .filter_map(|slug| store.get_by_slug(slug))
+        .filter(|a| visibility.allows(store, a))
+        .map(|a| ArticleTeaser {
+            slug: a.slug_with_category(),
+            metadata: a.metadata.clone(),
+            edit_url: state.config.edit_url(store.root(), &a.file_path),
+        })
+        .collect()
+}
+
+async fn get_collections(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<CollectionSummary>> {
+    let store = state.store.read().await;
+    Json(
+        state
+            .collections
+            .all()
+            .into_iter()
+            .map(|c| CollectionSummary {
+                count: teasers(&state, &store, visibility, &c).len(),
+                name: c.name,
+                title: c.title,
+                description: c.description,
+            })
+            .collect(),
+    )
+}
+
+async fn get_collection(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(name): Path<String>,
+) -> Result<Json<CollectionDetail>, AppError> {
+    let collection = state
+        .collections
+        .get(&name)
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::CollectionNotFound,
+            message: format!("Collection {} not found", name),
+        })?;
+    let store = state.store.read().await;
+    let articles = teasers(&state, &store, visibility, &collection);
+    Ok(Json(CollectionDetail {
+        name: collection.name,
+        title: collection.title,
+        description: collection.description,
+        articles,
+    }))
+}
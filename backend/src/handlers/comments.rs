@@ -1,10 +1,31 @@
+use crate::handlers::articles::rewrite_article;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::ValidationErrors;
+use crate::models::article::CommentStatus;
+use crate::models::comment::{DisqusImportSummary, ImportSummary, ImportedComment, NewComment};
 use crate::server::app::AppState;
+use crate::server::auth::{get_user_from_cookie_header, require_admin};
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::client_ip::ClientIp;
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::services::comment_service::{
+    current_timestamp, import_comments, list_comments, normalize_timestamp, sanitize_html,
+};
+use crate::services::disqus_service::{parse_export, slug_from_link};
+use crate::services::service::ArticleStore;
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, Request, StatusCode, header};
 use axum::middleware::{self, Next};
-use axum::response::Response;
-use axum::{Router, routing::get};
-use std::collections::HashMap;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router, routing::get};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
@@ -12,21 +33,24 @@ use tokio::sync::Mutex;
 /// Tracks comment submissions from users or IP addresses to prevent abuse.
 ///
 /// Requests over the threshold in the given window will immediately receive a
-/// `429 Too Many Requests` response.
-async fn rate_limit(req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
-    // Identify the client either by a custom `X-User-Id` header or fall back to IP.
-    let key = req
-        .headers()
-        .get("x-user-id")
-        .and_then(|v| v.to_str().ok())
-        .map(|id| format!("user:{id}"))
-        .or_else(|| {
-            req.headers()
-                .get("x-forwarded-for")
-                .and_then(|v| v.to_str().ok())
-                .map(|ip| format!("ip:{ip}"))
-        })
-        .unwrap_or_else(|| "ip:unknown".to_string());
+/// `429 Too Many Requests` response. Lifted for routes whose
+/// `[[route_layers]]` group sets `rate_limit = false`.
+pub async fn rate_limit(req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+    let state = req.extensions().get::<Arc<AppState>>();
+    if state.is_some_and(|state| state.route_layers.rate_limit(req.uri().path()) == Some(false)) {
+        return Ok(next.run(req).await);
+    }
+
+    // Identify the client by their signed session, or else by their IP as
+    // the trusted proxies report it. Nothing the client can simply change
+    // per request picks the bucket.
+    let key = state
+        .and_then(|state| get_user_from_cookie_header(&req, &state.cookie_key).ok())
+        .map(|user| format!("user:{}", user.github_id))
+        .unwrap_or_else(|| {
+            let ClientIp(ip) = ClientIp::of_request(req.headers(), req.extensions());
+            format!("ip:{ip}")
+        });
 
     // Window and threshold for rate limiting.
     const WINDOW: Duration = Duration::from_secs(60);
@@ -55,12 +79,337 @@ async fn rate_limit(req: Request<Body>, next: Next) -> Result<Response, StatusCo
     Ok(next.run(req).await)
 }
 
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/api/comments", get(not_implemented).post(not_implemented))
+        .route(
+            "/api/comments",
+            with_body_limit(
+                get(not_implemented).post(create_comment),
+                BodyGroup::Comments,
+            ),
+        )
         .layer(middleware::from_fn(rate_limit))
+        .route(
+            "/api/admin/comments/export",
+            with_load_limit(get(export), LoadClass::Exports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/comments/import",
+            with_body_limit(post(import), BodyGroup::Imports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/comments/import/disqus",
+            with_body_limit(post(import_disqus), BodyGroup::Imports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/comments/pause",
+            get(pause_status)
+                .post(pause)
+                .delete(resume)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles/{slug}/comments/lock",
+            post(lock_thread)
+                .delete(unlock_thread)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
 }
 
 async fn not_implemented() -> &'static str {
     "Comments feature not implemented"
 }
+
+async fn create_comment(State(state): State<Arc<AppState>>) -> Result<&'static str, AppError> {
+    ensure_comments_open(&state)?;
+    Ok(not_implemented().await)
+}
+
+/// Refuses a new comment or guestbook entry while comments are paused.
+pub fn ensure_comments_open(state: &AppState) -> Result<(), AppError> {
+    if state.comments_paused.load(Ordering::Relaxed) {
+        return Err(AppError::Forbidden {
+            code: ErrorCode::CommentsPaused,
+            message: "New comments are paused".to_string(),
+        });
+    }
+    Ok(())
+}
+
+async fn pause_status(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(json!({ "paused": state.comments_paused.load(Ordering::Relaxed) }))
+}
+
+/// Stops new comments until resumed or restarted; `comments_paused` in the
+/// config sets the state the server starts in.
+async fn pause(State(state): State<Arc<AppState>>) -> Json<Value> {
+    state.comments_paused.store(true, Ordering::Relaxed);
+    Json(json!({ "paused": true }))
+}
+
+async fn resume(State(state): State<Arc<AppState>>) -> Json<Value> {
+    state.comments_paused.store(false, Ordering::Relaxed);
+    Json(json!({ "paused": false }))
+}
+
+/// Every stored comment, as JSON or as CSV with a header row.
+async fn export(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, AppError> {
+    let comments = list_comments(&state.db).await.map_err(internal)?;
+    export_rows(&comments, params.format, "comments.csv")
+}
+
+/// Responds with `rows` as JSON, or as a CSV attachment named `filename`
+/// with a header row.
+pub fn export_rows<T: Serialize>(
+    rows: &[T],
+    format: ExportFormat,
+    filename: &str,
+) -> Result<Response, AppError> {
+    match format {
+        ExportFormat::Json => Ok(Json(rows).into_response()),
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows {
+                writer.serialize(row).map_err(internal)?;
+            }
+            let body = writer.into_inner().map_err(internal)?;
+            let disposition = format!("attachment; filename=\"{}\"", filename);
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                body,
+            )
+                .into_response())
+        }
+    }
+}
+
+/// Parses an import body in the export format: a JSON array, or CSV when
+/// the request is sent as `text/csv`.
+pub fn parse_rows<T: DeserializeOwned>(
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<Vec<T>, AppError> {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/csv"));
+    if is_csv {
+        csv::Reader::from_reader(body.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .map_err(|e| AppError::BadRequest {
+                code: ErrorCode::BadRequest,
+                message: format!("Invalid CSV: {}", e),
+            })
+    } else {
+        serde_json::from_str(body).map_err(|e| AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: format!("Invalid JSON: {}", e),
+        })
+    }
+}
+
+/// Imports comments in the export format: a JSON array, or CSV when the
+/// request is sent as `text/csv`. Nothing is stored unless every row is valid.
+async fn import(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportSummary>, AppError> {
+    let rows: Vec<ImportedComment> = parse_rows(&headers, &body)?;
+
+    let comments = {
+        let store = state.store.read().await;
+        validate_rows(&store, rows).map_err(AppError::Validation)?
+    };
+    let summary = import_comments(&state.db, &comments)
+        .await
+        .map_err(internal)?;
+    state.cache.invalidate_all();
+    Ok(Json(summary))
+}
+
+/// Imports a Disqus XML export. Threads are matched to articles by URL,
+/// following registered redirects, and comments keep their Disqus author
+/// name and timestamp. Posts on unmatched threads are reported, not stored.
+async fn import_disqus(
+    State(state): State<Arc<AppState>>,
+    body: String,
+) -> Result<Json<DisqusImportSummary>, AppError> {
+    let export = parse_export(&body).map_err(|e| AppError::BadRequest {
+        code: ErrorCode::BadRequest,
+        message: format!("Invalid Disqus export: {}", e),
+    })?;
+
+    let mut slugs: HashMap<String, Option<String>> = HashMap::new();
+    let mut unmatched_threads = BTreeSet::new();
+    let mut comments = Vec::new();
+    {
+        let store = state.store.read().await;
+        for post in export.posts {
+            let slug = slugs
+                .entry(post.thread_link.clone())
+                .or_insert_with(|| thread_slug(&state, &store, &post.thread_link));
+            let Some(slug) = slug.clone() else {
+                unmatched_threads.insert(post.thread_link);
+                continue;
+            };
+            let content = sanitize_html(&post.message);
+            if content.trim().is_empty() {
+                continue;
+            }
+            comments.push(NewComment {
+                article_slug: slug,
+                author_github_id: 0,
+                content,
+                created_at: normalize_timestamp(&post.created_at).unwrap_or_else(current_timestamp),
+                author_name: Some(post.author_name),
+            });
+        }
+    }
+
+    let summary = import_comments(&state.db, &comments)
+        .await
+        .map_err(internal)?;
+    state.cache.invalidate_all();
+    Ok(Json(DisqusImportSummary {
+        summary,
+        ignored: export.ignored,
+        unmatched_threads: unmatched_threads.into_iter().collect(),
+    }))
+}
+
+async fn lock_thread(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    set_comment_status(&state, &slug, CommentStatus::Closed).await
+}
+
+async fn unlock_thread(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    set_comment_status(&state, &slug, CommentStatus::Open).await
+}
+
+/// Writes `comments` to the front matter of an article. Its comments are
+/// kept either way; closing a thread only stops new ones.
+async fn set_comment_status(
+    state: &AppState,
+    slug: &str,
+    status: CommentStatus,
+) -> Result<Json<Value>, AppError> {
+    let (mut article, content) = {
+        let store = state.store.read().await;
+        let article = store.get_by_slug(slug).ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })?;
+        let content = store.load_source_for(article).map_err(internal)?;
+        (article.clone(), content)
+    };
+
+    if article.metadata.comments != status {
+        article.metadata.comments = status;
+        rewrite_article(state, &article, &content, None).await?;
+    }
+    Ok(Json(json!({ "slug": slug, "comments": status })))
+}
+
+/// Slug of the article a Disqus thread URL belongs to, if it exists.
+fn thread_slug(state: &AppState, store: &ArticleStore, link: &str) -> Option<String> {
+    let redirect = Url::parse(link)
+        .ok()
+        .and_then(|url| state.redirects.lookup(url.path()))
+        .map(|(to_url, _)| to_url);
+    let slug = slug_from_link(redirect.as_deref().unwrap_or(link))?;
+    store.get_by_slug(&slug).map(|_| slug)
+}
+
+/// Checks each row against the article store and cleans its content.
+/// Errors are reported per row under `comments`, numbered from 1.
+fn validate_rows(
+    store: &ArticleStore,
+    rows: Vec<ImportedComment>,
+) -> Result<Vec<NewComment>, ValidationErrors> {
+    let mut errors = ValidationErrors::default();
+    let mut comments = Vec::with_capacity(rows.len());
+    for (i, row) in rows.into_iter().enumerate() {
+        let n = i + 1;
+        if store.get_by_slug(&row.article_slug).is_none() {
+            errors.add(
+                "comments",
+                format!("row {}: unknown article slug {}", n, row.article_slug),
+            );
+        }
+        let author_name = row
+            .author_name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        // Authors without a GitHub account are stored by name with ID 0.
+        let valid_author = match &author_name {
+            Some(_) => row.author_github_id >= 0,
+            None => row.author_github_id > 0,
+        };
+        if !valid_author {
+            errors.add(
+                "comments",
+                format!(
+                    "row {}: author_github_id must be positive, or 0 with an author_name",
+                    n
+                ),
+            );
+        }
+        let content = sanitize_html(&row.content);
+        if content.trim().is_empty() {
+            errors.add("comments", format!("row {}: content must not be empty", n));
+        }
+        let created_at = match row.created_at.as_deref().map(str::trim) {
+            None | Some("") => Some(current_timestamp()),
+            Some(value) => normalize_timestamp(value),
+        };
+        let Some(created_at) = created_at else {
+            errors.add("comments", format!("row {}: invalid created_at", n));
+            continue;
+        };
+        comments.push(NewComment {
+            article_slug: row.article_slug,
+            author_github_id: row.author_github_id,
+            content,
+            created_at,
+            author_name,
+        });
+    }
+    errors.into_result().map(|()| comments)
+}
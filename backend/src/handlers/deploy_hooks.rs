@@ -0,0 +1,91 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::notification_service::{
+    SIGNATURE_HEADER, hook_secret, trigger_deploy, verify,
+};
+use crate::services::site_event_service::{DEPLOYED, record_event};
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::middleware;
+use axum::routing::post;
+use axum::{Json, Router};
+use bytes::Bytes;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/deploy-hooks/{name}/trigger",
+            post(trigger).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route("/api/deploy-hooks/{name}/callback", post(callback))
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn hook_not_found(name: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::DeployHookNotFound,
+        message: format!("Deploy hook {} is not configured", name),
+    }
+}
+
+/// Queues a trigger of a configured deploy hook. Delivery happens through
+/// the job queue, so a provider that is briefly down is retried and one
+/// that keeps failing ends up in the dead letter list.
+async fn trigger(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    if !state.config.deploy_hooks.contains_key(&name) {
+        return Err(hook_not_found(&name));
+    }
+    let job_id = trigger_deploy(&state, &name).await.map_err(internal)?;
+    Ok(Json(json!({
+        "hook": name,
+        "job_id": job_id,
+        "message": "Deploy hook queued",
+    })))
+}
+
+/// Called by the downstream build once a deploy has gone live. The body,
+/// whatever it holds, must be signed with the hook's secret like the
+/// triggers sent to it, so hooks without a `secret_env` cannot report back.
+async fn callback(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, AppError> {
+    let hook = state
+        .config
+        .deploy_hooks
+        .get(&name)
+        .ok_or_else(|| hook_not_found(&name))?;
+    let secret = hook_secret(hook).map_err(internal)?;
+    let signed = secret
+        .zip(headers.get(SIGNATURE_HEADER))
+        .is_some_and(|(secret, signature)| {
+            signature
+                .to_str()
+                .is_ok_and(|signature| verify(&secret, &body, signature))
+        });
+    if !signed {
+        return Err(AppError::Unauthorized {
+            code: ErrorCode::InvalidSignature,
+            message: format!(
+                "Callback for deploy hook {} is not signed with its secret",
+                name
+            ),
+        });
+    }
+    record_event(&state.db, DEPLOYED).await.map_err(internal)?;
+    Ok(Json(json!({ "hook": name, "message": "Deploy recorded" })))
+}
@@ -0,0 +1,268 @@
+use crate::handlers::articles::{article_headers, members_only};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::Article;
+use crate::server::app::AppState;
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::server::visibility::Visibility;
+use crate::services::bundle_service::build_bundle;
+use crate::services::citation_service::article_references;
+use crate::services::glossary_service::glossary_linker;
+use crate::services::pdf_service::render_pdf;
+use crate::services::render_service::{code_snippets, render_document, render_plain_text};
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use gray_matter::Matter;
+use gray_matter::engine::YAML;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadFormat {
+    #[default]
+    Md,
+    /// Standalone print-optimized HTML, shown inline.
+    Print,
+    Pdf,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DownloadParams {
+    #[serde(default)]
+    format: DownloadFormat,
+    /// Keep the YAML front matter in Markdown downloads.
+    #[serde(default = "default_front_matter")]
+    front_matter: bool,
+}
+
+fn default_front_matter() -> bool {
+    true
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/articles/{slug}/download",
+            with_load_limit(get(download_article), LoadClass::Exports),
+        )
+        .route("/api/articles/{slug}/text", get(article_text))
+        .route("/api/articles/{slug}/snippets", get(article_snippets))
+        .route(
+            "/api/articles/{slug}/bundle.zip",
+            with_load_limit(get(download_bundle), LoadClass::Exports),
+        )
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn download_article(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+    Query(params): Query<DownloadParams>,
+) -> Result<Response, AppError> {
+    let (article, raw) = load_article(&state, visibility, &slug).await?;
+    let headers = article_headers(&article.metadata);
+    let mut metadata = article.metadata;
+    metadata.references = article_references(&metadata, std::path::Path::new(&article.file_path));
+
+    let response = match params.format {
+        DownloadFormat::Md => {
+            let body = if params.front_matter {
+                raw
+            } else {
+                strip_front_matter(&raw)?
+            };
+            file_response(
+                &slug,
+                "md",
+                "text/markdown; charset=utf-8",
+                "attachment",
+                body,
+            )
+        }
+        DownloadFormat::Print => {
+            let content = strip_front_matter(&raw)?;
+            let glossary = glossary_linker(&state);
+            let html = render_document(
+                &metadata,
+                &content,
+                &state.config.article_url(&slug),
+                glossary.as_ref(),
+            );
+            file_response(&slug, "html", "text/html; charset=utf-8", "inline", html)
+        }
+        DownloadFormat::Pdf => {
+            let pdf = state
+                .config
+                .pdf
+                .as_ref()
+                .ok_or_else(|| AppError::BadRequest {
+                    code: ErrorCode::PdfDisabled,
+                    message: "PDF export is not configured".to_string(),
+                })?;
+            let content = strip_front_matter(&raw)?;
+            let glossary = glossary_linker(&state);
+            let html = render_document(
+                &metadata,
+                &content,
+                &state.config.article_url(&slug),
+                glossary.as_ref(),
+            );
+            let bytes = render_pdf(pdf, &html).await.map_err(|message| {
+                tracing::warn!("PDF export of {} failed: {}", slug, message);
+                AppError::InternalServerError {
+                    code: ErrorCode::PdfRenderFailed,
+                    message,
+                }
+            })?;
+            file_response(&slug, "pdf", "application/pdf", "attachment", bytes)
+        }
+    };
+    Ok((headers, response).into_response())
+}
+
+/// Reader-mode text of an article: the title followed by the body with all
+/// markdown stripped.
+async fn article_text(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let (article, raw) = load_article(&state, visibility, &slug).await?;
+    let content = strip_front_matter(&raw)?;
+    let text = format!(
+        "{}\n\n{}\n",
+        article.metadata.title,
+        render_plain_text(&content)
+    );
+    Ok((
+        article_headers(&article.metadata),
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        text,
+    )
+        .into_response())
+}
+
+/// The fenced code blocks of an article, for tools that copy or export
+/// its code. Line numbers refer to the `content` of the article response.
+async fn article_snippets(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let (headers, content) = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| visibility.allows_direct(&store, a))
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        if !visibility.unlocks(article) {
+            return Err(members_only(article));
+        }
+        (
+            article_headers(&article.metadata),
+            store.load_content_for(article).map_err(internal)?,
+        )
+    };
+    Ok((headers, Json(code_snippets(&content))).into_response())
+}
+
+/// Offline copy of an article: the print page, its metadata and the local
+/// images it references, zipped.
+async fn download_bundle(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    let (article, raw) = load_article(&state, visibility, &slug).await?;
+    let content = strip_front_matter(&raw)?;
+    let url = state.config.article_url(&slug);
+    let bundle_slug = slug.clone();
+    let glossary = glossary_linker(&state);
+    let bytes = tokio::task::spawn_blocking(move || {
+        let article_path = std::path::Path::new(&article.file_path);
+        let article_dir = article_path.parent().unwrap_or(std::path::Path::new("."));
+        let mut metadata = article.metadata.clone();
+        metadata.references = article_references(&metadata, article_path);
+        build_bundle(
+            &bundle_slug,
+            &metadata,
+            &content,
+            &url,
+            article_dir,
+            glossary.as_ref(),
+        )
+    })
+    .await
+    .map_err(internal)?
+    .map_err(internal)?;
+    Ok(file_response(
+        &slug,
+        "zip",
+        "application/zip",
+        "attachment",
+        bytes,
+    ))
+}
+
+/// A published article the caller may see, with its raw file contents.
+async fn load_article(
+    state: &AppState,
+    visibility: Visibility,
+    slug: &str,
+) -> Result<(Article, String), AppError> {
+    let store = state.store.read().await;
+    let article = store
+        .get_by_slug(slug)
+        .filter(|a| visibility.allows_direct(&store, a))
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })?;
+    if !visibility.unlocks(article) {
+        return Err(members_only(article));
+    }
+    let raw = tokio::fs::read_to_string(&article.file_path)
+        .await
+        .map_err(internal)?;
+    Ok((article.clone(), raw))
+}
+
+fn strip_front_matter(raw: &str) -> Result<String, AppError> {
+    Matter::<YAML>::new()
+        .parse::<serde_yaml::Value>(raw)
+        .map(|parsed| parsed.content)
+        .map_err(internal)
+}
+
+fn file_response(
+    slug: &str,
+    extension: &str,
+    content_type: &'static str,
+    disposition: &str,
+    body: impl Into<axum::body::Body>,
+) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("{}; filename=\"{}.{}\"", disposition, slug, extension),
+            ),
+        ],
+        body.into(),
+    )
+        .into_response()
+}
@@ -1,34 +1,505 @@
+use crate::handlers::response::ErrorBody;
+use crate::handlers::validation::ValidationErrors;
+use crate::models::duplicate::SimilarArticle;
+use crate::models::lock::ArticleLock;
 use axum::Json;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Serialize, Serializer};
+use serde_json::{Value, json};
 use serde_yaml::Error as SerdeYAMLError;
+use std::borrow::Cow;
 use std::io::Error as IoError;
 use tracing::error;
 
-pub const ERR_INTERNAL_SERVER: &str = "ERR_INTERNAL_SERVER";
-pub const ERR_BAD_REQUEST: &str = "ERR_BAD_REQUEST";
-pub const ERR_ARTICLE_NOT_FOUND: &str = "ERR_ARTICLE_NOT_FOUND";
-pub const ERR_NOTE_NOT_FOUND: &str = "ERR_NOTE_NOT_FOUND";
-pub const ERR_VERSION_NOT_FOUND: &str = "ERR_VERSION_NOT_FOUND";
-pub const ERR_FULLTEXT_DISABLED: &str = "ERR_FULLTEXT_DISABLED";
-pub const ERR_EMPTY_SEARCH_QUERY: &str = "ERR_EMPTY_SEARCH_QUERY";
-#[allow(dead_code)]
-pub const ERR_INVALID_SESSION: &str = "ERR_INVALID_SESSION";
-pub const ERR_UNAUTHORIZED: &str = "ERR_UNAUTHORIZED";
-pub const ERR_FORBIDDEN: &str = "ERR_FORBIDDEN";
+/// Stable, machine-readable error codes returned in API error responses.
+/// `GET /api/errors` lists every code with its HTTP status and description.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    InternalServer,
+    BadRequest,
+    NotFound,
+    ArticleNotFound,
+    NoteNotFound,
+    VersionNotFound,
+    FulltextDisabled,
+    EmptySearchQuery,
+    InvalidSession,
+    Unauthorized,
+    Forbidden,
+    InvalidReaction,
+    PollNotFound,
+    PollExists,
+    InvalidPollOption,
+    AlreadyVoted,
+    GuestbookEntryNotFound,
+    ValidationFailed,
+    SummarizerDisabled,
+    DescriptionExists,
+    SummarizerFailed,
+    PrivateNotesDisabled,
+    NoteDecryptionFailed,
+    PdfDisabled,
+    PdfRenderFailed,
+    RedirectNotFound,
+    RedirectExists,
+    ShortlinkNotFound,
+    StoreRebuildFailed,
+    PayloadTooLarge,
+    ServerBusy,
+    PersistenceRolledBack,
+    ArticleExists,
+    TagNotFound,
+    CategoryNotFound,
+    SuggestionNotFound,
+    SuggestionMismatch,
+    InvalidApiKey,
+    ApiKeyReadOnly,
+    ApiKeyNotFound,
+    ApiQuotaExceeded,
+    ReadOnly,
+    DeployHookNotFound,
+    InvalidSignature,
+    SyncDisabled,
+    SimilarTitle,
+    GitHubRateLimited,
+    PreviewNotFound,
+    ArticleLocked,
+    SavedSearchNotFound,
+    ModerationRuleNotFound,
+    CommentsPaused,
+    PreviewBranchesDisabled,
+    BranchCheckoutFailed,
+    PreviewBranchNotFound,
+    UndoTokenNotFound,
+    UndoConflict,
+    PublicApiRateLimited,
+    PageNotFound,
+    AdminLockedOut,
+    AssetNotFound,
+    UnsupportedAssetType,
+    LinkPreviewFailed,
+    ReminderNotFound,
+    CrosspostDisabled,
+    InvalidSearchSnapshot,
+    CollectionNotFound,
+    AttachmentNotFound,
+    AccessCodeNotFound,
+    InvalidAccessCode,
+    MembersOnly,
+    SponsorsDisabled,
+    SponsorsUnavailable,
+    RouteRateLimited,
+    SchemaNotFound,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::InternalServer,
+        ErrorCode::BadRequest,
+        ErrorCode::NotFound,
+        ErrorCode::ArticleNotFound,
+        ErrorCode::NoteNotFound,
+        ErrorCode::VersionNotFound,
+        ErrorCode::FulltextDisabled,
+        ErrorCode::EmptySearchQuery,
+        ErrorCode::InvalidSession,
+        ErrorCode::Unauthorized,
+        ErrorCode::Forbidden,
+        ErrorCode::InvalidReaction,
+        ErrorCode::PollNotFound,
+        ErrorCode::PollExists,
+        ErrorCode::InvalidPollOption,
+        ErrorCode::AlreadyVoted,
+        ErrorCode::GuestbookEntryNotFound,
+        ErrorCode::ValidationFailed,
+        ErrorCode::SummarizerDisabled,
+        ErrorCode::DescriptionExists,
+        ErrorCode::SummarizerFailed,
+        ErrorCode::PrivateNotesDisabled,
+        ErrorCode::NoteDecryptionFailed,
+        ErrorCode::PdfDisabled,
+        ErrorCode::PdfRenderFailed,
+        ErrorCode::RedirectNotFound,
+        ErrorCode::RedirectExists,
+        ErrorCode::ShortlinkNotFound,
+        ErrorCode::StoreRebuildFailed,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::ServerBusy,
+        ErrorCode::PersistenceRolledBack,
+        ErrorCode::ArticleExists,
+        ErrorCode::TagNotFound,
+        ErrorCode::CategoryNotFound,
+        ErrorCode::SuggestionNotFound,
+        ErrorCode::SuggestionMismatch,
+        ErrorCode::InvalidApiKey,
+        ErrorCode::ApiKeyReadOnly,
+        ErrorCode::ApiKeyNotFound,
+        ErrorCode::ApiQuotaExceeded,
+        ErrorCode::ReadOnly,
+        ErrorCode::DeployHookNotFound,
+        ErrorCode::InvalidSignature,
+        ErrorCode::SyncDisabled,
+        ErrorCode::SimilarTitle,
+        ErrorCode::GitHubRateLimited,
+        ErrorCode::PreviewNotFound,
+        ErrorCode::ArticleLocked,
+        ErrorCode::SavedSearchNotFound,
+        ErrorCode::ModerationRuleNotFound,
+        ErrorCode::CommentsPaused,
+        ErrorCode::PreviewBranchesDisabled,
+        ErrorCode::BranchCheckoutFailed,
+        ErrorCode::PreviewBranchNotFound,
+        ErrorCode::UndoTokenNotFound,
+        ErrorCode::UndoConflict,
+        ErrorCode::PublicApiRateLimited,
+        ErrorCode::PageNotFound,
+        ErrorCode::AdminLockedOut,
+        ErrorCode::AssetNotFound,
+        ErrorCode::UnsupportedAssetType,
+        ErrorCode::LinkPreviewFailed,
+        ErrorCode::ReminderNotFound,
+        ErrorCode::CrosspostDisabled,
+        ErrorCode::InvalidSearchSnapshot,
+        ErrorCode::CollectionNotFound,
+        ErrorCode::AttachmentNotFound,
+        ErrorCode::AccessCodeNotFound,
+        ErrorCode::InvalidAccessCode,
+        ErrorCode::MembersOnly,
+        ErrorCode::SponsorsDisabled,
+        ErrorCode::SponsorsUnavailable,
+        ErrorCode::RouteRateLimited,
+        ErrorCode::SchemaNotFound,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InternalServer => "ERR_INTERNAL_SERVER",
+            ErrorCode::BadRequest => "ERR_BAD_REQUEST",
+            ErrorCode::NotFound => "ERR_NOT_FOUND",
+            ErrorCode::ArticleNotFound => "ERR_ARTICLE_NOT_FOUND",
+            ErrorCode::NoteNotFound => "ERR_NOTE_NOT_FOUND",
+            ErrorCode::VersionNotFound => "ERR_VERSION_NOT_FOUND",
+            ErrorCode::FulltextDisabled => "ERR_FULLTEXT_DISABLED",
+            ErrorCode::EmptySearchQuery => "ERR_EMPTY_SEARCH_QUERY",
+            ErrorCode::InvalidSession => "ERR_INVALID_SESSION",
+            ErrorCode::Unauthorized => "ERR_UNAUTHORIZED",
+            ErrorCode::Forbidden => "ERR_FORBIDDEN",
+            ErrorCode::InvalidReaction => "ERR_INVALID_REACTION",
+            ErrorCode::PollNotFound => "ERR_POLL_NOT_FOUND",
+            ErrorCode::PollExists => "ERR_POLL_EXISTS",
+            ErrorCode::InvalidPollOption => "ERR_INVALID_POLL_OPTION",
+            ErrorCode::AlreadyVoted => "ERR_ALREADY_VOTED",
+            ErrorCode::GuestbookEntryNotFound => "ERR_GUESTBOOK_ENTRY_NOT_FOUND",
+            ErrorCode::ValidationFailed => "ERR_VALIDATION_FAILED",
+            ErrorCode::SummarizerDisabled => "ERR_SUMMARIZER_DISABLED",
+            ErrorCode::DescriptionExists => "ERR_DESCRIPTION_EXISTS",
+            ErrorCode::SummarizerFailed => "ERR_SUMMARIZER_FAILED",
+            ErrorCode::PrivateNotesDisabled => "ERR_PRIVATE_NOTES_DISABLED",
+            ErrorCode::NoteDecryptionFailed => "ERR_NOTE_DECRYPTION_FAILED",
+            ErrorCode::PdfDisabled => "ERR_PDF_DISABLED",
+            ErrorCode::PdfRenderFailed => "ERR_PDF_RENDER_FAILED",
+            ErrorCode::RedirectNotFound => "ERR_REDIRECT_NOT_FOUND",
+            ErrorCode::RedirectExists => "ERR_REDIRECT_EXISTS",
+            ErrorCode::ShortlinkNotFound => "ERR_SHORTLINK_NOT_FOUND",
+            ErrorCode::StoreRebuildFailed => "ERR_STORE_REBUILD_FAILED",
+            ErrorCode::PayloadTooLarge => "ERR_PAYLOAD_TOO_LARGE",
+            ErrorCode::ServerBusy => "ERR_SERVER_BUSY",
+            ErrorCode::PersistenceRolledBack => "ERR_PERSISTENCE_ROLLED_BACK",
+            ErrorCode::ArticleExists => "ERR_ARTICLE_EXISTS",
+            ErrorCode::TagNotFound => "ERR_TAG_NOT_FOUND",
+            ErrorCode::CategoryNotFound => "ERR_CATEGORY_NOT_FOUND",
+            ErrorCode::SuggestionNotFound => "ERR_SUGGESTION_NOT_FOUND",
+            ErrorCode::SuggestionMismatch => "ERR_SUGGESTION_MISMATCH",
+            ErrorCode::InvalidApiKey => "ERR_INVALID_API_KEY",
+            ErrorCode::ApiKeyReadOnly => "ERR_API_KEY_READ_ONLY",
+            ErrorCode::ApiKeyNotFound => "ERR_API_KEY_NOT_FOUND",
+            ErrorCode::ApiQuotaExceeded => "ERR_API_QUOTA_EXCEEDED",
+            ErrorCode::ReadOnly => "ERR_READ_ONLY",
+            ErrorCode::DeployHookNotFound => "ERR_DEPLOY_HOOK_NOT_FOUND",
+            ErrorCode::InvalidSignature => "ERR_INVALID_SIGNATURE",
+            ErrorCode::SyncDisabled => "ERR_SYNC_DISABLED",
+            ErrorCode::SimilarTitle => "ERR_SIMILAR_TITLE",
+            ErrorCode::GitHubRateLimited => "ERR_GITHUB_RATE_LIMITED",
+            ErrorCode::PreviewNotFound => "ERR_PREVIEW_NOT_FOUND",
+            ErrorCode::ArticleLocked => "ERR_ARTICLE_LOCKED",
+            ErrorCode::SavedSearchNotFound => "ERR_SAVED_SEARCH_NOT_FOUND",
+            ErrorCode::ModerationRuleNotFound => "ERR_MODERATION_RULE_NOT_FOUND",
+            ErrorCode::CommentsPaused => "ERR_COMMENTS_PAUSED",
+            ErrorCode::PreviewBranchesDisabled => "ERR_PREVIEW_BRANCHES_DISABLED",
+            ErrorCode::BranchCheckoutFailed => "ERR_BRANCH_CHECKOUT_FAILED",
+            ErrorCode::PreviewBranchNotFound => "ERR_PREVIEW_BRANCH_NOT_FOUND",
+            ErrorCode::UndoTokenNotFound => "ERR_UNDO_TOKEN_NOT_FOUND",
+            ErrorCode::UndoConflict => "ERR_UNDO_CONFLICT",
+            ErrorCode::PublicApiRateLimited => "ERR_PUBLIC_API_RATE_LIMITED",
+            ErrorCode::PageNotFound => "ERR_PAGE_NOT_FOUND",
+            ErrorCode::AdminLockedOut => "ERR_ADMIN_LOCKED_OUT",
+            ErrorCode::AssetNotFound => "ERR_ASSET_NOT_FOUND",
+            ErrorCode::UnsupportedAssetType => "ERR_UNSUPPORTED_ASSET_TYPE",
+            ErrorCode::LinkPreviewFailed => "ERR_LINK_PREVIEW_FAILED",
+            ErrorCode::ReminderNotFound => "ERR_REMINDER_NOT_FOUND",
+            ErrorCode::CrosspostDisabled => "ERR_CROSSPOST_DISABLED",
+            ErrorCode::InvalidSearchSnapshot => "ERR_INVALID_SEARCH_SNAPSHOT",
+            ErrorCode::CollectionNotFound => "ERR_COLLECTION_NOT_FOUND",
+            ErrorCode::AttachmentNotFound => "ERR_ATTACHMENT_NOT_FOUND",
+            ErrorCode::AccessCodeNotFound => "ERR_ACCESS_CODE_NOT_FOUND",
+            ErrorCode::InvalidAccessCode => "ERR_INVALID_ACCESS_CODE",
+            ErrorCode::MembersOnly => "ERR_MEMBERS_ONLY",
+            ErrorCode::SponsorsDisabled => "ERR_SPONSORS_DISABLED",
+            ErrorCode::SponsorsUnavailable => "ERR_SPONSORS_UNAVAILABLE",
+            ErrorCode::RouteRateLimited => "ERR_ROUTE_RATE_LIMITED",
+            ErrorCode::SchemaNotFound => "ERR_SCHEMA_NOT_FOUND",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InternalServer
+            | ErrorCode::NoteDecryptionFailed
+            | ErrorCode::PdfRenderFailed
+            | ErrorCode::StoreRebuildFailed
+            | ErrorCode::PersistenceRolledBack => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::BadRequest
+            | ErrorCode::FulltextDisabled
+            | ErrorCode::EmptySearchQuery
+            | ErrorCode::InvalidReaction
+            | ErrorCode::InvalidPollOption
+            | ErrorCode::SummarizerDisabled
+            | ErrorCode::PrivateNotesDisabled
+            | ErrorCode::PdfDisabled
+            | ErrorCode::SyncDisabled
+            | ErrorCode::PreviewBranchesDisabled
+            | ErrorCode::BranchCheckoutFailed
+            | ErrorCode::UnsupportedAssetType
+            | ErrorCode::CrosspostDisabled
+            | ErrorCode::InvalidAccessCode
+            | ErrorCode::SponsorsDisabled
+            | ErrorCode::InvalidSearchSnapshot => StatusCode::BAD_REQUEST,
+            ErrorCode::NotFound
+            | ErrorCode::ArticleNotFound
+            | ErrorCode::NoteNotFound
+            | ErrorCode::PageNotFound
+            | ErrorCode::AssetNotFound
+            | ErrorCode::VersionNotFound
+            | ErrorCode::PollNotFound
+            | ErrorCode::ReminderNotFound
+            | ErrorCode::CollectionNotFound
+            | ErrorCode::AttachmentNotFound
+            | ErrorCode::AccessCodeNotFound
+            | ErrorCode::GuestbookEntryNotFound
+            | ErrorCode::RedirectNotFound
+            | ErrorCode::ShortlinkNotFound
+            | ErrorCode::TagNotFound
+            | ErrorCode::CategoryNotFound
+            | ErrorCode::SuggestionNotFound
+            | ErrorCode::ApiKeyNotFound
+            | ErrorCode::DeployHookNotFound
+            | ErrorCode::PreviewNotFound
+            | ErrorCode::SavedSearchNotFound
+            | ErrorCode::ModerationRuleNotFound
+            | ErrorCode::PreviewBranchNotFound
+            | ErrorCode::UndoTokenNotFound
+            | ErrorCode::SchemaNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::InvalidSession
+            | ErrorCode::Unauthorized
+            | ErrorCode::InvalidApiKey
+            | ErrorCode::InvalidSignature => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden
+            | ErrorCode::ApiKeyReadOnly
+            | ErrorCode::ReadOnly
+            | ErrorCode::CommentsPaused
+            | ErrorCode::MembersOnly => StatusCode::FORBIDDEN,
+            ErrorCode::PollExists
+            | ErrorCode::AlreadyVoted
+            | ErrorCode::DescriptionExists
+            | ErrorCode::RedirectExists
+            | ErrorCode::ArticleExists
+            | ErrorCode::SuggestionMismatch
+            | ErrorCode::SimilarTitle
+            | ErrorCode::ArticleLocked
+            | ErrorCode::UndoConflict => StatusCode::CONFLICT,
+            ErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::SummarizerFailed
+            | ErrorCode::LinkPreviewFailed
+            | ErrorCode::SponsorsUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::ServerBusy | ErrorCode::GitHubRateLimited => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::ApiQuotaExceeded
+            | ErrorCode::PublicApiRateLimited
+            | ErrorCode::RouteRateLimited
+            | ErrorCode::AdminLockedOut => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::InternalServer => "Unexpected internal error",
+            ErrorCode::BadRequest => "Request parameters were invalid",
+            ErrorCode::NotFound => "No route matches the requested path",
+            ErrorCode::ArticleNotFound => "Requested article does not exist",
+            ErrorCode::NoteNotFound => "Requested note does not exist",
+            ErrorCode::VersionNotFound => "Requested article version is missing",
+            ErrorCode::FulltextDisabled => "Full-text search service is not available",
+            ErrorCode::EmptySearchQuery => "Search query parameter was empty",
+            ErrorCode::InvalidSession => "Session cookie is missing or invalid",
+            ErrorCode::Unauthorized => "Authentication is required",
+            ErrorCode::Forbidden => "Credentials do not grant access to this resource",
+            ErrorCode::InvalidReaction => "Emoji is not one of the configured reactions",
+            ErrorCode::PollNotFound => "Requested poll does not exist",
+            ErrorCode::PollExists => "A poll with this id already exists",
+            ErrorCode::InvalidPollOption => "Poll has no option with this index",
+            ErrorCode::AlreadyVoted => "Voter has already voted in this poll",
+            ErrorCode::GuestbookEntryNotFound => "Requested guestbook entry does not exist",
+            ErrorCode::ValidationFailed => {
+                "Request body failed validation; `details` maps fields to messages"
+            }
+            ErrorCode::SummarizerDisabled => "No summarizer endpoint is configured",
+            ErrorCode::DescriptionExists => {
+                "Article already has a description; pass `force` to replace it"
+            }
+            ErrorCode::SummarizerFailed => "Summarizer endpoint failed or returned no summary",
+            ErrorCode::PrivateNotesDisabled => "No note encryption key is configured",
+            ErrorCode::NoteDecryptionFailed => {
+                "Private note could not be decrypted with the configured key"
+            }
+            ErrorCode::PdfDisabled => "No PDF converter is configured",
+            ErrorCode::PdfRenderFailed => "PDF converter failed or timed out",
+            ErrorCode::RedirectNotFound => "Requested redirect does not exist",
+            ErrorCode::RedirectExists => "A redirect from this path already exists",
+            ErrorCode::ShortlinkNotFound => "No article has this short code",
+            ErrorCode::StoreRebuildFailed => "Content could not be reloaded from disk",
+            ErrorCode::PayloadTooLarge => "Request body exceeds the limit for this route",
+            ErrorCode::ServerBusy => "Too many expensive requests in progress; see `Retry-After`",
+            ErrorCode::PersistenceRolledBack => {
+                "Saving the article failed part-way and its file and versions were rolled back"
+            }
+            ErrorCode::ArticleExists => "An article with this slug already exists in the category",
+            ErrorCode::TagNotFound => "No published article has the requested tag",
+            ErrorCode::CategoryNotFound => "No published article is in the requested category",
+            ErrorCode::SuggestionNotFound => "Requested suggestion does not exist or was resolved",
+            ErrorCode::SuggestionMismatch => {
+                "The text a suggestion replaces does not appear exactly once in the article"
+            }
+            ErrorCode::InvalidApiKey => "The `X-Api-Key` token is unknown or was revoked",
+            ErrorCode::ApiKeyReadOnly => "API keys only allow reading; the request was not a read",
+            ErrorCode::ApiKeyNotFound => "Requested API key does not exist or was revoked",
+            ErrorCode::ApiQuotaExceeded => "API key has used up its requests for the day",
+            ErrorCode::ReadOnly => {
+                "The instance is read-only; content changes go through the repository"
+            }
+            ErrorCode::DeployHookNotFound => "No deploy hook is configured under that name",
+            ErrorCode::InvalidSignature => "Request body is not signed with the expected secret",
+            ErrorCode::SyncDisabled => "Content sync is not enabled on this instance",
+            ErrorCode::SimilarTitle => "An article with a nearly identical title already exists",
+            ErrorCode::GitHubRateLimited => {
+                "GitHub's API quota is used up; sign-in works again once it resets"
+            }
+            ErrorCode::PreviewNotFound => "Preview link does not exist, expired or was revoked",
+            ErrorCode::ArticleLocked => "Another editing session holds the lock on the article",
+            ErrorCode::SavedSearchNotFound => "Saved search does not exist",
+            ErrorCode::ModerationRuleNotFound => "Moderation rule does not exist",
+            ErrorCode::CommentsPaused => "New comments are paused; existing ones can still be read",
+            ErrorCode::PreviewBranchesDisabled => "Branch previews are not configured",
+            ErrorCode::BranchCheckoutFailed => {
+                "The branch could not be checked out; the message has git's output"
+            }
+            ErrorCode::PreviewBranchNotFound => "No preview is checked out for that branch",
+            ErrorCode::UndoTokenNotFound => {
+                "Undo token does not exist, expired or was already used"
+            }
+            ErrorCode::UndoConflict => {
+                "The deleted item clashes with one created since, e.g. a redirect from the same path"
+            }
+            ErrorCode::PublicApiRateLimited => {
+                "Client has used up its public API requests for the minute"
+            }
+            ErrorCode::PageNotFound => "Requested page does not exist",
+            ErrorCode::AdminLockedOut => {
+                "Client sent too many wrong admin tokens and must wait before trying again"
+            }
+            ErrorCode::AssetNotFound => "Requested asset does not exist",
+            ErrorCode::UnsupportedAssetType => "Uploaded file is not of a supported asset type",
+            ErrorCode::LinkPreviewFailed => "Linked page could not be fetched to prefill the post",
+            ErrorCode::ReminderNotFound => {
+                "Evergreen reminder does not exist or was already decided"
+            }
+            ErrorCode::CrosspostDisabled => "Cross-posting is not configured",
+            ErrorCode::InvalidSearchSnapshot => {
+                "Upload is not a search index snapshot of this schema"
+            }
+            ErrorCode::CollectionNotFound => "Requested collection does not exist",
+            ErrorCode::AttachmentNotFound => "Requested article download does not exist",
+            ErrorCode::AccessCodeNotFound => "Requested access code does not exist or was revoked",
+            ErrorCode::InvalidAccessCode => "Access code is unknown, revoked or used up",
+            ErrorCode::MembersOnly => "Article is for members only",
+            ErrorCode::SponsorsDisabled => "No sponsors are configured",
+            ErrorCode::SponsorsUnavailable => "Sponsors could not be fetched and none are cached",
+            ErrorCode::RouteRateLimited => {
+                "Client has used up its requests to rate-limited routes for the minute"
+            }
+            ErrorCode::SchemaNotFound => "No response schema has the requested name",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl JsonSchema for ErrorCode {
+    fn schema_name() -> Cow<'static, str> {
+        "ErrorCode".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let codes: Vec<&str> = ErrorCode::ALL.iter().map(|code| code.as_str()).collect();
+        json_schema!({ "type": "string", "enum": codes })
+    }
+}
+
+/// Body of error responses outside `/api/v1`, which wraps errors in its
+/// envelope instead.
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorResponse {
+    pub error_code: ErrorCode,
+    pub message: String,
+    /// What went wrong in more detail, e.g. the failed validations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
 
 #[derive(Debug)]
 pub enum AppError {
-    NotFound { code: &'static str, message: String },
-    BadRequest { code: &'static str, message: String },
-    InternalServerError { code: &'static str, message: String },
-    Unauthorized { code: &'static str, message: String },
-    Forbidden { code: &'static str, message: String },
+    NotFound { code: ErrorCode, message: String },
+    BadRequest { code: ErrorCode, message: String },
+    InternalServerError { code: ErrorCode, message: String },
+    Unauthorized { code: ErrorCode, message: String },
+    Forbidden { code: ErrorCode, message: String },
+    Conflict { code: ErrorCode, message: String },
+    BadGateway { code: ErrorCode, message: String },
+    PayloadTooLarge { code: ErrorCode, message: String },
+    ServiceUnavailable { code: ErrorCode, message: String },
+    TooManyRequests { code: ErrorCode, message: String },
+    Validation(ValidationErrors),
+    /// Articles whose titles are nearly identical to one being created.
+    SimilarTitles(Vec<SimilarArticle>),
+    /// The live lock another editing session holds on an article.
+    Locked(ArticleLock),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let mut details = None;
         let (status, code, message) = match self {
             AppError::NotFound { code, message } => (StatusCode::NOT_FOUND, code, message),
             AppError::BadRequest { code, message } => (StatusCode::BAD_REQUEST, code, message),
@@ -37,16 +508,66 @@ impl IntoResponse for AppError {
             }
             AppError::Unauthorized { code, message } => (StatusCode::UNAUTHORIZED, code, message),
             AppError::Forbidden { code, message } => (StatusCode::FORBIDDEN, code, message),
+            AppError::Conflict { code, message } => (StatusCode::CONFLICT, code, message),
+            AppError::BadGateway { code, message } => (StatusCode::BAD_GATEWAY, code, message),
+            AppError::PayloadTooLarge { code, message } => {
+                (StatusCode::PAYLOAD_TOO_LARGE, code, message)
+            }
+            AppError::ServiceUnavailable { code, message } => {
+                (StatusCode::SERVICE_UNAVAILABLE, code, message)
+            }
+            AppError::TooManyRequests { code, message } => {
+                (StatusCode::TOO_MANY_REQUESTS, code, message)
+            }
+            AppError::Validation(errors) => {
+                details = serde_json::to_value(errors).ok();
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    ErrorCode::ValidationFailed,
+                    "Request validation failed".to_string(),
+                )
+            }
+            AppError::SimilarTitles(similar) => {
+                details = Some(json!({ "similar": similar }));
+                (
+                    StatusCode::CONFLICT,
+                    ErrorCode::SimilarTitle,
+                    "Articles with nearly identical titles exist".to_string(),
+                )
+            }
+            AppError::Locked(lock) => {
+                let message = format!(
+                    "Article {} is being edited by {} until {}",
+                    lock.article_slug, lock.locked_by, lock.locked_until
+                );
+                details = serde_json::to_value(lock).ok();
+                (StatusCode::CONFLICT, ErrorCode::ArticleLocked, message)
+            }
         };
 
-        error!(error_code = code, message = %message);
+        debug_assert_eq!(
+            status,
+            code.status(),
+            "{} used with mismatched status",
+            code
+        );
+        error!(error_code = code.as_str(), message = %message);
 
-        let body = Json(json!({
-            "error_code": code,
-            "message": message,
-        }));
+        let body = ErrorResponse {
+            error_code: code,
+            message: message.clone(),
+            details: details.clone(),
+        };
 
-        (status, body).into_response()
+        // The structured error travels with the response so `/api/v1`
+        // requests can be answered with the standard envelope.
+        let mut response = (status, Json(body)).into_response();
+        response.extensions_mut().insert(ErrorBody {
+            code,
+            message,
+            details,
+        });
+        response
     }
 }
 
@@ -57,6 +578,7 @@ pub enum LoadError {
     MatterParse(String),
     InvalidFileName(String),
     MissingFrontMatter(String),
+    InvalidCollections(String),
 }
 
 impl std::fmt::Display for LoadError {
@@ -69,6 +591,7 @@ impl std::fmt::Display for LoadError {
             LoadError::MissingFrontMatter(filename) => {
                 write!(f, "Missing front matter in file: {}", filename)
             }
+            LoadError::InvalidCollections(msg) => write!(f, "Invalid collections: {}", msg),
         }
     }
 }
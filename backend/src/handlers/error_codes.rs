@@ -0,0 +1,51 @@
+use crate::handlers::error::ErrorCode;
+use crate::server::app::AppState;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Serialize, Debug)]
+pub struct ErrorCodeInfo {
+    pub code: ErrorCode,
+    pub status: u16,
+    pub description: &'static str,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/errors", get(list_error_codes))
+}
+
+fn error_codes() -> Vec<ErrorCodeInfo> {
+    ErrorCode::ALL
+        .iter()
+        .map(|&code| ErrorCodeInfo {
+            code,
+            status: code.status().as_u16(),
+            description: code.description(),
+        })
+        .collect()
+}
+
+async fn list_error_codes() -> Json<Vec<ErrorCodeInfo>> {
+    Json(error_codes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn codes_are_unique_and_documented() {
+        let codes = error_codes();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+        let unique: HashSet<&str> = codes.iter().map(|c| c.code.as_str()).collect();
+        assert_eq!(unique.len(), codes.len());
+        for info in &codes {
+            assert!(info.code.as_str().starts_with("ERR_"));
+            assert!(!info.description.is_empty());
+            assert!(info.status >= 400);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::evergreen::EvergreenReminder;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::crosspost_service::queue_reminder;
+use crate::services::evergreen_service::{decide_reminder, pending_reminders};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/evergreen",
+            get(list_pending).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/evergreen/{id}/approve",
+            post(approve).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/evergreen/{id}/dismiss",
+            post(dismiss).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn reminder_not_found(id: i64) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::ReminderNotFound,
+        message: format!("Pending evergreen reminder {} not found", id),
+    }
+}
+
+async fn list_pending(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<EvergreenReminder>>, AppError> {
+    Ok(Json(pending_reminders(&state.db).await.map_err(internal)?))
+}
+
+/// Queues posting the article again to the `[crosspost]` accounts.
+async fn approve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<EvergreenReminder>, AppError> {
+    if state.config.crosspost.is_none() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::CrosspostDisabled,
+            message: "Cross-posting is not configured".to_string(),
+        });
+    }
+    let reminder = decide_reminder(&state.db, id, "approved")
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| reminder_not_found(id))?;
+    queue_reminder(&state, &reminder).await;
+    Ok(Json(reminder))
+}
+
+async fn dismiss(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<EvergreenReminder>, AppError> {
+    decide_reminder(&state.db, id, "dismissed")
+        .await
+        .map_err(internal)?
+        .map(Json)
+        .ok_or_else(|| reminder_not_found(id))
+}
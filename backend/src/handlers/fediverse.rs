@@ -0,0 +1,43 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::fediverse::FediverseReplies;
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::fediverse_service::fediverse_replies;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/articles/{slug}/fediverse-replies",
+        get(get_fediverse_replies),
+    )
+}
+
+/// Replies to the article's Mastodon post, shown read-only next to its
+/// comments.
+async fn get_fediverse_replies(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+) -> Result<Json<FediverseReplies>, AppError> {
+    let article = {
+        let store = state.store.read().await;
+        store
+            .get_by_slug(&slug)
+            .filter(|article| visibility.allows_direct(&store, article))
+            .cloned()
+    };
+    let article = article.ok_or_else(|| AppError::NotFound {
+        code: ErrorCode::ArticleNotFound,
+        message: format!("Article with slug {} not found", slug),
+    })?;
+    fediverse_replies(&state, &article)
+        .await
+        .map(Json)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })
+}
@@ -0,0 +1,276 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::{Article, AudioAttachment};
+use crate::server::app::AppState;
+use crate::server::snapshots::Document;
+use crate::server::visibility::Visibility;
+use crate::services::article_service::revised_at;
+use crate::services::asset_service::{ASSET_DIR, asset_path, content_type_for};
+use crate::services::render_service::escape_html;
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, header};
+use axum::response::Response;
+use axum::routing::get;
+use reqwest::Url;
+use std::cmp::Reverse;
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/feed/updated.xml", get(get_updated_feed))
+        .route("/feed/podcast.xml", get(get_podcast_feed))
+}
+
+/// RSS feed of articles revised since they were published, most recently
+/// revised first, so readers following evergreen posts hear about
+/// substantial updates. Holds `latest_articles_count` items.
+///
+/// Feed readers carry no membership, so members-only articles are listed
+/// with their title and description alone.
+pub async fn render_updated_feed(state: &AppState, visibility: Visibility) -> String {
+    let store = state.store.read().await;
+    let hostname = state.config.hostname.trim_end_matches('/');
+    let mut revised: Vec<_> = store
+        .query(|a| visibility.allows(&store, a), 0, usize::MAX)
+        .filter_map(|a| revised_at(&state.data_dir, a).map(|at| (at, a)))
+        .collect();
+    revised.sort_by_key(|&(at, _)| Reverse(at));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    xml.push_str("<rss version=\"2.0\" xmlns:media=\"http://search.yahoo.com/mrss/\"><channel>");
+    xml.push_str(&format!(
+        "<title>Recently updated</title><link>{}</link><description>Articles revised since they were published</description>",
+        escape_html(hostname)
+    ));
+    if let Some((latest, _)) = revised.first() {
+        xml.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>",
+            latest.to_rfc2822()
+        ));
+    }
+
+    for (updated, article) in revised.iter().take(state.config.latest_articles_count) {
+        let url = escape_html(&state.config.article_url(&article.slug));
+        // Link posts point readers at the page they are about and keep the
+        // article as the place to discuss it.
+        let (title, link, extra) = match &article.metadata.link {
+            Some(link) => (
+                format!("→ {}", article.metadata.title),
+                escape_html(&link.url),
+                format!(
+                    "<comments>{}</comments>{}",
+                    url,
+                    link.image
+                        .as_ref()
+                        .map(|image| format!("<media:thumbnail url=\"{}\"/>", escape_html(image)))
+                        .unwrap_or_default()
+                ),
+            ),
+            None => (article.metadata.title.clone(), url.clone(), String::new()),
+        };
+        // The guid changes with each revision, so readers show it again.
+        xml.push_str(&format!(
+            "<item><title>{}</title><link>{}</link><guid isPermaLink=\"false\">{}#{}</guid><description>{}</description><pubDate>{}</pubDate>{}{}</item>",
+            escape_html(&title),
+            link,
+            url,
+            updated.timestamp(),
+            escape_html(&article.metadata.description),
+            updated.to_rfc2822(),
+            extra,
+            if article.metadata.members_only {
+                String::new()
+            } else {
+                gallery_media(state, article)
+            }
+        ));
+    }
+    xml.push_str("</channel></rss>");
+    xml
+}
+
+/// Media RSS entries for the photos of a gallery post, so feed readers can
+/// show them. Sources are resolved against the article's URL, like images
+/// in its body.
+fn gallery_media(state: &AppState, article: &Article) -> String {
+    let Ok(base) = Url::parse(&state.config.article_url(&article.slug)) else {
+        return String::new();
+    };
+    let mut xml = String::new();
+    for image in &article.metadata.gallery {
+        let Ok(url) = base.join(&image.src) else {
+            continue;
+        };
+        xml.push_str(&format!(
+            "<media:content url=\"{}\" medium=\"image\">",
+            escape_html(url.as_str())
+        ));
+        if let Some(caption) = &image.caption {
+            xml.push_str(&format!(
+                "<media:description>{}</media:description>",
+                escape_html(caption)
+            ));
+        }
+        xml.push_str("</media:content>");
+    }
+    xml
+}
+
+/// The enclosure of an episode: its absolute URL, size in bytes and MIME
+/// type. The size is known for uploaded assets and `0` otherwise, which
+/// podcast apps accept.
+fn enclosure(state: &AppState, audio: &AudioAttachment) -> (String, u64, &'static str) {
+    let hostname = state.config.hostname.trim_end_matches('/');
+    let url = if audio.file.contains("://") {
+        audio.file.clone()
+    } else {
+        format!("{}/{}", hostname, audio.file.trim_start_matches('/'))
+    };
+    let length = audio
+        .file
+        .strip_prefix("/api/assets/")
+        .and_then(|name| asset_path(&state.data_dir.join(ASSET_DIR), name))
+        .and_then(|path| std::fs::metadata(path).ok())
+        .map_or(0, |m| m.len());
+    let extension = audio.file.rsplit('.').next().unwrap_or_default();
+    let mime = content_type_for(&extension.to_ascii_lowercase()).unwrap_or("audio/mpeg");
+    (url, length, mime)
+}
+
+/// RSS feed of the articles with `audio`, newest first, with the iTunes
+/// tags podcast directories expect. Every episode is listed, since apps
+/// offer the whole back catalogue. Members-only episodes are left out,
+/// since podcast apps fetch the audio without a membership.
+pub async fn render_podcast_feed(state: &AppState, visibility: Visibility) -> String {
+    let store = state.store.read().await;
+    let podcast = &state.config.podcast;
+    let hostname = state.config.hostname.trim_end_matches('/');
+    let mut episodes: Vec<_> = store
+        .query(
+            |a| {
+                a.metadata.audio.is_some()
+                    && !a.metadata.members_only
+                    && visibility.allows(&store, a)
+            },
+            0,
+            usize::MAX,
+        )
+        .collect();
+    episodes.sort_by_key(|a| Reverse(a.metadata.date));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    xml.push_str("<rss version=\"2.0\" xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\"><channel>");
+    xml.push_str(&format!(
+        "<title>{}</title><link>{}</link><description>{}</description>",
+        escape_html(podcast.title.as_deref().unwrap_or("Podcast")),
+        escape_html(hostname),
+        escape_html(&podcast.description)
+    ));
+    if let Some(language) = &podcast.language {
+        xml.push_str(&format!("<language>{}</language>", escape_html(language)));
+    }
+    if let Some(author) = &podcast.author {
+        xml.push_str(&format!(
+            "<itunes:author>{}</itunes:author>",
+            escape_html(author)
+        ));
+    }
+    if let Some(image) = &podcast.image {
+        xml.push_str(&format!("<itunes:image href=\"{}\"/>", escape_html(image)));
+    }
+    if let Some(category) = &podcast.category {
+        xml.push_str(&format!(
+            "<itunes:category text=\"{}\"/>",
+            escape_html(category)
+        ));
+    }
+    xml.push_str(&format!(
+        "<itunes:explicit>{}</itunes:explicit>",
+        podcast.explicit
+    ));
+    if let Some(email) = &podcast.owner_email {
+        xml.push_str(&format!(
+            "<itunes:owner>{}<itunes:email>{}</itunes:email></itunes:owner>",
+            podcast
+                .author
+                .as_ref()
+                .map(|name| format!("<itunes:name>{}</itunes:name>", escape_html(name)))
+                .unwrap_or_default(),
+            escape_html(email)
+        ));
+    }
+    if let Some(latest) = episodes.first() {
+        xml.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>",
+            latest.metadata.date.to_rfc2822()
+        ));
+    }
+
+    for article in episodes {
+        let Some(audio) = &article.metadata.audio else {
+            continue;
+        };
+        let url = escape_html(&state.config.article_url(&article.slug));
+        let (file, length, mime) = enclosure(state, audio);
+        xml.push_str(&format!(
+            "<item><title>{}</title><link>{}</link><guid isPermaLink=\"true\">{}</guid><description>{}</description><pubDate>{}</pubDate><enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>",
+            escape_html(&article.metadata.title),
+            url,
+            url,
+            escape_html(&article.metadata.description),
+            article.metadata.date.to_rfc2822(),
+            escape_html(&file),
+            length,
+            mime
+        ));
+        if let Some(duration) = audio.duration {
+            xml.push_str(&format!("<itunes:duration>{}</itunes:duration>", duration));
+        }
+        if let Some(episode) = audio.episode {
+            xml.push_str(&format!("<itunes:episode>{}</itunes:episode>", episode));
+        }
+        xml.push_str("</item>");
+    }
+    xml.push_str("</channel></rss>");
+    xml
+}
+
+/// Served from the snapshot unless the requester may see restricted
+/// articles or the snapshot is behind the content.
+async fn serve_feed(
+    state: &AppState,
+    document: Document,
+    visibility: Visibility,
+    headers: &HeaderMap,
+) -> Result<Response, AppError> {
+    if !visibility.includes_restricted()
+        && let Some(res) = state.snapshots.serve(document, headers).await
+    {
+        return Ok(res);
+    }
+    let xml = document.render_for(state, visibility).await;
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, document.content_type())
+        .body(axum::body::Body::from(xml))
+        .map_err(|_| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: "Failed to build feed response".to_string(),
+        })
+}
+
+async fn get_updated_feed(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    serve_feed(&state, Document::UpdatedFeed, visibility, &headers).await
+}
+
+async fn get_podcast_feed(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    serve_feed(&state, Document::PodcastFeed, visibility, &headers).await
+}
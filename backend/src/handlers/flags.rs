@@ -0,0 +1,36 @@
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::server::access::Access;
+use crate::server::app::AppState;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Serialize, Debug)]
+pub struct EnabledFlags {
+    pub flags: Vec<String>,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/flags", get(get_flags))
+}
+
+/// The names of the `flags` from the config that are on for the requester,
+/// so the frontend can stage features behind server-side switches.
+async fn get_flags(
+    State(state): State<Arc<AppState>>,
+    access: Access,
+    jar: SignedJar,
+) -> Json<EnabledFlags> {
+    let user = session_user(&jar).ok();
+    let login = user.as_ref().map(|u| u.github_login.as_str());
+    let flags = state
+        .config
+        .flags
+        .iter()
+        .filter(|(_, flag)| flag.is_on(access.includes_restricted(), login))
+        .map(|(name, _)| name.clone())
+        .collect();
+    Json(EnabledFlags { flags })
+}
@@ -0,0 +1,23 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::glossary::GlossaryEntry;
+use crate::server::app::AppState;
+use crate::services::glossary_service::load_glossary;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/glossary", get(get_glossary))
+}
+
+async fn get_glossary(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<GlossaryEntry>>, AppError> {
+    load_glossary(&state.glossary_dir)
+        .map(Json)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })
+}
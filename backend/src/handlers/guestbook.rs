@@ -0,0 +1,179 @@
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::comments::{ensure_comments_open, rate_limit};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::moderation::passes_moderation;
+use crate::handlers::response::{Pagination, page_offset, requested_page, total_pages};
+use crate::handlers::undo::delete_with_undo;
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::guestbook::{GuestbookEntry, PaginatedGuestbook};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::services::comment_service::sanitize_content;
+use crate::services::guestbook_service::{add_entry, approve_entry, list_entries};
+use crate::services::undo_service::GUESTBOOK_ENTRIES;
+use axum::extract::{Extension, Path, Query, State};
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const MAX_ENTRY_LENGTH: usize = 2_000;
+
+#[derive(Deserialize, Debug)]
+pub struct GuestbookParams {
+    #[serde(default = "default_page")]
+    page: usize,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateEntryRequest {
+    pub content: String,
+}
+
+impl Validate for CreateEntryRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("content", &self.content);
+        errors.max_chars("content", &self.content, MAX_ENTRY_LENGTH);
+        errors.into_result()
+    }
+}
+
+fn default_page() -> usize {
+    1
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/guestbook", get(list_guestbook))
+        .route(
+            "/api/guestbook",
+            with_body_limit(post(sign_guestbook), BodyGroup::Comments)
+                .route_layer(middleware::from_fn(rate_limit)),
+        )
+        .route(
+            "/api/admin/guestbook/pending",
+            get(list_pending).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/guestbook/{id}/approve",
+            post(approve).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/guestbook/{id}",
+            delete(remove).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn paginated(
+    state: &AppState,
+    approved: bool,
+    params: &GuestbookParams,
+) -> Result<(Extension<Pagination>, Json<PaginatedGuestbook>), AppError> {
+    let limit = state.config.pagination.limit(params.limit);
+    let page = requested_page(params.page);
+    let offset = page_offset(page, limit);
+    let (entries, total) = list_entries(&state.db, approved, offset, limit)
+        .await
+        .map_err(db_error)?;
+    let total_pages = total_pages(total, limit);
+    Ok((
+        Extension(Pagination {
+            page,
+            limit,
+            total_pages,
+            max_limit: state.config.pagination.max_limit,
+        }),
+        Json(PaginatedGuestbook {
+            entries,
+            total_pages,
+            current_page: page,
+        }),
+    ))
+}
+
+async fn list_guestbook(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GuestbookParams>,
+) -> Result<(Extension<Pagination>, Json<PaginatedGuestbook>), AppError> {
+    paginated(&state, true, &params).await
+}
+
+async fn sign_guestbook(
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+    ValidJson(payload): ValidJson<CreateEntryRequest>,
+) -> Result<Json<GuestbookEntry>, AppError> {
+    let user = session_user(&jar)?;
+    ensure_comments_open(&state)?;
+
+    let approved = passes_moderation(&state, "content", &payload.content).await?
+        && !state.config.guestbook_moderation;
+    let content = sanitize_content(&payload.content);
+    let entry = add_entry(
+        &state.db,
+        user.github_id,
+        &user.github_login,
+        &content,
+        approved,
+    )
+    .await
+    .map_err(db_error)?;
+
+    if approved {
+        state.cache.invalidate_all();
+    }
+    Ok(Json(entry))
+}
+
+async fn list_pending(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GuestbookParams>,
+) -> Result<(Extension<Pagination>, Json<PaginatedGuestbook>), AppError> {
+    paginated(&state, false, &params).await
+}
+
+async fn approve(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    if !approve_entry(&state.db, id).await.map_err(db_error)? {
+        return Err(AppError::NotFound {
+            code: ErrorCode::GuestbookEntryNotFound,
+            message: format!("Guestbook entry {} not found", id),
+        });
+    }
+    state.cache.invalidate_all();
+    Ok(Json(
+        json!({ "id": id, "message": "Guestbook entry approved" }),
+    ))
+}
+
+async fn remove(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let response = delete_with_undo(
+        &state,
+        &[(&GUESTBOOK_ENTRIES, id)],
+        || AppError::NotFound {
+            code: ErrorCode::GuestbookEntryNotFound,
+            message: format!("Guestbook entry {} not found", id),
+        },
+        "Guestbook entry deleted",
+    )
+    .await?;
+    state.cache.invalidate_all();
+    Ok(response)
+}
@@ -0,0 +1,33 @@
+use crate::models::diagnostics::Readiness;
+use crate::server::app::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/readyz", get(get_readiness))
+}
+
+/// For load balancers and orchestrators: `200` while the database answers
+/// and every file watcher runs, `503` otherwise. A watcher that failed is
+/// reported with its error while its supervisor restarts it.
+async fn get_readiness(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Readiness>) {
+    let database = sqlx::query("SELECT 1").execute(&state.db).await.is_ok();
+    let watchers = state.watchers.snapshot();
+    let ready = database && watchers.values().all(|health| health.running);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(Readiness {
+            ready,
+            database,
+            watchers,
+        }),
+    )
+}
@@ -0,0 +1,126 @@
+use crate::config::HomeSection;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::{Article, ArticleTeaser};
+use crate::models::comment::Comment;
+use crate::models::home::{HomePage, HomeSectionData};
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::comment_service::recent_comments;
+use crate::services::service::ArticleStore;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/home", get(get_home))
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn teaser(
+    state: &AppState,
+    store: &ArticleStore,
+    article: &Article,
+    slug: String,
+) -> ArticleTeaser {
+    ArticleTeaser {
+        slug,
+        metadata: article.metadata.clone(),
+        edit_url: state.config.edit_url(store.root(), &article.file_path),
+    }
+}
+
+/// The sections of the home page as laid out by the `home` config, so the
+/// frontend needs a single request. Entries the requester may not see are
+/// left out, including featured articles and pinned notes that do not
+/// exist.
+async fn get_home(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Result<Json<HomePage>, AppError> {
+    let default_limit = state.config.latest_articles_count;
+    let mut sections = Vec::new();
+    for section in &state.config.home {
+        let data = match section {
+            HomeSection::Featured { slugs } => {
+                let store = state.store.read().await;
+                let articles = slugs
+                    .iter()
+                    .filter_map(|slug| store.get_by_slug(slug))
+                    .filter(|a| visibility.allows(&store, a))
+                    .map(|a| teaser(&state, &store, a, a.slug_with_category()))
+                    .collect();
+                HomeSectionData::Featured { articles }
+            }
+            HomeSection::Latest { limit } => {
+                let store = state.store.read().await;
+                let articles = store
+                    .query(
+                        |a| visibility.allows(&store, a),
+                        0,
+                        limit.unwrap_or(default_limit),
+                    )
+                    .map(|a| teaser(&state, &store, a, a.slug_with_category()))
+                    .collect();
+                HomeSectionData::Latest { articles }
+            }
+            HomeSection::PinnedNotes { paths } => {
+                let store = state.note_store.read().await;
+                let notes = paths
+                    .iter()
+                    .filter_map(|path| store.get_by_path(path.trim_matches('/')))
+                    .filter(|n| visibility.allows(&store, n))
+                    .map(|n| teaser(&state, &store, n, n.slug_with_category()))
+                    .collect();
+                HomeSectionData::PinnedNotes { notes }
+            }
+            HomeSection::PopularTags { limit } => {
+                let store = state.store.read().await;
+                let mut tags = store.tag_counts(visibility.includes_restricted());
+                tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+                tags.truncate(limit.unwrap_or(default_limit));
+                HomeSectionData::PopularTags { tags }
+            }
+            HomeSection::RecentComments { limit } => {
+                let comments =
+                    visible_comments(&state, visibility, limit.unwrap_or(default_limit)).await?;
+                HomeSectionData::RecentComments { comments }
+            }
+        };
+        sections.push(data);
+    }
+    Ok(Json(HomePage { sections }))
+}
+
+/// The newest `limit` comments on articles the requester may see.
+async fn visible_comments(
+    state: &AppState,
+    visibility: Visibility,
+    limit: usize,
+) -> Result<Vec<Comment>, AppError> {
+    let mut comments = Vec::new();
+    let mut before = i64::MAX;
+    while comments.len() < limit {
+        let page = recent_comments(&state.db, before, limit)
+            .await
+            .map_err(db_error)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = last.id;
+        let store = state.store.read().await;
+        comments.extend(page.into_iter().filter(|c| {
+            store
+                .get_by_slug(&c.article_slug)
+                .is_some_and(|a| visibility.allows(&store, a))
+        }));
+    }
+    comments.truncate(limit);
+    Ok(comments)
+}
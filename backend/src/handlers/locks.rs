@@ -0,0 +1,97 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::lock::ArticleLock;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::lock_service::{acquire_lock, release_lock};
+use crate::services::presence_service::editing_changed;
+use axum::extract::{Path, Query, State};
+use axum::middleware;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// How long a lock lasts without a heartbeat.
+pub const LOCK_TTL_SECS: u64 = 120;
+const MAX_EDITOR_LENGTH: usize = 100;
+
+/// `editor` names the editing session, e.g. the device, and must stay the
+/// same for its heartbeats.
+#[derive(Deserialize, Debug)]
+pub struct LockRequest {
+    pub editor: String,
+}
+
+impl Validate for LockRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("editor", &self.editor);
+        errors.max_chars("editor", &self.editor, MAX_EDITOR_LENGTH);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/admin/articles/{slug}/lock",
+        post(lock)
+            .delete(unlock)
+            .route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Takes the lock on an article, or renews it as a heartbeat. While another
+/// session holds a live lock the request is refused with that lock.
+async fn lock(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<LockRequest>,
+) -> Result<Json<ArticleLock>, AppError> {
+    let exists = state
+        .store
+        .read()
+        .await
+        .get_by_slug(&slug)
+        .is_some_and(|a| !a.deleted);
+    if !exists {
+        return Err(AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        });
+    }
+
+    let editor = payload.editor.trim();
+    let lock = acquire_lock(&state.db, &slug, editor, LOCK_TTL_SECS)
+        .await
+        .map_err(internal)?;
+    if lock.locked_by != editor {
+        return Err(AppError::Locked(lock));
+    }
+    editing_changed(&state).await;
+    Ok(Json(lock))
+}
+
+/// Releases the lock when the session is done editing. Releasing a lock the
+/// session does not hold changes nothing.
+async fn unlock(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Query(params): Query<LockRequest>,
+) -> Result<Json<Value>, AppError> {
+    let released = release_lock(&state.db, &slug, params.editor.trim())
+        .await
+        .map_err(internal)?;
+    if released {
+        editing_changed(&state).await;
+    }
+    Ok(Json(json!({ "slug": slug, "released": released })))
+}
@@ -0,0 +1,94 @@
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::cache::CacheClassStats;
+use crate::services::search::IndexCollection;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Router, middleware};
+use std::fmt::Write;
+use std::sync::Arc;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/metrics",
+        get(get_metrics).route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+/// Appends a gauge in the Prometheus text format. Samples are
+/// `(labels, value)` pairs, with labels already formatted.
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// Appends a counter in the Prometheus text format, like [`gauge`].
+fn counter(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// Response cache usage per class, to tune `[response_cache]`, and search
+/// index freshness, so operators can tell when results are stale.
+async fn get_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let mut out = String::new();
+    let classes = state.cache.stats();
+    let samples = |value: fn(&CacheClassStats) -> u64| -> Vec<(String, f64)> {
+        classes
+            .iter()
+            .map(|class| (format!("{{class=\"{}\"}}", class.name), value(class) as f64))
+            .collect()
+    };
+    gauge(
+        &mut out,
+        "scribe_response_cache_entries",
+        "Responses held in the response cache.",
+        &samples(|class| class.entries),
+    );
+    counter(
+        &mut out,
+        "scribe_response_cache_hits_total",
+        "Requests answered from the response cache.",
+        &samples(|class| class.hits),
+    );
+    counter(
+        &mut out,
+        "scribe_response_cache_misses_total",
+        "Cacheable requests the response cache could not answer.",
+        &samples(|class| class.misses),
+    );
+    if let Some(search) = &state.search_service {
+        let commits: Vec<(String, f64)> = IndexCollection::ALL
+            .into_iter()
+            .filter_map(|collection| {
+                let at = search.last_commit_for(collection)?;
+                let labels = format!("{{collection=\"{}\"}}", collection.as_str());
+                Some((labels, at.timestamp_millis() as f64 / 1000.0))
+            })
+            .collect();
+        gauge(
+            &mut out,
+            "scribe_search_last_commit_timestamp_seconds",
+            "Unix time of the last successful search index commit.",
+            &commits,
+        );
+        let pending = state.index_tx.as_ref().map_or(0, |q| q.pending());
+        gauge(
+            &mut out,
+            "scribe_search_pending_index_jobs",
+            "Index jobs queued but not yet committed.",
+            &[(String::new(), pending as f64)],
+        );
+    }
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], out)
+}
@@ -0,0 +1,118 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::undo::delete_with_undo;
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::moderation::ModerationRule;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::moderation_service::{
+    HOLD, REJECT, create_rule, list_rules, rule_regex, screen,
+};
+use crate::services::undo_service::MODERATION_RULES;
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+const MAX_PATTERN_LENGTH: usize = 500;
+
+/// `action` is `reject` or `hold`.
+#[derive(Deserialize, Debug)]
+pub struct CreateRuleRequest {
+    pub pattern: String,
+    #[serde(default)]
+    pub regex: bool,
+    pub action: String,
+}
+
+impl Validate for CreateRuleRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("pattern", &self.pattern);
+        errors.max_chars("pattern", &self.pattern, MAX_PATTERN_LENGTH);
+        if let Err(e) = rule_regex(&self.pattern, self.regex) {
+            errors.add("pattern", format!("invalid regular expression: {}", e));
+        }
+        if self.action != REJECT && self.action != HOLD {
+            errors.add("action", "must be reject or hold");
+        }
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/moderation/rules",
+            get(list)
+                .post(add)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/moderation/rules/{id}",
+            delete(remove).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<ModerationRule>>, AppError> {
+    Ok(Json(list_rules(&state.db).await.map_err(internal)?))
+}
+
+async fn add(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<CreateRuleRequest>,
+) -> Result<Json<ModerationRule>, AppError> {
+    let pattern = if payload.regex {
+        payload.pattern.as_str()
+    } else {
+        payload.pattern.trim()
+    };
+    let rule = create_rule(&state.db, pattern, payload.regex, &payload.action)
+        .await
+        .map_err(internal)?;
+    Ok(Json(rule))
+}
+
+async fn remove(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    delete_with_undo(
+        &state,
+        &[(&MODERATION_RULES, id)],
+        || AppError::NotFound {
+            code: ErrorCode::ModerationRuleNotFound,
+            message: format!("Moderation rule {} not found", id),
+        },
+        "Moderation rule deleted",
+    )
+    .await
+}
+
+/// Whether a submission may be published right away under the moderation
+/// rules. Submissions breaking a `reject` rule are refused with a
+/// validation error on `field`; those breaking a `hold` rule return `false`.
+pub async fn passes_moderation(
+    state: &AppState,
+    field: &'static str,
+    text: &str,
+) -> Result<bool, AppError> {
+    match screen(&state.db, text).await.map_err(internal)? {
+        Some(rule) if rule.action == REJECT => {
+            let mut errors = ValidationErrors::default();
+            errors.add(field, "is not allowed by the moderation rules");
+            Err(AppError::Validation(errors))
+        }
+        Some(_) => Ok(false),
+        None => Ok(true),
+    }
+}
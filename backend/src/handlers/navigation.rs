@@ -0,0 +1,78 @@
+use crate::config::NavItem;
+use crate::models::navigation::NavLink;
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use reqwest::Url;
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/navigation", get(get_navigation))
+}
+
+/// Whether `url` points away from the site. Relative URLs are routes of
+/// the frontend.
+fn is_external(url: &str, hostname: &str) -> bool {
+    let site = Url::parse(hostname).ok();
+    Url::parse(url).is_ok_and(|u| {
+        site.as_ref()
+            .is_none_or(|site| site.host_str() != u.host_str())
+    })
+}
+
+fn nav_links(items: &[NavItem], hostname: &str) -> Vec<(i32, NavLink)> {
+    items
+        .iter()
+        .map(|item| {
+            let mut children = nav_links(&item.children, hostname);
+            children.sort_by_key(|(order, _)| *order);
+            let link = NavLink {
+                title: item.title.clone(),
+                url: item.url.clone(),
+                external: is_external(&item.url, hostname),
+                children: children.into_iter().map(|(_, link)| link).collect(),
+            };
+            (item.order, link)
+        })
+        .collect()
+}
+
+/// The site menu: the `navigation` items of the config and the pages with
+/// a `menu` entry the requester may see, by ascending order. Items of the
+/// same order keep the config's order and come before pages.
+async fn get_navigation(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<NavLink>> {
+    let hostname = &state.config.hostname;
+    let mut links = nav_links(&state.config.navigation, hostname);
+
+    let store = state.page_store.read().await;
+    let mut pages: Vec<_> = store
+        .query(
+            |p| p.metadata.menu.is_some() && visibility.allows(&store, p),
+            0,
+            usize::MAX,
+        )
+        .filter_map(|page| {
+            let menu = page.metadata.menu.as_ref()?;
+            let title = menu.title.as_ref().unwrap_or(&page.metadata.title);
+            Some((menu.order, title.clone(), page.slug_with_category()))
+        })
+        .collect();
+    pages.sort();
+    links.extend(pages.into_iter().map(|(order, title, slug)| {
+        let link = NavLink {
+            title,
+            url: format!("/pages/{}", slug),
+            external: false,
+            children: Vec::new(),
+        };
+        (order, link)
+    }));
+
+    links.sort_by_key(|(order, _)| *order);
+    Json(links.into_iter().map(|(_, link)| link).collect())
+}
@@ -1,12 +1,28 @@
-use crate::handlers::error::{AppError, ERR_BAD_REQUEST, ERR_NOTE_NOT_FOUND};
+use crate::handlers::articles::{members_only, rolled_back, write_article_to_file};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::response::{Pagination, page_offset, requested_page, total_pages};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
 use crate::models::article::{
-    Article, ArticleContent, ArticleRepresentation, ArticleTeaser, PaginatedArticles,
+    Article, ArticleContent, ArticleRepresentation, ArticleTeaser, Metadata, NoteDetail,
+    PaginatedArticles, TermCount,
 };
-use crate::server::app::AppState;
-use axum::extract::{Path, Query, State};
+use crate::server::app::{AppState, IndexJob, record_content_change};
+use crate::server::auth::{require_admin, require_author};
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::server::visibility::Visibility;
+use crate::services::article_service::ArticleWrite;
+use crate::services::atomic_file::write_atomic;
+use crate::services::front_matter::front_matter_yaml;
+use crate::services::note_crypto::{NOTES_ENCRYPTION_KEY, NoteCipher};
+use crate::services::service::ArticleStore;
+use crate::services::site_time::{parse_date, site_timezone, today};
+use axum::extract::{Extension, Path, Query, State};
+use axum::middleware;
 use axum::response::IntoResponse;
-use axum::routing::get;
+use axum::routing::{get, post};
 use axum::{Json, Router};
+use chrono::{NaiveDate, Utc};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -14,107 +30,140 @@ use std::sync::Arc;
 pub struct NoteParams {
     tag: Option<String>,
     category: Option<String>,
+    /// A folder path such as `programming/rust`; notes in its sub-folders
+    /// are included.
+    folder: Option<String>,
     q: Option<String>,
     include_content: Option<bool>,
     #[serde(default = "default_page")]
     page: usize,
-    #[serde(default = "default_limit")]
-    limit: usize,
+    limit: Option<usize>,
 }
 
 fn default_page() -> usize {
     1
 }
 
-fn default_limit() -> usize {
-    10
+/// An entry for the daily note of `date`, today in the site timezone by
+/// default. With `append` the content is added to the end of the note
+/// instead of replacing its body.
+#[derive(Deserialize, Debug)]
+pub struct DailyNoteRequest {
+    pub date: Option<NaiveDate>,
+    pub content: String,
+    #[serde(default)]
+    pub append: bool,
+}
+
+impl Validate for DailyNoteRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("content", &self.content);
+        errors.into_result()
+    }
 }
 
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/api/notes", get(get_notes_list))
-        .route("/api/notes/{path}", get(get_note_by_slug))
+        .route(
+            "/api/notes",
+            with_load_limit(get(get_notes_list), LoadClass::ContentListings),
+        )
+        .route("/api/notes/{*path}", get(get_note_by_slug))
+        .route("/api/notes/daily/{date}", get(get_daily_note))
+        .route("/api/notes/tags", get(get_note_tags))
+        .route("/api/notes/tags/counts", get(get_note_tag_counts))
+        .route("/api/notes/categories", get(get_note_categories))
+        .route(
+            "/api/notes/categories/counts",
+            get(get_note_category_counts),
+        )
+        .route(
+            "/api/admin/notes/daily",
+            with_body_limit(post(write_daily_note), BodyGroup::Articles)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/notes/private",
+            get(get_private_notes).route_layer(middleware::from_fn(require_author)),
+        )
+        .route(
+            "/api/notes/private/{*path}",
+            get(get_private_note).route_layer(middleware::from_fn(require_author)),
+        )
 }
 
-async fn get_notes_list(
+pub(crate) async fn get_notes_list(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
     Query(params): Query<NoteParams>,
 ) -> Result<impl IntoResponse, AppError> {
     let store = state.note_store.read().await;
-    let limit = if params.limit > 0 { params.limit } else { 10 };
-    let page = if params.page > 0 { params.page } else { 1 };
-    let offset = (page - 1) * limit;
+    let limit = state.config.pagination.limit(params.limit);
+    let page = requested_page(params.page);
+    let offset = page_offset(page, limit);
 
     let tag = params.tag.clone();
     let category = params.category.clone();
     let query_lower = params.q.clone().map(|q| q.to_lowercase());
+    let folder = params
+        .folder
+        .as_deref()
+        .filter(|f| !f.trim_matches('/').is_empty())
+        .map(|f| store.categories_under(f));
 
-    let tag1 = tag.clone();
-    let category1 = category.clone();
-    let query1 = query_lower.clone();
-    let filter = move |a: &Article| {
-        if a.metadata.draft {
+    let filter = |a: &Article| {
+        if !visibility.allows(&store, a) {
             return false;
         }
-        if let Some(ref t) = tag1 {
+        if let Some(ref t) = tag {
             if !a.metadata.tags.contains(t) {
                 return false;
             }
         }
-        if let Some(ref c) = category1 {
+        if let Some(ref c) = category {
             if a.metadata.category.as_ref() != Some(c) {
                 return false;
             }
         }
-        if let Some(ref ql) = query1 {
-            a.metadata.title.to_lowercase().contains(ql)
-                || a.metadata.description.to_lowercase().contains(ql)
-        } else {
-            true
-        }
-    };
-
-    let paginated_vec: Vec<&Article> = store.query(filter, offset, limit).collect();
-
-    let tag2 = tag;
-    let category2 = category;
-    let query2 = query_lower;
-    let filter_total = move |a: &Article| {
-        if a.metadata.draft {
+        if let Some(ref folder) = folder
+            && !a
+                .metadata
+                .category
+                .as_deref()
+                .is_some_and(|c| folder.contains(c))
+        {
             return false;
         }
-        if let Some(ref t) = tag2 {
-            if !a.metadata.tags.contains(t) {
-                return false;
-            }
-        }
-        if let Some(ref c) = category2 {
-            if a.metadata.category.as_ref() != Some(c) {
-                return false;
-            }
-        }
-        if let Some(ref ql) = query2 {
+        if let Some(ref ql) = query_lower {
             a.metadata.title.to_lowercase().contains(ql)
                 || a.metadata.description.to_lowercase().contains(ql)
         } else {
             true
         }
     };
-    let total_notes = store.query(filter_total, 0, usize::MAX).count();
-    let total_pages = (total_notes as f64 / limit as f64).ceil() as usize;
+
+    let (paginated_vec, total_notes) = store.query_paginated(filter, offset, limit);
+    let total_pages = total_pages(total_notes, limit);
 
     let paginated = paginated_vec.into_iter();
 
     let result = if params.include_content.unwrap_or(false) {
         let notes_with_content = paginated
             .map(|note| {
-                let content = store
-                    .load_content_for(note)
-                    .unwrap_or_else(|_| String::new());
+                // Members-only bodies are left out for non-members.
+                let content = if visibility.unlocks(note) {
+                    store
+                        .load_content_for(note)
+                        .unwrap_or_else(|_| String::new())
+                } else {
+                    String::new()
+                };
                 ArticleRepresentation::Full(ArticleContent {
                     slug: note.slug_with_category(),
                     metadata: note.metadata.clone(),
                     content,
+                    edit_url: state.config.edit_url(store.root(), &note.file_path),
                 })
             })
             .collect::<Vec<_>>();
@@ -129,6 +178,7 @@ async fn get_notes_list(
                 ArticleRepresentation::Teaser(ArticleTeaser {
                     slug: note.slug_with_category(),
                     metadata: note.metadata.clone(),
+                    edit_url: state.config.edit_url(store.root(), &note.file_path),
                 })
             })
             .collect::<Vec<_>>();
@@ -139,45 +189,271 @@ async fn get_notes_list(
         })
     };
 
-    Ok(result)
+    let pagination = Pagination {
+        page,
+        limit,
+        total_pages,
+        max_limit: state.config.pagination.max_limit,
+    };
+    Ok((Extension(pagination), result))
 }
 
-async fn get_note_by_slug(
+pub(crate) async fn get_note_by_slug(
     State(state): State<Arc<AppState>>,
+    visibility: Visibility,
     Path(path): Path<String>,
 ) -> Result<impl IntoResponse, AppError> {
     let store = state.note_store.read().await;
 
-    let (category, slug) = match path.rsplit_once('/') {
-        Some((cat, slug)) => (Some(cat.to_string()), slug.to_string()),
-        None => (None, path.clone()),
-    };
-
-    let note = store
-        .query(
-            |n| n.slug == slug && n.metadata.category.as_deref() == category.as_deref(),
-            0,
-            usize::MAX,
-        )
-        .next();
-
-    match note {
-        Some(note) if !note.metadata.draft => {
+    match find_note(&store, &path) {
+        Some(note) if visibility.allows_direct(&store, note) => {
+            if !visibility.unlocks(note) {
+                return Err(members_only(note));
+            }
             let content = store
                 .load_content_for(note)
                 .map_err(|e| AppError::BadRequest {
-                    code: ERR_BAD_REQUEST,
+                    code: ErrorCode::BadRequest,
                     message: e.to_string(),
                 })?;
-            Ok(Json(ArticleContent {
-                slug: note.slug_with_category(),
-                metadata: note.metadata.clone(),
-                content,
+            Ok(Json(NoteDetail {
+                note: ArticleContent {
+                    slug: note.slug_with_category(),
+                    metadata: note.metadata.clone(),
+                    content,
+                    edit_url: state.config.edit_url(store.root(), &note.file_path),
+                },
+                archived: note.metadata.is_archived(),
             }))
         }
-        _ => Err(AppError::NotFound {
-            code: ERR_NOTE_NOT_FOUND,
-            message: format!("Note with slug {} not found", path),
-        }),
+        _ => Err(note_not_found(&path)),
+    }
+}
+
+/// Tags of published notes, kept apart from the article taxonomy.
+async fn get_note_tags(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<String>> {
+    let store = state.note_store.read().await;
+    Json(store.get_all_tags(visibility.includes_restricted()))
+}
+
+async fn get_note_tag_counts(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<TermCount>> {
+    let store = state.note_store.read().await;
+    Json(store.tag_counts(visibility.includes_restricted()))
+}
+
+/// Folders holding published notes, by their full path.
+async fn get_note_categories(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<String>> {
+    let store = state.note_store.read().await;
+    Json(store.get_all_categories(visibility.includes_restricted()))
+}
+
+async fn get_note_category_counts(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<TermCount>> {
+    let store = state.note_store.read().await;
+    Json(store.category_counts(visibility.includes_restricted()))
+}
+
+/// The daily note for a `YYYY-MM-DD` date, served like any other note.
+async fn get_daily_note(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(date): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let date = date
+        .parse::<NaiveDate>()
+        .map_err(|_| AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: format!("Invalid date {}, expected YYYY-MM-DD", date),
+        })?;
+    let path = state.config.daily_note_path(date);
+    get_note_by_slug(State(state), visibility, Path(path)).await
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Creates the daily note for a date, or rewrites or appends to the one
+/// already there. An existing note keeps its front matter as written.
+async fn write_daily_note(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<DailyNoteRequest>,
+) -> Result<Json<ArticleContent>, AppError> {
+    let date = payload.date.unwrap_or_else(today);
+    let path = state.config.daily_note_path(date);
+    let (file_path, existing) = {
+        let store = state.note_store.read().await;
+        let file_path = store.root().join(format!("{}.md", path));
+        let existing = match find_note(&store, &path) {
+            Some(note) if note.metadata.private => {
+                return Err(AppError::BadRequest {
+                    code: ErrorCode::BadRequest,
+                    message: format!("Daily note {} is private and cannot be written", path),
+                });
+            }
+            Some(note) => {
+                let body = store.load_source_for(note).map_err(internal)?;
+                let file = std::fs::read_to_string(&note.file_path).map_err(internal)?;
+                let front_matter = front_matter_yaml(&file).unwrap_or_default().to_string();
+                Some((body, front_matter))
+            }
+            None => None,
+        };
+        (file_path, existing)
+    };
+
+    let content = payload.content.trim_end();
+    let write = ArticleWrite::begin(&file_path).map_err(internal)?;
+    match existing {
+        Some((body, front_matter)) => {
+            let body = if payload.append && !body.trim().is_empty() {
+                format!("{}\n\n{}\n", body.trim_end(), content)
+            } else {
+                format!("{}\n", content)
+            };
+            write_atomic(&file_path, format!("---\n{}---\n\n{}", front_matter, body))
+                .map_err(internal)?;
+        }
+        None => {
+            let metadata = Metadata {
+                title: date.to_string(),
+                author: "system".to_string(),
+                date: if date == today() {
+                    Utc::now()
+                } else {
+                    parse_date(&date.to_string(), site_timezone()).unwrap_or_else(Utc::now)
+                },
+                display_date: None,
+                tags: Vec::new(),
+                description: String::new(),
+                status: Default::default(),
+                last_updated: None,
+                category: None,
+                poll: None,
+                lang: None,
+                translations: Default::default(),
+                private: false,
+                http_headers: Default::default(),
+                comments: Default::default(),
+                menu: None,
+                audio: None,
+                gallery: Vec::new(),
+                link: None,
+                syndication: Default::default(),
+                references: Vec::new(),
+                downloads: Vec::new(),
+                description_generated: false,
+                members_only: false,
+            };
+            write_article_to_file(&metadata, &format!("{}\n", content), &file_path, None)?;
+        }
+    }
+
+    let file_path = file_path.to_string_lossy().to_string();
+    let note = {
+        let mut store = state.note_store.write().await;
+        if let Err(e) = store.update_single_article(&file_path) {
+            return Err(rolled_back(write, "update the note store", e));
+        }
+        let note = find_note(&store, &path).ok_or_else(|| note_not_found(&path))?;
+        ArticleContent {
+            slug: note.slug_with_category(),
+            metadata: note.metadata.clone(),
+            content: store.load_content_for(note).map_err(internal)?,
+            edit_url: state.config.edit_url(store.root(), &note.file_path),
+        }
+    };
+
+    if let Some(tx) = &state.index_tx {
+        let _ = tx.send(IndexJob::for_article(ArticleContent {
+            slug: format!("notes/{}", note.slug),
+            ..note.clone()
+        }));
+    }
+    state.cache.invalidate_all();
+    record_content_change(&state).await;
+    Ok(Json(note))
+}
+
+/// The note at `path`, its folders under the notes directory followed by
+/// its slug, at any depth.
+fn find_note<'a>(store: &'a ArticleStore, path: &str) -> Option<&'a Article> {
+    store.get_by_path(path.trim_matches('/'))
+}
+
+fn note_not_found(path: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::NoteNotFound,
+        message: format!("Note with slug {} not found", path),
     }
 }
+
+async fn get_private_notes(State(state): State<Arc<AppState>>) -> Json<Vec<ArticleTeaser>> {
+    let store = state.note_store.read().await;
+    let notes = store
+        .query(|n| n.metadata.private, 0, usize::MAX)
+        .map(|note| ArticleTeaser {
+            slug: note.slug_with_category(),
+            metadata: note.metadata.clone(),
+            edit_url: None,
+        })
+        .collect();
+    Json(notes)
+}
+
+/// Decrypts a private note for the author. Bodies that have not been sealed
+/// yet are returned as they are.
+async fn get_private_note(
+    State(state): State<Arc<AppState>>,
+    Path(path): Path<String>,
+) -> Result<Json<ArticleContent>, AppError> {
+    let cipher = state
+        .note_cipher
+        .as_ref()
+        .ok_or_else(|| AppError::BadRequest {
+            code: ErrorCode::PrivateNotesDisabled,
+            message: format!("Set {} to read private notes", NOTES_ENCRYPTION_KEY),
+        })?;
+    let store = state.note_store.read().await;
+    let note = find_note(&store, &path)
+        .filter(|n| n.metadata.private)
+        .ok_or_else(|| note_not_found(&path))?;
+
+    let body = store
+        .load_content_for(note)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    let content = if NoteCipher::is_sealed(&body) {
+        cipher
+            .decrypt(&body)
+            .map_err(|e| AppError::InternalServerError {
+                code: ErrorCode::NoteDecryptionFailed,
+                message: e.to_string(),
+            })?
+    } else {
+        body
+    };
+
+    Ok(Json(ArticleContent {
+        slug: note.slug_with_category(),
+        metadata: note.metadata.clone(),
+        content,
+        edit_url: None,
+    }))
+}
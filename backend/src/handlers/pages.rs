@@ -0,0 +1,88 @@
+use crate::handlers::articles::members_only;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::{Article, ArticleContent, ArticleTeaser};
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use axum::extract::{Path, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/pages", get(get_pages_list))
+        .route("/api/pages/{*path}", get(get_page))
+}
+
+/// Pages with a `menu` entry come first, in menu order, followed by the
+/// rest by title.
+fn menu_order(a: &Article, b: &Article) -> std::cmp::Ordering {
+    let key = |p: &Article| {
+        p.metadata.menu.as_ref().map(|menu| {
+            (
+                menu.order,
+                menu.title
+                    .clone()
+                    .unwrap_or_else(|| p.metadata.title.clone()),
+            )
+        })
+    };
+    match (key(a), key(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.metadata.title.cmp(&b.metadata.title),
+    }
+}
+
+pub(crate) async fn get_pages_list(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Json<Vec<ArticleTeaser>> {
+    let store = state.page_store.read().await;
+    let mut pages: Vec<&Article> = store
+        .query(|p| visibility.allows(&store, p), 0, usize::MAX)
+        .collect();
+    pages.sort_by(|a, b| menu_order(a, b));
+    Json(
+        pages
+            .into_iter()
+            .map(|page| ArticleTeaser {
+                slug: page.slug_with_category(),
+                metadata: page.metadata.clone(),
+                edit_url: state.config.edit_url(store.root(), &page.file_path),
+            })
+            .collect(),
+    )
+}
+
+pub(crate) async fn get_page(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(path): Path<String>,
+) -> Result<Json<ArticleContent>, AppError> {
+    let store = state.page_store.read().await;
+    match store.get_by_path(path.trim_matches('/')) {
+        Some(page) if visibility.allows_direct(&store, page) => {
+            if !visibility.unlocks(page) {
+                return Err(members_only(page));
+            }
+            let content = store
+                .load_content_for(page)
+                .map_err(|e| AppError::BadRequest {
+                    code: ErrorCode::BadRequest,
+                    message: e.to_string(),
+                })?;
+            Ok(Json(ArticleContent {
+                slug: page.slug_with_category(),
+                metadata: page.metadata.clone(),
+                content,
+                edit_url: state.config.edit_url(store.root(), &page.file_path),
+            }))
+        }
+        _ => Err(AppError::NotFound {
+            code: ErrorCode::PageNotFound,
+            message: format!("Page {} not found", path),
+        }),
+    }
+}
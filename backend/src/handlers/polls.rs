@@ -0,0 +1,231 @@
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::article::ArticleContent;
+use crate::models::poll::{Poll, PollResults};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::client_ip::{ClientIp, stored_ip};
+use crate::services::poll_service::{
+    create_poll, find_article_poll, front_matter_poll, get_poll, poll_results, record_vote,
+};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use slug::slugify;
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct CreatePollRequest {
+    pub id: Option<String>,
+    pub question: String,
+    pub options: Vec<String>,
+    pub article_slug: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct VoteRequest {
+    pub option: usize,
+}
+
+const MAX_QUESTION_LENGTH: usize = 300;
+
+impl Validate for CreatePollRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("question", &self.question);
+        errors.max_chars("question", &self.question, MAX_QUESTION_LENGTH);
+        if self.options.len() < 2 {
+            errors.add("options", "must contain at least two options");
+        }
+        if self.options.iter().any(|o| o.trim().is_empty()) {
+            errors.add("options", "must not contain empty options");
+        }
+        if let Some(id) = &self.id
+            && slugify(id).is_empty()
+        {
+            errors.add("id", "must contain letters or digits");
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for VoteRequest {
+    // Whether the option exists depends on the poll, which the handler checks.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        Ok(())
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/polls/{id}", get(get_poll_results))
+        .route("/api/polls/{id}/vote", post(vote))
+        .route(
+            "/api/admin/polls",
+            post(create_admin_poll).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Looks a poll up by id, first among admin-created polls and then among
+/// polls declared in published articles' front matter.
+async fn resolve_poll(state: &AppState, id: &str) -> Result<Option<Poll>, AppError> {
+    if let Some(poll) = get_poll(&state.db, id).await.map_err(db_error)? {
+        return Ok(Some(poll));
+    }
+
+    let store = state.store.read().await;
+    let poll = store
+        .query(
+            |a| a.metadata.is_published() && a.metadata.poll.is_some(),
+            0,
+            usize::MAX,
+        )
+        .filter_map(|a| {
+            a.metadata
+                .poll
+                .as_ref()
+                .map(|def| front_matter_poll(&a.slug_with_category(), def))
+        })
+        .find(|poll| poll.id == id);
+    Ok(poll)
+}
+
+/// Results for the poll attached to an article, preferring the front matter
+/// definition over admin-created polls.
+pub async fn article_poll_results(
+    state: &AppState,
+    article: &ArticleContent,
+) -> Result<Option<PollResults>, AppError> {
+    let poll = match &article.metadata.poll {
+        Some(definition) => Some(front_matter_poll(&article.slug, definition)),
+        None => find_article_poll(&state.db, &article.slug)
+            .await
+            .map_err(db_error)?,
+    };
+
+    match poll {
+        Some(poll) => Ok(Some(
+            poll_results(&state.db, &poll).await.map_err(db_error)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+async fn get_poll_results(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<PollResults>, AppError> {
+    let poll = resolve_poll(&state, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::PollNotFound,
+            message: format!("Poll {} not found", id),
+        })?;
+    let results = poll_results(&state.db, &poll).await.map_err(db_error)?;
+    Ok(Json(results))
+}
+
+/// Identifies a voter by their session when logged in, otherwise by their
+/// IP address as `privacy.ip_storage` keeps it, so raw addresses are never
+/// stored.
+fn voter_key(state: &AppState, jar: &SignedJar, client_ip: &str) -> String {
+    match session_user(jar) {
+        Ok(user) => format!("user:{}", user.github_id),
+        Err(_) => format!(
+            "ip:{}",
+            stored_ip(client_ip, state.config.privacy.ip_storage)
+        ),
+    }
+}
+
+async fn vote(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    ClientIp(client_ip): ClientIp,
+    jar: SignedJar,
+    ValidJson(payload): ValidJson<VoteRequest>,
+) -> Result<Json<PollResults>, AppError> {
+    let poll = resolve_poll(&state, &id)
+        .await?
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::PollNotFound,
+            message: format!("Poll {} not found", id),
+        })?;
+
+    if payload.option >= poll.options.len() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::InvalidPollOption,
+            message: format!("Poll {} has no option {}", id, payload.option),
+        });
+    }
+
+    let key = voter_key(&state, &jar, &client_ip);
+    let counted = record_vote(&state.db, &poll.id, payload.option, &key)
+        .await
+        .map_err(db_error)?;
+    if !counted {
+        return Err(AppError::Conflict {
+            code: ErrorCode::AlreadyVoted,
+            message: "You have already voted in this poll".to_string(),
+        });
+    }
+
+    state.cache.invalidate_all();
+
+    let results = poll_results(&state.db, &poll).await.map_err(db_error)?;
+    Ok(Json(results))
+}
+
+async fn create_admin_poll(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<CreatePollRequest>,
+) -> Result<Json<Poll>, AppError> {
+    if let Some(ref slug) = payload.article_slug {
+        let store = state.store.read().await;
+        if store.get_by_slug(slug).is_none() {
+            return Err(AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            });
+        }
+    }
+
+    let id = payload
+        .id
+        .as_deref()
+        .map(slugify)
+        .unwrap_or_else(|| slugify(&payload.question));
+    if id.is_empty() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "Invalid poll id".to_string(),
+        });
+    }
+    if resolve_poll(&state, &id).await?.is_some() {
+        return Err(AppError::Conflict {
+            code: ErrorCode::PollExists,
+            message: format!("Poll {} already exists", id),
+        });
+    }
+
+    let poll = Poll {
+        id,
+        question: payload.question,
+        options: payload.options,
+        article_slug: payload.article_slug,
+    };
+    create_poll(&state.db, &poll).await.map_err(db_error)?;
+    state.cache.invalidate_all();
+
+    Ok(Json(poll))
+}
@@ -0,0 +1,38 @@
+use crate::server::app::AppState;
+use crate::services::presence_service::{PresenceEvent, editing_sessions};
+use axum::Router;
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/presence", get(presence))
+}
+
+/// Server-sent presence events: `editing` with the current number of
+/// editing sessions first, then `editing` and `published` as they happen.
+async fn presence(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let events = state.presence.subscribe();
+    let current = PresenceEvent::Editing {
+        sessions: editing_sessions(&state).await,
+    };
+    let live = stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => return Some((event, events)),
+                // A slow reader misses what it fell behind on.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    let stream = stream::once(async { current })
+        .chain(live)
+        .map(|event| Event::default().event(event.name()).json_data(&event));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,229 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::article::{Article, ArticleContent, ArticleTeaser};
+use crate::models::preview_branch::PreviewBranchSummary;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::preview_branch_service::{PreviewBranch, check_out, is_valid_branch, slugs};
+use crate::services::service::ArticleStore;
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// Directory under the data directory that branches are checked out into.
+const CHECKOUT_DIR: &str = "preview-branches";
+
+#[derive(Deserialize, Debug)]
+pub struct PreviewBranchRequest {
+    pub branch: String,
+}
+
+impl Validate for PreviewBranchRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("branch", &self.branch);
+        if !self.branch.is_empty() && !is_valid_branch(&self.branch) {
+            errors.add("branch", "is not a supported branch name");
+        }
+        errors.into_result()
+    }
+}
+
+/// Branch names may contain `/`; they are sent percent-encoded in the
+/// `{branch}` segment, e.g. `/preview/reorg%2F2024/api/articles`.
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/preview-branches",
+            get(list)
+                .post(create)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/preview-branches/{branch}",
+            delete(remove).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/preview/{branch}/api/articles",
+            get(list_articles).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/preview/{branch}/api/articles/{slug}",
+            get(get_article).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/preview/{branch}/api/notes",
+            get(list_notes).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/preview/{branch}/api/notes/{*path}",
+            get(get_note).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn not_found(branch: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::PreviewBranchNotFound,
+        message: format!("Branch {} is not checked out", branch),
+    }
+}
+
+/// Checks out `branch`, replacing an earlier checkout of it, and reports
+/// how its entries differ from the live content.
+async fn create(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<PreviewBranchRequest>,
+) -> Result<Json<PreviewBranchSummary>, AppError> {
+    let config = state
+        .config
+        .preview_branches
+        .as_ref()
+        .ok_or_else(|| AppError::BadRequest {
+            code: ErrorCode::PreviewBranchesDisabled,
+            message: "Branch previews are not enabled".to_string(),
+        })?;
+
+    let live = {
+        let articles = state.store.read().await;
+        let notes = state.note_store.read().await;
+        slugs(&articles, &notes)
+    };
+    let preview = check_out(
+        config,
+        &state.data_dir.join(CHECKOUT_DIR),
+        &payload.branch,
+        &live,
+    )
+    .await
+    .map_err(|message| AppError::BadRequest {
+        code: ErrorCode::BranchCheckoutFailed,
+        message,
+    })?;
+    let summary = preview.summary.clone();
+    state
+        .preview_branches
+        .write()
+        .await
+        .insert(payload.branch, preview);
+    Ok(Json(summary))
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Json<Vec<PreviewBranchSummary>> {
+    let previews = state.preview_branches.read().await;
+    Json(previews.values().map(|p| p.summary.clone()).collect())
+}
+
+/// Drops a preview and its checkout.
+async fn remove(
+    State(state): State<Arc<AppState>>,
+    Path(branch): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let removed = state.preview_branches.write().await.remove(&branch);
+    if removed.is_none() {
+        return Err(not_found(&branch));
+    }
+    Ok(Json(
+        json!({ "branch": branch, "message": "Preview branch removed" }),
+    ))
+}
+
+/// Every entry of a store, drafts included, as the admin listing shows
+/// them. Private notes are left out of previews.
+fn teasers(store: &ArticleStore, slug: impl Fn(&Article) -> String) -> Vec<ArticleTeaser> {
+    store
+        .query(|a| !a.metadata.private, 0, usize::MAX)
+        .map(|a| ArticleTeaser {
+            slug: slug(a),
+            metadata: a.metadata.clone(),
+            edit_url: None,
+        })
+        .collect()
+}
+
+fn content(
+    store: &ArticleStore,
+    entry: &Article,
+    slug: String,
+) -> Result<Json<ArticleContent>, AppError> {
+    let content = store
+        .load_content_for(entry)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    Ok(Json(ArticleContent {
+        slug,
+        metadata: entry.metadata.clone(),
+        content,
+        edit_url: None,
+    }))
+}
+
+async fn with_preview<T>(
+    state: &AppState,
+    branch: &str,
+    f: impl FnOnce(&PreviewBranch) -> Result<T, AppError>,
+) -> Result<T, AppError> {
+    let previews = state.preview_branches.read().await;
+    let preview = previews.get(branch).ok_or_else(|| not_found(branch))?;
+    f(preview)
+}
+
+async fn list_articles(
+    State(state): State<Arc<AppState>>,
+    Path(branch): Path<String>,
+) -> Result<Json<Vec<ArticleTeaser>>, AppError> {
+    with_preview(&state, &branch, |preview| {
+        Ok(Json(teasers(&preview.articles, |a| a.slug.clone())))
+    })
+    .await
+}
+
+async fn get_article(
+    State(state): State<Arc<AppState>>,
+    Path((branch, slug)): Path<(String, String)>,
+) -> Result<Json<ArticleContent>, AppError> {
+    with_preview(&state, &branch, |preview| {
+        let article = preview
+            .articles
+            .get_by_slug(&slug)
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        content(&preview.articles, article, article.slug.clone())
+    })
+    .await
+}
+
+async fn list_notes(
+    State(state): State<Arc<AppState>>,
+    Path(branch): Path<String>,
+) -> Result<Json<Vec<ArticleTeaser>>, AppError> {
+    with_preview(&state, &branch, |preview| {
+        Ok(Json(teasers(&preview.notes, Article::slug_with_category)))
+    })
+    .await
+}
+
+async fn get_note(
+    State(state): State<Arc<AppState>>,
+    Path((branch, path)): Path<(String, String)>,
+) -> Result<Json<ArticleContent>, AppError> {
+    with_preview(&state, &branch, |preview| {
+        let note = preview
+            .notes
+            .get_by_path(path.trim_matches('/'))
+            .filter(|n| !n.metadata.private)
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::NoteNotFound,
+                message: format!("Note with slug {} not found", path),
+            })?;
+        content(&preview.notes, note, note.slug_with_category())
+    })
+    .await
+}
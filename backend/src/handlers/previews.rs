@@ -0,0 +1,231 @@
+use crate::handlers::comments::rate_limit;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::article::ArticleContent;
+use crate::models::preview::{IssuedPreviewLink, Preview, PreviewLink, ReviewComment};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::services::comment_service::sanitize_content;
+use crate::services::notification_service::notify_author;
+use crate::services::preview_service::{
+    add_review_comment, issue_preview, list_previews, live_preview, review_comments, revoke_preview,
+};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const DEFAULT_EXPIRY_HOURS: u64 = 72;
+const MAX_EXPIRY_HOURS: u64 = 30 * 24;
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_COMMENT_LENGTH: usize = 5_000;
+
+/// `reviewer` notes who the link is for. The link expires after
+/// `expires_in_hours`, 72 by default.
+#[derive(Deserialize, Debug)]
+pub struct IssuePreviewRequest {
+    pub reviewer: Option<String>,
+    pub expires_in_hours: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReviewCommentRequest {
+    pub name: String,
+    pub content: String,
+}
+
+impl Validate for IssuePreviewRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some(reviewer) = &self.reviewer {
+            errors.max_chars("reviewer", reviewer, MAX_NAME_LENGTH);
+        }
+        if let Some(hours) = self.expires_in_hours
+            && !(1..=MAX_EXPIRY_HOURS).contains(&hours)
+        {
+            errors.add(
+                "expires_in_hours",
+                format!("must be between 1 and {}", MAX_EXPIRY_HOURS),
+            );
+        }
+        errors.into_result()
+    }
+}
+
+impl Validate for ReviewCommentRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("name", &self.name);
+        errors.max_chars("name", &self.name, MAX_NAME_LENGTH);
+        errors.require("content", &self.content);
+        errors.max_chars("content", &self.content, MAX_COMMENT_LENGTH);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/previews/{token}", get(preview))
+        .route(
+            "/api/previews/{token}/comments",
+            with_body_limit(post(comment), BodyGroup::Comments)
+                .route_layer(middleware::from_fn(rate_limit)),
+        )
+        .route(
+            "/api/admin/articles/{slug}/previews",
+            get(list)
+                .post(issue)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles/{slug}/review-comments",
+            get(list_comments).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/previews/{id}",
+            delete(revoke).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn article_not_found(slug: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::ArticleNotFound,
+        message: format!("Article with slug {} not found", slug),
+    }
+}
+
+/// The live link for `token`. Unknown, revoked and expired links are all
+/// reported alike.
+async fn live_link(state: &AppState, token: &str) -> Result<PreviewLink, AppError> {
+    live_preview(&state.db, token)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::PreviewNotFound,
+            message: "Preview link not found or expired".to_string(),
+        })
+}
+
+async fn issue(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<IssuePreviewRequest>,
+) -> Result<Json<IssuedPreviewLink>, AppError> {
+    let slug = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| !a.deleted)
+            .ok_or_else(|| article_not_found(&slug))?;
+        article.slug.clone()
+    };
+    let reviewer = payload
+        .reviewer
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let hours = payload.expires_in_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+    let issued = issue_preview(&state.db, &slug, reviewer, hours)
+        .await
+        .map_err(internal)?;
+    Ok(Json(issued))
+}
+
+async fn list(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<PreviewLink>>, AppError> {
+    Ok(Json(
+        list_previews(&state.db, &slug).await.map_err(internal)?,
+    ))
+}
+
+async fn list_comments(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<ReviewComment>>, AppError> {
+    Ok(Json(
+        review_comments(&state.db, &slug).await.map_err(internal)?,
+    ))
+}
+
+async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    if !revoke_preview(&state.db, id).await.map_err(internal)? {
+        return Err(AppError::NotFound {
+            code: ErrorCode::PreviewNotFound,
+            message: format!("Preview link {} not found", id),
+        });
+    }
+    Ok(Json(json!({ "id": id, "message": "Preview link revoked" })))
+}
+
+/// The article behind a preview link, drafts included, with the review
+/// comments left on it so far.
+async fn preview(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<Preview>, AppError> {
+    let link = live_link(&state, &token).await?;
+    let article = {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&link.article_slug)
+            .filter(|a| !a.deleted)
+            .ok_or_else(|| article_not_found(&link.article_slug))?;
+        ArticleContent {
+            slug: article.slug.clone(),
+            metadata: article.metadata.clone(),
+            content: store.load_content_for(article).map_err(internal)?,
+            edit_url: None,
+        }
+    };
+    let comments = review_comments(&state.db, &link.article_slug)
+        .await
+        .map_err(internal)?;
+    Ok(Json(Preview {
+        article,
+        expires_at: link.expires_at,
+        comments,
+    }))
+}
+
+async fn comment(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    ValidJson(payload): ValidJson<ReviewCommentRequest>,
+) -> Result<Json<ReviewComment>, AppError> {
+    let link = live_link(&state, &token).await?;
+    let comment = add_review_comment(
+        &state.db,
+        &link,
+        payload.name.trim(),
+        &sanitize_content(&payload.content),
+    )
+    .await
+    .map_err(internal)?;
+
+    notify_author(
+        &state,
+        &json!({
+            "event": "review_comment",
+            "slug": link.article_slug,
+            "comment": comment,
+        }),
+    )
+    .await;
+    Ok(Json(comment))
+}
@@ -0,0 +1,35 @@
+use crate::handlers::{articles, categories, notes, pages, tags};
+use crate::server::app::AppState;
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use axum::Router;
+use axum::routing::get;
+use std::sync::Arc;
+
+/// Read-only surface for third-party consumers such as static site
+/// builders and mobile apps. It serves what anonymous visitors see through
+/// the same handlers as `/api`, but under its own paths, so the headers,
+/// quotas and caching that `harden_public_api` applies here leave the
+/// authoring API untouched.
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/public/articles",
+            with_load_limit(get(articles::get_articles_list), LoadClass::ContentListings),
+        )
+        .route(
+            "/api/public/articles/{slug}",
+            get(articles::get_article_by_slug),
+        )
+        .route(
+            "/api/public/notes",
+            with_load_limit(get(notes::get_notes_list), LoadClass::ContentListings),
+        )
+        .route("/api/public/notes/{*path}", get(notes::get_note_by_slug))
+        .route("/api/public/pages", get(pages::get_pages_list))
+        .route("/api/public/pages/{*path}", get(pages::get_page))
+        .route("/api/public/tags", get(tags::get_all_tags))
+        .route(
+            "/api/public/categories",
+            get(categories::get_all_categories),
+        )
+}
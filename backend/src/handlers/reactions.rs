@@ -0,0 +1,94 @@
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::reaction::ReactionCount;
+use crate::server::app::AppState;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::visibility::Visibility;
+use crate::services::reaction_service::{reaction_counts, toggle_reaction};
+use axum::extract::{Path, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, Debug)]
+pub struct ReactionRequest {
+    pub emoji: String,
+}
+
+// Membership in the configured emoji set is checked by the handler.
+impl Validate for ReactionRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("emoji", &self.emoji);
+        errors.into_result()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReactionResponse {
+    pub slug: String,
+    pub emoji: String,
+    pub reacted: bool,
+    pub reactions: Vec<ReactionCount>,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/articles/{slug}/reactions",
+        with_body_limit(post(toggle_article_reaction), BodyGroup::Comments),
+    )
+}
+
+async fn toggle_article_reaction(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(slug): Path<String>,
+    jar: SignedJar,
+    ValidJson(payload): ValidJson<ReactionRequest>,
+) -> Result<Json<ReactionResponse>, AppError> {
+    let user = session_user(&jar)?;
+
+    if !state.config.reaction_emojis.contains(&payload.emoji) {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::InvalidReaction,
+            message: format!("Unsupported reaction: {}", payload.emoji),
+        });
+    }
+
+    // Reactions are kept under `category/slug`, whichever form the path used.
+    let slug = {
+        let store = state.store.read().await;
+        store
+            .get_by_slug(&slug)
+            .filter(|article| visibility.allows(&store, article))
+            .map(|article| article.slug_with_category())
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?
+    };
+
+    let reacted = toggle_reaction(&state.db, &slug, user.github_id, &payload.emoji)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    let reactions = reaction_counts(&state.db, &slug, &state.config.reaction_emojis)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+
+    state.cache.invalidate_all();
+
+    Ok(Json(ReactionResponse {
+        slug,
+        emoji: payload.emoji,
+        reacted,
+        reactions,
+    }))
+}
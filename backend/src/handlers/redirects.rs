@@ -0,0 +1,118 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::undo::delete_with_undo;
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::redirect::Redirect;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::redirects::normalize_path;
+use crate::services::redirect_service::{add_redirect, list_redirects};
+use crate::services::undo_service::REDIRECTS;
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+const MAX_PATH_LENGTH: usize = 2_000;
+/// Redirecting these would lock the admin out of the API that undoes it.
+const PROTECTED_PREFIX: &str = "/api/admin";
+
+#[derive(Deserialize, Debug)]
+pub struct CreateRedirectRequest {
+    pub from: String,
+    pub to: String,
+    #[serde(default = "default_status")]
+    pub status: u16,
+}
+
+fn default_status() -> u16 {
+    301
+}
+
+impl Validate for CreateRedirectRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("from", &self.from);
+        errors.max_chars("from", &self.from, MAX_PATH_LENGTH);
+        if !self.from.starts_with('/') || self.from.contains(['?', '#']) {
+            errors.add("from", "must be a path starting with / and without a query");
+        } else if self.from.starts_with(PROTECTED_PREFIX) {
+            errors.add("from", format!("must not be under {}", PROTECTED_PREFIX));
+        }
+
+        errors.require("to", &self.to);
+        errors.max_chars("to", &self.to, MAX_PATH_LENGTH);
+        let is_path = self.to.starts_with('/') && !self.to.starts_with("//");
+        let is_url = Url::parse(&self.to).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !is_path && !is_url {
+            errors.add("to", "must be a path starting with / or an http(s) URL");
+        } else if is_path && normalize_path(&self.to) == normalize_path(&self.from) {
+            errors.add("to", "must differ from `from`");
+        }
+
+        errors.one_of("status", &self.status.to_string(), &["301", "308"]);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/redirects",
+            get(list)
+                .post(create)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/redirects/{id}",
+            delete(remove).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Redirect>>, AppError> {
+    list_redirects(&state.db).await.map(Json).map_err(db_error)
+}
+
+async fn create(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<CreateRedirectRequest>,
+) -> Result<Json<Redirect>, AppError> {
+    let from = normalize_path(&payload.from);
+    let redirect = add_redirect(&state.db, from, &payload.to, payload.status)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::Conflict {
+            code: ErrorCode::RedirectExists,
+            message: format!("A redirect from {} already exists", from),
+        })?;
+    state.redirects.reload(&state.db).await.map_err(db_error)?;
+    Ok(Json(redirect))
+}
+
+async fn remove(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let response = delete_with_undo(
+        &state,
+        &[(&REDIRECTS, id)],
+        || AppError::NotFound {
+            code: ErrorCode::RedirectNotFound,
+            message: format!("Redirect {} not found", id),
+        },
+        "Redirect deleted",
+    )
+    .await?;
+    state.redirects.reload(&state.db).await.map_err(db_error)?;
+    Ok(response)
+}
@@ -0,0 +1,74 @@
+use crate::handlers::error::ErrorCode;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Body of every JSON response served under `/api/v1`. Exactly one of `data`
+/// and `error` is non-null.
+#[derive(Serialize, Debug)]
+pub struct Envelope {
+    pub data: Option<Value>,
+    pub error: Option<ErrorBody>,
+    pub meta: Meta,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Meta {
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+/// Paging information for list endpoints. Handlers attach it to the response
+/// as an `Extension` so the envelope can report it in `meta`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Pagination {
+    pub page: usize,
+    pub limit: usize,
+    pub total_pages: usize,
+    /// Largest `limit` the endpoint accepts.
+    pub max_limit: usize,
+}
+
+/// The page a listing request asks for, counting from 1. Page 0 is read as
+/// the first.
+pub fn requested_page(page: usize) -> usize {
+    page.max(1)
+}
+
+/// Index of the first item on `page` of a listing split into pages of
+/// `limit`. Pages far past the end saturate instead of overflowing, so
+/// they come back empty.
+pub fn page_offset(page: usize, limit: usize) -> usize {
+    requested_page(page).saturating_sub(1).saturating_mul(limit)
+}
+
+/// Pages of `limit` items needed to list `total` items.
+pub fn total_pages(total: usize, limit: usize) -> usize {
+    total.div_ceil(limit)
+}
+
+impl Envelope {
+    pub fn success(data: Value, meta: Meta) -> Self {
+        Self {
+            data: Some(data),
+            error: None,
+            meta,
+        }
+    }
+
+    pub fn failure(error: ErrorBody, meta: Meta) -> Self {
+        Self {
+            data: None,
+            error: Some(error),
+            meta,
+        }
+    }
+}
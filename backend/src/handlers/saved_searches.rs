@@ -0,0 +1,157 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::undo::delete_with_undo;
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::saved_search::{SavedSearch, SavedSearchAlert};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::notification_service::notify_author;
+use crate::services::saved_search_service::{
+    create_saved_search, list_saved_searches, new_matching_comments, record_hits,
+};
+use crate::services::undo_service::{SAVED_SEARCH_HITS, SAVED_SEARCHES};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_QUERY_LENGTH: usize = 500;
+/// How many of the best hits are compared with earlier matches.
+const MAX_HITS: usize = 100;
+
+/// With `notify`, new matches are sent to the author's `notify_webhook`.
+#[derive(Deserialize, Debug)]
+pub struct SaveSearchRequest {
+    pub name: String,
+    pub query: String,
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl Validate for SaveSearchRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("name", &self.name);
+        errors.max_chars("name", &self.name, MAX_NAME_LENGTH);
+        errors.require("query", &self.query);
+        errors.max_chars("query", &self.query, MAX_QUERY_LENGTH);
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/saved-searches",
+            get(list)
+                .post(save)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/saved-searches/{id}",
+            delete(remove).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Slugs of the entries matching `query` in the author's index, drafts
+/// included. Empty when full-text search is disabled.
+fn matching_entries(state: &AppState, query: &str) -> Result<Vec<String>, String> {
+    let Some(search) = &state.search_service else {
+        return Ok(Vec::new());
+    };
+    let hits = search
+        .search_all(query, MAX_HITS)
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(hits.into_iter().map(|hit| hit.slug).collect())
+}
+
+/// Saves a query. What it matches now is remembered, so only content and
+/// comments added later are reported.
+async fn save(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<SaveSearchRequest>,
+) -> Result<Json<SavedSearch>, AppError> {
+    let query = payload.query.trim();
+    let entries = matching_entries(&state, query).map_err(|e| AppError::BadRequest {
+        code: ErrorCode::BadRequest,
+        message: format!("Invalid search query: {}", e),
+    })?;
+    let search = create_saved_search(&state.db, payload.name.trim(), query, payload.notify)
+        .await
+        .map_err(internal)?;
+    record_hits(&state.db, search.id, &entries)
+        .await
+        .map_err(internal)?;
+    Ok(Json(search))
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<SavedSearch>>, AppError> {
+    Ok(Json(
+        list_saved_searches(&state.db).await.map_err(internal)?,
+    ))
+}
+
+async fn remove(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    delete_with_undo(
+        &state,
+        &[(&SAVED_SEARCHES, id), (&SAVED_SEARCH_HITS, id)],
+        || AppError::NotFound {
+            code: ErrorCode::SavedSearchNotFound,
+            message: format!("Saved search {} not found", id),
+        },
+        "Saved search deleted",
+    )
+    .await
+}
+
+/// Checks saved searches with `notify` set for entries and comments that
+/// matched since the last check, for the `check_saved_searches` job. Each
+/// search with new matches is sent to the author as a `saved_search` event.
+pub async fn check_saved_searches(state: &AppState) -> Result<Vec<SavedSearchAlert>, String> {
+    let searches = list_saved_searches(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut alerts = Vec::new();
+    for search in searches.into_iter().filter(|s| s.notify) {
+        let entries = match matching_entries(state, &search.query) {
+            Ok(entries) => record_hits(&state.db, search.id, &entries)
+                .await
+                .map_err(|e| e.to_string())?,
+            Err(e) => {
+                tracing::warn!("Saved search {} failed: {}", search.id, e);
+                Vec::new()
+            }
+        };
+        let comments = new_matching_comments(&state.db, &search)
+            .await
+            .map_err(|e| e.to_string())?;
+        if entries.is_empty() && comments.is_empty() {
+            continue;
+        }
+        let alert = SavedSearchAlert {
+            search,
+            entries,
+            comments,
+        };
+        notify_author(
+            state,
+            &json!({ "event": "saved_search", "saved_search": alert }),
+        )
+        .await;
+        alerts.push(alert);
+    }
+    Ok(alerts)
+}
@@ -0,0 +1,83 @@
+use crate::handlers::error::{AppError, ErrorCode, ErrorResponse};
+use crate::handlers::search::{SearchPageResponse, SearchResponse};
+use crate::models::article::{ArticleRepresentation, ArticleTeaser, PaginatedArticles};
+use crate::server::app::AppState;
+use axum::extract::Path;
+use axum::routing::get;
+use axum::{Json, Router};
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use std::sync::Arc;
+
+/// Names of the published schemas, each with the responses it describes.
+/// Responses under `/api/v1` carry the same bodies in the `data` of their
+/// envelope.
+pub const SCHEMAS: &[(&str, &str)] = &[
+    (
+        "article-teaser",
+        "An article in listings without its content",
+    ),
+    (
+        "paginated-articles",
+        "GET /api/articles and GET /api/notes, with or without content",
+    ),
+    ("search-results", "GET /api/search"),
+    ("search-page", "GET /api/search/page"),
+    ("error", "Every error response"),
+];
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/schema", get(list_schemas))
+        .route("/api/schema/{name}", get(get_schema))
+}
+
+/// The schema of `T` as it is serialized, so fields left out of responses
+/// are neither listed nor required.
+fn schema_of<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// The schema published as `name`.
+pub fn schema(name: &str) -> Option<Schema> {
+    Some(match name {
+        "article-teaser" => schema_of::<ArticleTeaser>(),
+        "paginated-articles" => schema_of::<PaginatedArticles<ArticleRepresentation>>(),
+        "search-results" => schema_of::<SearchResponse>(),
+        "search-page" => schema_of::<SearchPageResponse>(),
+        "error" => schema_of::<ErrorResponse>(),
+        _ => return None,
+    })
+}
+
+async fn list_schemas() -> Json<serde_json::Value> {
+    let schemas: serde_json::Map<_, _> = SCHEMAS
+        .iter()
+        .map(|&(name, describes)| (name.to_string(), describes.into()))
+        .collect();
+    Json(schemas.into())
+}
+
+async fn get_schema(Path(name): Path<String>) -> Result<Json<Schema>, AppError> {
+    schema(&name).map(Json).ok_or_else(|| AppError::NotFound {
+        code: ErrorCode::SchemaNotFound,
+        message: format!("No schema named {}", name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_listed_schema_is_published() {
+        for (name, _) in SCHEMAS {
+            let schema = schema(name).unwrap();
+            assert!(schema.get("$schema").is_some(), "{} has no $schema", name);
+        }
+        assert!(schema("article").is_none());
+    }
+}
@@ -1,12 +1,25 @@
-use crate::handlers::error::{AppError, ERR_EMPTY_SEARCH_QUERY, ERR_FULLTEXT_DISABLED};
-use crate::server::app::{AppState, reindex_all_content};
-use crate::server::auth::require_author;
-use crate::services::search::SearchResult;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::response::Pagination;
+use crate::models::article::{Article, ArticleStatus, Metadata, TermCount};
+use crate::models::search_consistency::IndexConsistency;
+use crate::server::app::{AppState, reindex_all_content, searchable_content};
+use crate::server::auth::{require_admin, require_author};
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::load_shed::{LoadClass, with_load_limit};
+use crate::server::visibility::Visibility;
+use crate::services::search::{
+    IndexCollection, SearchError, SearchResult, SearchService, check_consistency,
+};
+use axum::Extension;
+use axum::body::Bytes;
 use axum::extract::{Query, State};
-use axum::response::IntoResponse;
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router, middleware};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
 
 #[derive(Deserialize, Debug)]
@@ -16,11 +29,60 @@ pub struct SearchParams {
     pub highlights: Option<bool>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Debug)]
+pub struct SuggestionParams {
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct SearchResponse {
     pub results: Vec<SearchResult>,
     pub query: String,
     pub total_found: usize,
+    pub groups: Vec<SearchGroup>,
+}
+
+/// The hits from one collection, for sectioned results. `count` is every
+/// visible hit in it, up to the maximum page size, while `hits` holds the
+/// best of them up to the requested limit.
+#[derive(Serialize, JsonSchema)]
+pub struct SearchGroup {
+    pub collection: &'static str,
+    pub count: usize,
+    pub hits: Vec<SearchResult>,
+}
+
+/// Everything a search results page shows: the hits, plus the tags and
+/// categories whose names match the query, as shortcuts to their pages.
+#[derive(Serialize, JsonSchema)]
+pub struct SearchPageResponse {
+    #[serde(flatten)]
+    pub search: SearchResponse,
+    pub tags: Vec<TermCount>,
+    pub categories: Vec<TermCount>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AdminSearchParams {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+/// A hit from the author's index, with the entry's workflow status.
+#[derive(Serialize)]
+pub struct AdminSearchResult {
+    #[serde(flatten)]
+    pub result: SearchResult,
+    pub status: ArticleStatus,
+}
+
+#[derive(Serialize)]
+pub struct AdminSearchResponse {
+    pub results: Vec<AdminSearchResult>,
+    pub query: String,
+    pub total_found: usize,
 }
 
 #[derive(Serialize)]
@@ -36,119 +98,317 @@ pub struct PopularSearch {
 
 pub fn create_router() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/api/search", get(search_articles))
+        .route(
+            "/api/search",
+            with_load_limit(get(search_articles), LoadClass::Search),
+        )
+        .route(
+            "/api/search/opensearch-suggestions",
+            with_load_limit(get(opensearch_suggestions), LoadClass::Search),
+        )
+        .route(
+            "/api/search/page",
+            with_load_limit(get(search_page), LoadClass::Search),
+        )
         .route("/api/search/popular", get(get_popular_searches))
         .route(
             "/api/search/reindex",
             post(trigger_reindex).route_layer(middleware::from_fn(require_author)),
         )
+        .route(
+            "/api/admin/search",
+            get(admin_search).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/search/consistency",
+            get(get_consistency).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/search/consistency/repair",
+            post(repair_consistency).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/search/snapshot",
+            post(snapshot_index).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/search/restore",
+            with_body_limit(post(restore_index), BodyGroup::Imports)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
 }
 
-async fn search_articles(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<SearchParams>,
-) -> Result<impl IntoResponse, AppError> {
-    let search_service = state
+pub fn enabled_search(state: &AppState) -> Result<&Arc<SearchService>, AppError> {
+    state
         .search_service
         .as_ref()
         .ok_or_else(|| AppError::BadRequest {
-            code: ERR_FULLTEXT_DISABLED,
+            code: ErrorCode::FulltextDisabled,
             message: "Full-text search is not enabled".to_string(),
-        })?;
+        })
+}
 
-    let limit = params.limit.unwrap_or(20);
+fn search_failed(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn search_articles(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Query(params): Query<SearchParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let limit = state.config.pagination.limit(params.limit);
+    // Search returns a single page of the best hits.
+    let pagination = Extension(Pagination {
+        page: 1,
+        limit,
+        total_pages: 1,
+        max_limit: state.config.pagination.max_limit,
+    });
     let highlights = params.highlights.unwrap_or(true);
+    let response = run_search(&state, visibility, params.q, limit, highlights).await?;
+    Ok((pagination, Json(response)))
+}
 
-    if params.q.trim().is_empty() {
+async fn search_page(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchPageResponse>, AppError> {
+    let limit = state.config.pagination.limit(params.limit);
+    let highlights = params.highlights.unwrap_or(true);
+    let query = params.q.trim().to_lowercase();
+    let words: Vec<&str> = query.split_whitespace().collect();
+    // A term matches when its name contains the query or is one of its words.
+    let matching = |terms: Vec<TermCount>| -> Vec<TermCount> {
+        terms
+            .into_iter()
+            .filter(|t| {
+                let name = t.name.to_lowercase();
+                name.contains(&query) || words.contains(&name.as_str())
+            })
+            .collect()
+    };
+
+    let search = run_search(&state, visibility, params.q, limit, highlights).await?;
+    let store = state.store.read().await;
+    let restricted = visibility.includes_restricted();
+    Ok(Json(SearchPageResponse {
+        search,
+        tags: matching(store.tag_counts(restricted)),
+        categories: matching(store.category_counts(restricted)),
+    }))
+}
+
+/// Searches the public index for `q`, keeping the hits `visibility` allows.
+/// When the index fails, titles and descriptions are matched instead.
+async fn run_search(
+    state: &AppState,
+    visibility: Visibility,
+    q: String,
+    limit: usize,
+    highlights: bool,
+) -> Result<SearchResponse, AppError> {
+    let search_service = enabled_search(state)?;
+    if q.trim().is_empty() {
         return Err(AppError::BadRequest {
-            code: ERR_EMPTY_SEARCH_QUERY,
+            code: ErrorCode::EmptySearchQuery,
             message: "Search query cannot be empty".to_string(),
         });
     }
 
-    match search_service.search(&params.q, limit, highlights).await {
+    // Groups count hits beyond the page, so more are fetched than shown.
+    let candidates = limit.max(state.config.pagination.max_limit);
+    match search_service.search(&q, candidates, highlights).await {
         Ok(results) => {
-            let response = SearchResponse {
-                total_found: results.len(),
-                query: params.q,
-                results,
-            };
-            Ok(Json(response))
+            let results = visible_results(state, visibility, &q, results).await;
+            Ok(search_response(q, results, limit))
         }
         Err(e) => {
             tracing::error!("Search error: {:?}", e);
 
-            let query_lower = params.q.to_lowercase();
-
-            let articles_results: Vec<SearchResult> = {
-                let store = state.store.read().await;
-                store
-                    .query(
-                        |article| {
-                            !article.metadata.draft
-                                && (article.metadata.title.to_lowercase().contains(&query_lower)
-                                    || article
-                                        .metadata
-                                        .description
-                                        .to_lowercase()
-                                        .contains(&query_lower))
-                        },
-                        0,
-                        usize::MAX,
-                    )
-                    .map(|article| SearchResult {
-                        slug: article.slug_with_category(),
-                        title: article.metadata.title.clone(),
-                        description: article.metadata.description.clone(),
-                        score: 1.0,
-                        highlights: None,
-                    })
-                    .collect()
-            };
-
-            let notes_results: Vec<SearchResult> = {
-                let store = state.note_store.read().await;
-                store
-                    .query(
-                        |note| {
-                            !note.metadata.draft
-                                && (note.metadata.title.to_lowercase().contains(&query_lower)
-                                    || note
-                                        .metadata
-                                        .description
-                                        .to_lowercase()
-                                        .contains(&query_lower))
-                        },
-                        0,
-                        usize::MAX,
-                    )
-                    .map(|note| {
-                        let slug = note.slug_with_category();
-                        SearchResult {
-                            slug: format!("notes/{}", slug),
-                            title: note.metadata.title.clone(),
-                            description: note.metadata.description.clone(),
+            let query_lower = q.to_lowercase();
+            let mut combined = Vec::new();
+            for collection in IndexCollection::ALL {
+                let store = state.store_of(collection).read().await;
+                combined.extend(
+                    store
+                        .query(
+                            |entry| {
+                                visibility.allows(&store, entry)
+                                    && (entry.metadata.title.to_lowercase().contains(&query_lower)
+                                        || entry
+                                            .metadata
+                                            .description
+                                            .to_lowercase()
+                                            .contains(&query_lower))
+                            },
+                            0,
+                            usize::MAX,
+                        )
+                        .map(|entry| SearchResult {
+                            slug: collection.slug_of(entry),
+                            title: entry.metadata.title.clone(),
+                            description: entry.metadata.description.clone(),
                             score: 1.0,
                             highlights: None,
-                        }
-                    })
-                    .collect()
-            };
-
-            let mut combined = articles_results;
-            combined.extend(notes_results);
-            let fallback_results: Vec<SearchResult> = combined.into_iter().take(limit).collect();
-
-            let response = SearchResponse {
-                total_found: fallback_results.len(),
-                query: params.q,
-                results: fallback_results,
-            };
-            Ok(Json(response))
+                        }),
+                );
+            }
+            combined.truncate(candidates);
+            Ok(search_response(q, combined, limit))
         }
     }
 }
 
+/// The best `limit` of `results` overall and per collection. `results` is
+/// ordered best first.
+fn search_response(query: String, results: Vec<SearchResult>, limit: usize) -> SearchResponse {
+    let groups = IndexCollection::ALL
+        .into_iter()
+        .map(|collection| {
+            let mut hits = results
+                .iter()
+                .filter(|r| IndexCollection::of(&r.slug) == collection);
+            let count = hits.clone().count();
+            SearchGroup {
+                collection: collection.as_str(),
+                count,
+                hits: hits.by_ref().take(limit).cloned().collect(),
+            }
+        })
+        .collect();
+    let mut results = results;
+    results.truncate(limit);
+    SearchResponse {
+        total_found: results.len(),
+        query,
+        results,
+        groups,
+    }
+}
+
+/// Drops index hits the requester may not see, including entries the
+/// index has not caught up with yet. Note and page hits are indexed as
+/// `notes/{category}/{slug}` and `pages/{category}/{slug}`.
+///
+/// A members-only entry the requester cannot read is only kept when a
+/// word of `query` appears in its title or description, so a hit does not
+/// give away what its body says.
+async fn visible_results(
+    state: &AppState,
+    visibility: Visibility,
+    query: &str,
+    results: Vec<SearchResult>,
+) -> Vec<SearchResult> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let in_metadata = |metadata: &Metadata| {
+        let title = metadata.title.to_lowercase();
+        let description = metadata.description.to_lowercase();
+        words
+            .iter()
+            .any(|word| title.contains(word) || description.contains(word))
+    };
+    let store = state.store.read().await;
+    let notes = state.note_store.read().await;
+    let pages = state.page_store.read().await;
+    let readable = |entry: &Article| visibility.unlocks(entry) || in_metadata(&entry.metadata);
+    results
+        .into_iter()
+        .filter(|result| match IndexCollection::split(&result.slug) {
+            (IndexCollection::Articles, slug) => store
+                .get_by_slug(slug)
+                .is_some_and(|a| visibility.allows(&store, a) && readable(a)),
+            (IndexCollection::Notes, path) => notes
+                .get_by_path(path)
+                .is_some_and(|n| visibility.allows(&notes, n) && readable(n)),
+            (IndexCollection::Pages, path) => pages
+                .get_by_path(path)
+                .is_some_and(|p| visibility.allows(&pages, p) && readable(p)),
+        })
+        .collect()
+}
+
+/// Searches drafts, scheduled and archived entries as well as published
+/// ones, so the author can find their own unpublished work.
+async fn admin_search(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminSearchParams>,
+) -> Result<Json<AdminSearchResponse>, AppError> {
+    let search_service = enabled_search(&state)?;
+    if params.q.trim().is_empty() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::EmptySearchQuery,
+            message: "Search query cannot be empty".to_string(),
+        });
+    }
+    let limit = state.config.pagination.limit(params.limit);
+    let hits = search_service
+        .search_all(&params.q, limit)
+        .map_err(search_failed)?;
+
+    // Hits for entries deleted since their last commit are dropped.
+    let store = state.store.read().await;
+    let notes = state.note_store.read().await;
+    let pages = state.page_store.read().await;
+    let results: Vec<AdminSearchResult> = hits
+        .into_iter()
+        .filter_map(|result| {
+            let entry = match IndexCollection::split(&result.slug) {
+                (IndexCollection::Articles, slug) => store.get_by_slug(slug).filter(|a| !a.deleted),
+                (IndexCollection::Notes, path) => notes.get_by_path(path),
+                (IndexCollection::Pages, path) => pages.get_by_path(path),
+            }?;
+            let status = entry.metadata.status;
+            (!entry.metadata.private).then_some(AdminSearchResult { result, status })
+        })
+        .collect();
+    Ok(Json(AdminSearchResponse {
+        total_found: results.len(),
+        query: params.q,
+        results,
+    }))
+}
+
+/// Title completions in the OpenSearch suggestions format browsers read
+/// when the blog is added as a search engine: the query, then matching
+/// titles, their descriptions and their URLs as parallel arrays.
+async fn opensearch_suggestions(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Query(params): Query<SuggestionParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let search_service = enabled_search(&state)?;
+    let limit = state.config.pagination.limit(params.limit);
+    let suggestions = search_service
+        .autocomplete(&params.q, limit)
+        .map_err(search_failed)?;
+    let suggestions = visible_results(&state, visibility, &params.q, suggestions).await;
+
+    let hostname = state.config.hostname.trim_end_matches('/');
+    let urls: Vec<String> = suggestions
+        .iter()
+        .map(|s| match IndexCollection::of(&s.slug) {
+            IndexCollection::Notes | IndexCollection::Pages => {
+                format!("{}/{}", hostname, s.slug)
+            }
+            IndexCollection::Articles => state.config.article_url(&s.slug),
+        })
+        .collect();
+    let (titles, descriptions): (Vec<String>, Vec<String>) = suggestions
+        .into_iter()
+        .map(|s| (s.title, s.description))
+        .unzip();
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-suggestions+json")],
+        Json(json!([params.q, titles, descriptions, urls])),
+    ))
+}
+
 async fn get_popular_searches(
     State(state): State<Arc<AppState>>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -156,7 +416,7 @@ async fn get_popular_searches(
         .search_service
         .as_ref()
         .ok_or_else(|| AppError::BadRequest {
-            code: ERR_FULLTEXT_DISABLED,
+            code: ErrorCode::FulltextDisabled,
             message: "Full-text search is not enabled".to_string(),
         })?;
 
@@ -173,5 +433,98 @@ async fn trigger_reindex(
     State(state): State<Arc<AppState>>,
 ) -> Result<impl IntoResponse, AppError> {
     reindex_all_content(&state).await;
-    Ok(Json(serde_json::json!({ "message": "Reindex completed" })))
+    Ok(Json(json!({ "message": "Reindex completed" })))
+}
+
+async fn get_consistency(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<IndexConsistency>, AppError> {
+    let search_service = enabled_search(&state)?;
+    let expected = searchable_content(&state).await;
+    let indexed = search_service.indexed_documents().map_err(search_failed)?;
+    Ok(Json(check_consistency(&expected, &indexed)))
+}
+
+/// Reindexes missing and stale entries and deletes orphaned documents,
+/// leaving the rest of the index alone. Returns what was repaired.
+async fn repair_consistency(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<IndexConsistency>, AppError> {
+    let search_service = enabled_search(&state)?;
+    let expected = searchable_content(&state).await;
+    let indexed = search_service.indexed_documents().map_err(search_failed)?;
+    let report = check_consistency(&expected, &indexed);
+    if report.is_consistent() {
+        return Ok(Json(report));
+    }
+
+    // Orphaned drafts are reindexed too, which keeps them out of the public
+    // index but in the author's.
+    let to_index: Vec<_> = expected
+        .into_iter()
+        .filter(|e| {
+            report.missing.contains(&e.slug)
+                || report.stale.contains(&e.slug)
+                || report.orphans.contains(&e.slug)
+        })
+        .collect();
+    search_service
+        .apply_batch(
+            &to_index,
+            &report.orphans,
+            state.config.search_index_heap_size,
+        )
+        .map_err(search_failed)?;
+    tracing::info!(
+        "Repaired search index: {} missing, {} stale, {} orphaned",
+        report.missing.len(),
+        report.stale.len(),
+        report.orphans.len()
+    );
+    Ok(Json(report))
+}
+
+/// The public index on disk as a zip, for [`restore_index`] on another
+/// host.
+async fn snapshot_index(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let search_service = Arc::clone(enabled_search(&state)?);
+    let archive = tokio::task::spawn_blocking(move || search_service.snapshot())
+        .await
+        .map_err(search_failed)?
+        .map_err(search_failed)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"search-index.zip\"",
+            ),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+/// Replaces the public index with an uploaded snapshot. The author's index
+/// is built from the content and stays as it is.
+async fn restore_index(
+    State(state): State<Arc<AppState>>,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let search_service = Arc::clone(enabled_search(&state)?);
+    let documents = tokio::task::spawn_blocking(move || search_service.restore(&body))
+        .await
+        .map_err(search_failed)?
+        .map_err(|e| match e {
+            SearchError::ArchiveError(_) | SearchError::InvalidSnapshot(_) => {
+                AppError::BadRequest {
+                    code: ErrorCode::InvalidSearchSnapshot,
+                    message: e.to_string(),
+                }
+            }
+            e => search_failed(e),
+        })?;
+    state.cache.invalidate_all();
+    tracing::info!("Restored search index with {} documents", documents);
+    Ok(Json(json!({ "documents": documents })))
 }
@@ -0,0 +1,71 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::shortlink::Shortlink;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::shortlink_service::{follow_shortlink, shortlink_for};
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::middleware;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Serialize, Debug)]
+pub struct ShortlinkResponse {
+    #[serde(flatten)]
+    pub shortlink: Shortlink,
+    pub url: String,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/s/{code}", get(follow)).route(
+        "/api/admin/articles/{slug}/shortlink",
+        post(create).route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn create(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<ShortlinkResponse>, AppError> {
+    if state.store.read().await.get_by_slug(&slug).is_none() {
+        return Err(AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        });
+    }
+    let shortlink = shortlink_for(&state.db, &slug).await.map_err(db_error)?;
+    let url = format!(
+        "{}/s/{}",
+        state.config.hostname.trim_end_matches('/'),
+        shortlink.code
+    );
+    Ok(Json(ShortlinkResponse { shortlink, url }))
+}
+
+async fn follow(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Response, AppError> {
+    let slug = follow_shortlink(&state.db, &code)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ShortlinkNotFound,
+            message: format!("Shortlink {} not found", code),
+        })?;
+    Ok((
+        StatusCode::MOVED_PERMANENTLY,
+        [(header::LOCATION, state.config.article_url(&slug))],
+    )
+        .into_response())
+}
@@ -1,8 +1,11 @@
-use crate::handlers::error::{AppError, ERR_INTERNAL_SERVER};
+use crate::handlers::error::{AppError, ErrorCode};
 use crate::server::app::AppState;
+use crate::server::snapshots::Document;
+use crate::server::visibility::Visibility;
+use crate::services::site_time;
 use axum::Router;
 use axum::extract::State;
-use axum::http::header;
+use axum::http::{HeaderMap, header};
 use axum::response::Response;
 use axum::routing::get;
 use std::sync::Arc;
@@ -11,10 +14,11 @@ pub fn create_router() -> Router<Arc<AppState>> {
     Router::new().route("/sitemap.xml", get(get_sitemap))
 }
 
-async fn get_sitemap(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+/// The sitemap of the articles and pages `visibility` allows.
+pub async fn render_sitemap(state: &AppState, visibility: Visibility) -> String {
     let store = state.store.read().await;
     let hostname = state.config.hostname.trim_end_matches('/');
-    let articles = store.query(|a| !a.metadata.draft, 0, usize::MAX);
+    let articles = store.query(|a| visibility.allows(&store, a), 0, usize::MAX);
 
     let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
     xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">");
@@ -24,17 +28,49 @@ async fn get_sitemap(State(state): State<Arc<AppState>>) -> Result<Response, App
             "<url><loc>{}/articles/{}</loc><lastmod>{}</lastmod></url>",
             hostname,
             article.slug,
-            article.updated_at.to_rfc3339()
+            article
+                .updated_at
+                .with_timezone(&site_time::site_timezone())
+                .to_rfc3339()
+        ));
+    }
+
+    drop(store);
+    let store = state.page_store.read().await;
+    for page in store.query(|p| visibility.allows(&store, p), 0, usize::MAX) {
+        xml.push_str(&format!(
+            "<url><loc>{}/pages/{}</loc><lastmod>{}</lastmod></url>",
+            hostname,
+            page.slug_with_category(),
+            page.updated_at
+                .with_timezone(&site_time::site_timezone())
+                .to_rfc3339()
         ));
     }
 
     xml.push_str("</urlset>");
+    xml
+}
+
+/// Served from the snapshot unless the requester may see restricted
+/// articles or the snapshot is behind the content.
+async fn get_sitemap(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    if !visibility.includes_restricted()
+        && let Some(res) = state.snapshots.serve(Document::Sitemap, &headers).await
+    {
+        return Ok(res);
+    }
+    let xml = render_sitemap(&state, visibility).await;
 
     Response::builder()
-        .header(header::CONTENT_TYPE, "application/xml")
+        .header(header::CONTENT_TYPE, Document::Sitemap.content_type())
         .body(axum::body::Body::from(xml))
         .map_err(|_| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
+            code: ErrorCode::InternalServer,
             message: "Failed to build sitemap response".to_string(),
         })
 }
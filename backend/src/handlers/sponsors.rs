@@ -0,0 +1,29 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::sponsor::SponsorList;
+use crate::server::app::AppState;
+use crate::services::sponsor_service::current_sponsors;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/sponsors", get(get_sponsors))
+}
+
+/// The supporters for a thanks section, GitHub sponsors first.
+async fn get_sponsors(State(state): State<Arc<AppState>>) -> Result<Json<SponsorList>, AppError> {
+    if state.config.sponsors.is_none() {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::SponsorsDisabled,
+            message: "Sponsors are not configured".to_string(),
+        });
+    }
+    current_sponsors(&state)
+        .await
+        .map(Json)
+        .map_err(|e| AppError::BadGateway {
+            code: ErrorCode::SponsorsUnavailable,
+            message: e,
+        })
+}
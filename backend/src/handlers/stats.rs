@@ -0,0 +1,122 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::stats::{Calendar, CalendarDay, PopularArticle, PublicStats};
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::analytics_service::{K_ANONYMITY, coarsen, total_views, views_since};
+use crate::services::site_time;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const MOST_READ_LIMIT: usize = 5;
+
+#[derive(Deserialize, Debug)]
+pub struct CalendarParams {
+    year: Option<i32>,
+    /// Also sum the words published each day. Loads article bodies.
+    #[serde(default)]
+    words: bool,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/stats/calendar", get(get_calendar))
+        .route("/api/stats/public", get(get_public_stats))
+}
+
+async fn get_calendar(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Query(params): Query<CalendarParams>,
+) -> Result<Json<Calendar>, AppError> {
+    let year = params.year.unwrap_or_else(|| site_time::today().year());
+    if !(1..=9999).contains(&year) {
+        return Err(AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: format!("Invalid year: {}", year),
+        });
+    }
+
+    let store = state.store.read().await;
+    let mut days: BTreeMap<NaiveDate, CalendarDay> = BTreeMap::new();
+    for article in store.query(
+        |a| visibility.allows(&store, a) && a.metadata.day().year() == year,
+        0,
+        usize::MAX,
+    ) {
+        let date = article.metadata.day();
+        let day = days.entry(date).or_insert_with(|| CalendarDay {
+            date,
+            count: 0,
+            words: params.words.then_some(0),
+        });
+        day.count += 1;
+        // Members-only bodies are not counted for non-members.
+        if let Some(words) = day.words.as_mut()
+            && visibility.unlocks(article)
+        {
+            match store.load_content_for(article) {
+                Ok(content) => *words += content.split_whitespace().count(),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to load content for article {}: {:?}",
+                        article.slug,
+                        e
+                    )
+                }
+            }
+        }
+    }
+
+    Ok(Json(Calendar {
+        year,
+        days: days.into_values().collect(),
+    }))
+}
+
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Readership for display on the site. Restricted articles are left out
+/// whoever asks, since the response is the same for every visitor.
+async fn get_public_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PublicStats>, AppError> {
+    let today = Utc::now().date_naive();
+    let month_start = today.with_day(1).unwrap_or(today);
+    let total = total_views(&state.db).await.map_err(db_error)?;
+    let counts = views_since(&state.db, month_start)
+        .await
+        .map_err(db_error)?;
+
+    let store = state.store.read().await;
+    let most_read = counts
+        .into_iter()
+        .filter(|(_, views)| *views >= K_ANONYMITY)
+        .filter_map(|(slug, views)| {
+            let article = store
+                .get_by_slug(&slug)
+                .filter(|a| Visibility::public().allows(&store, a))?;
+            Some(PopularArticle {
+                slug,
+                title: article.metadata.title.clone(),
+                views: coarsen(views),
+            })
+        })
+        .take(MOST_READ_LIMIT)
+        .collect();
+
+    Ok(Json(PublicStats {
+        total_views: coarsen(total),
+        month: month_start.format("%Y-%m").to_string(),
+        most_read,
+    }))
+}
@@ -0,0 +1,207 @@
+use crate::handlers::articles::{members_only, rewrite_article};
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::comments::rate_limit;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::suggestion::Suggestion;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::server::visibility::Visibility;
+use crate::services::notification_service::notify_author;
+use crate::services::suggestion_service::{
+    add_suggestion, apply_suggestion, pending_suggestion, pending_suggestions, resolve_suggestion,
+};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+const MAX_TEXT_LENGTH: usize = 2_000;
+const MAX_NOTE_LENGTH: usize = 500;
+
+/// A correction to the article body: `original` is the exact text to
+/// replace and must appear once in it.
+#[derive(Deserialize, Debug)]
+pub struct CreateSuggestionRequest {
+    pub original: String,
+    pub replacement: String,
+    pub note: Option<String>,
+}
+
+impl Validate for CreateSuggestionRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("original", &self.original);
+        errors.max_chars("original", &self.original, MAX_TEXT_LENGTH);
+        errors.max_chars("replacement", &self.replacement, MAX_TEXT_LENGTH);
+        if self.original == self.replacement {
+            errors.add("replacement", "must differ from the original text");
+        }
+        if let Some(note) = &self.note {
+            errors.max_chars("note", note, MAX_NOTE_LENGTH);
+        }
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/articles/{slug}/suggestions",
+            with_body_limit(post(suggest), BodyGroup::Comments)
+                .route_layer(middleware::from_fn(rate_limit)),
+        )
+        .route(
+            "/api/admin/suggestions",
+            get(list_pending).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/suggestions/{id}/accept",
+            post(accept).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/suggestions/{id}/dismiss",
+            post(dismiss).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn mismatch(slug: &str) -> AppError {
+    AppError::Conflict {
+        code: ErrorCode::SuggestionMismatch,
+        message: format!(
+            "The text to replace does not appear exactly once in article {}",
+            slug
+        ),
+    }
+}
+
+fn suggestion_not_found(id: i64) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::SuggestionNotFound,
+        message: format!("Pending suggestion {} not found", id),
+    }
+}
+
+async fn suggest(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    jar: SignedJar,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<CreateSuggestionRequest>,
+) -> Result<Json<Suggestion>, AppError> {
+    let user = session_user(&jar)?;
+    {
+        let store = state.store.read().await;
+        let article = store
+            .get_by_slug(&slug)
+            .filter(|a| visibility.allows(&store, a))
+            .ok_or_else(|| AppError::NotFound {
+                code: ErrorCode::ArticleNotFound,
+                message: format!("Article with slug {} not found", slug),
+            })?;
+        // Whether the text matches would tell non-members about the body.
+        if !visibility.unlocks(article) {
+            return Err(members_only(article));
+        }
+        let body = store.load_source_for(article).map_err(internal)?;
+        if apply_suggestion(&body, &payload.original, &payload.replacement).is_none() {
+            return Err(mismatch(&slug));
+        }
+    }
+
+    let note = payload
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+    let suggestion = add_suggestion(
+        &state.db,
+        &slug,
+        user.github_id,
+        &user.github_login,
+        &payload.original,
+        &payload.replacement,
+        note,
+    )
+    .await
+    .map_err(internal)?;
+
+    notify_author(
+        &state,
+        &json!({
+            "event": "suggestion",
+            "url": state.config.article_url(&slug),
+            "suggestion": suggestion,
+        }),
+    )
+    .await;
+    Ok(Json(suggestion))
+}
+
+async fn list_pending(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Suggestion>>, AppError> {
+    Ok(Json(
+        pending_suggestions(&state.db).await.map_err(internal)?,
+    ))
+}
+
+/// Applies a suggestion to the article body and saves it as a new version.
+/// Fails without changes when the article no longer contains the original
+/// text once.
+async fn accept(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let suggestion = pending_suggestion(&state.db, id)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| suggestion_not_found(id))?;
+    let slug = &suggestion.article_slug;
+
+    let (article, body) = {
+        let store = state.store.read().await;
+        let article = store.get_by_slug(slug).ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })?;
+        let body = store.load_source_for(article).map_err(internal)?;
+        (article.clone(), body)
+    };
+    let patched = apply_suggestion(&body, &suggestion.original, &suggestion.replacement)
+        .ok_or_else(|| mismatch(slug))?;
+
+    let message = format!(
+        "Applied suggestion {} from {}",
+        suggestion.id, suggestion.author_login
+    );
+    rewrite_article(&state, &article, &patched, Some(&message)).await?;
+    resolve_suggestion(&state.db, id, "accepted")
+        .await
+        .map_err(internal)?;
+    Ok(Json(json!({ "id": id, "message": "Suggestion applied" })))
+}
+
+async fn dismiss(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    if !resolve_suggestion(&state.db, id, "dismissed")
+        .await
+        .map_err(internal)?
+    {
+        return Err(suggestion_not_found(id));
+    }
+    Ok(Json(json!({ "id": id, "message": "Suggestion dismissed" })))
+}
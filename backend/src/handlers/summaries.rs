@@ -0,0 +1,161 @@
+use crate::handlers::articles::rewrite_article;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::summarizer::summarize;
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const MAX_DESCRIPTION_LENGTH: usize = 300;
+
+/// Without `description`, a summary is generated and returned for review.
+/// Sending it back as `description` confirms it and writes it to the front
+/// matter.
+#[derive(Deserialize, Debug)]
+pub struct SummarizeRequest {
+    pub description: Option<String>,
+    /// Replace an existing description.
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl Validate for SummarizeRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if let Some(description) = &self.description {
+            errors.require("description", description);
+            errors.max_chars("description", description, MAX_DESCRIPTION_LENGTH);
+        }
+        errors.into_result()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SummaryResponse {
+    pub slug: String,
+    pub description: String,
+    pub saved: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BackfillResponse {
+    /// The articles written, with the description each was given.
+    pub backfilled: Vec<SummaryResponse>,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/articles/{slug}/summarize",
+            post(summarize_article).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/articles/descriptions/backfill",
+            post(backfill_descriptions).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+async fn summarize_article(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<SummarizeRequest>,
+) -> Result<Json<SummaryResponse>, AppError> {
+    let summarizer = state
+        .config
+        .summarizer
+        .as_ref()
+        .ok_or_else(|| AppError::BadRequest {
+            code: ErrorCode::SummarizerDisabled,
+            message: "Summaries are not enabled".to_string(),
+        })?;
+
+    let (mut article, content) = {
+        let store = state.store.read().await;
+        let article = store.get_by_slug(&slug).ok_or_else(|| AppError::NotFound {
+            code: ErrorCode::ArticleNotFound,
+            message: format!("Article with slug {} not found", slug),
+        })?;
+        let content = store.load_source_for(article).map_err(internal)?;
+        (article.clone(), content)
+    };
+
+    if !payload.force
+        && !article.metadata.description_generated
+        && !article.metadata.description.trim().is_empty()
+    {
+        return Err(AppError::Conflict {
+            code: ErrorCode::DescriptionExists,
+            message: format!("Article {} already has a description", slug),
+        });
+    }
+
+    let Some(description) = payload.description else {
+        let description = summarize(&state.http, summarizer, &article.metadata.title, &content)
+            .await
+            .map_err(|message| AppError::BadGateway {
+                code: ErrorCode::SummarizerFailed,
+                message,
+            })?;
+        return Ok(Json(SummaryResponse {
+            slug,
+            description,
+            saved: false,
+        }));
+    };
+
+    article.metadata.description = description.trim().to_string();
+    rewrite_article(&state, &article, &content, None).await?;
+
+    Ok(Json(SummaryResponse {
+        slug,
+        description: article.metadata.description,
+        saved: true,
+    }))
+}
+
+/// Writes the descriptions derived from first paragraphs into the front
+/// matter of the articles relying on them, each as a new version.
+async fn backfill_descriptions(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BackfillResponse>, AppError> {
+    let articles = {
+        let store = state.store.read().await;
+        store
+            .query(|a| a.metadata.description_generated, 0, usize::MAX)
+            .map(|article| {
+                let content = store.load_source_for(article).map_err(internal)?;
+                Ok((article.clone(), content))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?
+    };
+
+    let mut backfilled = Vec::new();
+    for (mut article, content) in articles {
+        article.metadata.description_generated = false;
+        rewrite_article(
+            &state,
+            &article,
+            &content,
+            Some("Add a description from the first paragraph"),
+        )
+        .await?;
+        backfilled.push(SummaryResponse {
+            slug: article.slug,
+            description: article.metadata.description,
+            saved: true,
+        });
+    }
+    Ok(Json(BackfillResponse { backfilled }))
+}
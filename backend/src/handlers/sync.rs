@@ -0,0 +1,125 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::sync::{SyncManifest, UploadReport, UploadedFile};
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::server::body_limit::{BodyGroup, with_body_limit};
+use crate::services::notification_service::{SIGNATURE_HEADER, sign};
+use crate::services::sync_service::{SYNC_SECRET, SyncRoots, apply_upload, change_feed, manifest};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, header};
+use axum::middleware;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::env;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+
+#[derive(Deserialize, Debug)]
+pub struct ChangesParams {
+    /// Milliseconds since the epoch; everything when absent.
+    #[serde(default)]
+    pub since: u64,
+}
+
+/// Most files one upload may carry.
+const MAX_UPLOAD_FILES: usize = 100;
+
+#[derive(Deserialize, Debug)]
+pub struct UploadRequest {
+    pub files: Vec<UploadedFile>,
+}
+
+impl Validate for UploadRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.files.is_empty() {
+            errors.add("files", "must not be empty");
+        } else if self.files.len() > MAX_UPLOAD_FILES {
+            errors.add(
+                "files",
+                format!("must not hold more than {} files", MAX_UPLOAD_FILES),
+            );
+        }
+        errors.into_result()
+    }
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/sync/changes", get(changes))
+        .route(
+            "/api/admin/sync/manifest",
+            get(sync_manifest).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/sync/upload",
+            with_body_limit(post(upload), BodyGroup::Articles)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+/// Hash and version of every article, for editing clients to find what
+/// changed on either side since they last synced.
+async fn sync_manifest(State(state): State<Arc<AppState>>) -> Result<Json<SyncManifest>, AppError> {
+    let store = state.store.read().await;
+    manifest(&store, &state.data_dir)
+        .map(Json)
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })
+}
+
+/// Writes the files an editing client changed, each only if the server
+/// copy is still the one the client started from.
+async fn upload(
+    State(state): State<Arc<AppState>>,
+    ValidJson(payload): ValidJson<UploadRequest>,
+) -> Json<UploadReport> {
+    Json(apply_upload(&state, payload.files).await)
+}
+
+/// Change feed for replicas. It holds drafts and private notes as they are
+/// on disk, so it is only served to requests bearing the sync secret, and
+/// the body is signed with the same secret.
+async fn changes(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ChangesParams>,
+) -> Result<Response, AppError> {
+    let secret = env::var(SYNC_SECRET).map_err(|_| AppError::BadRequest {
+        code: ErrorCode::SyncDisabled,
+        message: format!("Content sync is disabled; set {} to enable it", SYNC_SECRET),
+    })?;
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| token.as_bytes().ct_eq(secret.as_bytes()).into());
+    if !authorized {
+        return Err(AppError::Unauthorized {
+            code: ErrorCode::Unauthorized,
+            message: "The change feed requires the sync secret".to_string(),
+        });
+    }
+
+    let internal = |e: String| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e,
+    };
+    let roots = SyncRoots::of(&state).await;
+    let feed = change_feed(&roots, params.since).map_err(|e| internal(e.to_string()))?;
+    let body = serde_json::to_vec(&feed).map_err(|e| internal(e.to_string()))?;
+    let signature = format!("sha256={}", sign(&secret, &body));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::HeaderName::from_static(SIGNATURE_HEADER), signature),
+        ],
+        body,
+    )
+        .into_response())
+}
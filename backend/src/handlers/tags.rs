@@ -1,17 +1,71 @@
-use crate::handlers::error::AppError;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::Article;
+use crate::models::taxonomy::TermDetail;
 use crate::server::app::AppState;
-use axum::extract::State;
+use crate::server::visibility::Visibility;
+use crate::services::taxonomy::{TermKind, term_detail};
+use axum::extract::{Path, State};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Json, Router};
 use std::sync::Arc;
 
 pub fn create_router() -> Router<Arc<AppState>> {
-    Router::new().route("/api/tags", get(get_all_tags))
+    Router::new()
+        .route("/api/tags", get(get_all_tags))
+        .route("/api/tags/counts", get(get_tag_counts))
+        .route("/api/tags/{tag}", get(get_tag))
 }
 
-async fn get_all_tags(State(state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+pub(crate) async fn get_all_tags(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Result<impl IntoResponse, AppError> {
     let store = state.store.read().await;
-    let tags = store.get_all_tags();
+    let tags = store.get_all_tags(visibility.includes_restricted());
     Ok(Json(tags))
 }
+
+async fn get_tag_counts(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.store.read().await;
+    Ok(Json(store.tag_counts(visibility.includes_restricted())))
+}
+
+/// Landing page data for a tag, from its `taxonomy/tags/` page and the
+/// articles tagged with it.
+async fn get_tag(
+    State(state): State<Arc<AppState>>,
+    visibility: Visibility,
+    Path(tag): Path<String>,
+) -> Result<Json<TermDetail>, AppError> {
+    let store = state.store.read().await;
+    let articles: Vec<&Article> = store
+        .query(
+            |a| visibility.allows(&store, a) && a.metadata.tags.contains(&tag),
+            0,
+            usize::MAX,
+        )
+        .collect();
+    if articles.is_empty() {
+        return Err(AppError::NotFound {
+            code: ErrorCode::TagNotFound,
+            message: format!("Tag {} not found", tag),
+        });
+    }
+    let page_size = state.config.pagination.default_limit;
+    let detail = term_detail(
+        &state.taxonomy_dir,
+        TermKind::Tag,
+        &tag,
+        &articles,
+        page_size,
+    )
+    .map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    })?;
+    Ok(Json(detail))
+}
@@ -0,0 +1,150 @@
+use crate::handlers::articles::rewrite_article;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::article::Article;
+use crate::server::app::AppState;
+use crate::server::auth::require_admin;
+use crate::services::service::ArticleStore;
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Links `slug` as the `lang` translation of the article in the path.
+#[derive(Deserialize, Debug)]
+pub struct TranslationRequest {
+    pub slug: String,
+    pub lang: String,
+    /// Language of the source article, if its front matter does not say.
+    pub source_lang: Option<String>,
+}
+
+fn is_language_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= 35
+        && tag
+            .split('-')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+impl Validate for TranslationRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        errors.require("slug", &self.slug);
+        if !is_language_tag(&self.lang) {
+            errors.add("lang", "must be a language tag such as `en` or `zh-CN`");
+        }
+        if let Some(source_lang) = &self.source_lang
+            && !is_language_tag(source_lang)
+        {
+            errors.add(
+                "source_lang",
+                "must be a language tag such as `en` or `zh-CN`",
+            );
+        }
+        errors.into_result()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TranslationLink {
+    pub lang: String,
+    pub slug: String,
+    pub title: String,
+}
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/admin/articles/{slug}/translations",
+        get(list_translations)
+            .post(add_translation)
+            .route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+fn not_found(slug: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::ArticleNotFound,
+        message: format!("Article with slug {} not found", slug),
+    }
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn links(store: &ArticleStore, article: &Article) -> Vec<TranslationLink> {
+    article
+        .metadata
+        .translations
+        .iter()
+        .filter_map(|(lang, slug)| {
+            store.get_by_slug(slug).map(|t| TranslationLink {
+                lang: lang.clone(),
+                slug: slug.clone(),
+                title: t.metadata.title.clone(),
+            })
+        })
+        .collect()
+}
+
+async fn list_translations(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<TranslationLink>>, AppError> {
+    let store = state.store.read().await;
+    let article = store.get_by_slug(&slug).ok_or_else(|| not_found(&slug))?;
+    Ok(Json(links(&store, article)))
+}
+
+async fn add_translation(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    ValidJson(payload): ValidJson<TranslationRequest>,
+) -> Result<Json<Vec<TranslationLink>>, AppError> {
+    if payload.slug == slug {
+        let mut errors = ValidationErrors::default();
+        errors.add("slug", "must differ from the source article");
+        return Err(AppError::Validation(errors));
+    }
+
+    let (mut source, source_content, mut target, target_content) = {
+        let store = state.store.read().await;
+        let source = store.get_by_slug(&slug).ok_or_else(|| not_found(&slug))?;
+        let target = store
+            .get_by_slug(&payload.slug)
+            .ok_or_else(|| not_found(&payload.slug))?;
+        (
+            source.clone(),
+            store.load_source_for(source).map_err(internal)?,
+            target.clone(),
+            store.load_source_for(target).map_err(internal)?,
+        )
+    };
+
+    let source_lang = payload.source_lang.or_else(|| source.metadata.lang.clone());
+    source
+        .metadata
+        .translations
+        .insert(payload.lang.clone(), target.slug.clone());
+    if let Some(source_lang) = source_lang {
+        source.metadata.lang = Some(source_lang.clone());
+        target
+            .metadata
+            .translations
+            .insert(source_lang, source.slug.clone());
+    }
+    target.metadata.lang = Some(payload.lang);
+
+    rewrite_article(&state, &source, &source_content, None).await?;
+    rewrite_article(&state, &target, &target_content, None).await?;
+
+    let store = state.store.read().await;
+    let source = store.get_by_slug(&slug).ok_or_else(|| not_found(&slug))?;
+    Ok(Json(links(&store, source)))
+}
@@ -0,0 +1,128 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::article::ArticleContent;
+use crate::models::trash::{TrashItem, TrashPreview};
+use crate::server::app::{AppState, IndexJob, record_content_change};
+use crate::server::auth::require_admin;
+use crate::services::service::ArticleStore;
+use crate::services::trash_service::{list_trash, purge_trashed, restore_trashed, trashed};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use std::io::ErrorKind;
+use std::path::Path as StdPath;
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/api/admin/trash",
+            get(list).route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/trash/{id}",
+            get(preview)
+                .delete(purge)
+                .route_layer(middleware::from_fn(require_admin)),
+        )
+        .route(
+            "/api/admin/trash/{id}/restore",
+            post(restore).route_layer(middleware::from_fn(require_admin)),
+        )
+}
+
+fn internal(e: impl ToString) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+fn not_in_trash(id: &str) -> AppError {
+    AppError::NotFound {
+        code: ErrorCode::ArticleNotFound,
+        message: format!("Nothing is in the trash as {}", id),
+    }
+}
+
+async fn list(State(state): State<Arc<AppState>>) -> Result<Json<Vec<TrashItem>>, AppError> {
+    list_trash(&state.data_dir).map(Json).map_err(internal)
+}
+
+async fn preview(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<TrashPreview>, AppError> {
+    trashed(&state.data_dir, &id)
+        .map_err(internal)?
+        .map(Json)
+        .ok_or_else(|| not_in_trash(&id))
+}
+
+/// Loads the article put back at `path` into `store` and indexes it
+/// again. Callers clear the response cache once done.
+pub(crate) fn reinstate(
+    state: &AppState,
+    store: &mut ArticleStore,
+    path: &StdPath,
+) -> Result<(), AppError> {
+    let path = path.to_string_lossy();
+    store.update_single_article(&path).map_err(internal)?;
+    if let Some(tx) = &state.index_tx
+        && let Some(article) = store.query(|a| a.file_path == path, 0, 1).next()
+    {
+        let content = store.load_content_for(article).map_err(internal)?;
+        let _ = tx.send(IndexJob::for_article(ArticleContent {
+            slug: article.slug_with_category(),
+            metadata: article.metadata.clone(),
+            content,
+            edit_url: None,
+        }));
+    }
+    Ok(())
+}
+
+/// Puts a trashed article back where it was and indexes it again.
+async fn restore(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let Some(preview) = trashed(&state.data_dir, &id).map_err(internal)? else {
+        return Err(not_in_trash(&id));
+    };
+    let mut store = state.store.write().await;
+    let path = match restore_trashed(&state.data_dir, store.root(), &id) {
+        Ok(Some(path)) => path,
+        Ok(None) => return Err(not_in_trash(&id)),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            return Err(AppError::Conflict {
+                code: ErrorCode::ArticleExists,
+                message: format!(
+                    "Another article has taken the place of {}",
+                    preview.item.slug
+                ),
+            });
+        }
+        Err(e) => return Err(internal(e)),
+    };
+    reinstate(&state, &mut store, &path)?;
+    drop(store);
+    state.cache.invalidate_all();
+    record_content_change(&state).await;
+    Ok(Json(json!({
+        "id": id,
+        "slug": preview.item.slug,
+        "message": "Article restored",
+    })))
+}
+
+async fn purge(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    if !purge_trashed(&state.data_dir, &id).map_err(internal)? {
+        return Err(not_in_trash(&id));
+    }
+    Ok(Json(json!({ "id": id, "message": "Article purged" })))
+}
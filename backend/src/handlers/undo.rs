@@ -0,0 +1,87 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::trash::reinstate;
+use crate::server::app::{AppState, record_content_change};
+use crate::server::auth::require_admin;
+use crate::services::trash_service::restore_trashed;
+use crate::services::undo_service::{Table, Undo, delete_undoably, undo};
+use axum::extract::{Path, State};
+use axum::middleware;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/admin/undo/{token}",
+        post(restore).route_layer(middleware::from_fn(require_admin)),
+    )
+}
+
+fn internal(e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
+}
+
+/// Deletes an item for an admin handler and answers with the token that
+/// undoes it, or with `not_found` when the item does not exist. See
+/// [`delete_undoably`] for `targets`.
+pub async fn delete_with_undo(
+    state: &AppState,
+    targets: &[(&'static Table, i64)],
+    not_found: impl FnOnce() -> AppError,
+    message: &str,
+) -> Result<Json<Value>, AppError> {
+    let id = targets[0].1;
+    let token = delete_undoably(&state.db, targets, state.config.undo_window_secs)
+        .await
+        .map_err(internal)?
+        .ok_or_else(not_found)?;
+    Ok(Json(json!({
+        "id": id,
+        "message": message,
+        "undo_token": token,
+        "undo_expires_in": state.config.undo_window_secs,
+    })))
+}
+
+/// Puts back what the deletion that issued `token` removed.
+async fn restore(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let mut store = state.store.write().await;
+    let root = store.root().to_path_buf();
+    let restored = undo(&state.db, &token, |id| {
+        restore_trashed(&state.data_dir, &root, id)
+    })
+    .await
+    .map_err(internal)?;
+    let articles = match restored {
+        Undo::Restored(articles) => articles,
+        Undo::NotFound => {
+            return Err(AppError::NotFound {
+                code: ErrorCode::UndoTokenNotFound,
+                message: "Nothing to undo for this token".to_string(),
+            });
+        }
+        Undo::Conflict => {
+            return Err(AppError::Conflict {
+                code: ErrorCode::UndoConflict,
+                message: "The deleted item clashes with one created since".to_string(),
+            });
+        }
+    };
+    for path in &articles {
+        reinstate(&state, &mut store, path)?;
+    }
+    drop(store);
+    state.redirects.reload(&state.db).await.map_err(internal)?;
+    state.cache.invalidate_all();
+    if !articles.is_empty() {
+        record_content_change(&state).await;
+    }
+    Ok(Json(json!({ "message": "Deletion undone" })))
+}
@@ -0,0 +1,61 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::models::link_preview::LinkPreview;
+use crate::server::app::AppState;
+use crate::server::auth::require_authenticated;
+use crate::services::link_preview::{LinkPreviewError, fetch_link_preview};
+use axum::extract::{Query, State};
+use axum::middleware;
+use axum::routing::get;
+use axum::{Json, Router};
+use reqwest::Url;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route(
+        "/api/meta/unfurl",
+        get(unfurl).route_layer(middleware::from_fn(require_authenticated)),
+    )
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UnfurlParams {
+    pub url: String,
+}
+
+/// The card of an external page, for link previews in the editor and in
+/// comments. Pages are fetched server-side under the same address checks
+/// as link posts, and cached by URL, so a popular link costs one fetch
+/// however many clients show it.
+async fn unfurl(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<UnfurlParams>,
+) -> Result<Json<LinkPreview>, AppError> {
+    let mut url = Url::parse(params.url.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| AppError::BadRequest {
+            code: ErrorCode::BadRequest,
+            message: "url must be an http or https URL".to_string(),
+        })?;
+    url.set_fragment(None);
+
+    let http = &state.config.http;
+    state
+        .link_previews
+        .try_get_with(url.to_string(), fetch_link_preview(http, url.as_str()))
+        .await
+        .map(Json)
+        .map_err(|e| match *e {
+            LinkPreviewError::UnsupportedUrl | LinkPreviewError::BlockedAddress(_) => {
+                AppError::BadRequest {
+                    code: ErrorCode::BadRequest,
+                    message: e.to_string(),
+                }
+            }
+            _ => AppError::BadGateway {
+                code: ErrorCode::LinkPreviewFailed,
+                message: e.to_string(),
+            },
+        })
+}
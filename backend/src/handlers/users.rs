@@ -1,11 +1,20 @@
-use crate::handlers::error::{AppError, ERR_BAD_REQUEST, ERR_INTERNAL_SERVER};
+use crate::handlers::auth::{SignedJar, session_user};
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::handlers::validation::{ValidJson, Validate, ValidationErrors};
+use crate::models::account::{AccountDeletion, AccountExport};
 use crate::models::user::User;
 use crate::models::user_preferences::UserPreferences;
 use crate::server::app::AppState;
+use crate::services::account_service::{delete_account, export_account};
+use crate::services::field_crypto::FieldCipher;
+use crate::services::github_service::{self, GitHubError};
+use crate::services::user_preferences_service::{get_preferences, save_preferences};
 use axum::extract::State;
-use axum::routing::put;
+use axum::http::HeaderMap;
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::routing::{delete, get, put};
 use axum::{Json, Router};
-use reqwest::header::USER_AGENT;
+use axum_extra::extract::cookie::Cookie;
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -22,24 +31,26 @@ pub struct GitHubProfile {
 
 /// Fetches the GitHub profile of the currently authenticated user using
 /// the provided OAuth access token.
-pub async fn fetch_github_profile(token: &str) -> Result<GitHubProfile, AppError> {
-    let profile: GitHubProfile = reqwest::Client::new()
-        .get("https://api.github.com/user")
-        .header(USER_AGENT, "scribe")
-        .bearer_auth(token)
-        .send()
+pub async fn fetch_github_profile(
+    state: &AppState,
+    token: &str,
+) -> Result<GitHubProfile, AppError> {
+    let body = github_service::get(state, "/user", Some(token))
         .await
-        .map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
-            message: e.to_string(),
-        })?
-        .json()
-        .await
-        .map_err(|e| AppError::InternalServerError {
-            code: ERR_INTERNAL_SERVER,
-            message: e.to_string(),
+        .map_err(|e| match e {
+            GitHubError::RateLimited { .. } => AppError::ServiceUnavailable {
+                code: ErrorCode::GitHubRateLimited,
+                message: e.to_string(),
+            },
+            GitHubError::Request(_) => AppError::InternalServerError {
+                code: ErrorCode::InternalServer,
+                message: e.to_string(),
+            },
         })?;
-    Ok(profile)
+    serde_json::from_str(&body).map_err(|e| AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    })
 }
 
 /// Applies GitHub profile data to the given user, respecting any
@@ -81,88 +92,146 @@ pub struct UpdateProfileRequest {
 }
 
 const ALLOWED_THEMES: &[&str] = &["light", "dark"];
-const ALLOWED_LANGUAGES: &[&str] = &["en", "zh"];
-
-fn validate_profile(input: &UpdateProfileRequest) -> Result<(), AppError> {
-    if let Some(name) = &input.display_name {
-        if name.len() > 50 {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "display_name too long".to_string(),
-            });
-        }
-        if !name
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
-        {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "display_name contains invalid characters".to_string(),
-            });
-        }
-    }
+pub(crate) const ALLOWED_LANGUAGES: &[&str] = &["en", "zh"];
+
+/// The allowed language the `Accept-Language` header ranks highest, matched
+/// by primary subtag so `zh-CN` picks `zh`.
+pub(crate) fn negotiate_language(headers: &HeaderMap) -> Option<String> {
+    let header = headers.get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+    let mut ranked: Vec<(f32, &str)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let tag = params.next()?.trim();
+            let q = params
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse().ok())?;
+            Some((q, tag))
+        })
+        .filter(|(q, _)| *q > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.into_iter().find_map(|(_, tag)| {
+        let primary = tag.split('-').next()?;
+        ALLOWED_LANGUAGES
+            .iter()
+            .find(|lang| lang.eq_ignore_ascii_case(primary))
+            .map(|lang| lang.to_string())
+    })
+}
 
-    if let Some(bio) = &input.bio {
-        if bio.len() > 160 {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "bio too long".to_string(),
-            });
+impl Validate for UpdateProfileRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+
+        if let Some(name) = &self.display_name {
+            errors.max_chars("display_name", name, 50);
+            if !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+            {
+                errors.add("display_name", "contains invalid characters");
+            }
         }
-    }
 
-    if let Some(website) = &input.website {
-        if website.len() > 200 {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "website too long".to_string(),
-            });
+        if let Some(bio) = &self.bio {
+            errors.max_chars("bio", bio, 160);
+            if FieldCipher::is_sealed(bio) {
+                errors.add("bio", "must not start with the encrypted field prefix");
+            }
         }
-        if reqwest::Url::parse(website).is_err() {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "invalid website".to_string(),
-            });
+
+        if let Some(website) = &self.website {
+            errors.max_chars("website", website, 200);
+            if FieldCipher::is_sealed(website) {
+                errors.add("website", "must not start with the encrypted field prefix");
+            }
+            if reqwest::Url::parse(website).is_err() {
+                errors.add("website", "must be a valid URL");
+            }
         }
-    }
 
-    if let Some(theme) = &input.theme {
-        if !ALLOWED_THEMES.contains(&theme.as_str()) {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "invalid theme".to_string(),
-            });
+        if let Some(theme) = &self.theme {
+            errors.one_of("theme", theme, ALLOWED_THEMES);
         }
-    }
 
-    if let Some(language) = &input.language {
-        if !ALLOWED_LANGUAGES.contains(&language.as_str()) {
-            return Err(AppError::BadRequest {
-                code: ERR_BAD_REQUEST,
-                message: "invalid language".to_string(),
-            });
+        if let Some(language) = &self.language {
+            errors.one_of("language", language, ALLOWED_LANGUAGES);
         }
+
+        errors.into_result()
     }
+}
 
-    Ok(())
+fn db_error(e: sqlx::Error) -> AppError {
+    AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: e.to_string(),
+    }
 }
 
+/// Saves the profile overrides and preferences of the signed-in user,
+/// replacing the ones saved before. A saved avatar is kept.
 async fn update_profile(
-    State(_state): State<Arc<AppState>>,
-    Json(payload): Json<UpdateProfileRequest>,
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+    ValidJson(payload): ValidJson<UpdateProfileRequest>,
 ) -> Result<Json<UserPreferences>, AppError> {
-    validate_profile(&payload)?;
+    let user = session_user(&jar)?;
+    let saved = get_preferences(&state.db, state.field_cipher.as_ref(), user.github_id)
+        .await
+        .map_err(db_error)?;
     let prefs = UserPreferences {
         display_name: payload.display_name,
         bio: payload.bio,
-        avatar: None,
+        avatar: saved.and_then(|p| p.avatar),
         website: payload.website,
         theme: payload.theme,
         language: payload.language,
     };
+    save_preferences(
+        &state.db,
+        state.field_cipher.as_ref(),
+        user.github_id,
+        &prefs,
+    )
+    .await
+    .map_err(db_error)?;
     Ok(Json(prefs))
 }
 
+/// Everything stored about the signed-in user, as one JSON document.
+async fn export_me(
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+) -> Result<Json<AccountExport>, AppError> {
+    let user = session_user(&jar)?;
+    let export = export_account(&state.db, state.field_cipher.as_ref(), user)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(export))
+}
+
+/// Deletes the personal data of the signed-in user and signs them out.
+async fn delete_me(
+    State(state): State<Arc<AppState>>,
+    jar: SignedJar,
+) -> Result<(SignedJar, Json<AccountDeletion>), AppError> {
+    let user = session_user(&jar)?;
+    let deletion = delete_account(&state.db, user.github_id)
+        .await
+        .map_err(db_error)?;
+    tracing::info!(
+        "Deleted the personal data of GitHub user {}",
+        user.github_id
+    );
+    let jar = jar.remove(Cookie::build("user_session").path("/"));
+    Ok((jar, Json(deletion)))
+}
+
 pub fn create_router() -> Router<Arc<AppState>> {
-    Router::new().route("/api/users/me/profile", put(update_profile))
+    Router::new()
+        .route("/api/users/me", delete(delete_me))
+        .route("/api/users/me/export", get(export_me))
+        .route("/api/users/me/profile", put(update_profile))
 }
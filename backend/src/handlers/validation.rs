@@ -0,0 +1,159 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use axum::Json;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+
+/// Field-level validation failures, keyed by field name. Serialized as
+/// `{"field": ["message", ...]}` in the error response's `details`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct ValidationErrors(BTreeMap<&'static str, Vec<String>>);
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.entry(field).or_default().push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+
+    pub fn require(&mut self, field: &'static str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "must not be empty");
+        }
+    }
+
+    pub fn max_chars(&mut self, field: &'static str, value: &str, max: usize) {
+        if value.chars().count() > max {
+            self.add(field, format!("must be at most {} characters", max));
+        }
+    }
+
+    pub fn one_of(&mut self, field: &'static str, value: &str, allowed: &[&str]) {
+        if !allowed.contains(&value) {
+            self.add(field, format!("must be one of: {}", allowed.join(", ")));
+        }
+    }
+}
+
+/// Implemented by request bodies to check their fields before a handler
+/// runs. Checks that need application state (e.g. whether a slug exists)
+/// stay in the handlers.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// `Json` extractor that also runs the payload's [`Validate`] impl, rejecting
+/// invalid bodies with a 422 and a map of field errors.
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge {
+                    code: ErrorCode::PayloadTooLarge,
+                    message: rejection.body_text(),
+                },
+                _ => AppError::BadRequest {
+                    code: ErrorCode::BadRequest,
+                    message: rejection.body_text(),
+                },
+            })?;
+        value.validate().map_err(AppError::Validation)?;
+        Ok(ValidJson(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use serde::Deserialize;
+    use serde_json::{Value, json};
+
+    #[derive(Deserialize)]
+    struct Payload {
+        name: String,
+    }
+
+    impl Validate for Payload {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::default();
+            errors.require("name", &self.name);
+            errors.max_chars("name", &self.name, 3);
+            errors.into_result()
+        }
+    }
+
+    async fn extract(body: &str) -> Result<ValidJson<Payload>, AppError> {
+        let req = Request::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        ValidJson::<Payload>::from_request(req, &()).await
+    }
+
+    #[test]
+    fn collects_messages_per_field() {
+        let mut errors = ValidationErrors::default();
+        errors.require("title", "  ");
+        errors.max_chars("title", "abcdef", 5);
+        errors.one_of("theme", "blue", &["light", "dark"]);
+        assert_eq!(
+            serde_json::to_value(&errors).unwrap(),
+            json!({
+                "theme": ["must be one of: light, dark"],
+                "title": ["must not be empty", "must be at most 5 characters"]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn valid_bodies_pass_through() {
+        let ValidJson(payload) = extract(r#"{"name": "abc"}"#).await.unwrap();
+        assert_eq!(payload.name, "abc");
+    }
+
+    #[tokio::test]
+    async fn invalid_bodies_are_rejected_with_422() {
+        let err = match extract(r#"{"name": "abcd"}"#).await {
+            Err(err) => err,
+            Ok(_) => panic!("expected validation failure"),
+        };
+        let res = err.into_response();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], ErrorCode::ValidationFailed.as_str());
+        assert_eq!(
+            body["details"],
+            json!({ "name": ["must be at most 3 characters"] })
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_a_bad_request() {
+        assert!(matches!(
+            extract("{").await,
+            Err(AppError::BadRequest { .. })
+        ));
+    }
+}
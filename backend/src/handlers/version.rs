@@ -0,0 +1,17 @@
+use crate::models::build_info::BuildInfo;
+use crate::server::app::AppState;
+use crate::services::build_info::build_info;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+pub fn create_router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/version", get(get_version))
+}
+
+/// Exactly what is deployed: version, commit, build time, enabled features
+/// and content counts.
+async fn get_version(State(state): State<Arc<AppState>>) -> Json<BuildInfo> {
+    Json(build_info(&state).await)
+}
@@ -0,0 +1,11 @@
+pub mod config;
+pub mod db;
+pub mod embed;
+pub mod handlers;
+pub mod models;
+pub mod seed;
+pub mod server;
+pub mod services;
+pub mod setup;
+
+pub use embed::{ScribeApp, ScribeAppBuilder};
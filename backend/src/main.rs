@@ -1,21 +1,69 @@
-use crate::config::{initialize_config, initialize_logging};
-use crate::server::app::{create_app_state, start_file_watcher, start_server};
-use std::sync::Arc;
-
-mod config;
-mod handlers;
-mod models;
-mod server;
-mod services;
-mod db;
+use scribe_backend::ScribeApp;
+use scribe_backend::config::{
+    ARTICLE_DIR, NOTES_DIR, initialize_config, initialize_logging, load_config,
+};
+use scribe_backend::seed::{SeedOptions, seed};
+use scribe_backend::server::app::start_server;
+use scribe_backend::services::bootstrap::init;
+use scribe_backend::services::build_info::log_startup_banner;
+use scribe_backend::setup::{GITHUB_TOKEN_URL, SetupOptions, run_setup};
+use std::path::Path;
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `--init` sets up a new site in the working directory and exits.
+    if std::env::args().skip(1).any(|arg| arg == "--init") {
+        let created = init(Path::new("."), ARTICLE_DIR, NOTES_DIR)?;
+        if created.is_empty() {
+            println!("Nothing to do: config.toml and the content directories exist");
+        }
+        for path in created {
+            println!("Created {}", path.display());
+        }
+        println!("Run `scribe setup` to create the secrets, then start the server");
+        return Ok(());
+    }
+    // `seed` writes made-up content to measure the server against and exits.
+    if std::env::args().nth(1).as_deref() == Some("seed") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        let options = SeedOptions::parse(&args, ARTICLE_DIR, NOTES_DIR)?;
+        seed(&options)?;
+        println!(
+            "Wrote {} articles to {} and {} notes to {}",
+            options.articles,
+            options.article_dir.display(),
+            options.notes,
+            options.notes_dir.display()
+        );
+        return Ok(());
+    }
+    // `setup` asks for the secrets, writes them to `.env` and exits.
+    if std::env::args().nth(1).as_deref() == Some("setup") {
+        let config = load_config().ok();
+        let options = SetupOptions {
+            env_path: Path::new(".env"),
+            redirect_url: config.as_ref().map(|c| c.github_redirect_url.as_str()),
+            token_url: GITHUB_TOKEN_URL,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+        };
+        run_setup(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            options,
+        )
+        .await?;
+        return Ok(());
+    }
+
     let config = initialize_config()?;
     initialize_logging(&config);
-    let _db = db::init_db("sqlite://comments.db").await?;
-    let app_state = create_app_state(&config).await?;
-    start_file_watcher(Arc::clone(&app_state));
-    start_server(app_state, &config).await?;
+    let app = ScribeApp::builder(config.clone()).build().await?;
+    log_startup_banner(&app.state()).await;
+    app.spawn_background_tasks();
+    start_server(app.router(), &config.server).await?;
+    app.shutdown().await;
     Ok(())
 }
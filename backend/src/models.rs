@@ -1,4 +1,49 @@
 pub mod article;
+pub mod guestbook;
+pub mod job;
+pub mod poll;
+pub mod reaction;
 pub mod user;
 pub mod version;
 pub mod user_preferences;
+pub mod stats;
+pub mod goal;
+pub mod seo;
+pub mod duplicate;
+pub mod redirect;
+pub mod shortlink;
+pub mod analytics;
+pub mod comment;
+pub mod search_consistency;
+pub mod store_stats;
+pub mod taxonomy;
+pub mod suggestion;
+pub mod api_key;
+pub mod sync;
+pub mod audit;
+pub mod github;
+pub mod preview;
+pub mod lock;
+pub mod merge;
+pub mod saved_search;
+pub mod moderation;
+pub mod preview_branch;
+pub mod home;
+pub mod navigation;
+pub mod account;
+pub mod asset;
+pub mod link_preview;
+pub mod fediverse;
+pub mod evergreen;
+pub mod changes;
+pub mod collection;
+pub mod glossary;
+pub mod citation;
+pub mod snippet;
+pub mod download;
+pub mod access_code;
+pub mod sponsor;
+pub mod diagnostics;
+pub mod build_info;
+pub mod content_diff;
+pub mod trash;
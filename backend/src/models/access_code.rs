@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+/// A code that makes whoever redeems it a member, for `members_only`
+/// articles. Only a hash of the code is stored.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct AccessCode {
+    pub id: i64,
+    /// What the code was issued for, e.g. a newsletter or a workshop.
+    pub name: String,
+    /// `None` when the code can be redeemed any number of times.
+    pub max_redemptions: Option<i64>,
+    pub redemptions: i64,
+    pub revoked: bool,
+    pub created_at: String,
+}
+
+/// A newly issued code. `code` is shown here once and cannot be recovered.
+#[derive(Serialize, Debug)]
+pub struct IssuedAccessCode {
+    #[serde(flatten)]
+    pub access_code: AccessCode,
+    pub code: String,
+}
@@ -0,0 +1,38 @@
+use crate::models::comment::Comment;
+use crate::models::guestbook::GuestbookEntry;
+use crate::models::reaction::ReactionRecord;
+use crate::models::suggestion::Suggestion;
+use crate::models::user::User;
+use crate::models::user_preferences::UserPreferences;
+use serde::Serialize;
+
+/// A vote the user cast in a poll while signed in.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct PollVote {
+    pub poll_id: String,
+    pub option_index: i64,
+    pub created_at: String,
+}
+
+/// Everything stored about a user, for `GET /api/users/me/export`.
+#[derive(Serialize, Debug)]
+pub struct AccountExport {
+    pub user: User,
+    pub preferences: Option<UserPreferences>,
+    pub comments: Vec<Comment>,
+    pub reactions: Vec<ReactionRecord>,
+    pub guestbook_entries: Vec<GuestbookEntry>,
+    pub suggestions: Vec<Suggestion>,
+    pub poll_votes: Vec<PollVote>,
+}
+
+/// What deleting an account changed, by kind of record.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct AccountDeletion {
+    pub comments_anonymized: u64,
+    pub suggestions_anonymized: u64,
+    pub poll_votes_anonymized: u64,
+    pub reactions_deleted: u64,
+    pub guestbook_entries_deleted: u64,
+    pub preferences_deleted: bool,
+}
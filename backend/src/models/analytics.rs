@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Views from one combination of referring site and campaign parameters.
+/// `None` fields mean the view had no such value, e.g. direct traffic.
+#[derive(Serialize, Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ReferrerCount {
+    pub referrer: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub views: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReferrerReport {
+    pub slug: Option<String>,
+    pub total_views: i64,
+    pub sources: Vec<ReferrerCount>,
+}
+
+/// One stored view, as exported and imported. `viewed_on` is a
+/// `YYYY-MM-DD` date.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ViewRecord {
+    pub slug: String,
+    pub viewed_on: String,
+    #[serde(default)]
+    pub referrer_host: Option<String>,
+    #[serde(default)]
+    pub utm_source: Option<String>,
+    #[serde(default)]
+    pub utm_medium: Option<String>,
+    #[serde(default)]
+    pub utm_campaign: Option<String>,
+}
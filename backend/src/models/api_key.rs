@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+/// A read-only API token issued to a third-party app. Only a hash of the
+/// token is stored.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub request_count: i64,
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+    pub created_at: String,
+    /// Overrides the configured daily quota when set; 0 lifts the limit.
+    pub daily_quota: Option<i64>,
+}
+
+/// A newly issued key. `token` is shown here once and cannot be recovered.
+#[derive(Serialize, Debug)]
+pub struct IssuedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub token: String,
+}
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct DailyUsage {
+    pub day: String,
+    pub requests: i64,
+}
+
+/// Requests made with a key per UTC day, newest first, against the quota
+/// that applies to it.
+#[derive(Serialize, Debug)]
+pub struct ApiKeyUsage {
+    pub id: i64,
+    pub name: String,
+    /// `None` when the key is not limited.
+    pub daily_quota: Option<u64>,
+    pub today: i64,
+    pub days: Vec<DailyUsage>,
+}
@@ -1,20 +1,281 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use crate::models::asset::ImageInfo;
+use crate::models::citation::Reference;
+use crate::models::poll::{PollDefinition, PollResults};
+use crate::models::reaction::ReactionCount;
+use crate::services::site_time::{deserialize_date, local_day};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::time::SystemTime;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Where an article is in the editorial workflow. Only published articles
+/// are shown to readers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArticleStatus {
+    Draft,
+    InReview,
+    /// Waiting to be published by the `publish_scheduled` job once its
+    /// `date` has passed.
+    Scheduled,
+    #[default]
+    Published,
+    Archived,
+}
+
+impl ArticleStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArticleStatus::Draft => "draft",
+            ArticleStatus::InReview => "in_review",
+            ArticleStatus::Scheduled => "scheduled",
+            ArticleStatus::Published => "published",
+            ArticleStatus::Archived => "archived",
+        }
+    }
+
+    /// Whether an article may move from this status to `next`. Published
+    /// articles can only be taken back to drafts or archived, and archived
+    /// ones only brought back as drafts or republished.
+    pub fn can_become(self, next: ArticleStatus) -> bool {
+        use ArticleStatus::*;
+        match (self, next) {
+            _ if self == next => true,
+            (Draft | InReview | Scheduled, _) => true,
+            (Published, Draft | Archived) => true,
+            (Archived, Draft | Published) => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for ArticleStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether an article takes new comments.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentStatus {
+    #[default]
+    Open,
+    Closed,
+}
+
+impl CommentStatus {
+    pub fn is_open(&self) -> bool {
+        *self == CommentStatus::Open
+    }
+}
+
+/// Reads `status`, or the `draft` flag it replaced: `draft: true` is a
+/// draft and `draft: false` published.
+fn deserialize_status<'de, D>(deserializer: D) -> Result<ArticleStatus, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StatusOrFlag {
+        Status(ArticleStatus),
+        Flag(bool),
+    }
+    Ok(match StatusOrFlag::deserialize(deserializer)? {
+        StatusOrFlag::Status(status) => status,
+        StatusOrFlag::Flag(true) => ArticleStatus::Draft,
+        StatusOrFlag::Flag(false) => ArticleStatus::Published,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub struct Metadata {
     pub title: String,
     pub author: String,
+    /// Publication time. Values without an offset are read in the site
+    /// timezone.
+    #[serde(deserialize_with = "deserialize_date")]
     pub date: DateTime<Utc>,
+    /// Day shown to readers and used for archives in place of the day of
+    /// `date`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_date: Option<NaiveDate>,
     #[serde(default)]
     pub tags: Vec<String>,
-    pub description: String,
     #[serde(default)]
-    pub draft: bool,
+    pub description: String,
+    /// Set when the front matter has no `description` and it was derived
+    /// from the first paragraph of the body. Never written to front matter.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub description_generated: bool,
+    /// Written as `status`; the older `draft: true` is read as a draft.
+    #[serde(default, alias = "draft", deserialize_with = "deserialize_status")]
+    pub status: ArticleStatus,
     pub last_updated: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll: Option<PollDefinition>,
+    /// Language of the article, e.g. `en` or `zh-CN`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Slugs of translated variants, keyed by language.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, String>,
+    /// Private notes have their body encrypted at rest and are only served
+    /// to the author.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub private: bool,
+    /// Extra headers sent whenever the article is served, e.g.
+    /// `http_headers: {X-Robots-Tag: noindex}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub http_headers: BTreeMap<String, String>,
+    /// `comments: closed` stops new comments; existing ones stay visible.
+    #[serde(default, skip_serializing_if = "CommentStatus::is_open")]
+    pub comments: CommentStatus,
+    /// Places a page in the site navigation, e.g. `menu: {order: 1}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub menu: Option<MenuEntry>,
+    /// Makes the article an episode of the podcast feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioAttachment>,
+    /// Photos of a gallery post, in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gallery: Vec<GalleryImage>,
+    /// Makes the article a link post about the page at `link`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<LinkPost>,
+    /// Copies of the article on other sites, by service, e.g. the Mastodon
+    /// status it was cross-posted as.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub syndication: BTreeMap<String, String>,
+    /// Works the article cites as `[@id]`. A BibTeX file named like the
+    /// article adds more.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Reference>,
+    /// Files offered for download with the article, e.g. the PDF of a
+    /// guide.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downloads: Vec<ArticleDownload>,
+    /// Only members see the body; others get the metadata.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub members_only: bool,
+}
+
+/// A photo of a gallery post. `src` is written like an image in the body:
+/// a path next to the article, the `url` of an uploaded asset, or a URL.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct GalleryImage {
+    pub src: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+}
+
+/// The page a link post is about. Front matter may give just the URL,
+/// `link: https://...`, or a map with a preview `image` too.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct LinkPost {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for LinkPost {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum UrlOrFull {
+            Url(String),
+            Full {
+                url: String,
+                #[serde(default)]
+                image: Option<String>,
+            },
+        }
+        Ok(match UrlOrFull::deserialize(deserializer)? {
+            UrlOrFull::Url(url) => LinkPost { url, image: None },
+            UrlOrFull::Full { url, image } => LinkPost { url, image },
+        })
+    }
+}
+
+/// A file readers can download from an article page at
+/// `/api/articles/{slug}/downloads/{name}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct ArticleDownload {
+    /// File name the download is saved as, e.g. `guide.pdf`.
+    pub name: String,
+    /// The `url` of an uploaded asset.
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Only signed-in readers may download the file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub login_required: bool,
+}
+
+/// The audio of a podcast episode.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct AudioAttachment {
+    /// URL of the audio file, or a path on the site such as the `url` of
+    /// an uploaded asset.
+    pub file: String,
+    /// Length in seconds. `h:mm:ss` and `mm:ss` are read as well.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duration: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode: Option<u32>,
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SecondsOrClock {
+        Seconds(u32),
+        Clock(String),
+    }
+    match Option::<SecondsOrClock>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(SecondsOrClock::Seconds(seconds)) => Ok(Some(seconds)),
+        Some(SecondsOrClock::Clock(clock)) => {
+            let parts: Option<Vec<u32>> = clock.split(':').map(|p| p.trim().parse().ok()).collect();
+            match parts {
+                Some(parts) if (1..=3).contains(&parts.len()) => {
+                    Ok(Some(parts.iter().fold(0, |total, part| total * 60 + part)))
+                }
+                _ => Err(serde::de::Error::custom(format!(
+                    "invalid duration '{}', expected seconds or h:mm:ss",
+                    clock
+                ))),
+            }
+        }
+    }
+}
+
+/// Where a page goes in the navigation. Entries are sorted by `order`, then
+/// by label.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct MenuEntry {
+    #[serde(default)]
+    pub order: i32,
+    /// Label in the menu; the title by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -27,37 +288,137 @@ pub struct Article {
     pub file_path: String,
     #[serde(skip_serializing)]
     pub last_modified: SystemTime,
+    /// Paths of the partials the body includes, and of the category
+    /// defaults files that apply to it.
+    #[serde(skip_serializing)]
+    pub includes: Vec<String>,
     #[serde(skip_serializing, default)]
     pub deleted: bool,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ArticleContent {
     pub slug: String,
     pub metadata: Metadata,
     pub content: String,
+    /// Link to edit the source file, when a repository is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_url: Option<String>,
 }
 
+/// Single-article response: the article itself plus dynamic data stored
+/// outside the content tree.
 #[derive(Serialize, Debug, Clone)]
+pub struct ArticleDetail {
+    #[serde(flatten)]
+    pub article: ArticleContent,
+    pub reactions: Vec<ReactionCount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll: Option<PollResults>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// The article is `members_only` and the requester not a member, so
+    /// `content` is empty.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// Size and placeholder of each local image and uploaded asset the
+    /// content shows, by source as written in the Markdown.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub images: BTreeMap<String, ImageInfo>,
+}
+
+/// Single-note response, marked when the note was archived.
+#[derive(Serialize, Debug, Clone)]
+pub struct NoteDetail {
+    #[serde(flatten)]
+    pub note: ArticleContent,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+}
+
+/// The parts of an article page that change without its content being
+/// edited, for pages to refresh while the full response stays cached.
+/// `views` is floored like the public statistics.
+#[derive(Serialize, Debug, Clone)]
+pub struct ArticleMeta {
+    pub slug: String,
+    pub comment_count: i64,
+    pub reactions: Vec<ReactionCount>,
+    pub views: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 pub struct ArticleTeaser {
     pub slug: String,
     pub metadata: Metadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_url: Option<String>,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum ArticleRepresentation {
     Full(ArticleContent),
     Teaser(ArticleTeaser),
 }
 
-#[derive(Serialize, Debug)]
+/// A tag or category with the number of published articles using it.
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct TermCount {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
 pub struct PaginatedArticles<T> {
     pub articles: Vec<T>,
     pub total_pages: usize,
     pub current_page: usize,
 }
 
+impl Metadata {
+    /// Calendar day the article is filed under: `display_date` when set,
+    /// otherwise the day of `date` in the site timezone.
+    pub fn day(&self) -> NaiveDate {
+        self.display_date.unwrap_or_else(|| local_day(self.date))
+    }
+
+    /// Whether the entry is public content: published and not a private
+    /// note. What a given request may see is decided by
+    /// [`Visibility`](crate::server::visibility::Visibility).
+    pub fn is_published(&self) -> bool {
+        self.status == ArticleStatus::Published && !self.private
+    }
+
+    /// Whether the entry was archived: still reachable by its slug, but left
+    /// out of listings, feeds and search.
+    pub fn is_archived(&self) -> bool {
+        self.status == ArticleStatus::Archived && !self.private
+    }
+
+    /// The metadata as front matter holds it: a generated description is
+    /// left out, so it is derived afresh when the body changes.
+    pub fn for_front_matter(&self) -> Cow<'_, Metadata> {
+        if !self.description_generated {
+            return Cow::Borrowed(self);
+        }
+        Cow::Owned(Metadata {
+            description: String::new(),
+            description_generated: false,
+            ..self.clone()
+        })
+    }
+
+    /// Value of one of `http_headers`, whatever case its name is written in.
+    pub fn http_header(&self, name: &str) -> Option<&str> {
+        self.http_headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
 impl Article {
     pub fn slug_with_category(&self) -> String {
         if let Some(cat) = &self.metadata.category {
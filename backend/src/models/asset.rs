@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+/// An uploaded file, named after the SHA-256 of its content.
+#[derive(Serialize, Debug, Clone)]
+pub struct StoredAsset {
+    pub name: String,
+    pub hash: String,
+    pub size: u64,
+    pub content_type: &'static str,
+    pub url: String,
+    /// The same content had been uploaded before; nothing new was written.
+    pub duplicate: bool,
+    /// Set for raster images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageInfo>,
+}
+
+/// What a page needs to reserve space for an image before it loads: its
+/// size in pixels and a [BlurHash](https://blurha.sh) placeholder.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub blurhash: Option<String>,
+}
+
+/// A stored asset whose content no longer hashes to its name.
+#[derive(Serialize, Debug, Clone)]
+pub struct AssetMismatch {
+    pub name: String,
+    pub expected_hash: String,
+    pub actual_hash: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct AssetVerification {
+    pub checked: usize,
+    pub mismatches: Vec<AssetMismatch>,
+}
@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+/// Something the server did on the author's behalf, such as telling a
+/// search engine about a new article, and how it went.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,
+    /// What the action was aimed at, e.g. the endpoint that was pinged.
+    pub target: String,
+    pub succeeded: bool,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Optional parts of the server that are switched on in this instance.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EnabledFeatures {
+    /// Full-text search is configured and its index opened.
+    pub search: bool,
+    pub comments: bool,
+    pub read_only: bool,
+    /// `NOTES_ENCRYPTION_KEY` is set.
+    pub private_notes: bool,
+}
+
+/// Published entries in each store.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ContentCounts {
+    pub articles: usize,
+    pub notes: usize,
+    pub pages: usize,
+}
+
+/// What is deployed, as served by `GET /api/version`.
+#[derive(Serialize, Debug, Clone)]
+pub struct BuildInfo {
+    pub version: String,
+    /// `unknown` when the build had no git checkout.
+    pub git_commit: String,
+    pub built_at: Option<DateTime<Utc>>,
+    pub features: EnabledFeatures,
+    pub content: ContentCounts,
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// Article and note slugs, as their endpoints take them.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedSlugs {
+    pub articles: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// What anonymous visitors see differently since a marker.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContentChanges {
+    /// The `since` of the next request; also sent as the `ETag`.
+    pub marker: String,
+    /// Entries added or edited, including ones that became public.
+    pub changed: ChangedSlugs,
+    /// Entries deleted, or no longer public.
+    pub removed: ChangedSlugs,
+}
@@ -0,0 +1,31 @@
+use crate::models::article::ArticleTeaser;
+use serde::{Deserialize, Serialize};
+
+/// A reading list as written in `collections.yaml`: article slugs in the
+/// order they should be read.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Collection {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub articles: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CollectionSummary {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    /// Articles of the list the requester may see.
+    pub count: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CollectionDetail {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub articles: Vec<ArticleTeaser>,
+}
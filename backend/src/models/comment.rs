@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Comment {
+    pub id: i64,
+    pub article_slug: String,
+    pub author_github_id: i64,
+    pub content: String,
+    pub created_at: String,
+    /// Display name of an imported author without a GitHub account, whose
+    /// `author_github_id` is then 0.
+    pub author_name: Option<String>,
+}
+
+/// A comment as read from an export file. Other columns, such as `id`, are
+/// ignored; `created_at` defaults to the time of import.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ImportedComment {
+    pub article_slug: String,
+    pub author_github_id: i64,
+    pub content: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub author_name: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Rows identical to a comment already stored.
+    pub skipped: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DisqusImportSummary {
+    #[serde(flatten)]
+    pub summary: ImportSummary,
+    /// Posts Disqus marked deleted or spam, or without a thread.
+    pub ignored: usize,
+    /// Thread URLs that match no article; their posts are not imported.
+    pub unmatched_threads: Vec<String>,
+}
+
+/// A validated comment ready to be stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewComment {
+    pub article_slug: String,
+    pub author_github_id: i64,
+    pub content: String,
+    pub created_at: String,
+    pub author_name: Option<String>,
+}
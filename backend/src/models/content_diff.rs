@@ -0,0 +1,45 @@
+use crate::models::article::ArticleStatus;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// An article dated within the window.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AddedArticle {
+    pub slug: String,
+    pub title: String,
+    pub status: ArticleStatus,
+    pub date: DateTime<Utc>,
+}
+
+/// An older article saved through the API within the window.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EditedArticle {
+    pub slug: String,
+    pub title: String,
+    /// Versions saved within the window.
+    pub edits: usize,
+    /// Lines of the body added and removed by those edits.
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub last_edited_at: DateTime<Utc>,
+    /// Change messages given with the edits, oldest first.
+    pub messages: Vec<String>,
+}
+
+/// An article whose file was removed within the window.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeletedArticle {
+    pub slug: String,
+    pub title: Option<String>,
+    pub deleted_at: String,
+}
+
+/// What changed on the site between two points in time.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ContentDiff {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub added: Vec<AddedArticle>,
+    pub edited: Vec<EditedArticle>,
+    pub deleted: Vec<DeletedArticle>,
+}
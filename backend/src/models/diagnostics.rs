@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Worth a look, but the server works.
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DiagnosticCheck {
+    /// e.g. `disk_space:articles` or `database`.
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// How a file watcher is doing, as reported by `/readyz` and the
+/// diagnostics.
+#[derive(Serialize, Debug, Clone)]
+pub struct WatcherHealth {
+    pub running: bool,
+    /// Times its supervisor started it again after a failure.
+    pub restarts: u32,
+    /// Why it last failed, if it ever did.
+    pub last_error: Option<String>,
+    /// When it last started or stopped.
+    pub since: DateTime<Utc>,
+}
+
+/// Whether the server can take traffic, answered by `/readyz`.
+#[derive(Serialize, Debug, Clone)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: bool,
+    /// By what each watches. Hot reload is broken while one is stopped.
+    pub watchers: BTreeMap<&'static str, WatcherHealth>,
+}
+
+/// The outcome of the self-tests run by `/api/admin/diagnostics`.
+#[derive(Serialize, Debug, Clone)]
+pub struct Diagnostics {
+    /// No check failed; warnings do not count.
+    pub healthy: bool,
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: i64,
+    pub checks: Vec<DiagnosticCheck>,
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// A download of an article, as listed by
+/// `GET /api/articles/{slug}/downloads`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DownloadListing {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Where to fetch the file; each request there is counted.
+    pub url: String,
+    pub content_type: &'static str,
+    /// `None` when the asset is missing.
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub login_required: bool,
+    /// How often the file was downloaded.
+    pub downloads: i64,
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// Two articles whose bodies overlap enough to look like copies.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicatePair {
+    pub first: String,
+    pub second: String,
+    /// Estimated Jaccard similarity of the two bodies, from 0 to 1.
+    pub similarity: f64,
+}
+
+/// An existing article whose title is nearly identical to another.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SimilarArticle {
+    pub slug: String,
+    pub title: String,
+    /// Similarity of the two titles, from 0 to 1.
+    pub similarity: f64,
+}
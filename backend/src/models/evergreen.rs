@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+/// An older article the `suggest_evergreen_posts` job picked to share
+/// again, with its `views` over the lookback period.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct EvergreenReminder {
+    pub id: i64,
+    pub article_slug: String,
+    pub views: i64,
+    /// `pending`, `approved` or `dismissed`.
+    pub status: String,
+    pub created_at: String,
+    pub decided_at: Option<String>,
+}
@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+/// A public reply to the Mastodon post of an article. `content` is the
+/// reply's HTML, sanitized like comments.
+#[derive(Serialize, Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct FediverseReply {
+    pub id: String,
+    pub url: String,
+    pub in_reply_to_id: Option<String>,
+    pub author_acct: String,
+    pub author_name: String,
+    pub author_url: String,
+    pub author_avatar: Option<String>,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// The replies of an article's Mastodon thread, oldest first, as of
+/// `fetched_at`. Both are empty for articles without a Mastodon post.
+#[derive(Serialize, Debug)]
+pub struct FediverseReplies {
+    pub post_url: Option<String>,
+    pub fetched_at: Option<String>,
+    pub replies: Vec<FediverseReply>,
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// GitHub's REST API quota as of the last response, from its
+/// `x-ratelimit-*` headers. Fields are `None` until GitHub has been asked.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct GitHubRateLimitStatus {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<DateTime<Utc>>,
+}
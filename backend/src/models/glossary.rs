@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Front matter of a definition under `glossary/`. The term defaults to
+/// the file name.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GlossaryMetadata {
+    pub term: Option<String>,
+    /// Other spellings that link to the same definition, e.g. plurals.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GlossaryEntry {
+    /// File name without `.md`; the anchor terms link to.
+    pub slug: String,
+    pub term: String,
+    pub aliases: Vec<String>,
+    /// Markdown body of the definition.
+    pub definition: String,
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalKind {
+    WordsPerWeek,
+    PostsPerMonth,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub kind: GoalKind,
+    pub target: usize,
+    /// First day of the current week (Monday) or month, in UTC.
+    pub period_start: NaiveDate,
+    pub current: usize,
+    /// Progress towards the target in the current period; may exceed 100.
+    pub percent: usize,
+    /// Consecutive periods that met the target, up to the current one. The
+    /// current period only counts once it is met, but not meeting it yet
+    /// does not break the streak.
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct GuestbookEntry {
+    pub id: i64,
+    pub author_github_id: i64,
+    pub author_login: String,
+    pub content: String,
+    pub approved: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PaginatedGuestbook {
+    pub entries: Vec<GuestbookEntry>,
+    pub total_pages: usize,
+    pub current_page: usize,
+}
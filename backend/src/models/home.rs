@@ -0,0 +1,20 @@
+use crate::models::article::{ArticleTeaser, TermCount};
+use crate::models::comment::Comment;
+use serde::Serialize;
+
+/// Everything the home page shows, in the order of the `home` sections in
+/// the config.
+#[derive(Serialize, Debug)]
+pub struct HomePage {
+    pub sections: Vec<HomeSectionData>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HomeSectionData {
+    Featured { articles: Vec<ArticleTeaser> },
+    Latest { articles: Vec<ArticleTeaser> },
+    PinnedNotes { notes: Vec<ArticleTeaser> },
+    PopularTags { tags: Vec<TermCount> },
+    RecentComments { comments: Vec<Comment> },
+}
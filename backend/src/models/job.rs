@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+/// A delivery waiting in (or given up on by) the persistent job queue.
+/// `next_retry_at` is a Unix timestamp in seconds.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct QueuedJob {
+    pub id: i64,
+    pub job_type: String,
+    pub payload: String,
+    pub attempts: i64,
+    pub next_retry_at: i64,
+    pub last_error: Option<String>,
+    pub dead: bool,
+    pub created_at: String,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// What a page says about itself through its Open Graph and Twitter card
+/// tags. `url` is the page's canonical URL, or the one it was fetched
+/// from, and `site_name` falls back to its host.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkPreview {
+    pub url: String,
+    pub site_name: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+}
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+/// An editing session's claim on an article, kept alive by heartbeats
+/// until `locked_until`.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ArticleLock {
+    pub article_slug: String,
+    /// The editing session holding the lock, as it named itself.
+    pub locked_by: String,
+    pub locked_until: String,
+    pub locked_at: String,
+}
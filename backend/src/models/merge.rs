@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+/// A region both sides changed differently, with the text each side has
+/// there.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// Words before the region, for locating it.
+    pub context: String,
+    pub base: String,
+    /// The client's edit.
+    pub yours: String,
+    /// What the article holds now.
+    pub current: String,
+}
+
+/// The outcome of merging a client's edit into an article changed since
+/// they loaded it. `content` is only set when every change merged cleanly.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergeResult {
+    pub merged: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<MergeConflict>,
+}
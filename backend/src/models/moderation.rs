@@ -0,0 +1,13 @@
+use serde::Serialize;
+
+/// A word, or with `regex` a regular expression, that submissions must not
+/// contain. `action` is `reject` to refuse them or `hold` to queue them for
+/// moderation.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ModerationRule {
+    pub id: i64,
+    pub pattern: String,
+    pub regex: bool,
+    pub action: String,
+    pub created_at: String,
+}
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+/// An entry of the site menu. `external` is set for links to other sites,
+/// which the frontend may open in a new tab.
+#[derive(Serialize, Debug, Clone)]
+pub struct NavLink {
+    pub title: String,
+    pub url: String,
+    pub external: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<NavLink>,
+}
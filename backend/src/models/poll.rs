@@ -0,0 +1,35 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Poll declared in an article's front matter. When `id` is omitted the
+/// article slug is used.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+pub struct PollDefinition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Poll {
+    pub id: String,
+    pub question: String,
+    pub options: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub article_slug: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PollOptionResult {
+    pub option: String,
+    pub votes: i64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PollResults {
+    pub id: String,
+    pub question: String,
+    pub options: Vec<PollOptionResult>,
+    pub total_votes: i64,
+}
@@ -0,0 +1,45 @@
+use crate::models::article::ArticleContent;
+use serde::Serialize;
+
+/// A link that lets reviewers read an unpublished article and comment on
+/// it until `expires_at`. Only a hash of the token is stored.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct PreviewLink {
+    pub id: i64,
+    pub article_slug: String,
+    /// Who the link was shared with, as noted by the author.
+    pub reviewer: Option<String>,
+    pub expires_at: String,
+    pub revoked: bool,
+    pub created_at: String,
+}
+
+/// A newly issued link. `token` is shown here once and cannot be recovered.
+#[derive(Serialize, Debug)]
+pub struct IssuedPreviewLink {
+    #[serde(flatten)]
+    pub link: PreviewLink,
+    pub token: String,
+}
+
+/// A comment left through a preview link. Review comments are kept apart
+/// from public comments and only shown to the author and to reviewers.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct ReviewComment {
+    pub id: i64,
+    pub preview_id: i64,
+    pub article_slug: String,
+    pub author_name: String,
+    /// Sanitized HTML rendered from the submitted Markdown.
+    pub content: String,
+    pub created_at: String,
+}
+
+/// What a reviewer sees: the article as it stands and the review so far.
+#[derive(Serialize, Debug)]
+pub struct Preview {
+    #[serde(flatten)]
+    pub article: ArticleContent,
+    pub expires_at: String,
+    pub comments: Vec<ReviewComment>,
+}
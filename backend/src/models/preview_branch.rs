@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A branch checked out for review. `added` and `removed` compare it with
+/// the live content by slug, with notes under `notes/`.
+#[derive(Serialize, Debug, Clone)]
+pub struct PreviewBranchSummary {
+    pub branch: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub articles: usize,
+    pub notes: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: i64,
+}
+
+/// One stored reaction, as exported and imported. An empty `created_at`
+/// defaults to the time of import.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ReactionRecord {
+    pub article_slug: String,
+    pub user_github_id: i64,
+    pub emoji: String,
+    #[serde(default)]
+    pub created_at: String,
+}
@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Redirect {
+    pub id: i64,
+    pub from_path: String,
+    pub to_url: String,
+    /// 301 or 308.
+    pub status: u16,
+    pub created_at: String,
+}
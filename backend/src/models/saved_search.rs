@@ -0,0 +1,26 @@
+use crate::models::comment::Comment;
+use serde::Serialize;
+
+/// A named query the author keeps around. With `notify` set, the
+/// `check_saved_searches` job reports new content and comments matching it.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub query: String,
+    pub notify: bool,
+    /// The last comment already checked against the query.
+    #[serde(skip)]
+    pub last_comment_id: i64,
+    pub created_at: String,
+}
+
+/// What newly matched a saved search since it was last checked: entries as
+/// search slugs, with notes under `notes/`, and comments.
+#[derive(Serialize, Debug)]
+pub struct SavedSearchAlert {
+    #[serde(flatten)]
+    pub search: SavedSearch,
+    pub entries: Vec<String>,
+    pub comments: Vec<Comment>,
+}
@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+/// Differences between the content stores and the search index. Note slugs
+/// carry their `notes/` prefix.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct IndexConsistency {
+    /// Published entries with no search document.
+    pub missing: Vec<String>,
+    /// Entries whose document is out of date or indexed more than once.
+    pub stale: Vec<String>,
+    /// Documents for entries that no longer exist or are not searchable.
+    pub orphans: Vec<String>,
+}
+
+impl IndexConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.stale.is_empty() && self.orphans.is_empty()
+    }
+}
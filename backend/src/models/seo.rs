@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SeoReport {
+    pub slug: String,
+    pub title_length: usize,
+    pub description_length: usize,
+    pub word_count: usize,
+    /// Heading levels in document order, e.g. `[2, 3, 3, 2]`.
+    pub headings: Vec<usize>,
+    pub images: usize,
+    pub images_missing_alt: usize,
+    pub internal_links: usize,
+    pub external_links: usize,
+    pub keyword: Option<String>,
+    /// Keyword occurrences per hundred words.
+    pub keyword_density: Option<f64>,
+    pub warnings: Vec<String>,
+}
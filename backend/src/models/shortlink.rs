@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Shortlink {
+    pub code: String,
+    pub slug: String,
+    pub clicks: i64,
+    pub created_at: String,
+}
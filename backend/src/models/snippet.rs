@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// A fenced code block of an article, as returned by
+/// `GET /api/articles/{slug}/snippets`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CodeSnippet {
+    /// `snippet-{n}`, numbering the fenced blocks of the article from 1.
+    pub anchor: String,
+    /// First word of the info string, e.g. `rust`.
+    pub language: Option<String>,
+    /// Lines of the code within the article's `content`, 1-based and
+    /// inclusive, fences excluded.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub code: String,
+}
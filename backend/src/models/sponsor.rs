@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Someone supporting the blog, from GitHub Sponsors or the sponsors file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sponsor {
+    pub name: String,
+    /// GitHub login, for sponsors from GitHub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    /// When the sponsorship started, for sponsors from GitHub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// The supporters shown by `GET /api/sponsors`, as last fetched.
+#[derive(Serialize, Debug, Clone)]
+pub struct SponsorList {
+    pub sponsors: Vec<Sponsor>,
+    pub fetched_at: DateTime<Utc>,
+}
@@ -0,0 +1,34 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<usize>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PopularArticle {
+    pub slug: String,
+    pub title: String,
+    pub views: i64,
+}
+
+/// Site-wide readership safe to show visitors: every count is coarsened
+/// and thinly read articles are left out.
+#[derive(Serialize, Debug)]
+pub struct PublicStats {
+    pub total_views: i64,
+    /// Calendar month (UTC) `most_read` covers, as `YYYY-MM`.
+    pub month: String,
+    pub most_read: Vec<PopularArticle>,
+}
+
+/// Publishing activity for one year. Days without posts are omitted.
+#[derive(Serialize, Debug)]
+pub struct Calendar {
+    pub year: i32,
+    pub days: Vec<CalendarDay>,
+}
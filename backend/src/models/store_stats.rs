@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// Size of an article store's in-memory state.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StoreStats {
+    /// Loaded entries, drafts and scheduled posts included.
+    pub articles: usize,
+    /// Soft-deleted entries kept until the next full reload.
+    pub tombstones: usize,
+    pub content_cache_entries: usize,
+    /// Bytes of cached article bodies.
+    pub content_cache_bytes: usize,
+    pub slug_map_entries: usize,
+    /// How long the last full load from disk took.
+    pub last_rebuild_ms: u64,
+}
@@ -0,0 +1,17 @@
+use serde::Serialize;
+
+/// A correction proposed by a reader: `replacement` for the one occurrence
+/// of `original` in the body of an article.
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+pub struct Suggestion {
+    pub id: i64,
+    pub article_slug: String,
+    pub author_github_id: i64,
+    pub author_login: String,
+    pub original: String,
+    pub replacement: String,
+    pub note: Option<String>,
+    /// `pending`, `accepted` or `dismissed`.
+    pub status: String,
+    pub created_at: String,
+}
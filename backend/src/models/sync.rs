@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Trees of files a replica copies from its primary.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncCollection {
+    Articles,
+    Notes,
+    Pages,
+    /// Saved article versions and their change messages.
+    Versions,
+}
+
+/// A file changed on the primary, with its path relative to the root of
+/// its collection.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncedFile {
+    pub collection: SyncCollection,
+    pub path: String,
+    /// Modification time on the primary, in milliseconds since the epoch.
+    pub modified: u64,
+    pub content: String,
+}
+
+/// Changes on the primary since a point in time. `articles`, `notes` and
+/// `pages` list every file the primary has now, so replicas can drop the
+/// ones that were removed; versions are only ever added.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangeFeed {
+    /// Time the primary scanned its files at; the `since` of the next pull.
+    pub until: u64,
+    pub files: Vec<SyncedFile>,
+    pub articles: Vec<String>,
+    pub notes: Vec<String>,
+    pub pages: Vec<String>,
+}
+
+/// What one pull changed on a replica.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncReport {
+    pub written: usize,
+    pub removed: usize,
+    /// Collections that had files written or removed.
+    pub changed: Vec<SyncCollection>,
+}
+
+/// What the server has of one article, for an editing client to compare
+/// its working copy against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Path of the file relative to the content directory, `/`-separated.
+    pub path: String,
+    /// SHA-256 of the file, hex-encoded.
+    pub hash: String,
+    /// Latest version saved through the API, 0 for none.
+    pub version: u64,
+}
+
+/// Every article file on the server, keyed by slug. Articles in categories
+/// are keyed as `category/slug`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SyncManifest {
+    pub articles: BTreeMap<String, ManifestEntry>,
+}
+
+/// A file an editing client changed, with the hash of the server copy it
+/// started from, `None` for a new file.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UploadedFile {
+    pub path: String,
+    pub base_hash: Option<String>,
+    pub content: String,
+}
+
+/// A file that changed on the server since the client's copy.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    pub path: String,
+    pub base_hash: Option<String>,
+    /// Hash of the server copy, `None` when it no longer exists.
+    pub server_hash: Option<String>,
+}
+
+/// A file that could not be written.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct SyncRejection {
+    pub path: String,
+    pub message: String,
+}
+
+/// Outcome of an upload; every file lands in exactly one list.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct UploadReport {
+    /// The files written, with their new manifest entries.
+    pub applied: Vec<ManifestEntry>,
+    pub conflicts: Vec<SyncConflict>,
+    pub rejected: Vec<SyncRejection>,
+}
@@ -0,0 +1,82 @@
+use crate::models::article::ArticleTeaser;
+use serde::{Deserialize, Serialize};
+
+/// Front matter of a page under `taxonomy/` describing a tag or category.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TermPageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub cover: Option<String>,
+    /// Articles featured at the top of the landing page, by slug or
+    /// `category/slug`.
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+/// Everything a tag or category landing page shows: the number of
+/// published articles under it, the curated intro from its taxonomy page
+/// when it has one, its pinned articles and the first page of its listing.
+#[derive(Serialize, Debug)]
+pub struct TermDetail {
+    pub name: String,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<String>,
+    /// Markdown body of the taxonomy page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Pinned articles in the order the page lists them. Pins to articles
+    /// outside the term, or hidden from the requester, are left out.
+    pub pinned: Vec<ArticleTeaser>,
+    /// First page of the term's articles, newest first, as listed by
+    /// `/api/articles?tag=` or `?category=`.
+    pub articles: Vec<ArticleTeaser>,
+    pub total_pages: usize,
+}
+
+/// A tag or category that the `[taxonomy]` rules do not allow.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TaxonomyViolation {
+    /// `tags` or `category`.
+    pub field: &'static str,
+    pub value: String,
+}
+
+impl std::fmt::Display for TaxonomyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = if self.field == "tags" {
+            "tag"
+        } else {
+            "category"
+        };
+        write!(f, "{} {} is not allowed", kind, self.value)
+    }
+}
+
+/// A word missing from the dictionary, by line of the source file.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Misspelling {
+    pub line: usize,
+    pub word: String,
+}
+
+/// The rule violations of one article or note, by slug; notes are listed
+/// under `notes/`. Misspellings are only checked with `[spellcheck]`.
+#[derive(Serialize, Debug)]
+pub struct ContentLint {
+    pub slug: String,
+    pub violations: Vec<TaxonomyViolation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub misspellings: Vec<Misspelling>,
+    /// `gallery` sources whose file or uploaded asset does not exist.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_gallery_images: Vec<String>,
+    /// The front matter has no `description`, so one is derived from the
+    /// first paragraph.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub auto_description: bool,
+}
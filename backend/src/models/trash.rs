@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// An article deleted through the API, kept until it is restored or purged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrashItem {
+    /// Identifies this deletion; an article deleted twice has two entries.
+    pub id: String,
+    /// `category/slug` of the article.
+    pub slug: String,
+    pub title: String,
+    /// Where the file lived, relative to the content directory, and where
+    /// restoring it puts it back.
+    pub path: String,
+    pub deleted_at: DateTime<Utc>,
+    /// GitHub login of the author who deleted it.
+    pub deleted_by: String,
+    /// Size of the file in bytes.
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// A trashed article together with its file, front matter included.
+#[derive(Serialize, Debug, Clone)]
+pub struct TrashPreview {
+    #[serde(flatten)]
+    pub item: TrashItem,
+    pub content: String,
+}
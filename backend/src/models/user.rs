@@ -1,3 +1,4 @@
+use crate::models::user_preferences::ResolvedPreferences;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -46,6 +47,7 @@ pub struct UserInfo {
     pub bio: Option<String>,
     pub avatar: Option<String>,
     pub is_author: bool,
+    pub preferences: ResolvedPreferences,
 }
 
 impl From<User> for UserInfo {
@@ -60,6 +62,7 @@ impl From<User> for UserInfo {
             bio: user.bio,
             avatar: user.avatar,
             is_author,
+            preferences: ResolvedPreferences::default(),
         }
     }
 }
\ No newline at end of file
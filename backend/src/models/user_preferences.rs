@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 /// that may differ from data provided by GitHub. When a field is
 /// `Some`, the application should respect that value instead of
 /// refreshing it from GitHub.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UserPreferences {
     pub display_name: Option<String>,
     pub bio: Option<String>,
@@ -13,3 +13,13 @@ pub struct UserPreferences {
     pub theme: Option<String>,
     pub language: Option<String>,
 }
+
+/// The theme and language the frontend should apply, the same on every
+/// device the user signs in from. `language` falls back to the
+/// `Accept-Language` of the request when none is saved; unset values leave
+/// the choice to the frontend.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResolvedPreferences {
+    pub theme: Option<String>,
+    pub language: Option<String>,
+}
@@ -9,3 +9,24 @@ pub struct VersionRecord {
     pub timestamp: DateTime<Utc>,
     pub editor: String,
 }
+
+/// One saved revision of an article in its public changelog.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangelogEntry {
+    pub version: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Lines of the body added and removed since the previous version;
+    /// absent for the oldest one.
+    pub lines_added: Option<usize>,
+    pub lines_removed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Changelog {
+    pub slug: String,
+    pub published: DateTime<Utc>,
+    pub revised_at: Option<DateTime<Utc>>,
+    pub entries: Vec<ChangelogEntry>,
+}
@@ -0,0 +1,366 @@
+//! `scribe seed`: writes made-up articles and notes, so store load time,
+//! search indexing and listing latency can be measured at a given size
+//! before committing to it.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Prefix of every generated file name, so generated content is easy to
+/// tell apart and remove.
+pub const SEED_PREFIX: &str = "seed-";
+
+/// Categories articles are filed under, as nested directories.
+const CATEGORIES: &[&str] = &[
+    "rust",
+    "rust/async",
+    "rust/embedded",
+    "web",
+    "web/frontend",
+    "web/http",
+    "databases",
+    "devops",
+    "devops/kubernetes",
+    "essays",
+    "reviews",
+    "travel",
+];
+
+/// Tags to pick from, separated by whitespace.
+const TAGS: &str = "rust axum tokio serde sqlite postgres redis docker kubernetes linux \
+    networking http tls performance testing debugging security css \
+    typescript vue wasm compilers parsers concurrency memory databases \
+    search caching observability logging ci git books writing productivity \
+    career hardware keyboards photography travel";
+
+/// Words the text is made of, separated by whitespace.
+const WORDS: &str = "the a an of to in and with for on that this every when while because \
+    after before without under server request response handler router thread \
+    task runtime future stream buffer index query cache lock channel message \
+    queue worker pool connection socket packet header body file directory \
+    disk memory allocation pointer reference lifetime borrow trait type \
+    generic macro compiler error panic result option value field struct enum \
+    match loop function closure iterator vector map set tree graph node edge \
+    schema table row column migration transaction commit branch merge deploy \
+    release container cluster service endpoint latency throughput benchmark \
+    profile trace metric log alert incident fix patch test fixture mock \
+    assertion build pipeline artifact binary library crate module package \
+    version dependency feature flag config setting default option user \
+    author reader post draft note page feed search tag category quickly \
+    slowly carefully finally rarely often simply mostly fast slow small \
+    large simple subtle broken stable shared local remote async blocking \
+    lazy eager clean messy hidden runs waits returns reads writes sends \
+    keeps drops holds parses renders loads stores checks retries fails wins \
+    breaks";
+
+const LANGUAGES: &[&str] = &["rust", "sql", "toml", "bash", "typescript"];
+
+/// How much `scribe seed` writes, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedOptions {
+    pub articles: usize,
+    pub notes: usize,
+    /// Starts the generator, so the same options give the same files.
+    pub seed: u64,
+    pub article_dir: PathBuf,
+    pub notes_dir: PathBuf,
+}
+
+impl SeedOptions {
+    /// Reads `--articles N`, `--notes N`, `--seed N` and `--dir PATH`, the
+    /// directory holding `article` and `notes` (the working directory by
+    /// default), from the arguments after `seed`.
+    pub fn parse(
+        args: &[String],
+        article_dir: &str,
+        notes_dir: &str,
+    ) -> Result<SeedOptions, String> {
+        let mut articles = 1000;
+        let mut notes = 200;
+        let mut seed = 1;
+        let mut root = PathBuf::from(".");
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value", flag))?;
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} needs a number, not '{}'", flag, value))
+            };
+            match flag.as_str() {
+                "--articles" => articles = number()? as usize,
+                "--notes" => notes = number()? as usize,
+                "--seed" => seed = number()?,
+                "--dir" => root = PathBuf::from(value),
+                _ => return Err(format!("unknown option '{}'", flag)),
+            }
+        }
+        Ok(SeedOptions {
+            articles,
+            notes,
+            seed,
+            article_dir: root.join(article_dir),
+            notes_dir: root.join(notes_dir),
+        })
+    }
+}
+
+/// Picks from the vocabulary with SplitMix64: small, fast and plenty for
+/// made-up text.
+struct Rng {
+    state: u64,
+    words: Vec<&'static str>,
+    tags: Vec<&'static str>,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            words: WORDS.split_whitespace().collect(),
+            tags: TAGS.split_whitespace().collect(),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `low..=high`.
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % (high - low + 1) as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.between(0, items.len() - 1)]
+    }
+
+    fn word(&mut self) -> &'static str {
+        let index = self.between(0, self.words.len() - 1);
+        self.words[index]
+    }
+
+    fn tag(&mut self) -> &'static str {
+        let index = self.between(0, self.tags.len() - 1);
+        self.tags[index]
+    }
+
+    fn words(&mut self, count: usize) -> Vec<&'static str> {
+        (0..count).map(|_| self.word()).collect()
+    }
+}
+
+fn sentence(rng: &mut Rng) -> String {
+    let count = rng.between(6, 18);
+    let mut words = rng.words(count).join(" ");
+    if let Some(first) = words.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    words.push('.');
+    words
+}
+
+fn title(rng: &mut Rng) -> String {
+    let count = rng.between(3, 7);
+    rng.words(count)
+        .iter()
+        .map(|word| {
+            let mut word = word.to_string();
+            if let Some(first) = word.get_mut(0..1) {
+                first.make_ascii_uppercase();
+            }
+            word
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn paragraph(rng: &mut Rng) -> String {
+    let count = rng.between(2, 6);
+    let mut sentences: Vec<String> = (0..count).map(|_| sentence(rng)).collect();
+    // Some inline markup, as real posts have.
+    if rng.chance(30) {
+        let word = rng.word();
+        sentences.push(format!("See [{}](https://example.com/{}).", word, word));
+    }
+    if rng.chance(20) {
+        sentences.push(format!("It is **{}**, not `{}`.", rng.word(), rng.word()));
+    }
+    sentences.join(" ")
+}
+
+/// A Markdown body of roughly `paragraphs` paragraphs with headings, lists
+/// and code blocks mixed in.
+fn body(rng: &mut Rng, paragraphs: usize) -> String {
+    let mut blocks = Vec::new();
+    for i in 0..paragraphs {
+        if i > 0 && i % 4 == 0 {
+            blocks.push(format!("## {}", title(rng)));
+        }
+        blocks.push(paragraph(rng));
+        if rng.chance(10) {
+            let items = rng.between(2, 5);
+            let list: Vec<String> = (0..items).map(|_| format!("- {}", sentence(rng))).collect();
+            blocks.push(list.join("\n"));
+        }
+        if rng.chance(8) {
+            let language = rng.pick(LANGUAGES);
+            let lines = rng.between(2, 8);
+            let code: Vec<String> = (0..lines)
+                .map(|_| {
+                    let count = rng.between(2, 6);
+                    rng.words(count).join("_")
+                })
+                .collect();
+            blocks.push(format!("```{}\n{}\n```", language, code.join("\n")));
+        }
+    }
+    blocks.join("\n\n")
+}
+
+fn tags(rng: &mut Rng) -> String {
+    let count = rng.between(1, 5);
+    let mut tags: Vec<&str> = (0..count).map(|_| rng.tag()).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    let quoted: Vec<String> = tags.iter().map(|tag| format!("\"{}\"", tag)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+/// A date in the six years before `until`, so archives and feeds have
+/// content spread over many months.
+fn date(rng: &mut Rng, until: DateTime<Utc>) -> DateTime<Utc> {
+    until - Duration::minutes(rng.between(0, 6 * 365 * 24 * 60) as i64)
+}
+
+fn front_matter(rng: &mut Rng, title: &str, date: DateTime<Utc>, draft: bool) -> String {
+    let mut front = format!(
+        "---\ntitle: \"{}\"\nauthor: \"Scribe\"\ndate: {}\ndescription: \"{}\"\ntags: {}\n",
+        title,
+        date.to_rfc3339(),
+        sentence(rng).trim_end_matches('.'),
+        tags(rng),
+    );
+    if draft {
+        front.push_str("status: draft\n");
+    }
+    if rng.chance(15) {
+        let updated = date + Duration::days(rng.between(1, 200) as i64);
+        front.push_str(&format!("last_updated: {}\n", updated.to_rfc3339()));
+    }
+    front.push_str("---\n\n");
+    front
+}
+
+fn file_name(index: usize, title: &str) -> String {
+    let slug = title.to_lowercase().replace(' ', "-");
+    format!("{}{:05}-{}.md", SEED_PREFIX, index, slug)
+}
+
+/// Writes `options.articles` articles, filed under nested categories with
+/// one in twenty a draft, and `options.notes` shorter notes. Returns the
+/// files written.
+pub fn seed(options: &SeedOptions) -> io::Result<Vec<PathBuf>> {
+    let mut rng = Rng::new(options.seed);
+    let until = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let mut written = Vec::with_capacity(options.articles + options.notes);
+
+    for index in 0..options.articles {
+        let title = title(&mut rng);
+        let dir = options.article_dir.join(rng.pick(CATEGORIES));
+        let date = date(&mut rng, until);
+        let draft = rng.chance(5);
+        let paragraphs = rng.between(3, 30);
+        let contents = front_matter(&mut rng, &title, date, draft) + &body(&mut rng, paragraphs);
+        written.push(write(&dir, &file_name(index, &title), &contents)?);
+    }
+    for index in 0..options.notes {
+        let title = title(&mut rng);
+        let date = date(&mut rng, until);
+        let paragraphs = rng.between(1, 5);
+        let contents = front_matter(&mut rng, &title, date, false) + &body(&mut rng, paragraphs);
+        written.push(write(
+            &options.notes_dir,
+            &file_name(index, &title),
+            &contents,
+        )?);
+    }
+    Ok(written)
+}
+
+fn write(dir: &Path, name: &str, contents: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::service::ArticleStore;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_the_options() {
+        let options = SeedOptions::parse(
+            &args("--articles 5000 --notes 20 --dir site"),
+            "article",
+            "notes",
+        )
+        .unwrap();
+        assert_eq!(options.articles, 5000);
+        assert_eq!(options.notes, 20);
+        assert_eq!(options.article_dir, Path::new("site/article"));
+        assert!(SeedOptions::parse(&args("--articles many"), "article", "notes").is_err());
+        assert!(SeedOptions::parse(&args("--articles"), "article", "notes").is_err());
+        assert!(SeedOptions::parse(&args("--pages 3"), "article", "notes").is_err());
+    }
+
+    #[test]
+    fn generated_content_loads() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let options = SeedOptions {
+            articles: 60,
+            notes: 10,
+            seed: 7,
+            article_dir: dir.path().join("article"),
+            notes_dir: dir.path().join("notes"),
+        };
+        assert_eq!(seed(&options).unwrap().len(), 70);
+
+        let articles = ArticleStore::new(&options.article_dir, true).unwrap();
+        assert_eq!(articles.query(|_| true, 0, usize::MAX).count(), 60);
+        assert!(articles.get_all_categories(true).len() > 5);
+        assert!(articles.get_all_tags(true).len() > 10);
+        let notes = ArticleStore::new(&options.notes_dir, false).unwrap();
+        assert_eq!(notes.query(|_| true, 0, usize::MAX).count(), 10);
+
+        // The same seed writes the same files.
+        let again = tempfile::TempDir::new().unwrap();
+        let first = fs::read_to_string(seed(&options).unwrap()[0].clone()).unwrap();
+        let options = SeedOptions {
+            article_dir: again.path().join("article"),
+            notes_dir: again.path().join("notes"),
+            ..options
+        };
+        assert_eq!(
+            fs::read_to_string(&seed(&options).unwrap()[0]).unwrap(),
+            first
+        );
+    }
+}
@@ -1,3 +1,25 @@
+pub mod access;
 pub mod app;
 pub mod auth;
 pub mod cache;
+pub mod client_ip;
+pub mod envelope;
+pub mod error_log;
+pub mod index_queue;
+pub mod job_queue;
+pub mod redirects;
+pub mod scheduler;
+pub mod versioning;
+pub mod listener;
+pub mod body_limit;
+pub mod load_shed;
+pub mod file_events;
+pub mod visibility;
+pub mod api_keys;
+pub mod read_only;
+pub mod http_client;
+pub mod public_api;
+pub mod snapshots;
+pub mod admin_lockout;
+pub mod panics;
+pub mod route_layers;
@@ -0,0 +1,127 @@
+use crate::config::get_admin_token_hash;
+use crate::handlers::auth::session_user;
+use crate::models::article::Article;
+use crate::server::admin_lockout::report_failure;
+use crate::server::app::AppState;
+use crate::server::client_ip::ClientIp;
+use crate::services::access_code_service::code_is_live;
+use crate::services::service::ArticleStore;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum_extra::extract::cookie::SignedCookieJar;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+use subtle::ConstantTimeEq;
+
+/// What the requester may see of `restricted_categories`.
+///
+/// Handlers take the [`Visibility`](crate::server::visibility::Visibility)
+/// policy, which combines this with the status and private flag. Signed-in
+/// users and admin token holders see everything, anonymous visitors see
+/// nothing under a restricted category. Responses for
+/// requests carrying credentials are never cached, so an anonymous cache
+/// hit cannot leak restricted content.
+///
+/// Members may also read the body of `members_only` articles: admin token
+/// holders, signed-in users allowed by `[members]`, and visitors whose
+/// [`MEMBER_COOKIE`] names an access code that has not been revoked.
+#[derive(Debug, Clone, Copy)]
+pub struct Access {
+    authenticated: bool,
+    admin: bool,
+    member: bool,
+}
+
+/// Signed cookie holding the id of the access code a visitor redeemed.
+pub const MEMBER_COOKIE: &str = "member_access";
+
+impl Access {
+    pub fn anonymous() -> Self {
+        Self {
+            authenticated: false,
+            admin: false,
+            member: false,
+        }
+    }
+
+    pub fn includes_restricted(self) -> bool {
+        self.authenticated
+    }
+
+    /// Whether the request carries the admin token.
+    pub fn is_admin(self) -> bool {
+        self.admin
+    }
+
+    /// Whether the request may read members-only articles.
+    pub fn is_member(self) -> bool {
+        self.member
+    }
+
+    pub fn can_view(self, store: &ArticleStore, article: &Article) -> bool {
+        self.authenticated || !store.is_restricted(article)
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for Access {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let jar = SignedCookieJar::from_headers(&parts.headers, state.cookie_key.clone());
+        let user = session_user(&jar).ok();
+
+        // A wrong token counts towards the admin lockout here too, so
+        // restricted content cannot be used to guess it, and is ignored
+        // while the client is locked out.
+        let mut has_admin_token = false;
+        if let Some(token) = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            && let Some(stored) = get_admin_token_hash().ok()
+        {
+            let ClientIp(client) =
+                ClientIp::of(&parts.headers, &parts.extensions, &state.trusted_proxies);
+            if state
+                .admin_lockout
+                .locked_for(&client, Instant::now())
+                .is_none()
+            {
+                let provided: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+                has_admin_token = provided.ct_eq(&stored).unwrap_u8() == 1;
+                if has_admin_token {
+                    state.admin_lockout.record_success(&client);
+                } else {
+                    report_failure(state, &client).await;
+                }
+            }
+        }
+
+        let mut member = has_admin_token
+            || user
+                .as_ref()
+                .is_some_and(|user| state.config.members.allows(user));
+        if !member
+            && let Some(id) = jar
+                .get(MEMBER_COOKIE)
+                .and_then(|cookie| cookie.value().parse::<i64>().ok())
+        {
+            member = code_is_live(&state.db, id).await.unwrap_or_else(|e| {
+                tracing::warn!("Failed to check access code {}: {}", id, e);
+                false
+            });
+        }
+
+        Ok(Self {
+            authenticated: user.is_some() || has_admin_token,
+            admin: has_admin_token,
+            member,
+        })
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config::AdminLockoutConfig;
+use crate::server::app::AppState;
+use crate::services::notification_service::notify_author;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Clients tracked before those no longer locked out are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Wrong admin tokens sent by each client address, and the lockouts they
+/// earned. Kept in memory, so a restart lifts every lockout.
+pub struct AdminLockout {
+    max_failures: u32,
+    lockout: Duration,
+    max_lockout: Duration,
+    clients: Mutex<HashMap<String, Failures>>,
+}
+
+impl AdminLockout {
+    pub fn new(config: &AdminLockoutConfig) -> Self {
+        Self {
+            max_failures: config.max_failures,
+            lockout: Duration::from_secs(config.lockout_secs),
+            max_lockout: Duration::from_secs(config.max_lockout_secs),
+            clients: Mutex::default(),
+        }
+    }
+
+    /// Seconds until `client` may send the admin token again, rounded up,
+    /// if it is locked out.
+    pub fn locked_for(&self, client: &str, now: Instant) -> Option<u64> {
+        let clients = self.clients.lock().unwrap();
+        let until = clients.get(client)?.locked_until?;
+        let left = until.checked_duration_since(now)?;
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
+    }
+
+    /// Counts a wrong token from `client`. Returns the number of failures
+    /// and the lockout when this one locks the client out. Failures are
+    /// forgotten once `max_lockout` has passed without one.
+    pub fn record_failure(&self, client: &str, now: Instant) -> Option<(u32, Duration)> {
+        if self.max_failures == 0 {
+            return None;
+        }
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            clients.retain(|_, f| f.locked_until.is_some_and(|until| until > now));
+        }
+        let failures = clients.entry(client.to_string()).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        if now.duration_since(failures.last) >= self.max_lockout {
+            failures.count = 0;
+        }
+        failures.count += 1;
+        failures.last = now;
+        if failures.count < self.max_failures {
+            return None;
+        }
+        let doublings = (failures.count - self.max_failures).min(20);
+        let lockout = self
+            .lockout
+            .saturating_mul(1 << doublings)
+            .min(self.max_lockout);
+        failures.locked_until = Some(now + lockout);
+        Some((failures.count, lockout))
+    }
+
+    /// Forgets the failures of `client` once it sends the right token.
+    pub fn record_success(&self, client: &str) {
+        self.clients.lock().unwrap().remove(client);
+    }
+}
+
+/// Counts a wrong admin token from `client`. A lockout it starts is logged
+/// and reported to the `notify_webhook` as an `admin_lockout` event.
+pub async fn report_failure(state: &AppState, client: &str) {
+    warn!("Wrong admin token from {}", client);
+    let Some((failures, lockout)) = state.admin_lockout.record_failure(client, Instant::now())
+    else {
+        return;
+    };
+    warn!(
+        "Locked {} out of the admin API for {}s after {} wrong tokens",
+        client,
+        lockout.as_secs(),
+        failures
+    );
+    let event = json!({
+        "event": "admin_lockout",
+        "client": client,
+        "failures": failures,
+        "lockout_secs": lockout.as_secs(),
+    });
+    notify_author(state, &event).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockouts_double_with_each_further_failure() {
+        let lockout = AdminLockout::new(&AdminLockoutConfig {
+            max_failures: 3,
+            lockout_secs: 60,
+            max_lockout_secs: 200,
+        });
+        let now = Instant::now();
+        assert_eq!(lockout.record_failure("a", now), None);
+        assert_eq!(lockout.record_failure("a", now), None);
+        assert_eq!(lockout.locked_for("a", now), None);
+        assert_eq!(
+            lockout.record_failure("a", now),
+            Some((3, Duration::from_secs(60)))
+        );
+        assert_eq!(lockout.locked_for("a", now), Some(60));
+        assert_eq!(lockout.locked_for("b", now), None);
+
+        let later = now + Duration::from_secs(61);
+        assert_eq!(lockout.locked_for("a", later), None);
+        assert_eq!(
+            lockout.record_failure("a", later),
+            Some((4, Duration::from_secs(120)))
+        );
+        assert_eq!(
+            lockout.record_failure("a", later),
+            Some((5, Duration::from_secs(200)))
+        );
+
+        lockout.record_success("a");
+        assert_eq!(lockout.locked_for("a", later), None);
+        assert_eq!(lockout.record_failure("a", later), None);
+    }
+}
@@ -0,0 +1,74 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use crate::services::api_key_service::{KeyUse, use_key};
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderValue, Method, Request, header::RETRY_AFTER};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{Days, Utc};
+use std::sync::Arc;
+
+/// Header carrying a read-only API token. It is kept apart from the
+/// `Authorization` header used for the admin token so that neither can be
+/// mistaken for the other.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Checks the API token of requests that carry one. Unknown and revoked
+/// tokens are rejected, as is any request that is not a read, since tokens
+/// are read-only. A token grants no more than anonymous access otherwise;
+/// every request made with it is counted against it, and once its daily
+/// quota is used up it gets 429 until the next UTC day.
+pub async fn authenticate_api_key(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(token) = req.headers().get(API_KEY_HEADER) else {
+        return Ok(next.run(req).await);
+    };
+    let invalid = || AppError::Unauthorized {
+        code: ErrorCode::InvalidApiKey,
+        message: "Unknown or revoked API key".to_string(),
+    };
+    let token = token.to_str().map_err(|_| invalid())?;
+
+    if !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Err(AppError::Forbidden {
+            code: ErrorCode::ApiKeyReadOnly,
+            message: "API keys can only be used to read".to_string(),
+        });
+    }
+    let used = use_key(&state.db, token, state.config.api_keys.daily_quota)
+        .await
+        .map_err(|e| AppError::InternalServerError {
+            code: ErrorCode::InternalServer,
+            message: e.to_string(),
+        })?;
+    match used {
+        KeyUse::Counted(_) => Ok(next.run(req).await),
+        KeyUse::Invalid => Err(invalid()),
+        KeyUse::OverQuota(quota) => {
+            let mut response = AppError::TooManyRequests {
+                code: ErrorCode::ApiQuotaExceeded,
+                message: format!("API key has made its {} requests for today", quota),
+            }
+            .into_response();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs_until_tomorrow()));
+            Ok(response)
+        }
+    }
+}
+
+/// Seconds until the next UTC midnight, when daily quotas start over.
+fn secs_until_tomorrow() -> i64 {
+    let now = Utc::now();
+    let tomorrow = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc());
+    tomorrow.map_or(1, |midnight| (midnight - now).num_seconds().max(1))
+}
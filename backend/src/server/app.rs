@@ -1,212 +1,825 @@
 use crate::config::{
-    ARTICLE_DIR, CACHE_MAX_CAPACITY, CACHE_TTL_SECONDS, Config, ENABLE_NESTED_CATEGORIES,
-    NOTES_DIR, SERVER_ADDR,
+    ARTICLE_DIR, CACHE_MAX_CAPACITY, COLLECTIONS_FILE, Config, DATA_DIR, ENABLE_NESTED_CATEGORIES,
+    GLOSSARY_DIR, META_CACHE_TTL_SECONDS, NOTES_DIR, PAGES_DIR, SERVER_ADDR, ServerConfig,
+    TAXONOMY_DIR, UNFURL_CACHE_TTL_SECONDS,
 };
-use crate::models::article::ArticleContent;
-use crate::server::cache::{CachedResponse, ResponseCacheLayer};
-use crate::services::search::SearchService;
+use crate::db::DbPool;
+use crate::handlers::error::LoadError;
+use crate::handlers::response::ErrorBody;
+use crate::models::article::{ArticleContent, ArticleMeta};
+use crate::models::link_preview::LinkPreview;
+use crate::server::admin_lockout::AdminLockout;
+use crate::server::api_keys::authenticate_api_key;
+use crate::server::body_limit::default_body_limit;
+use crate::server::cache::{ResponseCache, ResponseCacheLayer};
+use crate::server::client_ip::{ClientIp, TrustedProxies};
+use crate::server::envelope::{RequestId, envelope};
+use crate::server::error_log::ErrorLog;
+use crate::server::file_events::{
+    MAX_BATCH_WINDOW, QUIET_PERIOD, RestartBackoff, WatcherStatus, file_events, supervise,
+};
+use crate::server::http_client::HttpClient;
+use crate::server::index_queue::{IndexQueue, start_index_worker};
+use crate::server::job_queue::JobQueue;
+use crate::server::listener::serve;
+use crate::server::load_shed::LoadLimits;
+use crate::server::panics::{Panicked, catch_panics};
+use crate::server::public_api::{PublicQuota, harden_public_api};
+use crate::server::read_only::refuse_writes;
+use crate::server::redirects::{RedirectTable, serve_redirects};
+use crate::server::route_layers::{RouteLayers, apply_route_layers, compression_layer};
+use crate::server::scheduler::Scheduler;
+use crate::server::snapshots::{SNAPSHOT_DIR, XmlSnapshots};
+use crate::server::versioning::{deprecation_headers, rewrite_versioned_path};
+use crate::services::atomic_file::is_temp_file;
+use crate::services::audit_service::{self, ARTICLE_DELETED};
+use crate::services::bootstrap::scaffold_content;
+use crate::services::collab_service::DraftRooms;
+use crate::services::collection_service::Collections;
+use crate::services::crosspost_service;
+use crate::services::error_reporter::{ErrorEvent, report_error};
+use crate::services::fediverse_service::MastodonRateLimits;
+use crate::services::field_crypto::{DATABASE_ENCRYPTION_KEY, FieldCipher};
+use crate::services::github_service::GitHubRateLimit;
+use crate::services::image_service::ImageInfoCache;
+use crate::services::note_crypto::{NOTES_ENCRYPTION_KEY, NoteCipher};
+use crate::services::notification_service;
+use crate::services::ping_service;
+use crate::services::presence_service::Presence;
+use crate::services::preview_branch_service::PreviewBranch;
+use crate::services::search::{IndexCollection, SearchService};
 use crate::services::service::{ArticleStore, FileChange};
-use axum::body::Body;
+use crate::services::site_event_service::{CONTENT_CHANGED, record_event};
+use crate::services::site_time::set_site_timezone;
+use crate::services::spellcheck::SpellChecker;
+use crate::services::sponsor_service::SponsorCache;
+use crate::services::taxonomy::TaxonomyRules;
+use crate::services::user_preferences_service::seal_saved_preferences;
+use axum::body::{Body, to_bytes};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{StatusCode, header};
 use axum::middleware::{self, Next};
 use axum::response::Response;
-use axum::{Router, http::Request};
+use axum::{Extension, Router, http::Request};
+use chrono::{DateTime, Utc};
 use cookie::Key;
 use moka2::future::Cache;
 use notify::{RecursiveMode, Watcher};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tokio::sync::mpsc;
 use tracing::{error, info};
 
 pub enum IndexJob {
-    Index(ArticleContent),
+    Index(Box<ArticleContent>),
     Remove(String),
 }
 
+impl IndexJob {
+    /// Indexes an article, or removes a private one from the index. The
+    /// public index leaves out articles that are not published.
+    pub fn for_article(article: ArticleContent) -> Self {
+        if article.metadata.private {
+            IndexJob::Remove(article.slug)
+        } else {
+            IndexJob::Index(Box::new(article))
+        }
+    }
+}
+
 pub struct AppState {
     pub store: Arc<RwLock<ArticleStore>>,
     pub note_store: Arc<RwLock<ArticleStore>>,
+    /// Standalone pages such as about or uses, kept out of the dated
+    /// listings and feeds. The directory is optional.
+    pub page_store: Arc<RwLock<ArticleStore>>,
     pub config: Arc<Config>,
     pub search_service: Option<Arc<SearchService>>,
-    pub index_tx: Option<mpsc::UnboundedSender<IndexJob>>,
-    pub cache: Arc<Cache<String, CachedResponse>>,
+    pub index_tx: Option<IndexQueue>,
+    /// Anonymous `GET` responses, split by `[response_cache]` class.
+    pub cache: Arc<ResponseCache>,
+    /// Article counters by slug, kept for `META_CACHE_TTL_SECONDS`.
+    pub meta_cache: Cache<String, ArticleMeta>,
     pub cookie_key: Key,
+    pub db: DbPool,
+    pub error_log: Arc<ErrorLog>,
+    pub scheduler: Arc<Scheduler>,
+    pub job_queue: Arc<JobQueue>,
+    pub redirects: Arc<RedirectTable>,
+    pub snapshots: XmlSnapshots,
+    /// Root for files the server writes itself, such as article versions.
+    pub data_dir: PathBuf,
+    /// Pages describing tags and categories. Read on request, so edits
+    /// show up without a reload; the directory is optional.
+    pub taxonomy_dir: PathBuf,
+    /// Reading lists from `collections.yaml`, reloaded when it changes.
+    pub collections: Collections,
+    /// Term definitions, read on request like the taxonomy pages.
+    pub glossary_dir: PathBuf,
+    /// Key for private notes. `None` when `NOTES_ENCRYPTION_KEY` is unset.
+    pub note_cipher: Option<NoteCipher>,
+    /// Key for personal data in the database. `None` when
+    /// `DATABASE_ENCRYPTION_KEY` is unset.
+    pub field_cipher: Option<FieldCipher>,
+    pub load_limits: LoadLimits,
+    pub taxonomy_rules: TaxonomyRules,
+    /// `None` unless `[spellcheck]` is configured.
+    pub spell_checker: Option<SpellChecker>,
+    /// Shared client for outbound requests, configured by `[http]`.
+    pub http: HttpClient,
+    pub github_rate_limit: GitHubRateLimit,
+    /// Instances that asked for a pause in fetching reply threads.
+    pub mastodon_rate_limits: MastodonRateLimits,
+    /// Starts as `comments_paused` and is switched through
+    /// `/api/admin/comments/pause`.
+    pub comments_paused: AtomicBool,
+    /// Branches checked out for review, by name.
+    pub preview_branches: RwLock<BTreeMap<String, PreviewBranch>>,
+    pub public_quota: PublicQuota,
+    pub admin_lockout: AdminLockout,
+    /// Sizes and placeholders of the images articles show.
+    pub image_info: ImageInfoCache,
+    /// Unfurled external pages by URL, kept for `UNFURL_CACHE_TTL_SECONDS`.
+    pub link_previews: Cache<String, LinkPreview>,
+    /// Live editing and publishing events streamed by `/api/presence`.
+    pub presence: Presence,
+    /// Drafts being edited live over `/api/admin/articles/{slug}/ws`.
+    pub drafts: DraftRooms,
+    /// Supporters from `[sponsors]`, as last fetched.
+    pub sponsors: SponsorCache,
+    pub search_index_dir: PathBuf,
+    /// The content and collections watchers, for diagnostics.
+    pub watchers: WatcherStatus,
+    pub started_at: DateTime<Utc>,
+    pub trusted_proxies: TrustedProxies,
+    /// Layers switched on or off per route group by `[[route_layers]]`.
+    pub route_layers: Arc<RouteLayers>,
 }
 
-pub async fn create_app_state(
-    config: &Arc<Config>,
-) -> Result<Arc<AppState>, Box<dyn std::error::Error>> {
-    let article_store = ArticleStore::new(ARTICLE_DIR, ENABLE_NESTED_CATEGORIES)?;
-    let note_store = ArticleStore::new(NOTES_DIR, true)?;
-    let cache = Cache::builder()
-        .max_capacity(CACHE_MAX_CAPACITY)
-        .time_to_live(Duration::from_secs(CACHE_TTL_SECONDS))
-        .build();
-
-    let (search_service, index_tx) = if config.enable_full_text_search {
-        match SearchService::new(&config.search_index_dir) {
-            Ok(service) => {
-                let mut all = article_store.load_full_articles();
-                let mut notes = note_store.load_full_articles();
-                for n in &mut notes {
-                    n.slug = format!("notes/{}", n.slug_with_category());
+impl AppState {
+    pub fn builder(config: &Arc<Config>, db: DbPool) -> AppStateBuilder {
+        AppStateBuilder::new(config, db)
+    }
+
+    pub fn store_of(&self, collection: IndexCollection) -> &Arc<RwLock<ArticleStore>> {
+        match collection {
+            IndexCollection::Articles => &self.store,
+            IndexCollection::Notes => &self.note_store,
+            IndexCollection::Pages => &self.page_store,
+        }
+    }
+}
+
+/// Assembles an [`AppState`]. Paths default to the directories the server
+/// uses in production, relative to the working directory; tests and
+/// embedders can point each of them anywhere.
+pub struct AppStateBuilder {
+    config: Arc<Config>,
+    db: DbPool,
+    article_dir: PathBuf,
+    notes_dir: PathBuf,
+    pages_dir: PathBuf,
+    data_dir: PathBuf,
+    taxonomy_dir: PathBuf,
+    collections_file: PathBuf,
+    glossary_dir: PathBuf,
+    search_index_dir: PathBuf,
+    cookie_key: Option<Key>,
+    note_cipher: Option<NoteCipher>,
+    field_cipher: Option<FieldCipher>,
+}
+
+impl AppStateBuilder {
+    pub fn new(config: &Arc<Config>, db: DbPool) -> Self {
+        Self {
+            config: Arc::clone(config),
+            db,
+            article_dir: PathBuf::from(ARTICLE_DIR),
+            notes_dir: PathBuf::from(NOTES_DIR),
+            pages_dir: PathBuf::from(PAGES_DIR),
+            data_dir: PathBuf::from(DATA_DIR),
+            taxonomy_dir: PathBuf::from(TAXONOMY_DIR),
+            collections_file: PathBuf::from(COLLECTIONS_FILE),
+            glossary_dir: PathBuf::from(GLOSSARY_DIR),
+            search_index_dir: PathBuf::from(&config.search_index_dir),
+            cookie_key: None,
+            note_cipher: None,
+            field_cipher: None,
+        }
+    }
+
+    pub fn article_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.article_dir = dir.into();
+        self
+    }
+
+    pub fn notes_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.notes_dir = dir.into();
+        self
+    }
+
+    pub fn pages_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.pages_dir = dir.into();
+        self
+    }
+
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = dir.into();
+        self
+    }
+
+    pub fn taxonomy_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.taxonomy_dir = dir.into();
+        self
+    }
+
+    pub fn collections_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.collections_file = path.into();
+        self
+    }
+
+    pub fn glossary_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.glossary_dir = dir.into();
+        self
+    }
+
+    pub fn search_index_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_index_dir = dir.into();
+        self
+    }
+
+    /// Signing key for session cookies. Derived from `COOKIE_SECRET` when
+    /// not set.
+    pub fn cookie_key(mut self, key: Key) -> Self {
+        self.cookie_key = Some(key);
+        self
+    }
+
+    /// Key for private notes. Read from `NOTES_ENCRYPTION_KEY` when not set.
+    pub fn note_cipher(mut self, cipher: NoteCipher) -> Self {
+        self.note_cipher = Some(cipher);
+        self
+    }
+
+    /// Key for personal data in the database. Read from
+    /// `DATABASE_ENCRYPTION_KEY` when not set.
+    pub fn field_cipher(mut self, cipher: FieldCipher) -> Self {
+        self.field_cipher = Some(cipher);
+        self
+    }
+
+    pub async fn build(self) -> Result<Arc<AppState>, Box<dyn std::error::Error>> {
+        let cookie_key = match self.cookie_key {
+            Some(key) => key,
+            None => {
+                let cookie_secret = env::var("COOKIE_SECRET")
+                    .map_err(|_| "COOKIE_SECRET environment variable must be set")?;
+                Key::derive_from(cookie_secret.as_bytes())
+            }
+        };
+
+        let note_cipher = match self.note_cipher {
+            Some(cipher) => Some(cipher),
+            None => {
+                NoteCipher::from_env().map_err(|e| format!("{}: {}", NOTES_ENCRYPTION_KEY, e))?
+            }
+        };
+
+        let field_cipher = match self.field_cipher {
+            Some(cipher) => Some(cipher),
+            None => FieldCipher::from_env()
+                .map_err(|e| format!("{}: {}", DATABASE_ENCRYPTION_KEY, e))?,
+        };
+        // Rows saved before the key was set are encrypted once, at startup.
+        if let Some(cipher) = &field_cipher {
+            let sealed = seal_saved_preferences(&self.db, cipher).await?;
+            if sealed > 0 {
+                tracing::info!("Encrypted the saved preferences of {} users", sealed);
+            }
+        }
+
+        let config = &self.config;
+        // Content dates are read in the site timezone, so it is set before
+        // any store loads.
+        set_site_timezone(config.site_timezone());
+        if config.bootstrap_content {
+            for path in scaffold_content(&self.article_dir, &self.notes_dir)? {
+                info!("Created {}", path.display());
+            }
+        }
+        if !self.article_dir.exists() {
+            return Err(format!(
+                "Article directory does not exist: {} (run with --init or set bootstrap_content = true to create it)",
+                self.article_dir.display()
+            )
+            .into());
+        }
+
+        if !self.notes_dir.exists() {
+            return Err(format!(
+                "Notes directory does not exist: {} (run with --init or set bootstrap_content = true to create it)",
+                self.notes_dir.display()
+            )
+            .into());
+        }
+
+        let snapshot = |collection| {
+            config
+                .store_snapshots
+                .then(|| store_snapshot_path(&self.data_dir, collection))
+        };
+        let mut article_store = load_store(
+            &self.article_dir,
+            ENABLE_NESTED_CATEGORIES,
+            snapshot(IndexCollection::Articles).as_deref(),
+        )?;
+        article_store.restrict_categories(&config.restricted_categories);
+        if config.warmup_articles > 0 {
+            let warmed = article_store.warm_content_cache(config.warmup_articles);
+            info!("Warmed content cache with {} articles", warmed);
+        }
+        let mut note_store = load_store(
+            &self.notes_dir,
+            true,
+            snapshot(IndexCollection::Notes).as_deref(),
+        )?;
+        note_store.restrict_categories(&config.restricted_categories);
+        match &note_cipher {
+            Some(cipher) => seal_private_notes(&mut note_store, cipher),
+            None => {
+                for note in note_store.query(|n| n.metadata.private, 0, usize::MAX) {
+                    tracing::warn!(
+                        "Private note {} is stored unencrypted; set {} to encrypt it",
+                        note.file_path,
+                        NOTES_ENCRYPTION_KEY
+                    );
                 }
-                all.extend(notes);
-                if let Err(e) = service.index_articles(&all, config.search_index_heap_size) {
-                    tracing::warn!("Failed to index articles: {:?}", e);
+            }
+        }
+        // Pages are optional; a missing directory is an empty collection.
+        let mut page_store = load_store(
+            &self.pages_dir,
+            true,
+            snapshot(IndexCollection::Pages).as_deref(),
+        )?;
+        page_store.restrict_categories(&config.restricted_categories);
+        let cache = ResponseCache::new(&config.response_cache);
+
+        let (search_service, index_tx) = if config.enable_full_text_search {
+            match SearchService::new(&self.search_index_dir) {
+                Ok(service) => {
+                    let mut all = searchable_entries(&article_store, IndexCollection::Articles);
+                    all.extend(searchable_entries(&note_store, IndexCollection::Notes));
+                    all.extend(searchable_entries(&page_store, IndexCollection::Pages));
+                    if let Err(e) = service.index_articles(&all, config.search_index_heap_size) {
+                        tracing::warn!("Failed to index articles: {:?}", e);
+                        (None, None)
+                    } else {
+                        info!("Search index updated successfully!");
+                        let service = Arc::new(service);
+                        let queue = start_index_worker(
+                            Arc::clone(&service),
+                            config.search_index_heap_size,
+                            Duration::from_secs(config.search_commit_debounce_secs),
+                        );
+                        (Some(service), Some(queue))
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to initialize search service: {:?}", e);
                     (None, None)
-                } else {
-                    info!("Search index updated successfully!");
-                    let service = Arc::new(service);
-                    let (tx, mut rx) = mpsc::unbounded_channel();
-                    let search = Arc::clone(&service);
-                    let heap_size = config.search_index_heap_size;
-                    tokio::spawn(async move {
-                        let mut to_index = Vec::new();
-                        let mut to_remove = Vec::new();
-                        while let Some(job) = rx.recv().await {
-                            match job {
-                                IndexJob::Index(a) => to_index.push(a),
-                                IndexJob::Remove(s) => to_remove.push(s),
-                            }
-                            while let Ok(job) = rx.try_recv() {
-                                match job {
-                                    IndexJob::Index(a) => to_index.push(a),
-                                    IndexJob::Remove(s) => to_remove.push(s),
-                                }
-                            }
-                            if let Err(e) = search.apply_batch(&to_index, &to_remove, heap_size) {
-                                tracing::warn!("Failed to process search index batch: {:?}", e);
-                            }
-                            to_index.clear();
-                            to_remove.clear();
-                        }
-                    });
-                    (Some(service), Some(tx))
                 }
             }
-            Err(e) => {
-                tracing::warn!("Failed to initialize search service: {:?}", e);
-                (None, None)
+        } else {
+            (None, None)
+        };
+
+        let scheduler = Scheduler::from_config(&config.jobs)?;
+        let mut job_queue = JobQueue::default();
+        notification_service::register(&mut job_queue);
+        ping_service::register(&mut job_queue);
+        crosspost_service::register(&mut job_queue);
+        let redirects = RedirectTable::load(&self.db).await?;
+
+        Ok(Arc::new(AppState {
+            store: Arc::new(RwLock::new(article_store)),
+            note_store: Arc::new(RwLock::new(note_store)),
+            page_store: Arc::new(RwLock::new(page_store)),
+            config: Arc::clone(config),
+            search_service,
+            index_tx,
+            cache: Arc::new(cache),
+            meta_cache: Cache::builder()
+                .max_capacity(CACHE_MAX_CAPACITY)
+                .time_to_live(Duration::from_secs(META_CACHE_TTL_SECONDS))
+                .build(),
+            cookie_key,
+            db: self.db,
+            error_log: Arc::new(ErrorLog::default()),
+            scheduler: Arc::new(scheduler),
+            job_queue: Arc::new(job_queue),
+            redirects: Arc::new(redirects),
+            snapshots: XmlSnapshots::new(self.data_dir.join(SNAPSHOT_DIR)),
+            data_dir: self.data_dir,
+            taxonomy_dir: self.taxonomy_dir,
+            collections: Collections::load(self.collections_file)?,
+            glossary_dir: self.glossary_dir,
+            note_cipher,
+            field_cipher,
+            load_limits: LoadLimits::new(&config.concurrency),
+            taxonomy_rules: TaxonomyRules::new(&config.taxonomy)?,
+            spell_checker: config
+                .spellcheck
+                .as_ref()
+                .map(SpellChecker::from_config)
+                .transpose()?,
+            http: HttpClient::from_config(&config.http)?,
+            github_rate_limit: GitHubRateLimit::default(),
+            mastodon_rate_limits: MastodonRateLimits::default(),
+            comments_paused: AtomicBool::new(config.comments_paused),
+            preview_branches: RwLock::default(),
+            public_quota: PublicQuota::new(&config.public_api),
+            admin_lockout: AdminLockout::new(&config.admin_lockout),
+            image_info: ImageInfoCache::default(),
+            link_previews: Cache::builder()
+                .max_capacity(CACHE_MAX_CAPACITY)
+                .time_to_live(Duration::from_secs(UNFURL_CACHE_TTL_SECONDS))
+                .build(),
+            presence: Presence::default(),
+            drafts: DraftRooms::default(),
+            sponsors: SponsorCache::default(),
+            search_index_dir: self.search_index_dir,
+            watchers: WatcherStatus::default(),
+            started_at: Utc::now(),
+            trusted_proxies: TrustedProxies::new(&config.trusted_proxies)?,
+            route_layers: Arc::new(RouteLayers::new(&config.route_layers)),
+        }))
+    }
+}
+
+/// Encrypts the bodies of private notes that are still plain text on disk
+/// and reloads them into the store.
+fn seal_private_notes(store: &mut ArticleStore, cipher: &NoteCipher) {
+    let paths: Vec<String> = store
+        .query(|n| n.metadata.private, 0, usize::MAX)
+        .map(|n| n.file_path.clone())
+        .collect();
+    for path in paths {
+        match cipher.seal_file(Path::new(&path)) {
+            Ok(true) => {
+                info!("Encrypted private note {}", path);
+                if let Err(e) = store.update_single_article(&path) {
+                    tracing::warn!("Failed to reload private note {}: {:?}", path, e);
+                }
             }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to encrypt private note {}: {}", path, e),
         }
-    } else {
-        (None, None)
-    };
+    }
+}
 
-    let cookie_secret =
-        env::var("COOKIE_SECRET").map_err(|_| "COOKIE_SECRET environment variable must be set")?;
-    let cookie_key = Key::derive_from(cookie_secret.as_bytes());
-
-    Ok(Arc::new(AppState {
-        store: Arc::new(RwLock::new(article_store)),
-        note_store: Arc::new(RwLock::new(note_store)),
-        config: Arc::clone(config),
-        search_service,
-        index_tx,
-        cache: Arc::new(cache),
-        cookie_key,
-    }))
+pub async fn create_app_state(
+    config: &Arc<Config>,
+    db: DbPool,
+) -> Result<Arc<AppState>, Box<dyn std::error::Error>> {
+    AppState::builder(config, db).build().await
 }
 
+/// Starts the content and collections watchers, each under a supervisor
+/// that restarts it when it fails.
 pub fn start_file_watcher(app_state: Arc<AppState>) {
-    let article_state = Arc::clone(&app_state);
-    tokio::spawn(watch_articles(article_state));
-    tokio::spawn(watch_notes(app_state));
+    for collection in IndexCollection::ALL {
+        let state = Arc::clone(&app_state);
+        tokio::spawn(async move {
+            supervise(
+                &state.watchers,
+                collection.as_str(),
+                RestartBackoff::default(),
+                || watch_directory(Arc::clone(&state), collection),
+            )
+            .await;
+        });
+    }
+    tokio::spawn(async move {
+        supervise(
+            &app_state.watchers,
+            COLLECTIONS_WATCHER,
+            RestartBackoff::default(),
+            || watch_collections(Arc::clone(&app_state)),
+        )
+        .await;
+    });
 }
 
-pub async fn start_server(
-    app_state: Arc<AppState>,
-    config: &Config,
-) -> Result<(), Box<dyn std::error::Error>> {
+/// Builds the complete application: every route plus the middleware stack.
+pub fn build_router(app_state: Arc<AppState>) -> Router {
+    build_router_with(app_state, Router::new())
+}
+
+/// Like [`build_router`], with `extra` routes merged in next to the built-in
+/// ones so they share the state, caching, versioning and envelope layers.
+pub fn build_router_with(app_state: Arc<AppState>, extra: Router<Arc<AppState>>) -> Router {
     let mut app = Router::new()
         .merge(crate::handlers::root::create_router())
+        .merge(crate::handlers::admin::create_router())
+        .merge(crate::handlers::analytics::create_router())
+        .merge(crate::handlers::api_keys::create_router())
+        .merge(crate::handlers::access_codes::create_router())
+        .merge(crate::handlers::deploy_hooks::create_router())
+        .merge(crate::handlers::error_codes::create_router())
+        .merge(crate::handlers::schema::create_router())
+        .merge(crate::handlers::evergreen::create_router())
+        .merge(crate::handlers::fediverse::create_router())
+        .merge(crate::handlers::metrics::create_router())
         .merge(crate::handlers::articles::create_router())
+        .merge(crate::handlers::downloads::create_router())
+        .merge(crate::handlers::attachments::create_router())
+        .merge(crate::handlers::locks::create_router())
+        .merge(crate::handlers::saved_searches::create_router())
+        .merge(crate::handlers::moderation::create_router())
+        .merge(crate::handlers::preview_branches::create_router())
         .merge(crate::handlers::notes::create_router())
+        .merge(crate::handlers::pages::create_router())
+        .merge(crate::handlers::navigation::create_router())
+        .merge(crate::handlers::flags::create_router())
+        .merge(crate::handlers::glossary::create_router())
+        .merge(crate::handlers::health::create_router())
+        .merge(crate::handlers::assets::create_router())
         .merge(crate::handlers::article_versions::create_router())
         .merge(crate::handlers::tags::create_router())
         .merge(crate::handlers::categories::create_router())
+        .merge(crate::handlers::collections::create_router())
+        .merge(crate::handlers::changes::create_router())
         .merge(crate::handlers::search::create_router())
+        .merge(crate::handlers::shortlinks::create_router())
+        .merge(crate::handlers::polls::create_router())
+        .merge(crate::handlers::presence::create_router())
+        .merge(crate::handlers::collab::create_router())
+        .merge(crate::handlers::previews::create_router())
+        .merge(crate::handlers::public::create_router())
+        .merge(crate::handlers::reactions::create_router())
+        .merge(crate::handlers::redirects::create_router())
         .merge(crate::handlers::sitemap::create_router())
-        .merge(crate::handlers::users::create_router());
+        .merge(crate::handlers::sponsors::create_router())
+        .merge(crate::handlers::feeds::create_router())
+        .merge(crate::handlers::home::create_router())
+        .merge(crate::handlers::stats::create_router())
+        .merge(crate::handlers::summaries::create_router())
+        .merge(crate::handlers::sync::create_router())
+        .merge(crate::handlers::translations::create_router())
+        .merge(crate::handlers::trash::create_router())
+        .merge(crate::handlers::undo::create_router())
+        .merge(crate::handlers::unfurl::create_router())
+        .merge(crate::handlers::users::create_router())
+        .merge(crate::handlers::version::create_router());
 
-    if config.comments {
+    if app_state.config.comments {
         app = app
             .merge(crate::handlers::auth::create_router())
-            .merge(crate::handlers::comments::create_router());
+            .merge(crate::handlers::comments::create_router())
+            .merge(crate::handlers::guestbook::create_router())
+            .merge(crate::handlers::suggestions::create_router());
+    }
+
+    app = app.merge(extra);
+    if app_state.config.read_only {
+        app = app.layer(middleware::from_fn(refuse_writes));
     }
 
     let app = app
-        .layer(middleware::from_fn(log_errors))
-        .layer(ResponseCacheLayer::new(app_state.cache.clone()))
-        .with_state(app_state);
+        .layer(catch_panics())
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            log_errors,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            default_body_limit,
+        ))
+        .layer(DefaultBodyLimit::max(app_state.config.body_limits.default))
+        // `require_author` and friends read the state from extensions.
+        .layer(Extension(Arc::clone(&app_state)))
+        .with_state(Arc::clone(&app_state));
+
+    // Rewriting `/api/v1` paths has to happen before routing, so the routes
+    // are nested as a fallback and these layers wrap them from the outside.
+    // The cache sits outside the rewrite to keep versions under separate
+    // keys, API keys are checked outside the cache so cached responses are
+    // counted too. The public API is hardened outside both, so its quota
+    // counts cache hits and API keys are dropped before they are checked.
+    // The `[[route_layers]]` of other routes apply just outside it. The
+    // envelope sits outside all of them so every response gets a fresh
+    // request id. Registered redirects are answered before any of that, and
+    // compression, when a group asks for it, wraps the finished body.
+    // Layers added last run first.
+    let router = Router::new()
+        .fallback_service(app)
+        .layer(middleware::map_request(rewrite_versioned_path))
+        .layer(ResponseCacheLayer::new(
+            app_state.cache.clone(),
+            Arc::clone(&app_state.route_layers),
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            authenticate_api_key,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            harden_public_api,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            apply_route_layers,
+        ))
+        .layer(middleware::from_fn(envelope))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            deprecation_headers,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&app_state),
+            serve_redirects,
+        ));
+    if app_state.route_layers.compresses() {
+        router.layer(compression_layer())
+    } else {
+        router
+    }
+}
 
+pub async fn start_server(
+    app: Router,
+    settings: &ServerConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
     let addr: SocketAddr = SERVER_ADDR.parse()?;
     info!("Starting server on http://{}", addr);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    serve(listener, app, settings, shutdown_signal()).await;
     Ok(())
 }
 
-async fn log_errors(req: Request<Body>, next: Next) -> Response {
-    let res = next.run(req).await;
-    if res.status().is_server_error() {
-        error!("Internal server error: {}", res.status());
-    }
-    res
-}
-
-async fn watch_directory<F>(dir: &'static str, state: Arc<AppState>, store_ref: F, is_notes: bool)
-where
-    F: Fn(&AppState) -> &RwLock<ArticleStore> + Send + Sync + 'static,
-{
-    let (tx, mut rx) = mpsc::unbounded_channel();
-
-    let tx_watcher = tx.clone();
-    let mut watcher =
-        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
-            if let Ok(event) = res
-                && (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
-            {
-                if tx_watcher.send(()).is_err() {
-                    error!("File change notification receiver dropped");
-                }
+/// Resolves on Ctrl-C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
             }
-        }) {
-            Ok(w) => w,
             Err(e) => {
-                error!("Failed to initialize file watcher: {:?}", e);
-                return;
+                error!("Failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
             }
-        };
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    if let Err(e) = watcher.watch(std::path::Path::new(dir), RecursiveMode::Recursive) {
-        error!("Failed to watch directory '{}': {:?}", dir, e);
-        return;
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
     }
+    info!("Shutting down");
+}
 
-    info!("Hot reloading enable for '{}'", dir);
+/// Largest error body [`with_request_id`] rewrites.
+const MAX_ERROR_BODY_SIZE: usize = 64 * 1024;
 
-    let nested_categories = if is_notes {
-        true
-    } else {
-        ENABLE_NESTED_CATEGORIES
+/// Records server errors, reports panics and `500`s to `[error_reporting]`
+/// and adds the request id to their JSON bodies.
+async fn log_errors(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let request_id = req.extensions().get::<RequestId>().map(|id| id.0.clone());
+    let ClientIp(client) = ClientIp::of(req.headers(), req.extensions(), &state.trusted_proxies);
+    let res = next.run(req).await;
+    let status = res.status();
+    if !status.is_server_error() {
+        return res;
+    }
+    let panic = res.extensions().get::<Panicked>().map(|p| p.0.clone());
+    match &panic {
+        Some(message) => error!(
+            "Handler panicked on {} {} from {}: {}",
+            method, path, client, message
+        ),
+        None => error!(
+            "Internal server error on {} {} from {}: {}",
+            method, path, client, status
+        ),
+    }
+    state
+        .error_log
+        .record(&method, &path, status.as_u16(), request_id.as_deref());
+    if status == StatusCode::INTERNAL_SERVER_ERROR {
+        let error = res.extensions().get::<ErrorBody>();
+        let event = ErrorEvent {
+            message: panic
+                .clone()
+                .or_else(|| error.map(|e| e.message.clone()))
+                .unwrap_or_else(|| status.to_string()),
+            panic: panic.is_some(),
+            error_code: error.map(|e| e.code.as_str().to_string()),
+            method,
+            path,
+            request_id: request_id.clone(),
+        };
+        let state = Arc::clone(&state);
+        tokio::spawn(async move { report_error(&state, event).await });
+    }
+    match request_id {
+        Some(id) => with_request_id(res, id).await,
+        None => res,
+    }
+}
+
+/// Adds `request_id` to a JSON error body so it can be quoted back.
+async fn with_request_id(res: Response, request_id: String) -> Response {
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if !is_json {
+        return res;
+    }
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, MAX_ERROR_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut body)) => {
+            body.insert("request_id".to_string(), request_id.into());
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Body::from(serde_json::Value::Object(body).to_string())
+        }
+        _ => Body::from(bytes),
     };
-    let prefix = if is_notes { "notes/" } else { "" };
-    let entity = if is_notes { "note" } else { "article" };
-    let entity_plural = if is_notes { "Notes" } else { "Articles" };
+    Response::from_parts(parts, body)
+}
+
+/// Keeps `collection` in sync with its directory. Returns an error when
+/// the watch cannot be set up or breaks, e.g. when the directory is
+/// removed, for [`supervise`] to start it again.
+async fn watch_directory(state: Arc<AppState>, collection: IndexCollection) -> Result<(), String> {
+    let store = state.store_of(collection);
+    let dir = store.read().await.root().to_path_buf();
+    if collection == IndexCollection::Pages && !dir.exists() {
+        return Ok(());
+    }
+    let (tx, mut events) = file_events(QUIET_PERIOD, MAX_BATCH_WINDOW);
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let delivered = match res {
+            Ok(event)
+                if (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
+                    && !event.paths.iter().all(|path| is_temp_file(path)) =>
+            {
+                tx.notify()
+            }
+            Ok(_) => true,
+            Err(e) => tx.fail(e.to_string()),
+        };
+        if !delivered {
+            error!("File change notification receiver dropped");
+        }
+    })
+    .map_err(|e| format!("failed to initialize file watcher: {}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("failed to watch directory '{}': {}", dir.display(), e))?;
+    let _watching = state.watchers.watching(collection.as_str());
 
-    while rx.recv().await.is_some() {
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    info!("Hot reloading enable for '{}'", dir.display());
 
+    while events.next_burst().await {
+        if let Some(e) = events.failure() {
+            return Err(e);
+        }
+        if !dir.is_dir() {
+            return Err(format!("'{}' is gone", dir.display()));
+        }
         info!("File change detected, performing incremental update...");
-        let mut store_guard = store_ref(&state).write().await;
+        let mut store_guard = store.write().await;
 
-        let changes = match store_guard.detect_file_changes(dir, nested_categories) {
+        let changes = match store_guard.detect_file_changes() {
             Ok(c) => c,
             Err(e) => {
                 tracing::error!("Error detecting file changes: {:?}", e);
@@ -226,18 +839,21 @@ where
                     .query(|a| a.file_path == change.path, 0, usize::MAX)
                     .next()
                 {
-                    let slug = if is_notes {
-                        format!("{}{}", prefix, article.slug_with_category())
-                    } else {
-                        article.slug.clone()
-                    };
-                    removed_map.insert(change.path.clone(), slug);
+                    removed_map.insert(
+                        change.path.clone(),
+                        (collection.slug_of(article), article.metadata.title.clone()),
+                    );
                 }
             }
         }
 
-        match store_guard.incremental_update(dir, nested_categories) {
+        match store_guard.incremental_update() {
             Ok(true) => {
+                if collection == IndexCollection::Notes
+                    && let Some(cipher) = &state.note_cipher
+                {
+                    seal_private_notes(&mut store_guard, cipher);
+                }
                 if let Some(tx) = &state.index_tx {
                     for change in &changes {
                         match change.change_type {
@@ -246,28 +862,26 @@ where
                                     .query(|a| a.file_path == change.path, 0, usize::MAX)
                                     .next()
                                 {
+                                    let slug = collection.slug_of(article);
+                                    if article.metadata.private {
+                                        let _ = tx.send(IndexJob::Remove(slug));
+                                        continue;
+                                    }
                                     match store_guard.load_content_for(article) {
                                         Ok(content) => {
-                                            let slug = if is_notes {
-                                                format!(
-                                                    "{}{}",
-                                                    prefix,
-                                                    article.slug_with_category()
-                                                )
-                                            } else {
-                                                article.slug.clone()
-                                            };
                                             let article_content = ArticleContent {
                                                 slug,
                                                 metadata: article.metadata.clone(),
                                                 content,
+                                                edit_url: None,
                                             };
-                                            let _ = tx.send(IndexJob::Index(article_content));
+                                            let _ =
+                                                tx.send(IndexJob::Index(Box::new(article_content)));
                                         }
                                         Err(e) => {
                                             tracing::warn!(
                                                 "Failed to load content for {} {}: {:?}",
-                                                entity,
+                                                collection.as_str(),
                                                 article.slug,
                                                 e
                                             );
@@ -276,7 +890,7 @@ where
                                 }
                             }
                             FileChange::Removed => {
-                                if let Some(slug) = removed_map.get(&change.path) {
+                                if let Some((slug, _)) = removed_map.get(&change.path) {
                                     let _ = tx.send(IndexJob::Remove(slug.clone()));
                                 }
                             }
@@ -284,8 +898,12 @@ where
                     }
                 }
 
+                if collection == IndexCollection::Articles {
+                    record_deletions(&state, &store_guard, removed_map.into_values()).await;
+                }
                 state.cache.invalidate_all();
-                info!("{} updated incrementally!", entity_plural);
+                record_content_change(&state).await;
+                info!("Updated {} incrementally!", collection.as_str());
             }
             Ok(false) => {
                 tracing::debug!("No file changes detected, skipping update");
@@ -293,12 +911,13 @@ where
             Err(e) => {
                 tracing::error!("Error during incremental update: {:?}", e);
                 info!("Falling back to full reload...");
-                match ArticleStore::new(dir, nested_categories) {
+                match store_guard.reload() {
                     Ok(new_store) => {
                         *store_guard = new_store;
 
                         reindex_all_content(&state).await;
                         state.cache.invalidate_all();
+                        record_content_change(&state).await;
 
                         info!("Full reload completed successfully!");
                     }
@@ -309,28 +928,193 @@ where
             }
         }
     }
+    Err("file events stopped".to_string())
+}
+
+/// Name of the `collections.yaml` watcher in [`WatcherStatus`].
+const COLLECTIONS_WATCHER: &str = "collections";
+
+/// Reloads `collections.yaml` when it is written. The parent directory is
+/// watched, as editors often replace the file instead of writing it in
+/// place.
+async fn watch_collections(state: Arc<AppState>) -> Result<(), String> {
+    let path = state.collections.path().to_path_buf();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let Some(file_name) = path.file_name().map(|name| name.to_os_string()) else {
+        return Ok(());
+    };
+    let (tx, mut events) = file_events(QUIET_PERIOD, MAX_BATCH_WINDOW);
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let delivered = match res {
+            Ok(event)
+                if (event.kind.is_modify() || event.kind.is_create() || event.kind.is_remove())
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == Some(file_name.as_os_str())) =>
+            {
+                tx.notify()
+            }
+            Ok(_) => true,
+            Err(e) => tx.fail(e.to_string()),
+        };
+        if !delivered {
+            error!("File change notification receiver dropped");
+        }
+    })
+    .map_err(|e| format!("failed to initialize collections watcher: {}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("failed to watch directory '{}': {}", dir.display(), e))?;
+    let _watching = state.watchers.watching(COLLECTIONS_WATCHER);
+
+    while events.next_burst().await {
+        if let Some(e) = events.failure() {
+            return Err(e);
+        }
+        match state.collections.reload() {
+            Ok(()) => {
+                state.cache.invalidate_all();
+                info!("Reloaded {}", path.display());
+            }
+            Err(e) => {
+                tracing::error!("Keeping previous collections: {}", e);
+            }
+        }
+    }
+    Err("file events stopped".to_string())
+}
+
+/// Notes that content changed, so the admin overview can tell when the
+/// public site lags behind, and has the feed and sitemap snapshots
+/// rendered again. Failing to record it is not worth failing the
+/// write over.
+pub async fn record_content_change(state: &AppState) {
+    state.snapshots.content_changed();
+    if let Err(e) = record_event(&state.db, CONTENT_CHANGED).await {
+        tracing::warn!("Failed to record content change: {:?}", e);
+    }
+}
+
+/// Records the articles whose files went away in the audit log, for
+/// `/api/admin/content/diff`. An article whose file merely moved is still
+/// in `store` and is not recorded.
+pub(crate) async fn record_deletions(
+    state: &AppState,
+    store: &ArticleStore,
+    removed: impl Iterator<Item = (String, String)>,
+) {
+    for (slug, title) in removed {
+        if store.get_by_slug(&slug).is_some() {
+            continue;
+        }
+        if let Err(e) =
+            audit_service::record_audit(&state.db, ARTICLE_DELETED, &slug, true, Some(&title))
+                .await
+        {
+            tracing::warn!("Failed to record the deletion of {}: {:?}", slug, e);
+        }
+    }
+}
+
+/// Reloads one collection from disk, as the watcher does when an
+/// incremental update fails, then reindexes search and clears the response
+/// cache. Returns the number of entries loaded.
+pub async fn rebuild_store(
+    state: &Arc<AppState>,
+    collection: IndexCollection,
+) -> Result<usize, LoadError> {
+    let store = state.store_of(collection);
+    let count = {
+        let mut store_guard = store.write().await;
+        let mut new_store = store_guard.reload()?;
+        if collection == IndexCollection::Notes
+            && let Some(cipher) = &state.note_cipher
+        {
+            seal_private_notes(&mut new_store, cipher);
+        }
+        *store_guard = new_store;
+        store_guard.query(|_| true, 0, usize::MAX).count()
+    };
+
+    reindex_all_content(state).await;
+    state.cache.invalidate_all();
+    state.snapshots.content_changed();
+    info!(
+        "Rebuilt {} store with {} entries",
+        collection.as_str(),
+        count
+    );
+    Ok(count)
+}
+
+/// Directory under the data directory holding the store snapshots.
+const STORE_SNAPSHOT_DIR: &str = "store-snapshots";
+
+fn store_snapshot_path(data_dir: &Path, collection: IndexCollection) -> PathBuf {
+    data_dir
+        .join(STORE_SNAPSHOT_DIR)
+        .join(format!("{}.json", collection.as_str()))
+}
+
+/// Restores a store from its snapshot when one is given and usable, and
+/// loads it from the files otherwise.
+fn load_store(dir: &Path, nested: bool, snapshot: Option<&Path>) -> Result<ArticleStore, LoadError> {
+    if let Some(path) = snapshot
+        && let Some(store) = ArticleStore::from_snapshot(path, dir, nested)
+    {
+        info!("Restored {} from {}", dir.display(), path.display());
+        return Ok(store);
+    }
+    ArticleStore::new(dir, nested)
+}
+
+/// Saves the stores for a warm start, when `store_snapshots` is on.
+/// Failing is only worth a warning: the next start parses the files.
+pub async fn save_store_snapshots(state: &AppState) {
+    if !state.config.store_snapshots {
+        return;
+    }
+    for collection in IndexCollection::ALL {
+        let store = state.store_of(collection).read().await;
+        let path = store_snapshot_path(&state.data_dir, collection);
+        if let Err(e) = store.save_snapshot(&path) {
+            tracing::warn!("Failed to save snapshot {}: {:?}", path.display(), e);
+        }
+    }
 }
 
-async fn watch_articles(state: Arc<AppState>) {
-    watch_directory(ARTICLE_DIR, state, |s| &s.store, false).await;
+/// The entries of `store` as the search index holds them for
+/// `collection`: keyed by `category/slug`, with note and page slugs under
+/// their collection.
+fn searchable_entries(store: &ArticleStore, collection: IndexCollection) -> Vec<ArticleContent> {
+    let mut entries = store.load_full_articles();
+    if collection != IndexCollection::Articles {
+        for entry in &mut entries {
+            entry.slug = format!("{}/{}", collection.as_str(), entry.slug);
+        }
+    }
+    entries
 }
 
-async fn watch_notes(state: Arc<AppState>) {
-    watch_directory(NOTES_DIR, state, |s| &s.note_store, true).await;
+/// Every collection as the search index should hold it.
+pub async fn searchable_content(state: &AppState) -> Vec<ArticleContent> {
+    let mut all = Vec::new();
+    for collection in IndexCollection::ALL {
+        let store = state.store_of(collection).read().await;
+        all.extend(searchable_entries(&store, collection));
+    }
+    all
 }
 
 pub async fn reindex_all_content(state: &Arc<AppState>) {
     if let Some(ref search_service) = state.search_service {
-        let store = state.store.read().await;
-        let mut all = store.load_full_articles();
-        drop(store);
-        let notes_store = state.note_store.read().await;
-        let mut notes = notes_store.load_full_articles();
-        drop(notes_store);
-        for n in &mut notes {
-            n.slug = format!("notes/{}", n.slug_with_category());
-        }
-        all.extend(notes);
+        let all = searchable_content(state).await;
         if let Err(e) = search_service.index_articles(&all, state.config.search_index_heap_size) {
             tracing::warn!("Failed to reindex articles for search: {:?}", e);
         } else {
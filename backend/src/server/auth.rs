@@ -1,99 +1,119 @@
 use crate::config::get_admin_token_hash;
-use crate::handlers::error::{AppError, ERR_FORBIDDEN, ERR_UNAUTHORIZED};
+use crate::handlers::auth::session_user;
+use crate::handlers::error::{AppError, ErrorCode};
 use crate::models::user::User;
+use crate::server::admin_lockout::report_failure;
 use crate::server::app::AppState;
+use crate::server::client_ip::ClientIp;
 use axum::body::Body;
-use axum::http::{Request, header::AUTHORIZATION, header::COOKIE};
+use axum::http::header::{AUTHORIZATION, RETRY_AFTER, SEC_WEBSOCKET_PROTOCOL, UPGRADE};
+use axum::http::{HeaderMap, HeaderValue, Request};
 use axum::middleware::Next;
-use axum::response::Response;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::SignedCookieJar;
 use cookie::Key;
 use sha2::{Digest, Sha256};
 use subtle::ConstantTimeEq;
 use std::sync::Arc;
+use std::time::Instant;
 
-// Helper function to extract user from cookie header
-fn get_user_from_cookie_header(req: &Request<Body>, _key: &Key) -> Result<User, AppError> {
-    use axum_extra::extract::cookie::Cookie;
-
-    let cookie_header = req.headers().get(COOKIE)
-        .ok_or(AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
-            message: "No cookies found".to_string(),
-        })?;
+// Helper function to extract the user from the signed session cookie
+pub(crate) fn get_user_from_cookie_header(
+    req: &Request<Body>,
+    key: &Key,
+) -> Result<User, AppError> {
+    let jar = SignedCookieJar::from_headers(req.headers(), key.clone());
+    session_user(&jar)
+}
 
-    let cookie_str = cookie_header.to_str()
-        .map_err(|_| AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
-            message: "Invalid cookie format".to_string(),
-        })?;
+/// WebSocket subprotocol a browser offers, followed by the admin token, to
+/// authenticate an upgrade: `new WebSocket(url, ["scribe-admin", token])`.
+/// Browsers cannot set `Authorization` on a WebSocket.
+pub const ADMIN_TOKEN_PROTOCOL: &str = "scribe-admin";
 
-    // Parse cookies manually and look for user_session
-    for cookie_pair in cookie_str.split(';') {
-        let cookie_pair = cookie_pair.trim();
-        if let Some(cookie) = Cookie::parse(cookie_pair).ok() {
-            if cookie.name() == "user_session" {
-                // This is a simplified version - in production you'd want proper signing verification
-                let user_json = cookie.value();
-                return serde_json::from_str(user_json)
-                    .map_err(|_| AppError::Unauthorized {
-                        code: ERR_UNAUTHORIZED,
-                        message: "Invalid session data".to_string(),
-                    });
-            }
-        }
+/// The admin token from `Authorization`, or for WebSocket upgrades from the
+/// entry following `ADMIN_TOKEN_PROTOCOL` in `Sec-WebSocket-Protocol`.
+fn admin_token(headers: &HeaderMap) -> Option<&str> {
+    if let Some(token) = headers.get(AUTHORIZATION).and_then(|h| h.to_str().ok()) {
+        return Some(token);
     }
-
-    Err(AppError::Unauthorized {
-        code: ERR_UNAUTHORIZED,
-        message: "No user session found".to_string(),
-    })
+    let upgrade = headers.get(UPGRADE).and_then(|h| h.to_str().ok())?;
+    if !upgrade.eq_ignore_ascii_case("websocket") {
+        return None;
+    }
+    let mut protocols = headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim);
+    protocols.find(|p| *p == ADMIN_TOKEN_PROTOCOL)?;
+    protocols.next().filter(|token| !token.is_empty())
 }
 
 pub async fn require_admin(req: Request<Body>, next: Next) -> Result<Response, AppError> {
-    let auth_header = req
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok());
-
-    let token = match auth_header {
+    let token = match admin_token(req.headers()) {
         Some(t) => t,
         None => {
             return Err(AppError::Unauthorized {
-                code: ERR_UNAUTHORIZED,
+                code: ErrorCode::Unauthorized,
                 message: "Missing authorization token".to_string(),
             });
         }
     };
 
+    // Clients that keep guessing are turned away before the token is
+    // checked. The app state is inserted into request extensions by
+    // `build_router`.
+    let state = req.extensions().get::<Arc<AppState>>().cloned();
+    let ClientIp(client) = ClientIp::of_request(req.headers(), req.extensions());
+    if let Some(state) = &state
+        && let Some(retry_after) = state.admin_lockout.locked_for(&client, Instant::now())
+    {
+        let mut res = AppError::TooManyRequests {
+            code: ErrorCode::AdminLockedOut,
+            message: format!("Too many wrong admin tokens, try again in {}s", retry_after),
+        }
+        .into_response();
+        res.headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        return Ok(res);
+    }
+
     let stored_hash = get_admin_token_hash().expect("ADMIN_TOKEN_HASH must be set");
     let provided_hash: [u8; 32] = Sha256::digest(token.as_bytes()).into();
 
     if provided_hash.ct_eq(&stored_hash).unwrap_u8() == 1 {
+        if let Some(state) = &state {
+            state.admin_lockout.record_success(&client);
+        }
         Ok(next.run(req).await)
     } else {
+        if let Some(state) = &state {
+            report_failure(state, &client).await;
+        }
         Err(AppError::Forbidden {
-            code: ERR_FORBIDDEN,
+            code: ErrorCode::Forbidden,
             message: "Invalid admin token".to_string(),
         })
     }
 }
 
 pub async fn require_author(req: Request<Body>, next: Next) -> Result<Response, AppError> {
-    // For now, let's use a simpler approach - extract from extensions
-    // This will be populated by the app state middleware
+    // The app state is inserted into request extensions by `build_router`.
     if let Some(app_state) = req.extensions().get::<Arc<AppState>>() {
         let user = get_user_from_cookie_header(&req, &app_state.cookie_key)?;
         if user.is_author() {
             Ok(next.run(req).await)
         } else {
             Err(AppError::Forbidden {
-                code: ERR_FORBIDDEN,
+                code: ErrorCode::Forbidden,
                 message: "Author role required".to_string(),
             })
         }
     } else {
         Err(AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
+            code: ErrorCode::Unauthorized,
             message: "Application state not found".to_string(),
         })
     }
@@ -105,7 +125,7 @@ pub async fn require_authenticated(req: Request<Body>, next: Next) -> Result<Res
         Ok(next.run(req).await)
     } else {
         Err(AppError::Unauthorized {
-            code: ERR_UNAUTHORIZED,
+            code: ErrorCode::Unauthorized,
             message: "Application state not found".to_string(),
         })
     }
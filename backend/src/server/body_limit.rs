@@ -0,0 +1,98 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use axum::Extension;
+use axum::body::Body;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{Request, StatusCode, header::CONTENT_LENGTH};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use http_body_util::Limited;
+use std::sync::Arc;
+
+/// Routes sharing a request body limit. Routes outside every group get the
+/// configured default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyGroup {
+    /// Creating and updating articles.
+    Articles,
+    /// Comments, guestbook entries and reactions from visitors.
+    Comments,
+    /// Comment exports and search index snapshots uploaded by admins.
+    Imports,
+    /// Images and other files uploaded by admins.
+    Assets,
+}
+
+/// Marks a 413 answered with the limit of a route group, so the default
+/// limit does not overwrite it.
+#[derive(Clone, Copy)]
+struct GroupLimitExceeded;
+
+pub fn payload_too_large(limit: usize) -> AppError {
+    AppError::PayloadTooLarge {
+        code: ErrorCode::PayloadTooLarge,
+        message: format!("Request body exceeds the limit of {} bytes", limit),
+    }
+}
+
+fn content_length(req: &Request<Body>) -> Option<usize> {
+    req.headers()
+        .get(CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+async fn limit_body(
+    State(group): State<BodyGroup>,
+    Extension(state): Extension<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let limit = state.config.body_limits.limit(group);
+    let response = if content_length(&req).is_some_and(|length| length > limit) {
+        None
+    } else {
+        let req = req.map(|body| Body::new(Limited::new(body, limit)));
+        Some(next.run(req).await)
+    };
+    match response {
+        // Extractors report a body cut off at the limit with their own 413.
+        Some(response) if response.status() != StatusCode::PAYLOAD_TOO_LARGE => response,
+        _ => {
+            let mut response = payload_too_large(limit).into_response();
+            response.extensions_mut().insert(GroupLimitExceeded);
+            response
+        }
+    }
+}
+
+/// Applies the limit of `group` to `route`. Bodies announced as larger are
+/// rejected before they are read; others are cut off once they pass the
+/// limit.
+pub fn with_body_limit(
+    route: MethodRouter<Arc<AppState>>,
+    group: BodyGroup,
+) -> MethodRouter<Arc<AppState>> {
+    route
+        .route_layer(middleware::from_fn_with_state(group, limit_body))
+        .route_layer(DefaultBodyLimit::disable())
+}
+
+/// Replaces the plain-text 413 that extractors return for bodies over the
+/// default limit with a structured error.
+pub async fn default_body_limit(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let response = next.run(req).await;
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE
+        && response.extensions().get::<GroupLimitExceeded>().is_none()
+    {
+        return Err(payload_too_large(state.config.body_limits.default));
+    }
+    Ok(response)
+}
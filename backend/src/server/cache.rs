@@ -1,7 +1,9 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use axum::body::{Body, to_bytes};
 use axum::http::{self, Method, Request, Response};
@@ -9,25 +11,145 @@ use bytes::Bytes;
 use moka2::future::Cache;
 use tower::{Layer, Service};
 
-// Routes that should never be cached (e.g. authentication endpoints).
-const CACHE_BYPASS_PATHS: &[&str] = &["/api/auth/"];
+use crate::config::ResponseCacheConfig;
+use crate::server::route_layers::RouteLayers;
+use crate::server::versioning::unversioned_path;
+
+// Routes that should never be cached (e.g. authentication endpoints,
+// shortlinks, which count every click, previews, which can be revoked or
+// commented on at any time, and the change feed and readiness probe, which
+// must not lag behind). Feeds and the sitemap are served from their own snapshots,
+// which answer conditional requests, and assets are served from disk with
+// range and conditional request support.
+const CACHE_BYPASS_PATHS: &[&str] = &[
+    "/api/auth/",
+    "/api/assets/",
+    "/api/changes",
+    "/s/",
+    "/api/previews/",
+    "/feed/",
+    "/sitemap.xml",
+    "/readyz",
+];
+// File downloads can outgrow the cache, and article counters are cached
+// separately for a much shorter time.
+const CACHE_BYPASS_SUFFIXES: &[&str] = &["/download", "/downloads", "/bundle.zip", "/meta"];
+// Attached files, whose downloads are counted.
+const CACHE_BYPASS_SEGMENTS: &[&str] = &["/downloads/"];
+/// Streamed responses are passed through instead of being buffered.
+const STREAMING_CONTENT_TYPES: &[&str] = &["application/x-ndjson", "text/event-stream"];
 /// Maximum response body size that will be cached (1 MiB).
 const MAX_CACHED_RESPONSE_SIZE: usize = 1 * 1024 * 1024;
 
 #[derive(Clone)]
 pub struct CachedResponse {
     pub body: Bytes,
-    pub content_type: Option<String>,
+    /// Response headers other than `Set-Cookie`, such as the ones an
+    /// article's front matter adds.
+    pub headers: http::HeaderMap,
+    /// Kept so outer layers see the same extensions (e.g. pagination) on a
+    /// cache hit as on a miss.
+    pub extensions: http::Extensions,
+}
+
+/// Cached responses of one class of routes, with its own lifetime and
+/// size, and how often it answered.
+struct CacheClass {
+    name: String,
+    prefix: String,
+    cache: Cache<String, CachedResponse>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheClass {
+    fn new(name: &str, prefix: &str, ttl_secs: u64, capacity: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+            cache: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(Duration::from_secs(ttl_secs))
+                .build(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Usage of a class of the response cache since startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheClassStats {
+    pub name: String,
+    pub entries: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// The response cache, split into the classes of `[response_cache]` and a
+/// `default` class for every other route.
+pub struct ResponseCache {
+    /// Longest prefix first, so the first match is the most specific.
+    classes: Vec<CacheClass>,
+    default: CacheClass,
+}
+
+impl ResponseCache {
+    pub fn new(config: &ResponseCacheConfig) -> Self {
+        let mut classes: Vec<CacheClass> = config
+            .classes
+            .iter()
+            .map(|(name, class)| {
+                CacheClass::new(name, &class.prefix, class.ttl_secs, class.capacity)
+            })
+            .collect();
+        classes.sort_by_key(|class| std::cmp::Reverse(class.prefix.len()));
+        Self {
+            classes,
+            default: CacheClass::new("default", "/", config.ttl_secs, config.capacity),
+        }
+    }
+
+    fn class_for(&self, path: &str) -> &CacheClass {
+        self.classes
+            .iter()
+            .find(|class| path.starts_with(&class.prefix))
+            .unwrap_or(&self.default)
+    }
+
+    pub fn invalidate_all(&self) {
+        for class in self.classes.iter().chain([&self.default]) {
+            class.cache.invalidate_all();
+        }
+    }
+
+    /// Entries, hits and misses per class, `default` last.
+    pub fn stats(&self) -> Vec<CacheClassStats> {
+        self.classes
+            .iter()
+            .chain([&self.default])
+            .map(|class| CacheClassStats {
+                name: class.name.clone(),
+                entries: class.cache.entry_count(),
+                hits: class.hits.load(Ordering::Relaxed),
+                misses: class.misses.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone)]
 pub struct ResponseCacheLayer {
-    cache: Arc<Cache<String, CachedResponse>>,
+    cache: Arc<ResponseCache>,
+    route_layers: Arc<RouteLayers>,
 }
 
 impl ResponseCacheLayer {
-    pub fn new(cache: Arc<Cache<String, CachedResponse>>) -> Self {
-        Self { cache }
+    pub fn new(cache: Arc<ResponseCache>, route_layers: Arc<RouteLayers>) -> Self {
+        Self {
+            cache,
+            route_layers,
+        }
     }
 }
 
@@ -38,6 +160,7 @@ impl<S> Layer<S> for ResponseCacheLayer {
         ResponseCacheService {
             inner,
             cache: self.cache.clone(),
+            route_layers: self.route_layers.clone(),
         }
     }
 }
@@ -45,7 +168,8 @@ impl<S> Layer<S> for ResponseCacheLayer {
 #[derive(Clone)]
 pub struct ResponseCacheService<S> {
     inner: S,
-    cache: Arc<Cache<String, CachedResponse>>,
+    cache: Arc<ResponseCache>,
+    route_layers: Arc<RouteLayers>,
 }
 
 impl<S> Service<Request<Body>> for ResponseCacheService<S>
@@ -74,8 +198,16 @@ where
             || req.headers().contains_key(http::header::COOKIE);
 
         // Bypass cache if credentials are present or the path is sensitive to
-        // avoid leaking user-specific responses.
-        if has_auth || CACHE_BYPASS_PATHS.iter().any(|p| path.starts_with(p)) {
+        // avoid leaking user-specific responses, or if `[[route_layers]]`
+        // turns caching off for it. The cache sits outside the `/api/v1`
+        // rewrite, so paths are matched as the routes know them.
+        let route = unversioned_path(&path);
+        if has_auth
+            || !self.route_layers.cache(&route)
+            || CACHE_BYPASS_PATHS.iter().any(|p| route.starts_with(p))
+            || CACHE_BYPASS_SUFFIXES.iter().any(|s| route.ends_with(s))
+            || CACHE_BYPASS_SEGMENTS.iter().any(|s| route.contains(s))
+        {
             let fut = self.inner.call(req);
             return Box::pin(async move { fut.await });
         }
@@ -91,17 +223,30 @@ where
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
-            if let Some(cached) = cache.get(&cache_key).await {
-                let CachedResponse { body, content_type } = cached;
-                let mut builder = Response::builder();
-                if let Some(ct) = content_type {
-                    builder = builder.header(axum::http::header::CONTENT_TYPE, ct);
-                }
-                let resp = builder.body(Body::from(body)).unwrap();
+            let class = cache.class_for(&path);
+            if let Some(cached) = class.cache.get(&cache_key).await {
+                class.hits.fetch_add(1, Ordering::Relaxed);
+                let CachedResponse {
+                    body,
+                    headers,
+                    extensions,
+                } = cached;
+                let mut resp = Response::new(Body::from(body));
+                *resp.headers_mut() = headers;
+                *resp.extensions_mut() = extensions;
                 return Ok(resp);
             }
 
+            class.misses.fetch_add(1, Ordering::Relaxed);
             let resp = inner.call(req).await?;
+            let streamed = resp
+                .headers()
+                .get(axum::http::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|ct| STREAMING_CONTENT_TYPES.iter().any(|s| ct.starts_with(s)));
+            if streamed {
+                return Ok(resp);
+            }
             let (parts, body) = resp.into_parts();
             let bytes = match to_bytes(body, MAX_CACHED_RESPONSE_SIZE).await {
                 Ok(b) => b,
@@ -112,18 +257,19 @@ where
                 }
             };
 
-            if parts.status.is_success() && bytes.len() <= MAX_CACHED_RESPONSE_SIZE {
-                let content_type = parts
-                    .headers
-                    .get(axum::http::header::CONTENT_TYPE)
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string());
-                cache
+            // Partial content is never cached, or it would be served as the
+            // whole body.
+            if parts.status == http::StatusCode::OK && bytes.len() <= MAX_CACHED_RESPONSE_SIZE {
+                let mut headers = parts.headers.clone();
+                headers.remove(http::header::SET_COOKIE);
+                class
+                    .cache
                     .insert(
                         cache_key,
                         CachedResponse {
                             body: bytes.clone(),
-                            content_type,
+                            headers,
+                            extensions: parts.extensions.clone(),
                         },
                     )
                     .await;
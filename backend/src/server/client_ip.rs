@@ -0,0 +1,179 @@
+use crate::config::IpStorage;
+use crate::server::app::AppState;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap};
+use ipnet::IpNet;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+/// The reverse proxies in front of the server, from `trusted_proxies`.
+/// Only their `X-Forwarded-For` headers are believed.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    /// Parses addresses and CIDR ranges such as `10.0.0.0/8`.
+    pub fn new(entries: &[String]) -> Result<Self, String> {
+        entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("'{}' is not an address or CIDR range", entry))
+            })
+            .collect::<Result<_, _>>()
+            .map(TrustedProxies)
+    }
+
+    pub fn trusts(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|net| net.contains(&ip))
+    }
+
+    /// The client behind `peer`. A peer that is not a trusted proxy is the
+    /// client itself. Otherwise `X-Forwarded-For` is read from the right,
+    /// where each proxy appended the address it saw: the first hop that is
+    /// not a trusted proxy is the client, as everything left of it could
+    /// have been made up. Without a peer, e.g. when the router is called
+    /// in-process, the headers are read as if a trusted proxy sent them.
+    fn resolve<'a>(
+        &self,
+        peer: Option<IpAddr>,
+        forwarded_for: impl DoubleEndedIterator<Item = &'a str>,
+    ) -> Option<String> {
+        if let Some(peer) = peer
+            && !self.trusts(peer)
+        {
+            return Some(peer.to_string());
+        }
+        let mut leftmost = None;
+        for hop in forwarded_for.rev() {
+            match hop.parse::<IpAddr>() {
+                Ok(ip) if self.trusts(ip) => leftmost = Some(hop),
+                _ => return Some(hop.to_string()),
+            }
+        }
+        // Every hop is a trusted proxy, so the first one sent the request.
+        leftmost.map(str::to_string).or(peer.map(|p| p.to_string()))
+    }
+}
+
+/// Address of the requesting client, as resolved by [`TrustedProxies`].
+#[derive(Debug, Clone)]
+pub struct ClientIp(pub String);
+
+impl FromRequestParts<Arc<AppState>> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(ClientIp::of(
+            &parts.headers,
+            &parts.extensions,
+            &state.trusted_proxies,
+        ))
+    }
+}
+
+impl ClientIp {
+    /// The client address of a request, for middleware that has the whole
+    /// request rather than its parts.
+    pub fn of(headers: &HeaderMap, extensions: &Extensions, proxies: &TrustedProxies) -> Self {
+        let peer = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let forwarded_for = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|hop| !hop.is_empty())
+            .collect::<Vec<_>>();
+        let ip = proxies
+            .resolve(peer, forwarded_for.into_iter())
+            .unwrap_or_else(|| "unknown".to_string());
+        ClientIp(ip)
+    }
+
+    /// Like [`ClientIp::of`], with the proxies of the app state that
+    /// `build_router` puts in the request extensions.
+    pub fn of_request(headers: &HeaderMap, extensions: &Extensions) -> Self {
+        match extensions.get::<Arc<AppState>>() {
+            Some(state) => ClientIp::of(headers, extensions, &state.trusted_proxies),
+            None => ClientIp::of(headers, extensions, &TrustedProxies::default()),
+        }
+    }
+}
+
+/// What is stored of `ip` under the configured `privacy.ip_storage`. The
+/// raw address is never kept: a value that does not parse as an address is
+/// hashed either way.
+pub fn stored_ip(ip: &str, storage: IpStorage) -> String {
+    match (storage, ip.parse::<IpAddr>()) {
+        (IpStorage::Truncate, Ok(IpAddr::V4(v4))) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.0", a, b, c)
+        }
+        (IpStorage::Truncate, Ok(IpAddr::V6(v6))) => {
+            let mut octets = [0; 16];
+            octets[..6].copy_from_slice(&v6.octets()[..6]);
+            Ipv6Addr::from(octets).to_string()
+        }
+        _ => hex::encode(Sha256::digest(ip.as_bytes())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_rightmost_untrusted_hop_is_the_client() {
+        let proxies =
+            TrustedProxies::new(&["10.0.0.0/8".to_string(), "192.0.2.1".to_string()]).unwrap();
+        let proxy: IpAddr = "10.1.2.3".parse().unwrap();
+        let hops = |list: &'static str| list.split(',').map(str::trim);
+
+        // Spoofed entries left of the untrusted hop are ignored.
+        assert_eq!(
+            proxies.resolve(Some(proxy), hops("6.6.6.6, 203.0.113.7, 192.0.2.1")),
+            Some("203.0.113.7".to_string())
+        );
+        assert_eq!(
+            proxies.resolve(Some(proxy), hops("10.9.9.9, 192.0.2.1")),
+            Some("10.9.9.9".to_string())
+        );
+        assert_eq!(
+            proxies.resolve(Some(proxy), std::iter::empty()),
+            Some("10.1.2.3".to_string())
+        );
+        // Clients that connect directly cannot pick their address.
+        assert_eq!(
+            proxies.resolve(Some("198.51.100.2".parse().unwrap()), hops("203.0.113.7")),
+            Some("198.51.100.2".to_string())
+        );
+        assert!(proxies.trusts("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(TrustedProxies::new(&["10.0.0.0/33".to_string()]).is_err());
+    }
+
+    #[test]
+    fn truncation_keeps_only_the_network() {
+        assert_eq!(stored_ip("203.0.113.7", IpStorage::Truncate), "203.0.113.0");
+        assert_eq!(
+            stored_ip("2001:db8:85a3::8a2e:370:7334", IpStorage::Truncate),
+            "2001:db8:85a3::"
+        );
+        assert_eq!(stored_ip("unknown", IpStorage::Truncate).len(), 64);
+        assert_eq!(
+            stored_ip("203.0.113.7", IpStorage::Hash),
+            hex::encode(Sha256::digest(b"203.0.113.7"))
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use crate::handlers::error::ErrorCode;
+use crate::handlers::response::{Envelope, ErrorBody, Meta, Pagination};
+use crate::server::versioning::is_versioned;
+use axum::body::{Body, HttpBody, to_bytes};
+use axum::http::{HeaderValue, Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::Utc;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Largest body the envelope will re-encode; bigger responses pass through.
+const MAX_ENVELOPE_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+/// The id of the request being handled, for layers and handlers inside the
+/// envelope.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+static PROCESS_START: LazyLock<i64> = LazyLock::new(|| Utc::now().timestamp_millis());
+static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Reuses a sane client-supplied `X-Request-Id`, otherwise generates one that
+/// is unique for the lifetime of the process.
+fn request_id(req: &Request<Body>) -> String {
+    if let Some(id) = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        && !id.is_empty()
+        && id.len() <= 128
+        && id.chars().all(|c| c.is_ascii_graphic())
+    {
+        return id.to_string();
+    }
+    let n = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", *PROCESS_START, n)
+}
+
+/// Error body for failures that did not come from `AppError`, such as
+/// unmatched routes or extractor rejections.
+fn fallback_error(status: StatusCode, body: &[u8]) -> ErrorBody {
+    let code = match status {
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        s if s.is_server_error() => ErrorCode::InternalServer,
+        _ => ErrorCode::BadRequest,
+    };
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    let message = if text.is_empty() {
+        status
+            .canonical_reason()
+            .unwrap_or("Request failed")
+            .to_string()
+    } else {
+        text
+    };
+    ErrorBody {
+        code,
+        message,
+        details: None,
+    }
+}
+
+/// Whether the body of `res` may exceed [`MAX_ENVELOPE_BODY_SIZE`], going
+/// by its `Content-Length` or, for bodies of unknown length, its size hint.
+/// Such bodies are passed through rather than buffered.
+fn too_large(res: &Response) -> bool {
+    let max = MAX_ENVELOPE_BODY_SIZE as u64;
+    let declared = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    match declared {
+        Some(length) => length > max,
+        None => res
+            .body()
+            .size_hint()
+            .upper()
+            .is_none_or(|upper| upper > max),
+    }
+}
+
+/// Tags every response with an `X-Request-Id` and wraps `/api/v1` responses
+/// in the standard [`Envelope`]. Non-JSON successes (feeds, sitemaps) are
+/// left untouched.
+pub async fn envelope(mut req: Request<Body>, next: Next) -> Response {
+    let request_id = request_id(&req);
+    req.extensions_mut()
+        .insert(RequestId(request_id.clone()));
+    let versioned = is_versioned(req.uri().path());
+    let mut res = next.run(req).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    if !versioned {
+        return res;
+    }
+
+    let status = res.status();
+    let failed = status.is_client_error() || status.is_server_error();
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if (!failed && !is_json) || too_large(&res) {
+        return res;
+    }
+
+    let error = res.extensions().get::<ErrorBody>().cloned();
+    let pagination = res.extensions().get::<Pagination>().copied();
+    let (mut parts, body) = res.into_parts();
+    let bytes = match to_bytes(body, MAX_ENVELOPE_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            // The body outgrew its size hint and is partly consumed.
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+
+    let meta = Meta {
+        request_id,
+        pagination,
+    };
+    let envelope = if failed {
+        Envelope::failure(
+            error.unwrap_or_else(|| fallback_error(status, &bytes)),
+            meta,
+        )
+    } else {
+        match serde_json::from_slice(&bytes) {
+            Ok(data) => Envelope::success(data, meta),
+            Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    };
+
+    let body = match serde_json::to_vec(&envelope) {
+        Ok(body) => body,
+        Err(_) => return Response::from_parts(parts, Body::from(bytes)),
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::error::AppError;
+    use axum::extract::Extension;
+    use axum::routing::get;
+    use axum::{Json, Router, middleware};
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/api/v1/items",
+                get(|| async {
+                    (
+                        Extension(Pagination {
+                            page: 2,
+                            limit: 10,
+                            total_pages: 3,
+                            max_limit: 100,
+                        }),
+                        Json(json!(["a", "b"])),
+                    )
+                }),
+            )
+            .route(
+                "/api/v1/missing",
+                get(|| async {
+                    Err::<Json<Value>, _>(AppError::NotFound {
+                        code: ErrorCode::ArticleNotFound,
+                        message: "gone".to_string(),
+                    })
+                }),
+            )
+            .route(
+                "/api/v1/large",
+                get(|| async { Json(vec!["x".repeat(1024); MAX_ENVELOPE_BODY_SIZE / 1024]) }),
+            )
+            .route("/api/items", get(|| async { Json(json!(["a"])) }))
+            .layer(middleware::from_fn(envelope))
+    }
+
+    async fn call(uri: &str) -> (StatusCode, Value) {
+        let res = app()
+            .oneshot(
+                Request::builder()
+                    .uri(uri)
+                    .header(REQUEST_ID_HEADER, "req-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "req-1");
+        let status = res.status();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn wraps_success_with_pagination() {
+        let (status, body) = call("/api/v1/items").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "data": ["a", "b"],
+                "error": null,
+                "meta": {
+                    "request_id": "req-1",
+                    "pagination": { "page": 2, "limit": 10, "total_pages": 3, "max_limit": 100 }
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn wraps_app_errors() {
+        let (status, body) = call("/api/v1/missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["data"], Value::Null);
+        assert_eq!(
+            body["error"],
+            json!({ "code": ErrorCode::ArticleNotFound, "message": "gone" })
+        );
+    }
+
+    #[tokio::test]
+    async fn wraps_unmatched_routes() {
+        let res = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/nope")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], ErrorCode::NotFound.as_str());
+        assert!(body["meta"]["request_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn passes_oversized_bodies_through_unwrapped() {
+        let res = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/large")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body: Vec<String> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.len(), MAX_ENVELOPE_BODY_SIZE / 1024);
+    }
+
+    #[tokio::test]
+    async fn leaves_legacy_paths_unwrapped() {
+        let (_, body) = call("/api/items").await;
+        assert_eq!(body, json!(["a"]));
+    }
+}
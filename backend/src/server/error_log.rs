@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Number of server errors kept for the admin overview.
+const MAX_RECENT_ERRORS: usize = 50;
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorRecord {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Bounded in-memory log of recent 5xx responses.
+#[derive(Default)]
+pub struct ErrorLog {
+    entries: Mutex<VecDeque<ErrorRecord>>,
+}
+
+impl ErrorLog {
+    pub fn record(&self, method: &str, path: &str, status: u16, request_id: Option<&str>) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == MAX_RECENT_ERRORS {
+            entries.pop_front();
+        }
+        entries.push_back(ErrorRecord {
+            timestamp: Utc::now(),
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            request_id: request_id.map(str::to_string),
+        });
+    }
+
+    /// Returns recorded errors, newest first.
+    pub fn recent(&self) -> Vec<ErrorRecord> {
+        self.entries.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
@@ -0,0 +1,292 @@
+use crate::models::diagnostics::WatcherHealth;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender, error::TrySendError};
+use tokio::time::{Instant, timeout_at};
+use tracing::{error, info};
+
+/// How long the directory has to stay quiet before an update pass starts.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(500);
+/// Longest an update pass is put off while events keep arriving, so a
+/// steady trickle of writes still shows up.
+pub const MAX_BATCH_WINDOW: Duration = Duration::from_secs(5);
+
+/// Wakes the watcher task from notify's event thread. At most one wakeup is
+/// pending at a time: every update pass rescans the whole directory, so
+/// further events before it starts carry no extra information.
+#[derive(Clone)]
+pub struct FileEventSender {
+    tx: Sender<()>,
+    failure: Arc<Mutex<Option<String>>>,
+}
+
+impl FileEventSender {
+    /// Returns false once the watcher task is gone.
+    pub fn notify(&self) -> bool {
+        !matches!(self.tx.try_send(()), Err(TrySendError::Closed(_)))
+    }
+
+    /// Reports an error from notify's event thread, which the watcher task
+    /// picks up after the coming pass.
+    pub fn fail(&self, error: String) -> bool {
+        self.failure.lock().unwrap().get_or_insert(error);
+        self.notify()
+    }
+}
+
+pub struct FileEvents {
+    rx: Receiver<()>,
+    failure: Arc<Mutex<Option<String>>>,
+    quiet: Duration,
+    max_window: Duration,
+}
+
+pub fn file_events(quiet: Duration, max_window: Duration) -> (FileEventSender, FileEvents) {
+    let (tx, rx) = mpsc::channel(1);
+    let failure = Arc::new(Mutex::new(None));
+    (
+        FileEventSender {
+            tx,
+            failure: Arc::clone(&failure),
+        },
+        FileEvents {
+            rx,
+            failure,
+            quiet,
+            max_window,
+        },
+    )
+}
+
+impl FileEvents {
+    /// Waits for the next burst of events to settle: until no event has
+    /// arrived for the quiet period, or the batch window since the first
+    /// one has passed. Returns false when the sender is gone.
+    pub async fn next_burst(&mut self) -> bool {
+        if self.rx.recv().await.is_none() {
+            return false;
+        }
+        let window_end = Instant::now() + self.max_window;
+        loop {
+            let quiet_end = (Instant::now() + self.quiet).min(window_end);
+            match timeout_at(quiet_end, self.rx.recv()).await {
+                Ok(Some(())) if Instant::now() < window_end => continue,
+                // Quiet, or out of time; an event that arrived just as the
+                // window closed is covered by the coming pass.
+                Ok(Some(())) | Ok(None) | Err(_) => return true,
+            }
+        }
+    }
+
+    /// The error the watcher reported, if it failed.
+    pub fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap().take()
+    }
+}
+
+/// Whether each file watcher is running, by what it watches, so one that
+/// gave up is noticed. Watchers that were never started are not listed.
+#[derive(Default)]
+pub struct WatcherStatus {
+    watchers: Mutex<BTreeMap<&'static str, WatcherHealth>>,
+}
+
+/// Keeps a watcher marked as running until it is dropped.
+pub struct Watching<'a> {
+    status: &'a WatcherStatus,
+    name: &'static str,
+}
+
+impl Drop for Watching<'_> {
+    fn drop(&mut self) {
+        self.status.stopped(self.name);
+    }
+}
+
+impl WatcherStatus {
+    /// Marks `name` as running until its task drops the returned guard,
+    /// however it ends.
+    pub fn watching(&self, name: &'static str) -> Watching<'_> {
+        self.update(name, |health| health.running = true);
+        Watching { status: self, name }
+    }
+
+    /// Marks `name` as not running, e.g. when it failed to start.
+    pub fn stopped(&self, name: &'static str) {
+        self.update(name, |health| health.running = false);
+    }
+
+    /// Marks `name` as stopped by `error`, kept until it fails again.
+    pub fn failed(&self, name: &'static str, error: &str) {
+        self.update(name, |health| {
+            health.running = false;
+            health.last_error = Some(error.to_string());
+        });
+    }
+
+    /// Counts a restart of `name` by its supervisor.
+    pub fn restarting(&self, name: &'static str) {
+        self.update(name, |health| health.restarts += 1);
+    }
+
+    fn update(&self, name: &'static str, change: impl FnOnce(&mut WatcherHealth)) {
+        let mut watchers = self.watchers.lock().unwrap();
+        let health = watchers.entry(name).or_insert_with(|| WatcherHealth {
+            running: false,
+            restarts: 0,
+            last_error: None,
+            since: Utc::now(),
+        });
+        let was_running = health.running;
+        change(health);
+        if health.running != was_running {
+            health.since = Utc::now();
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, WatcherHealth> {
+        self.watchers.lock().unwrap().clone()
+    }
+}
+
+/// How long a supervisor waits before starting a failed watcher again.
+/// The wait doubles with each failure in a row, up to `max`.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartBackoff {
+    pub first: Duration,
+    pub max: Duration,
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        Self {
+            first: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Runs the watcher `run` starts, and starts it again whenever it fails or
+/// panics, waiting longer after each failure in a row. A watcher that ran
+/// for `backoff.max` before failing is restarted after `backoff.first`.
+/// Returns when the watcher ends without an error, e.g. because there is
+/// nothing to watch.
+pub async fn supervise<F, Fut>(
+    status: &WatcherStatus,
+    name: &'static str,
+    backoff: RestartBackoff,
+    mut run: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let mut wait = backoff.first;
+    loop {
+        let started = Instant::now();
+        let error = match tokio::spawn(run()).await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e,
+            Err(e) if e.is_panic() => "the watcher panicked".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if started.elapsed() >= backoff.max {
+            wait = backoff.first;
+        }
+        error!(
+            "The {} watcher failed: {}; restarting in {:?}",
+            name, error, wait
+        );
+        status.failed(name, &error);
+        tokio::time::sleep(wait).await;
+        wait = (wait * 2).min(backoff.max);
+        status.restarting(name);
+        info!("Restarting the {} watcher", name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn thousands_of_rapid_events_make_one_burst() {
+        let (tx, mut events) = file_events(Duration::from_millis(100), Duration::from_secs(10));
+        // notify delivers events from its own thread.
+        let sender = std::thread::spawn(move || {
+            for _ in 0..10_000 {
+                assert!(tx.notify());
+            }
+            tx
+        });
+
+        let started = Instant::now();
+        assert!(events.next_burst().await);
+        let tx = sender.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Nothing is left over to trigger another pass.
+        drop(tx);
+        assert!(!events.next_burst().await);
+    }
+
+    #[tokio::test]
+    async fn a_steady_trickle_is_cut_at_the_batch_window() {
+        let (tx, mut events) = file_events(Duration::from_millis(200), Duration::from_millis(400));
+        let trickle = tokio::spawn(async move {
+            for _ in 0..40 {
+                tx.notify();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+
+        let started = Instant::now();
+        assert!(events.next_burst().await);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(1500), "{elapsed:?}");
+        trickle.abort();
+    }
+
+    #[tokio::test]
+    async fn failed_watchers_are_restarted_with_backoff() {
+        let status = WatcherStatus::default();
+        let backoff = RestartBackoff {
+            first: Duration::from_millis(20),
+            max: Duration::from_millis(60),
+        };
+        let runs = Arc::new(Mutex::new(Vec::new()));
+        let started = Instant::now();
+        supervise(&status, "articles", backoff, || {
+            let runs = Arc::clone(&runs);
+            async move {
+                let run = {
+                    let mut runs = runs.lock().unwrap();
+                    runs.push(started.elapsed());
+                    runs.len()
+                };
+                match run {
+                    1 => Err("watch failed".to_string()),
+                    2 => Err("watch failed again".to_string()),
+                    3 => panic!("lost the watcher"),
+                    _ => Ok(()),
+                }
+            }
+        })
+        .await;
+
+        let runs = runs.lock().unwrap();
+        assert_eq!(runs.len(), 4);
+        // 20ms, then 40ms; the panic is slow to unwind, so the last wait
+        // may have started over.
+        for (gap, wait) in runs.windows(2).zip([20, 40, 20]) {
+            assert!(gap[1] - gap[0] >= Duration::from_millis(wait), "{runs:?}");
+        }
+        let health = &status.snapshot()["articles"];
+        assert_eq!(health.restarts, 3);
+        assert!(!health.running);
+        assert_eq!(health.last_error.as_deref(), Some("the watcher panicked"));
+    }
+}
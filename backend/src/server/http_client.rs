@@ -0,0 +1,69 @@
+use crate::config::HttpConfig;
+use reqwest::{IntoUrl, Method, NoProxy, Proxy, RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+
+/// The client every outbound request goes through. Built once from the
+/// `[http]` settings, so connections are pooled and the proxy, timeouts and
+/// retry policy apply everywhere.
+pub struct HttpClient {
+    client: reqwest::Client,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl HttpClient {
+    pub fn from_config(config: &HttpConfig) -> reqwest::Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(concat!("scribe/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs));
+        if let Some(proxy) = &config.proxy {
+            let no_proxy = config.no_proxy.as_deref().and_then(NoProxy::from_string);
+            builder = builder.proxy(Proxy::all(proxy)?.no_proxy(no_proxy));
+        }
+        Ok(Self {
+            client: builder.build()?,
+            retries: config.retries,
+            backoff: Duration::from_millis(config.retry_backoff_ms),
+        })
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Sends `request`, retrying connection failures, timeouts and 429 or
+    /// 5xx responses up to the configured number of times. The last
+    /// attempt's outcome is returned as is. Requests whose body cannot be
+    /// copied, such as streams, are sent once.
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let Some(copy) = (attempt < self.retries)
+                .then(|| request.try_clone())
+                .flatten()
+            else {
+                return request.send().await;
+            };
+            match copy.send().await {
+                Ok(response) if !is_transient(response.status()) => return Ok(response),
+                Err(e) if !(e.is_connect() || e.is_timeout()) => return Err(e),
+                _ => {}
+            }
+            tokio::time::sleep(self.backoff * 2u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
@@ -0,0 +1,236 @@
+use crate::models::article::ArticleContent;
+use crate::server::app::IndexJob;
+use crate::services::search::SearchService;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedSender, error::SendError};
+use tokio::sync::oneshot;
+use tokio::time::{Instant, sleep_until};
+
+/// Sending half of the search index worker. Counts jobs from the moment they
+/// are queued until the batch containing them has been committed, or has
+/// failed, so operators can see how far the index lags behind the stores.
+pub struct IndexQueue {
+    tx: UnboundedSender<IndexJob>,
+    flush_tx: UnboundedSender<oneshot::Sender<()>>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl IndexQueue {
+    pub fn send(&self, job: IndexJob) -> Result<(), SendError<IndexJob>> {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        self.tx.send(job).inspect_err(|_| {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        })
+    }
+
+    /// Jobs not yet applied to the index.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Commits queued jobs now instead of at the end of the debounce window,
+    /// and waits for the commit.
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.flush_tx.send(done_tx).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// Jobs waiting for the next commit. Later jobs for a slug replace earlier
+/// ones, so a batch never indexes and removes the same entry.
+#[derive(Default)]
+struct Batch {
+    to_index: Vec<ArticleContent>,
+    to_remove: Vec<String>,
+    jobs: usize,
+}
+
+impl Batch {
+    fn push(&mut self, job: IndexJob) {
+        self.jobs += 1;
+        match job {
+            IndexJob::Index(article) => {
+                self.to_remove.retain(|s| *s != article.slug);
+                self.to_index.retain(|a| a.slug != article.slug);
+                self.to_index.push(*article);
+            }
+            IndexJob::Remove(slug) => {
+                self.to_index.retain(|a| a.slug != slug);
+                if !self.to_remove.contains(&slug) {
+                    self.to_remove.push(slug);
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.jobs == 0
+    }
+
+    /// Applies the batch to the index. Returns false when there was nothing
+    /// to commit.
+    fn commit(&mut self, search: &SearchService, heap_size: usize, pending: &AtomicUsize) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        if let Err(e) = search.apply_batch(&self.to_index, &self.to_remove, heap_size) {
+            tracing::warn!("Failed to process search index batch: {:?}", e);
+        }
+        pending.fetch_sub(self.jobs, Ordering::Relaxed);
+        *self = Batch::default();
+        true
+    }
+}
+
+async fn wait_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Spawns the worker that applies queued jobs to the index. Jobs are
+/// committed together, at most once per `debounce` window; with a zero
+/// window they are committed as soon as the previous commit is done. Pending
+/// jobs are committed when the queue is flushed or dropped.
+pub fn start_index_worker(
+    search: Arc<SearchService>,
+    heap_size: usize,
+    debounce: Duration,
+) -> IndexQueue {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let (flush_tx, mut flush_rx) = mpsc::unbounded_channel::<oneshot::Sender<()>>();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let queue = IndexQueue {
+        tx,
+        flush_tx,
+        pending: Arc::clone(&pending),
+    };
+
+    tokio::spawn(async move {
+        let mut batch = Batch::default();
+        let mut last_commit: Option<Instant> = None;
+        loop {
+            let deadline = (!batch.is_empty())
+                .then(|| last_commit.map_or_else(Instant::now, |at| at + debounce));
+            tokio::select! {
+                job = rx.recv() => match job {
+                    Some(job) => batch.push(job),
+                    None => {
+                        batch.commit(&search, heap_size, &pending);
+                        break;
+                    }
+                },
+                Some(done) = flush_rx.recv() => {
+                    // Jobs sent before the flush may not have been received yet.
+                    while let Ok(job) = rx.try_recv() {
+                        batch.push(job);
+                    }
+                    if batch.commit(&search, heap_size, &pending) {
+                        last_commit = Some(Instant::now());
+                    }
+                    let _ = done.send(());
+                }
+                _ = wait_until(deadline) => {
+                    if batch.commit(&search, heap_size, &pending) {
+                        last_commit = Some(Instant::now());
+                    }
+                }
+            }
+        }
+    });
+
+    queue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::{ArticleStatus, Metadata};
+    use tempfile::tempdir;
+
+    fn article(slug: &str) -> Box<ArticleContent> {
+        Box::new(ArticleContent {
+            slug: slug.to_string(),
+            metadata: Metadata {
+                title: slug.to_string(),
+                author: "a".to_string(),
+                date: chrono::Utc::now(),
+                display_date: None,
+                tags: Vec::new(),
+                description: String::new(),
+                status: ArticleStatus::Published,
+                last_updated: None,
+                category: None,
+                poll: None,
+                lang: None,
+                translations: Default::default(),
+                private: false,
+                http_headers: Default::default(),
+                comments: Default::default(),
+                menu: None,
+                audio: None,
+                gallery: Vec::new(),
+                link: None,
+                syndication: Default::default(),
+                references: Vec::new(),
+                downloads: Vec::new(),
+                description_generated: false,
+                members_only: false,
+            },
+            content: "Body".to_string(),
+            edit_url: None,
+        })
+    }
+
+    #[test]
+    fn later_jobs_for_a_slug_win() {
+        let mut batch = Batch::default();
+        batch.push(IndexJob::Index(article("a")));
+        batch.push(IndexJob::Remove("a".to_string()));
+        batch.push(IndexJob::Index(article("b")));
+        batch.push(IndexJob::Index(article("b")));
+        batch.push(IndexJob::Remove("c".to_string()));
+        batch.push(IndexJob::Index(article("c")));
+
+        let slugs: Vec<&str> = batch.to_index.iter().map(|a| a.slug.as_str()).collect();
+        assert_eq!(slugs, ["b", "c"]);
+        assert_eq!(batch.to_remove, ["a"]);
+        assert_eq!(batch.jobs, 6);
+    }
+
+    #[tokio::test]
+    async fn jobs_wait_for_the_debounce_window_or_a_flush() {
+        let dir = tempdir().unwrap();
+        let search = Arc::new(SearchService::new(dir.path().join("index")).unwrap());
+        let queue = start_index_worker(Arc::clone(&search), 15_000_000, Duration::from_secs(3600));
+
+        // The first commit is not delayed, since nothing was committed yet.
+        queue.send(IndexJob::Index(article("first"))).unwrap();
+        queue.flush().await;
+        queue.send(IndexJob::Index(article("second"))).unwrap();
+        queue.send(IndexJob::Remove("first".to_string())).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let slugs = |search: &SearchService| -> Vec<String> {
+            let mut slugs: Vec<String> = search
+                .indexed_documents()
+                .unwrap()
+                .into_iter()
+                .map(|d| d.slug)
+                .collect();
+            slugs.sort();
+            slugs
+        };
+        assert_eq!(slugs(&search), ["first"]);
+        assert_eq!(queue.pending(), 2);
+
+        queue.flush().await;
+        assert_eq!(slugs(&search), ["second"]);
+        assert_eq!(queue.pending(), 0);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::server::app::AppState;
+use crate::server::scheduler::JobFuture;
+use crate::services::job_queue_service::{complete_job, due_jobs, enqueue, fail_job};
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+pub type JobHandler = Arc<dyn Fn(Arc<AppState>, Value) -> JobFuture + Send + Sync>;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const BATCH_SIZE: usize = 20;
+
+/// Persistent queue for outbound deliveries. Jobs are stored in SQLite so
+/// pending retries survive restarts; a single worker task runs them through
+/// the handler registered for their job type.
+#[derive(Default)]
+pub struct JobQueue {
+    handlers: HashMap<&'static str, JobHandler>,
+    wake: Notify,
+}
+
+impl JobQueue {
+    pub fn register(&mut self, job_type: &'static str, handler: JobHandler) {
+        self.handlers.insert(job_type, handler);
+    }
+
+    /// Stores a job and wakes the worker so it is attempted right away.
+    pub async fn push(
+        &self,
+        state: &AppState,
+        job_type: &str,
+        payload: &Value,
+    ) -> Result<i64, sqlx::Error> {
+        let id = enqueue(&state.db, job_type, payload, Utc::now().timestamp()).await?;
+        self.wake.notify_one();
+        Ok(id)
+    }
+}
+
+async fn run_due_jobs(state: &Arc<AppState>) -> Result<(), sqlx::Error> {
+    let now = Utc::now().timestamp();
+    for job in due_jobs(&state.db, now, BATCH_SIZE).await? {
+        let result = match state.job_queue.handlers.get(job.job_type.as_str()) {
+            Some(handler) => match serde_json::from_str(&job.payload) {
+                Ok(payload) => handler(Arc::clone(state), payload).await,
+                Err(e) => Err(format!("invalid payload: {}", e)),
+            },
+            None => Err(format!("no handler for job type '{}'", job.job_type)),
+        };
+
+        match result {
+            Ok(()) => complete_job(&state.db, job.id).await?,
+            Err(e) => {
+                let dead = fail_job(&state.db, &job, &e, Utc::now().timestamp()).await?;
+                if dead {
+                    warn!(
+                        "Job {} ({}) moved to dead letter: {}",
+                        job.id, job.job_type, e
+                    );
+                } else {
+                    info!(
+                        "Job {} ({}) failed, will retry: {}",
+                        job.id, job.job_type, e
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Spawns the worker that drains due jobs whenever something is enqueued and
+/// at a fixed interval for scheduled retries.
+pub fn start_job_worker(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = run_due_jobs(&state).await {
+                error!("Failed to process job queue: {:?}", e);
+            }
+            tokio::select! {
+                _ = state.job_queue.wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+}
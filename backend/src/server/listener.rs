@@ -0,0 +1,150 @@
+use crate::config::ServerConfig;
+use axum::Router;
+use axum::extract::ConnectInfo;
+use axum::http::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::future::Future;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use tracing::{debug, warn};
+
+/// Protocol settings shared by every accepted connection.
+fn connection_builder(settings: &ServerConfig) -> Builder<TokioExecutor> {
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .keep_alive(settings.http1_keep_alive)
+        .header_read_timeout(
+            (settings.header_read_timeout_secs > 0)
+                .then(|| Duration::from_secs(settings.header_read_timeout_secs)),
+        );
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .max_concurrent_streams(settings.http2_max_concurrent_streams)
+        .keep_alive_interval(
+            (settings.http2_keep_alive_interval_secs > 0)
+                .then(|| Duration::from_secs(settings.http2_keep_alive_interval_secs)),
+        )
+        .keep_alive_timeout(Duration::from_secs(settings.http2_keep_alive_timeout_secs));
+    if settings.http2 {
+        builder
+    } else {
+        builder.http1_only()
+    }
+}
+
+/// Serves `app` on `listener` until `shutdown` resolves, then waits for open
+/// connections to finish their requests. Handlers see the peer address as
+/// `ConnectInfo<SocketAddr>`, as with `axum::serve`.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    settings: &ServerConfig,
+    shutdown: impl Future<Output = ()>,
+) {
+    let builder = connection_builder(settings);
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = std::pin::pin!(shutdown);
+
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Usually running out of file descriptors; give other
+                    // connections a moment to close.
+                    warn!("Failed to accept connection: {:?}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+        if let Err(e) = stream.set_nodelay(settings.tcp_nodelay) {
+            debug!("Failed to set TCP_NODELAY for {}: {:?}", remote_addr, e);
+        }
+
+        let service = app.clone().map_request(move |mut req: Request<_>| {
+            req.extensions_mut().insert(ConnectInfo(remote_addr));
+            req
+        });
+        let connection = builder
+            .serve_connection(TokioIo::new(stream), TowerToHyperService::new(service))
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("Connection from {} closed with error: {:?}", remote_addr, e);
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use tokio::sync::oneshot;
+
+    const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+    async fn spawn(settings: ServerConfig) -> (SocketAddr, oneshot::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/peer",
+            get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.to_string() }),
+        );
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            serve(listener, app, &settings, async {
+                let _ = stop_rx.await;
+            })
+            .await
+        });
+        (addr, stop_tx)
+    }
+
+    async fn exchange(addr: SocketAddr, request: &[u8]) -> Vec<u8> {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request).await.unwrap();
+        let mut response = vec![0; 1024];
+        let read = stream.read(&mut response).await.unwrap();
+        response.truncate(read);
+        response
+    }
+
+    #[tokio::test]
+    async fn serves_http1_with_peer_address_and_optional_http2() {
+        let (addr, stop) = spawn(ServerConfig::default()).await;
+        let response = exchange(addr, b"GET /peer HTTP/1.1\r\nHost: x\r\n\r\n").await;
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("\r\n\r\n127.0.0.1:"), "{response}");
+
+        // The server answers the HTTP/2 preface with a SETTINGS frame.
+        let response = exchange(addr, H2_PREFACE).await;
+        assert_eq!(response.get(3), Some(&0x4));
+        let _ = stop.send(());
+
+        let (addr, _stop) = spawn(ServerConfig {
+            http2: false,
+            ..Default::default()
+        })
+        .await;
+        let response = exchange(addr, H2_PREFACE).await;
+        assert!(!response.starts_with(&[0, 0]), "{response:?}");
+    }
+}
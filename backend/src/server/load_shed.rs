@@ -0,0 +1,136 @@
+use crate::config::ConcurrencyConfig;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use axum::Extension;
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{HeaderValue, Request, header::RETRY_AFTER};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Expensive kinds of requests, each with its own concurrency limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadClass {
+    Search,
+    /// Article and note listings with `include_content=true`.
+    ContentListings,
+    /// Comment exports, article downloads and bundles.
+    Exports,
+}
+
+/// Requests of each class allowed in flight at once. Requests over the
+/// limit are not queued but turned away, so a burst cannot pile up work
+/// that clients have long given up on.
+pub struct LoadLimits {
+    search: Option<Arc<Semaphore>>,
+    content_listings: Option<Arc<Semaphore>>,
+    exports: Option<Arc<Semaphore>>,
+    retry_after_secs: u64,
+}
+
+fn semaphore(permits: usize) -> Option<Arc<Semaphore>> {
+    (permits > 0).then(|| Arc::new(Semaphore::new(permits)))
+}
+
+impl LoadLimits {
+    pub fn new(config: &ConcurrencyConfig) -> Self {
+        Self {
+            search: semaphore(config.search),
+            content_listings: semaphore(config.content_listings),
+            exports: semaphore(config.exports),
+            retry_after_secs: config.retry_after_secs,
+        }
+    }
+
+    /// Takes a slot for a request of `class`, or fails when all are in use.
+    /// Unlimited classes need no slot.
+    pub fn try_acquire(&self, class: LoadClass) -> Result<Option<OwnedSemaphorePermit>, AppError> {
+        let semaphore = match class {
+            LoadClass::Search => &self.search,
+            LoadClass::ContentListings => &self.content_listings,
+            LoadClass::Exports => &self.exports,
+        };
+        let Some(semaphore) = semaphore else {
+            return Ok(None);
+        };
+        Arc::clone(semaphore)
+            .try_acquire_owned()
+            .map(Some)
+            .map_err(|_| AppError::ServiceUnavailable {
+                code: ErrorCode::ServerBusy,
+                message: "Too many similar requests in progress, try again shortly".to_string(),
+            })
+    }
+}
+
+#[derive(Deserialize)]
+struct ContentParam {
+    #[serde(default)]
+    include_content: bool,
+}
+
+async fn shed_load(
+    State(class): State<LoadClass>,
+    Extension(state): Extension<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if class == LoadClass::ContentListings {
+        let includes_content = Query::<ContentParam>::try_from_uri(req.uri())
+            .is_ok_and(|Query(param)| param.include_content);
+        if !includes_content {
+            return next.run(req).await;
+        }
+    }
+    match state.load_limits.try_acquire(class) {
+        Ok(_permit) => next.run(req).await,
+        Err(e) => {
+            let mut response = e.into_response();
+            response.headers_mut().insert(
+                RETRY_AFTER,
+                HeaderValue::from(state.load_limits.retry_after_secs),
+            );
+            response
+        }
+    }
+}
+
+/// Limits how many requests of `class` `route` serves at once. The slot is
+/// held until the handler has produced its response.
+pub fn with_load_limit(
+    route: MethodRouter<Arc<AppState>>,
+    class: LoadClass,
+) -> MethodRouter<Arc<AppState>> {
+    route.route_layer(middleware::from_fn_with_state(class, shed_load))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_requests_away_once_every_slot_is_taken() {
+        let limits = LoadLimits::new(&ConcurrencyConfig {
+            search: 2,
+            content_listings: 0,
+            exports: 1,
+            retry_after_secs: 1,
+        });
+        let first = limits.try_acquire(LoadClass::Search).unwrap();
+        let _second = limits.try_acquire(LoadClass::Search).unwrap();
+        assert!(limits.try_acquire(LoadClass::Search).is_err());
+        drop(first);
+        assert!(limits.try_acquire(LoadClass::Search).unwrap().is_some());
+
+        // Classes do not share slots, and 0 lifts the limit.
+        assert!(limits.try_acquire(LoadClass::Exports).unwrap().is_some());
+        let held: Vec<_> = (0..100)
+            .map(|_| limits.try_acquire(LoadClass::ContentListings).unwrap())
+            .collect();
+        assert!(held.iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,41 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use axum::body::Body;
+use axum::http::Response;
+use axum::response::IntoResponse;
+use std::any::Any;
+use tower_http::catch_panic::CatchPanicLayer;
+
+/// Builds the response that stands in for a panicked handler.
+pub type PanicHandler = fn(Box<dyn Any + Send + 'static>) -> Response<Body>;
+
+/// Marks a response that stands in for a handler that panicked, with the
+/// panic message.
+#[derive(Debug, Clone)]
+pub struct Panicked(pub String);
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+fn panic_response(payload: Box<dyn Any + Send + 'static>) -> Response<Body> {
+    let message = panic_message(payload.as_ref());
+    let mut response = AppError::InternalServerError {
+        code: ErrorCode::InternalServer,
+        message: "The server hit an unexpected error".to_string(),
+    }
+    .into_response();
+    response.extensions_mut().insert(Panicked(message));
+    response
+}
+
+/// Answers a handler that panics with a `500 ERR_INTERNAL_SERVER` instead of
+/// dropping the connection. The panic message is kept out of the body.
+pub fn catch_panics() -> CatchPanicLayer<PanicHandler> {
+    CatchPanicLayer::custom(panic_response as PanicHandler)
+}
@@ -0,0 +1,193 @@
+use crate::config::PublicApiConfig;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::api_keys::API_KEY_HEADER;
+use crate::server::app::AppState;
+use crate::server::client_ip::ClientIp;
+use crate::server::versioning::API_V1_PREFIX;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderValue, Method, Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Prefix of the read-only API for third-party consumers.
+pub const PUBLIC_API_PREFIX: &str = "/api/public";
+const WINDOW: Duration = Duration::from_secs(60);
+/// Clients tracked before windows that have run out are dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+pub fn is_public(path: &str) -> bool {
+    let rest = path
+        .strip_prefix(API_V1_PREFIX)
+        .map(|rest| format!("/api{}", rest));
+    let path = rest.as_deref().unwrap_or(path);
+    path.strip_prefix(PUBLIC_API_PREFIX)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Requests each client address has made to the public API in its current
+/// one-minute window.
+pub struct PublicQuota {
+    requests_per_minute: u32,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl PublicQuota {
+    pub fn new(config: &PublicApiConfig) -> Self {
+        Self {
+            requests_per_minute: config.requests_per_minute,
+            windows: Mutex::default(),
+        }
+    }
+
+    /// Counts a request from `client` and returns how many it has left in
+    /// the window, or the seconds until the window resets once it has none.
+    /// Unlimited when `requests_per_minute` is 0.
+    pub fn take(&self, client: &str, now: Instant) -> Result<Option<u32>, u64> {
+        if self.requests_per_minute == 0 {
+            return Ok(None);
+        }
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= MAX_TRACKED_CLIENTS {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+        let (start, count) = windows.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.requests_per_minute {
+            let reset = WINDOW.saturating_sub(now.duration_since(*start));
+            return Err(reset.as_secs().max(1));
+        }
+        *count += 1;
+        Ok(Some(self.requests_per_minute - *count))
+    }
+}
+
+/// Hardens requests under [`PUBLIC_API_PREFIX`] for anonymous cross-origin
+/// use. Preflights are answered here with a long `Access-Control-Max-Age`.
+/// Cookies, the admin token and API keys are dropped, so responses are the
+/// ones an anonymous visitor gets and can be shared by the response cache,
+/// and each client address gets `requests_per_minute`. Successful responses
+/// may be cached by browsers and CDNs for `cache_max_age_secs`. Runs
+/// outside the response cache so cache hits are counted too. A
+/// `[[route_layers]]` group can lift the quota or drop the CORS headers.
+pub async fn harden_public_api(
+    State(state): State<Arc<AppState>>,
+    ClientIp(client): ClientIp,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !is_public(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let config = &state.config.public_api;
+    let path = req.uri().path();
+    let cors = state.route_layers.cors(path) != Some(false);
+    let limited = state.route_layers.rate_limit(path) != Some(false);
+    let with_cors = |res| if cors { with_cors(res) } else { res };
+
+    if cors && req.method() == Method::OPTIONS {
+        let mut res = StatusCode::NO_CONTENT.into_response();
+        let headers = res.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, HEAD, OPTIONS"),
+        );
+        if let Some(requested) = req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from(config.cors_max_age_secs),
+        );
+        return with_cors(res);
+    }
+
+    let quota = if limited {
+        state.public_quota.take(&client, Instant::now())
+    } else {
+        Ok(None)
+    };
+    let remaining = match quota {
+        Ok(remaining) => remaining,
+        Err(retry_after) => {
+            let mut res = AppError::TooManyRequests {
+                code: ErrorCode::PublicApiRateLimited,
+                message: format!(
+                    "Public API allows {} requests a minute per client",
+                    config.requests_per_minute
+                ),
+            }
+            .into_response();
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return with_cors(res);
+        }
+    };
+
+    let headers = req.headers_mut();
+    headers.remove(header::COOKIE);
+    headers.remove(header::AUTHORIZATION);
+    headers.remove(API_KEY_HEADER);
+    let mut res = next.run(req).await;
+
+    let success = res.status().is_success();
+    let headers = res.headers_mut();
+    headers.remove(header::SET_COOKIE);
+    if success && !headers.contains_key(header::CACHE_CONTROL) {
+        let cache_control = format!("public, max-age={}", config.cache_max_age_secs);
+        if let Ok(value) = HeaderValue::from_str(&cache_control) {
+            headers.insert(header::CACHE_CONTROL, value);
+        }
+    }
+    if let Some(remaining) = remaining {
+        headers.insert(
+            "x-ratelimit-limit",
+            HeaderValue::from(config.requests_per_minute),
+        );
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+    }
+    with_cors(res)
+}
+
+fn with_cors(mut res: Response) -> Response {
+    res.headers_mut().insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_public_paths() {
+        for path in ["/api/public", "/api/public/articles", "/api/v1/public/tags"] {
+            assert!(is_public(path), "{}", path);
+        }
+        for path in ["/api/articles", "/api/publications", "/public/articles"] {
+            assert!(!is_public(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn clients_get_their_own_window() {
+        let quota = PublicQuota::new(&PublicApiConfig {
+            requests_per_minute: 2,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        assert_eq!(quota.take("a", start), Ok(Some(1)));
+        assert_eq!(quota.take("a", start), Ok(Some(0)));
+        assert_eq!(quota.take("a", start + Duration::from_secs(15)), Err(45));
+        assert_eq!(quota.take("b", start), Ok(Some(1)));
+        assert_eq!(quota.take("a", start + WINDOW), Ok(Some(1)));
+    }
+}
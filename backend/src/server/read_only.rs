@@ -0,0 +1,41 @@
+use crate::handlers::error::{AppError, ErrorCode};
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::{Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Routes that keep accepting writes in read-only mode. None of them change
+/// content: they rebuild state derived from the files, manage API access,
+/// check out branches for review, trigger downstream builds or count a
+/// page view.
+const WRITABLE_ROUTES: &[&str] = &[
+    "/api/search/reindex",
+    "/api/admin/search/consistency/repair",
+    "/api/admin/stores/{collection}/rebuild",
+    "/api/admin/keys",
+    "/api/admin/keys/{id}",
+    "/api/admin/preview-branches",
+    "/api/admin/preview-branches/{branch}",
+    "/api/admin/deploy-hooks/{name}/trigger",
+    "/api/deploy-hooks/{name}/callback",
+    "/api/articles/{slug}/views",
+];
+
+/// Refuses every request that is not a read, outside [`WRITABLE_ROUTES`].
+/// Layered on the router when `read_only` is set, for deployments where
+/// content only changes through the repository.
+pub async fn refuse_writes(req: Request<Body>, next: Next) -> Result<Response, AppError> {
+    let reads = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let writable = req
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| WRITABLE_ROUTES.contains(&path.as_str()));
+    if !reads && !writable {
+        return Err(AppError::Forbidden {
+            code: ErrorCode::ReadOnly,
+            message: "This instance is read-only".to_string(),
+        });
+    }
+    Ok(next.run(req).await)
+}
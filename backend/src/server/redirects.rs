@@ -0,0 +1,104 @@
+use crate::db::DbPool;
+use crate::models::redirect::Redirect;
+use crate::server::app::AppState;
+use crate::services::redirect_service::list_redirects;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Request, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Registered redirects keyed by source path. Kept in memory so the
+/// middleware does not hit the database on every request; the admin API
+/// reloads it after each change.
+#[derive(Default)]
+pub struct RedirectTable {
+    routes: RwLock<HashMap<String, (String, StatusCode)>>,
+}
+
+impl RedirectTable {
+    pub async fn load(pool: &DbPool) -> Result<Self, sqlx::Error> {
+        let table = Self::default();
+        table.reload(pool).await?;
+        Ok(table)
+    }
+
+    pub async fn reload(&self, pool: &DbPool) -> Result<(), sqlx::Error> {
+        let redirects = list_redirects(pool).await?;
+        self.replace(&redirects);
+        Ok(())
+    }
+
+    fn replace(&self, redirects: &[Redirect]) {
+        let routes = redirects
+            .iter()
+            .map(|r| {
+                let status =
+                    StatusCode::from_u16(r.status).unwrap_or(StatusCode::MOVED_PERMANENTLY);
+                (r.from_path.clone(), (r.to_url.clone(), status))
+            })
+            .collect();
+        *self.routes.write().unwrap() = routes;
+    }
+
+    pub fn lookup(&self, path: &str) -> Option<(String, StatusCode)> {
+        self.routes
+            .read()
+            .unwrap()
+            .get(normalize_path(path))
+            .cloned()
+    }
+}
+
+/// Source paths match with or without a trailing slash.
+pub fn normalize_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Answers requests for registered paths with their redirect before any
+/// route runs. The query string carries over unless the target has its own.
+pub async fn serve_redirects(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some((to_url, status)) = state.redirects.lookup(req.uri().path()) else {
+        return next.run(req).await;
+    };
+    let location = match req.uri().query() {
+        Some(query) if !to_url.contains('?') => format!("{}?{}", to_url, query),
+        _ => to_url,
+    };
+    (status, [(header::LOCATION, location)]).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_with_or_without_trailing_slash() {
+        let table = RedirectTable::default();
+        table.replace(&[Redirect {
+            id: 1,
+            from_path: "/old/post".to_string(),
+            to_url: "/articles/new-post".to_string(),
+            status: 308,
+            created_at: String::new(),
+        }]);
+
+        let expected = Some((
+            "/articles/new-post".to_string(),
+            StatusCode::PERMANENT_REDIRECT,
+        ));
+        assert_eq!(table.lookup("/old/post"), expected);
+        assert_eq!(table.lookup("/old/post/"), expected);
+        assert_eq!(table.lookup("/old"), None);
+        assert_eq!(normalize_path("/"), "/");
+    }
+}
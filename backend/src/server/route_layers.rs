@@ -0,0 +1,193 @@
+use crate::config::RouteLayersConfig;
+use crate::handlers::error::{AppError, ErrorCode};
+use crate::server::app::AppState;
+use crate::server::client_ip::ClientIp;
+use crate::server::public_api::is_public;
+use crate::server::versioning::API_V1_PREFIX;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{
+    Extensions, HeaderMap, HeaderValue, Method, Request, StatusCode, Version, header,
+};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+use std::time::Instant;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{DefaultPredicate, Predicate};
+
+/// Methods allowed by CORS preflights of `cors = true` groups.
+const CORS_METHODS: &str = "GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS";
+
+/// Marks a response of a route group with `compression = true`.
+#[derive(Debug, Clone, Copy)]
+struct Compress;
+
+/// The layers switched on or off for groups of routes by `[[route_layers]]`.
+/// For each layer, the group with the longest prefix that sets it decides,
+/// so `/api` can turn compression on while `/api/search` turns caching off.
+#[derive(Debug, Default)]
+pub struct RouteLayers {
+    /// Longest prefix first.
+    groups: Vec<RouteLayersConfig>,
+}
+
+impl RouteLayers {
+    pub fn new(groups: &[RouteLayersConfig]) -> Self {
+        let mut groups = groups.to_vec();
+        groups.sort_by_key(|group| std::cmp::Reverse(group.prefix.len()));
+        Self { groups }
+    }
+
+    /// How the most specific group matching `path` sets a layer, if any
+    /// does. `/api/v1` paths match the groups of their `/api` path.
+    fn setting(
+        &self,
+        path: &str,
+        layer: impl Fn(&RouteLayersConfig) -> Option<bool>,
+    ) -> Option<bool> {
+        let unversioned = path
+            .strip_prefix(API_V1_PREFIX)
+            .map(|rest| format!("/api{}", rest));
+        let path = unversioned.as_deref().unwrap_or(path);
+        self.groups
+            .iter()
+            .filter(|group| {
+                path.strip_prefix(group.prefix.as_str())
+                    .is_some_and(|rest| {
+                        rest.is_empty() || rest.starts_with('/') || group.prefix.ends_with('/')
+                    })
+            })
+            .find_map(layer)
+    }
+
+    /// Whether responses may be kept in the response cache. On by default.
+    pub fn cache(&self, path: &str) -> bool {
+        self.setting(path, |g| g.cache).unwrap_or(true)
+    }
+
+    /// Whether responses are compressed. Off by default.
+    pub fn compression(&self, path: &str) -> bool {
+        self.setting(path, |g| g.compression).unwrap_or(false)
+    }
+
+    /// Whether any group compresses, so the layer is needed at all.
+    pub fn compresses(&self) -> bool {
+        self.groups.iter().any(|g| g.compression == Some(true))
+    }
+
+    /// `Some(false)` lifts the built-in rate limits, the public API quota
+    /// and the comment limit; `Some(true)` applies the public API quota.
+    pub fn rate_limit(&self, path: &str) -> Option<bool> {
+        self.setting(path, |g| g.rate_limit)
+    }
+
+    /// `Some(false)` drops the public API's CORS headers; `Some(true)`
+    /// allows any origin.
+    pub fn cors(&self, path: &str) -> Option<bool> {
+        self.setting(path, |g| g.cors)
+    }
+}
+
+/// Gzips the responses of `compression = true` groups for clients that
+/// accept it, leaving out the small, image and streamed ones.
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let marked = |_: StatusCode, _: Version, _: &HeaderMap, extensions: &Extensions| {
+        extensions.get::<Compress>().is_some()
+    };
+    CompressionLayer::new().compress_when(DefaultPredicate::new().and(marked))
+}
+
+/// Applies the `[[route_layers]]` settings of routes outside the public
+/// API, which applies its own: the public API quota where `rate_limit =
+/// true` and any-origin CORS where `cors = true`. Marks responses to
+/// compress.
+pub async fn apply_route_layers(
+    State(state): State<Arc<AppState>>,
+    ClientIp(client): ClientIp,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let layers = &state.route_layers;
+    let public = is_public(&path);
+    let cors = !public && layers.cors(&path) == Some(true);
+
+    if cors && req.method() == Method::OPTIONS {
+        let mut res = StatusCode::NO_CONTENT.into_response();
+        let headers = res.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(CORS_METHODS),
+        );
+        if let Some(requested) = req.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from(state.config.public_api.cors_max_age_secs),
+        );
+        return with_cors(res);
+    }
+
+    if !public && layers.rate_limit(&path) == Some(true) {
+        let requests_per_minute = state.config.public_api.requests_per_minute;
+        if let Err(retry_after) = state.public_quota.take(&client, Instant::now()) {
+            let mut res = AppError::TooManyRequests {
+                code: ErrorCode::RouteRateLimited,
+                message: format!(
+                    "These routes allow {} requests a minute per client",
+                    requests_per_minute
+                ),
+            }
+            .into_response();
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return if cors { with_cors(res) } else { res };
+        }
+    }
+
+    let mut res = next.run(req).await;
+    if layers.compression(&path) {
+        res.extensions_mut().insert(Compress);
+    }
+    if cors { with_cors(res) } else { res }
+}
+
+fn with_cors(mut res: Response) -> Response {
+    res.headers_mut().insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(prefix: &str, cache: Option<bool>, compression: Option<bool>) -> RouteLayersConfig {
+        RouteLayersConfig {
+            prefix: prefix.to_string(),
+            cache,
+            compression,
+            rate_limit: None,
+            cors: None,
+        }
+    }
+
+    #[test]
+    fn the_most_specific_group_that_sets_a_layer_decides() {
+        let layers = RouteLayers::new(&[
+            group("/api", None, Some(true)),
+            group("/api/search", Some(false), None),
+        ]);
+        assert!(!layers.cache("/api/search/suggest"));
+        assert!(!layers.cache("/api/v1/search"));
+        assert!(layers.compression("/api/search"));
+        assert!(layers.cache("/api/articles"));
+        assert!(layers.cache("/api/searches"));
+        assert!(!layers.compression("/feed/rss.xml"));
+        assert!(layers.compresses());
+    }
+}
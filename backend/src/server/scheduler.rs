@@ -0,0 +1,485 @@
+use crate::handlers::articles::publish_scheduled;
+use crate::handlers::saved_searches::check_saved_searches;
+use crate::server::app::{AppState, reindex_all_content};
+use crate::services::evergreen_service::suggest_evergreen_posts;
+use crate::services::fediverse_service::refresh_fediverse_replies;
+use crate::services::retention_service::purge_expired;
+use crate::services::sponsor_service::refresh_sponsors;
+use crate::services::sync_service;
+use crate::services::trash_service::purge_expired_trash;
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{info, warn};
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+pub type JobFn = Arc<dyn Fn(Arc<AppState>) -> JobFuture + Send + Sync>;
+
+/// Parsed five-field cron expression (`minute hour day-of-month month
+/// day-of-week`), evaluated in UTC.
+///
+/// Fields accept `*`, single values, `a-b` ranges, `/n` steps and comma
+/// separated lists. The `@hourly`, `@daily`, `@weekly`, `@monthly` and
+/// `@yearly` shorthands are also understood.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step cannot be zero".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a: u32 = a.parse().map_err(|_| format!("invalid value '{}'", a))?;
+            let b: u32 = b.parse().map_err(|_| format!("invalid value '{}'", b))?;
+            (a, b)
+        } else {
+            let value: u32 = range
+                .parse()
+                .map_err(|_| format!("invalid value '{}'", range))?;
+            // `5/15` means "every 15 starting at 5".
+            if part.contains('/') {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "expected 5 fields in cron expression, found {}",
+                fields.len()
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7)?;
+        // Both 0 and 7 mean Sunday.
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days_of_month: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            days_of_week,
+            any_day_of_month: fields[2] == "*",
+            any_day_of_week: fields[4] == "*",
+        })
+    }
+
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let dom = self.days_of_month[time.day() as usize];
+        let dow = self.days_of_week[time.weekday().num_days_from_sunday() as usize];
+        // Standard cron semantics: when both day fields are restricted a
+        // match on either one is enough.
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+
+    /// First matching minute strictly after `after`, searching at most about
+    /// four years ahead.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limit = after + Duration::days(366 * 4);
+
+        while time <= limit {
+            if !self.months[time.month() as usize] || !self.matches_day(&time) {
+                time = (time + Duration::days(1)).with_hour(0)?.with_minute(0)?;
+                continue;
+            }
+            if !self.hours[time.hour() as usize] {
+                time = (time + Duration::hours(1)).with_minute(0)?;
+                continue;
+            }
+            if !self.minutes[time.minute() as usize] {
+                time += Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct JobStatus {
+    pub name: String,
+    pub schedule: String,
+    pub running: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_success: Option<bool>,
+    pub last_error: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+struct ScheduledJob {
+    name: String,
+    expr: String,
+    schedule: CronSchedule,
+    run: JobFn,
+    running: AtomicBool,
+    status: Mutex<JobStatus>,
+}
+
+/// Runs registered jobs on their cron schedules. A job that is still running
+/// when it comes due again is skipped rather than started twice.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<Arc<ScheduledJob>>,
+}
+
+/// Jobs that can be scheduled by name from the `[jobs]` config table.
+fn builtin_job(name: &str) -> Option<JobFn> {
+    let job: JobFn = match name {
+        "reindex_search" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                if state.search_service.is_none() {
+                    return Err("full-text search is disabled".to_string());
+                }
+                reindex_all_content(&state).await;
+                Ok(())
+            }) as JobFuture
+        }),
+        "clear_cache" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                state.cache.invalidate_all();
+                Ok(())
+            }) as JobFuture
+        }),
+        "sync_from_primary" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let report = sync_service::pull(&state).await?;
+                if report.written + report.removed > 0 {
+                    info!(
+                        "Synced from primary: {} files written, {} removed",
+                        report.written, report.removed
+                    );
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "publish_scheduled" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let published = publish_scheduled(&state).await?;
+                if published > 0 {
+                    info!("Published {} scheduled articles", published);
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "check_saved_searches" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let alerts = check_saved_searches(&state).await?;
+                if !alerts.is_empty() {
+                    info!("{} saved searches have new matches", alerts.len());
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "purge_expired_data" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let days = state
+                    .config
+                    .privacy
+                    .retention_days
+                    .ok_or("privacy.retention_days is not set")?;
+                let report = purge_expired(&state.db, days)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if report.total() > 0 {
+                    info!(
+                        "Purged data older than {} days: {} views, {} API key usage rows, {} poll vote addresses",
+                        days, report.article_views, report.api_key_usage, report.poll_votes
+                    );
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "purge_trash" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let days = state.config.trash_retention_days;
+                let purged = purge_expired_trash(&state.data_dir, days, Utc::now())
+                    .map_err(|e| e.to_string())?;
+                if !purged.is_empty() {
+                    info!(
+                        "Purged {} articles trashed more than {} days ago",
+                        purged.len(),
+                        days
+                    );
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "suggest_evergreen_posts" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let picked = suggest_evergreen_posts(&state).await?;
+                if !picked.is_empty() {
+                    info!("Picked {} evergreen articles for approval", picked.len());
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "fetch_fediverse_replies" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let refreshed = refresh_fediverse_replies(&state).await?;
+                if refreshed > 0 {
+                    info!("Fetched the replies of {} Mastodon threads", refreshed);
+                }
+                Ok(())
+            }) as JobFuture
+        }),
+        "refresh_sponsors" => Arc::new(|state: Arc<AppState>| {
+            Box::pin(async move {
+                let count = refresh_sponsors(&state).await?;
+                info!("Refreshed the list of {} sponsors", count);
+                Ok(())
+            }) as JobFuture
+        }),
+        _ => return None,
+    };
+    Some(job)
+}
+
+impl Scheduler {
+    /// Builds a scheduler from the `[jobs]` config table mapping built-in
+    /// job names to cron expressions.
+    pub fn from_config(jobs: &HashMap<String, String>) -> Result<Self, String> {
+        let mut scheduler = Scheduler::default();
+        let mut names: Vec<&String> = jobs.keys().collect();
+        names.sort();
+        for name in names {
+            let run = builtin_job(name).ok_or_else(|| format!("Unknown job '{}'", name))?;
+            scheduler.register(name, &jobs[name], run)?;
+        }
+        Ok(scheduler)
+    }
+
+    pub fn register(&mut self, name: &str, expr: &str, run: JobFn) -> Result<(), String> {
+        let schedule = CronSchedule::parse(expr)
+            .map_err(|e| format!("Invalid schedule for job '{}': {}", name, e))?;
+        let next_run_at = schedule.next_after(Utc::now());
+        self.jobs.push(Arc::new(ScheduledJob {
+            name: name.to_string(),
+            expr: expr.to_string(),
+            schedule,
+            run,
+            running: AtomicBool::new(false),
+            status: Mutex::new(JobStatus {
+                name: name.to_string(),
+                schedule: expr.to_string(),
+                running: false,
+                last_run_at: None,
+                last_duration_ms: None,
+                last_success: None,
+                last_error: None,
+                next_run_at,
+            }),
+        }));
+        Ok(())
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs
+            .iter()
+            .map(|job| {
+                let mut status = job.status.lock().unwrap().clone();
+                status.running = job.running.load(Ordering::SeqCst);
+                status
+            })
+            .collect()
+    }
+
+    fn run_due(&self, state: &Arc<AppState>, now: DateTime<Utc>) {
+        for job in &self.jobs {
+            let due = {
+                let mut status = job.status.lock().unwrap();
+                match status.next_run_at {
+                    Some(next) if next <= now => {
+                        status.next_run_at = job.schedule.next_after(now);
+                        true
+                    }
+                    _ => false,
+                }
+            };
+            if !due {
+                continue;
+            }
+
+            if job
+                .running
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                warn!(
+                    "Skipping job '{}': previous run still in progress",
+                    job.name
+                );
+                continue;
+            }
+
+            let job = Arc::clone(job);
+            let state = Arc::clone(state);
+            tokio::spawn(async move {
+                info!("Running scheduled job '{}' ({})", job.name, job.expr);
+                let started_at = Utc::now();
+                let timer = Instant::now();
+                let result = (job.run)(state).await;
+                {
+                    let mut status = job.status.lock().unwrap();
+                    status.last_run_at = Some(started_at);
+                    status.last_duration_ms = Some(timer.elapsed().as_millis() as u64);
+                    status.last_success = Some(result.is_ok());
+                    status.last_error = result.as_ref().err().cloned();
+                }
+                if let Err(e) = result {
+                    warn!("Scheduled job '{}' failed: {}", job.name, e);
+                }
+                job.running.store(false, Ordering::SeqCst);
+            });
+        }
+    }
+}
+
+/// Spawns the loop that wakes up every minute and runs due jobs.
+pub fn start_scheduler(state: Arc<AppState>) {
+    if state.scheduler.jobs.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let next_minute =
+                now.duration_trunc(Duration::minutes(1)).unwrap_or(now) + Duration::minutes(1);
+            let wait = (next_minute - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            state.scheduler.run_due(&state, Utc::now());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_steps_ranges_and_lists() {
+        let schedule = CronSchedule::parse("*/15 9-17 * * 1,3,5").unwrap();
+        // Wednesday 2024-06-05 10:07 -> 10:15 the same day.
+        assert_eq!(
+            schedule.next_after(at(2024, 6, 5, 10, 7)),
+            Some(at(2024, 6, 5, 10, 15))
+        );
+        // Friday 17:50 -> Monday 09:00.
+        assert_eq!(
+            schedule.next_after(at(2024, 6, 7, 17, 50)),
+            Some(at(2024, 6, 10, 9, 0))
+        );
+    }
+
+    #[test]
+    fn shorthands_expand() {
+        let daily = CronSchedule::parse("@daily").unwrap();
+        assert_eq!(
+            daily.next_after(at(2024, 12, 31, 23, 59)),
+            Some(at(2025, 1, 1, 0, 0))
+        );
+        assert_eq!(daily, CronSchedule::parse("0 0 * * *").unwrap());
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 13th of the month or any Friday.
+        let schedule = CronSchedule::parse("0 12 13 * 5").unwrap();
+        // Tuesday 2024-08-06 -> Friday 2024-08-09.
+        assert_eq!(
+            schedule.next_after(at(2024, 8, 6, 0, 0)),
+            Some(at(2024, 8, 9, 12, 0))
+        );
+        // Saturday 2024-08-10 -> Tuesday 2024-08-13.
+        assert_eq!(
+            schedule.next_after(at(2024, 8, 10, 0, 0)),
+            Some(at(2024, 8, 13, 12, 0))
+        );
+    }
+
+    #[test]
+    fn impossible_dates_yield_none() {
+        let schedule = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(schedule.next_after(at(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(
+            Scheduler::from_config(&HashMap::from([(
+                "no_such_job".to_string(),
+                "@daily".to_string()
+            )]))
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::handlers::feeds::{render_podcast_feed, render_updated_feed};
+use crate::handlers::sitemap::render_sitemap;
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::atomic_file::write_atomic;
+use axum::body::Body;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Notify;
+use tracing::warn;
+
+/// Directory under the data directory holding the snapshots.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// XML documents whose cost grows with the number of articles, kept as
+/// snapshots on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Document {
+    Sitemap,
+    UpdatedFeed,
+    PodcastFeed,
+}
+
+impl Document {
+    pub const ALL: [Document; 3] = [
+        Document::Sitemap,
+        Document::UpdatedFeed,
+        Document::PodcastFeed,
+    ];
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Document::Sitemap => "sitemap.xml",
+            Document::UpdatedFeed => "feed-updated.xml",
+            Document::PodcastFeed => "feed-podcast.xml",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Document::Sitemap => "application/xml",
+            Document::UpdatedFeed | Document::PodcastFeed => "application/rss+xml",
+        }
+    }
+
+    /// The document as `visibility` allows it.
+    pub async fn render_for(self, state: &AppState, visibility: Visibility) -> String {
+        match self {
+            Document::Sitemap => render_sitemap(state, visibility).await,
+            Document::UpdatedFeed => render_updated_feed(state, visibility).await,
+            Document::PodcastFeed => render_podcast_feed(state, visibility).await,
+        }
+    }
+
+    async fn render(self, state: &AppState) -> String {
+        self.render_for(state, Visibility::public()).await
+    }
+}
+
+/// The content version a snapshot was rendered from, and its `ETag`.
+#[derive(Clone)]
+struct Snapshot {
+    version: u64,
+    etag: String,
+}
+
+/// Snapshots of the [`Document`]s as anonymous visitors get them. They are
+/// rendered by a background worker whenever content changes, so requests
+/// only read a file. A snapshot is served only while no change has been
+/// recorded since it was rendered; until the worker catches up, handlers
+/// render the document themselves.
+pub struct XmlSnapshots {
+    dir: PathBuf,
+    /// Bumped by every content change.
+    version: AtomicU64,
+    changed: Notify,
+    built: RwLock<HashMap<Document, Snapshot>>,
+}
+
+impl XmlSnapshots {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            version: AtomicU64::new(0),
+            changed: Notify::new(),
+            built: RwLock::default(),
+        }
+    }
+
+    /// Marks the snapshots as stale and wakes the worker.
+    pub fn content_changed(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+        self.changed.notify_one();
+    }
+
+    /// The current snapshot of `document`, or `304 Not Modified` when it
+    /// matches the request's `If-None-Match`. `None` when there is no
+    /// up-to-date snapshot.
+    pub async fn serve(&self, document: Document, headers: &HeaderMap) -> Option<Response> {
+        let snapshot = self.built.read().unwrap().get(&document).cloned()?;
+        if snapshot.version != self.version.load(Ordering::SeqCst) {
+            return None;
+        }
+        let etag = HeaderValue::from_str(&snapshot.etag).ok()?;
+        let not_modified = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.split(',').any(|tag| tag.trim() == snapshot.etag));
+        if not_modified {
+            return Some((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+        }
+        let body = tokio::fs::read(self.dir.join(document.file_name()))
+            .await
+            .ok()?;
+        Some(
+            (
+                [
+                    (
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static(document.content_type()),
+                    ),
+                    (header::ETAG, etag),
+                ],
+                Body::from(body),
+            )
+                .into_response(),
+        )
+    }
+}
+
+/// Renders every document and writes its snapshot.
+pub async fn refresh_snapshots(state: &AppState) {
+    let snapshots = &state.snapshots;
+    let version = snapshots.version.load(Ordering::SeqCst);
+    if let Err(e) = std::fs::create_dir_all(&snapshots.dir) {
+        warn!("Failed to create {}: {:?}", snapshots.dir.display(), e);
+        return;
+    }
+    for document in Document::ALL {
+        let xml = document.render(state).await;
+        let path = snapshots.dir.join(document.file_name());
+        if let Err(e) = write_atomic(&path, &xml) {
+            warn!("Failed to write snapshot {}: {:?}", path.display(), e);
+            snapshots.built.write().unwrap().remove(&document);
+            continue;
+        }
+        let etag = format!("\"{}\"", &hex::encode(Sha256::digest(xml.as_bytes()))[..32]);
+        snapshots
+            .built
+            .write()
+            .unwrap()
+            .insert(document, Snapshot { version, etag });
+    }
+}
+
+/// Renders the snapshots at startup and again after each content change.
+/// Changes recorded while rendering trigger another pass.
+pub fn start_snapshot_worker(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        loop {
+            refresh_snapshots(&state).await;
+            state.snapshots.changed.notified().await;
+        }
+    });
+}
@@ -0,0 +1,136 @@
+use crate::server::app::AppState;
+use crate::server::public_api::is_public;
+use axum::body::Body;
+use axum::extract::{FromRequestParts, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, Request, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+/// Prefix of the current, versioned API.
+pub const API_V1_PREFIX: &str = "/api/v1";
+const LEGACY_API_PREFIX: &str = "/api/";
+
+/// API version a request was made against. Routes are registered once under
+/// their legacy `/api/...` paths; `/api/v1/...` requests are rewritten onto
+/// them and tagged with [`ApiVersion::V1`] so handlers can vary their
+/// response format when a breaking change is rolled out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    Legacy,
+    V1,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<ApiVersion>()
+            .copied()
+            .unwrap_or(ApiVersion::Legacy))
+    }
+}
+
+pub fn is_versioned(path: &str) -> bool {
+    path.strip_prefix(API_V1_PREFIX)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// `path` as the route table knows it, with a `/api/v1` prefix mapped onto
+/// `/api`, for layers that run before [`rewrite_versioned_path`].
+pub fn unversioned_path(path: &str) -> Cow<'_, str> {
+    if is_versioned(path) {
+        Cow::Owned(format!("/api{}", &path[API_V1_PREFIX.len()..]))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+fn strip_version(uri: &Uri) -> Option<Uri> {
+    if !is_versioned(uri.path()) {
+        return None;
+    }
+    let rest = &uri.path()[API_V1_PREFIX.len()..];
+    let path = format!("/api{}", rest);
+    let path_and_query = match uri.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    };
+    path_and_query.parse().ok()
+}
+
+/// Maps `/api/v1/...` onto the route table. Must run before routing.
+pub async fn rewrite_versioned_path(mut req: Request<Body>) -> Request<Body> {
+    if let Some(uri) = strip_version(req.uri()) {
+        *req.uri_mut() = uri;
+        req.extensions_mut().insert(ApiVersion::V1);
+    }
+    req
+}
+
+/// Marks responses served from unversioned `/api/...` paths as deprecated and
+/// points clients at the `/api/v1` successor. The public API is left alone,
+/// being a stable surface of its own. When `legacy_api_sunset` is
+/// configured it is advertised in a `Sunset` header as well.
+pub async fn deprecation_headers(
+    State(state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let mut res = next.run(req).await;
+
+    if !path.starts_with(LEGACY_API_PREFIX) || is_versioned(&path) || is_public(&path) {
+        return res;
+    }
+
+    let headers = res.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    let successor = format!(
+        "<{}{}>; rel=\"successor-version\"",
+        API_V1_PREFIX,
+        &path["/api".len()..]
+    );
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert("link", link);
+    }
+    if let Some(sunset) = &state.config.legacy_api_sunset
+        && let Ok(sunset) = HeaderValue::from_str(sunset)
+    {
+        headers.insert("sunset", sunset);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_version_prefix() {
+        let uri: Uri = "/api/v1/articles?page=2".parse().unwrap();
+        assert_eq!(strip_version(&uri).unwrap(), "/api/articles?page=2");
+
+        let uri: Uri = "/api/v1".parse().unwrap();
+        assert_eq!(strip_version(&uri).unwrap(), "/api");
+
+        assert_eq!(
+            unversioned_path("/api/v1/previews/abc"),
+            "/api/previews/abc"
+        );
+        assert_eq!(unversioned_path("/api/v10/articles"), "/api/v10/articles");
+    }
+
+    #[test]
+    fn leaves_other_paths_alone() {
+        for path in ["/api/articles", "/api/v10/articles", "/sitemap.xml"] {
+            let uri: Uri = path.parse().unwrap();
+            assert!(strip_version(&uri).is_none(), "{}", path);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::models::article::Article;
+use crate::server::access::Access;
+use crate::server::app::AppState;
+use crate::services::service::ArticleStore;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+/// Which articles and notes a request is served.
+///
+/// Listings, detail pages, search (including its fallback), feeds, the
+/// sitemap and statistics all filter through [`Visibility::allows`], so the
+/// rule lives in one place: an entry is shown when it is published, that is
+/// has the `published` status and is not a private note, and its category
+/// is not restricted from the requester (see [`Access`]). Private notes are served to the
+/// author by their own endpoints, which do not go through this policy.
+///
+/// Archived entries stay reachable by their slug through
+/// [`Visibility::allows_direct`], but are only listed for admin requests
+/// that ask for them with [`Visibility::with_archived`].
+#[derive(Debug, Clone, Copy)]
+pub struct Visibility {
+    access: Access,
+    archived: bool,
+}
+
+impl Visibility {
+    /// What an anonymous visitor sees, for responses that are the same for
+    /// everyone.
+    pub fn public() -> Self {
+        Access::anonymous().into()
+    }
+
+    pub fn includes_restricted(self) -> bool {
+        self.access.includes_restricted()
+    }
+
+    /// Lists archived entries too when `include_archived` is asked for by
+    /// an admin request; other requests are unaffected.
+    pub fn with_archived(self, include_archived: bool) -> Self {
+        Self {
+            archived: include_archived && self.access.is_admin(),
+            ..self
+        }
+    }
+
+    /// Whether `article` may be listed.
+    pub fn allows(self, store: &ArticleStore, article: &Article) -> bool {
+        let metadata = &article.metadata;
+        !article.deleted
+            && (metadata.is_published() || (self.archived && metadata.is_archived()))
+            && self.access.can_view(store, article)
+    }
+
+    /// Whether the body of `article` may be served, which for a
+    /// `members_only` article takes a member (see [`Access`]). Its metadata
+    /// is shown to everyone [`Visibility::allows`] it for.
+    pub fn unlocks(self, article: &Article) -> bool {
+        !article.metadata.members_only || self.access.is_member()
+    }
+
+    /// Whether `article` may be served by its slug, which archived entries
+    /// still are.
+    pub fn allows_direct(self, store: &ArticleStore, article: &Article) -> bool {
+        let metadata = &article.metadata;
+        !article.deleted
+            && (metadata.is_published() || metadata.is_archived())
+            && self.access.can_view(store, article)
+    }
+}
+
+impl From<Access> for Visibility {
+    fn from(access: Access) -> Self {
+        Self {
+            access,
+            archived: false,
+        }
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for Visibility {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        Access::from_request_parts(parts, state)
+            .await
+            .map(Self::from)
+    }
+}
@@ -1,4 +1,68 @@
+pub mod analytics_service;
 pub mod article_service;
+pub mod bundle_service;
 pub mod comment_service;
+pub mod disqus_service;
+pub mod duplicate_service;
+pub mod goal_service;
+pub mod guestbook_service;
+pub mod job_queue_service;
+pub mod note_crypto;
+pub mod pdf_service;
+pub mod poll_service;
+pub mod reaction_service;
+pub mod redirect_service;
+pub mod render_service;
 pub mod search;
+pub mod seo_service;
 pub mod service;
+pub mod shortlink_service;
+pub mod summarizer;
+pub mod atomic_file;
+pub mod front_matter;
+pub mod site_time;
+pub mod taxonomy;
+pub mod changelog;
+pub mod suggestion_service;
+pub mod notification_service;
+pub mod api_key_service;
+pub mod site_event_service;
+pub mod sync_service;
+pub mod audit_service;
+pub mod ping_service;
+pub mod github_service;
+pub mod preview_service;
+pub mod lock_service;
+pub mod merge_service;
+pub mod saved_search_service;
+pub mod spellcheck;
+pub mod moderation_service;
+pub mod preview_branch_service;
+pub mod undo_service;
+pub mod user_preferences_service;
+pub mod account_service;
+pub mod retention_service;
+pub mod field_crypto;
+pub mod asset_service;
+pub mod image_service;
+pub mod link_preview;
+pub mod crosspost_service;
+pub mod fediverse_service;
+pub mod evergreen_service;
+pub mod change_service;
+pub mod presence_service;
+pub mod collection_service;
+pub mod glossary_service;
+pub mod citation_service;
+pub mod download_service;
+pub mod access_code_service;
+pub mod sponsor_service;
+pub mod diagnostics_service;
+pub mod error_reporter;
+pub mod build_info;
+pub mod bootstrap;
+pub mod collab_service;
+pub mod content_diff_service;
+pub mod trash_service;
+pub mod secret_box;
+pub mod token;
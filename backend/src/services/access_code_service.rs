@@ -0,0 +1,149 @@
+use crate::db::DbPool;
+use crate::models::access_code::{AccessCode, IssuedAccessCode};
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Letters and digits that cannot be mistaken for one another when a code
+/// is read out or typed.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_GROUPS: usize = 3;
+const CODE_GROUP_LENGTH: usize = 4;
+
+const CODE_COLUMNS: &str = "id, name, max_redemptions, redemptions, revoked, created_at";
+
+/// A code such as `K7QF-M2XD-9RHT`.
+fn generate_code() -> String {
+    let mut bytes = [0u8; CODE_GROUPS * CODE_GROUP_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+        .chunks(CODE_GROUP_LENGTH)
+        .map(|group| {
+            group
+                .iter()
+                .map(|b| CODE_ALPHABET[*b as usize % CODE_ALPHABET.len()] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Codes are matched regardless of case, dashes and spaces.
+fn hash_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+pub async fn issue_code(
+    pool: &DbPool,
+    name: &str,
+    max_redemptions: Option<u64>,
+) -> Result<IssuedAccessCode, sqlx::Error> {
+    let code = generate_code();
+    let access_code = sqlx::query_as(&format!(
+        "INSERT INTO access_codes (name, code_hash, max_redemptions) VALUES (?, ?, ?) \
+         RETURNING {}",
+        CODE_COLUMNS
+    ))
+    .bind(name)
+    .bind(hash_code(&code))
+    .bind(max_redemptions.map(|max| max as i64))
+    .fetch_one(pool)
+    .await?;
+    Ok(IssuedAccessCode { access_code, code })
+}
+
+pub async fn list_codes(pool: &DbPool) -> Result<Vec<AccessCode>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM access_codes ORDER BY id",
+        CODE_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Revokes a code for good, which also ends the membership of everyone
+/// who redeemed it. Returns `false` when it does not exist or was already
+/// revoked.
+pub async fn revoke_code(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query("UPDATE access_codes SET revoked = 1 WHERE id = ? AND revoked = 0")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(updated > 0)
+}
+
+/// Counts a redemption of `code` and returns it, or `None` when the code is
+/// unknown, revoked or used up.
+pub async fn redeem_code(pool: &DbPool, code: &str) -> Result<Option<AccessCode>, sqlx::Error> {
+    // Checked and counted in one statement, so concurrent redemptions
+    // cannot go past the limit.
+    sqlx::query_as(&format!(
+        "UPDATE access_codes SET redemptions = redemptions + 1 \
+         WHERE code_hash = ? AND revoked = 0 \
+         AND (max_redemptions IS NULL OR redemptions < max_redemptions) RETURNING {}",
+        CODE_COLUMNS
+    ))
+    .bind(hash_code(code))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Whether the code with `id` still grants membership to those who
+/// redeemed it.
+pub async fn code_is_live(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let live: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM access_codes WHERE id = ? AND revoked = 0")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(live.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn codes_are_redeemed_until_used_up_or_revoked() {
+        let pool = memory_db().await;
+        let issued = issue_code(&pool, "workshop", Some(2)).await.unwrap();
+        assert_eq!(issued.code.len(), 14);
+        assert!(
+            issued
+                .code
+                .bytes()
+                .all(|b| b == b'-' || CODE_ALPHABET.contains(&b))
+        );
+
+        let typed = issued.code.to_lowercase().replace('-', " ");
+        let redeemed = redeem_code(&pool, &typed).await.unwrap().unwrap();
+        assert_eq!(redeemed.redemptions, 1);
+        assert!(redeem_code(&pool, &issued.code).await.unwrap().is_some());
+        assert!(redeem_code(&pool, &issued.code).await.unwrap().is_none());
+        assert!(
+            redeem_code(&pool, "NOPE-NOPE-NOPE")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let id = issued.access_code.id;
+        assert!(code_is_live(&pool, id).await.unwrap());
+        assert!(revoke_code(&pool, id).await.unwrap());
+        assert!(!revoke_code(&pool, id).await.unwrap());
+        assert!(!code_is_live(&pool, id).await.unwrap());
+
+        let open = issue_code(&pool, "newsletter", None).await.unwrap();
+        for _ in 0..3 {
+            assert!(redeem_code(&pool, &open.code).await.unwrap().is_some());
+        }
+        assert_eq!(list_codes(&pool).await.unwrap().len(), 2);
+    }
+}
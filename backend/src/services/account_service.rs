@@ -0,0 +1,118 @@
+use crate::db::DbPool;
+use crate::models::account::{AccountDeletion, AccountExport, PollVote};
+use crate::models::user::User;
+use crate::services::field_crypto::FieldCipher;
+use crate::services::user_preferences_service::get_preferences;
+
+/// Everything stored about `user`, oldest records first.
+pub async fn export_account(
+    pool: &DbPool,
+    cipher: Option<&FieldCipher>,
+    user: User,
+) -> Result<AccountExport, sqlx::Error> {
+    let id = user.github_id as i64;
+    let preferences = get_preferences(pool, cipher, user.github_id).await?;
+    let comments = sqlx::query_as(
+        "SELECT id, article_slug, author_github_id, content, created_at, author_name \
+         FROM comments WHERE author_github_id = ? ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    let reactions = sqlx::query_as(
+        "SELECT article_slug, user_github_id, emoji, created_at FROM reactions \
+         WHERE user_github_id = ? ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    let guestbook_entries = sqlx::query_as(
+        "SELECT id, author_github_id, author_login, content, approved, created_at \
+         FROM guestbook_entries WHERE author_github_id = ? ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    let suggestions = sqlx::query_as(
+        "SELECT id, article_slug, author_github_id, author_login, original, replacement, \
+         note, status, created_at FROM article_suggestions WHERE author_github_id = ? \
+         ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+    let poll_votes: Vec<PollVote> = sqlx::query_as(
+        "SELECT poll_id, option_index, created_at FROM poll_votes \
+         WHERE voter_key = ? ORDER BY id",
+    )
+    .bind(format!("user:{}", id))
+    .fetch_all(pool)
+    .await?;
+    Ok(AccountExport {
+        user,
+        preferences,
+        comments,
+        reactions,
+        guestbook_entries,
+        suggestions,
+        poll_votes,
+    })
+}
+
+/// Removes the personal data of a user in one transaction. Comments,
+/// suggestions and poll votes stay, detached from the account, so threads
+/// and poll results keep making sense; reactions, guestbook entries and
+/// preferences are deleted.
+pub async fn delete_account(pool: &DbPool, github_id: u64) -> Result<AccountDeletion, sqlx::Error> {
+    let id = github_id as i64;
+    let mut tx = pool.begin().await?;
+    let comments_anonymized = sqlx::query(
+        "UPDATE comments SET author_github_id = 0, author_name = NULL \
+         WHERE author_github_id = ?",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let suggestions_anonymized = sqlx::query(
+        "UPDATE article_suggestions SET author_github_id = 0, author_login = '' \
+         WHERE author_github_id = ?",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    // Row ids keep the anonymized keys unique within a poll.
+    let poll_votes_anonymized =
+        sqlx::query("UPDATE poll_votes SET voter_key = 'deleted:' || id WHERE voter_key = ?")
+            .bind(format!("user:{}", id))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    let reactions_deleted = sqlx::query("DELETE FROM reactions WHERE user_github_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let guestbook_entries_deleted =
+        sqlx::query("DELETE FROM guestbook_entries WHERE author_github_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    let preferences_deleted = sqlx::query("DELETE FROM user_preferences WHERE github_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+        > 0;
+    tx.commit().await?;
+    Ok(AccountDeletion {
+        comments_anonymized,
+        suggestions_anonymized,
+        poll_votes_anonymized,
+        reactions_deleted,
+        guestbook_entries_deleted,
+        preferences_deleted,
+    })
+}
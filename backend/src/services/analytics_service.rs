@@ -0,0 +1,270 @@
+use crate::db::DbPool;
+use crate::models::analytics::{ReferrerCount, ViewRecord};
+use chrono::NaiveDate;
+use reqwest::Url;
+
+const MAX_UTM_LENGTH: usize = 64;
+/// Counts shown publicly are floored to multiples of this, and articles
+/// with fewer views are not listed, so no figure can single out a handful
+/// of readers.
+pub const K_ANONYMITY: i64 = 10;
+
+/// Where a view came from, after scrubbing. Only the referring site's host
+/// and the source/medium/campaign parameters are kept: no full referrer URL,
+/// client address or user agent, and no `utm_term`/`utm_content`, which
+/// often carry per-recipient identifiers.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ViewSource {
+    pub referrer_host: Option<String>,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+}
+
+/// Host of an http(s) referrer, lowercased and without `www.`. Links from
+/// `own_host` are internal navigation and count as no referrer.
+pub fn referrer_host(referrer: &str, own_host: Option<&str>) -> Option<String> {
+    let url = Url::parse(referrer.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let own = own_host.map(|h| h.to_lowercase());
+    if own.as_deref().map(|h| h.strip_prefix("www.").unwrap_or(h)) == Some(host) {
+        return None;
+    }
+    Some(host.to_string())
+}
+
+/// Lowercased campaign value limited to a short, plain vocabulary. Values
+/// that look like e-mail addresses are dropped entirely.
+pub fn scrub_utm(value: &str) -> Option<String> {
+    if value.contains('@') {
+        return None;
+    }
+    let scrubbed: String = value
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | ' '))
+        .take(MAX_UTM_LENGTH)
+        .collect();
+    let scrubbed = scrubbed.trim();
+    (!scrubbed.is_empty()).then(|| scrubbed.to_string())
+}
+
+pub async fn record_view(
+    pool: &DbPool,
+    slug: &str,
+    source: &ViewSource,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO article_views (slug, referrer_host, utm_source, utm_medium, utm_campaign) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(slug)
+    .bind(&source.referrer_host)
+    .bind(&source.utm_source)
+    .bind(&source.utm_medium)
+    .bind(&source.utm_campaign)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Views grouped by referrer and campaign, most frequent first, for one
+/// article or, without a slug, the whole site.
+pub async fn referrer_counts(
+    pool: &DbPool,
+    slug: Option<&str>,
+) -> Result<Vec<ReferrerCount>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT referrer_host AS referrer, utm_source, utm_medium, utm_campaign, \
+         COUNT(*) AS views FROM article_views WHERE (?1 IS NULL OR slug = ?1) \
+         GROUP BY referrer_host, utm_source, utm_medium, utm_campaign \
+         ORDER BY views DESC, referrer_host",
+    )
+    .bind(slug)
+    .fetch_all(pool)
+    .await
+}
+
+/// Floors a view count to the public granularity.
+pub fn coarsen(views: i64) -> i64 {
+    views / K_ANONYMITY * K_ANONYMITY
+}
+
+pub async fn total_views(pool: &DbPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM article_views")
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn article_views(pool: &DbPool, slug: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM article_views WHERE slug = ?")
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+}
+
+/// Views per article since `since`, most viewed first.
+pub async fn views_since(
+    pool: &DbPool,
+    since: NaiveDate,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT slug, COUNT(*) AS views FROM article_views WHERE viewed_on >= ? \
+         GROUP BY slug ORDER BY views DESC, slug",
+    )
+    .bind(since.format("%Y-%m-%d").to_string())
+    .fetch_all(pool)
+    .await
+}
+
+/// `YYYY-MM-DD` bounds of an inclusive date range, for SQL comparison with
+/// stored dates.
+pub fn day_bounds(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> (Option<String>, Option<String>) {
+    let day = |date: NaiveDate| date.format("%Y-%m-%d").to_string();
+    (from.map(day), to.map(day))
+}
+
+/// Every stored view from `from` to `to`, both inclusive, oldest first.
+pub async fn export_views(
+    pool: &DbPool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<ViewRecord>, sqlx::Error> {
+    let (from, to) = day_bounds(from, to);
+    sqlx::query_as(
+        "SELECT slug, viewed_on, referrer_host, utm_source, utm_medium, utm_campaign \
+         FROM article_views WHERE (?1 IS NULL OR viewed_on >= ?1) \
+         AND (?2 IS NULL OR viewed_on <= ?2) ORDER BY viewed_on, id",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stores `views` in one transaction. Views carry no identity, so every row
+/// is added: importing the same file twice counts its views twice.
+pub async fn import_views(pool: &DbPool, views: &[ViewRecord]) -> Result<usize, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for view in views {
+        sqlx::query(
+            "INSERT INTO article_views \
+             (slug, viewed_on, referrer_host, utm_source, utm_medium, utm_campaign) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&view.slug)
+        .bind(&view.viewed_on)
+        .bind(&view.referrer_host)
+        .bind(&view.utm_source)
+        .bind(&view.utm_medium)
+        .bind(&view.utm_campaign)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(views.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[test]
+    fn scrubs_referrers_and_campaigns() {
+        let own = Some("blog.example");
+        assert_eq!(
+            referrer_host("https://www.News.example/item?id=1&user=bob", own),
+            Some("news.example".to_string())
+        );
+        assert_eq!(referrer_host("https://blog.example/articles/x", own), None);
+        assert_eq!(referrer_host("android-app://com.slack", own), None);
+        assert_eq!(referrer_host("not a url", own), None);
+
+        assert_eq!(
+            scrub_utm(" Newsletter<script> "),
+            Some("newsletterscript".to_string())
+        );
+        assert_eq!(scrub_utm("bob@example.com"), None);
+        assert_eq!(scrub_utm("  "), None);
+        assert_eq!(scrub_utm(&"x".repeat(100)).unwrap().len(), MAX_UTM_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn groups_views_by_source() {
+        let pool = memory_db().await;
+        let hn = ViewSource {
+            referrer_host: Some("news.example".to_string()),
+            ..Default::default()
+        };
+        record_view(&pool, "a", &hn).await.unwrap();
+        record_view(&pool, "a", &hn).await.unwrap();
+        record_view(&pool, "a", &ViewSource::default())
+            .await
+            .unwrap();
+        record_view(&pool, "b", &hn).await.unwrap();
+
+        let counts = referrer_counts(&pool, Some("a")).await.unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[0].referrer.as_deref(), Some("news.example"));
+        assert_eq!(counts[0].views, 2);
+        assert_eq!(counts[1].referrer, None);
+
+        let counts = referrer_counts(&pool, None).await.unwrap();
+        assert_eq!(counts[0].views, 3);
+
+        assert_eq!(total_views(&pool).await.unwrap(), 4);
+        let today = chrono::Utc::now().date_naive();
+        assert_eq!(
+            views_since(&pool, today).await.unwrap(),
+            vec![("a".to_string(), 3), ("b".to_string(), 1)]
+        );
+        assert!(
+            views_since(&pool, today.succ_opt().unwrap())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!((coarsen(9), coarsen(27)), (0, 20));
+    }
+
+    #[tokio::test]
+    async fn exports_views_by_day_and_imports_them_back() {
+        let pool = memory_db().await;
+        let view = |slug: &str, day: &str| ViewRecord {
+            slug: slug.to_string(),
+            viewed_on: day.to_string(),
+            referrer_host: None,
+            utm_source: Some("newsletter".to_string()),
+            utm_medium: None,
+            utm_campaign: None,
+        };
+        let views = [
+            view("a", "2024-07-01"),
+            view("b", "2024-07-02"),
+            view("a", "2024-07-03"),
+        ];
+        assert_eq!(import_views(&pool, &views).await.unwrap(), 3);
+
+        assert_eq!(export_views(&pool, None, None).await.unwrap(), views);
+        let day = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        assert_eq!(
+            export_views(&pool, day("2024-07-02"), day("2024-07-03"))
+                .await
+                .unwrap(),
+            views[1..]
+        );
+        assert_eq!(
+            export_views(&pool, None, day("2024-07-01")).await.unwrap(),
+            views[..1]
+        );
+    }
+}
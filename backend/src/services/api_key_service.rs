@@ -0,0 +1,195 @@
+use crate::db::DbPool;
+use crate::models::api_key::{ApiKey, DailyUsage, IssuedApiKey};
+use crate::services::token::{generate_token, hash_token};
+
+/// Prefix of issued tokens, so they are recognizable in logs and secret
+/// scanners.
+const TOKEN_PREFIX: &str = "scribe_";
+
+const KEY_COLUMNS: &str = "id, name, request_count, last_used_at, revoked, created_at, daily_quota";
+
+/// Outcome of a request made with an API token.
+#[derive(Debug)]
+pub enum KeyUse {
+    /// The request was counted against the key.
+    Counted(ApiKey),
+    /// The key has used up its daily quota; the request was not counted.
+    OverQuota(u64),
+    /// The token is unknown or revoked.
+    Invalid,
+}
+
+pub async fn issue_key(
+    pool: &DbPool,
+    name: &str,
+    daily_quota: Option<u64>,
+) -> Result<IssuedApiKey, sqlx::Error> {
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+    let key = sqlx::query_as(&format!(
+        "INSERT INTO api_keys (name, token_hash, daily_quota) VALUES (?, ?, ?) RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(name)
+    .bind(hash_token(&token))
+    .bind(daily_quota.map(|quota| quota as i64))
+    .fetch_one(pool)
+    .await?;
+    Ok(IssuedApiKey { key, token })
+}
+
+pub async fn list_keys(pool: &DbPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM api_keys ORDER BY id", KEY_COLUMNS))
+        .fetch_all(pool)
+        .await
+}
+
+/// Revokes a key for good. Returns `false` when it does not exist or was
+/// already revoked.
+pub async fn revoke_key(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query("UPDATE api_keys SET revoked = 1 WHERE id = ? AND revoked = 0")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(updated > 0)
+}
+
+pub async fn get_key(pool: &DbPool, id: i64) -> Result<Option<ApiKey>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM api_keys WHERE id = ?",
+        KEY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Daily quota of `key`: its own when set, otherwise `default_quota`.
+/// `None` when the key is not limited.
+pub fn quota_for(key: &ApiKey, default_quota: u64) -> Option<u64> {
+    let quota = key.daily_quota.map_or(default_quota, |quota| quota as u64);
+    (quota > 0).then_some(quota)
+}
+
+/// Looks up the live key for `token` and counts the request against it and
+/// its usage for the current UTC day, unless that would exceed its quota.
+pub async fn use_key(
+    pool: &DbPool,
+    token: &str,
+    default_quota: u64,
+) -> Result<KeyUse, sqlx::Error> {
+    let key: Option<ApiKey> = sqlx::query_as(&format!(
+        "SELECT {} FROM api_keys WHERE token_hash = ? AND revoked = 0",
+        KEY_COLUMNS
+    ))
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await?;
+    let Some(key) = key else {
+        return Ok(KeyUse::Invalid);
+    };
+
+    let quota = quota_for(&key, default_quota);
+    // The conditional upsert checks and counts in one statement, so
+    // concurrent requests cannot overshoot the quota.
+    let counted = sqlx::query(
+        "INSERT INTO api_key_usage (key_id, day, requests) VALUES (?, date('now'), 1) \
+         ON CONFLICT (key_id, day) DO UPDATE SET requests = requests + 1 WHERE requests < ?",
+    )
+    .bind(key.id)
+    .bind(quota.map_or(i64::MAX, |quota| quota as i64))
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if counted == 0 {
+        return Ok(KeyUse::OverQuota(quota.unwrap_or_default()));
+    }
+
+    let key = sqlx::query_as(&format!(
+        "UPDATE api_keys SET request_count = request_count + 1, last_used_at = CURRENT_TIMESTAMP \
+         WHERE id = ? RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(key.id)
+    .fetch_one(pool)
+    .await?;
+    Ok(KeyUse::Counted(key))
+}
+
+/// Requests made with key `id` on each of the last `days` UTC days that saw
+/// any, newest first.
+pub async fn daily_usage(
+    pool: &DbPool,
+    id: i64,
+    days: usize,
+) -> Result<Vec<DailyUsage>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT day, requests FROM api_key_usage \
+         WHERE key_id = ? AND day > date('now', '-' || ? || ' days') ORDER BY day DESC",
+    )
+    .bind(id)
+    .bind(days as i64)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn tokens_are_counted_until_revoked() {
+        let pool = memory_db().await;
+        let issued = issue_key(&pool, "reader app", None).await.unwrap();
+        assert!(issued.token.starts_with(TOKEN_PREFIX));
+        let other = issue_key(&pool, "other", None).await.unwrap();
+        assert_ne!(issued.token, other.token);
+
+        use_key(&pool, &issued.token, 0).await.unwrap();
+        let KeyUse::Counted(key) = use_key(&pool, &issued.token, 0).await.unwrap() else {
+            panic!("key was not counted");
+        };
+        assert_eq!(key.request_count, 2);
+        assert!(key.last_used_at.is_some());
+        let usage = daily_usage(&pool, key.id, 30).await.unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].requests, 2);
+        assert!(matches!(
+            use_key(&pool, "scribe_unknown", 0).await.unwrap(),
+            KeyUse::Invalid
+        ));
+
+        assert!(revoke_key(&pool, key.id).await.unwrap());
+        assert!(!revoke_key(&pool, key.id).await.unwrap());
+        assert!(matches!(
+            use_key(&pool, &issued.token, 0).await.unwrap(),
+            KeyUse::Invalid
+        ));
+        assert!(list_keys(&pool).await.unwrap()[0].revoked);
+    }
+
+    #[tokio::test]
+    async fn quotas_stop_counting_once_used_up() {
+        let pool = memory_db().await;
+        let limited = issue_key(&pool, "limited", Some(2)).await.unwrap();
+        let unlimited = issue_key(&pool, "unlimited", Some(0)).await.unwrap();
+        assert_eq!(quota_for(&limited.key, 100), Some(2));
+        assert_eq!(quota_for(&unlimited.key, 100), None);
+
+        for _ in 0..2 {
+            let counted = use_key(&pool, &limited.token, 100).await.unwrap();
+            assert!(matches!(counted, KeyUse::Counted(_)));
+        }
+        assert!(matches!(
+            use_key(&pool, &limited.token, 100).await.unwrap(),
+            KeyUse::OverQuota(2)
+        ));
+        for _ in 0..3 {
+            let counted = use_key(&pool, &unlimited.token, 1).await.unwrap();
+            assert!(matches!(counted, KeyUse::Counted(_)));
+        }
+        let key = get_key(&pool, limited.key.id).await.unwrap().unwrap();
+        assert_eq!(key.request_count, 2);
+    }
+}
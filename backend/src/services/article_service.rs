@@ -1,17 +1,27 @@
 use crate::models::article::Article;
-use chrono::Utc;
+use crate::services::atomic_file::write_atomic;
+use crate::services::site_time::{parse_date, site_timezone};
+use chrono::{DateTime, Utc};
 use std::fs::{self, OpenOptions};
 use std::io::{ErrorKind, Result, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub fn save_version(article: &Article) -> Result<()> {
-    let version_dir = format!("data/articles/{}/versions", article.slug);
+/// Directory holding the saved versions of `slug` under the data root.
+/// Articles in categories are keyed as `category/slug`.
+pub fn version_dir(data_dir: &Path, slug: &str) -> PathBuf {
+    data_dir.join("articles").join(slug).join("versions")
+}
+
+/// Saves the current file content of `article` as a new version and returns
+/// the path of the version file.
+pub fn save_version(data_dir: &Path, article: &Article) -> Result<PathBuf> {
+    let version_dir = version_dir(data_dir, &article.slug_with_category());
     fs::create_dir_all(&version_dir)?;
     let version = Utc::now().timestamp_millis() as u64;
     let content = fs::read_to_string(&article.file_path)?;
     let mut candidate = version;
     loop {
-        let version_file = Path::new(&version_dir).join(format!("{}.md", candidate));
+        let version_file = version_dir.join(format!("{}.md", candidate));
         match OpenOptions::new()
             .write(true)
             .create_new(true)
@@ -19,7 +29,7 @@ pub fn save_version(article: &Article) -> Result<()> {
         {
             Ok(mut file) => {
                 file.write_all(content.as_bytes())?;
-                break;
+                return Ok(version_file);
             }
             Err(e) if e.kind() == ErrorKind::AlreadyExists => {
                 candidate += 1;
@@ -27,5 +37,306 @@ pub fn save_version(article: &Article) -> Result<()> {
             Err(e) => return Err(e),
         }
     }
-    Ok(())
+}
+
+/// File holding the change message of the version saved at `version_file`.
+fn message_path(version_file: &Path) -> PathBuf {
+    version_file.with_extension("message")
+}
+
+/// The change message recorded with `version` of `slug`, if any.
+pub fn version_message(data_dir: &Path, slug: &str, version: u64) -> Option<String> {
+    let version_file = version_dir(data_dir, slug).join(format!("{}.md", version));
+    fs::read_to_string(message_path(&version_file)).ok()
+}
+
+/// Versions saved for `slug`, oldest first. A version is numbered by the
+/// millisecond timestamp of the edit that replaced it.
+pub fn saved_versions(data_dir: &Path, slug: &str) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(version_dir(data_dir, slug)) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<u64> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?.strip_suffix(".md")?.parse().ok()
+        })
+        .collect();
+    versions.sort_unstable();
+    versions
+}
+
+/// When `article` was last revised after it was published: the later of
+/// `last_updated` in its front matter and its most recent edit through the
+/// API. `None` for articles untouched since their publish date.
+pub fn revised_at(data_dir: &Path, article: &Article) -> Option<DateTime<Utc>> {
+    let from_front_matter = article
+        .metadata
+        .last_updated
+        .as_deref()
+        .and_then(|value| parse_date(value, site_timezone()));
+    let from_versions = saved_versions(data_dir, &article.slug_with_category())
+        .last()
+        .and_then(|&version| DateTime::from_timestamp_millis(version as i64));
+    from_front_matter
+        .max(from_versions)
+        .filter(|revised| *revised > article.metadata.date)
+}
+
+/// Tracks the on-disk effects of saving an article so they can be undone
+/// when a later step fails, leaving the file and its versions as they were.
+pub struct ArticleWrite {
+    path: PathBuf,
+    previous: Option<String>,
+    moved_from: Option<(PathBuf, String)>,
+    /// Version directory moved along with the article, from and to.
+    moved_versions: Option<(PathBuf, PathBuf)>,
+    version: Option<PathBuf>,
+    message: Option<PathBuf>,
+}
+
+impl ArticleWrite {
+    /// Remembers the current content of `path`, if any, before it is
+    /// overwritten.
+    pub fn begin(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let previous = match fs::read_to_string(&path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            path,
+            previous,
+            moved_from: None,
+            moved_versions: None,
+            version: None,
+            message: None,
+        })
+    }
+
+    /// Removes `old`, the file the article was moved away from. A rollback
+    /// puts it back.
+    pub fn remove_moved(&mut self, old: &Path) -> Result<()> {
+        let content = fs::read_to_string(old)?;
+        fs::remove_file(old)?;
+        self.moved_from = Some((old.to_path_buf(), content));
+        Ok(())
+    }
+
+    /// Moves the saved versions of an article that moved from the `from`
+    /// key to the `to` key. Nothing is moved when there are none, or when
+    /// `to` already has versions of its own. A rollback moves them back.
+    pub fn move_versions(&mut self, data_dir: &Path, from: &str, to: &str) -> Result<()> {
+        let (old, new) = (version_dir(data_dir, from), version_dir(data_dir, to));
+        if from == to || !old.exists() || new.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = new.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old, &new)?;
+        self.moved_versions = Some((old, new));
+        Ok(())
+    }
+
+    /// Saves a version of `article` that a rollback removes again, along
+    /// with `message` describing the change when one is given.
+    pub fn save_version(
+        &mut self,
+        data_dir: &Path,
+        article: &Article,
+        message: Option<&str>,
+    ) -> Result<()> {
+        let version = save_version(data_dir, article)?;
+        self.version = Some(version.clone());
+        if let Some(message) = message {
+            let path = message_path(&version);
+            self.message = Some(path.clone());
+            write_atomic(path, message)?;
+        }
+        Ok(())
+    }
+
+    /// Restores the previous file content, or removes a newly created file,
+    /// and drops the saved version. Returns a description of each step,
+    /// including any that could not be undone.
+    pub fn rollback(self) -> Vec<String> {
+        let mut steps = Vec::new();
+        let restored = match &self.previous {
+            Some(content) => write_atomic(&self.path, content)
+                .map(|()| "restored the previous article file".to_string()),
+            None => {
+                fs::remove_file(&self.path).map(|()| "removed the new article file".to_string())
+            }
+        };
+        steps.push(
+            restored
+                .unwrap_or_else(|e| format!("could not restore {}: {}", self.path.display(), e)),
+        );
+        if let Some((old, content)) = &self.moved_from {
+            let restored = old
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| write_atomic(old, content));
+            steps.push(match restored {
+                Ok(()) => format!("restored {}", old.display()),
+                Err(e) => format!("could not restore {}: {}", old.display(), e),
+            });
+        }
+        if let Some(version) = &self.version {
+            let name = version.file_name().unwrap_or_default().to_string_lossy();
+            steps.push(match fs::remove_file(version) {
+                Ok(()) => format!("removed version {}", name),
+                Err(e) => format!("could not remove version {}: {}", name, e),
+            });
+        }
+        if let Some(message) = &self.message
+            && message.exists()
+        {
+            let name = message.file_name().unwrap_or_default().to_string_lossy();
+            steps.push(match fs::remove_file(message) {
+                Ok(()) => format!("removed message {}", name),
+                Err(e) => format!("could not remove message {}: {}", name, e),
+            });
+        }
+        if let Some((old, new)) = &self.moved_versions {
+            steps.push(match fs::rename(new, old) {
+                Ok(()) => "moved the saved versions back".to_string(),
+                Err(e) => format!("could not move the saved versions back: {}", e),
+            });
+        }
+        steps
+    }
+}
+
+/// Removes `dir` and its parents up to, but not including, `root` for as long
+/// as they are empty.
+pub fn remove_empty_dirs(dir: &Path, root: &Path) {
+    let mut dir = Some(dir);
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::{ArticleStatus, Metadata};
+    use std::time::SystemTime;
+    use tempfile::tempdir;
+
+    fn article(file_path: &Path) -> Article {
+        Article {
+            slug: "post".to_string(),
+            metadata: Metadata {
+                title: "Post".to_string(),
+                author: "Scribe".to_string(),
+                date: Utc::now(),
+                display_date: None,
+                tags: vec![],
+                description: String::new(),
+                status: ArticleStatus::Published,
+                last_updated: None,
+                category: None,
+                poll: None,
+                lang: None,
+                translations: Default::default(),
+                private: false,
+                http_headers: Default::default(),
+                comments: Default::default(),
+                menu: None,
+                audio: None,
+                gallery: Vec::new(),
+                link: None,
+                syndication: Default::default(),
+                references: Vec::new(),
+                downloads: Vec::new(),
+                description_generated: false,
+                members_only: false,
+            },
+            version: 0,
+            updated_at: Utc::now(),
+            file_path: file_path.to_string_lossy().to_string(),
+            last_modified: SystemTime::now(),
+            includes: Vec::new(),
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn revisions_come_from_front_matter_and_saved_versions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        fs::write(&path, "post").unwrap();
+        let mut post = article(&path);
+        post.metadata.date = Utc::now() - chrono::Duration::days(30);
+        assert_eq!(revised_at(dir.path(), &post), None);
+
+        let week_ago = Utc::now() - chrono::Duration::days(7);
+        post.metadata.last_updated = Some(week_ago.to_rfc3339());
+        assert_eq!(revised_at(dir.path(), &post), Some(week_ago));
+
+        save_version(dir.path(), &post).unwrap();
+        let revised = revised_at(dir.path(), &post).unwrap();
+        assert!(revised > week_ago);
+        assert_eq!(saved_versions(dir.path(), "post").len(), 1);
+
+        // Edits before the publish date are not revisions.
+        post.metadata.date = Utc::now() + chrono::Duration::days(1);
+        assert_eq!(revised_at(dir.path(), &post), None);
+    }
+
+    #[test]
+    fn rollback_restores_the_file_and_drops_the_version() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        fs::write(&path, "old").unwrap();
+
+        let mut write = ArticleWrite::begin(&path).unwrap();
+        fs::write(&path, "new").unwrap();
+        write
+            .save_version(dir.path(), &article(&path), Some("Fix a typo"))
+            .unwrap();
+        let version = saved_versions(dir.path(), "post")[0];
+        assert_eq!(
+            version_message(dir.path(), "post", version).as_deref(),
+            Some("Fix a typo")
+        );
+        let steps = write.rollback();
+        assert_eq!(steps.len(), 3, "{steps:?}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        let versions = version_dir(dir.path(), "post");
+        assert_eq!(fs::read_dir(versions).unwrap().count(), 0);
+
+        let created = dir.path().join("created.md");
+        let write = ArticleWrite::begin(&created).unwrap();
+        fs::write(&created, "new").unwrap();
+        assert_eq!(write.rollback(), ["removed the new article file"]);
+        assert!(!created.exists());
+    }
+
+    #[test]
+    fn rollback_puts_a_moved_file_back() {
+        let dir = tempdir().unwrap();
+        let old = dir.path().join("guides/deep/post.md");
+        let new = dir.path().join("news/post.md");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::create_dir_all(new.parent().unwrap()).unwrap();
+        fs::write(&old, "post").unwrap();
+
+        let mut write = ArticleWrite::begin(&new).unwrap();
+        fs::write(&new, "post").unwrap();
+        write.remove_moved(&old).unwrap();
+        remove_empty_dirs(old.parent().unwrap(), dir.path());
+        assert!(!dir.path().join("guides").exists());
+        assert!(dir.path().join("news").exists());
+
+        write.rollback();
+        assert!(!new.exists());
+        assert_eq!(fs::read_to_string(&old).unwrap(), "post");
+    }
 }
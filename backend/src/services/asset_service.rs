@@ -0,0 +1,153 @@
+use crate::models::asset::{AssetMismatch, AssetVerification, StoredAsset};
+use crate::services::atomic_file::{is_temp_file, write_atomic};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory under the data directory holding uploaded assets.
+pub const ASSET_DIR: &str = "assets";
+
+/// File extensions of the accepted upload types, by content type.
+const ASSET_TYPES: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/avif", "avif"),
+    ("image/svg+xml", "svg"),
+    ("audio/mpeg", "mp3"),
+    ("audio/mp4", "m4a"),
+    ("video/mp4", "mp4"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+];
+
+pub fn extension_for(content_type: &str) -> Option<&'static str> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    ASSET_TYPES
+        .iter()
+        .find(|(ty, _)| ty.eq_ignore_ascii_case(essence))
+        .map(|(_, ext)| *ext)
+}
+
+pub fn content_type_for(extension: &str) -> Option<&'static str> {
+    ASSET_TYPES
+        .iter()
+        .find(|(_, ext)| *ext == extension)
+        .map(|(ty, _)| *ty)
+}
+
+/// Whether assets with `extension` are worth compressing for transfer.
+/// Images, audio, video and archives are compressed already, and served
+/// as they are so ranges keep working for seeking.
+pub fn is_compressible(extension: &str) -> bool {
+    extension == "svg"
+}
+
+/// Splits an asset name into its hash and extension. `None` for anything
+/// else, so request paths cannot reach other files.
+fn parse_name(name: &str) -> Option<(&str, &str)> {
+    let (hash, extension) = name.split_once('.')?;
+    let valid_hash = hash.len() == 64
+        && hash
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    (valid_hash && content_type_for(extension).is_some()).then_some((hash, extension))
+}
+
+/// Where the asset called `name` is stored, if `name` is an asset name.
+pub fn asset_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    parse_name(name).map(|_| dir.join(name))
+}
+
+/// Stores `bytes` as `{sha256}.{extension}`. Content that is already stored
+/// is not written again and is reported as a duplicate.
+pub fn store_asset(dir: &Path, bytes: &[u8], extension: &'static str) -> io::Result<StoredAsset> {
+    let hash = hex::encode(Sha256::digest(bytes));
+    let name = format!("{}.{}", hash, extension);
+    let path = dir.join(&name);
+    let duplicate = path.exists();
+    if !duplicate {
+        fs::create_dir_all(dir)?;
+        write_atomic(&path, bytes)?;
+    }
+    Ok(StoredAsset {
+        url: format!("/api/assets/{}", name),
+        name,
+        hash,
+        size: bytes.len() as u64,
+        content_type: content_type_for(extension).unwrap_or("application/octet-stream"),
+        duplicate,
+        image: None,
+    })
+}
+
+/// Re-hashes every stored asset and reports those whose content no longer
+/// matches their name, e.g. after bit rot or tampering. A missing directory
+/// has nothing to check.
+pub fn verify_assets(dir: &Path) -> io::Result<AssetVerification> {
+    let mut report = AssetVerification::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(e),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !is_temp_file(&entry.path()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| parse_name(name).is_some())
+        .collect();
+    names.sort();
+    for name in names {
+        let actual_hash = hex::encode(Sha256::digest(fs::read(dir.join(&name))?));
+        report.checked += 1;
+        let (expected_hash, _) = parse_name(&name).unwrap_or_default();
+        if actual_hash != expected_hash {
+            report.mismatches.push(AssetMismatch {
+                expected_hash: expected_hash.to_string(),
+                name,
+                actual_hash,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deduplicates_uploads_and_detects_changed_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let first = store_asset(dir.path(), b"pixels", "png").unwrap();
+        assert!(!first.duplicate);
+        let again = store_asset(dir.path(), b"pixels", "png").unwrap();
+        assert!(again.duplicate);
+        assert_eq!(again.name, first.name);
+        let other = store_asset(dir.path(), b"sound", "mp3").unwrap();
+
+        let report = verify_assets(dir.path()).unwrap();
+        assert_eq!(report.checked, 2);
+        assert!(report.mismatches.is_empty());
+
+        fs::write(dir.path().join(&other.name), b"tampered").unwrap();
+        let report = verify_assets(dir.path()).unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].name, other.name);
+        assert_eq!(report.mismatches[0].expected_hash, other.hash);
+    }
+
+    #[test]
+    fn only_asset_names_resolve_to_paths() {
+        let dir = Path::new("assets");
+        let name = format!("{}.png", "a".repeat(64));
+        assert_eq!(asset_path(dir, &name), Some(dir.join(&name)));
+        assert!(asset_path(dir, "../config.toml").is_none());
+        assert!(asset_path(dir, &format!("{}.exe", "a".repeat(64))).is_none());
+        assert!(asset_path(dir, &format!("{}.png", "A".repeat(64))).is_none());
+        assert_eq!(extension_for("image/JPEG; charset=binary"), Some("jpg"));
+    }
+}
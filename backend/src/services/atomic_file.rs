@@ -0,0 +1,89 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Suffix of the temporary files [`write_atomic`] writes before renaming.
+const TEMP_SUFFIX: &str = ".tmp";
+
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Whether `path` is a temporary file left by [`write_atomic`], which file
+/// watchers should not react to.
+pub fn is_temp_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(TEMP_SUFFIX))
+}
+
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let unique = NEXT_TEMP.fetch_add(1, Ordering::Relaxed);
+    Ok(path.with_file_name(format!(
+        ".{}.{}-{}{}",
+        name,
+        std::process::id(),
+        unique,
+        TEMP_SUFFIX
+    )))
+}
+
+/// Replaces `path` with `contents` so that readers, and a crash, see either
+/// the old file or the complete new one: the data goes to a temporary file
+/// in the same directory, is synced to disk and renamed over `path`.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    let temp = temp_path(path)?;
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    // Persist the rename itself. Directories cannot be opened for syncing
+    // on every platform, so this is best effort.
+    if let Some(dir) = path.parent()
+        && let Ok(dir) = File::open(dir)
+    {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn replaces_files_without_leaving_temp_files() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("post.md");
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let missing = dir.path().join("missing/post.md");
+        assert!(write_atomic(&missing, "x").is_err());
+    }
+
+    #[test]
+    fn recognizes_temp_files() {
+        let path = temp_path(Path::new("/content/post.md")).unwrap();
+        assert!(is_temp_file(&path));
+        assert_eq!(path.extension().unwrap(), "tmp");
+        assert!(!is_temp_file(Path::new("/content/post.md")));
+        assert!(!is_temp_file(Path::new("/content/.hidden.md")));
+    }
+}
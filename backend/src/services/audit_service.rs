@@ -0,0 +1,111 @@
+use crate::db::DbPool;
+use crate::models::audit::AuditEntry;
+use chrono::{DateTime, Utc};
+
+/// A search engine was asked to recrawl the sitemap.
+pub const SITEMAP_PING: &str = "sitemap_ping";
+/// A WebSub hub was told that a feed changed.
+pub const WEBSUB_PING: &str = "websub_ping";
+/// An article was posted to a social account.
+pub const CROSSPOST: &str = "crosspost";
+/// An older article was shared again after the admin approved it.
+pub const CROSSPOST_REMINDER: &str = "crosspost_reminder";
+/// An article file was removed; the target is its slug and the detail its
+/// title.
+pub const ARTICLE_DELETED: &str = "article_deleted";
+
+const AUDIT_COLUMNS: &str = "id, action, target, succeeded, detail, created_at";
+
+pub async fn record_audit(
+    pool: &DbPool,
+    action: &str,
+    target: &str,
+    succeeded: bool,
+    detail: Option<&str>,
+) -> Result<AuditEntry, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "INSERT INTO audit_log (action, target, succeeded, detail) \
+         VALUES (?, ?, ?, ?) RETURNING {}",
+        AUDIT_COLUMNS
+    ))
+    .bind(action)
+    .bind(target)
+    .bind(succeeded)
+    .bind(detail)
+    .fetch_one(pool)
+    .await
+}
+
+/// The latest `limit` entries, newest first, optionally of one `action`.
+pub async fn recent_audit(
+    pool: &DbPool,
+    action: Option<&str>,
+    limit: usize,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM audit_log WHERE ?1 IS NULL OR action = ?1 ORDER BY id DESC LIMIT ?2",
+        AUDIT_COLUMNS
+    ))
+    .bind(action)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+}
+
+/// Entries of `action` recorded from `from` up to `to`, oldest first.
+pub async fn audit_between(
+    pool: &DbPool,
+    action: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    // `created_at` is SQLite's `CURRENT_TIMESTAMP`, which sorts as text.
+    let stamp = |time: DateTime<Utc>| time.format("%Y-%m-%d %H:%M:%S").to_string();
+    sqlx::query_as(&format!(
+        "SELECT {} FROM audit_log WHERE action = ? AND created_at >= ? AND created_at < ? \
+         ORDER BY id",
+        AUDIT_COLUMNS
+    ))
+    .bind(action)
+    .bind(stamp(from))
+    .bind(stamp(to))
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn lists_the_latest_entries_first() {
+        let pool = memory_db().await;
+        record_audit(&pool, SITEMAP_PING, "https://a.example", true, None)
+            .await
+            .unwrap();
+        record_audit(&pool, WEBSUB_PING, "https://b.example", false, Some("503"))
+            .await
+            .unwrap();
+        record_audit(&pool, SITEMAP_PING, "https://c.example", true, None)
+            .await
+            .unwrap();
+
+        let all = recent_audit(&pool, None, 10).await.unwrap();
+        let targets: Vec<_> = all.iter().map(|e| e.target.as_str()).collect();
+        assert_eq!(
+            targets,
+            [
+                "https://c.example",
+                "https://b.example",
+                "https://a.example"
+            ]
+        );
+        assert!(!all[1].succeeded);
+        assert_eq!(all[1].detail.as_deref(), Some("503"));
+
+        let pings = recent_audit(&pool, Some(SITEMAP_PING), 1).await.unwrap();
+        assert_eq!(pings.len(), 1);
+        assert_eq!(pings[0].target, "https://c.example");
+    }
+}
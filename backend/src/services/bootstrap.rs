@@ -0,0 +1,94 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the sample article written into a new article directory.
+pub const SAMPLE_ARTICLE_FILE: &str = "hello-scribe.md";
+
+/// The `config.toml` `--init` writes when there is none.
+pub const STARTER_CONFIG: &str = r#"log_level = "scribe=info,tower_http=info"
+hostname = "http://localhost:3000"
+latest_articles_count = 10
+comments = false
+github_redirect_url = "http://localhost:3000/api/auth/github/callback"
+enable_full_text_search = true
+# Create the article and notes directories on startup when they are missing.
+bootstrap_content = true
+"#;
+
+const SAMPLE_ARTICLE: &str = r#"---
+title: "Hello, Scribe"
+author: "Scribe"
+date: 2025-01-01T00:00:00Z
+description: "A sample article to show the front matter Scribe reads."
+tags: ["scribe"]
+status: published
+---
+
+This article was created when the site was set up. Edit it or delete
+it: the server picks up changes to the `article` directory while it runs.
+"#;
+
+/// Creates `article_dir` and `notes_dir` when they are missing, with a
+/// sample article in a new article directory. Existing directories are
+/// left alone. Returns what was created.
+pub fn scaffold_content(article_dir: &Path, notes_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    if !article_dir.exists() {
+        fs::create_dir_all(article_dir)?;
+        created.push(article_dir.to_path_buf());
+        let sample = article_dir.join(SAMPLE_ARTICLE_FILE);
+        fs::write(&sample, SAMPLE_ARTICLE)?;
+        created.push(sample);
+    }
+    if !notes_dir.exists() {
+        fs::create_dir_all(notes_dir)?;
+        created.push(notes_dir.to_path_buf());
+    }
+    Ok(created)
+}
+
+/// Sets up a new site in `root`: a starter `config.toml` unless one exists,
+/// and the content directories. Returns what was created.
+pub fn init(root: &Path, article_dir: &str, notes_dir: &str) -> io::Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    let config = root.join("config.toml");
+    if !config.exists() {
+        fs::write(&config, STARTER_CONFIG)?;
+        created.push(config);
+    }
+    created.extend(scaffold_content(
+        &root.join(article_dir),
+        &root.join(notes_dir),
+    )?);
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn init_writes_a_valid_config_and_keeps_existing_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let created = init(dir.path(), "article", "notes").unwrap();
+        assert_eq!(created.len(), 4);
+        let config: Config =
+            toml::from_str(&fs::read_to_string(dir.path().join("config.toml")).unwrap()).unwrap();
+        config.validate().unwrap();
+        assert!(config.bootstrap_content);
+        let sample = dir.path().join("article").join(SAMPLE_ARTICLE_FILE);
+        assert!(sample.is_file());
+        assert!(dir.path().join("notes").is_dir());
+
+        fs::write(dir.path().join("config.toml"), "comments = false\n").unwrap();
+        fs::remove_file(&sample).unwrap();
+        assert!(init(dir.path(), "article", "notes").unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.toml")).unwrap(),
+            "comments = false\n"
+        );
+        assert!(!sample.exists());
+    }
+}
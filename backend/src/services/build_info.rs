@@ -0,0 +1,75 @@
+use crate::models::build_info::{BuildInfo, ContentCounts, EnabledFeatures};
+use crate::server::app::AppState;
+use crate::services::service::ArticleStore;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+use tracing::info;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Commit the binary was built from, recorded by `build.rs`.
+pub const GIT_COMMIT: &str = env!("SCRIBE_GIT_COMMIT");
+const BUILD_TIMESTAMP: &str = env!("SCRIBE_BUILD_TIMESTAMP");
+
+pub fn built_at() -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(BUILD_TIMESTAMP.parse().ok()?, 0)
+}
+
+async fn published(store: &RwLock<ArticleStore>) -> usize {
+    store
+        .read()
+        .await
+        .query(|a| a.metadata.is_published(), 0, usize::MAX)
+        .count()
+}
+
+pub async fn build_info(state: &AppState) -> BuildInfo {
+    BuildInfo {
+        version: VERSION.to_string(),
+        git_commit: GIT_COMMIT.to_string(),
+        built_at: built_at(),
+        features: EnabledFeatures {
+            search: state.search_service.is_some(),
+            comments: state.config.comments,
+            read_only: state.config.read_only,
+            private_notes: state.note_cipher.is_some(),
+        },
+        content: ContentCounts {
+            articles: published(&state.store).await,
+            notes: published(&state.note_store).await,
+            pages: published(&state.page_store).await,
+        },
+    }
+}
+
+/// Logs what is starting: version, commit, enabled features and how much
+/// content was loaded.
+pub async fn log_startup_banner(state: &AppState) {
+    let info = build_info(state).await;
+    let commit = &info.git_commit[..info.git_commit.len().min(12)];
+    let built = info
+        .built_at
+        .map_or_else(|| "at an unknown time".to_string(), |at| at.to_rfc3339());
+    info!(
+        "scribe {} (commit {}, built {})",
+        info.version, commit, built
+    );
+    let features = &info.features;
+    let enabled: Vec<&str> = [
+        ("search", features.search),
+        ("comments", features.comments),
+        ("read-only", features.read_only),
+        ("private notes", features.private_notes),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect();
+    if enabled.is_empty() {
+        info!("Features: none");
+    } else {
+        info!("Features: {}", enabled.join(", "));
+    }
+    info!(
+        "Content: {} articles, {} notes, {} pages",
+        info.content.articles, info.content.notes, info.content.pages
+    );
+}
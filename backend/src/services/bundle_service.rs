@@ -0,0 +1,139 @@
+use crate::models::article::Metadata;
+use crate::services::glossary_service::GlossaryLinker;
+use crate::services::render_service::{local_images, render_bundle_document};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::result::ZipResult;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[derive(Serialize)]
+struct BundleManifest<'a> {
+    slug: &'a str,
+    url: &'a str,
+    metadata: &'a Metadata,
+    images: Vec<&'a str>,
+}
+
+/// Zips a self-contained copy of an article under a `{slug}/` folder:
+/// `index.html` from the print renderer, `metadata.json`, and every local
+/// image found next to the article at the same relative path the page uses.
+/// Images that are referenced but missing keep pointing at the live site.
+pub fn build_bundle(
+    slug: &str,
+    metadata: &Metadata,
+    content: &str,
+    page_url: &str,
+    article_dir: &Path,
+    glossary: Option<&GlossaryLinker>,
+) -> ZipResult<Vec<u8>> {
+    let mut images = Vec::new();
+    for source in local_images(content) {
+        match fs::read(article_dir.join(&source)) {
+            Ok(bytes) => images.push((source, bytes)),
+            Err(e) => tracing::debug!("Skipping image {} in bundle for {}: {}", source, slug, e),
+        }
+    }
+    let bundled: HashSet<String> = images.iter().map(|(source, _)| source.clone()).collect();
+    let html = render_bundle_document(metadata, content, page_url, &bundled, glossary);
+    let manifest = BundleManifest {
+        slug,
+        url: page_url,
+        metadata,
+        images: images.iter().map(|(source, _)| source.as_str()).collect(),
+    };
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(format!("{}/index.html", slug), options)?;
+    zip.write_all(html.as_bytes())?;
+    zip.start_file(format!("{}/metadata.json", slug), options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::from)?)?;
+    for (source, bytes) in &images {
+        let name = source.trim_start_matches("./");
+        // Images are already compressed; deflating them again only costs time.
+        zip.start_file(
+            format!("{}/{}", slug, name),
+            options.compression_method(CompressionMethod::Stored),
+        )?;
+        zip.write_all(bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::ArticleStatus;
+    use chrono::Utc;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn bundles_page_metadata_and_local_images() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("img")).unwrap();
+        fs::write(dir.path().join("img/cat.png"), b"not really a png").unwrap();
+        let metadata = Metadata {
+            title: "Cats".to_string(),
+            author: "a".to_string(),
+            date: Utc::now(),
+            display_date: None,
+            tags: vec![],
+            description: String::new(),
+            status: ArticleStatus::Published,
+            last_updated: None,
+            category: None,
+            poll: None,
+            lang: None,
+            translations: Default::default(),
+            private: false,
+            http_headers: Default::default(),
+            comments: Default::default(),
+            menu: None,
+            audio: None,
+            gallery: Vec::new(),
+            link: None,
+            syndication: Default::default(),
+            references: Vec::new(),
+            downloads: Vec::new(),
+            description_generated: false,
+            members_only: false,
+        };
+
+        let bytes = build_bundle(
+            "cats",
+            &metadata,
+            "![cat](img/cat.png) ![dog](img/dog.png)",
+            "https://example.com/articles/cats",
+            dir.path(),
+            None,
+        )
+        .unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["cats/img/cat.png", "cats/index.html", "cats/metadata.json"]
+        );
+
+        let mut html = String::new();
+        archive
+            .by_name("cats/index.html")
+            .unwrap()
+            .read_to_string(&mut html)
+            .unwrap();
+        assert!(html.contains("src=\"img/cat.png\""));
+        assert!(html.contains("src=\"https://example.com/articles/img/dog.png\""));
+
+        let manifest: serde_json::Value =
+            serde_json::from_reader(archive.by_name("cats/metadata.json").unwrap()).unwrap();
+        assert_eq!(manifest["metadata"]["title"], "Cats");
+        assert_eq!(manifest["images"], serde_json::json!(["img/cat.png"]));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::db::DbPool;
+use crate::models::changes::{ChangedSlugs, ContentChanges};
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::search::IndexCollection;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Point in the content history a client last saw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeMarker {
+    /// A `marker` returned earlier, e.g. `r12`.
+    Revision(i64),
+    Time(DateTime<Utc>),
+}
+
+impl ChangeMarker {
+    /// Reads a marker, with or without the quotes and weak prefix of an
+    /// entity tag, or an RFC 3339 timestamp.
+    pub fn parse(value: &str) -> Option<Self> {
+        let tag = value.trim().trim_start_matches("W/").trim_matches('"');
+        if let Some(revision) = tag.strip_prefix('r') {
+            return revision.parse().ok().map(ChangeMarker::Revision);
+        }
+        DateTime::parse_from_rfc3339(tag)
+            .ok()
+            .map(|time| ChangeMarker::Time(time.with_timezone(&Utc)))
+    }
+}
+
+pub fn marker_of(revision: i64) -> String {
+    format!("r{}", revision)
+}
+
+/// Identifies what a visitor is served for an entry; it changes with every
+/// edit of the file and every saved version.
+fn fingerprint(version: u64, updated_at: DateTime<Utc>) -> String {
+    format!("{}:{}", version, updated_at.timestamp_millis())
+}
+
+/// Public articles and notes by collection and slug, with their
+/// fingerprints.
+async fn public_entries(state: &AppState) -> HashMap<(&'static str, String), String> {
+    let mut entries = HashMap::new();
+    for collection in [IndexCollection::Articles, IndexCollection::Notes] {
+        let store = state.store_of(collection).read().await;
+        for entry in store.query(
+            |e| Visibility::public().allows_direct(&store, e),
+            0,
+            usize::MAX,
+        ) {
+            let slug = match collection {
+                IndexCollection::Notes => entry.slug_with_category(),
+                _ => entry.slug.clone(),
+            };
+            entries.insert(
+                (collection.as_str(), slug),
+                fingerprint(entry.version, entry.updated_at),
+            );
+        }
+    }
+    entries
+}
+
+/// Brings the ledger in line with the stores and returns the current
+/// revision. Entries that appeared, changed or went away since the last
+/// call are stamped with a new revision and the current time.
+pub async fn reconcile(state: &AppState) -> Result<i64, sqlx::Error> {
+    let current = public_entries(state).await;
+    let mut tx = state.db.begin().await?;
+    let ledger: Vec<(String, String, Option<String>)> =
+        sqlx::query_as("SELECT collection, slug, fingerprint FROM content_ledger")
+            .fetch_all(&mut *tx)
+            .await?;
+    let revision: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(revision), 0) FROM content_ledger")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let known: HashMap<(&str, &str), Option<&str>> = ledger
+        .iter()
+        .map(|(collection, slug, fp)| ((collection.as_str(), slug.as_str()), fp.as_deref()))
+        .collect();
+    let mut updates: Vec<(&str, &str, Option<&str>)> = current
+        .iter()
+        .filter(|((collection, slug), fp)| {
+            known.get(&(*collection, slug.as_str())) != Some(&Some(fp.as_str()))
+        })
+        .map(|((collection, slug), fp)| (*collection, slug.as_str(), Some(fp.as_str())))
+        .collect();
+    updates.extend(
+        known
+            .iter()
+            .filter(|((collection, slug), fp)| {
+                fp.is_some() && !current.contains_key(&(*collection, slug.to_string()))
+            })
+            .map(|((collection, slug), _)| (*collection, *slug, None)),
+    );
+    if updates.is_empty() {
+        return Ok(revision);
+    }
+
+    let revision = revision + 1;
+    let now = Utc::now().timestamp_millis();
+    for (collection, slug, fp) in updates {
+        sqlx::query(
+            "INSERT INTO content_ledger (collection, slug, fingerprint, revision, changed_at) \
+             VALUES (?, ?, ?, ?, ?) ON CONFLICT (collection, slug) DO UPDATE SET \
+             fingerprint = excluded.fingerprint, revision = excluded.revision, \
+             changed_at = excluded.changed_at",
+        )
+        .bind(collection)
+        .bind(slug)
+        .bind(fp)
+        .bind(revision)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(revision)
+}
+
+/// Entries stamped after `since`, or every public entry without it. A
+/// revision newer than `revision`, as from before the ledger was reset,
+/// counts as no marker.
+pub async fn changes_since(
+    pool: &DbPool,
+    since: Option<ChangeMarker>,
+    revision: i64,
+) -> Result<ContentChanges, sqlx::Error> {
+    let since = since.filter(|m| !matches!(m, ChangeMarker::Revision(seen) if *seen > revision));
+    let (column, bound) = match since {
+        Some(ChangeMarker::Revision(seen)) => ("revision", seen),
+        Some(ChangeMarker::Time(time)) => ("changed_at", time.timestamp_millis()),
+        None => ("revision", 0),
+    };
+    let rows: Vec<(String, String, Option<String>)> = sqlx::query_as(&format!(
+        "SELECT collection, slug, fingerprint FROM content_ledger WHERE {} > ? \
+         ORDER BY collection, slug",
+        column
+    ))
+    .bind(bound)
+    .fetch_all(pool)
+    .await?;
+
+    let mut changes = ContentChanges {
+        marker: marker_of(revision),
+        changed: ChangedSlugs::default(),
+        removed: ChangedSlugs::default(),
+    };
+    for (collection, slug, fp) in rows {
+        // A client without a marker holds nothing to remove.
+        if fp.is_none() && since.is_none() {
+            continue;
+        }
+        let slugs = if fp.is_some() {
+            &mut changes.changed
+        } else {
+            &mut changes.removed
+        };
+        match collection.as_str() {
+            "notes" => slugs.notes.push(slug),
+            _ => slugs.articles.push(slug),
+        }
+    }
+    Ok(changes)
+}
@@ -0,0 +1,103 @@
+use crate::models::article::Article;
+use crate::models::version::{Changelog, ChangelogEntry};
+use crate::services::article_service::{revised_at, saved_versions, version_dir, version_message};
+use crate::services::front_matter::front_matter_yaml;
+use chrono::DateTime;
+use std::fs;
+use std::path::Path;
+
+/// The text of an article file after its front matter, so that metadata
+/// such as `last_updated` does not count as a change.
+fn body(text: &str) -> &str {
+    let Some(yaml) = front_matter_yaml(text) else {
+        return text;
+    };
+    // The block is the opening delimiter line, the YAML and the closing one.
+    let (_, rest) = text.split_once('\n').unwrap_or_default();
+    let closing = &rest[yaml.len()..];
+    closing.split_once('\n').map_or("", |(_, body)| body)
+}
+
+/// Counts the lines added and removed between `old` and `new`, from the
+/// longest common subsequence of their lines.
+pub fn line_changes(old: &str, new: &str) -> (usize, usize) {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let mut previous = vec![0usize; new.len() + 1];
+    let mut current = vec![0usize; new.len() + 1];
+    for line in old {
+        for (j, other) in new.iter().enumerate() {
+            current[j + 1] = if line == other {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    let common = previous[new.len()];
+    (new.len() - common, old.len() - common)
+}
+
+/// Builds the changelog of `article` from its saved versions, newest first.
+/// Each version holds the file as an edit left it, so the lines an edit
+/// changed are counted against the version before it. The oldest version has
+/// nothing to compare against and carries no counts.
+pub fn changelog(data_dir: &Path, article: &Article) -> Changelog {
+    let key = article.slug_with_category();
+    let dir = version_dir(data_dir, &key);
+    let mut previous: Option<String> = None;
+    let mut entries = Vec::new();
+    for version in saved_versions(data_dir, &key) {
+        let Ok(text) = fs::read_to_string(dir.join(format!("{}.md", version))) else {
+            continue;
+        };
+        let text = body(&text).to_string();
+        let changes = previous.as_deref().map(|old| line_changes(old, &text));
+        entries.push(ChangelogEntry {
+            version,
+            timestamp: DateTime::from_timestamp_millis(version as i64).unwrap_or_default(),
+            lines_added: changes.map(|(added, _)| added),
+            lines_removed: changes.map(|(_, removed)| removed),
+            message: version_message(data_dir, &key, version),
+        });
+        previous = Some(text);
+    }
+    entries.reverse();
+    Changelog {
+        slug: key,
+        published: article.metadata.date,
+        revised_at: revised_at(data_dir, article),
+        entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_changed_lines_and_ignores_front_matter() {
+        assert_eq!(line_changes("a\nb\nc\n", "a\nb\nc\n"), (0, 0));
+        assert_eq!(line_changes("a\nb\nc\n", "a\nx\nc\nd\n"), (2, 1));
+        assert_eq!(line_changes("", "a\nb\n"), (2, 0));
+        assert_eq!(line_changes("a\nb\na\n", "b\na\nb\n"), (1, 1));
+
+        let old = "---\ntitle: x\n---\n\nBody\n";
+        let new = "---\ntitle: x\nlast_updated: today\n---\n\nBody\nMore\n";
+        assert_eq!(body(old), "\nBody\n");
+        assert_eq!(line_changes(body(old), body(new)), (1, 0));
+        assert_eq!(body("---\n---\nBody"), "Body");
+        assert_eq!(body("No front matter"), "No front matter");
+    }
+}
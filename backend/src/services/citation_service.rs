@@ -0,0 +1,307 @@
+use crate::models::article::Metadata;
+use crate::models::citation::Reference;
+use crate::services::render_service::escape_html;
+use pulldown_cmark::{CowStr, Event};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// `[@key]`, or several keys as `[@a; @b]`.
+static CITATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[@[\w:./-]+(?:\s*;\s*@[\w:./-]+)*\]").expect("valid citation pattern")
+});
+
+/// The references of the article stored at `article_path`: those in its
+/// front matter, then the entries of the BibTeX file next to it with the
+/// same name, e.g. `post.bib` for `post.md`, whose keys are not taken yet.
+/// A sidecar that fails to parse is skipped.
+pub fn article_references(metadata: &Metadata, article_path: &Path) -> Vec<Reference> {
+    let mut references = metadata.references.clone();
+    let path = article_path.with_extension("bib");
+    let sidecar = match std::fs::read_to_string(&path) {
+        Ok(text) => parse_bibtex(&text)
+            .inspect_err(|e| tracing::warn!("Skipping {}: {}", path.display(), e))
+            .unwrap_or_default(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            tracing::warn!("Failed to read {}: {}", path.display(), e);
+            Vec::new()
+        }
+    };
+    for reference in sidecar {
+        if !references.iter().any(|r| r.id == reference.id) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// The entries of a BibTeX file. `title`, `author`, `year`, `url` and
+/// `doi` are read, with `journal`, `booktitle` or `publisher` as the venue;
+/// other fields, `@string` macros and `#` concatenation are ignored.
+pub fn parse_bibtex(text: &str) -> Result<Vec<Reference>, String> {
+    let mut references = Vec::new();
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let open = rest
+            .find(['{', '('])
+            .ok_or_else(|| "entry without a body".to_string())?;
+        let kind = rest[..open].trim().to_lowercase();
+        let (body, after) = delimited(&rest[open..])?;
+        rest = after;
+        if matches!(kind.as_str(), "comment" | "string" | "preamble") {
+            continue;
+        }
+        let (key, fields) = body
+            .split_once(',')
+            .ok_or_else(|| format!("entry '{}' has no fields", body.trim()))?;
+        let key = key.trim();
+        let fields = parse_fields(fields)?;
+        let field = |name: &str| fields.get(name).filter(|v| !v.is_empty()).cloned();
+        references.push(Reference {
+            id: key.to_string(),
+            title: field("title").ok_or_else(|| format!("entry '{}' has no title", key))?,
+            authors: field("author")
+                .map(|authors| {
+                    authors
+                        .split(" and ")
+                        .map(|author| author.trim().to_string())
+                        .filter(|author| !author.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            year: field("year").and_then(|year| year.parse().ok()),
+            venue: field("journal")
+                .or_else(|| field("booktitle"))
+                .or_else(|| field("publisher")),
+            url: field("url"),
+            doi: field("doi"),
+        });
+    }
+    Ok(references)
+}
+
+/// Splits `text`, which starts with `{` or `(`, into what is inside the
+/// matching delimiter and what follows it. Braces nest inside either.
+fn delimited(text: &str) -> Result<(&str, &str), String> {
+    let close = if text.starts_with('(') { ')' } else { '}' };
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            c if c == close && depth == 0 => return Ok((&text[1..i], &text[i + 1..])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    Err("unbalanced braces".to_string())
+}
+
+/// `name = value` pairs, with lowercased names and values stripped of
+/// their braces and quotes.
+fn parse_fields(mut text: &str) -> Result<HashMap<String, String>, String> {
+    let mut fields = HashMap::new();
+    loop {
+        text = text.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if text.is_empty() {
+            return Ok(fields);
+        }
+        let (name, rest) = text
+            .split_once('=')
+            .ok_or_else(|| format!("field without a value near '{}'", text.trim()))?;
+        let rest = rest.trim_start();
+        let (value, rest) = if rest.starts_with('{') {
+            delimited(rest)?
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or_else(|| "unterminated quoted value".to_string())?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        let value = value.replace(['{', '}'], "");
+        fields.insert(
+            name.trim().to_lowercase(),
+            value.split_whitespace().collect::<Vec<_>>().join(" "),
+        );
+        text = rest;
+    }
+}
+
+/// Turns `[@key]` citations in rendered text into numbered links to the
+/// bibliography. References are numbered in order of first citation.
+pub struct Citations<'r> {
+    references: &'r [Reference],
+}
+
+impl<'r> Citations<'r> {
+    pub fn new(references: &'r [Reference]) -> Self {
+        Self { references }
+    }
+
+    /// `text` with its citations replaced, or `None` when it cites nothing
+    /// known. A citation with an unknown key is left as written. Cited
+    /// references are added to `cited`.
+    pub fn cite<'a>(&self, text: &str, cited: &mut Vec<usize>) -> Option<Vec<Event<'a>>> {
+        if self.references.is_empty() {
+            return None;
+        }
+        let mut events = Vec::new();
+        let mut rest = 0;
+        for found in CITATION.find_iter(text) {
+            let keys = found.as_str()[1..found.len() - 1]
+                .split(';')
+                .map(|key| key.trim().trim_start_matches('@'));
+            let Some(entries) = keys
+                .map(|key| self.references.iter().position(|r| r.id == key))
+                .collect::<Option<Vec<usize>>>()
+            else {
+                continue;
+            };
+            let links: Vec<String> = entries
+                .into_iter()
+                .map(|entry| {
+                    let n = match cited.iter().position(|&c| c == entry) {
+                        Some(i) => i + 1,
+                        None => {
+                            cited.push(entry);
+                            cited.len()
+                        }
+                    };
+                    format!("<a href=\"#ref-{n}\">{n}</a>")
+                })
+                .collect();
+            if found.start() > rest {
+                events.push(Event::Text(CowStr::from(
+                    text[rest..found.start()].to_string(),
+                )));
+            }
+            events.push(Event::InlineHtml(CowStr::from(format!(
+                "<sup class=\"citation\">[{}]</sup>",
+                links.join(", ")
+            ))));
+            rest = found.end();
+        }
+        if events.is_empty() {
+            return None;
+        }
+        if rest < text.len() {
+            events.push(Event::Text(CowStr::from(text[rest..].to_string())));
+        }
+        Some(events)
+    }
+
+    /// The reference list: cited references in citation order, then the
+    /// others. Empty when there are no references.
+    pub fn bibliography(&self, cited: &[usize]) -> String {
+        if self.references.is_empty() {
+            return String::new();
+        }
+        let uncited = (0..self.references.len()).filter(|i| !cited.contains(i));
+        let mut output =
+            String::from("<section class=\"references\">\n<h2>References</h2>\n<ol>\n");
+        for (i, entry) in cited.iter().copied().chain(uncited).enumerate() {
+            output.push_str(&format!(
+                "<li id=\"ref-{}\">{}</li>\n",
+                i + 1,
+                format_reference(&self.references[entry])
+            ));
+        }
+        output.push_str("</ol>\n</section>\n");
+        output
+    }
+}
+
+/// `Authors. Title. Venue. Year.` followed by a link to the work.
+fn format_reference(reference: &Reference) -> String {
+    let mut parts = Vec::new();
+    if !reference.authors.is_empty() {
+        parts.push(escape_html(&reference.authors.join(", ")));
+    }
+    parts.push(escape_html(&reference.title));
+    if let Some(venue) = &reference.venue {
+        parts.push(format!("<em>{}</em>", escape_html(venue)));
+    }
+    if let Some(year) = reference.year {
+        parts.push(year.to_string());
+    }
+    let mut output = format!("{}.", parts.join(". "));
+    let url = reference.url.clone().or_else(|| {
+        reference
+            .doi
+            .as_ref()
+            .map(|doi| format!("https://doi.org/{}", doi))
+    });
+    if let Some(url) = url {
+        let url = escape_html(&url);
+        output.push_str(&format!(" <a href=\"{url}\">{url}</a>"));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bibtex_entries() {
+        let text = r#"
+@comment{ignored}
+@article{knuth84,
+  author = {Donald E. Knuth and {The} Editors},
+  title  = {Literate {Programming}},
+  journal = "The Computer Journal",
+  year = 1984,
+  doi = {10.1093/comjnl/27.2.97},
+}
+@book(sicp, title = {Structure and Interpretation of Computer Programs},
+      publisher = {MIT Press})
+"#;
+        let references = parse_bibtex(text).unwrap();
+        assert_eq!(
+            references,
+            vec![
+                Reference {
+                    id: "knuth84".to_string(),
+                    title: "Literate Programming".to_string(),
+                    authors: vec!["Donald E. Knuth".to_string(), "The Editors".to_string()],
+                    year: Some(1984),
+                    venue: Some("The Computer Journal".to_string()),
+                    url: None,
+                    doi: Some("10.1093/comjnl/27.2.97".to_string()),
+                },
+                Reference {
+                    id: "sicp".to_string(),
+                    title: "Structure and Interpretation of Computer Programs".to_string(),
+                    authors: Vec::new(),
+                    year: None,
+                    venue: Some("MIT Press".to_string()),
+                    url: None,
+                    doi: None,
+                },
+            ]
+        );
+        assert!(parse_bibtex("@misc{untitled, year = 2020}").is_err());
+        assert!(parse_bibtex("@misc{open, title = {x}").is_err());
+    }
+
+    #[test]
+    fn formats_references_with_a_link() {
+        let reference = parse_bibtex(
+            "@article{k, author = {A and B}, title = {T & U}, journal = {J}, \
+             year = 2001, doi = {10.1/x}}",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(
+            format_reference(&reference),
+            "A, B. T &amp; U. <em>J</em>. 2001. \
+             <a href=\"https://doi.org/10.1/x\">https://doi.org/10.1/x</a>"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Name the article lock is held under while a draft is edited live, so
+/// sessions using the lock API see the article as taken.
+pub const COLLAB_EDITOR: &str = "live-session";
+
+/// Messages kept for sessions that fall behind; a session that misses
+/// some is sent a fresh snapshot.
+const ROOM_BUFFER: usize = 256;
+
+/// What an editing session sends. Offsets count characters of the draft.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Replaces the characters from `from` up to `to` with `text`.
+    Patch {
+        from: usize,
+        to: usize,
+        text: String,
+    },
+    /// Keeps the article lock alive, like heartbeats of the lock API.
+    Heartbeat,
+    /// Asks for a snapshot, e.g. after missing a revision.
+    Sync,
+}
+
+/// What editing sessions are sent.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The whole draft, sent on joining and on `sync`.
+    Snapshot {
+        revision: u64,
+        content: String,
+        editors: Vec<String>,
+    },
+    /// A patch as applied, offsets clamped to the draft it was applied to.
+    /// Revisions go up by one with each patch, so a session that sees a
+    /// gap knows to `sync`.
+    Patch {
+        revision: u64,
+        editor: String,
+        from: usize,
+        to: usize,
+        text: String,
+    },
+    /// The sessions editing the draft, whenever one joins or leaves.
+    Presence { editors: Vec<String> },
+    /// A message of this session that was not understood or not applied.
+    Error { message: String },
+}
+
+struct Draft {
+    content: String,
+    revision: u64,
+    /// Open sessions by editor name; a name may be open in several tabs.
+    editors: BTreeMap<String, usize>,
+}
+
+impl Draft {
+    fn editors(&self) -> Vec<String> {
+        self.editors.keys().cloned().collect()
+    }
+
+    fn snapshot(&self) -> ServerMessage {
+        ServerMessage::Snapshot {
+            revision: self.revision,
+            content: self.content.clone(),
+            editors: self.editors(),
+        }
+    }
+}
+
+/// A draft being edited live. Patches are applied in the order they
+/// arrive, without transforming them against each other: the last writer
+/// wins, and everyone is sent every patch.
+pub struct DraftRoom {
+    draft: Mutex<Draft>,
+    events: broadcast::Sender<ServerMessage>,
+}
+
+impl DraftRoom {
+    pub fn snapshot(&self) -> ServerMessage {
+        self.draft.lock().unwrap().snapshot()
+    }
+
+    /// Applies `editor`'s patch and sends it to every session.
+    pub fn apply(&self, editor: &str, from: usize, to: usize, text: String) -> ServerMessage {
+        let mut draft = self.draft.lock().unwrap();
+        let chars = draft.content.chars().count();
+        let to = to.min(chars);
+        let from = from.min(to);
+        let byte = |offset: usize| {
+            draft
+                .content
+                .char_indices()
+                .nth(offset)
+                .map_or(draft.content.len(), |(i, _)| i)
+        };
+        let range = byte(from)..byte(to);
+        draft.content.replace_range(range, &text);
+        draft.revision += 1;
+        let patch = ServerMessage::Patch {
+            revision: draft.revision,
+            editor: editor.to_string(),
+            from,
+            to,
+            text,
+        };
+        // Nobody listening is not an error.
+        let _ = self.events.send(patch.clone());
+        patch
+    }
+}
+
+/// The drafts being edited live, by slug.
+#[derive(Default)]
+pub struct DraftRooms {
+    rooms: Mutex<HashMap<String, Arc<DraftRoom>>>,
+}
+
+impl DraftRooms {
+    /// Adds a session of `editor` to the draft of `slug`, opened with
+    /// `content` when nobody is editing it yet. Returns the room, the
+    /// session's subscription and the snapshot it starts from; the
+    /// subscription holds every change after the snapshot.
+    pub fn join(
+        &self,
+        slug: &str,
+        editor: &str,
+        content: impl FnOnce() -> String,
+    ) -> (
+        Arc<DraftRoom>,
+        broadcast::Receiver<ServerMessage>,
+        ServerMessage,
+    ) {
+        let room = Arc::clone(
+            self.rooms
+                .lock()
+                .unwrap()
+                .entry(slug.to_string())
+                .or_insert_with(|| {
+                    Arc::new(DraftRoom {
+                        draft: Mutex::new(Draft {
+                            content: content(),
+                            revision: 0,
+                            editors: BTreeMap::new(),
+                        }),
+                        events: broadcast::channel(ROOM_BUFFER).0,
+                    })
+                }),
+        );
+        let (events, snapshot) = {
+            let mut draft = room.draft.lock().unwrap();
+            *draft.editors.entry(editor.to_string()).or_default() += 1;
+            let events = room.events.subscribe();
+            let _ = room.events.send(ServerMessage::Presence {
+                editors: draft.editors(),
+            });
+            (events, draft.snapshot())
+        };
+        (room, events, snapshot)
+    }
+
+    /// Removes a session of `editor` from the draft of `slug`. Returns
+    /// whether it was the last one, which closes the room.
+    pub fn leave(&self, slug: &str, editor: &str) -> bool {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get(slug) else {
+            return false;
+        };
+        let mut draft = room.draft.lock().unwrap();
+        if let Some(sessions) = draft.editors.get_mut(editor) {
+            *sessions -= 1;
+            if *sessions == 0 {
+                draft.editors.remove(editor);
+            }
+        }
+        if draft.editors.is_empty() {
+            drop(draft);
+            rooms.remove(slug);
+            return true;
+        }
+        let _ = room.events.send(ServerMessage::Presence {
+            editors: draft.editors(),
+        });
+        false
+    }
+
+    /// Slugs of the drafts being edited live.
+    pub fn open(&self) -> Vec<String> {
+        let mut slugs: Vec<String> = self.rooms.lock().unwrap().keys().cloned().collect();
+        slugs.sort();
+        slugs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_reach_every_session_and_rooms_close_when_empty() {
+        let rooms = DraftRooms::default();
+        let (room, mut laptop, snapshot) = rooms.join("draft", "laptop", || "Héllo".to_string());
+        assert_eq!(
+            snapshot,
+            ServerMessage::Snapshot {
+                revision: 0,
+                content: "Héllo".to_string(),
+                editors: vec!["laptop".to_string()],
+            }
+        );
+        let (_, mut phone, snapshot) = rooms.join("draft", "phone", || unreachable!());
+        assert!(matches!(snapshot, ServerMessage::Snapshot { editors, .. } if editors.len() == 2));
+
+        room.apply("phone", 1, 2, "e".to_string());
+        // Offsets past the end are clamped.
+        room.apply("laptop", 99, 120, ", world".to_string());
+        let ServerMessage::Snapshot {
+            revision, content, ..
+        } = room.snapshot()
+        else {
+            unreachable!()
+        };
+        assert_eq!((revision, content.as_str()), (2, "Hello, world"));
+
+        let received: Vec<ServerMessage> = std::iter::from_fn(|| laptop.try_recv().ok()).collect();
+        assert_eq!(received.len(), 4);
+        assert!(matches!(
+            &received[3],
+            ServerMessage::Patch { from: 5, to: 5, .. }
+        ));
+        assert!(matches!(
+            phone.try_recv().unwrap(),
+            ServerMessage::Presence { .. }
+        ));
+
+        assert!(!rooms.leave("draft", "laptop"));
+        assert_eq!(rooms.open(), ["draft"]);
+        assert!(rooms.leave("draft", "phone"));
+        assert!(rooms.open().is_empty());
+    }
+}
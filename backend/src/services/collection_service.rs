@@ -0,0 +1,110 @@
+use crate::handlers::error::LoadError;
+use crate::models::collection::Collection;
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// The reading lists curated in `collections.yaml`, in file order. Reloaded
+/// by the watcher when the file changes; a missing file means no lists.
+pub struct Collections {
+    path: PathBuf,
+    lists: RwLock<Vec<Collection>>,
+}
+
+impl Collections {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, LoadError> {
+        let path = path.into();
+        let lists = read_collections(&path)?;
+        Ok(Self {
+            path,
+            lists: RwLock::new(lists),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the file again. The lists loaded before stay when it is
+    /// invalid.
+    pub fn reload(&self) -> Result<(), LoadError> {
+        let lists = read_collections(&self.path)?;
+        *self.lists.write().unwrap() = lists;
+        Ok(())
+    }
+
+    pub fn all(&self) -> Vec<Collection> {
+        self.lists.read().unwrap().clone()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Collection> {
+        self.lists
+            .read()
+            .unwrap()
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+    }
+}
+
+fn read_collections(path: &Path) -> Result<Vec<Collection>, LoadError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let lists: Vec<Collection> = serde_yaml::from_str(&text)?;
+    let mut names = HashSet::new();
+    for list in &lists {
+        if list.name.trim().is_empty() {
+            return Err(LoadError::InvalidCollections(
+                "collection name cannot be empty".to_string(),
+            ));
+        }
+        if !names.insert(list.name.as_str()) {
+            return Err(LoadError::InvalidCollections(format!(
+                "collection '{}' is defined twice",
+                list.name
+            )));
+        }
+    }
+    Ok(lists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn keeps_the_loaded_lists_when_the_file_turns_invalid() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("collections.yaml");
+        let collections = Collections::load(&path).unwrap();
+        assert!(collections.all().is_empty());
+
+        fs::write(
+            &path,
+            "- name: start-here\n  title: Start here\n  articles: [b, a]\n",
+        )
+        .unwrap();
+        collections.reload().unwrap();
+        assert_eq!(collections.get("start-here").unwrap().articles, ["b", "a"]);
+
+        fs::write(
+            &path,
+            "- name: start-here\n  title: One\n- name: start-here\n  title: Two\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            collections.reload(),
+            Err(LoadError::InvalidCollections(_))
+        ));
+        assert_eq!(collections.get("start-here").unwrap().title, "Start here");
+    }
+}
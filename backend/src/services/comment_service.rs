@@ -1,8 +1,14 @@
+use crate::db::DbPool;
+use crate::models::comment::{Comment, ImportSummary, NewComment};
 use ammonia::Builder;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use pulldown_cmark::{Options, Parser, html};
 use std::fs;
 use std::path::Path;
 
+/// SQLite's `CURRENT_TIMESTAMP` format, used for every stored comment.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
 /// Convert Markdown into sanitized HTML and persist to the provided path.
 ///
 /// This function first renders the provided Markdown to HTML, then uses
@@ -14,16 +20,113 @@ pub fn save_comment<P: AsRef<Path>>(raw_markdown: &str, path: P) -> std::io::Res
 }
 
 /// Convert Markdown text to sanitized HTML.
-fn sanitize_content(raw_markdown: &str) -> String {
+pub fn sanitize_content(raw_markdown: &str) -> String {
     let parser = Parser::new_ext(raw_markdown, Options::all());
     let mut html_output = String::new();
     html::push_html(&mut html_output, parser);
     Builder::default().clean(&html_output).to_string()
 }
 
+/// Clean HTML that was rendered elsewhere, such as imported comments.
+pub fn sanitize_html(html: &str) -> String {
+    Builder::default().clean(html).to_string()
+}
+
+/// Parses a timestamp in the stored format or RFC 3339 and returns it in
+/// the stored format, in UTC.
+pub fn normalize_timestamp(value: &str) -> Option<String> {
+    let value = value.trim();
+    let parsed = NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .map(|naive| naive.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(|dt| dt.with_timezone(&Utc)))
+        .ok()?;
+    Some(parsed.format(TIMESTAMP_FORMAT).to_string())
+}
+
+pub fn current_timestamp() -> String {
+    Utc::now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+pub async fn list_comments(pool: &DbPool) -> Result<Vec<Comment>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, article_slug, author_github_id, content, created_at, author_name \
+         FROM comments ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Up to `limit` comments older than the comment `before`, newest first.
+pub async fn recent_comments(
+    pool: &DbPool,
+    before: i64,
+    limit: usize,
+) -> Result<Vec<Comment>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, article_slug, author_github_id, content, created_at, author_name \
+         FROM comments WHERE id < ? ORDER BY id DESC LIMIT ?",
+    )
+    .bind(before)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn count_comments(pool: &DbPool, article_slug: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM comments WHERE article_slug = ?")
+        .bind(article_slug)
+        .fetch_one(pool)
+        .await
+}
+
+/// Stores `comments` in one transaction. Rows matching an existing comment
+/// in every field are skipped, so re-running an import is harmless.
+pub async fn import_comments(
+    pool: &DbPool,
+    comments: &[NewComment],
+) -> Result<ImportSummary, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut summary = ImportSummary {
+        imported: 0,
+        skipped: 0,
+    };
+    for comment in comments {
+        let exists: Option<i64> = sqlx::query_scalar(
+            "SELECT id FROM comments WHERE article_slug = ? AND author_github_id = ? \
+             AND content = ? AND created_at = ?",
+        )
+        .bind(&comment.article_slug)
+        .bind(comment.author_github_id)
+        .bind(&comment.content)
+        .bind(&comment.created_at)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if exists.is_some() {
+            summary.skipped += 1;
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO comments \
+             (article_slug, author_github_id, content, created_at, author_name) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&comment.article_slug)
+        .bind(comment.author_github_id)
+        .bind(&comment.content)
+        .bind(&comment.created_at)
+        .bind(&comment.author_name)
+        .execute(&mut *tx)
+        .await?;
+        summary.imported += 1;
+    }
+    tx.commit().await?;
+    Ok(summary)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::memory_db;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -51,4 +154,37 @@ mod tests {
         let stored = std::fs::read_to_string(path).unwrap();
         assert!(!stored.contains("<script"));
     }
+
+    #[test]
+    fn normalizes_timestamps_to_utc() {
+        assert_eq!(
+            normalize_timestamp("2024-03-01T12:00:00+02:00").as_deref(),
+            Some("2024-03-01 10:00:00")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-01 10:00:00").as_deref(),
+            Some("2024-03-01 10:00:00")
+        );
+        assert_eq!(normalize_timestamp("yesterday"), None);
+    }
+
+    #[tokio::test]
+    async fn reimporting_skips_existing_comments() {
+        let pool = memory_db().await;
+        let comments = vec![NewComment {
+            article_slug: "hello-world".to_string(),
+            author_github_id: 7,
+            content: "<p>Nice</p>".to_string(),
+            created_at: "2024-03-01 10:00:00".to_string(),
+            author_name: None,
+        }];
+        let first = import_comments(&pool, &comments).await.unwrap();
+        assert_eq!((first.imported, first.skipped), (1, 0));
+        let again = import_comments(&pool, &comments).await.unwrap();
+        assert_eq!((again.imported, again.skipped), (0, 1));
+
+        let stored = list_comments(&pool).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].created_at, "2024-03-01 10:00:00");
+    }
 }
@@ -0,0 +1,70 @@
+use crate::models::audit::AuditEntry;
+use crate::models::content_diff::{AddedArticle, ContentDiff, DeletedArticle, EditedArticle};
+use crate::services::changelog::changelog;
+use crate::services::service::ArticleStore;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+/// Summarizes what changed from `from` up to `to`: articles dated within
+/// the window, edits of older ones from their saved versions, and the
+/// `deletions` the audit log recorded. An article both added and edited
+/// within the window is only listed as added. The first version saved of
+/// an article has nothing to compare against, so its lines are not counted.
+pub fn content_diff(
+    data_dir: &Path,
+    store: &ArticleStore,
+    deletions: Vec<AuditEntry>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> ContentDiff {
+    let within = |time: DateTime<Utc>| from <= time && time < to;
+    let mut added = Vec::new();
+    let mut edited = Vec::new();
+    for article in store.query(|_| true, 0, usize::MAX) {
+        if within(article.metadata.date) {
+            added.push(AddedArticle {
+                slug: article.slug.clone(),
+                title: article.metadata.title.clone(),
+                status: article.metadata.status,
+                date: article.metadata.date,
+            });
+            continue;
+        }
+        let mut entries: Vec<_> = changelog(data_dir, article)
+            .entries
+            .into_iter()
+            .filter(|e| within(e.timestamp))
+            .collect();
+        let Some(last) = entries.first() else {
+            continue;
+        };
+        let last_edited_at = last.timestamp;
+        entries.reverse();
+        edited.push(EditedArticle {
+            slug: article.slug.clone(),
+            title: article.metadata.title.clone(),
+            edits: entries.len(),
+            lines_added: entries.iter().filter_map(|e| e.lines_added).sum(),
+            lines_removed: entries.iter().filter_map(|e| e.lines_removed).sum(),
+            last_edited_at,
+            messages: entries.into_iter().filter_map(|e| e.message).collect(),
+        });
+    }
+    added.sort_by_key(|a| a.date);
+    edited.sort_by_key(|e| std::cmp::Reverse(e.last_edited_at));
+
+    ContentDiff {
+        from,
+        to,
+        added,
+        edited,
+        deleted: deletions
+            .into_iter()
+            .map(|entry| DeletedArticle {
+                slug: entry.target,
+                title: entry.detail,
+                deleted_at: entry.created_at,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,373 @@
+use crate::config::{BlueskyConfig, CrosspostConfig, MastodonConfig};
+use crate::handlers::articles::rewrite_article;
+use crate::models::article::Article;
+use crate::models::evergreen::EvergreenReminder;
+use crate::server::app::AppState;
+use crate::server::http_client::HttpClient;
+use crate::server::job_queue::JobQueue;
+use crate::services::audit_service::{CROSSPOST, CROSSPOST_REMINDER, record_audit};
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::env;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Job type of cross-posts.
+pub const CROSSPOST_JOB: &str = "crosspost";
+/// Job type of approved evergreen reminders, one per account.
+pub const REMINDER_JOB: &str = "crosspost_reminder";
+/// Keys of the article's `syndication` the posts are recorded under.
+pub const MASTODON: &str = "mastodon";
+pub const BLUESKY: &str = "bluesky";
+
+const MASTODON_MAX_CHARS: usize = 500;
+const BLUESKY_MAX_CHARS: usize = 300;
+
+#[derive(Deserialize, Debug)]
+struct Crosspost {
+    slug: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Reminder {
+    reminder: i64,
+    slug: String,
+    service: String,
+}
+
+/// A post about an article: `headline` followed by its URL. `key` tells
+/// apart retries of the same post from new posts.
+struct Announcement<'a> {
+    article: &'a Article,
+    headline: String,
+    url: String,
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct MastodonStatus {
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueskySession {
+    access_jwt: String,
+    did: String,
+}
+
+#[derive(Deserialize)]
+struct BlueskyRecord {
+    uri: String,
+}
+
+/// Queues posting `slug` to the `[crosspost]` accounts. Nothing is queued
+/// without a `[crosspost]` table.
+pub async fn queue_crosspost(state: &AppState, slug: &str) {
+    if state.config.crosspost.is_none() {
+        return;
+    }
+    if let Err(e) = state
+        .job_queue
+        .push(state, CROSSPOST_JOB, &json!({ "slug": slug }))
+        .await
+    {
+        warn!("Failed to queue cross-post: {:?}", e);
+    }
+}
+
+/// Posts a published article to every configured account it has not been
+/// posted to yet. Each post's URL is saved to the article's `syndication`
+/// as soon as it exists, so the retry of a partly failed job only posts
+/// where it failed.
+pub async fn deliver_crosspost(state: Arc<AppState>, payload: Value) -> Result<(), String> {
+    // Cross-posting may have been turned off since the job was queued.
+    let Some(config) = &state.config.crosspost else {
+        return Ok(());
+    };
+    let job: Crosspost = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+    let mut failures = Vec::new();
+    for service in [MASTODON, BLUESKY] {
+        // Unpublished or deleted since it was queued.
+        let Some(article) = state
+            .store
+            .read()
+            .await
+            .get_by_slug(&job.slug)
+            .filter(|a| a.metadata.is_published())
+            .cloned()
+        else {
+            return Ok(());
+        };
+        if article.metadata.syndication.contains_key(service) {
+            continue;
+        }
+        let announcement = Announcement {
+            article: &article,
+            headline: article.metadata.title.clone(),
+            url: state.config.article_url(&article.slug),
+            key: format!("scribe-{}", article.slug),
+        };
+        match announce(&state, config, service, CROSSPOST, &announcement).await {
+            None => {}
+            Some(Ok(post)) => record_syndication(&state, article, service, post).await?,
+            Some(Err(e)) => failures.push(format!("{}: {}", service, e)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Cross-posting failed: {}", failures.join("; ")))
+    }
+}
+
+/// Queues the posts of an approved evergreen reminder, one job per
+/// configured account, so a failed one is retried alone.
+pub async fn queue_reminder(state: &AppState, reminder: &EvergreenReminder) {
+    let Some(config) = &state.config.crosspost else {
+        return;
+    };
+    let services = [
+        (MASTODON, config.mastodon.is_some()),
+        (BLUESKY, config.bluesky.is_some()),
+    ];
+    for (service, _) in services.iter().filter(|(_, configured)| *configured) {
+        let job = json!({
+            "reminder": reminder.id,
+            "slug": reminder.article_slug,
+            "service": service,
+        });
+        if let Err(e) = state.job_queue.push(state, REMINDER_JOB, &job).await {
+            warn!("Failed to queue evergreen reminder: {:?}", e);
+        }
+    }
+}
+
+/// Posts an approved evergreen reminder to one account. The article's
+/// `syndication` keeps pointing at the original post.
+pub async fn deliver_reminder(state: Arc<AppState>, payload: Value) -> Result<(), String> {
+    let Some(config) = &state.config.crosspost else {
+        return Ok(());
+    };
+    let job: Reminder = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+    let Some(article) = state
+        .store
+        .read()
+        .await
+        .get_by_slug(&job.slug)
+        .filter(|a| a.metadata.is_published())
+        .cloned()
+    else {
+        return Ok(());
+    };
+    let announcement = Announcement {
+        article: &article,
+        headline: format!("From the archive: {}", article.metadata.title),
+        url: state.config.article_url(&article.slug),
+        key: format!("scribe-{}-reminder-{}", article.slug, job.reminder),
+    };
+    match announce(
+        &state,
+        config,
+        &job.service,
+        CROSSPOST_REMINDER,
+        &announcement,
+    )
+    .await
+    {
+        Some(Err(e)) => Err(format!("Evergreen reminder failed: {}: {}", job.service, e)),
+        _ => Ok(()),
+    }
+}
+
+/// Posts `announcement` to `service` and records the attempt in the audit
+/// log as `action`. `None` when the service is not configured.
+async fn announce(
+    state: &AppState,
+    config: &CrosspostConfig,
+    service: &str,
+    action: &str,
+    announcement: &Announcement<'_>,
+) -> Option<Result<String, String>> {
+    let (target, result) = match (service, &config.mastodon, &config.bluesky) {
+        (MASTODON, Some(mastodon), _) => (
+            mastodon.instance.as_str(),
+            post_to_mastodon(&state.http, mastodon, announcement).await,
+        ),
+        (BLUESKY, _, Some(bluesky)) => (
+            bluesky.handle.as_str(),
+            post_to_bluesky(&state.http, bluesky, announcement).await,
+        ),
+        _ => return None,
+    };
+    let outcome = match &result {
+        Ok(post) => post.as_str(),
+        Err(e) => e.as_str(),
+    };
+    let detail = format!("{}: {}", announcement.article.slug, outcome);
+    if let Err(e) = record_audit(&state.db, action, target, result.is_ok(), Some(&detail)).await {
+        warn!("Failed to record cross-post in the audit log: {:?}", e);
+    }
+    Some(result)
+}
+
+async fn record_syndication(
+    state: &AppState,
+    mut article: Article,
+    service: &str,
+    post: String,
+) -> Result<(), String> {
+    let content = state
+        .store
+        .read()
+        .await
+        .load_source_for(&article)
+        .map_err(|e| format!("Failed to load {}: {}", article.slug, e))?;
+    article
+        .metadata
+        .syndication
+        .insert(service.to_string(), post);
+    rewrite_article(state, &article, &content, None)
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to record the {} post of {}: {:?}",
+                service, article.slug, e
+            )
+        })
+}
+
+fn credential(var: &str) -> Result<String, String> {
+    env::var(var).map_err(|_| format!("{} is not set", var))
+}
+
+/// The status announcing an article: a headline and its URL, the headline
+/// shortened so the whole fits in `max_chars`.
+fn status_text(title: &str, url: &str, max_chars: usize) -> String {
+    let room = max_chars.saturating_sub(url.chars().count() + 2);
+    if title.chars().count() <= room {
+        return format!("{}\n\n{}", title, url);
+    }
+    let title: String = title.chars().take(room.saturating_sub(1)).collect();
+    format!("{}…\n\n{}", title.trim_end(), url)
+}
+
+/// Posts the status and returns its URL. The idempotency key makes the
+/// instance answer a retried request with the status it already created.
+async fn post_to_mastodon(
+    http: &HttpClient,
+    config: &MastodonConfig,
+    announcement: &Announcement<'_>,
+) -> Result<String, String> {
+    let token = credential(&config.token_env)?;
+    let endpoint = format!("{}/api/v1/statuses", config.instance.trim_end_matches('/'));
+    let request = http
+        .post(endpoint)
+        .bearer_auth(token)
+        .header("Idempotency-Key", &announcement.key)
+        .json(&json!({
+            "status": status_text(&announcement.headline, &announcement.url, MASTODON_MAX_CHARS),
+            "visibility": config.visibility,
+        }));
+    let status: MastodonStatus = http
+        .send(request)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(status.url)
+}
+
+/// Signs in with the app password, posts the title with the URL as a link
+/// and a link card, and returns the post's bsky.app URL.
+async fn post_to_bluesky(
+    http: &HttpClient,
+    config: &BlueskyConfig,
+    announcement: &Announcement<'_>,
+) -> Result<String, String> {
+    let Announcement { article, url, .. } = announcement;
+    let password = credential(&config.app_password_env)?;
+    let service = config.service.trim_end_matches('/');
+    let request = http
+        .post(format!("{}/xrpc/com.atproto.server.createSession", service))
+        .json(&json!({ "identifier": config.handle, "password": password }));
+    let session: BlueskySession = http
+        .send(request)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let text = status_text(&announcement.headline, url, BLUESKY_MAX_CHARS);
+    // Facets index the text by UTF-8 bytes; the URL ends it.
+    let record = json!({
+        "$type": "app.bsky.feed.post",
+        "text": text,
+        "createdAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "facets": [{
+            "index": { "byteStart": text.len() - url.len(), "byteEnd": text.len() },
+            "features": [{ "$type": "app.bsky.richtext.facet#link", "uri": url }],
+        }],
+        "embed": {
+            "$type": "app.bsky.embed.external",
+            "external": {
+                "uri": url,
+                "title": article.metadata.title,
+                "description": article.metadata.description,
+            },
+        },
+    });
+    let request = http
+        .post(format!("{}/xrpc/com.atproto.repo.createRecord", service))
+        .bearer_auth(session.access_jwt)
+        .json(&json!({
+            "repo": session.did,
+            "collection": "app.bsky.feed.post",
+            "record": record,
+        }));
+    let created: BlueskyRecord = http
+        .send(request)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    let rkey = created.uri.rsplit('/').next().unwrap_or_default();
+    Ok(format!(
+        "https://bsky.app/profile/{}/post/{}",
+        config.handle, rkey
+    ))
+}
+
+/// Registers the cross-post and reminder handlers with `queue`.
+pub fn register(queue: &mut JobQueue) {
+    queue.register(
+        CROSSPOST_JOB,
+        Arc::new(|state, payload| Box::pin(deliver_crosspost(state, payload))),
+    );
+    queue.register(
+        REMINDER_JOB,
+        Arc::new(|state, payload| Box::pin(deliver_reminder(state, payload))),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_titles_are_shortened_to_fit() {
+        let url = "https://blog.example/articles/a";
+        assert_eq!(status_text("Short", url, 300), format!("Short\n\n{}", url));
+
+        let text = status_text(&"x".repeat(400), url, 300);
+        assert_eq!(text.chars().count(), 300);
+        assert!(text.ends_with(&format!("x…\n\n{}", url)));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::models::diagnostics::{CheckStatus, DiagnosticCheck, Diagnostics};
+use crate::server::app::AppState;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use reqwest::header::DATE;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Free space below which a directory fails the disk check.
+pub const MIN_FREE_BYTES: u64 = 100 * 1024 * 1024;
+/// Free space below which a directory gets a warning.
+pub const LOW_FREE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Difference from the GitHub API's clock beyond which the check fails.
+/// Signed cookies, scheduled posts and GitHub tokens all go by the clock.
+pub const MAX_CLOCK_SKEW_SECS: i64 = 30;
+
+const CLOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn check(
+    name: impl Into<String>,
+    status: CheckStatus,
+    detail: impl Into<String>,
+) -> DiagnosticCheck {
+    DiagnosticCheck {
+        name: name.into(),
+        status,
+        detail: detail.into(),
+    }
+}
+
+/// The directories the server reads content from or writes to, by name.
+/// The pages directory is optional and left out when missing, as is the
+/// search index when full-text search is off.
+async fn directories(state: &AppState) -> Vec<(&'static str, PathBuf)> {
+    let mut dirs = vec![
+        ("articles", state.store.read().await.root().to_path_buf()),
+        ("notes", state.note_store.read().await.root().to_path_buf()),
+    ];
+    let pages = state.page_store.read().await.root().to_path_buf();
+    if pages.is_dir() {
+        dirs.push(("pages", pages));
+    }
+    dirs.push(("data", state.data_dir.clone()));
+    if state.config.enable_full_text_search {
+        dirs.push(("search_index", state.search_index_dir.clone()));
+    }
+    dirs
+}
+
+fn mib(bytes: u64) -> u64 {
+    bytes / (1024 * 1024)
+}
+
+/// Measured on the nearest existing ancestor, as the data directory is
+/// only created on the first write.
+fn disk_space(name: &str, dir: &Path) -> DiagnosticCheck {
+    let name = format!("disk_space:{}", name);
+    let existing = dir.ancestors().find(|d| d.exists()).unwrap_or(dir);
+    match fs4::available_space(existing) {
+        Ok(free) if free < MIN_FREE_BYTES => check(
+            name,
+            CheckStatus::Fail,
+            format!("{} MiB free in {}", mib(free), dir.display()),
+        ),
+        Ok(free) if free < LOW_FREE_BYTES => check(
+            name,
+            CheckStatus::Warn,
+            format!("{} MiB free in {}", mib(free), dir.display()),
+        ),
+        Ok(free) => check(name, CheckStatus::Pass, format!("{} MiB free", mib(free))),
+        Err(e) => check(name, CheckStatus::Fail, format!("{}: {}", dir.display(), e)),
+    }
+}
+
+/// Writes and removes a probe file in `dir`, creating the directory as a
+/// first real write would. The name is one file watchers skip as a
+/// temporary file, so the probe does not trigger a reload.
+fn writable(name: &str, dir: &Path) -> DiagnosticCheck {
+    let name = format!("writable:{}", name);
+    let probe = dir.join(format!(".diagnostics-{}.tmp", std::process::id()));
+    let written = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b"probe"))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match written {
+        Ok(()) => check(name, CheckStatus::Pass, dir.display().to_string()),
+        Err(e) => check(name, CheckStatus::Fail, format!("{}: {}", dir.display(), e)),
+    }
+}
+
+async fn database(state: &AppState) -> DiagnosticCheck {
+    match sqlx::query_scalar::<_, String>("PRAGMA quick_check")
+        .fetch_all(&state.db)
+        .await
+    {
+        Ok(rows) if rows == ["ok"] => check("database", CheckStatus::Pass, "quick_check ok"),
+        Ok(rows) => check("database", CheckStatus::Fail, rows.join("; ")),
+        Err(e) => check("database", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+/// Fails when a watcher has stopped, with the error it stopped on.
+/// Watchers only run under the server, so none having started is a
+/// warning.
+fn watchers(state: &AppState) -> DiagnosticCheck {
+    let watchers = state.watchers.snapshot();
+    if watchers.is_empty() {
+        return check(
+            "watchers",
+            CheckStatus::Warn,
+            "no file watchers were started",
+        );
+    }
+    let stopped: Vec<String> = watchers
+        .iter()
+        .filter(|(_, health)| !health.running)
+        .map(|(name, health)| match &health.last_error {
+            Some(error) => format!("{} ({})", name, error),
+            None => name.to_string(),
+        })
+        .collect();
+    if stopped.is_empty() {
+        let running: Vec<String> = watchers
+            .iter()
+            .map(|(name, health)| match health.restarts {
+                0 => name.to_string(),
+                restarts => format!("{} (restarted {} times)", name, restarts),
+            })
+            .collect();
+        check(
+            "watchers",
+            CheckStatus::Pass,
+            format!("watching {}", running.join(", ")),
+        )
+    } else {
+        check(
+            "watchers",
+            CheckStatus::Fail,
+            format!("stopped: {}", stopped.join(", ")),
+        )
+    }
+}
+
+/// Compares the clock against the `Date` header of the GitHub API, taking
+/// the middle of the request as the local time. A server that cannot be
+/// reached gives a warning.
+async fn clock_skew(state: &AppState) -> DiagnosticCheck {
+    let sent = Utc::now();
+    let response = state
+        .http
+        .request(Method::HEAD, &state.config.github_api_url)
+        .timeout(CLOCK_TIMEOUT)
+        .send()
+        .await;
+    let received = Utc::now();
+    let remote = match &response {
+        Ok(response) => response
+            .headers()
+            .get(DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok()),
+        Err(e) => {
+            return check(
+                "clock_skew",
+                CheckStatus::Warn,
+                format!(
+                    "{} could not be reached: {}",
+                    state.config.github_api_url, e
+                ),
+            );
+        }
+    };
+    let Some(remote) = remote else {
+        return check(
+            "clock_skew",
+            CheckStatus::Warn,
+            format!("{} sent no Date header", state.config.github_api_url),
+        );
+    };
+    let local = sent + (received - sent) / 2;
+    let skew = (local - remote.with_timezone(&Utc)).num_seconds();
+    let status = if skew.abs() > MAX_CLOCK_SKEW_SECS {
+        CheckStatus::Fail
+    } else {
+        CheckStatus::Pass
+    };
+    check(
+        "clock_skew",
+        status,
+        format!("{}s from {}", skew, state.config.github_api_url),
+    )
+}
+
+/// Runs every self-test. The server is healthy when none fails.
+pub async fn run_diagnostics(state: &AppState) -> Diagnostics {
+    let mut checks = Vec::new();
+    let dirs = directories(state).await;
+    for (name, dir) in &dirs {
+        checks.push(disk_space(name, dir));
+    }
+    for (name, dir) in &dirs {
+        checks.push(writable(name, dir));
+    }
+    checks.push(database(state).await);
+    checks.push(watchers(state));
+    checks.push(clock_skew(state).await);
+    let now = Utc::now();
+    Diagnostics {
+        healthy: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        started_at: state.started_at,
+        uptime_seconds: (now - state.started_at).num_seconds(),
+        checks,
+    }
+}
@@ -0,0 +1,154 @@
+use reqwest::Url;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+/// Namespace of the `dsq:id` attributes that link posts to threads.
+const DSQ_NS: &str = "http://disqus.com/disqus-internals";
+
+/// A comment from a Disqus export, before it is matched to an article.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisqusPost {
+    /// URL of the page the comment was left on.
+    pub thread_link: String,
+    pub author_name: String,
+    /// HTML as exported; not yet sanitized.
+    pub message: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Default)]
+pub struct DisqusExport {
+    pub posts: Vec<DisqusPost>,
+    /// Posts marked deleted or spam, or whose thread is missing.
+    pub ignored: usize,
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.tag_name().name() == name)
+        .and_then(|c| c.text())
+        .map(str::trim)
+}
+
+fn is_flagged(node: Node, name: &str) -> bool {
+    child_text(node, name) == Some("true")
+}
+
+/// Reads threads and posts from a Disqus XML export.
+pub fn parse_export(xml: &str) -> Result<DisqusExport, roxmltree::Error> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root_element();
+
+    let threads: HashMap<&str, &str> = root
+        .children()
+        .filter(|n| n.tag_name().name() == "thread")
+        .filter_map(|n| Some((n.attribute((DSQ_NS, "id"))?, child_text(n, "link")?)))
+        .collect();
+
+    let mut export = DisqusExport::default();
+    for post in root.children().filter(|n| n.tag_name().name() == "post") {
+        let thread_link = post
+            .children()
+            .find(|c| c.tag_name().name() == "thread")
+            .and_then(|t| t.attribute((DSQ_NS, "id")))
+            .and_then(|id| threads.get(id));
+        let (Some(thread_link), false, false) = (
+            thread_link,
+            is_flagged(post, "isDeleted"),
+            is_flagged(post, "isSpam"),
+        ) else {
+            export.ignored += 1;
+            continue;
+        };
+        let author = post.children().find(|c| c.tag_name().name() == "author");
+        let author_name = author
+            .and_then(|a| child_text(a, "name").or_else(|| child_text(a, "username")))
+            .filter(|name| !name.is_empty())
+            .unwrap_or("Anonymous");
+        export.posts.push(DisqusPost {
+            thread_link: thread_link.to_string(),
+            author_name: author_name.to_string(),
+            message: child_text(post, "message").unwrap_or_default().to_string(),
+            created_at: child_text(post, "createdAt")
+                .unwrap_or_default()
+                .to_string(),
+        });
+    }
+    Ok(export)
+}
+
+/// Article slug a thread URL points at: the segment after `/articles/` for
+/// this blog's permalinks, otherwise the last path segment without an
+/// `.html` extension, which covers most older blog engines. Relative paths
+/// are accepted too.
+pub fn slug_from_link(link: &str) -> Option<String> {
+    let base = Url::parse("http://localhost/").ok()?;
+    let url = base.join(link.trim()).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let slug = match segments.iter().position(|s| *s == "articles") {
+        Some(i) => segments.get(i + 1)?,
+        None => segments.last()?,
+    };
+    let slug = slug.strip_suffix(".html").unwrap_or(slug);
+    (!slug.is_empty()).then(|| slug.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<disqus xmlns="http://disqus.com" xmlns:dsq="http://disqus.com/disqus-internals">
+  <thread dsq:id="1">
+    <link>https://old.example/2013/10/hello-world.html</link>
+    <title>Hello</title>
+  </thread>
+  <post dsq:id="10">
+    <message><![CDATA[<p>First!</p>]]></message>
+    <createdAt>2013-10-29T19:16:29Z</createdAt>
+    <isDeleted>false</isDeleted>
+    <isSpam>false</isSpam>
+    <author><name>Bob</name><username>bob</username></author>
+    <thread dsq:id="1"/>
+  </post>
+  <post dsq:id="11">
+    <message><![CDATA[<p>Buy now</p>]]></message>
+    <isSpam>true</isSpam>
+    <thread dsq:id="1"/>
+  </post>
+  <post dsq:id="12">
+    <message>orphan</message>
+    <thread dsq:id="99"/>
+  </post>
+</disqus>"#;
+
+    #[test]
+    fn parses_posts_and_skips_flagged_ones() {
+        let export = parse_export(EXPORT).unwrap();
+        assert_eq!(export.ignored, 2);
+        assert_eq!(
+            export.posts,
+            vec![DisqusPost {
+                thread_link: "https://old.example/2013/10/hello-world.html".to_string(),
+                author_name: "Bob".to_string(),
+                message: "<p>First!</p>".to_string(),
+                created_at: "2013-10-29T19:16:29Z".to_string(),
+            }]
+        );
+        assert!(parse_export("<disqus>").is_err());
+    }
+
+    #[test]
+    fn maps_links_to_slugs() {
+        assert_eq!(
+            slug_from_link("https://blog.example/articles/axum-routing?x=1").as_deref(),
+            Some("axum-routing")
+        );
+        assert_eq!(
+            slug_from_link("https://old.example/2013/10/hello-world.html/").as_deref(),
+            Some("hello-world")
+        );
+        assert_eq!(slug_from_link("/articles/hello").as_deref(), Some("hello"));
+        assert_eq!(slug_from_link("https://old.example/"), None);
+    }
+}
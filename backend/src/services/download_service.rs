@@ -0,0 +1,50 @@
+use crate::db::DbPool;
+use std::collections::HashMap;
+
+/// Counts a download of the file `name` attached to `slug`.
+pub async fn record_download(pool: &DbPool, slug: &str, name: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO download_counts (slug, name, downloads) VALUES (?, ?, 1) \
+         ON CONFLICT (slug, name) DO UPDATE SET downloads = downloads + 1",
+    )
+    .bind(slug)
+    .bind(name)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Download counts of the files attached to `slug`, by name. Files never
+/// downloaded are left out.
+pub async fn download_counts(
+    pool: &DbPool,
+    slug: &str,
+) -> Result<HashMap<String, i64>, sqlx::Error> {
+    let rows: Vec<(String, i64)> =
+        sqlx::query_as("SELECT name, downloads FROM download_counts WHERE slug = ?")
+            .bind(slug)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn counts_downloads_per_file() {
+        let pool = memory_db().await;
+        record_download(&pool, "guide", "guide.pdf").await.unwrap();
+        record_download(&pool, "guide", "guide.pdf").await.unwrap();
+        record_download(&pool, "guide", "code.zip").await.unwrap();
+        record_download(&pool, "other", "guide.pdf").await.unwrap();
+
+        let counts = download_counts(&pool, "guide").await.unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["guide.pdf"], 2);
+        assert_eq!(counts["code.zip"], 1);
+        assert!(download_counts(&pool, "none").await.unwrap().is_empty());
+    }
+}
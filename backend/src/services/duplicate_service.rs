@@ -0,0 +1,213 @@
+use crate::models::duplicate::{DuplicatePair, SimilarArticle};
+
+/// Titles at least this similar count as nearly identical.
+pub const SIMILAR_TITLE_THRESHOLD: f64 = 0.85;
+
+/// Words per shingle.
+const SHINGLE_SIZE: usize = 5;
+/// Hash functions per signature; the similarity estimate is accurate to
+/// roughly `1 / sqrt(SIGNATURE_SIZE)`.
+const SIGNATURE_SIZE: usize = 128;
+
+fn fnv1a(words: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for word in words {
+        for byte in word.bytes().chain([b' ']) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+fn mix(mut x: u64) -> u64 {
+    // splitmix64 finalizer.
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// MinHash signature of the word shingles in `content`, or `None` when it
+/// has no words.
+pub fn signature(content: &str) -> Option<Vec<u64>> {
+    let lowered = content.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let shingles: Vec<u64> = words
+        .windows(SHINGLE_SIZE.min(words.len()))
+        .map(fnv1a)
+        .collect();
+    Some(
+        (0..SIGNATURE_SIZE as u64)
+            .map(|seed| {
+                let salt = mix(seed.wrapping_add(0x9e37_79b9_7f4a_7c15));
+                shingles
+                    .iter()
+                    .map(|&s| mix(s ^ salt))
+                    .min()
+                    .unwrap_or(u64::MAX)
+            })
+            .collect(),
+    )
+}
+
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len().max(1) as f64
+}
+
+/// Pairs of documents at least `threshold` similar, most similar first.
+pub fn find_duplicates(documents: &[(String, String)], threshold: f64) -> Vec<DuplicatePair> {
+    let signatures: Vec<(&str, Vec<u64>)> = documents
+        .iter()
+        .filter_map(|(slug, content)| signature(content).map(|s| (slug.as_str(), s)))
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (i, (first, a)) in signatures.iter().enumerate() {
+        for (second, b) in &signatures[i + 1..] {
+            let similarity = similarity(a, b);
+            if similarity >= threshold {
+                pairs.push(DuplicatePair {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                    similarity: (similarity * 100.0).round() / 100.0,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    pairs
+}
+
+/// The title lowercased, its words joined by single spaces, without
+/// punctuation.
+fn normalize_title(title: &str) -> Vec<char> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect()
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Similarity of two titles from 0 to 1: one minus their edit distance
+/// over the longer length, ignoring case and punctuation.
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_title(a), normalize_title(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / longest as f64
+}
+
+/// Articles, given as slug and title, whose titles are nearly identical to
+/// `title`, most similar first.
+pub fn similar_titles<'a>(
+    title: &str,
+    articles: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<SimilarArticle> {
+    let mut similar: Vec<SimilarArticle> = articles
+        .into_iter()
+        .map(|(slug, other)| SimilarArticle {
+            slug: slug.to_string(),
+            title: other.to_string(),
+            similarity: title_similarity(title, other),
+        })
+        .filter(|a| a.similarity >= SIMILAR_TITLE_THRESHOLD)
+        .collect();
+    similar.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(a.slug.cmp(&b.slug))
+    });
+    similar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "Axum is a web application framework that focuses on ergonomics \
+        and modularity. It routes requests to handlers with a macro-free API, declaratively \
+        parses requests using extractors, and has a simple and predictable error handling \
+        model. It generates responses with minimal boilerplate and takes full advantage of \
+        the tower and tower-http ecosystem of middleware, services, and utilities.";
+
+    fn doc(slug: &str, content: &str) -> (String, String) {
+        (slug.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn flags_copies_and_near_copies_only() {
+        let edited = ORIGINAL.replace("minimal boilerplate", "very little boilerplate");
+        let unrelated = "Tantivy is a full-text search engine library inspired by Apache \
+            Lucene and written in Rust. It is closer to Lucene than to Elasticsearch.";
+        let documents = [
+            doc("original", ORIGINAL),
+            doc("reimported", &ORIGINAL.to_uppercase()),
+            doc("edited", &edited),
+            doc("unrelated", unrelated),
+            doc("empty", ""),
+        ];
+
+        let pairs = find_duplicates(&documents, 0.6);
+        let names: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|p| (p.first.as_str(), p.second.as_str()))
+            .collect();
+        assert_eq!(pairs[0].similarity, 1.0);
+        assert_eq!(names[0], ("original", "reimported"));
+        assert!(names.contains(&("original", "edited")));
+        assert!(names.contains(&("reimported", "edited")));
+        assert_eq!(pairs.len(), 3);
+    }
+
+    #[test]
+    fn short_documents_still_get_a_signature() {
+        let a = signature("hello world").unwrap();
+        let b = signature("Hello, world!").unwrap();
+        assert_eq!(similarity(&a, &b), 1.0);
+        assert!(signature("  ...  ").is_none());
+    }
+
+    #[test]
+    fn flags_titles_that_differ_only_slightly() {
+        assert_eq!(title_similarity("Routing in Axum", "routing in axum!"), 1.0);
+        assert!(title_similarity("Routing in Axum", "Routing in Axun") > SIMILAR_TITLE_THRESHOLD);
+        assert!(title_similarity("Routing in Axum", "State in Axum") < SIMILAR_TITLE_THRESHOLD);
+
+        let articles = [
+            ("axum-routing", "Routing in Axum"),
+            ("axum-routing-2", "Routing in  Axum 2"),
+            ("axum-state", "State in Axum"),
+        ];
+        let similar = similar_titles("Routing in axum", articles);
+        let slugs: Vec<&str> = similar.iter().map(|a| a.slug.as_str()).collect();
+        assert_eq!(slugs, ["axum-routing", "axum-routing-2"]);
+        assert_eq!(similar[0].similarity, 1.0);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::server::app::AppState;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chrono::Utc;
+use serde_json::json;
+use tracing::warn;
+
+const SENTRY_CLIENT: &str = concat!("scribe/", env!("CARGO_PKG_VERSION"));
+
+/// Where a Sentry-compatible DSN, `https://<public key>@<host>/<project id>`,
+/// sends its events.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dsn {
+    pub store_url: String,
+    pub public_key: String,
+}
+
+impl Dsn {
+    pub fn parse(dsn: &str) -> Result<Dsn, String> {
+        let url = reqwest::Url::parse(dsn).map_err(|e| format!("invalid DSN: {}", e))?;
+        if url.username().is_empty() {
+            return Err("the DSN has no public key".to_string());
+        }
+        let host = url.host_str().ok_or("the DSN has no host")?;
+        let mut segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let project = segments
+            .pop()
+            .filter(|p| p.chars().all(|c| c.is_ascii_digit()))
+            .ok_or("the DSN has no project id")?;
+        let mut store_url = format!("{}://{}", url.scheme(), host);
+        if let Some(port) = url.port() {
+            store_url.push_str(&format!(":{}", port));
+        }
+        for segment in segments {
+            store_url.push_str(&format!("/{}", segment));
+        }
+        store_url.push_str(&format!("/api/{}/store/", project));
+        Ok(Dsn {
+            store_url,
+            public_key: url.username().to_string(),
+        })
+    }
+
+    fn auth_header(&self) -> String {
+        format!(
+            "Sentry sentry_version=7, sentry_client={}, sentry_key={}",
+            SENTRY_CLIENT, self.public_key
+        )
+    }
+}
+
+/// A panic or internal server error, with the request it happened in.
+#[derive(Debug, Clone)]
+pub struct ErrorEvent {
+    pub message: String,
+    pub panic: bool,
+    /// The `ERR_*` code of the response, when it came from `AppError`.
+    pub error_code: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub request_id: Option<String>,
+}
+
+fn event_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Sends `event` to the `[error_reporting]` DSN. Does nothing without one.
+/// Failures are logged, not retried.
+pub async fn report_error(state: &AppState, event: ErrorEvent) {
+    let Some(config) = &state.config.error_reporting else {
+        return;
+    };
+    let dsn = match Dsn::parse(&config.dsn) {
+        Ok(dsn) => dsn,
+        Err(e) => {
+            warn!("Not reporting error: {}", e);
+            return;
+        }
+    };
+    let mut tags = json!({ "kind": if event.panic { "panic" } else { "error" } });
+    if let Some(request_id) = &event.request_id {
+        tags["request_id"] = json!(request_id);
+    }
+    if let Some(code) = &event.error_code {
+        tags["error_code"] = json!(code);
+    }
+    let body = json!({
+        "event_id": event_id(),
+        "timestamp": Utc::now().to_rfc3339(),
+        "platform": "other",
+        "logger": "scribe",
+        "level": if event.panic { "fatal" } else { "error" },
+        "release": concat!("scribe@", env!("CARGO_PKG_VERSION")),
+        "environment": config.environment,
+        "server_name": state.config.hostname,
+        "message": { "formatted": event.message },
+        "request": {
+            "method": event.method,
+            "url": format!("{}{}", state.config.hostname.trim_end_matches('/'), event.path),
+        },
+        "tags": tags,
+    });
+    let request = state
+        .http
+        .post(&dsn.store_url)
+        .header("X-Sentry-Auth", dsn.auth_header())
+        .json(&body);
+    match state.http.send(request).await {
+        Ok(response) if response.status().is_success() => {}
+        Ok(response) => warn!("Error report was refused: {}", response.status()),
+        Err(e) => warn!("Failed to send error report: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dsns() {
+        let dsn = Dsn::parse("https://abc123@o1.ingest.example.com/42").unwrap();
+        assert_eq!(dsn.store_url, "https://o1.ingest.example.com/api/42/store/");
+        assert_eq!(dsn.public_key, "abc123");
+        assert_eq!(
+            Dsn::parse("http://key@localhost:9000/errors/7")
+                .unwrap()
+                .store_url,
+            "http://localhost:9000/errors/api/7/store/"
+        );
+        assert!(Dsn::parse("https://example.com/42").is_err());
+        assert!(Dsn::parse("https://key@example.com/").is_err());
+        assert!(Dsn::parse("not a dsn").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::db::DbPool;
+use crate::models::evergreen::EvergreenReminder;
+use crate::server::app::AppState;
+use crate::services::analytics_service::views_since;
+use crate::services::notification_service::notify_author;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use std::collections::HashSet;
+
+const REMINDER_COLUMNS: &str = "id, article_slug, views, status, created_at, decided_at";
+
+/// Picks the most viewed published articles of the last `lookback_days`
+/// that are older than `min_age_days` and were not picked within
+/// `cooldown_days`, and stores them as pending reminders. The author's
+/// `notify_webhook` is told about new picks.
+pub async fn suggest_evergreen_posts(state: &AppState) -> Result<Vec<EvergreenReminder>, String> {
+    let Some(crosspost) = &state.config.crosspost else {
+        return Err("cross-posting is not configured".to_string());
+    };
+    let settings = &crosspost.evergreen;
+    let now = Utc::now();
+    let since = (now - Duration::days(settings.lookback_days.into())).date_naive();
+    let views = views_since(&state.db, since)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let recent: HashSet<String> = sqlx::query_scalar(
+        "SELECT article_slug FROM evergreen_reminders WHERE created_at > datetime('now', ?)",
+    )
+    .bind(format!("-{} days", settings.cooldown_days))
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .collect();
+    let old_enough = now - Duration::days(settings.min_age_days.into());
+    let picks: Vec<(String, i64)> = {
+        let store = state.store.read().await;
+        views
+            .into_iter()
+            .filter(|(slug, _)| {
+                !recent.contains(slug)
+                    && store
+                        .get_by_slug(slug)
+                        .is_some_and(|a| a.metadata.is_published() && a.metadata.date <= old_enough)
+            })
+            .take(settings.picks)
+            .collect()
+    };
+
+    let mut reminders = Vec::new();
+    for (slug, views) in picks {
+        let reminder = sqlx::query_as(&format!(
+            "INSERT INTO evergreen_reminders (article_slug, views) VALUES (?, ?) RETURNING {}",
+            REMINDER_COLUMNS
+        ))
+        .bind(&slug)
+        .bind(views)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        reminders.push(reminder);
+    }
+    if !reminders.is_empty() {
+        notify_author(
+            state,
+            &json!({ "event": "evergreen_reminders", "reminders": reminders }),
+        )
+        .await;
+    }
+    Ok(reminders)
+}
+
+pub async fn pending_reminders(pool: &DbPool) -> Result<Vec<EvergreenReminder>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM evergreen_reminders WHERE status = 'pending' ORDER BY id",
+        REMINDER_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Moves a pending reminder to `status` and returns it. `None` when it
+/// does not exist or was already decided.
+pub async fn decide_reminder(
+    pool: &DbPool,
+    id: i64,
+    status: &str,
+) -> Result<Option<EvergreenReminder>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "UPDATE evergreen_reminders SET status = ?, decided_at = CURRENT_TIMESTAMP \
+         WHERE id = ? AND status = 'pending' RETURNING {}",
+        REMINDER_COLUMNS
+    ))
+    .bind(status)
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,350 @@
+use crate::db::DbPool;
+use crate::models::article::Article;
+use crate::models::fediverse::{FediverseReplies, FediverseReply};
+use crate::server::app::AppState;
+use crate::services::comment_service::{current_timestamp, normalize_timestamp, sanitize_html};
+use crate::services::crosspost_service::MASTODON;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Threads fetched more recently than this are served as stored.
+pub const REFRESH_AFTER_SECS: i64 = 15 * 60;
+
+#[derive(Deserialize)]
+struct Context {
+    descendants: Vec<Status>,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    id: String,
+    url: Option<String>,
+    uri: String,
+    in_reply_to_id: Option<String>,
+    visibility: String,
+    content: String,
+    created_at: String,
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    acct: String,
+    display_name: String,
+    url: String,
+    avatar: Option<String>,
+}
+
+/// Tracks the rate limits Mastodon instances announce in their response
+/// headers, so threads on an instance that is out of requests are left
+/// alone until its limit resets.
+#[derive(Default)]
+pub struct MastodonRateLimits {
+    blocked: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MastodonRateLimits {
+    /// Updates the limit of `instance` from a response. A `429` without a
+    /// reset time backs off for five minutes.
+    pub fn record(
+        &self,
+        instance: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        now: DateTime<Utc>,
+    ) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let reset = header("x-ratelimit-reset")
+            .and_then(|v| DateTime::parse_from_rfc3339(v.trim()).ok())
+            .map(|at| at.with_timezone(&Utc));
+        let exhausted =
+            header("x-ratelimit-remaining").and_then(|v| v.trim().parse::<u64>().ok()) == Some(0);
+        let until = if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = header(RETRY_AFTER.as_str())
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .map(|secs| now + Duration::seconds(secs));
+            Some(retry_after.or(reset).unwrap_or(now + Duration::minutes(5)))
+        } else if exhausted {
+            reset
+        } else {
+            None
+        };
+        let mut blocked = self.blocked.lock().unwrap();
+        match until {
+            Some(until) => blocked.insert(instance.to_string(), until),
+            None => blocked.remove(instance),
+        };
+    }
+
+    /// When `instance` may be asked again, if it is out of requests.
+    pub fn blocked_until(&self, instance: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.blocked
+            .lock()
+            .unwrap()
+            .get(instance)
+            .copied()
+            .filter(|until| *until > now)
+    }
+}
+
+/// The instance of a Mastodon status and the API URL of its thread, from
+/// the status URL, e.g. `https://mastodon.social/@blog/109…`.
+fn context_url(post_url: &str) -> Option<(String, Url)> {
+    let url = Url::parse(post_url).ok()?;
+    let id = url.path_segments()?.rfind(|s| !s.is_empty())?;
+    if !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let api = url.join(&format!("/api/v1/statuses/{}/context", id)).ok()?;
+    Some((url.origin().ascii_serialization(), api))
+}
+
+/// Fetches the public replies under `post_url`. `Ok(None)` when its
+/// instance is rate limited.
+async fn fetch_thread(
+    state: &AppState,
+    post_url: &str,
+) -> Result<Option<Vec<FediverseReply>>, String> {
+    let (instance, api) = context_url(post_url)
+        .ok_or_else(|| format!("{} is not the URL of a Mastodon status", post_url))?;
+    let now = Utc::now();
+    if state
+        .mastodon_rate_limits
+        .blocked_until(&instance, now)
+        .is_some()
+    {
+        return Ok(None);
+    }
+    let response = state
+        .http
+        .send(state.http.get(api))
+        .await
+        .map_err(|e| e.to_string())?;
+    state
+        .mastodon_rate_limits
+        .record(&instance, response.status(), response.headers(), now);
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Ok(None);
+    }
+    let context: Context = response
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let replies = context
+        .descendants
+        .into_iter()
+        .filter(|status| matches!(status.visibility.as_str(), "public" | "unlisted"))
+        .map(|status| FediverseReply {
+            id: status.id,
+            url: status.url.unwrap_or(status.uri),
+            in_reply_to_id: status.in_reply_to_id,
+            author_name: if status.account.display_name.trim().is_empty() {
+                status.account.acct.clone()
+            } else {
+                status.account.display_name
+            },
+            author_acct: status.account.acct,
+            author_url: status.account.url,
+            author_avatar: status.account.avatar,
+            content: sanitize_html(&status.content),
+            created_at: normalize_timestamp(&status.created_at).unwrap_or(status.created_at),
+        })
+        .collect();
+    Ok(Some(replies))
+}
+
+/// Replaces the stored replies of `slug`, so deleted replies disappear.
+async fn store_thread(
+    pool: &DbPool,
+    slug: &str,
+    post_url: &str,
+    replies: &[FediverseReply],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM fediverse_replies WHERE article_slug = ?")
+        .bind(slug)
+        .execute(&mut *tx)
+        .await?;
+    for reply in replies {
+        sqlx::query(
+            "INSERT OR REPLACE INTO fediverse_replies (article_slug, id, url, in_reply_to_id, \
+             author_acct, author_name, author_url, author_avatar, content, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(slug)
+        .bind(&reply.id)
+        .bind(&reply.url)
+        .bind(&reply.in_reply_to_id)
+        .bind(&reply.author_acct)
+        .bind(&reply.author_name)
+        .bind(&reply.author_url)
+        .bind(&reply.author_avatar)
+        .bind(&reply.content)
+        .bind(&reply.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query(
+        "INSERT INTO fediverse_threads (article_slug, post_url, fetched_at) VALUES (?, ?, ?) \
+         ON CONFLICT(article_slug) DO UPDATE SET post_url = excluded.post_url, \
+         fetched_at = excluded.fetched_at",
+    )
+    .bind(slug)
+    .bind(post_url)
+    .bind(current_timestamp())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Fetches and stores the thread of `slug`. `Ok(false)` when it was
+/// skipped because its instance is rate limited.
+async fn refresh_thread(state: &AppState, slug: &str, post_url: &str) -> Result<bool, String> {
+    let Some(replies) = fetch_thread(state, post_url).await? else {
+        return Ok(false);
+    };
+    store_thread(&state.db, slug, post_url, &replies)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Refreshes the Mastodon threads of all published articles, for the
+/// `fetch_fediverse_replies` job. Returns how many were refreshed;
+/// threads on rate-limited instances wait for the next run.
+pub async fn refresh_fediverse_replies(state: &AppState) -> Result<usize, String> {
+    let threads: Vec<(String, String)> = {
+        let store = state.store.read().await;
+        store
+            .query(|a| a.metadata.is_published(), 0, usize::MAX)
+            .filter_map(|a| {
+                let post = a.metadata.syndication.get(MASTODON)?;
+                Some((a.slug.clone(), post.clone()))
+            })
+            .collect()
+    };
+    let mut refreshed = 0;
+    let mut failures = Vec::new();
+    for (slug, post_url) in &threads {
+        match refresh_thread(state, slug, post_url).await {
+            Ok(true) => refreshed += 1,
+            Ok(false) => {}
+            Err(e) => failures.push(format!("{}: {}", slug, e)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(refreshed)
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+/// The stored replies to the Mastodon post of `article`. A thread that
+/// was never fetched, or not in the last [`REFRESH_AFTER_SECS`], is
+/// fetched first; if that fails, the stored replies are served.
+pub async fn fediverse_replies(
+    state: &AppState,
+    article: &Article,
+) -> Result<FediverseReplies, sqlx::Error> {
+    let Some(post_url) = article.metadata.syndication.get(MASTODON) else {
+        return Ok(FediverseReplies {
+            post_url: None,
+            fetched_at: None,
+            replies: Vec::new(),
+        });
+    };
+    let fresh: Option<bool> = sqlx::query_scalar(
+        "SELECT fetched_at > datetime('now', ?) FROM fediverse_threads \
+         WHERE article_slug = ? AND post_url = ?",
+    )
+    .bind(format!("-{} seconds", REFRESH_AFTER_SECS))
+    .bind(&article.slug)
+    .bind(post_url)
+    .fetch_optional(&state.db)
+    .await?;
+    if fresh != Some(true)
+        && let Err(e) = refresh_thread(state, &article.slug, post_url).await
+    {
+        warn!("Failed to fetch the replies of {}: {}", article.slug, e);
+    }
+
+    let fetched_at = sqlx::query_scalar(
+        "SELECT fetched_at FROM fediverse_threads WHERE article_slug = ? AND post_url = ?",
+    )
+    .bind(&article.slug)
+    .bind(post_url)
+    .fetch_optional(&state.db)
+    .await?;
+    let replies = sqlx::query_as(
+        "SELECT id, url, in_reply_to_id, author_acct, author_name, author_url, author_avatar, \
+         content, created_at FROM fediverse_replies WHERE article_slug = ? \
+         ORDER BY created_at, id",
+    )
+    .bind(&article.slug)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(FediverseReplies {
+        post_url: Some(post_url.clone()),
+        fetched_at,
+        replies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn finds_the_thread_of_a_status_url() {
+        let (instance, api) = context_url("https://mastodon.social/@blog/109876").unwrap();
+        assert_eq!(instance, "https://mastodon.social");
+        assert_eq!(
+            api.as_str(),
+            "https://mastodon.social/api/v1/statuses/109876/context"
+        );
+        assert!(context_url("https://mastodon.social/@blog").is_none());
+    }
+
+    #[test]
+    fn backs_off_instances_until_their_limit_resets() {
+        let limits = MastodonRateLimits::default();
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let instance = "https://mastodon.social";
+
+        let spent = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "2023-11-14T22:16:40Z"),
+        ]);
+        limits.record(instance, StatusCode::OK, &spent, now);
+        let reset = now + Duration::seconds(200);
+        assert_eq!(limits.blocked_until(instance, now), Some(reset));
+        assert_eq!(limits.blocked_until(instance, reset), None);
+        assert_eq!(limits.blocked_until("https://other.example", now), None);
+
+        limits.record(instance, StatusCode::OK, &headers(&[]), now);
+        assert_eq!(limits.blocked_until(instance, now), None);
+
+        limits.record(instance, StatusCode::TOO_MANY_REQUESTS, &headers(&[]), now);
+        assert_eq!(
+            limits.blocked_until(instance, now),
+            Some(now + Duration::minutes(5))
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::services::secret_box::{OpenError, SecretBox};
+use thiserror::Error;
+
+/// Environment variable holding the 32-byte database key as 64 hex
+/// characters.
+pub const DATABASE_ENCRYPTION_KEY: &str = "DATABASE_ENCRYPTION_KEY";
+
+const PREFIX: &str = "enc1:";
+
+#[derive(Error, Debug)]
+pub enum FieldCryptoError {
+    #[error("Database key must be 64 hex characters")]
+    InvalidKey,
+    #[error("Stored value is not a valid encrypted field")]
+    Malformed,
+    #[error("Stored value is encrypted but {DATABASE_ENCRYPTION_KEY} is not set")]
+    MissingKey,
+    #[error("Stored value could not be decrypted with the configured key")]
+    Decrypt,
+}
+
+/// XChaCha20-Poly1305 cipher for personal data kept in SQLite, so a copy
+/// of the database file on shared hosting does not reveal it. Sealed values
+/// are stored as `enc1:` followed by the hex of nonce plus ciphertext;
+/// values without the prefix were written before a key was configured and
+/// are read as they are.
+#[derive(Clone)]
+pub struct FieldCipher {
+    secret_box: SecretBox,
+}
+
+impl FieldCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            secret_box: SecretBox::new(key),
+        }
+    }
+
+    pub fn from_hex(key: &str) -> Result<Self, FieldCryptoError> {
+        SecretBox::from_hex(key)
+            .map(|secret_box| Self { secret_box })
+            .map_err(|_| FieldCryptoError::InvalidKey)
+    }
+
+    /// Reads the key from `DATABASE_ENCRYPTION_KEY`. `Ok(None)` when unset.
+    pub fn from_env() -> Result<Option<Self>, FieldCryptoError> {
+        SecretBox::from_env(DATABASE_ENCRYPTION_KEY)
+            .map(|secret_box| secret_box.map(|secret_box| Self { secret_box }))
+            .map_err(|_| FieldCryptoError::InvalidKey)
+    }
+
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(PREFIX)
+    }
+
+    pub fn seal(&self, plaintext: &str) -> String {
+        format!("{}{}", PREFIX, hex::encode(self.secret_box.seal(plaintext)))
+    }
+
+    pub fn open(&self, value: &str) -> Result<String, FieldCryptoError> {
+        let Some(encoded) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let payload = hex::decode(encoded).map_err(|_| FieldCryptoError::Malformed)?;
+        self.secret_box.open(&payload).map_err(|e| match e {
+            OpenError::Malformed => FieldCryptoError::Malformed,
+            OpenError::Decrypt => FieldCryptoError::Decrypt,
+        })
+    }
+}
+
+/// Seals `value` when a cipher is configured. Values are sealed even when
+/// they happen to start with the sealed prefix, so they read back as
+/// written.
+pub fn seal_field(cipher: Option<&FieldCipher>, value: Option<&str>) -> Option<String> {
+    let value = value?;
+    Some(match cipher {
+        Some(cipher) => cipher.seal(value),
+        None => value.to_string(),
+    })
+}
+
+/// Opens a value read from the database. Sealed values need a cipher.
+pub fn open_field(
+    cipher: Option<&FieldCipher>,
+    value: Option<String>,
+) -> Result<Option<String>, FieldCryptoError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match cipher {
+        Some(cipher) => cipher.open(&value).map(Some),
+        None if FieldCipher::is_sealed(&value) => Err(FieldCryptoError::MissingKey),
+        None => Ok(Some(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> FieldCipher {
+        FieldCipher::new(&[7; 32])
+    }
+
+    #[test]
+    fn round_trips_and_reads_plain_values() {
+        let sealed = seal_field(Some(&cipher()), Some("Ada Lovelace")).unwrap();
+        assert!(FieldCipher::is_sealed(&sealed));
+        assert!(!sealed.contains("Ada"));
+        assert_eq!(
+            open_field(Some(&cipher()), Some(sealed.clone())).unwrap(),
+            Some("Ada Lovelace".to_string())
+        );
+        assert_eq!(
+            open_field(Some(&cipher()), Some("plain".to_string())).unwrap(),
+            Some("plain".to_string())
+        );
+        assert!(matches!(
+            open_field(Some(&FieldCipher::new(&[8; 32])), Some(sealed.clone())),
+            Err(FieldCryptoError::Decrypt)
+        ));
+        assert!(matches!(
+            open_field(None, Some(sealed)),
+            Err(FieldCryptoError::MissingKey)
+        ));
+        // Values looking sealed are sealed again rather than stored as is.
+        let lookalike = seal_field(Some(&cipher()), Some("enc1:x")).unwrap();
+        assert_eq!(
+            open_field(Some(&cipher()), Some(lookalike)).unwrap(),
+            Some("enc1:x".to_string())
+        );
+        assert!(matches!(
+            FieldCipher::from_hex("abcd"),
+            Err(FieldCryptoError::InvalidKey)
+        ));
+    }
+}
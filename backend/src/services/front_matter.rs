@@ -0,0 +1,341 @@
+use crate::models::article::Metadata;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Keys with the older name they replace, which files may still use. A
+/// changed value is written over the older entry.
+const RENAMED_KEYS: &[(&str, &str)] = &[("status", "draft")];
+
+/// File in a category directory holding front matter defaults for the
+/// articles below it.
+pub const CATEGORY_DEFAULTS_FILE: &str = "_index.yaml";
+
+/// The defaults files that apply to the article at `path`, one per
+/// directory between `root` and the article, outermost first. Files that
+/// do not exist yet are listed too, so creating one can be noticed.
+pub fn category_defaults_files(root: &Path, path: &Path) -> Vec<PathBuf> {
+    let Some(relative) = path.parent().and_then(|p| p.strip_prefix(root).ok()) else {
+        return Vec::new();
+    };
+    let mut dir = root.to_path_buf();
+    relative
+        .components()
+        .map(|component| {
+            dir.push(component);
+            dir.join(CATEGORY_DEFAULTS_FILE)
+        })
+        .collect()
+}
+
+/// Front matter defaults for the article at `path`, keys in inner
+/// categories overriding outer ones. Unreadable files are skipped with a
+/// warning.
+pub fn category_defaults(root: &Path, path: &Path) -> Mapping {
+    let mut defaults = Mapping::new();
+    for file in category_defaults_files(root, path) {
+        let Ok(text) = fs::read_to_string(&file) else {
+            continue;
+        };
+        match serde_yaml::from_str::<Value>(&text) {
+            Ok(Value::Mapping(mapping)) => defaults.extend(mapping),
+            Ok(Value::Null) => {}
+            Ok(_) => tracing::warn!("Ignoring {}: not a mapping", file.display()),
+            Err(e) => tracing::warn!("Ignoring {}: {}", file.display(), e),
+        }
+    }
+    defaults
+}
+
+/// `front_matter` with each key of `defaults` it does not set, under its
+/// current or older name.
+pub fn with_defaults(defaults: &Mapping, front_matter: Value) -> Value {
+    let Value::Mapping(own) = front_matter else {
+        return front_matter;
+    };
+    let mut merged = Mapping::new();
+    for (key, value) in defaults {
+        let set = key.as_str().is_some_and(|key| {
+            own.contains_key(key)
+                || RENAMED_KEYS
+                    .iter()
+                    .filter(|(new, old)| *new == key || *old == key)
+                    .any(|(new, old)| own.contains_key(*new) || own.contains_key(*old))
+        });
+        if !set {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    merged.extend(own);
+    Value::Mapping(merged)
+}
+
+/// Returns the YAML between the `---` delimiters at the top of `text`.
+pub fn front_matter_yaml(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+    if rest.starts_with("---") {
+        return Some("");
+    }
+    let end = rest.find("\n---")?;
+    Some(&rest[..end + 1])
+}
+
+/// A top-level `key:` entry of a YAML block, as a range of lines.
+struct Entry {
+    key: String,
+    start: usize,
+    end: usize,
+}
+
+fn top_level_key(line: &str) -> Option<String> {
+    if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-') {
+        return None;
+    }
+    let (key, _) = line.split_once(':')?;
+    Some(
+        key.trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string(),
+    )
+}
+
+/// Splits `lines` into top-level entries. An entry runs over the indented
+/// lines and block sequence items after its key; trailing blank lines and
+/// comments between entries belong to none.
+fn entries(lines: &[&str]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(key) = top_level_key(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let start = i;
+        let mut end = i + 1;
+        let mut j = i + 1;
+        while j < lines.len() {
+            let line = lines[j];
+            if line.trim().is_empty() {
+                j += 1;
+                continue;
+            }
+            if !(line.starts_with(char::is_whitespace) || line.starts_with('-')) {
+                break;
+            }
+            j += 1;
+            end = j;
+        }
+        entries.push(Entry { key, start, end });
+        i = end;
+    }
+    entries
+}
+
+fn render_entry(key: &str, value: &Value) -> Result<String, serde_yaml::Error> {
+    let mut entry = Mapping::new();
+    entry.insert(Value::String(key.to_string()), value.clone());
+    serde_yaml::to_string(&entry)
+}
+
+fn as_mapping(value: Value) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping,
+        _ => Mapping::new(),
+    }
+}
+
+/// Rewrites `original`, the front matter YAML of an article, to hold
+/// `metadata`. Only keys whose values changed are replaced, added or
+/// removed; every other line, including comments, key order and quoting,
+/// is kept. Keys unknown to [`Metadata`] are left alone. Values still
+/// matching the category `defaults` are not written out. Falls back to a
+/// fresh serialization when `original` is not valid article metadata.
+pub fn patch_front_matter(
+    original: &str,
+    metadata: &Metadata,
+    defaults: &Mapping,
+) -> Result<String, serde_yaml::Error> {
+    let new = as_mapping(serde_yaml::to_value(metadata)?);
+    // Compare against the original as `Metadata` would write it, so values
+    // that are merely spelled differently do not count as changes.
+    let old = serde_yaml::from_str::<Value>(original)
+        .and_then(|own| serde_yaml::from_value::<Metadata>(with_defaults(defaults, own)));
+    let old = match old {
+        Ok(old) => as_mapping(serde_yaml::to_value(&old)?),
+        Err(_) => return serde_yaml::to_string(metadata),
+    };
+
+    let lines: Vec<&str> = original.lines().collect();
+    let entries = entries(&lines);
+    let mut replaced: Vec<Option<String>> = vec![None; lines.len()];
+    let mut removed = vec![false; lines.len()];
+    let mut appended = String::new();
+
+    for (key, value) in &new {
+        let Some(key) = key.as_str() else { continue };
+        if old.get(key) == Some(value) {
+            continue;
+        }
+        let rendered = render_entry(key, value)?;
+        let found = entries
+            .iter()
+            .find(|entry| entry.key == key || RENAMED_KEYS.contains(&(key, entry.key.as_str())));
+        match found {
+            Some(entry) => {
+                replaced[entry.start] = Some(rendered);
+                removed[entry.start + 1..entry.end].fill(true);
+            }
+            None => appended.push_str(&rendered),
+        }
+    }
+    for key in old.keys().filter_map(Value::as_str) {
+        if new.contains_key(key) {
+            continue;
+        }
+        if let Some(entry) = entries.iter().find(|entry| entry.key == key) {
+            removed[entry.start..entry.end].fill(true);
+        }
+    }
+
+    let mut yaml = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(rendered) = &replaced[i] {
+            yaml.push_str(rendered);
+        } else if !removed[i] {
+            yaml.push_str(line);
+            yaml.push('\n');
+        }
+    }
+    yaml.push_str(&appended);
+    Ok(yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::ArticleStatus;
+
+    const HAND_WRITTEN: &str = "\
+# Written by hand
+title: 'Routing in Axum'
+date: 2024-05-01T00:00:00Z
+author: \"Scribe\"
+tags:
+  - rust   # the language
+  - web
+description: \"\"
+cover: images/routing.png
+draft: false
+";
+
+    fn metadata(original: &str) -> Metadata {
+        serde_yaml::from_str(original).unwrap()
+    }
+
+    #[test]
+    fn unchanged_metadata_keeps_the_block_as_written() {
+        let yaml =
+            patch_front_matter(HAND_WRITTEN, &metadata(HAND_WRITTEN), &Mapping::new()).unwrap();
+        assert_eq!(yaml, HAND_WRITTEN);
+    }
+
+    #[test]
+    fn only_changed_keys_are_rewritten() {
+        let mut changed = metadata(HAND_WRITTEN);
+        changed.tags = vec!["rust".to_string()];
+        changed.category = Some("guides".to_string());
+        changed.status = ArticleStatus::InReview;
+        let yaml = patch_front_matter(HAND_WRITTEN, &changed, &Mapping::new()).unwrap();
+        assert_eq!(
+            yaml,
+            "\
+# Written by hand
+title: 'Routing in Axum'
+date: 2024-05-01T00:00:00Z
+author: \"Scribe\"
+tags:
+- rust
+description: \"\"
+cover: images/routing.png
+status: in_review
+category: guides
+"
+        );
+        let parsed = metadata(&yaml);
+        assert_eq!(parsed.status, ArticleStatus::InReview);
+        assert_eq!(parsed.tags, ["rust"]);
+        assert_eq!(parsed.category.as_deref(), Some("guides"));
+    }
+
+    #[test]
+    fn draft_flags_are_read_as_statuses() {
+        let draft = HAND_WRITTEN.replace("draft: false", "draft: true");
+        assert_eq!(metadata(&draft).status, ArticleStatus::Draft);
+        assert_eq!(metadata(HAND_WRITTEN).status, ArticleStatus::Published);
+        let archived = HAND_WRITTEN.replace("draft: false", "status: archived");
+        assert_eq!(metadata(&archived).status, ArticleStatus::Archived);
+        let bare = HAND_WRITTEN.replace("draft: false\n", "");
+        assert_eq!(metadata(&bare).status, ArticleStatus::Published);
+    }
+
+    #[test]
+    fn keys_serialized_as_absent_are_removed() {
+        let original = format!("{}category: guides\n", HAND_WRITTEN);
+        let mut changed = metadata(&original);
+        changed.category = None;
+        let yaml = patch_front_matter(&original, &changed, &Mapping::new()).unwrap();
+        assert_eq!(yaml, HAND_WRITTEN);
+    }
+
+    #[test]
+    fn inherited_values_are_not_written_out() {
+        let defaults: Mapping = serde_yaml::from_str("author: Scribe\ntags: [rust]\n").unwrap();
+        let original = "title: Guide\ndate: 2024-05-01T00:00:00Z\ndraft: true\n";
+        let own = serde_yaml::from_str(original).unwrap();
+        let mut changed: Metadata = serde_yaml::from_value(with_defaults(&defaults, own)).unwrap();
+        assert_eq!(changed.author, "Scribe");
+        assert_eq!(changed.tags, ["rust"]);
+        changed.title = "Routing Guide".to_string();
+        let yaml = patch_front_matter(original, &changed, &defaults).unwrap();
+        assert_eq!(
+            yaml,
+            "title: Routing Guide\ndate: 2024-05-01T00:00:00Z\ndraft: true\n"
+        );
+    }
+
+    #[test]
+    fn own_keys_override_defaults() {
+        let defaults: Mapping = serde_yaml::from_str("status: draft\nauthor: Scribe\n").unwrap();
+        let own = serde_yaml::from_str("draft: false\nauthor: Ferris\n").unwrap();
+        assert_eq!(
+            with_defaults(&defaults, own),
+            serde_yaml::from_str::<Value>("draft: false\nauthor: Ferris\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn defaults_files_run_from_the_outermost_category() {
+        let root = Path::new("/content");
+        assert_eq!(
+            category_defaults_files(root, Path::new("/content/guides/web/routing.md")),
+            [
+                root.join("guides").join(CATEGORY_DEFAULTS_FILE),
+                root.join("guides/web").join(CATEGORY_DEFAULTS_FILE),
+            ]
+        );
+        assert!(category_defaults_files(root, Path::new("/content/post.md")).is_empty());
+    }
+
+    #[test]
+    fn extracts_the_yaml_block() {
+        assert_eq!(
+            front_matter_yaml("---\ntitle: x\n---\n\nBody"),
+            Some("title: x\n")
+        );
+        assert_eq!(front_matter_yaml("---\n---\nBody"), Some(""));
+        assert_eq!(front_matter_yaml("Body"), None);
+    }
+}
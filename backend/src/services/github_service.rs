@@ -0,0 +1,234 @@
+use crate::db::DbPool;
+use crate::models::github::GitHubRateLimitStatus;
+use crate::server::app::AppState;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, ETAG, HeaderMap, IF_NONE_MATCH, RETRY_AFTER};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+#[derive(Debug)]
+pub enum GitHubError {
+    /// The quota is used up and nothing is cached for the request.
+    RateLimited {
+        reset_at: DateTime<Utc>,
+    },
+    Request(String),
+}
+
+impl std::fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubError::RateLimited { reset_at } => write!(
+                f,
+                "GitHub rate limit exceeded until {}",
+                reset_at.to_rfc3339()
+            ),
+            GitHubError::Request(message) => write!(f, "GitHub request failed: {}", message),
+        }
+    }
+}
+
+/// Tracks GitHub's quota from response headers, so the instance stops
+/// asking once it is used up instead of getting itself banned.
+#[derive(Default)]
+pub struct GitHubRateLimit {
+    status: Mutex<GitHubRateLimitStatus>,
+}
+
+impl GitHubRateLimit {
+    pub fn status(&self) -> GitHubRateLimitStatus {
+        *self.status.lock().unwrap()
+    }
+
+    /// Updates the quota from a response. A `Retry-After` on a refused
+    /// request, GitHub's answer to bursts, counts as an exhausted quota
+    /// until then.
+    pub fn record(&self, status: StatusCode, headers: &HeaderMap, now: DateTime<Utc>) {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let mut current = self.status.lock().unwrap();
+        if let Some(limit) = number("x-ratelimit-limit") {
+            current.limit = Some(limit);
+        }
+        if let Some(remaining) = number("x-ratelimit-remaining") {
+            current.remaining = Some(remaining);
+        }
+        if let Some(reset) = number("x-ratelimit-reset") {
+            current.reset_at = DateTime::from_timestamp(reset as i64, 0);
+        }
+        let refused = matches!(
+            status,
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+        );
+        if refused && let Some(secs) = number(RETRY_AFTER.as_str()) {
+            current.remaining = Some(0);
+            current.reset_at = Some(now + Duration::seconds(secs as i64));
+        }
+    }
+
+    /// When requests may be made again, if the quota is used up.
+    pub fn blocked_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let current = self.status.lock().unwrap();
+        match (current.remaining, current.reset_at) {
+            (Some(0), Some(reset_at)) if reset_at > now => Some(reset_at),
+            _ => None,
+        }
+    }
+}
+
+/// Cache key of a request. Responses to authenticated requests differ per
+/// token, which is only kept as a hash.
+fn cache_key(path: &str, token: Option<&str>) -> String {
+    match token {
+        Some(token) => format!("{}#{}", path, hex::encode(Sha256::digest(token.as_bytes()))),
+        None => path.to_string(),
+    }
+}
+
+async fn cached(pool: &DbPool, key: &str) -> Result<Option<(String, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT etag, body FROM github_cache WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+}
+
+async fn store(pool: &DbPool, key: &str, etag: &str, body: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO github_cache (key, etag, body) VALUES (?, ?, ?) \
+         ON CONFLICT (key) DO UPDATE SET etag = excluded.etag, body = excluded.body, \
+         fetched_at = CURRENT_TIMESTAMP",
+    )
+    .bind(key)
+    .bind(etag)
+    .bind(body)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Body of `GET {github_api_url}{path}`, as `token` when given. Responses
+/// are cached with their ETag and revalidated with `If-None-Match`, which
+/// GitHub does not count against the quota. While the quota is used up the
+/// cached body is returned without asking.
+pub async fn get(state: &AppState, path: &str, token: Option<&str>) -> Result<String, GitHubError> {
+    let internal = |e: sqlx::Error| GitHubError::Request(e.to_string());
+    let key = cache_key(path, token);
+    let cached = cached(&state.db, &key).await.map_err(internal)?;
+    let limit = &state.github_rate_limit;
+    let rate_limited = |reset_at| match &cached {
+        Some((_, body)) => Ok(body.clone()),
+        None => Err(GitHubError::RateLimited { reset_at }),
+    };
+    if let Some(reset_at) = limit.blocked_until(Utc::now()) {
+        return rate_limited(reset_at);
+    }
+
+    let url = format!(
+        "{}{}",
+        state.config.github_api_url.trim_end_matches('/'),
+        path
+    );
+    let mut request = state
+        .http
+        .get(url)
+        .header(ACCEPT, "application/vnd.github+json");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    if let Some((etag, _)) = &cached {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let response = state
+        .http
+        .send(request)
+        .await
+        .map_err(|e| GitHubError::Request(e.to_string()))?;
+    let status = response.status();
+    limit.record(status, response.headers(), Utc::now());
+
+    if status == StatusCode::NOT_MODIFIED
+        && let Some((_, body)) = &cached
+    {
+        return Ok(body.clone());
+    }
+    if !status.is_success() {
+        if let Some(reset_at) = limit.blocked_until(Utc::now()) {
+            return rate_limited(reset_at);
+        }
+        return Err(GitHubError::Request(format!("GitHub answered {}", status)));
+    }
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| GitHubError::Request(e.to_string()))?;
+    if let Some(etag) = etag {
+        store(&state.db, &key, &etag, &body)
+            .await
+            .map_err(internal)?;
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn tracks_the_quota_and_backs_off_until_it_resets() {
+        let limit = GitHubRateLimit::default();
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(limit.status(), GitHubRateLimitStatus::default());
+
+        let ok = headers(&[
+            ("x-ratelimit-limit", "60"),
+            ("x-ratelimit-remaining", "1"),
+            ("x-ratelimit-reset", "1700000600"),
+        ]);
+        limit.record(StatusCode::OK, &ok, now);
+        assert_eq!(limit.status().remaining, Some(1));
+        assert_eq!(limit.blocked_until(now), None);
+
+        let exhausted = headers(&[("x-ratelimit-remaining", "0")]);
+        limit.record(StatusCode::FORBIDDEN, &exhausted, now);
+        let reset_at = DateTime::from_timestamp(1_700_000_600, 0).unwrap();
+        assert_eq!(limit.blocked_until(now), Some(reset_at));
+        assert_eq!(limit.blocked_until(reset_at), None);
+
+        // Secondary limits only say how long to wait.
+        let limit = GitHubRateLimit::default();
+        limit.record(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("retry-after", "30")]),
+            now,
+        );
+        assert_eq!(limit.blocked_until(now), Some(now + Duration::seconds(30)));
+    }
+
+    #[test]
+    fn tokens_are_only_kept_as_hashes() {
+        assert_eq!(cache_key("/users/octocat", None), "/users/octocat");
+        let key = cache_key("/user", Some("gho_secret"));
+        assert!(key.starts_with("/user#"));
+        assert!(!key.contains("gho_secret"));
+        assert_ne!(key, cache_key("/user", Some("gho_other")));
+    }
+}
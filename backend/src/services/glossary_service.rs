@@ -0,0 +1,173 @@
+use crate::handlers::error::LoadError;
+use crate::models::glossary::{GlossaryEntry, GlossaryMetadata};
+use crate::server::app::AppState;
+use gray_matter::Matter;
+use gray_matter::engine::YAML;
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+use regex::Regex;
+use serde_yaml::from_value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Every definition under `root`, one `{slug}.md` per term, sorted by
+/// term. Read on request like the taxonomy pages; the directory is
+/// optional.
+pub fn load_glossary(root: &Path) -> Result<Vec<GlossaryEntry>, LoadError> {
+    let dir = match fs::read_dir(root) {
+        Ok(dir) => dir,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = Vec::new();
+    for file in dir {
+        let path = file?.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let Some(slug) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            return Err(LoadError::InvalidFileName(
+                path.to_string_lossy().to_string(),
+            ));
+        };
+        let text = fs::read_to_string(&path)?;
+        let parsed = Matter::<YAML>::new()
+            .parse::<serde_yaml::Value>(&text)
+            .map_err(|e| {
+                LoadError::MatterParse(format!(
+                    "Failed to parse front matter in {}: {}",
+                    path.to_string_lossy(),
+                    e
+                ))
+            })?;
+        let metadata: GlossaryMetadata = match parsed.data {
+            Some(data) => from_value(data)?,
+            None => GlossaryMetadata::default(),
+        };
+        entries.push(GlossaryEntry {
+            slug: slug.to_string(),
+            term: metadata.term.unwrap_or_else(|| slug.to_string()),
+            aliases: metadata.aliases,
+            definition: parsed.content.trim().to_string(),
+        });
+    }
+    entries.sort_by_cached_key(|entry| entry.term.to_lowercase());
+    Ok(entries)
+}
+
+/// Turns the first occurrence of each glossary term in rendered text into
+/// a link to its definition. Terms match whole words, ignoring case.
+pub struct GlossaryLinker {
+    pattern: Regex,
+    /// Lowercased terms and aliases, by the entry they stand for.
+    targets: HashMap<String, usize>,
+    urls: Vec<String>,
+}
+
+impl GlossaryLinker {
+    /// `None` when there are no terms to link. `page_url` is the absolute
+    /// URL of the glossary page.
+    pub fn new(entries: &[GlossaryEntry], page_url: &str) -> Option<Self> {
+        let mut targets = HashMap::new();
+        let mut urls = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            urls.push(format!("{}#{}", page_url, entry.slug));
+            for name in std::iter::once(&entry.term).chain(&entry.aliases) {
+                let name = name.trim();
+                if !name.is_empty() {
+                    targets.entry(name.to_lowercase()).or_insert(i);
+                }
+            }
+        }
+        // Longest first, so "borrow checker" is linked rather than "borrow".
+        let mut names: Vec<&String> = targets.keys().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        let alternatives: Vec<String> = names.into_iter().map(|name| word(name)).collect();
+        if alternatives.is_empty() {
+            return None;
+        }
+        let pattern = Regex::new(&format!("(?i){}", alternatives.join("|"))).ok()?;
+        Some(Self {
+            pattern,
+            targets,
+            urls,
+        })
+    }
+
+    /// `text` with the terms not in `linked` yet turned into links, or
+    /// `None` when it has none. Linked entries are added to `linked`.
+    pub fn link<'a>(&self, text: &str, linked: &mut HashSet<usize>) -> Option<Vec<Event<'a>>> {
+        let mut events = Vec::new();
+        let mut rest = 0;
+        for found in self.pattern.find_iter(text) {
+            let Some(&entry) = self.targets.get(&found.as_str().to_lowercase()) else {
+                continue;
+            };
+            if !linked.insert(entry) {
+                continue;
+            }
+            if found.start() > rest {
+                events.push(text_event(&text[rest..found.start()]));
+            }
+            events.push(Event::Start(Tag::Link {
+                link_type: LinkType::Inline,
+                dest_url: CowStr::from(self.urls[entry].clone()),
+                title: CowStr::from(""),
+                id: CowStr::from(""),
+            }));
+            events.push(text_event(found.as_str()));
+            events.push(Event::End(TagEnd::Link));
+            rest = found.end();
+        }
+        if events.is_empty() {
+            return None;
+        }
+        if rest < text.len() {
+            events.push(text_event(&text[rest..]));
+        }
+        Some(events)
+    }
+}
+
+fn text_event<'a>(text: &str) -> Event<'a> {
+    Event::Text(CowStr::from(text.to_string()))
+}
+
+/// Pattern matching `name` as a whole word. Edges that are not word
+/// characters, as in `C++`, need no boundary.
+fn word(name: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    format!(
+        "{}{}{}",
+        if is_word(name.chars().next()) {
+            r"\b"
+        } else {
+            ""
+        },
+        regex::escape(name),
+        if is_word(name.chars().last()) {
+            r"\b"
+        } else {
+            ""
+        },
+    )
+}
+
+/// The linker for rendered articles, or `None` when `link_terms` is off or
+/// the glossary is empty. A glossary that fails to load is skipped.
+pub fn glossary_linker(state: &AppState) -> Option<GlossaryLinker> {
+    let glossary = &state.config.glossary;
+    if !glossary.link_terms {
+        return None;
+    }
+    let entries = load_glossary(&state.glossary_dir)
+        .inspect_err(|e| tracing::warn!("Failed to load the glossary: {}", e))
+        .ok()?;
+    let page_url = format!(
+        "{}{}",
+        state.config.hostname.trim_end_matches('/'),
+        glossary.page
+    );
+    GlossaryLinker::new(&entries, &page_url)
+}
@@ -0,0 +1,165 @@
+use crate::models::goal::{GoalKind, GoalProgress};
+use chrono::{Datelike, Days, NaiveDate};
+use std::collections::HashMap;
+
+/// A published article as far as goals are concerned: when it went out and
+/// how long it is.
+#[derive(Debug, Clone, Copy)]
+pub struct Publication {
+    pub date: NaiveDate,
+    pub words: usize,
+}
+
+fn period_start(kind: GoalKind, date: NaiveDate) -> NaiveDate {
+    match kind {
+        GoalKind::WordsPerWeek => date - Days::new(date.weekday().num_days_from_monday().into()),
+        GoalKind::PostsPerMonth => date.with_day(1).unwrap_or(date),
+    }
+}
+
+fn previous_period(kind: GoalKind, start: NaiveDate) -> NaiveDate {
+    match kind {
+        GoalKind::WordsPerWeek => start - Days::new(7),
+        GoalKind::PostsPerMonth => period_start(kind, start - Days::new(1)),
+    }
+}
+
+fn next_period(kind: GoalKind, start: NaiveDate) -> NaiveDate {
+    match kind {
+        GoalKind::WordsPerWeek => start + Days::new(7),
+        GoalKind::PostsPerMonth => period_start(kind, start + Days::new(31)),
+    }
+}
+
+/// Progress towards `target` in the period containing `today`, plus streaks
+/// of consecutive periods that met it.
+pub fn goal_progress(
+    kind: GoalKind,
+    target: usize,
+    publications: &[Publication],
+    today: NaiveDate,
+) -> GoalProgress {
+    let mut totals: HashMap<NaiveDate, usize> = HashMap::new();
+    for p in publications.iter().filter(|p| p.date <= today) {
+        let amount = match kind {
+            GoalKind::WordsPerWeek => p.words,
+            GoalKind::PostsPerMonth => 1,
+        };
+        *totals.entry(period_start(kind, p.date)).or_default() += amount;
+    }
+    let met = |start: NaiveDate| totals.get(&start).is_some_and(|&total| total >= target);
+
+    let current_start = period_start(kind, today);
+    let current = totals.get(&current_start).copied().unwrap_or(0);
+
+    let mut current_streak = 0;
+    let mut start = current_start;
+    if met(start) {
+        current_streak += 1;
+    }
+    loop {
+        start = previous_period(kind, start);
+        if !met(start) {
+            break;
+        }
+        current_streak += 1;
+    }
+
+    let mut longest_streak = 0;
+    let mut run = 0;
+    if let Some(&first) = totals.keys().min() {
+        let mut start = first;
+        while start <= current_start {
+            if met(start) {
+                run += 1;
+                longest_streak = longest_streak.max(run);
+            } else {
+                run = 0;
+            }
+            start = next_period(kind, start);
+        }
+    }
+
+    GoalProgress {
+        kind,
+        target,
+        period_start: current_start,
+        current,
+        percent: current * 100 / target,
+        current_streak,
+        longest_streak,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn posts(dates: &[NaiveDate]) -> Vec<Publication> {
+        dates
+            .iter()
+            .map(|&date| Publication { date, words: 500 })
+            .collect()
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2024-05-15 is a Wednesday.
+        let progress = goal_progress(
+            GoalKind::WordsPerWeek,
+            1000,
+            &posts(&[day(2024, 5, 13), day(2024, 5, 12)]),
+            day(2024, 5, 15),
+        );
+        assert_eq!(progress.period_start, day(2024, 5, 13));
+        assert_eq!(progress.current, 500);
+        assert_eq!(progress.percent, 50);
+    }
+
+    #[test]
+    fn unmet_current_period_keeps_the_streak() {
+        let publications = posts(&[
+            day(2024, 1, 3),
+            day(2024, 3, 1),
+            day(2024, 4, 2),
+            day(2024, 5, 20),
+            // Future-dated posts are scheduled, not published.
+            day(2024, 6, 20),
+        ]);
+
+        let progress = goal_progress(GoalKind::PostsPerMonth, 1, &publications, day(2024, 6, 10));
+        assert_eq!(progress.current, 0);
+        assert_eq!(progress.current_streak, 3);
+        assert_eq!(progress.longest_streak, 3);
+
+        // Once the June post is out, it extends the streak.
+        let progress = goal_progress(GoalKind::PostsPerMonth, 1, &publications, day(2024, 7, 1));
+        assert_eq!(progress.current_streak, 4);
+        assert_eq!(progress.longest_streak, 4);
+
+        let progress = goal_progress(GoalKind::PostsPerMonth, 1, &publications, day(2024, 8, 1));
+        assert_eq!(progress.current_streak, 0);
+        assert_eq!(progress.longest_streak, 4);
+    }
+
+    #[test]
+    fn met_current_period_extends_the_streak() {
+        let publications = posts(&[day(2024, 5, 6), day(2024, 5, 8), day(2024, 5, 14)]);
+        let progress = goal_progress(GoalKind::WordsPerWeek, 500, &publications, day(2024, 5, 14));
+        assert_eq!(progress.current_streak, 2);
+        assert_eq!(progress.percent, 100);
+
+        let progress = goal_progress(
+            GoalKind::WordsPerWeek,
+            1000,
+            &publications,
+            day(2024, 5, 14),
+        );
+        assert_eq!(progress.current_streak, 1);
+        assert_eq!(progress.longest_streak, 1);
+    }
+}
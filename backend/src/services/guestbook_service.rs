@@ -0,0 +1,99 @@
+use crate::db::DbPool;
+use crate::models::guestbook::GuestbookEntry;
+
+pub async fn add_entry(
+    pool: &DbPool,
+    author_github_id: u64,
+    author_login: &str,
+    content: &str,
+    approved: bool,
+) -> Result<GuestbookEntry, sqlx::Error> {
+    sqlx::query_as(
+        "INSERT INTO guestbook_entries (author_github_id, author_login, content, approved) \
+         VALUES (?, ?, ?, ?) \
+         RETURNING id, author_github_id, author_login, content, approved, created_at",
+    )
+    .bind(author_github_id as i64)
+    .bind(author_login)
+    .bind(content)
+    .bind(approved)
+    .fetch_one(pool)
+    .await
+}
+
+/// Lists entries newest first together with the total number of matching
+/// entries. `approved` selects published entries or the moderation queue.
+pub async fn list_entries(
+    pool: &DbPool,
+    approved: bool,
+    offset: usize,
+    limit: usize,
+) -> Result<(Vec<GuestbookEntry>, usize), sqlx::Error> {
+    let entries = sqlx::query_as(
+        "SELECT id, author_github_id, author_login, content, approved, created_at \
+         FROM guestbook_entries WHERE approved = ? ORDER BY id DESC LIMIT ? OFFSET ?",
+    )
+    .bind(approved)
+    .bind(limit as i64)
+    .bind(offset as i64)
+    .fetch_all(pool)
+    .await?;
+    let total = count_entries(pool, approved).await?;
+    Ok((entries, total))
+}
+
+pub async fn count_entries(pool: &DbPool, approved: bool) -> Result<usize, sqlx::Error> {
+    let total: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM guestbook_entries WHERE approved = ?")
+            .bind(approved)
+            .fetch_one(pool)
+            .await?;
+    Ok(total as usize)
+}
+
+pub async fn approve_entry(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query("UPDATE guestbook_entries SET approved = 1 WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(updated > 0)
+}
+
+pub async fn delete_entry(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM guestbook_entries WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn pending_entries_are_hidden_until_approved() {
+        let pool = memory_db().await;
+        add_entry(&pool, 1, "alice", "<p>hi</p>", true)
+            .await
+            .unwrap();
+        let pending = add_entry(&pool, 2, "bob", "<p>hello</p>", false)
+            .await
+            .unwrap();
+
+        let (public, total) = list_entries(&pool, true, 0, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(public[0].author_login, "alice");
+
+        assert!(approve_entry(&pool, pending.id).await.unwrap());
+        let (public, total) = list_entries(&pool, true, 0, 10).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(public[0].author_login, "bob");
+
+        assert!(delete_entry(&pool, pending.id).await.unwrap());
+        assert!(!delete_entry(&pool, pending.id).await.unwrap());
+    }
+}
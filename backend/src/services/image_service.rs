@@ -0,0 +1,124 @@
+use crate::models::asset::ImageInfo;
+use crate::services::asset_service::asset_path;
+use crate::services::render_service::is_local_path;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Components of the placeholders, across and down.
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+/// Images are scaled down to fit this square before hashing; the
+/// placeholder is a blur anyway.
+const BLURHASH_SAMPLE: u32 = 32;
+
+/// The file behind an image source as written in an article: a path next
+/// to the article in `article_dir`, or an uploaded asset. `None` for remote
+/// images and anything outside those directories.
+pub fn image_file(source: &str, article_dir: &Path, asset_dir: &Path) -> Option<PathBuf> {
+    match source.strip_prefix("/api/assets/") {
+        Some(name) => asset_path(asset_dir, name),
+        None => is_local_path(source).then(|| article_dir.join(source)),
+    }
+}
+
+/// Size and placeholder of a raster image. `None` for anything that does
+/// not decode, SVGs included.
+pub fn image_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let sample = image.thumbnail(BLURHASH_SAMPLE, BLURHASH_SAMPLE).to_rgba8();
+    let (x, y) = BLURHASH_COMPONENTS;
+    let blurhash = blurhash::encode(x, y, sample.width(), sample.height(), sample.as_raw()).ok();
+    Some(ImageInfo {
+        width: image.width(),
+        height: image.height(),
+        blurhash,
+    })
+}
+
+/// Details of a file as of its modification time.
+type CachedInfo = (SystemTime, Option<ImageInfo>);
+
+/// Image details by file, computed the first time an image is referenced
+/// and again only once the file changes.
+#[derive(Clone, Default)]
+pub struct ImageInfoCache {
+    entries: Arc<Mutex<HashMap<PathBuf, CachedInfo>>>,
+}
+
+impl ImageInfoCache {
+    /// Reads and decodes the file on a miss, so call it off the async
+    /// runtime.
+    pub fn get(&self, path: &Path) -> Option<ImageInfo> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        if let Some((at, info)) = self.entries.lock().unwrap().get(path)
+            && *at == modified
+        {
+            return info.clone();
+        }
+        let info = fs::read(path).ok().and_then(|bytes| image_info(&bytes));
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (modified, info.clone()));
+        info
+    }
+
+    /// Details of the images behind `sources`, by source as written.
+    /// Remote and missing images are left out.
+    pub fn for_sources(
+        &self,
+        sources: impl IntoIterator<Item = String>,
+        article_dir: &Path,
+        asset_dir: &Path,
+    ) -> BTreeMap<String, ImageInfo> {
+        sources
+            .into_iter()
+            .filter_map(|source| {
+                let info = self.get(&image_file(&source, article_dir, asset_dir)?)?;
+                Some((source, info))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::asset_service::store_asset;
+    use crate::services::render_service::image_sources;
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, _| Rgb([(x * 8) as u8, 90, 200]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn describes_local_images_and_assets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("img")).unwrap();
+        fs::write(dir.path().join("img/wide.png"), png(30, 10)).unwrap();
+        fs::write(dir.path().join("img/broken.png"), b"not an image").unwrap();
+        let asset_dir = dir.path().join("assets");
+        let asset = store_asset(&asset_dir, &png(8, 16), "png").unwrap();
+
+        let content = format!(
+            "![a](img/wide.png) ![b](img/broken.png) ![c]({}) ![d](https://example.com/x.png)",
+            asset.url
+        );
+        let images =
+            ImageInfoCache::default().for_sources(image_sources(&content), dir.path(), &asset_dir);
+        assert_eq!(images.len(), 2);
+        let wide = &images["img/wide.png"];
+        assert_eq!((wide.width, wide.height), (30, 10));
+        assert!(wide.blurhash.as_ref().is_some_and(|hash| !hash.is_empty()));
+        assert_eq!(images[&asset.url].height, 16);
+    }
+}
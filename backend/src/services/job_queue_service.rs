@@ -0,0 +1,146 @@
+use crate::db::DbPool;
+use crate::models::job::QueuedJob;
+
+/// Deliveries are dead-lettered after this many failed attempts.
+pub const MAX_ATTEMPTS: i64 = 8;
+const BASE_BACKOFF_SECONDS: i64 = 30;
+const MAX_BACKOFF_SECONDS: i64 = 6 * 60 * 60;
+
+const JOB_COLUMNS: &str =
+    "id, job_type, payload, attempts, next_retry_at, last_error, dead, created_at";
+
+/// Delay before the next attempt once `attempts` attempts have failed:
+/// 30s, 60s, 120s, ... capped at six hours.
+pub fn backoff_seconds(attempts: i64) -> i64 {
+    let exponent = (attempts.max(1) - 1).min(20) as u32;
+    (BASE_BACKOFF_SECONDS * 2_i64.pow(exponent)).min(MAX_BACKOFF_SECONDS)
+}
+
+pub async fn enqueue(
+    pool: &DbPool,
+    job_type: &str,
+    payload: &serde_json::Value,
+    now: i64,
+) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query_scalar(
+        "INSERT INTO job_queue (job_type, payload, next_retry_at) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(job_type)
+    .bind(payload.to_string())
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Live jobs whose retry time has passed, oldest first.
+pub async fn due_jobs(
+    pool: &DbPool,
+    now: i64,
+    limit: usize,
+) -> Result<Vec<QueuedJob>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM job_queue WHERE dead = 0 AND next_retry_at <= ? ORDER BY id LIMIT ?",
+        JOB_COLUMNS
+    ))
+    .bind(now)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn complete_job(pool: &DbPool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM job_queue WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Records a failed attempt, scheduling a retry with exponential backoff or
+/// moving the job to the dead-letter list once it runs out of attempts.
+/// Returns `true` when the job was dead-lettered.
+pub async fn fail_job(
+    pool: &DbPool,
+    job: &QueuedJob,
+    error: &str,
+    now: i64,
+) -> Result<bool, sqlx::Error> {
+    let attempts = job.attempts + 1;
+    let dead = attempts >= MAX_ATTEMPTS;
+    sqlx::query(
+        "UPDATE job_queue SET attempts = ?, next_retry_at = ?, last_error = ?, dead = ? WHERE id = ?",
+    )
+    .bind(attempts)
+    .bind(now + backoff_seconds(attempts))
+    .bind(error)
+    .bind(dead)
+    .bind(job.id)
+    .execute(pool)
+    .await?;
+    Ok(dead)
+}
+
+/// Dead-lettered jobs, newest first.
+pub async fn dead_letter_jobs(pool: &DbPool) -> Result<Vec<QueuedJob>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM job_queue WHERE dead = 1 ORDER BY id DESC",
+        JOB_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+    use serde_json::json;
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        assert_eq!(backoff_seconds(1), 30);
+        assert_eq!(backoff_seconds(2), 60);
+        assert_eq!(backoff_seconds(4), 240);
+        assert_eq!(backoff_seconds(50), MAX_BACKOFF_SECONDS);
+    }
+
+    #[tokio::test]
+    async fn failed_jobs_back_off_then_dead_letter() {
+        let pool = memory_db().await;
+        let id = enqueue(
+            &pool,
+            "webhook",
+            &json!({ "url": "https://example.com" }),
+            100,
+        )
+        .await
+        .unwrap();
+
+        assert!(due_jobs(&pool, 99, 10).await.unwrap().is_empty());
+        let job = due_jobs(&pool, 100, 10).await.unwrap().remove(0);
+        assert_eq!(job.id, id);
+
+        assert!(!fail_job(&pool, &job, "timeout", 100).await.unwrap());
+        assert!(due_jobs(&pool, 100, 10).await.unwrap().is_empty());
+        let mut job = due_jobs(&pool, 130, 10).await.unwrap().remove(0);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(job.last_error.as_deref(), Some("timeout"));
+
+        job.attempts = MAX_ATTEMPTS - 1;
+        assert!(fail_job(&pool, &job, "gone", 200).await.unwrap());
+        assert!(due_jobs(&pool, i64::MAX, 10).await.unwrap().is_empty());
+        let dead = dead_letter_jobs(&pool).await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert!(dead[0].dead);
+    }
+
+    #[tokio::test]
+    async fn completed_jobs_are_removed() {
+        let pool = memory_db().await;
+        let id = enqueue(&pool, "email", &json!({}), 0).await.unwrap();
+        complete_job(&pool, id).await.unwrap();
+        assert!(due_jobs(&pool, 0, 10).await.unwrap().is_empty());
+        assert!(dead_letter_jobs(&pool).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::config::HttpConfig;
+use crate::models::link_preview::LinkPreview;
+use regex::Regex;
+use reqwest::redirect::Policy;
+use reqwest::{StatusCode, Url, header};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::LazyLock;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Pages are read up to this many bytes; the metadata is in the head.
+const MAX_PAGE_BYTES: usize = 512 * 1024;
+const MAX_REDIRECTS: usize = 3;
+
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+static META: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<meta\s[^>]*>").unwrap());
+static ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([a-zA-Z:_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+#[derive(Error, Debug)]
+pub enum LinkPreviewError {
+    #[error("Only http and https URLs can be previewed")]
+    UnsupportedUrl,
+    #[error("{0} could not be resolved")]
+    Unresolvable(String),
+    #[error("{0} resolves to an address that is not public")]
+    BlockedAddress(String),
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Linked page answered {0}")]
+    Status(StatusCode),
+    #[error("Linked page redirected more than {MAX_REDIRECTS} times")]
+    TooManyRedirects,
+    #[error("Linked page is not HTML")]
+    NotHtml,
+}
+
+/// Whether `ip` is reachable on the public internet. Loopback, private,
+/// shared, link-local, multicast, documentation, reserved and unspecified
+/// addresses are not, nor are their IPv4-mapped forms.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || v4.is_unspecified()
+                || v4.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && (b == 18 || b == 19))
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let [first, second, ..] = v6.segments();
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && second == 0x0db8))
+        }
+    }
+}
+
+/// The address to connect to for `url`. Every address the host resolves
+/// to must be public, so a name cannot pair a public address with an
+/// internal one.
+async fn public_address(url: &Url) -> Result<SocketAddr, LinkPreviewError> {
+    let port = url
+        .port_or_known_default()
+        .ok_or(LinkPreviewError::UnsupportedUrl)?;
+    let host = url.host_str().ok_or(LinkPreviewError::UnsupportedUrl)?;
+    let addresses: Vec<SocketAddr> = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| LinkPreviewError::Unresolvable(host.to_string()))?
+            .collect(),
+    };
+    let host = host.to_string();
+    match addresses.first() {
+        None => Err(LinkPreviewError::Unresolvable(host)),
+        Some(_) if addresses.iter().any(|a| !is_public_ip(a.ip())) => {
+            Err(LinkPreviewError::BlockedAddress(host))
+        }
+        Some(&address) => Ok(address),
+    }
+}
+
+/// Fetches the title, description and preview image of the page at `url`.
+/// Each hop, redirects included, is resolved once, checked with
+/// [`is_public_ip`] and connected to at the checked address, so a DNS
+/// answer cannot change between the check and the request. For the same
+/// reason the `[http]` proxy is not used; its timeouts are.
+pub async fn fetch_link_preview(
+    config: &HttpConfig,
+    url: &str,
+) -> Result<LinkPreview, LinkPreviewError> {
+    let mut url = Url::parse(url).map_err(|_| LinkPreviewError::UnsupportedUrl)?;
+    for _ in 0..=MAX_REDIRECTS {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(LinkPreviewError::UnsupportedUrl);
+        }
+        let address = public_address(&url).await?;
+        let mut builder = reqwest::Client::builder()
+            .user_agent(concat!("scribe/", env!("CARGO_PKG_VERSION")))
+            .redirect(Policy::none())
+            .no_proxy()
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs));
+        if let Some(domain) = url.domain() {
+            builder = builder.resolve(domain, address);
+        }
+        let mut response = builder
+            .build()?
+            .get(url.clone())
+            .header(header::ACCEPT, "text/html")
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(LinkPreviewError::Status(status))?;
+            url = url
+                .join(location)
+                .map_err(|_| LinkPreviewError::UnsupportedUrl)?;
+            continue;
+        }
+        if !status.is_success() {
+            return Err(LinkPreviewError::Status(status));
+        }
+        let is_html = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ty| ty.contains("html"));
+        if !is_html {
+            return Err(LinkPreviewError::NotHtml);
+        }
+        let mut body = Vec::new();
+        while body.len() < MAX_PAGE_BYTES
+            && let Some(chunk) = response.chunk().await?
+        {
+            body.extend_from_slice(&chunk);
+        }
+        return Ok(parse_preview(&String::from_utf8_lossy(&body), &url));
+    }
+    Err(LinkPreviewError::TooManyRedirects)
+}
+
+/// Reads the Open Graph and Twitter card tags of a page, falling back to
+/// its `<title>`, `description` and `base`. URLs are made absolute.
+pub fn parse_preview(html: &str, base: &Url) -> LinkPreview {
+    let mut meta: HashMap<String, String> = HashMap::new();
+    for tag in META.find_iter(html) {
+        let attributes: HashMap<String, &str> = ATTRIBUTE
+            .captures_iter(tag.as_str())
+            .map(|c| {
+                let value = c.get(2).or(c.get(3)).map_or("", |m| m.as_str());
+                (c[1].to_ascii_lowercase(), value)
+            })
+            .collect();
+        let key = attributes
+            .get("property")
+            .or(attributes.get("name"))
+            .map(|key| key.to_ascii_lowercase());
+        if let (Some(key), Some(content)) = (key, attributes.get("content")) {
+            meta.entry(key).or_insert_with(|| decode_entities(content));
+        }
+    }
+    let find = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| meta.get(*key))
+            .map(|value| collapse_whitespace(value))
+            .filter(|value| !value.is_empty())
+    };
+    let title = find(&["og:title", "twitter:title"]).or_else(|| {
+        TITLE
+            .captures(html)
+            .map(|c| collapse_whitespace(&decode_entities(&c[1])))
+            .filter(|title| !title.is_empty())
+    });
+    let absolute = |keys: &[&str]| {
+        find(keys)
+            .and_then(|src| base.join(&src).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(String::from)
+    };
+    LinkPreview {
+        url: absolute(&["og:url"]).unwrap_or_else(|| base.to_string()),
+        site_name: find(&["og:site_name"]).or(base.host_str().map(String::from)),
+        title,
+        description: find(&["og:description", "twitter:description", "description"]),
+        image: absolute(&["og:image", "og:image:url", "twitter:image"]),
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decodes the character references found in titles and attributes.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_open_graph_tags_with_fallbacks() {
+        let base = Url::parse("https://example.com/posts/1").unwrap();
+        let html = r#"<html><head>
+            <title>  Fallback
+              title </title>
+            <meta property="og:title" content="Rust &amp; friends">
+            <meta name='description' content='A &#x27;short&#39; read'>
+            <meta property="og:image" content="/cover.png">
+            <meta property="og:url" content="/posts/rust">
+            </head></html>"#;
+        assert_eq!(
+            parse_preview(html, &base),
+            LinkPreview {
+                url: "https://example.com/posts/rust".to_string(),
+                site_name: Some("example.com".to_string()),
+                title: Some("Rust & friends".to_string()),
+                description: Some("A 'short' read".to_string()),
+                image: Some("https://example.com/cover.png".to_string()),
+            }
+        );
+
+        let html = "<title>Only &lt;a&gt; title</title><meta property=og:image content=x>";
+        let preview = parse_preview(html, &base);
+        assert_eq!(preview.title.as_deref(), Some("Only <a> title"));
+        assert_eq!(preview.image, None);
+    }
+
+    #[test]
+    fn only_public_addresses_are_fetched() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_internal_hosts() {
+        let config = HttpConfig::default();
+        for url in [
+            "http://127.0.0.1:9/",
+            "http://[::1]/",
+            "http://localhost/",
+            "file:///etc/passwd",
+        ] {
+            assert!(
+                matches!(
+                    fetch_link_preview(&config, url).await,
+                    Err(LinkPreviewError::BlockedAddress(_) | LinkPreviewError::UnsupportedUrl)
+                ),
+                "{}",
+                url
+            );
+        }
+    }
+}
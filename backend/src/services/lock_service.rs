@@ -0,0 +1,90 @@
+use crate::db::DbPool;
+use crate::models::lock::ArticleLock;
+
+const LOCK_COLUMNS: &str = "article_slug, locked_by, locked_until, locked_at";
+
+/// Takes the lock on `article_slug` for `editor`, or renews it when they
+/// already hold it, for `secs` seconds. Returns the lock as it stands,
+/// which is someone else's while they hold a live one.
+pub async fn acquire_lock(
+    pool: &DbPool,
+    article_slug: &str,
+    editor: &str,
+    secs: u64,
+) -> Result<ArticleLock, sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO article_locks (article_slug, locked_by, locked_until) \
+         VALUES (?, ?, datetime('now', '+' || ? || ' seconds')) \
+         ON CONFLICT (article_slug) DO UPDATE SET \
+         locked_at = CASE WHEN locked_by = excluded.locked_by THEN locked_at \
+         ELSE CURRENT_TIMESTAMP END, \
+         locked_by = excluded.locked_by, locked_until = excluded.locked_until \
+         WHERE locked_by = excluded.locked_by OR locked_until <= datetime('now')",
+    )
+    .bind(article_slug)
+    .bind(editor)
+    .bind(secs as i64)
+    .execute(pool)
+    .await?;
+    sqlx::query_as(&format!(
+        "SELECT {} FROM article_locks WHERE article_slug = ?",
+        LOCK_COLUMNS
+    ))
+    .bind(article_slug)
+    .fetch_one(pool)
+    .await
+}
+
+/// Locks that have not expired, by article.
+pub async fn live_locks(pool: &DbPool) -> Result<Vec<ArticleLock>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM article_locks WHERE locked_until > datetime('now') \
+         ORDER BY article_slug",
+        LOCK_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Gives up `editor`'s lock on `article_slug`. Returns `false` when they
+/// did not hold it.
+pub async fn release_lock(
+    pool: &DbPool,
+    article_slug: &str,
+    editor: &str,
+) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM article_locks WHERE article_slug = ? AND locked_by = ?")
+        .bind(article_slug)
+        .bind(editor)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn locks_are_held_until_released_or_expired() {
+        let pool = memory_db().await;
+        let lock = acquire_lock(&pool, "draft", "laptop", 60).await.unwrap();
+        assert_eq!(lock.locked_by, "laptop");
+        let lock = acquire_lock(&pool, "draft", "phone", 60).await.unwrap();
+        assert_eq!(lock.locked_by, "laptop");
+        let renewed = acquire_lock(&pool, "draft", "laptop", 120).await.unwrap();
+        assert!(renewed.locked_until > lock.locked_until);
+        assert_eq!(live_locks(&pool).await.unwrap().len(), 1);
+
+        assert!(!release_lock(&pool, "draft", "phone").await.unwrap());
+        assert!(release_lock(&pool, "draft", "laptop").await.unwrap());
+        assert!(live_locks(&pool).await.unwrap().is_empty());
+
+        acquire_lock(&pool, "draft", "laptop", 0).await.unwrap();
+        assert!(live_locks(&pool).await.unwrap().is_empty());
+        let lock = acquire_lock(&pool, "draft", "phone", 60).await.unwrap();
+        assert_eq!(lock.locked_by, "phone");
+    }
+}
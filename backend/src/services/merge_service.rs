@@ -0,0 +1,188 @@
+use crate::models::merge::{MergeConflict, MergeResult};
+
+/// Above this many cells the diff table is not built, and the changed
+/// middle of the text is treated as one region.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+/// Words of context given before a conflict.
+const CONTEXT_WORDS: usize = 8;
+
+/// Splits text into words and the whitespace between them, so that joining
+/// the tokens gives the text back.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_space = None;
+    for (i, c) in text.char_indices() {
+        let space = c.is_whitespace();
+        if in_space.is_some_and(|s| s != space) {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        in_space = Some(space);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// For each token of `base`, the index of the token it is kept as in
+/// `other`, following a longest common subsequence.
+fn matches(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; base.len()];
+    let prefix = base.iter().zip(other).take_while(|(a, b)| a == b).count();
+    let suffix = base[prefix..]
+        .iter()
+        .rev()
+        .zip(other[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    for (i, m) in matched.iter_mut().enumerate().take(prefix) {
+        *m = Some(i);
+    }
+    for k in 0..suffix {
+        matched[base.len() - 1 - k] = Some(other.len() - 1 - k);
+    }
+
+    let a = &base[prefix..base.len() - suffix];
+    let b = &other[prefix..other.len() - suffix];
+    if a.is_empty() || b.is_empty() || a.len() * b.len() > MAX_DIFF_CELLS {
+        return matched;
+    }
+    // lengths[i][j] is the LCS length of a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = if a[i] == b[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            matched[prefix + i] = Some(prefix + j);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matched
+}
+
+/// Merges two edits of `base` word by word: `yours`, the client's, and
+/// `current`, what the article holds now. Regions changed on one side take
+/// that side's text, and regions changed alike on both merge as well; any
+/// other region both sides changed is a conflict.
+pub fn merge(base: &str, yours: &str, current: &str) -> MergeResult {
+    let base = tokenize(base);
+    let yours = tokenize(yours);
+    let current = tokenize(current);
+    let in_yours = matches(&base, &yours);
+    let in_current = matches(&base, &current);
+
+    let mut merged: Vec<&str> = Vec::new();
+    let mut conflicts = Vec::new();
+    let (mut b, mut y, mut c) = (0, 0, 0);
+    loop {
+        // Tokens kept by both sides.
+        while b < base.len() && in_yours[b] == Some(y) && in_current[b] == Some(c) {
+            merged.push(base[b]);
+            b += 1;
+            y += 1;
+            c += 1;
+        }
+        if b == base.len() && y == yours.len() && c == current.len() {
+            break;
+        }
+
+        // The changed region runs up to the next token both sides kept.
+        let (next_b, next_y, next_c) = (b..base.len())
+            .find_map(|k| Some((k, in_yours[k]?, in_current[k]?)))
+            .unwrap_or((base.len(), yours.len(), current.len()));
+        let (base_part, your_part, current_part) =
+            (&base[b..next_b], &yours[y..next_y], &current[c..next_c]);
+        if your_part == base_part || your_part == current_part {
+            merged.extend(current_part);
+        } else if current_part == base_part {
+            merged.extend(your_part);
+        } else {
+            let words: Vec<&str> = merged
+                .iter()
+                .rev()
+                .filter(|t| !t.trim().is_empty())
+                .take(CONTEXT_WORDS)
+                .copied()
+                .collect();
+            conflicts.push(MergeConflict {
+                context: words.into_iter().rev().collect::<Vec<_>>().join(" "),
+                base: base_part.concat(),
+                yours: your_part.concat(),
+                current: current_part.concat(),
+            });
+            merged.extend(current_part);
+        }
+        (b, y, c) = (next_b, next_y, next_c);
+    }
+
+    MergeResult {
+        merged: conflicts.is_empty(),
+        content: conflicts.is_empty().then(|| merged.concat()),
+        conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_edits_to_different_words() {
+        let base = "The quick brown fox jumps over the lazy dog.";
+        let yours = "The quick red fox jumps over the lazy dog.";
+        let current = "The quick brown fox leaps over the lazy dog!";
+        let result = merge(base, yours, current);
+        assert_eq!(
+            result.content.as_deref(),
+            Some("The quick red fox leaps over the lazy dog!")
+        );
+        assert!(result.conflicts.is_empty());
+
+        let result = merge(base, current, current);
+        assert_eq!(result.content.as_deref(), Some(current));
+        let result = merge("", "Hello", "");
+        assert_eq!(result.content.as_deref(), Some("Hello"));
+    }
+
+    #[test]
+    fn reports_words_changed_on_both_sides() {
+        let base = "One two three four.\n\nFive six.";
+        let yours = "One 2 three four.\n\nFive six seven.";
+        let current = "One II three four.\n\nFive six.";
+        let result = merge(base, yours, current);
+        assert!(!result.merged);
+        assert_eq!(result.content, None);
+        assert_eq!(
+            result.conflicts,
+            [MergeConflict {
+                context: "One".to_string(),
+                base: "two".to_string(),
+                yours: "2".to_string(),
+                current: "II".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn tokens_join_back_into_the_text() {
+        let text = "  Leading, and\n\ttrailing  ";
+        assert_eq!(tokenize(text).concat(), text);
+        assert_eq!(tokenize(text)[1], "Leading,");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::db::DbPool;
+use crate::models::moderation::ModerationRule;
+use regex::Regex;
+
+const COLUMNS: &str = "id, pattern, regex, action, created_at";
+
+/// Refuses a submission outright.
+pub const REJECT: &str = "reject";
+/// Queues a submission for moderation instead of publishing it.
+pub const HOLD: &str = "hold";
+
+pub async fn create_rule(
+    pool: &DbPool,
+    pattern: &str,
+    regex: bool,
+    action: &str,
+) -> Result<ModerationRule, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "INSERT INTO moderation_rules (pattern, regex, action) VALUES (?, ?, ?) RETURNING {}",
+        COLUMNS
+    ))
+    .bind(pattern)
+    .bind(regex)
+    .bind(action)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn list_rules(pool: &DbPool) -> Result<Vec<ModerationRule>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM moderation_rules ORDER BY id",
+        COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns `false` when the rule does not exist.
+pub async fn delete_rule(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM moderation_rules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+/// The expression a rule matches with. Words match whole words in any
+/// case; regular expressions are used as written.
+pub fn rule_regex(pattern: &str, regex: bool) -> Result<Regex, regex::Error> {
+    if regex {
+        Regex::new(pattern)
+    } else {
+        Regex::new(&format!(r"(?i)\b{}\b", regex::escape(pattern.trim())))
+    }
+}
+
+/// The rule `text` breaks, preferring one that rejects it over one that
+/// holds it. Rules that no longer compile are ignored.
+pub fn matching_rule<'a>(rules: &'a [ModerationRule], text: &str) -> Option<&'a ModerationRule> {
+    let mut matching = rules.iter().filter(|rule| {
+        rule_regex(&rule.pattern, rule.regex).is_ok_and(|regex| regex.is_match(text))
+    });
+    let first = matching.next()?;
+    if first.action == REJECT {
+        return Some(first);
+    }
+    Some(matching.find(|rule| rule.action == REJECT).unwrap_or(first))
+}
+
+/// Checks a submission against the stored rules.
+pub async fn screen(pool: &DbPool, text: &str) -> Result<Option<ModerationRule>, sqlx::Error> {
+    let rules = list_rules(pool).await?;
+    Ok(matching_rule(&rules, text).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn rejecting_rules_win_over_holding_ones() {
+        let pool = memory_db().await;
+        create_rule(&pool, "darn", false, HOLD).await.unwrap();
+        create_rule(&pool, r"buy\s+now", true, REJECT)
+            .await
+            .unwrap();
+
+        let held = screen(&pool, "Well, Darn.").await.unwrap().unwrap();
+        assert_eq!(held.action, HOLD);
+        let rejected = screen(&pool, "darn, buy   now").await.unwrap().unwrap();
+        assert_eq!(rejected.action, REJECT);
+        assert!(screen(&pool, "darning socks").await.unwrap().is_none());
+
+        assert!(delete_rule(&pool, held.id).await.unwrap());
+        assert!(!delete_rule(&pool, held.id).await.unwrap());
+        assert_eq!(list_rules(&pool).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::services::atomic_file::write_atomic;
+use crate::services::secret_box::{OpenError, SecretBox};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Environment variable holding the 32-byte note key as 64 hex characters.
+pub const NOTES_ENCRYPTION_KEY: &str = "NOTES_ENCRYPTION_KEY";
+
+const BEGIN: &str = "-----BEGIN SCRIBE PRIVATE NOTE-----";
+const END: &str = "-----END SCRIBE PRIVATE NOTE-----";
+const LINE_WIDTH: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum NoteCryptoError {
+    #[error("Note key must be 64 hex characters")]
+    InvalidKey,
+    #[error("Note body is not a valid encrypted block")]
+    Malformed,
+    #[error("Note could not be decrypted with the configured key")]
+    Decrypt,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// XChaCha20-Poly1305 cipher for the bodies of private notes. Front matter
+/// stays in plain text so notes can still be indexed by the store; only the
+/// markdown body is sealed, as a hex-armored block of nonce plus ciphertext.
+#[derive(Clone)]
+pub struct NoteCipher {
+    secret_box: SecretBox,
+}
+
+impl NoteCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            secret_box: SecretBox::new(key),
+        }
+    }
+
+    pub fn from_hex(key: &str) -> Result<Self, NoteCryptoError> {
+        SecretBox::from_hex(key)
+            .map(|secret_box| Self { secret_box })
+            .map_err(|_| NoteCryptoError::InvalidKey)
+    }
+
+    /// Reads the key from `NOTES_ENCRYPTION_KEY`. `Ok(None)` when unset.
+    pub fn from_env() -> Result<Option<Self>, NoteCryptoError> {
+        SecretBox::from_env(NOTES_ENCRYPTION_KEY)
+            .map(|secret_box| secret_box.map(|secret_box| Self { secret_box }))
+            .map_err(|_| NoteCryptoError::InvalidKey)
+    }
+
+    pub fn is_sealed(body: &str) -> bool {
+        body.trim_start().starts_with(BEGIN)
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let encoded = hex::encode(self.secret_box.seal(plaintext));
+
+        let mut armored = String::from(BEGIN);
+        for line in encoded.as_bytes().chunks(LINE_WIDTH) {
+            armored.push('\n');
+            armored.push_str(std::str::from_utf8(line).unwrap_or_default());
+        }
+        armored.push('\n');
+        armored.push_str(END);
+        armored
+    }
+
+    pub fn decrypt(&self, armored: &str) -> Result<String, NoteCryptoError> {
+        let inner = armored
+            .trim()
+            .strip_prefix(BEGIN)
+            .and_then(|s| s.strip_suffix(END))
+            .ok_or(NoteCryptoError::Malformed)?;
+        let encoded: String = inner.chars().filter(|c| !c.is_whitespace()).collect();
+        let payload = hex::decode(encoded).map_err(|_| NoteCryptoError::Malformed)?;
+        self.secret_box.open(&payload).map_err(|e| match e {
+            OpenError::Malformed => NoteCryptoError::Malformed,
+            OpenError::Decrypt => NoteCryptoError::Decrypt,
+        })
+    }
+
+    /// Encrypts the body of a note file in place. Returns `false` when the
+    /// file has no front matter or its body is already sealed.
+    pub fn seal_file(&self, path: &Path) -> Result<bool, NoteCryptoError> {
+        let text = fs::read_to_string(path)?;
+        let Some((front_matter, body)) = split_front_matter(&text) else {
+            return Ok(false);
+        };
+        if Self::is_sealed(body) {
+            return Ok(false);
+        }
+        let sealed = format!("{}\n\n{}\n", front_matter, self.encrypt(body.trim()));
+        write_atomic(path, sealed)?;
+        Ok(true)
+    }
+}
+
+/// Splits a markdown file into its `---` delimited front matter, delimiters
+/// included, and the body after it.
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("---")?;
+    let end = rest.find("\n---")?;
+    Some(text.split_at("---".len() + end + "\n---".len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> NoteCipher {
+        NoteCipher::new(&[7; 32])
+    }
+
+    #[test]
+    fn round_trips_and_rejects_other_keys() {
+        let sealed = cipher().encrypt("secret *markdown*");
+        assert!(NoteCipher::is_sealed(&sealed));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(cipher().decrypt(&sealed).unwrap(), "secret *markdown*");
+        assert!(matches!(
+            NoteCipher::new(&[8; 32]).decrypt(&sealed),
+            Err(NoteCryptoError::Decrypt)
+        ));
+        assert!(matches!(
+            NoteCipher::from_hex("abcd"),
+            Err(NoteCryptoError::InvalidKey)
+        ));
+    }
+
+    #[test]
+    fn seals_body_and_keeps_front_matter() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("diary.md");
+        let front_matter = "---\ntitle: \"Diary\"\nprivate: true\n---";
+        fs::write(&path, format!("{}\n\nDear diary\n", front_matter)).unwrap();
+
+        assert!(cipher().seal_file(&path).unwrap());
+        assert!(!cipher().seal_file(&path).unwrap());
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(front_matter));
+        assert!(!text.contains("Dear diary"));
+        let (_, body) = split_front_matter(&text).unwrap();
+        assert_eq!(cipher().decrypt(body).unwrap(), "Dear diary");
+    }
+}
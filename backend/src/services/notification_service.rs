@@ -0,0 +1,150 @@
+use crate::config::DeployHookConfig;
+use crate::server::app::AppState;
+use crate::server::http_client::HttpClient;
+use crate::server::job_queue::JobQueue;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::env;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+/// Job type of events delivered to the `notify_webhook`.
+pub const WEBHOOK_JOB: &str = "webhook";
+/// Job type of deploy hook triggers.
+pub const DEPLOY_HOOK_JOB: &str = "deploy_hook";
+/// Header carrying `sha256=<hex HMAC of the body>` on signed deliveries.
+pub const SIGNATURE_HEADER: &str = "x-scribe-signature";
+
+/// HMAC-SHA256 of `body` under `secret`, hex-encoded.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether `signature`, a [`SIGNATURE_HEADER`] value, is the signature of
+/// `body` under `secret`.
+pub fn verify(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(signature) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    sign(secret, body)
+        .as_bytes()
+        .ct_eq(signature.to_ascii_lowercase().as_bytes())
+        .into()
+}
+
+/// Secret a deploy hook's deliveries are signed with, read from its
+/// `secret_env`. `Ok(None)` when the hook is unsigned.
+pub fn hook_secret(hook: &DeployHookConfig) -> Result<Option<String>, String> {
+    match &hook.secret_env {
+        Some(var) => env::var(var)
+            .map(Some)
+            .map_err(|_| format!("{} is not set", var)),
+        None => Ok(None),
+    }
+}
+
+/// Posts `event` as JSON to `url`, signed with `secret` when one is given.
+async fn post_event(
+    http: &HttpClient,
+    url: &str,
+    event: &Value,
+    secret: Option<&str>,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(event).map_err(|e| e.to_string())?;
+    let mut request = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = secret {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body)));
+    }
+    http.send(request.body(body))
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|_| ())
+        .map_err(|e| format!("Webhook delivery failed: {}", e))
+}
+
+/// Queues `event` for delivery to the author's `notify_webhook`, retried by
+/// the job queue until the endpoint accepts it. Nothing is queued when no
+/// webhook is configured.
+pub async fn notify_author(state: &AppState, event: &Value) {
+    if state.config.notify_webhook.is_none() {
+        return;
+    }
+    if let Err(e) = state.job_queue.push(state, WEBHOOK_JOB, event).await {
+        warn!("Failed to queue author notification: {:?}", e);
+    }
+}
+
+async fn deliver(state: Arc<AppState>, event: Value) -> Result<(), String> {
+    // The webhook may have been removed from the config since the event
+    // was queued.
+    let Some(url) = &state.config.notify_webhook else {
+        return Ok(());
+    };
+    post_event(&state.http, url, &event, None).await
+}
+
+/// Queues a trigger of the deploy hook `name`, retried like any webhook
+/// delivery. Returns the job id.
+pub async fn trigger_deploy(state: &AppState, name: &str) -> Result<i64, sqlx::Error> {
+    let event = serde_json::json!({
+        "event": "deploy",
+        "hook": name,
+        "site": state.config.hostname,
+    });
+    state.job_queue.push(state, DEPLOY_HOOK_JOB, &event).await
+}
+
+async fn deliver_deploy(state: Arc<AppState>, event: Value) -> Result<(), String> {
+    let Some(name) = event["hook"].as_str() else {
+        return Err("deploy event names no hook".to_string());
+    };
+    // Like the notification webhook, the hook may have been removed from
+    // the config in the meantime.
+    let Some(hook) = state.config.deploy_hooks.get(name) else {
+        return Ok(());
+    };
+    let secret = hook_secret(hook)?;
+    post_event(&state.http, &hook.url, &event, secret.as_deref()).await
+}
+
+/// Registers the webhook and deploy hook delivery handlers with `queue`.
+pub fn register(queue: &mut JobQueue) {
+    queue.register(
+        WEBHOOK_JOB,
+        Arc::new(|state, event| Box::pin(deliver(state, event))),
+    );
+    queue.register(
+        DEPLOY_HOOK_JOB,
+        Arc::new(|state, event| Box::pin(deliver_deploy(state, event))),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        let signature = format!("sha256={}", sign("secret", b"{}"));
+        assert!(verify("secret", b"{}", &signature));
+        assert!(verify(
+            "secret",
+            b"{}",
+            &signature.to_uppercase().replace("SHA", "sha")
+        ));
+        assert!(!verify("other", b"{}", &signature));
+        assert!(!verify("secret", b"{}", &signature[7..]));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::config::PdfConfig;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Pipes `html` through the configured converter and returns its stdout.
+/// The process is killed if it outlives `timeout_secs`.
+pub async fn render_pdf(config: &PdfConfig, html: &str) -> Result<Vec<u8>, String> {
+    let (program, args) = config
+        .command
+        .split_first()
+        .ok_or_else(|| "PDF command is empty".to_string())?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "PDF converter stdin unavailable".to_string())?;
+    let input = html.as_bytes().to_vec();
+    // Written concurrently so a converter that streams output before
+    // reading all of its input cannot deadlock on a full pipe.
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(&input).await;
+        drop(stdin);
+        result
+    });
+
+    let output = tokio::time::timeout(
+        Duration::from_secs(config.timeout_secs),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| format!("{} timed out after {}s", program, config.timeout_secs))?
+    .map_err(|e| format!("{} failed: {}", program, e))?;
+
+    if let Ok(Err(e)) = writer.await {
+        return Err(format!("Failed to write to {}: {}", program, e));
+    }
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    if output.stdout.is_empty() {
+        return Err(format!("{} produced no output", program));
+    }
+    Ok(output.stdout)
+}
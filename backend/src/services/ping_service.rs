@@ -0,0 +1,106 @@
+use crate::server::app::AppState;
+use crate::server::job_queue::JobQueue;
+use crate::services::audit_service::{SITEMAP_PING, WEBSUB_PING, record_audit};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Job type of sitemap and WebSub pings.
+pub const PING_JOB: &str = "ping";
+
+/// One announcement: `topic`, the sitemap or feed that changed, sent to
+/// `endpoint` because `slug` was published.
+#[derive(Deserialize, Debug)]
+struct Ping {
+    action: String,
+    endpoint: String,
+    topic: String,
+    slug: String,
+}
+
+/// Queues the `[pings]` announcing that `slug` was published. Every
+/// endpoint gets its own job, so one that is down is retried without
+/// pinging the others again. Nothing is queued without a `[pings]` table.
+pub async fn announce_publication(state: &AppState, slug: &str) {
+    let Some(pings) = &state.config.pings else {
+        return;
+    };
+    let sitemap = format!(
+        "{}/sitemap.xml",
+        state.config.hostname.trim_end_matches('/')
+    );
+    let mut jobs: Vec<Value> = pings
+        .sitemap_endpoints
+        .iter()
+        .map(|endpoint| {
+            json!({ "action": SITEMAP_PING, "endpoint": endpoint, "topic": sitemap, "slug": slug })
+        })
+        .collect();
+    if let Some(hub) = &pings.websub_hub {
+        jobs.extend(pings.websub_topics.iter().map(
+            |topic| json!({ "action": WEBSUB_PING, "endpoint": hub, "topic": topic, "slug": slug }),
+        ));
+    }
+    for job in &jobs {
+        if let Err(e) = state.job_queue.push(state, PING_JOB, job).await {
+            warn!("Failed to queue ping: {:?}", e);
+        }
+    }
+}
+
+/// Sends one queued ping and records the outcome in the audit log. A
+/// failed ping is recorded and then retried by the job queue.
+pub async fn deliver_ping(state: Arc<AppState>, payload: Value) -> Result<(), String> {
+    // Pings may have been turned off since the job was queued.
+    if state.config.pings.is_none() {
+        return Ok(());
+    }
+    let ping: Ping = serde_json::from_value(payload).map_err(|e| e.to_string())?;
+    let request = match ping.action.as_str() {
+        SITEMAP_PING => {
+            let mut url = reqwest::Url::parse(&ping.endpoint).map_err(|e| e.to_string())?;
+            url.query_pairs_mut().append_pair("sitemap", &ping.topic);
+            state.http.get(url)
+        }
+        WEBSUB_PING => state
+            .http
+            .post(&ping.endpoint)
+            .form(&[("hub.mode", "publish"), ("hub.url", ping.topic.as_str())]),
+        other => return Err(format!("unknown ping action '{}'", other)),
+    };
+    let result = state
+        .http
+        .send(request)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map(|r| r.status());
+
+    let outcome = match &result {
+        Ok(status) => status.to_string(),
+        Err(e) => e.to_string(),
+    };
+    let detail = format!("{} for {}: {}", ping.topic, ping.slug, outcome);
+    if let Err(e) = record_audit(
+        &state.db,
+        &ping.action,
+        &ping.endpoint,
+        result.is_ok(),
+        Some(&detail),
+    )
+    .await
+    {
+        warn!("Failed to record ping in the audit log: {:?}", e);
+    }
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Ping failed: {}", e))
+}
+
+/// Registers the ping delivery handler with `queue`.
+pub fn register(queue: &mut JobQueue) {
+    queue.register(
+        PING_JOB,
+        Arc::new(|state, payload| Box::pin(deliver_ping(state, payload))),
+    );
+}
@@ -0,0 +1,152 @@
+use crate::db::DbPool;
+use crate::models::poll::{Poll, PollDefinition, PollOptionResult, PollResults};
+
+type PollRow = (String, Option<String>, String, String);
+
+fn poll_from_row((id, article_slug, question, options): PollRow) -> Poll {
+    Poll {
+        id,
+        question,
+        options: serde_json::from_str(&options).unwrap_or_default(),
+        article_slug,
+    }
+}
+
+/// Builds the poll declared in an article's front matter.
+pub fn front_matter_poll(article_slug: &str, definition: &PollDefinition) -> Poll {
+    Poll {
+        id: definition
+            .id
+            .clone()
+            .unwrap_or_else(|| article_slug.to_string()),
+        question: definition.question.clone(),
+        options: definition.options.clone(),
+        article_slug: Some(article_slug.to_string()),
+    }
+}
+
+pub async fn create_poll(pool: &DbPool, poll: &Poll) -> Result<(), sqlx::Error> {
+    let options = serde_json::to_string(&poll.options).unwrap_or_else(|_| "[]".to_string());
+    sqlx::query("INSERT INTO polls (id, article_slug, question, options) VALUES (?, ?, ?, ?)")
+        .bind(&poll.id)
+        .bind(&poll.article_slug)
+        .bind(&poll.question)
+        .bind(options)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_poll(pool: &DbPool, id: &str) -> Result<Option<Poll>, sqlx::Error> {
+    let row: Option<PollRow> =
+        sqlx::query_as("SELECT id, article_slug, question, options FROM polls WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.map(poll_from_row))
+}
+
+/// Returns the most recently created admin poll attached to an article.
+pub async fn find_article_poll(pool: &DbPool, slug: &str) -> Result<Option<Poll>, sqlx::Error> {
+    let row: Option<PollRow> = sqlx::query_as(
+        "SELECT id, article_slug, question, options FROM polls WHERE article_slug = ? \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(poll_from_row))
+}
+
+/// Records a vote. Returns `false` when `voter_key` has already voted on
+/// this poll.
+pub async fn record_vote(
+    pool: &DbPool,
+    poll_id: &str,
+    option_index: usize,
+    voter_key: &str,
+) -> Result<bool, sqlx::Error> {
+    let inserted = sqlx::query(
+        "INSERT OR IGNORE INTO poll_votes (poll_id, option_index, voter_key) VALUES (?, ?, ?)",
+    )
+    .bind(poll_id)
+    .bind(option_index as i64)
+    .bind(voter_key)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(inserted > 0)
+}
+
+pub async fn poll_results(pool: &DbPool, poll: &Poll) -> Result<PollResults, sqlx::Error> {
+    let rows: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT option_index, COUNT(*) FROM poll_votes WHERE poll_id = ? GROUP BY option_index",
+    )
+    .bind(&poll.id)
+    .fetch_all(pool)
+    .await?;
+
+    let options: Vec<PollOptionResult> = poll
+        .options
+        .iter()
+        .enumerate()
+        .map(|(idx, option)| PollOptionResult {
+            option: option.clone(),
+            votes: rows
+                .iter()
+                .find(|(i, _)| *i == idx as i64)
+                .map_or(0, |(_, count)| *count),
+        })
+        .collect();
+    let total_votes = options.iter().map(|o| o.votes).sum();
+
+    Ok(PollResults {
+        id: poll.id.clone(),
+        question: poll.question.clone(),
+        options,
+        total_votes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    fn sample_poll() -> Poll {
+        Poll {
+            id: "favourite-editor".to_string(),
+            question: "Favourite editor?".to_string(),
+            options: vec!["vim".to_string(), "emacs".to_string()],
+            article_slug: Some("editors".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn votes_are_deduplicated_per_voter() {
+        let pool = memory_db().await;
+        let poll = sample_poll();
+        create_poll(&pool, &poll).await.unwrap();
+
+        assert!(record_vote(&pool, &poll.id, 0, "ip:a").await.unwrap());
+        assert!(!record_vote(&pool, &poll.id, 1, "ip:a").await.unwrap());
+        assert!(record_vote(&pool, &poll.id, 1, "ip:b").await.unwrap());
+
+        let results = poll_results(&pool, &poll).await.unwrap();
+        assert_eq!(results.total_votes, 2);
+        assert_eq!(results.options[0].votes, 1);
+        assert_eq!(results.options[1].votes, 1);
+    }
+
+    #[tokio::test]
+    async fn polls_round_trip_through_storage() {
+        let pool = memory_db().await;
+        create_poll(&pool, &sample_poll()).await.unwrap();
+
+        let by_id = get_poll(&pool, "favourite-editor").await.unwrap().unwrap();
+        assert_eq!(by_id.options, vec!["vim", "emacs"]);
+        let by_article = find_article_poll(&pool, "editors").await.unwrap();
+        assert!(by_article.is_some());
+        assert!(get_poll(&pool, "missing").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::server::app::AppState;
+use crate::server::visibility::Visibility;
+use crate::services::lock_service::live_locks;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::broadcast;
+
+/// Events kept for subscribers that fall behind; older ones are skipped.
+const PRESENCE_BUFFER: usize = 64;
+
+/// What the live widgets of the frontend are told. Nothing here names a
+/// draft or the person editing.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PresenceEvent {
+    /// How many editing sessions hold a live article lock.
+    Editing { sessions: usize },
+    /// An article anonymous visitors can read just went live.
+    Published {
+        slug: String,
+        title: String,
+        url: String,
+    },
+}
+
+impl PresenceEvent {
+    /// Name of the server-sent event.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PresenceEvent::Editing { .. } => "editing",
+            PresenceEvent::Published { .. } => "published",
+        }
+    }
+}
+
+/// Fans presence events out to every open `/api/presence` stream.
+pub struct Presence {
+    events: broadcast::Sender<PresenceEvent>,
+    /// Sessions in the last `editing` event, so renewing a lock stays quiet.
+    editing: AtomicUsize,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self {
+            events: broadcast::channel(PRESENCE_BUFFER).0,
+            editing: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Presence {
+    pub fn subscribe(&self) -> broadcast::Receiver<PresenceEvent> {
+        self.events.subscribe()
+    }
+
+    fn send(&self, event: PresenceEvent) {
+        // Nobody listening is not an error.
+        let _ = self.events.send(event);
+    }
+}
+
+/// Editing sessions holding a live lock. Failing to count them is not
+/// worth failing the request over.
+pub async fn editing_sessions(state: &AppState) -> usize {
+    live_locks(&state.db).await.map_or_else(
+        |e| {
+            tracing::warn!("Failed to count article locks: {:?}", e);
+            0
+        },
+        |locks| locks.len(),
+    )
+}
+
+/// Sends an `editing` event when the number of editing sessions changed
+/// since the last one.
+pub async fn editing_changed(state: &AppState) {
+    let sessions = editing_sessions(state).await;
+    if state.presence.editing.swap(sessions, Ordering::Relaxed) != sessions {
+        state.presence.send(PresenceEvent::Editing { sessions });
+    }
+}
+
+/// Sends a `published` event for `slug` when anonymous visitors can read
+/// it; articles in restricted categories go live silently.
+pub async fn announce_live(state: &AppState, slug: &str) {
+    let event = {
+        let store = state.store.read().await;
+        let Some(article) = store
+            .get_by_slug(slug)
+            .filter(|a| Visibility::public().allows(&store, a))
+        else {
+            return;
+        };
+        PresenceEvent::Published {
+            slug: article.slug.clone(),
+            title: article.metadata.title.clone(),
+            url: state.config.article_url(&article.slug),
+        }
+    };
+    state.presence.send(event);
+}
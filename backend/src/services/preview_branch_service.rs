@@ -0,0 +1,161 @@
+use crate::config::{ARTICLE_DIR, ENABLE_NESTED_CATEGORIES, NOTES_DIR, PreviewBranchesConfig};
+use crate::models::preview_branch::PreviewBranchSummary;
+use crate::services::service::ArticleStore;
+use chrono::Utc;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+const MAX_BRANCH_LENGTH: usize = 200;
+
+/// The content of a branch, loaded into stores of its own.
+pub struct PreviewBranch {
+    pub summary: PreviewBranchSummary,
+    pub articles: ArticleStore,
+    pub notes: ArticleStore,
+    /// Checkout the stores were loaded from, removed with the preview.
+    pub dir: PathBuf,
+}
+
+impl Drop for PreviewBranch {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            tracing::warn!("Failed to remove {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+/// Whether `name` can be passed to git as a branch name. Stricter than git
+/// itself, so nothing can be read as an option or a path outside the
+/// checkout.
+pub fn is_valid_branch(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_BRANCH_LENGTH
+        && !name.starts_with(['-', '/', '.'])
+        && !name.ends_with(['/', '.'])
+        && !name.contains("..")
+        && !name.contains("//")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+/// Shallow-clones `branch` of the configured remote into a new directory
+/// under `root` and loads it. `live` holds the [`slugs`] of the content
+/// being served, to compare the branch with.
+pub async fn check_out(
+    config: &PreviewBranchesConfig,
+    root: &Path,
+    branch: &str,
+    live: &BTreeSet<String>,
+) -> Result<PreviewBranch, String> {
+    let checked_out_at = Utc::now();
+    let dir = root.join(format!(
+        "{}-{}",
+        hex::encode(branch),
+        checked_out_at.timestamp_micros()
+    ));
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+
+    let clone = Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", "--single-branch"])
+        .arg("--branch")
+        .arg(branch)
+        .arg("--")
+        .arg(&config.remote)
+        .arg(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), clone)
+        .await
+        .map_err(|_| format!("git clone timed out after {}s", config.timeout_secs))
+        .and_then(|output| output.map_err(|e| format!("Failed to start git: {}", e)));
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+    };
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(format!(
+            "git clone exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let (articles, notes) = match load(&dir.join(&config.path)) {
+        Ok(stores) => stores,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+    };
+
+    let branch_slugs = slugs(&articles, &notes);
+    Ok(PreviewBranch {
+        summary: PreviewBranchSummary {
+            branch: branch.to_string(),
+            checked_out_at,
+            articles: articles.query(|_| true, 0, usize::MAX).count(),
+            notes: notes.query(|n| !n.metadata.private, 0, usize::MAX).count(),
+            added: branch_slugs.difference(live).cloned().collect(),
+            removed: live.difference(&branch_slugs).cloned().collect(),
+        },
+        articles,
+        notes,
+        dir,
+    })
+}
+
+/// Loads the article and note directories under `content_dir` like the
+/// live stores. A branch without one of them has no entries of that kind.
+fn load(content_dir: &Path) -> Result<(ArticleStore, ArticleStore), String> {
+    let load_dir = |name: &str, nested: bool| {
+        let dir = content_dir.join(name);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        ArticleStore::new(&dir, nested).map_err(|e| format!("Failed to load {}: {}", name, e))
+    };
+    Ok((
+        load_dir(ARTICLE_DIR, ENABLE_NESTED_CATEGORIES)?,
+        load_dir(NOTES_DIR, true)?,
+    ))
+}
+
+/// Every slug in a pair of stores, with notes under `notes/`. Private
+/// notes are left out, as everywhere in previews.
+pub fn slugs(articles: &ArticleStore, notes: &ArticleStore) -> BTreeSet<String> {
+    articles
+        .query(|_| true, 0, usize::MAX)
+        .map(|a| a.slug.clone())
+        .chain(
+            notes
+                .query(|n| !n.metadata.private, 0, usize::MAX)
+                .map(|n| format!("notes/{}", n.slug_with_category())),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branch_names_cannot_escape_git_or_the_checkout() {
+        for name in ["main", "reorg/2024-05", "v1.2_fix"] {
+            assert!(is_valid_branch(name), "{name}");
+        }
+        for name in ["", "-upload-pack=x", "../main", "a..b", "/abs", "a b", "x/"] {
+            assert!(!is_valid_branch(name), "{name}");
+        }
+    }
+}
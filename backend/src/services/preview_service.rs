@@ -0,0 +1,130 @@
+use crate::db::DbPool;
+use crate::models::preview::{IssuedPreviewLink, PreviewLink, ReviewComment};
+use crate::services::token::{generate_token, hash_token};
+
+const LINK_COLUMNS: &str = "id, article_slug, reviewer, expires_at, revoked, created_at";
+const COMMENT_COLUMNS: &str = "id, preview_id, article_slug, author_name, content, created_at";
+
+/// Issues a link to the article `article_slug` that stays valid for
+/// `hours`.
+pub async fn issue_preview(
+    pool: &DbPool,
+    article_slug: &str,
+    reviewer: Option<&str>,
+    hours: u64,
+) -> Result<IssuedPreviewLink, sqlx::Error> {
+    let token = generate_token();
+    let link = sqlx::query_as(&format!(
+        "INSERT INTO preview_links (article_slug, token_hash, reviewer, expires_at) \
+         VALUES (?, ?, ?, datetime('now', '+' || ? || ' hours')) RETURNING {}",
+        LINK_COLUMNS
+    ))
+    .bind(article_slug)
+    .bind(hash_token(&token))
+    .bind(reviewer)
+    .bind(hours as i64)
+    .fetch_one(pool)
+    .await?;
+    Ok(IssuedPreviewLink { link, token })
+}
+
+/// Links issued for an article, newest first, including expired and
+/// revoked ones.
+pub async fn list_previews(
+    pool: &DbPool,
+    article_slug: &str,
+) -> Result<Vec<PreviewLink>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM preview_links WHERE article_slug = ? ORDER BY id DESC",
+        LINK_COLUMNS
+    ))
+    .bind(article_slug)
+    .fetch_all(pool)
+    .await
+}
+
+/// The link for `token`, unless it is unknown, revoked or expired.
+pub async fn live_preview(pool: &DbPool, token: &str) -> Result<Option<PreviewLink>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM preview_links \
+         WHERE token_hash = ? AND revoked = 0 AND expires_at > datetime('now')",
+        LINK_COLUMNS
+    ))
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Revokes a link before it expires. Returns `false` when it does not
+/// exist or was already revoked.
+pub async fn revoke_preview(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query("UPDATE preview_links SET revoked = 1 WHERE id = ? AND revoked = 0")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(updated > 0)
+}
+
+pub async fn add_review_comment(
+    pool: &DbPool,
+    link: &PreviewLink,
+    author_name: &str,
+    content: &str,
+) -> Result<ReviewComment, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "INSERT INTO review_comments (preview_id, article_slug, author_name, content) \
+         VALUES (?, ?, ?, ?) RETURNING {}",
+        COMMENT_COLUMNS
+    ))
+    .bind(link.id)
+    .bind(&link.article_slug)
+    .bind(author_name)
+    .bind(content)
+    .fetch_one(pool)
+    .await
+}
+
+/// Review comments on an article from every link, oldest first. They
+/// outlive the links they were left through.
+pub async fn review_comments(
+    pool: &DbPool,
+    article_slug: &str,
+) -> Result<Vec<ReviewComment>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM review_comments WHERE article_slug = ? ORDER BY id",
+        COMMENT_COLUMNS
+    ))
+    .bind(article_slug)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn links_work_until_revoked_or_expired() {
+        let pool = memory_db().await;
+        let issued = issue_preview(&pool, "draft", Some("Ada"), 24)
+            .await
+            .unwrap();
+        let link = live_preview(&pool, &issued.token).await.unwrap().unwrap();
+        assert_eq!(link.article_slug, "draft");
+        assert!(live_preview(&pool, "unknown").await.unwrap().is_none());
+
+        add_review_comment(&pool, &link, "Ada", "<p>Typo</p>")
+            .await
+            .unwrap();
+        assert!(revoke_preview(&pool, link.id).await.unwrap());
+        assert!(!revoke_preview(&pool, link.id).await.unwrap());
+        assert!(live_preview(&pool, &issued.token).await.unwrap().is_none());
+        assert_eq!(review_comments(&pool, "draft").await.unwrap().len(), 1);
+
+        let expired = issue_preview(&pool, "draft", None, 0).await.unwrap();
+        assert!(live_preview(&pool, &expired.token).await.unwrap().is_none());
+        assert_eq!(list_previews(&pool, "draft").await.unwrap().len(), 2);
+    }
+}
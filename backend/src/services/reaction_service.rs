@@ -0,0 +1,182 @@
+use crate::db::DbPool;
+use crate::models::comment::ImportSummary;
+use crate::models::reaction::{ReactionCount, ReactionRecord};
+use crate::services::analytics_service::day_bounds;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// Toggles `emoji` for the given user on an article.
+///
+/// Returns `true` when the reaction was added and `false` when an existing
+/// reaction was removed.
+pub async fn toggle_reaction(
+    pool: &DbPool,
+    article_slug: &str,
+    user_github_id: u64,
+    emoji: &str,
+) -> Result<bool, sqlx::Error> {
+    let removed = sqlx::query(
+        "DELETE FROM reactions WHERE article_slug = ? AND user_github_id = ? AND emoji = ?",
+    )
+    .bind(article_slug)
+    .bind(user_github_id as i64)
+    .bind(emoji)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if removed > 0 {
+        return Ok(false);
+    }
+
+    sqlx::query("INSERT INTO reactions (article_slug, user_github_id, emoji) VALUES (?, ?, ?)")
+        .bind(article_slug)
+        .bind(user_github_id as i64)
+        .bind(emoji)
+        .execute(pool)
+        .await?;
+    Ok(true)
+}
+
+/// Aggregates reaction counts for an article, reported in the order of the
+/// configured emoji set. Emojis no longer in the set are omitted.
+pub async fn reaction_counts(
+    pool: &DbPool,
+    article_slug: &str,
+    emojis: &[String],
+) -> Result<Vec<ReactionCount>, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT emoji, COUNT(*) FROM reactions WHERE article_slug = ? GROUP BY emoji",
+    )
+    .bind(article_slug)
+    .fetch_all(pool)
+    .await?;
+    let counts: HashMap<String, i64> = rows.into_iter().collect();
+
+    Ok(emojis
+        .iter()
+        .map(|emoji| ReactionCount {
+            emoji: emoji.clone(),
+            count: counts.get(emoji).copied().unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Every stored reaction given from `from` to `to`, both inclusive, oldest
+/// first.
+pub async fn export_reactions(
+    pool: &DbPool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<ReactionRecord>, sqlx::Error> {
+    let (from, to) = day_bounds(from, to);
+    sqlx::query_as(
+        "SELECT article_slug, user_github_id, emoji, created_at FROM reactions \
+         WHERE (?1 IS NULL OR date(created_at) >= ?1) \
+         AND (?2 IS NULL OR date(created_at) <= ?2) ORDER BY created_at, id",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stores `reactions` in one transaction. A reader reacts with an emoji once
+/// per article, so rows already stored are skipped.
+pub async fn import_reactions(
+    pool: &DbPool,
+    reactions: &[ReactionRecord],
+) -> Result<ImportSummary, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut imported = 0;
+    for reaction in reactions {
+        imported += sqlx::query(
+            "INSERT OR IGNORE INTO reactions (article_slug, user_github_id, emoji, created_at) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(&reaction.article_slug)
+        .bind(reaction.user_github_id)
+        .bind(&reaction.emoji)
+        .bind(&reaction.created_at)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as usize;
+    }
+    tx.commit().await?;
+    Ok(ImportSummary {
+        imported,
+        skipped: reactions.len() - imported,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    fn emojis() -> Vec<String> {
+        vec!["👍".to_string(), "🎉".to_string()]
+    }
+
+    #[tokio::test]
+    async fn toggle_adds_then_removes() {
+        let pool = memory_db().await;
+        assert!(toggle_reaction(&pool, "post", 1, "👍").await.unwrap());
+        assert!(!toggle_reaction(&pool, "post", 1, "👍").await.unwrap());
+        let counts = reaction_counts(&pool, "post", &emojis()).await.unwrap();
+        assert!(counts.iter().all(|c| c.count == 0));
+    }
+
+    #[tokio::test]
+    async fn counts_are_per_user_and_ordered_by_config() {
+        let pool = memory_db().await;
+        toggle_reaction(&pool, "post", 1, "🎉").await.unwrap();
+        toggle_reaction(&pool, "post", 2, "🎉").await.unwrap();
+        toggle_reaction(&pool, "post", 2, "👍").await.unwrap();
+        toggle_reaction(&pool, "other", 1, "👍").await.unwrap();
+
+        let counts = reaction_counts(&pool, "post", &emojis()).await.unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                ReactionCount {
+                    emoji: "👍".to_string(),
+                    count: 1
+                },
+                ReactionCount {
+                    emoji: "🎉".to_string(),
+                    count: 2
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn imports_skip_reactions_already_given() {
+        let pool = memory_db().await;
+        toggle_reaction(&pool, "post", 1, "👍").await.unwrap();
+        let reaction = |user: i64, created_at: &str| ReactionRecord {
+            article_slug: "post".to_string(),
+            user_github_id: user,
+            emoji: "👍".to_string(),
+            created_at: created_at.to_string(),
+        };
+        let rows = [
+            reaction(1, "2024-07-01 10:00:00"),
+            reaction(2, "2024-07-02 10:00:00"),
+        ];
+        let summary = import_reactions(&pool, &rows).await.unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 1,
+                skipped: 1
+            }
+        );
+
+        let day = NaiveDate::from_ymd_opt(2024, 7, 2);
+        let exported = export_reactions(&pool, day, day).await.unwrap();
+        assert_eq!(exported, rows[1..]);
+        assert_eq!(export_reactions(&pool, None, None).await.unwrap().len(), 2);
+    }
+}
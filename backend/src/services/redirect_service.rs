@@ -0,0 +1,65 @@
+use crate::db::DbPool;
+use crate::models::redirect::Redirect;
+
+pub async fn list_redirects(pool: &DbPool) -> Result<Vec<Redirect>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, from_path, to_url, status, created_at FROM redirects ORDER BY from_path",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Registers a redirect. Returns `None` when `from_path` already has one.
+pub async fn add_redirect(
+    pool: &DbPool,
+    from_path: &str,
+    to_url: &str,
+    status: u16,
+) -> Result<Option<Redirect>, sqlx::Error> {
+    sqlx::query_as(
+        "INSERT INTO redirects (from_path, to_url, status) VALUES (?, ?, ?) \
+         ON CONFLICT (from_path) DO NOTHING \
+         RETURNING id, from_path, to_url, status, created_at",
+    )
+    .bind(from_path)
+    .bind(to_url)
+    .bind(status)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_redirect(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM redirects WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn paths_have_at_most_one_redirect() {
+        let pool = memory_db().await;
+        let redirect = add_redirect(&pool, "/old", "/articles/new", 301)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redirect.status, 301);
+        assert!(
+            add_redirect(&pool, "/old", "/elsewhere", 308)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(list_redirects(&pool).await.unwrap().len(), 1);
+
+        assert!(delete_redirect(&pool, redirect.id).await.unwrap());
+        assert!(!delete_redirect(&pool, redirect.id).await.unwrap());
+        assert!(list_redirects(&pool).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,719 @@
+use crate::models::article::Metadata;
+use crate::models::citation::Reference;
+use crate::models::snippet::CodeSnippet;
+use crate::services::citation_service::Citations;
+use crate::services::glossary_service::GlossaryLinker;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd, html};
+use reqwest::Url;
+use std::collections::HashSet;
+
+/// Inlined so the page stays self-contained when printed or archived.
+const PRINT_STYLES: &str = "\
+@page { margin: 2cm; }
+body { font-family: Georgia, 'Times New Roman', serif; line-height: 1.6; color: #000; background: #fff; max-width: 42em; margin: 0 auto; padding: 1em; }
+h1, h2, h3, h4 { line-height: 1.25; break-after: avoid; }
+.meta, .source { color: #555; font-size: 0.9em; }
+pre, code { font-family: Menlo, Consolas, monospace; font-size: 0.9em; }
+pre { white-space: pre-wrap; border: 1px solid #ccc; padding: 0.75em; break-inside: avoid; }
+img { max-width: 100%; break-inside: avoid; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #999; padding: 0.25em 0.5em; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 3px solid #ccc; color: #333; }
+a { color: inherit; }
+.footnotes, .references { font-size: 0.9em; border-top: 1px solid #ccc; margin-top: 2em; }
+@media print { a[href^='http']::after { content: ' (' attr(href) ')'; font-size: 0.8em; word-break: break-all; } }
+";
+
+/// A standalone, print-optimized page for the article published at
+/// `page_url`: styles are inlined, relative links and images are made
+/// absolute, and footnotes are numbered in order of first reference and
+/// collected at the end with links back to the text. `[@id]` citations of
+/// the article's `references` are numbered the same way and link to a
+/// reference list. With a `glossary`, the first occurrence of each term
+/// links to its definition.
+pub fn render_document(
+    metadata: &Metadata,
+    content: &str,
+    page_url: &str,
+    glossary: Option<&GlossaryLinker>,
+) -> String {
+    render_page(metadata, content, page_url, &HashSet::new(), glossary)
+}
+
+/// Like [`render_document`], for a page saved next to its images: sources
+/// listed in `bundled` keep their relative path instead of pointing at the
+/// published site.
+pub fn render_bundle_document(
+    metadata: &Metadata,
+    content: &str,
+    page_url: &str,
+    bundled: &HashSet<String>,
+    glossary: Option<&GlossaryLinker>,
+) -> String {
+    render_page(metadata, content, page_url, bundled, glossary)
+}
+
+fn render_page(
+    metadata: &Metadata,
+    content: &str,
+    page_url: &str,
+    bundled: &HashSet<String>,
+    glossary: Option<&GlossaryLinker>,
+) -> String {
+    let base = Url::parse(page_url).ok();
+    let title = escape_html(&metadata.title);
+    let page_url = escape_html(page_url);
+    // Saved copies carry the crawler directives of the served page.
+    let robots = metadata
+        .http_header("x-robots-tag")
+        .map(|value| {
+            format!(
+                "<meta name=\"robots\" content=\"{}\">\n",
+                escape_html(value)
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n{robots}\
+         <title>{title}</title>\n<link rel=\"canonical\" href=\"{page_url}\">\n\
+         <style>\n{PRINT_STYLES}</style>\n</head>\n<body>\n<article>\n<h1>{title}</h1>\n\
+         <p class=\"meta\">{author} · {date}</p>\n{body}</article>\n\
+         <p class=\"source\">Originally published at <a href=\"{page_url}\">{page_url}</a></p>\n\
+         </body>\n</html>\n",
+        lang = escape_html(metadata.lang.as_deref().unwrap_or("en")),
+        author = escape_html(&metadata.author),
+        date = metadata.day().format("%Y-%m-%d"),
+        body = render_body(
+            content,
+            base.as_ref(),
+            bundled,
+            glossary,
+            &metadata.references
+        ),
+    )
+}
+
+fn render_body(
+    content: &str,
+    base: Option<&Url>,
+    bundled: &HashSet<String>,
+    glossary: Option<&GlossaryLinker>,
+    references: &[Reference],
+) -> String {
+    let defined: HashSet<String> = Parser::new_ext(content, Options::all())
+        .filter_map(|event| match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => Some(label.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut body = Vec::new();
+    // Definitions in source order; `referenced` holds labels in the order
+    // they are first cited, which gives the footnote numbers.
+    let mut definitions: Vec<(String, Vec<Event>)> = Vec::new();
+    let mut referenced: Vec<String> = Vec::new();
+    let mut current: Option<(String, Vec<Event>)> = None;
+    // Citations and glossary terms are only looked for outside links,
+    // images, headings and code blocks; terms are linked once each.
+    let citations = Citations::new(references);
+    let mut cited = Vec::new();
+    let mut linked = HashSet::new();
+    let mut unlinkable = 0;
+
+    for event in merge_text(Parser::new_ext(content, Options::all())) {
+        match &event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. } | Tag::Heading { .. })
+            | Event::Start(Tag::CodeBlock(_)) => unlinkable += 1,
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::Heading(_))
+            | Event::End(TagEnd::CodeBlock) => unlinkable -= 1,
+            _ => {}
+        }
+        if unlinkable == 0
+            && let Event::Text(text) = &event
+        {
+            let pieces = citations
+                .cite(text, &mut cited)
+                .unwrap_or_else(|| vec![event.clone()]);
+            let mut events = Vec::new();
+            for piece in pieces {
+                match piece {
+                    Event::Text(text) => {
+                        match glossary.and_then(|glossary| glossary.link(&text, &mut linked)) {
+                            Some(linked_events) => events.extend(linked_events),
+                            None => events.push(Event::Text(text)),
+                        }
+                    }
+                    piece => events.push(piece),
+                }
+            }
+            match current.as_mut() {
+                Some((_, events_so_far)) => events_so_far.extend(events),
+                None => body.extend(events),
+            }
+            continue;
+        }
+        let event = match event {
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                current = Some((label.to_string(), Vec::new()));
+                continue;
+            }
+            Event::End(TagEnd::FootnoteDefinition) => {
+                definitions.extend(current.take());
+                continue;
+            }
+            Event::FootnoteReference(label) if defined.contains(label.as_ref()) => {
+                let n = match referenced.iter().position(|l| l == label.as_ref()) {
+                    Some(i) => i + 1,
+                    None => {
+                        referenced.push(label.to_string());
+                        referenced.len()
+                    }
+                };
+                Event::InlineHtml(CowStr::from(format!(
+                    "<sup class=\"footnote-ref\" id=\"fnref-{n}\"><a href=\"#fn-{n}\">{n}</a></sup>"
+                )))
+            }
+            Event::FootnoteReference(label) => Event::Text(CowStr::from(format!("[^{}]", label))),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: absolute_url(dest_url, base),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if !bundled.contains(dest_url.as_ref()) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: absolute_url(dest_url, base),
+                title,
+                id,
+            }),
+            event => event,
+        };
+        match current.as_mut() {
+            Some((_, events)) => events.push(event),
+            None => body.push(event),
+        }
+    }
+
+    let mut output = String::new();
+    html::push_html(&mut output, body.into_iter());
+    output.push_str(&citations.bibliography(&cited));
+
+    // Cited footnotes first, in citation order, then any never cited.
+    definitions.sort_by_key(|(label, _)| {
+        referenced
+            .iter()
+            .position(|l| l == label)
+            .unwrap_or(usize::MAX)
+    });
+    if !definitions.is_empty() {
+        output.push_str("<section class=\"footnotes\">\n<ol>\n");
+        for (i, (label, mut events)) in definitions.into_iter().enumerate() {
+            let n = i + 1;
+            if referenced.contains(&label) {
+                let backref = Event::InlineHtml(CowStr::from(format!(
+                    " <a href=\"#fnref-{n}\" class=\"footnote-backref\">↩</a>"
+                )));
+                match events
+                    .iter()
+                    .rposition(|e| matches!(e, Event::End(TagEnd::Paragraph)))
+                {
+                    Some(pos) => events.insert(pos, backref),
+                    None => events.push(backref),
+                }
+            }
+            output.push_str(&format!("<li id=\"fn-{n}\">\n"));
+            html::push_html(&mut output, events.into_iter());
+            output.push_str("</li>\n");
+        }
+        output.push_str("</ol>\n</section>\n");
+    }
+    output
+}
+
+/// Joins runs of text events, which the parser splits around brackets,
+/// so citations and terms spanning them are found.
+fn merge_text<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut merged: Vec<Event<'a>> = Vec::new();
+    for event in events {
+        if let (Event::Text(text), Some(Event::Text(previous))) = (&event, merged.last_mut()) {
+            *previous = CowStr::from(format!("{}{}", previous, text));
+            continue;
+        }
+        merged.push(event);
+    }
+    merged
+}
+
+/// Every image source in `content`, in order of first use.
+pub fn image_sources(content: &str) -> Vec<String> {
+    let mut images: Vec<String> = Vec::new();
+    for event in Parser::new_ext(content, Options::all()) {
+        if let Event::Start(Tag::Image { dest_url, .. }) = event
+            && !images.iter().any(|i| i == dest_url.as_ref())
+        {
+            images.push(dest_url.to_string());
+        }
+    }
+    images
+}
+
+/// The fenced code blocks of `content` in order. Indented code blocks are
+/// left out.
+pub fn code_snippets(content: &str) -> Vec<CodeSnippet> {
+    let mut snippets = Vec::new();
+    let mut current: Option<CodeSnippet> = None;
+    for (event, range) in Parser::new_ext(content, Options::all()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                let fence_line = content[..range.start].matches('\n').count() + 1;
+                current = Some(CodeSnippet {
+                    anchor: format!("snippet-{}", snippets.len() + 1),
+                    language: info.split_whitespace().next().map(str::to_string),
+                    start_line: fence_line + 1,
+                    end_line: fence_line + 1,
+                    code: String::new(),
+                });
+            }
+            Event::Text(text) => {
+                if let Some(snippet) = current.as_mut() {
+                    snippet.code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some(mut snippet) = current.take() {
+                    snippet.end_line =
+                        snippet.start_line + snippet.code.lines().count().saturating_sub(1);
+                    snippets.push(snippet);
+                }
+            }
+            _ => {}
+        }
+    }
+    snippets
+}
+
+/// Relative image sources that stay inside the article's directory, in
+/// order of first use. Absolute and root-relative URLs, sources with a
+/// query or fragment, and paths climbing out with `..` are left out.
+pub fn local_images(content: &str) -> Vec<String> {
+    image_sources(content)
+        .into_iter()
+        .filter(|source| is_local_path(source))
+        .collect()
+}
+
+pub(crate) fn is_local_path(url: &str) -> bool {
+    !url.is_empty()
+        && Url::parse(url).is_err()
+        && !url.starts_with('/')
+        && !url.contains(['?', '#', '\\'])
+        && url.split('/').all(|part| !part.is_empty() && part != "..")
+}
+
+/// Markdown stripped down to its readable text, for text-to-speech and
+/// other tools that want prose rather than markup. Code blocks are kept
+/// verbatim, list items and table rows stay on their own lines, and raw
+/// HTML, link targets and image URLs are dropped.
+pub fn render_plain_text(content: &str) -> String {
+    let mut output = String::new();
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => end_block(&mut output),
+            Event::Text(text) => output.push_str(&text),
+            Event::Code(code) => output.push_str(&code),
+            Event::SoftBreak | Event::HardBreak => output.push('\n'),
+            Event::Start(Tag::Item) | Event::End(TagEnd::TableRow | TagEnd::TableHead) => {
+                end_line(&mut output)
+            }
+            Event::End(TagEnd::TableCell) => output.push('\t'),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::BlockQuote
+                | TagEnd::List(_)
+                | TagEnd::Table
+                | TagEnd::CodeBlock
+                | TagEnd::FootnoteDefinition,
+            )
+            | Event::Rule => end_block(&mut output),
+            _ => {}
+        }
+    }
+    output.trim_end().to_string()
+}
+
+/// The text of the first paragraph of prose, whitespace collapsed and cut
+/// at a word boundary to at most `max_chars`, for use as a description.
+/// Headings, code, quotes, lists, tables and paragraphs holding nothing but
+/// images, HTML or shortcodes are passed over.
+pub fn first_paragraph(content: &str, max_chars: usize) -> Option<String> {
+    // Paragraphs inside quotes, lists and footnotes are not the article's
+    // own prose, and image alt text is not prose at all.
+    let mut containers = 0usize;
+    let mut images = 0usize;
+    let mut paragraph: Option<String> = None;
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Start(Tag::BlockQuote | Tag::List(_) | Tag::FootnoteDefinition(_)) => {
+                containers += 1
+            }
+            Event::End(TagEnd::BlockQuote | TagEnd::List(_) | TagEnd::FootnoteDefinition) => {
+                containers = containers.saturating_sub(1)
+            }
+            Event::Start(Tag::Paragraph) if containers == 0 => paragraph = Some(String::new()),
+            Event::Start(Tag::Image { .. }) => images += 1,
+            Event::End(TagEnd::Image) => images = images.saturating_sub(1),
+            Event::Text(text) | Event::Code(text) if images == 0 => {
+                if let Some(paragraph) = &mut paragraph {
+                    paragraph.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(paragraph) = &mut paragraph {
+                    paragraph.push(' ');
+                }
+            }
+            Event::End(TagEnd::Paragraph) => {
+                let Some(paragraph) = paragraph.take() else {
+                    continue;
+                };
+                let text = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() && !text.starts_with("{{") {
+                    return Some(truncate_words(&text, max_chars));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `text` cut to at most `max_chars` at a word boundary, with an ellipsis
+/// when anything was cut.
+fn truncate_words(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut cut = String::new();
+    for word in text.split(' ') {
+        if cut.chars().count() + word.chars().count() + 2 > max_chars {
+            break;
+        }
+        if !cut.is_empty() {
+            cut.push(' ');
+        }
+        cut.push_str(word);
+    }
+    if cut.is_empty() {
+        cut = text.chars().take(max_chars - 1).collect();
+    }
+    let cut = cut.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    format!("{}…", cut)
+}
+
+/// Starts a new line unless the output already is at one.
+fn end_line(output: &mut String) {
+    let trimmed = output.trim_end_matches([' ', '\t']).len();
+    output.truncate(trimmed);
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+/// Separates blocks with a single blank line.
+fn end_block(output: &mut String) {
+    end_line(output);
+    if !output.is_empty() && !output.ends_with("\n\n") {
+        output.push('\n');
+    }
+}
+
+/// Resolves a link against the article's public URL. In-page anchors are
+/// kept so footnote and heading links keep working in a saved copy.
+fn absolute_url<'a>(url: CowStr<'a>, base: Option<&Url>) -> CowStr<'a> {
+    if url.starts_with('#') {
+        return url;
+    }
+    match base.and_then(|base| base.join(&url).ok()) {
+        Some(resolved) => CowStr::from(resolved.to_string()),
+        None => url,
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::ArticleStatus;
+    use crate::models::glossary::GlossaryEntry;
+    use chrono::Utc;
+
+    fn metadata() -> Metadata {
+        Metadata {
+            title: "Fish & <Chips>".to_string(),
+            author: "a".to_string(),
+            date: Utc::now(),
+            display_date: None,
+            tags: vec![],
+            description: String::new(),
+            status: ArticleStatus::Published,
+            last_updated: None,
+            category: None,
+            poll: None,
+            lang: Some("de".to_string()),
+            translations: Default::default(),
+            private: false,
+            http_headers: Default::default(),
+            comments: Default::default(),
+            menu: None,
+            audio: None,
+            gallery: Vec::new(),
+            link: None,
+            syndication: Default::default(),
+            references: Vec::new(),
+            downloads: Vec::new(),
+            description_generated: false,
+            members_only: false,
+        }
+    }
+
+    #[test]
+    fn renders_standalone_document() {
+        let page = render_document(
+            &metadata(),
+            "Some **bold** text",
+            "https://example.com/articles/fish",
+            None,
+        );
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<html lang=\"de\">"));
+        assert!(page.contains("<style>\n@page"));
+        assert!(page.contains("<h1>Fish &amp; &lt;Chips&gt;</h1>"));
+        assert!(page.contains("<strong>bold</strong>"));
+        assert!(
+            page.contains("<link rel=\"canonical\" href=\"https://example.com/articles/fish\">")
+        );
+        assert!(!page.contains("name=\"robots\""));
+
+        let mut noindex = metadata();
+        noindex
+            .http_headers
+            .insert("X-Robots-Tag".to_string(), "noindex, nofollow".to_string());
+        let page = render_document(&noindex, "Text", "https://example.com/articles/fish", None);
+        assert!(page.contains("<meta name=\"robots\" content=\"noindex, nofollow\">"));
+    }
+
+    #[test]
+    fn bundled_images_stay_relative() {
+        let content = "![a](img/a.png) ![b](img/b.png) ![a](img/a.png) ![up](../x.png) \
+                       ![abs](https://cdn.example/c.png) ![root](/d.png)";
+        assert_eq!(local_images(content), vec!["img/a.png", "img/b.png"]);
+
+        let bundled = HashSet::from(["img/a.png".to_string()]);
+        let page = render_bundle_document(
+            &metadata(),
+            content,
+            "https://example.com/articles/fish",
+            &bundled,
+            None,
+        );
+        assert!(page.contains("src=\"img/a.png\""));
+        assert!(page.contains("src=\"https://example.com/articles/img/b.png\""));
+    }
+
+    #[test]
+    fn plain_text_strips_markup_but_keeps_code() {
+        let content = "# Title\n\nSome **bold** and `code` with a [link](/x).\n\n\
+                       - one\n- two\n\n<div>raw</div>\n\n\
+                       ```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+                       > quoted\n";
+        assert_eq!(
+            render_plain_text(content),
+            "Title\n\nSome bold and code with a link.\n\none\ntwo\n\n\
+             fn main() {\n    println!(\"hi\");\n}\n\nquoted"
+        );
+    }
+
+    #[test]
+    fn descriptions_come_from_the_first_paragraph_of_prose() {
+        let content = "# Title\n\n![A cover](cover.png)\n\n> A quote\n\n\
+                       {{< include \"cta\" >}}\n\n- a list\n\n\
+                       Some **bold** and `code`\nwith a [link](/x).\n\nMore.\n";
+        assert_eq!(
+            first_paragraph(content, 160).as_deref(),
+            Some("Some bold and code with a link.")
+        );
+        assert_eq!(
+            first_paragraph("One two three four five.", 15).as_deref(),
+            Some("One two three…")
+        );
+        assert_eq!(
+            first_paragraph("# Only a heading\n\n```\ncode\n```\n", 160),
+            None
+        );
+    }
+
+    #[test]
+    fn resolves_links_and_footnotes() {
+        let content = "See [docs](/docs), ![cat](img/cat.png) and [top](#top).\n\n\
+                       Second[^b] then first[^a] and again[^b], missing[^x].\n\n\
+                       [^a]: Alpha.\n\n[^b]: Beta.\n";
+        let body = render_body(
+            content,
+            Url::parse("https://example.com/articles/fish")
+                .ok()
+                .as_ref(),
+            &HashSet::new(),
+            None,
+            &[],
+        );
+
+        assert!(body.contains("href=\"https://example.com/docs\""));
+        assert!(body.contains("src=\"https://example.com/articles/img/cat.png\""));
+        assert!(body.contains("href=\"#top\""));
+
+        // `b` is cited first, so it is footnote 1 and listed first.
+        assert!(
+            body.contains(
+                "Second<sup class=\"footnote-ref\" id=\"fnref-1\"><a href=\"#fn-1\">1</a>"
+            )
+        );
+        assert!(body.contains("first<sup class=\"footnote-ref\" id=\"fnref-2\">"));
+        assert!(body.contains("missing[^x]"));
+        let beta = body.find("<li id=\"fn-1\">\n<p>Beta.").unwrap();
+        let alpha = body.find("<li id=\"fn-2\">\n<p>Alpha.").unwrap();
+        assert!(beta < alpha);
+        assert!(body.contains("Beta. <a href=\"#fnref-1\" class=\"footnote-backref\">↩</a></p>"));
+    }
+
+    #[test]
+    fn links_the_first_occurrence_of_glossary_terms() {
+        let entries = [
+            GlossaryEntry {
+                slug: "borrow-checker".to_string(),
+                term: "Borrow checker".to_string(),
+                aliases: Vec::new(),
+                definition: String::new(),
+            },
+            GlossaryEntry {
+                slug: "cpp".to_string(),
+                term: "C++".to_string(),
+                aliases: vec!["borrow".to_string()],
+                definition: String::new(),
+            },
+        ];
+        let glossary = GlossaryLinker::new(&entries, "https://example.com/glossary").unwrap();
+        let content = "# Borrow checker\n\n\
+                       The [borrow checker](/x) and `borrow checker` in code.\n\n\
+                       Then the borrow checker, C++ and the Borrow checker again; \
+                       borrowing is not a term.\n";
+        let body = render_body(content, None, &HashSet::new(), Some(&glossary), &[]);
+
+        assert!(body.contains("<h1>Borrow checker</h1>"));
+        assert!(body.contains("<code>borrow checker</code>"));
+        assert!(body.contains(
+            "Then the <a href=\"https://example.com/glossary#borrow-checker\">borrow checker</a>, \
+             <a href=\"https://example.com/glossary#cpp\">C++</a> and the Borrow checker again"
+        ));
+        assert!(!body.contains("<a href=\"https://example.com/glossary#cpp\">borrow"));
+    }
+
+    #[test]
+    fn numbers_citations_and_lists_references() {
+        let references = [
+            Reference {
+                id: "knuth84".to_string(),
+                title: "Literate Programming".to_string(),
+                authors: vec!["Donald E. Knuth".to_string()],
+                year: Some(1984),
+                venue: None,
+                url: Some("https://example.com/lp".to_string()),
+                doi: None,
+            },
+            Reference {
+                id: "sicp".to_string(),
+                title: "SICP".to_string(),
+                authors: Vec::new(),
+                year: None,
+                venue: None,
+                url: None,
+                doi: None,
+            },
+            Reference {
+                id: "unused".to_string(),
+                title: "Never cited".to_string(),
+                authors: Vec::new(),
+                year: None,
+                venue: None,
+                url: None,
+                doi: None,
+            },
+        ];
+        let content = "As shown [@sicp; @knuth84], and again [@knuth84]. \
+                       Unknown [@nobody] stays, as does `[@sicp]`.\n";
+        let body = render_body(content, None, &HashSet::new(), None, &references);
+
+        assert!(body.contains(
+            "As shown <sup class=\"citation\">[<a href=\"#ref-1\">1</a>, \
+             <a href=\"#ref-2\">2</a>]</sup>, and again \
+             <sup class=\"citation\">[<a href=\"#ref-2\">2</a>]</sup>."
+        ));
+        assert!(body.contains("Unknown [@nobody] stays"));
+        assert!(body.contains("<code>[@sicp]</code>"));
+        assert!(body.contains(
+            "<li id=\"ref-1\">SICP.</li>\n\
+             <li id=\"ref-2\">Donald E. Knuth. Literate Programming. 1984. \
+             <a href=\"https://example.com/lp\">https://example.com/lp</a></li>\n\
+             <li id=\"ref-3\">Never cited.</li>"
+        ));
+    }
+
+    #[test]
+    fn extracts_fenced_code_blocks_with_their_lines() {
+        let content = "Intro\n\n```rust title=main.rs\nfn main() {}\n\nlet x = 1;\n```\n\n\
+                       Indented:\n\n    not a snippet\n\n- item\n\n  ~~~\n  plain\n  ~~~\n";
+        assert_eq!(
+            code_snippets(content),
+            vec![
+                CodeSnippet {
+                    anchor: "snippet-1".to_string(),
+                    language: Some("rust".to_string()),
+                    start_line: 4,
+                    end_line: 6,
+                    code: "fn main() {}\n\nlet x = 1;\n".to_string(),
+                },
+                CodeSnippet {
+                    anchor: "snippet-2".to_string(),
+                    language: None,
+                    start_line: 16,
+                    end_line: 16,
+                    code: "plain\n".to_string(),
+                },
+            ]
+        );
+    }
+}
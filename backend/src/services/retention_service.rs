@@ -0,0 +1,86 @@
+use crate::db::DbPool;
+
+/// Rows the retention purge deleted or anonymized.
+#[derive(Debug, Default, PartialEq)]
+pub struct PurgeReport {
+    pub article_views: u64,
+    pub api_key_usage: u64,
+    pub poll_votes: u64,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> u64 {
+        self.article_views + self.api_key_usage + self.poll_votes
+    }
+}
+
+/// Deletes article views and API key usage older than `days`, and replaces
+/// the address key of older anonymous poll votes so the vote still counts.
+pub async fn purge_expired(pool: &DbPool, days: u32) -> Result<PurgeReport, sqlx::Error> {
+    let modifier = format!("-{} days", days);
+    let mut tx = pool.begin().await?;
+    let article_views = sqlx::query("DELETE FROM article_views WHERE viewed_on < date('now', ?)")
+        .bind(&modifier)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let api_key_usage = sqlx::query("DELETE FROM api_key_usage WHERE day < date('now', ?)")
+        .bind(&modifier)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let poll_votes = sqlx::query(
+        "UPDATE poll_votes SET voter_key = 'expired:' || id \
+         WHERE voter_key LIKE 'ip:%' AND created_at < datetime('now', ?)",
+    )
+    .bind(&modifier)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(PurgeReport {
+        article_views,
+        api_key_usage,
+        poll_votes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn only_rows_past_the_window_are_purged() {
+        let pool = memory_db().await;
+        for sql in [
+            "INSERT INTO article_views (slug, viewed_on) VALUES ('a', date('now', '-40 days'))",
+            "INSERT INTO article_views (slug, viewed_on) VALUES ('a', date('now'))",
+            "INSERT INTO api_keys (name, token_hash) VALUES ('k', 'h')",
+            "INSERT INTO api_key_usage (key_id, day) VALUES (1, date('now', '-40 days'))",
+            "INSERT INTO poll_votes (poll_id, option_index, voter_key, created_at) \
+             VALUES ('p', 0, 'ip:abc', datetime('now', '-40 days'))",
+            "INSERT INTO poll_votes (poll_id, option_index, voter_key, created_at) \
+             VALUES ('p', 1, 'user:1', datetime('now', '-40 days'))",
+            "INSERT INTO poll_votes (poll_id, option_index, voter_key) VALUES ('p', 0, 'ip:def')",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+
+        let report = purge_expired(&pool, 30).await.unwrap();
+        assert_eq!(
+            report,
+            PurgeReport {
+                article_views: 1,
+                api_key_usage: 1,
+                poll_votes: 1,
+            }
+        );
+        let (votes,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM poll_votes")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(votes, 3);
+        assert_eq!(purge_expired(&pool, 30).await.unwrap().total(), 0);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::db::DbPool;
+use crate::models::comment::Comment;
+use crate::models::saved_search::SavedSearch;
+
+const COLUMNS: &str = "id, name, query, notify, last_comment_id, created_at";
+
+/// Saves a query. Comments posted before it are not reported.
+pub async fn create_saved_search(
+    pool: &DbPool,
+    name: &str,
+    query: &str,
+    notify: bool,
+) -> Result<SavedSearch, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "INSERT INTO saved_searches (name, query, notify, last_comment_id) \
+         VALUES (?, ?, ?, (SELECT COALESCE(MAX(id), 0) FROM comments)) RETURNING {}",
+        COLUMNS
+    ))
+    .bind(name)
+    .bind(query)
+    .bind(notify)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn list_saved_searches(pool: &DbPool) -> Result<Vec<SavedSearch>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM saved_searches ORDER BY id",
+        COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Removes a saved search and what it has matched. Returns `false` when it
+/// does not exist.
+pub async fn delete_saved_search(pool: &DbPool, id: i64) -> Result<bool, sqlx::Error> {
+    sqlx::query("DELETE FROM saved_search_hits WHERE saved_search_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    let deleted = sqlx::query("DELETE FROM saved_searches WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+/// Remembers `slugs` as matches of a saved search and returns those it had
+/// not matched before, in the given order.
+pub async fn record_hits(
+    pool: &DbPool,
+    id: i64,
+    slugs: &[String],
+) -> Result<Vec<String>, sqlx::Error> {
+    let mut new = Vec::new();
+    for slug in slugs {
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO saved_search_hits (saved_search_id, slug) VALUES (?, ?)",
+        )
+        .bind(id)
+        .bind(slug)
+        .execute(pool)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            new.push(slug.clone());
+        }
+    }
+    Ok(new)
+}
+
+/// Comments posted since the saved search was last checked that match its
+/// query, and moves it past them.
+pub async fn new_matching_comments(
+    pool: &DbPool,
+    search: &SavedSearch,
+) -> Result<Vec<Comment>, sqlx::Error> {
+    let comments: Vec<Comment> = sqlx::query_as(
+        "SELECT id, article_slug, author_github_id, content, created_at, author_name \
+         FROM comments WHERE id > ? ORDER BY id",
+    )
+    .bind(search.last_comment_id)
+    .fetch_all(pool)
+    .await?;
+    if let Some(last) = comments.last() {
+        sqlx::query("UPDATE saved_searches SET last_comment_id = ? WHERE id = ?")
+            .bind(last.id)
+            .bind(search.id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(comments
+        .into_iter()
+        .filter(|c| matches_query(&search.query, &c.content))
+        .collect())
+}
+
+/// Whether `text` contains every word of `query`, ignoring case. Comments
+/// are not in the search index, so they are matched this way instead.
+pub fn matches_query(query: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    let mut words = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .peekable();
+    words.peek().is_some() && words.all(|w| text.contains(&w.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn reports_only_what_is_new_since_the_last_check() {
+        let pool = memory_db().await;
+        let search = create_saved_search(&pool, "Crabs", "ferris", true)
+            .await
+            .unwrap();
+        let slugs = ["hello-world".to_string(), "notes/til".to_string()];
+        assert_eq!(
+            record_hits(&pool, search.id, &slugs[..1])
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            record_hits(&pool, search.id, &slugs).await.unwrap(),
+            ["notes/til"]
+        );
+
+        for content in ["<p>Ferris waves</p>", "<p>Nothing to see</p>"] {
+            sqlx::query(
+                "INSERT INTO comments (article_slug, author_github_id, content) VALUES ('a', 1, ?)",
+            )
+            .bind(content)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let comments = new_matching_comments(&pool, &search).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        let search = list_saved_searches(&pool).await.unwrap().remove(0);
+        assert!(
+            new_matching_comments(&pool, &search)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(delete_saved_search(&pool, search.id).await.unwrap());
+        assert!(!delete_saved_search(&pool, search.id).await.unwrap());
+    }
+
+    #[test]
+    fn every_word_of_the_query_must_appear() {
+        assert!(matches_query(
+            "Rust crab",
+            "A crab named ferris writes rust"
+        ));
+        assert!(!matches_query("rust crab", "Only rust here"));
+        assert!(!matches_query("  ", "Anything"));
+    }
+}
@@ -1,13 +1,37 @@
-use crate::models::article::ArticleContent;
+use crate::models::article::{Article, ArticleContent};
+use crate::models::search_consistency::IndexConsistency;
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::directory::{Directory, RamDirectory};
+use tantivy::query::{
+    AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery,
+};
 use tantivy::schema::*;
-use tantivy::{Index, ReloadPolicy, TantivyDocument, Term, doc};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term, doc};
 use thiserror::Error;
 use tokio::sync::RwLock;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Searches kept with their time, newest last.
+const MAX_RECENT_SEARCHES: usize = 1000;
+
+/// The file naming the live segments of an index.
+const META_FILE: &str = "meta.json";
+
+/// `{index_dir}.{suffix}`, where a rebuild stages the new index and moves
+/// the old one aside.
+fn sibling_dir(index_dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = index_dir.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", suffix));
+    index_dir.with_file_name(name)
+}
 
 #[derive(Error, Debug)]
 pub enum SearchError {
@@ -17,9 +41,13 @@ pub enum SearchError {
     QueryParseError(#[from] tantivy::query::QueryParserError),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Archive error: {0}")]
+    ArchiveError(#[from] zip::result::ZipError),
+    #[error("Invalid index snapshot: {0}")]
+    InvalidSnapshot(String),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SearchResult {
     pub slug: String,
     pub title: String,
@@ -35,10 +63,110 @@ pub struct SearchStats {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
-pub struct SearchService {
+/// The content collections sharing the index. Note and page documents are
+/// keyed by their path under `notes/` and `pages/`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexCollection {
+    Articles,
+    Notes,
+    Pages,
+}
+
+impl IndexCollection {
+    pub const ALL: [IndexCollection; 3] = [
+        IndexCollection::Articles,
+        IndexCollection::Notes,
+        IndexCollection::Pages,
+    ];
+
+    pub fn of(slug: &str) -> Self {
+        Self::split(slug).0
+    }
+
+    /// The collection of an indexed slug and the path of the entry in it.
+    pub fn split(slug: &str) -> (Self, &str) {
+        if let Some(path) = slug.strip_prefix("notes/") {
+            (IndexCollection::Notes, path)
+        } else if let Some(path) = slug.strip_prefix("pages/") {
+            (IndexCollection::Pages, path)
+        } else {
+            (IndexCollection::Articles, slug)
+        }
+    }
+
+    /// The slug `entry` of this collection is indexed under.
+    pub fn slug_of(self, entry: &Article) -> String {
+        match self {
+            IndexCollection::Articles => entry.slug_with_category(),
+            IndexCollection::Notes | IndexCollection::Pages => {
+                format!("{}/{}", self.as_str(), entry.slug_with_category())
+            }
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IndexCollection::Articles => "articles",
+            IndexCollection::Notes => "notes",
+            IndexCollection::Pages => "pages",
+        }
+    }
+}
+
+/// The stored fields of a document in the index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedDocument {
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+}
+
+/// The public index and the author's, with their readers. A full rebuild
+/// fills a new pair and swaps it in whole.
+struct Generation {
     index: Index,
-    reader: tantivy::IndexReader,
+    reader: IndexReader,
     query_parser: QueryParser,
+    author_index: Index,
+    author_reader: IndexReader,
+    author_query_parser: QueryParser,
+}
+
+impl Generation {
+    fn new(
+        index: Index,
+        author_index: Index,
+        default_fields: &[Field],
+    ) -> Result<Self, SearchError> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let query_parser = QueryParser::for_index(&index, default_fields.to_vec());
+        let author_reader = author_index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let author_query_parser = QueryParser::for_index(&author_index, default_fields.to_vec());
+        Ok(Generation {
+            index,
+            reader,
+            query_parser,
+            author_index,
+            author_reader,
+            author_query_parser,
+        })
+    }
+}
+
+/// Alongside the index on disk, which only holds published entries, the
+/// service keeps one in memory that also holds drafts, scheduled and
+/// archived entries for the author. Both are written together, and the
+/// second is rebuilt from the stores on startup.
+pub struct SearchService {
+    index_dir: PathBuf,
+    live: std::sync::RwLock<Arc<Generation>>,
     slug_field: Field,
     title_field: Field,
     content_field: Field,
@@ -47,13 +175,24 @@ pub struct SearchService {
     category_field: Field,
     search_stats: RwLock<HashMap<String, usize>>,
     recent_searches: RwLock<Vec<SearchStats>>,
+    last_commit: std::sync::RwLock<[Option<DateTime<Utc>>; 3]>,
+    /// Held while the index on disk is written, so snapshots and restores
+    /// see no commit halfway.
+    write_lock: Mutex<()>,
 }
 
 impl SearchService {
-    pub fn new(index_dir: &str) -> Result<Self, SearchError> {
+    pub fn new(index_dir: impl AsRef<Path>) -> Result<Self, SearchError> {
         let schema = Self::build_schema();
 
-        let index_path = Path::new(index_dir);
+        let index_path = index_dir.as_ref();
+        // Left behind when a rebuild was interrupted.
+        for suffix in ["next", "old"] {
+            let dir = sibling_dir(index_path, suffix);
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
         let index = if index_path.exists() {
             Index::open_in_dir(index_path)?
         } else {
@@ -68,20 +207,16 @@ impl SearchService {
         let tags_field = schema.get_field("tags")?;
         let category_field = schema.get_field("category")?;
 
-        let reader = index
-            .reader_builder()
-            .reload_policy(ReloadPolicy::Manual)
-            .try_into()?;
-
-        let query_parser = QueryParser::for_index(
-            &index,
-            vec![title_field, content_field, description_field, tags_field],
-        );
+        let author_index = Index::create_in_ram(index.schema());
+        let generation = Generation::new(
+            index,
+            author_index,
+            &[title_field, content_field, description_field, tags_field],
+        )?;
 
         Ok(SearchService {
-            index,
-            reader,
-            query_parser,
+            index_dir: index_path.to_path_buf(),
+            live: std::sync::RwLock::new(Arc::new(generation)),
             slug_field,
             title_field,
             content_field,
@@ -90,9 +225,25 @@ impl SearchService {
             category_field,
             search_stats: RwLock::new(HashMap::new()),
             recent_searches: RwLock::new(Vec::new()),
+            last_commit: std::sync::RwLock::new([None; 3]),
+            write_lock: Mutex::new(()),
         })
     }
 
+    /// The indexes searches are answered from.
+    fn live(&self) -> Arc<Generation> {
+        Arc::clone(&self.live.read().unwrap())
+    }
+
+    fn default_fields(&self) -> [Field; 4] {
+        [
+            self.title_field,
+            self.content_field,
+            self.description_field,
+            self.tags_field,
+        ]
+    }
+
     fn build_schema() -> Schema {
         let mut schema_builder = Schema::builder();
 
@@ -106,35 +257,100 @@ impl SearchService {
         schema_builder.build()
     }
 
+    fn document(&self, article: &ArticleContent) -> TantivyDocument {
+        let tags_text = article.metadata.tags.join(" ");
+        let category_text = article.metadata.category.as_deref().unwrap_or("");
+        doc!(
+            self.slug_field => article.slug.clone(),
+            self.title_field => article.metadata.title.clone(),
+            self.content_field => article.content.clone(),
+            self.description_field => article.metadata.description.clone(),
+            self.tags_field => tags_text,
+            self.category_field => category_text,
+        )
+    }
+
+    /// Adds `article` to the index being written; the public one only
+    /// takes it while it is published.
+    fn add(
+        &self,
+        writer: &mut IndexWriter,
+        article: &ArticleContent,
+        public: bool,
+    ) -> Result<(), SearchError> {
+        if !public || article.metadata.is_published() {
+            writer.add_document(self.document(article))?;
+        }
+        Ok(())
+    }
+
+    /// Applies `update` to the public index, then to the author's one,
+    /// committing each.
+    fn write(
+        &self,
+        heap_size: usize,
+        update: impl Fn(&mut IndexWriter, bool) -> Result<(), SearchError>,
+    ) -> Result<(), SearchError> {
+        let _paused = self.write_lock.lock().unwrap();
+        let live = self.live();
+        for (index, reader, public) in [
+            (&live.index, &live.reader, true),
+            (&live.author_index, &live.author_reader, false),
+        ] {
+            let mut index_writer = index.writer(heap_size)?;
+            update(&mut index_writer, public)?;
+            index_writer.commit()?;
+            reader.reload()?;
+        }
+        Ok(())
+    }
+
+    /// Rebuilds both indexes from `articles`. The new public index is
+    /// written in a sibling directory that replaces the live one once
+    /// committed, so searches are answered from the old indexes until the
+    /// new ones are complete. Other writes wait for the rebuild.
     pub fn index_articles(
         &self,
         articles: &[ArticleContent],
         heap_size: usize,
     ) -> Result<(), SearchError> {
-        let mut index_writer = self.index.writer(heap_size)?;
-
-        index_writer.delete_all_documents()?;
-
-        for article in articles {
-            if !article.metadata.draft {
-                let tags_text = article.metadata.tags.join(" ");
-                let category_text = article.metadata.category.as_deref().unwrap_or("");
-
-                let doc = doc!(
-                    self.slug_field => article.slug.clone(),
-                    self.title_field => article.metadata.title.clone(),
-                    self.content_field => article.content.clone(),
-                    self.description_field => article.metadata.description.clone(),
-                    self.tags_field => tags_text,
-                    self.category_field => category_text,
-                );
-
-                index_writer.add_document(doc)?;
+        let _paused = self.write_lock.lock().unwrap();
+        let next_dir = sibling_dir(&self.index_dir, "next");
+        if next_dir.exists() {
+            std::fs::remove_dir_all(&next_dir)?;
+        }
+        std::fs::create_dir_all(&next_dir)?;
+        let schema = self.live().index.schema();
+        let author_index = Index::create_in_ram(schema.clone());
+        {
+            let index = Index::create_in_dir(&next_dir, schema)?;
+            for (index, public) in [(&index, true), (&author_index, false)] {
+                let mut index_writer = index.writer(heap_size)?;
+                for article in articles {
+                    self.add(&mut index_writer, article, public)?;
+                }
+                index_writer.commit()?;
+                index_writer.wait_merging_threads()?;
             }
         }
 
-        index_writer.commit()?;
-        self.reader.reload()?;
+        // Open files of the old index stay readable after the rename, so
+        // searches still running on it finish undisturbed.
+        let old_dir = sibling_dir(&self.index_dir, "old");
+        if self.index_dir.exists() {
+            std::fs::rename(&self.index_dir, &old_dir)?;
+        }
+        std::fs::rename(&next_dir, &self.index_dir)?;
+        let generation = Generation::new(
+            Index::open_in_dir(&self.index_dir)?,
+            author_index,
+            &self.default_fields(),
+        )?;
+        *self.live.write().unwrap() = Arc::new(generation);
+        if let Err(e) = std::fs::remove_dir_all(&old_dir) {
+            tracing::warn!("Failed to remove the replaced search index: {}", e);
+        }
+        self.mark_committed(IndexCollection::ALL);
         Ok(())
     }
 
@@ -143,77 +359,186 @@ impl SearchService {
         article: &ArticleContent,
         heap_size: usize,
     ) -> Result<(), SearchError> {
-        let mut index_writer = self.index.writer(heap_size)?;
-        let term = Term::from_field_text(self.slug_field, &article.slug);
-        index_writer.delete_term(term);
-
-        if !article.metadata.draft {
-            let tags_text = article.metadata.tags.join(" ");
-            let category_text = article.metadata.category.as_deref().unwrap_or("");
-
-            let doc = doc!(
-                self.slug_field => article.slug.clone(),
-                self.title_field => article.metadata.title.clone(),
-                self.content_field => article.content.clone(),
-                self.description_field => article.metadata.description.clone(),
-                self.tags_field => tags_text,
-                self.category_field => category_text,
-            );
-
-            index_writer.add_document(doc)?;
-        }
-
-        index_writer.commit()?;
-        self.reader.reload()?;
+        self.write(heap_size, |index_writer, public| {
+            index_writer.delete_term(Term::from_field_text(self.slug_field, &article.slug));
+            self.add(index_writer, article, public)
+        })?;
+        self.mark_committed([IndexCollection::of(&article.slug)]);
         Ok(())
     }
 
     pub fn remove_article(&self, slug: &str, heap_size: usize) -> Result<(), SearchError> {
-        let mut index_writer = self.index.writer::<TantivyDocument>(heap_size)?;
-        let term = Term::from_field_text(self.slug_field, slug);
-        index_writer.delete_term(term);
-        index_writer.commit()?;
-        self.reader.reload()?;
+        self.write(heap_size, |index_writer, _| {
+            index_writer.delete_term(Term::from_field_text(self.slug_field, slug));
+            Ok(())
+        })?;
+        self.mark_committed([IndexCollection::of(slug)]);
         Ok(())
     }
 
+    /// Removes the entries in `to_remove` and reindexes those in
+    /// `to_index`. Unpublished entries are indexed for the author only.
     pub fn apply_batch(
         &self,
         to_index: &[ArticleContent],
         to_remove: &[String],
         heap_size: usize,
     ) -> Result<(), SearchError> {
-        let mut index_writer = self.index.writer(heap_size)?;
+        self.write(heap_size, |index_writer, public| {
+            for slug in to_remove {
+                index_writer.delete_term(Term::from_field_text(self.slug_field, slug));
+            }
+            for article in to_index {
+                index_writer.delete_term(Term::from_field_text(self.slug_field, &article.slug));
+                self.add(index_writer, article, public)?;
+            }
+            Ok(())
+        })?;
+        self.mark_committed(
+            to_index
+                .iter()
+                .map(|a| a.slug.as_str())
+                .chain(to_remove.iter().map(String::as_str))
+                .map(IndexCollection::of),
+        );
+        Ok(())
+    }
 
-        for slug in to_remove {
-            let term = Term::from_field_text(self.slug_field, slug);
-            index_writer.delete_term(term);
+    fn mark_committed(&self, collections: impl IntoIterator<Item = IndexCollection>) {
+        let now = Utc::now();
+        let mut last_commit = self.last_commit.write().unwrap();
+        for collection in collections {
+            last_commit[collection as usize] = Some(now);
         }
+    }
 
-        for article in to_index {
-            let term = Term::from_field_text(self.slug_field, &article.slug);
-            index_writer.delete_term(term);
+    /// Time of the last successful index commit made by this process.
+    pub fn last_commit_at(&self) -> Option<DateTime<Utc>> {
+        self.last_commit
+            .read()
+            .unwrap()
+            .iter()
+            .flatten()
+            .max()
+            .copied()
+    }
 
-            if !article.metadata.draft {
-                let tags_text = article.metadata.tags.join(" ");
-                let category_text = article.metadata.category.as_deref().unwrap_or("");
+    /// Time of the last successful commit that touched `collection`.
+    pub fn last_commit_for(&self, collection: IndexCollection) -> Option<DateTime<Utc>> {
+        self.last_commit.read().unwrap()[collection as usize]
+    }
 
-                let doc = doc!(
-                    self.slug_field => article.slug.clone(),
-                    self.title_field => article.metadata.title.clone(),
-                    self.content_field => article.content.clone(),
-                    self.description_field => article.metadata.description.clone(),
-                    self.tags_field => tags_text,
-                    self.category_field => category_text,
-                );
+    /// Zips the files of the last commit of the index on disk: `meta.json`
+    /// and the segments it names. Writes wait until the archive is built.
+    pub fn snapshot(&self) -> Result<Vec<u8>, SearchError> {
+        let _paused = self.write_lock.lock().unwrap();
+        let live = self.live();
+        let directory = live.index.directory();
+        let mut files: Vec<PathBuf> = live
+            .index
+            .searchable_segment_metas()?
+            .iter()
+            .flat_map(|segment| segment.list_files())
+            .collect();
+        files.sort();
+        files.push(PathBuf::from(META_FILE));
 
-                index_writer.add_document(doc)?;
+        // Segment files are compressed already.
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for path in files {
+            // Segments only have the components they need, such as deletes.
+            if !directory
+                .exists(&path)
+                .map_err(tantivy::TantivyError::from)?
+            {
+                continue;
             }
+            let bytes = directory
+                .atomic_read(&path)
+                .map_err(tantivy::TantivyError::from)?;
+            zip.start_file(path.to_string_lossy(), options)?;
+            zip.write_all(&bytes)?;
         }
+        Ok(zip.finish()?.into_inner())
+    }
 
-        index_writer.commit()?;
-        self.reader.reload()?;
-        Ok(())
+    /// Replaces the index on disk with one archived by [`snapshot`](Self::snapshot)
+    /// and returns how many documents it holds. The archive is opened in
+    /// memory first, so one that is incomplete or built for another schema
+    /// leaves the index untouched. Files of the replaced commit are removed
+    /// by the next one.
+    pub fn restore(&self, archive: &[u8]) -> Result<u64, SearchError> {
+        let invalid = |message: &str| SearchError::InvalidSnapshot(message.to_string());
+        let mut zip = ZipArchive::new(Cursor::new(archive))?;
+        let mut files = Vec::new();
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            let name = file.name().to_string();
+            if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+                return Err(invalid(&format!("unexpected file {}", name)));
+            }
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            files.push((PathBuf::from(name), bytes));
+        }
+        // meta.json goes last, so the new segments are complete once it
+        // points at them.
+        files.sort_by_key(|(path, _)| path == Path::new(META_FILE));
+        if files
+            .last()
+            .is_none_or(|(path, _)| path != Path::new(META_FILE))
+        {
+            return Err(invalid("meta.json is missing"));
+        }
+
+        let staged = RamDirectory::create();
+        for (path, bytes) in &files {
+            staged.atomic_write(path, bytes)?;
+        }
+        let candidate = Index::open(staged).map_err(|e| invalid(&e.to_string()))?;
+        let live = self.live();
+        if candidate.schema() != live.index.schema() {
+            return Err(invalid("the index was built for another schema"));
+        }
+        let documents = candidate
+            .reader()
+            .map_err(|e| invalid(&e.to_string()))?
+            .searcher()
+            .num_docs();
+
+        let _paused = self.write_lock.lock().unwrap();
+        // A rebuild may have replaced the indexes meanwhile.
+        let live = self.live();
+        let directory = live.index.directory();
+        for (path, bytes) in &files {
+            directory.atomic_write(path, bytes)?;
+        }
+        live.reader.reload()?;
+        Ok(documents)
+    }
+
+    /// Every live document in the public index, as of the last commit.
+    pub fn indexed_documents(&self) -> Result<Vec<IndexedDocument>, SearchError> {
+        let searcher = self.live().reader.searcher();
+        let addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+        let text = |doc: &TantivyDocument, field| {
+            doc.get_first(field)
+                .and_then(|f| f.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let mut documents = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            documents.push(IndexedDocument {
+                slug: text(&doc, self.slug_field),
+                title: text(&doc, self.title_field),
+                description: text(&doc, self.description_field),
+                category: text(&doc, self.category_field),
+            });
+        }
+        Ok(documents)
     }
 
     pub async fn search(
@@ -222,52 +547,111 @@ impl SearchService {
         limit: usize,
         with_highlights: bool,
     ) -> Result<Vec<SearchResult>, SearchError> {
-        let searcher = self.reader.searcher();
+        let live = self.live();
+        let searcher = live.reader.searcher();
 
         self.record_search(query_text).await;
 
-        let query = self.query_parser.parse_query(query_text)?;
+        let query = live.query_parser.parse_query(query_text)?;
         let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
 
         let mut results = Vec::new();
 
-        for (_score, doc_address) in top_docs {
+        for (score, doc_address) in top_docs {
             let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
+            let mut result = self.to_result(&retrieved_doc, score);
+            if with_highlights {
+                result.highlights = Some(self.create_simple_highlights(
+                    query_text,
+                    &result.title,
+                    &result.description,
+                ));
+            }
+            results.push(result);
+        }
 
-            let slug = retrieved_doc
-                .get_first(self.slug_field)
-                .and_then(|f| f.as_str())
-                .unwrap_or("")
-                .to_string();
+        Ok(results)
+    }
 
-            let title = retrieved_doc
-                .get_first(self.title_field)
-                .and_then(|f| f.as_str())
-                .unwrap_or("")
-                .to_string();
+    /// Searches every entry the author can edit, drafts, scheduled and
+    /// archived ones included. Unlike [`SearchService::search`], these
+    /// lookups are not counted as searches.
+    pub fn search_all(
+        &self,
+        query_text: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, SearchError> {
+        let live = self.live();
+        let searcher = live.author_reader.searcher();
+        let query = live.author_query_parser.parse_query(query_text)?;
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+        top_docs
+            .into_iter()
+            .map(|(score, doc_address)| {
+                let doc: TantivyDocument = searcher.doc(doc_address)?;
+                Ok(self.to_result(&doc, score))
+            })
+            .collect()
+    }
+
+    /// Completes what a reader is typing against indexed titles: every word
+    /// but the last must appear in the title, and the last may be cut short.
+    /// Unlike [`SearchService::search`], lookups are not counted as searches.
+    pub fn autocomplete(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>, SearchError> {
+        let live = self.live();
+        let mut words = Vec::new();
+        let mut tokenizer = live.index.tokenizer_for_field(self.title_field)?;
+        tokenizer
+            .token_stream(prefix)
+            .process(&mut |token| words.push(token.text.clone()));
+        let Some(last) = words.pop() else {
+            return Ok(Vec::new());
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = words
+            .iter()
+            .map(|word| {
+                let term = Term::from_field_text(self.title_field, word);
+                let query: Box<dyn Query> =
+                    Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                (Occur::Must, query)
+            })
+            .collect();
+        let last = Term::from_field_text(self.title_field, &last);
+        clauses.push((
+            Occur::Must,
+            Box::new(FuzzyTermQuery::new_prefix(last, 0, true)),
+        ));
+
+        let searcher = live.reader.searcher();
+        let top_docs = searcher.search(&BooleanQuery::new(clauses), &TopDocs::with_limit(limit))?;
+        top_docs
+            .into_iter()
+            .map(|(score, doc_address)| {
+                let doc: TantivyDocument = searcher.doc(doc_address)?;
+                Ok(self.to_result(&doc, score))
+            })
+            .collect()
+    }
 
-            let description = retrieved_doc
-                .get_first(self.description_field)
+    fn to_result(&self, doc: &TantivyDocument, score: f32) -> SearchResult {
+        let text = |field| {
+            doc.get_first(field)
                 .and_then(|f| f.as_str())
                 .unwrap_or("")
-                .to_string();
-
-            let highlights = if with_highlights {
-                Some(self.create_simple_highlights(query_text, &title, &description))
-            } else {
-                None
-            };
-
-            results.push(SearchResult {
-                slug,
-                title,
-                description,
-                score: _score,
-                highlights,
-            });
+                .to_string()
+        };
+        SearchResult {
+            slug: text(self.slug_field),
+            title: text(self.title_field),
+            description: text(self.description_field),
+            score,
+            highlights: None,
         }
-
-        Ok(results)
     }
 
     fn create_simple_highlights(&self, query: &str, title: &str, description: &str) -> Vec<String> {
@@ -304,9 +688,9 @@ impl SearchService {
         };
         recent.push(search_stat);
 
-        if recent.len() > 1000 {
+        if recent.len() > MAX_RECENT_SEARCHES {
             let len = recent.len();
-            recent.drain(0..len - 1000);
+            recent.drain(0..len - MAX_RECENT_SEARCHES);
         }
     }
 
@@ -318,6 +702,77 @@ impl SearchService {
         popular.sort_by(|a, b| b.1.cmp(&a.1));
         popular.into_iter().take(limit).collect()
     }
+
+    /// Recent searches made from `from` to `to`, both inclusive and in UTC,
+    /// oldest first.
+    pub async fn recent_searches(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Vec<SearchStats> {
+        let recent = self.recent_searches.read().await;
+        recent
+            .iter()
+            .filter(|s| {
+                let day = s.timestamp.date_naive();
+                from.is_none_or(|from| day >= from) && to.is_none_or(|to| day <= to)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Adds exported searches to the popular search counts and to the recent
+    /// searches, which keep the newest by time.
+    pub async fn import_searches(&self, searches: Vec<SearchStats>) {
+        let mut stats = self.search_stats.write().await;
+        for search in &searches {
+            *stats.entry(search.query.clone()).or_insert(0) += search.count;
+        }
+
+        let mut recent = self.recent_searches.write().await;
+        recent.extend(searches);
+        recent.sort_by_key(|s| s.timestamp);
+        if recent.len() > MAX_RECENT_SEARCHES {
+            let len = recent.len();
+            recent.drain(0..len - MAX_RECENT_SEARCHES);
+        }
+    }
+}
+
+/// Compares what should be searchable with what the index holds. Only the
+/// stored fields can be compared, so a body edit that left the title,
+/// description and category alone is not detected as stale.
+pub fn check_consistency(
+    expected: &[ArticleContent],
+    indexed: &[IndexedDocument],
+) -> IndexConsistency {
+    let mut documents: HashMap<&str, Vec<&IndexedDocument>> = HashMap::new();
+    for doc in indexed {
+        documents.entry(doc.slug.as_str()).or_default().push(doc);
+    }
+
+    let mut report = IndexConsistency::default();
+    for entry in expected.iter().filter(|e| e.metadata.is_published()) {
+        match documents.remove(entry.slug.as_str()).as_deref() {
+            None => report.missing.push(entry.slug.clone()),
+            Some([doc]) => {
+                let category = entry.metadata.category.as_deref().unwrap_or("");
+                if doc.title != entry.metadata.title
+                    || doc.description != entry.metadata.description
+                    || doc.category != category
+                {
+                    report.stale.push(entry.slug.clone());
+                }
+            }
+            Some(_) => report.stale.push(entry.slug.clone()),
+        }
+    }
+    report.orphans = documents.into_keys().map(str::to_string).collect();
+
+    report.missing.sort();
+    report.stale.sort();
+    report.orphans.sort();
+    report
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -338,3 +793,260 @@ impl Default for SearchQuery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::{ArticleStatus, Metadata};
+
+    fn entry(slug: &str, title: &str, draft: bool) -> ArticleContent {
+        ArticleContent {
+            slug: slug.to_string(),
+            metadata: Metadata {
+                title: title.to_string(),
+                author: "a".to_string(),
+                date: Utc::now(),
+                display_date: None,
+                tags: Vec::new(),
+                description: String::new(),
+                status: if draft {
+                    ArticleStatus::Draft
+                } else {
+                    ArticleStatus::Published
+                },
+                last_updated: None,
+                category: None,
+                poll: None,
+                lang: None,
+                translations: Default::default(),
+                private: false,
+                http_headers: Default::default(),
+                comments: Default::default(),
+                menu: None,
+                audio: None,
+                gallery: Vec::new(),
+                link: None,
+                syndication: Default::default(),
+                references: Vec::new(),
+                downloads: Vec::new(),
+                description_generated: false,
+                members_only: false,
+            },
+            content: "Body".to_string(),
+            edit_url: None,
+        }
+    }
+
+    fn doc(slug: &str, title: &str) -> IndexedDocument {
+        IndexedDocument {
+            slug: slug.to_string(),
+            title: title.to_string(),
+            description: String::new(),
+            category: String::new(),
+        }
+    }
+
+    #[test]
+    fn finds_missing_stale_and_orphaned_documents() {
+        let expected = [
+            entry("fresh", "Fresh", false),
+            entry("renamed", "New title", false),
+            entry("doubled", "Doubled", false),
+            entry("unindexed", "Unindexed", false),
+            entry("draft", "Draft", true),
+        ];
+        let indexed = [
+            doc("fresh", "Fresh"),
+            doc("renamed", "Old title"),
+            doc("doubled", "Doubled"),
+            doc("doubled", "Doubled"),
+            doc("draft", "Draft"),
+            doc("deleted", "Deleted"),
+        ];
+        assert_eq!(
+            check_consistency(&expected, &indexed),
+            IndexConsistency {
+                missing: vec!["unindexed".to_string()],
+                stale: vec!["doubled".to_string(), "renamed".to_string()],
+                orphans: vec!["deleted".to_string(), "draft".to_string()],
+            }
+        );
+        assert!(check_consistency(&expected[..1], &indexed[..1]).is_consistent());
+    }
+
+    #[tokio::test]
+    async fn autocompletes_the_last_word_of_titles() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = SearchService::new(dir.path().join("index")).unwrap();
+        let entries = [
+            entry("axum-routing", "Axum Routing", false),
+            entry("axum-state", "Sharing State in Axum", false),
+            entry("rust-traits", "Rust Traits", false),
+        ];
+        service.index_articles(&entries, 15_000_000).unwrap();
+
+        let slugs = |prefix: &str| {
+            let mut slugs: Vec<_> = service
+                .autocomplete(prefix, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.slug)
+                .collect();
+            slugs.sort();
+            slugs
+        };
+        assert_eq!(slugs("ax"), ["axum-routing", "axum-state"]);
+        assert_eq!(slugs("Axum rou"), ["axum-routing"]);
+        assert_eq!(slugs("rust trai"), ["rust-traits"]);
+        assert_eq!(slugs("routing ax"), ["axum-routing"]);
+        assert!(slugs("  ").is_empty());
+        assert!(service.get_popular_searches(10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn drafts_are_only_searchable_by_the_author() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = SearchService::new(dir.path().join("index")).unwrap();
+        let entries = [
+            entry("published", "Published body", false),
+            entry("draft", "Draft body", true),
+        ];
+        service.index_articles(&entries, 15_000_000).unwrap();
+        service
+            .apply_batch(&[entry("published", "Unpublished", true)], &[], 15_000_000)
+            .unwrap();
+
+        let slugs = |results: Vec<SearchResult>| -> Vec<String> {
+            let mut slugs: Vec<_> = results.into_iter().map(|r| r.slug).collect();
+            slugs.sort();
+            slugs
+        };
+        assert!(service.search("body", 10, false).await.unwrap().is_empty());
+        assert_eq!(
+            slugs(service.search_all("body", 10).unwrap()),
+            ["draft", "published"]
+        );
+        assert!(service.indexed_documents().unwrap().is_empty());
+
+        service.remove_article("draft", 15_000_000).unwrap();
+        assert_eq!(
+            slugs(service.search_all("body", 10).unwrap()),
+            ["published"]
+        );
+    }
+
+    #[tokio::test]
+    async fn rebuilds_swap_in_a_complete_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = dir.path().join("index");
+        let service = SearchService::new(&index_dir).unwrap();
+        service
+            .index_articles(&[entry("old", "Old body", false)], 15_000_000)
+            .unwrap();
+        let before = service.live();
+        service
+            .index_articles(&[entry("new", "New body", false)], 15_000_000)
+            .unwrap();
+
+        // Searches that started on the old index can still finish there.
+        let query = before.query_parser.parse_query("old").unwrap();
+        let hits = before
+            .reader
+            .searcher()
+            .search(&query, &TopDocs::with_limit(10))
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        let results = service.search("body", 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].slug, "new");
+        let entries: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, ["index"]);
+
+        // Later writes go to the swapped-in index on disk.
+        service
+            .apply_batch(&[entry("added", "Added body", false)], &[], 15_000_000)
+            .unwrap();
+        drop((before, service));
+        let service = SearchService::new(&index_dir).unwrap();
+        let mut slugs: Vec<_> = service
+            .indexed_documents()
+            .unwrap()
+            .into_iter()
+            .map(|d| d.slug)
+            .collect();
+        slugs.sort();
+        assert_eq!(slugs, ["added", "new"]);
+    }
+
+    #[tokio::test]
+    async fn restores_a_snapshot_over_another_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = SearchService::new(dir.path().join("source")).unwrap();
+        source
+            .index_articles(&[entry("kept", "Kept body", false)], 15_000_000)
+            .unwrap();
+        let target = SearchService::new(dir.path().join("target")).unwrap();
+        target
+            .index_articles(&[entry("replaced", "Replaced body", false)], 15_000_000)
+            .unwrap();
+
+        let archive = source.snapshot().unwrap();
+        assert_eq!(target.restore(&archive).unwrap(), 1);
+        let results = target.search("body", 10, false).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].slug, "kept");
+
+        // The restored index survives a restart and takes new commits.
+        drop(target);
+        let target = SearchService::new(dir.path().join("target")).unwrap();
+        target
+            .apply_batch(&[entry("added", "Added body", false)], &[], 15_000_000)
+            .unwrap();
+        assert_eq!(target.indexed_documents().unwrap().len(), 2);
+
+        assert!(matches!(
+            target.restore(b"not a zip"),
+            Err(SearchError::ArchiveError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn imported_searches_count_towards_popular_searches() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = SearchService::new(dir.path().join("index")).unwrap();
+        service.search("axum", 10, false).await.unwrap();
+
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        service
+            .import_searches(vec![
+                SearchStats {
+                    query: "axum".to_string(),
+                    count: 2,
+                    timestamp: at("2024-07-01T10:00:00Z"),
+                },
+                SearchStats {
+                    query: "tokio".to_string(),
+                    count: 1,
+                    timestamp: at("2024-07-02T10:00:00Z"),
+                },
+            ])
+            .await;
+
+        assert_eq!(
+            service.get_popular_searches(10).await,
+            [("axum".to_string(), 3), ("tokio".to_string(), 1)]
+        );
+        let day = NaiveDate::from_ymd_opt(2024, 7, 2);
+        let queries = |searches: Vec<SearchStats>| -> Vec<String> {
+            searches.into_iter().map(|s| s.query).collect()
+        };
+        assert_eq!(queries(service.recent_searches(day, day).await), ["tokio"]);
+        assert_eq!(
+            queries(service.recent_searches(None, None).await),
+            ["axum", "tokio", "axum"]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+const NONCE_LEN: usize = 24;
+
+/// The key given is not 64 hex characters.
+#[derive(Debug)]
+pub struct InvalidKey;
+
+/// Why a sealed payload could not be opened.
+#[derive(Debug)]
+pub enum OpenError {
+    /// Too short to hold a nonce, or not valid UTF-8 once opened.
+    Malformed,
+    /// Sealed with another key, or tampered with.
+    Decrypt,
+}
+
+/// XChaCha20-Poly1305 with a random nonce per message, shared by the
+/// ciphers for private notes and database fields. Sealed payloads are the
+/// nonce followed by the ciphertext; each cipher encodes them its own way.
+#[derive(Clone)]
+pub struct SecretBox {
+    cipher: XChaCha20Poly1305,
+}
+
+impl SecretBox {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(key)),
+        }
+    }
+
+    pub fn from_hex(key: &str) -> Result<Self, InvalidKey> {
+        let bytes: [u8; 32] = hex::decode(key.trim())
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(InvalidKey)?;
+        Ok(Self::new(&bytes))
+    }
+
+    /// Reads the key from the environment variable `name`. `Ok(None)` when
+    /// unset.
+    pub fn from_env(name: &str) -> Result<Option<Self>, InvalidKey> {
+        match std::env::var(name) {
+            Ok(key) => Self::from_hex(&key).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn seal(&self, plaintext: &str) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("XChaCha20-Poly1305 encryption is infallible for in-memory buffers");
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        payload
+    }
+
+    pub fn open(&self, payload: &[u8]) -> Result<String, OpenError> {
+        if payload.len() < NONCE_LEN {
+            return Err(OpenError::Malformed);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| OpenError::Decrypt)?;
+        String::from_utf8(plaintext).map_err(|_| OpenError::Malformed)
+    }
+}
@@ -0,0 +1,269 @@
+use crate::models::article::Metadata;
+use crate::models::seo::SeoReport;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+const TITLE_LENGTH: (usize, usize) = (30, 60);
+const DESCRIPTION_LENGTH: (usize, usize) = (70, 160);
+const KEYWORD_DENSITY: (f64, f64) = (0.5, 3.0);
+/// Articles longer than this should be broken up with subheadings.
+const WORDS_WITHOUT_HEADINGS: usize = 300;
+
+fn is_internal(url: &str, hostname: &str) -> bool {
+    if url.starts_with("mailto:") {
+        return false;
+    }
+    let hostname = hostname.trim_end_matches('/');
+    (!hostname.is_empty() && url.starts_with(hostname)) || !url.contains("://")
+}
+
+fn count_phrase(words: &[String], phrase: &[String]) -> usize {
+    if phrase.is_empty() || phrase.len() > words.len() {
+        return 0;
+    }
+    words.windows(phrase.len()).filter(|w| *w == phrase).count()
+}
+
+fn normalized_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Checks an article against common on-page SEO guidelines. `keyword`
+/// defaults to the first tag; `hostname` decides which absolute links count
+/// as internal.
+pub fn audit(
+    slug: &str,
+    metadata: &Metadata,
+    content: &str,
+    keyword: Option<&str>,
+    hostname: &str,
+) -> SeoReport {
+    let mut text = String::new();
+    let mut headings = Vec::new();
+    let mut images = 0;
+    let mut images_missing_alt = 0;
+    let mut internal_links = 0;
+    let mut external_links = 0;
+    let mut image_alt: Option<String> = None;
+
+    for event in Parser::new_ext(content, Options::all()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => headings.push(level as usize),
+            Event::Start(Tag::Image { .. }) => {
+                images += 1;
+                image_alt = Some(String::new());
+            }
+            Event::End(TagEnd::Image) => {
+                let alt = image_alt.take().unwrap_or_default();
+                images_missing_alt += usize::from(alt.trim().is_empty());
+            }
+            // In-page anchors are neither internal nor external links.
+            Event::Start(Tag::Link { dest_url, .. }) if !dest_url.starts_with('#') => {
+                if is_internal(&dest_url, hostname) {
+                    internal_links += 1;
+                } else {
+                    external_links += 1;
+                }
+            }
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for tag in html.split("<img").skip(1) {
+                    images += 1;
+                    let tag = tag.split('>').next().unwrap_or_default();
+                    if !tag.contains("alt=\"") || tag.contains("alt=\"\"") {
+                        images_missing_alt += 1;
+                    }
+                }
+            }
+            Event::Text(t) | Event::Code(t) => match image_alt.as_mut() {
+                Some(alt) => alt.push_str(&t),
+                None => {
+                    text.push_str(&t);
+                    text.push(' ');
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let words = normalized_words(&text);
+    let keyword = keyword
+        .map(str::to_string)
+        .or_else(|| metadata.tags.first().cloned())
+        .filter(|k| !k.trim().is_empty());
+    let keyword_density = keyword.as_ref().filter(|_| !words.is_empty()).map(|k| {
+        let hits = count_phrase(&words, &normalized_words(k));
+        (hits as f64 * 100.0 / words.len() as f64 * 100.0).round() / 100.0
+    });
+
+    let title_length = metadata.title.chars().count();
+    let description_length = metadata.description.chars().count();
+    let mut warnings = Vec::new();
+
+    if title_length < TITLE_LENGTH.0 || title_length > TITLE_LENGTH.1 {
+        warnings.push(format!(
+            "Title is {} characters; aim for {}-{}",
+            title_length, TITLE_LENGTH.0, TITLE_LENGTH.1
+        ));
+    }
+    if description_length == 0 {
+        warnings.push("Description is missing".to_string());
+    } else if description_length < DESCRIPTION_LENGTH.0 || description_length > DESCRIPTION_LENGTH.1
+    {
+        warnings.push(format!(
+            "Description is {} characters; aim for {}-{}",
+            description_length, DESCRIPTION_LENGTH.0, DESCRIPTION_LENGTH.1
+        ));
+    }
+    if headings.contains(&1) {
+        warnings.push("Body contains an H1; the title is already rendered as the H1".to_string());
+    }
+    if headings.is_empty() && words.len() > WORDS_WITHOUT_HEADINGS {
+        warnings.push(format!(
+            "No subheadings in {} words; break the text up with H2s",
+            words.len()
+        ));
+    }
+    let mut previous = 1;
+    for &level in &headings {
+        if level > previous + 1 {
+            warnings.push(format!(
+                "H{} follows H{}; heading levels are skipped",
+                level, previous
+            ));
+            break;
+        }
+        previous = level;
+    }
+    if images_missing_alt > 0 {
+        warnings.push(format!(
+            "{} of {} images have no alt text",
+            images_missing_alt, images
+        ));
+    }
+    if internal_links == 0 {
+        warnings.push("No internal links to other articles".to_string());
+    }
+    match (&keyword, keyword_density) {
+        (None, _) => warnings.push("No focus keyword; pass `keyword` or add a tag".to_string()),
+        (Some(k), Some(density)) => {
+            if density < KEYWORD_DENSITY.0 || density > KEYWORD_DENSITY.1 {
+                warnings.push(format!(
+                    "Keyword \"{}\" density is {}%; aim for {}-{}%",
+                    k, density, KEYWORD_DENSITY.0, KEYWORD_DENSITY.1
+                ));
+            }
+            if !metadata.title.to_lowercase().contains(&k.to_lowercase()) {
+                warnings.push(format!("Title does not contain the keyword \"{}\"", k));
+            }
+        }
+        (Some(_), None) => {}
+    }
+
+    SeoReport {
+        slug: slug.to_string(),
+        title_length,
+        description_length,
+        word_count: words.len(),
+        headings,
+        images,
+        images_missing_alt,
+        internal_links,
+        external_links,
+        keyword,
+        keyword_density,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::ArticleStatus;
+    use chrono::Utc;
+
+    fn metadata(title: &str, description: &str, tags: &[&str]) -> Metadata {
+        Metadata {
+            title: title.to_string(),
+            author: "a".to_string(),
+            date: Utc::now(),
+            display_date: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            description: description.to_string(),
+            status: ArticleStatus::Published,
+            last_updated: None,
+            category: None,
+            poll: None,
+            lang: None,
+            translations: Default::default(),
+            private: false,
+            http_headers: Default::default(),
+            comments: Default::default(),
+            menu: None,
+            audio: None,
+            gallery: Vec::new(),
+            link: None,
+            syndication: Default::default(),
+            references: Vec::new(),
+            downloads: Vec::new(),
+            description_generated: false,
+            members_only: false,
+        }
+    }
+
+    #[test]
+    fn collects_structure_links_and_images() {
+        let content = "## Intro\n\nRust is fast. Rust is safe.\n\n#### Deep\n\n\
+            ![](a.png) ![diagram](b.png) <img src=\"c.png\">\n\n\
+            [docs](/articles/docs) [site](https://example.com/x) \
+            [ext](https://rust-lang.org) [top](#intro)\n";
+        let report = audit(
+            "s",
+            &metadata("Rust", "", &["rust"]),
+            content,
+            None,
+            "https://example.com",
+        );
+        assert_eq!(report.headings, [2, 4]);
+        assert_eq!((report.images, report.images_missing_alt), (3, 2));
+        assert_eq!((report.internal_links, report.external_links), (2, 1));
+        assert_eq!(report.keyword.as_deref(), Some("rust"));
+        assert!(report.keyword_density.unwrap() > 3.0);
+        for expected in [
+            "Title is 4",
+            "Description is missing",
+            "H4 follows H2",
+            "2 of 3",
+            "density",
+        ] {
+            assert!(
+                report.warnings.iter().any(|w| w.contains(expected)),
+                "missing {:?} in {:?}",
+                expected,
+                report.warnings
+            );
+        }
+    }
+
+    #[test]
+    fn well_formed_article_has_no_warnings() {
+        let body = "filler words here ".repeat(30);
+        let content = format!(
+            "## Getting started with axum\n\n{} axum routing [more](/articles/next)\n",
+            body
+        );
+        let report = audit(
+            "s",
+            &metadata(
+                "Getting started with axum routing in Rust",
+                "A walkthrough of building routes, extracting parameters and sharing state with axum.",
+                &[],
+            ),
+            &content,
+            Some("axum"),
+            "",
+        );
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+}
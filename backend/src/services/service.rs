@@ -1,23 +1,80 @@
 use crate::handlers::error::LoadError;
-use crate::models::article::{Article, ArticleContent, Metadata};
+use crate::models::article::{Article, ArticleContent, Metadata, TermCount};
+use crate::models::store_stats::StoreStats;
+use crate::services::atomic_file::write_atomic;
+use crate::services::front_matter::{category_defaults, category_defaults_files, with_defaults};
+use crate::services::render_service::first_paragraph;
 use chrono::{DateTime, Utc};
 use gray_matter::Matter;
 use gray_matter::engine::YAML;
+use serde::{Deserialize, Serialize};
 use serde_yaml::from_value;
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 use walkdir::WalkDir;
 
 pub struct ArticleStore {
+    root: PathBuf,
+    nested_categories: bool,
     articles: Vec<Article>,
+    /// Keyed by `category/slug`, so equal file names in different
+    /// categories do not collide.
     slug_map: HashMap<String, usize>,
+    /// Bare slugs, for links that predate category-scoped keys. A slug used
+    /// in several categories points at the newest article.
+    bare_slugs: HashMap<String, usize>,
     pub tags: HashSet<String>,
     pub categories: HashSet<String>,
+    tag_counts: BTreeMap<String, usize>,
+    category_counts: BTreeMap<String, usize>,
+    restricted_categories: Vec<String>,
     file_cache: HashMap<String, SystemTime>,
+    /// Modification times of the partials the articles include and of the
+    /// category defaults files above them, `None` for ones that did not
+    /// exist, so editing either updates its articles.
+    partial_times: HashMap<String, Option<SystemTime>>,
     content_cache: Mutex<HashMap<String, String>>,
+    last_rebuild: Duration,
+}
+
+/// Bumped when the snapshot layout or the parsed form of an article
+/// changes.
+const SNAPSHOT_FORMAT: u32 = 4;
+
+/// Folder inside a content directory holding the snippets articles pull in
+/// with an `{{< include "name" >}}` line. It is not loaded as a category.
+pub const PARTIALS_DIR: &str = "partials";
+
+/// Longest description derived from an article's first paragraph, the
+/// most search engines show.
+const GENERATED_DESCRIPTION_CHARS: usize = 160;
+
+#[derive(Serialize, Deserialize)]
+struct StoreSnapshot {
+    format: u32,
+    crate_version: String,
+    root: PathBuf,
+    nested_categories: bool,
+    entries: Vec<SnapshotEntry>,
+    partials: HashMap<String, Option<SystemTime>>,
+}
+
+/// An [`Article`] with the fields its API form leaves out.
+#[derive(Serialize, Deserialize)]
+struct SnapshotEntry {
+    slug: String,
+    metadata: Metadata,
+    version: u64,
+    updated_at: DateTime<Utc>,
+    file_path: String,
+    last_modified: SystemTime,
+    includes: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -34,7 +91,15 @@ pub struct FileChangeInfo {
 }
 
 impl ArticleStore {
-    pub fn new(content_dir: &str, enable_nested_categories: bool) -> Result<Self, LoadError> {
+    /// Loads every article under `content_dir`. The store remembers the
+    /// directory, so later reloads and new files resolve against it rather
+    /// than the working directory.
+    pub fn new(
+        content_dir: impl AsRef<Path>,
+        enable_nested_categories: bool,
+    ) -> Result<Self, LoadError> {
+        let started = Instant::now();
+        let content_dir = content_dir.as_ref();
         let mut articles = Vec::new();
         let mut all_tags = HashSet::new();
         let mut all_categories = HashSet::new();
@@ -50,26 +115,166 @@ impl ArticleStore {
             Self::load_articles_flat(content_dir, &mut articles, &mut all_tags)?;
         }
 
-        articles.sort_by(|a, b| b.metadata.date.cmp(&a.metadata.date));
+        let mut store = Self::from_articles(content_dir, enable_nested_categories, articles);
+        store.rebuild_indexes();
+        store.last_rebuild = started.elapsed();
+        Ok(store)
+    }
 
-        let slug_map = articles
-            .iter()
-            .enumerate()
-            .map(|(idx, article)| (article.slug.clone(), idx))
-            .collect();
+    /// A store holding `articles` without reading any files, for tests of
+    /// code that queries one.
+    #[cfg(test)]
+    pub(crate) fn with_articles(articles: Vec<Article>) -> Self {
+        let mut store = Self::from_articles(Path::new(""), true, articles);
+        store.rebuild_indexes();
+        store
+    }
 
+    fn from_articles(content_dir: &Path, nested_categories: bool, articles: Vec<Article>) -> Self {
         let mut file_cache = HashMap::new();
         for article in &articles {
             file_cache.insert(article.file_path.clone(), article.last_modified);
         }
+        let partial_times = Self::partial_times(&articles);
 
-        Ok(Self {
+        Self {
+            root: content_dir.to_path_buf(),
+            nested_categories,
             articles,
-            slug_map,
-            tags: all_tags,
-            categories: all_categories,
+            slug_map: HashMap::new(),
+            bare_slugs: HashMap::new(),
+            tags: HashSet::new(),
+            categories: HashSet::new(),
+            tag_counts: BTreeMap::new(),
+            category_counts: BTreeMap::new(),
+            restricted_categories: Vec::new(),
             file_cache,
+            partial_times,
             content_cache: Mutex::new(HashMap::new()),
+            last_rebuild: Duration::ZERO,
+        }
+    }
+
+    /// Writes the parsed entries to `path`, so the next start can restore
+    /// them with [`ArticleStore::from_snapshot`] instead of parsing every
+    /// file. Bodies are not included.
+    pub fn save_snapshot(&self, path: &Path) -> io::Result<()> {
+        let snapshot = StoreSnapshot {
+            format: SNAPSHOT_FORMAT,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            root: self.root.clone(),
+            nested_categories: self.nested_categories,
+            entries: self
+                .articles
+                .iter()
+                .filter(|a| !a.deleted)
+                .map(|a| SnapshotEntry {
+                    slug: a.slug.clone(),
+                    metadata: a.metadata.clone(),
+                    version: a.version,
+                    updated_at: a.updated_at,
+                    file_path: a.file_path.clone(),
+                    last_modified: a.last_modified,
+                    includes: a.includes.clone(),
+                })
+                .collect(),
+            partials: self.partial_times.clone(),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(path, serde_json::to_vec(&snapshot)?)
+    }
+
+    /// Restores the store for `content_dir` from a snapshot written by
+    /// [`ArticleStore::save_snapshot`], then re-reads the files added,
+    /// modified or removed since, going by their modification times.
+    /// `None` when there is no usable snapshot of this directory, e.g. one
+    /// written by another version of the server.
+    pub fn from_snapshot(
+        path: &Path,
+        content_dir: impl AsRef<Path>,
+        enable_nested_categories: bool,
+    ) -> Option<Self> {
+        let started = Instant::now();
+        let content_dir = content_dir.as_ref();
+        let bytes = fs::read(path).ok()?;
+        let snapshot: StoreSnapshot = match serde_json::from_slice(&bytes) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable snapshot {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if snapshot.format != SNAPSHOT_FORMAT
+            || snapshot.crate_version != env!("CARGO_PKG_VERSION")
+            || snapshot.root != content_dir
+            || snapshot.nested_categories != enable_nested_categories
+        {
+            return None;
+        }
+
+        let articles = snapshot
+            .entries
+            .into_iter()
+            .map(|e| Article {
+                slug: e.slug,
+                metadata: e.metadata,
+                version: e.version,
+                updated_at: e.updated_at,
+                file_path: e.file_path,
+                last_modified: e.last_modified,
+                includes: e.includes,
+                deleted: false,
+            })
+            .collect();
+        let mut store = Self::from_articles(content_dir, enable_nested_categories, articles);
+        // Partials edited while the server was down are found against the
+        // times they had when the snapshot was written.
+        store.partial_times = snapshot.partials;
+        store.rebuild_indexes();
+        if let Err(e) = store.incremental_update() {
+            tracing::warn!("Failed to check snapshot {}: {:?}", path.display(), e);
+            return None;
+        }
+        store.last_rebuild = started.elapsed();
+        Some(store)
+    }
+
+    /// Directory the store was loaded from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Loads a fresh store from the same directory.
+    pub fn reload(&self) -> Result<Self, LoadError> {
+        let mut store = Self::new(&self.root, self.nested_categories)?;
+        store.restricted_categories = self.restricted_categories.clone();
+        Ok(store)
+    }
+
+    /// Marks categories, and everything nested under them, as visible to
+    /// signed-in visitors only. A trailing `/` is ignored.
+    pub fn restrict_categories(&mut self, categories: &[String]) {
+        self.restricted_categories = categories
+            .iter()
+            .map(|c| c.trim_matches('/').to_string())
+            .collect();
+    }
+
+    pub fn is_restricted(&self, article: &Article) -> bool {
+        article
+            .metadata
+            .category
+            .as_deref()
+            .is_some_and(|c| self.is_restricted_category(c))
+    }
+
+    fn is_restricted_category(&self, category: &str) -> bool {
+        self.restricted_categories.iter().any(|r| {
+            category
+                .strip_prefix(r.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
         })
     }
 
@@ -91,13 +296,9 @@ impl ArticleStore {
         result.join("\n")
     }
 
-    pub fn detect_file_changes(
-        &self,
-        content_dir: &str,
-        enable_nested_categories: bool,
-    ) -> Result<Vec<FileChangeInfo>, LoadError> {
+    pub fn detect_file_changes(&self) -> Result<Vec<FileChangeInfo>, LoadError> {
         let mut changes = Vec::new();
-        let current_files = self.collect_all_files(content_dir, enable_nested_categories)?;
+        let current_files = self.collect_all_files()?;
 
         for file_path in &current_files {
             match fs::metadata(file_path) {
@@ -131,15 +332,30 @@ impl ArticleStore {
             }
         }
 
+        // Articles including a partial that changed are re-read too.
+        for (partial, &seen) in &self.partial_times {
+            if Self::modified(Path::new(partial)) == seen {
+                continue;
+            }
+            for article in self
+                .articles
+                .iter()
+                .filter(|a| !a.deleted && a.includes.contains(partial))
+            {
+                if !changes.iter().any(|c| c.path == article.file_path) {
+                    changes.push(FileChangeInfo {
+                        path: article.file_path.clone(),
+                        change_type: FileChange::Modified,
+                    });
+                }
+            }
+        }
+
         Ok(changes)
     }
 
-    pub fn incremental_update(
-        &mut self,
-        content_dir: &str,
-        enable_nested_categories: bool,
-    ) -> Result<bool, LoadError> {
-        let changes = self.detect_file_changes(content_dir, enable_nested_categories)?;
+    pub fn incremental_update(&mut self) -> Result<bool, LoadError> {
+        let changes = self.detect_file_changes()?;
 
         if changes.is_empty() {
             return Ok(false);
@@ -152,11 +368,7 @@ impl ArticleStore {
         for change in changes {
             match change.change_type {
                 FileChange::Added | FileChange::Modified => {
-                    if let Err(e) = self.update_single_article(
-                        &change.path,
-                        content_dir,
-                        enable_nested_categories,
-                    ) {
+                    if let Err(e) = self.update_single_article(&change.path) {
                         tracing::warn!("Failed to update article {}: {:?}", change.path, e);
                         continue;
                     }
@@ -172,32 +384,28 @@ impl ArticleStore {
 
         if articles_changed {
             self.rebuild_indexes();
-            self.update_file_cache(content_dir, enable_nested_categories)?;
+            self.update_file_cache()?;
         }
 
         Ok(articles_changed)
     }
 
-    fn collect_all_files(
-        &self,
-        content_dir: &str,
-        enable_nested_categories: bool,
-    ) -> Result<HashSet<String>, LoadError> {
+    fn collect_all_files(&self) -> Result<HashSet<String>, LoadError> {
         let mut file_set = HashSet::new();
-        if enable_nested_categories {
-            self.collect_files_recursive(content_dir, &mut file_set)?;
+        if self.nested_categories {
+            self.collect_files_recursive(&self.root, &mut file_set)?;
         } else {
-            self.collect_files_flat(content_dir, &mut file_set)?;
+            self.collect_files_flat(&self.root, &mut file_set)?;
         }
         Ok(file_set)
     }
 
     fn collect_files_flat(
         &self,
-        content_dir: &str,
+        content_dir: &Path,
         file_set: &mut HashSet<String>,
     ) -> Result<(), LoadError> {
-        let entries = fs::read_dir(Path::new(content_dir))?;
+        let entries = fs::read_dir(content_dir)?;
 
         for entry in entries {
             let path = entry?.path();
@@ -213,10 +421,10 @@ impl ArticleStore {
 
     fn collect_files_recursive(
         &self,
-        content_dir: &str,
+        content_dir: &Path,
         file_set: &mut HashSet<String>,
     ) -> Result<(), LoadError> {
-        for entry in WalkDir::new(content_dir).into_iter().filter_map(|e| e.ok()) {
+        for entry in Self::walk_articles(content_dir) {
             let path = entry.path();
             if path.is_file()
                 && path.extension().is_some_and(|s| s == "md")
@@ -228,17 +436,12 @@ impl ArticleStore {
         Ok(())
     }
 
-    pub fn update_single_article(
-        &mut self,
-        file_path: &str,
-        content_dir: &str,
-        enable_nested_categories: bool,
-    ) -> Result<(), LoadError> {
+    pub fn update_single_article(&mut self, file_path: &str) -> Result<(), LoadError> {
         self.content_cache.lock().unwrap().remove(file_path);
         let path = Path::new(file_path);
 
-        let category = if enable_nested_categories {
-            self.calculate_category_from_path(path, content_dir)
+        let category = if self.nested_categories {
+            Self::calculate_category(path, &self.root)
         } else {
             None
         };
@@ -248,38 +451,58 @@ impl ArticleStore {
         Self::process_article_file(
             path,
             category.as_deref(),
-            content_dir,
+            &self.root,
             &mut temp_articles,
             &mut temp_tags,
         )?;
 
         if let Some(new_article) = temp_articles.into_iter().next() {
+            for partial in &new_article.includes {
+                self.partial_times
+                    .entry(partial.clone())
+                    .or_insert_with(|| Self::modified(Path::new(partial)));
+            }
             if let Some(existing_index) = self
                 .articles
                 .iter()
-                .position(|a| a.slug == new_article.slug)
+                .position(|a| a.file_path == new_article.file_path)
             {
                 self.articles[existing_index] = new_article;
             } else {
                 self.articles.push(new_article);
             }
 
-            for tag in &temp_tags {
-                self.tags.insert(tag.clone());
-            }
-            if let Some(ref cat) = category {
-                self.categories.insert(cat.clone());
-            }
+            self.rebuild_indexes();
         }
 
         Ok(())
     }
 
+    /// Replaces the article loaded from `old_path` with the one now at
+    /// `new_path`, so readers never see both files or neither.
+    pub fn move_article(&mut self, old_path: &str, new_path: &str) -> Result<(), LoadError> {
+        self.update_single_article(new_path)?;
+        self.remove_article_by_path(old_path);
+        self.rebuild_indexes();
+        Ok(())
+    }
+
+    /// Drops the article loaded from `file_path`, whose file was removed
+    /// through the API, so the watcher does not report it again.
+    pub fn remove_article(&mut self, file_path: &str) -> bool {
+        self.file_cache.remove(file_path);
+        let removed = self.remove_article_by_path(file_path);
+        if removed {
+            self.rebuild_indexes();
+        }
+        removed
+    }
+
     fn remove_article_by_path(&mut self, file_path: &str) -> bool {
         self.content_cache.lock().unwrap().remove(file_path);
         if let Some(article) = self.articles.iter_mut().find(|a| a.file_path == file_path) {
             article.deleted = true;
-            self.slug_map.remove(&article.slug);
+            self.slug_map.remove(&article.slug_with_category());
             tracing::info!("Soft deleted article: {}", article.slug);
             return true;
         }
@@ -290,23 +513,38 @@ impl ArticleStore {
         self.articles
             .sort_by(|a, b| b.metadata.date.cmp(&a.metadata.date));
 
-        self.slug_map = self
-            .articles
-            .iter()
-            .enumerate()
-            .filter(|(_, a)| !a.deleted)
-            .map(|(idx, article)| (article.slug.clone(), idx))
-            .collect();
+        self.slug_map.clear();
+        self.bare_slugs.clear();
+        for (idx, article) in self.articles.iter().enumerate() {
+            if article.deleted {
+                continue;
+            }
+            self.slug_map.insert(article.slug_with_category(), idx);
+            if let Some(&newest) = self.bare_slugs.get(&article.slug) {
+                tracing::warn!(
+                    "Slug {} is used by {} and {}; the bare slug resolves to the newer one",
+                    article.slug,
+                    self.articles[newest].slug_with_category(),
+                    article.slug_with_category()
+                );
+            } else {
+                self.bare_slugs.insert(article.slug.clone(), idx);
+            }
+        }
+
+        // Tag and category sets follow the published articles, so an article
+        // turned into a draft or removed takes its terms with it.
+        let (tag_counts, category_counts) = self.count_terms(true);
+        self.tags = tag_counts.keys().cloned().collect();
+        self.categories = category_counts.keys().cloned().collect();
+        self.tag_counts = tag_counts;
+        self.category_counts = category_counts;
     }
 
-    fn update_file_cache(
-        &mut self,
-        content_dir: &str,
-        enable_nested_categories: bool,
-    ) -> Result<(), LoadError> {
+    fn update_file_cache(&mut self) -> Result<(), LoadError> {
         self.file_cache.clear();
 
-        let current_files = self.collect_all_files(content_dir, enable_nested_categories)?;
+        let current_files = self.collect_all_files()?;
 
         for file_path in current_files {
             if let Ok(metadata) = fs::metadata(&file_path)
@@ -315,6 +553,7 @@ impl ArticleStore {
                 self.file_cache.insert(file_path, modified_time);
             }
         }
+        self.partial_times = Self::partial_times(&self.articles);
 
         self.content_cache
             .lock()
@@ -340,17 +579,12 @@ impl ArticleStore {
         }
     }
 
-    fn calculate_category_from_path(&self, path: &Path, base_path: &str) -> Option<String> {
-        let base = Path::new(base_path);
-        Self::calculate_category(path, base)
-    }
-
     fn load_articles_flat(
-        content_dir: &str,
+        content_dir: &Path,
         articles: &mut Vec<Article>,
         all_tags: &mut HashSet<String>,
     ) -> Result<(), LoadError> {
-        let entries = fs::read_dir(Path::new(content_dir))?;
+        let entries = fs::read_dir(content_dir)?;
 
         for entry in entries {
             let path = entry?.path();
@@ -362,20 +596,89 @@ impl ArticleStore {
         Ok(())
     }
 
+    /// Entries below `content_dir`, leaving out its partials folder.
+    fn walk_articles(content_dir: &Path) -> impl Iterator<Item = walkdir::DirEntry> {
+        let partials = content_dir.join(PARTIALS_DIR);
+        WalkDir::new(content_dir)
+            .into_iter()
+            .filter_entry(move |e| e.path() != partials)
+            .filter_map(|e| e.ok())
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    fn partial_times(articles: &[Article]) -> HashMap<String, Option<SystemTime>> {
+        articles
+            .iter()
+            .filter(|a| !a.deleted)
+            .flat_map(|a| &a.includes)
+            .map(|partial| (partial.clone(), Self::modified(Path::new(partial))))
+            .collect()
+    }
+
+    /// The partial named by an `{{< include "name" >}}` line, if `line` is
+    /// one.
+    fn include_directive(line: &str) -> Option<&str> {
+        let inner = line.trim().strip_prefix("{{<")?.strip_suffix(">}}")?;
+        let name = inner.trim().strip_prefix("include")?.trim();
+        name.strip_prefix('"')?
+            .strip_suffix('"')
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Path of the partial `name` under `root`, `.md` being implied. Names
+    /// reaching outside the partials folder are refused.
+    fn partial_path(root: &Path, name: &str) -> Option<PathBuf> {
+        let name = Path::new(name);
+        if !name.components().all(|c| matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        let mut path = root.join(PARTIALS_DIR).join(name);
+        if path.extension().is_none() {
+            path.set_extension("md");
+        }
+        Some(path)
+    }
+
+    /// Replaces each include line of `content` with the partial it names.
+    /// Partials are inserted as written; includes inside them are not
+    /// expanded. Missing partials are left out with a warning.
+    fn expand_includes<'a>(&self, content: &'a str, slug: &str) -> Cow<'a, str> {
+        if !content.contains("{{<") {
+            return Cow::Borrowed(content);
+        }
+        let lines: Vec<Cow<str>> = content
+            .lines()
+            .map(|line| {
+                let Some(name) = Self::include_directive(line) else {
+                    return Cow::Borrowed(line);
+                };
+                match Self::partial_path(&self.root, name).map(fs::read_to_string) {
+                    Some(Ok(partial)) => Cow::Owned(partial.trim_end().to_string()),
+                    _ => {
+                        tracing::warn!("Partial {} included by {} not found", name, slug);
+                        Cow::Borrowed("")
+                    }
+                }
+            })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    }
+
     fn load_articles_recursive(
-        content_dir: &str,
+        content_dir: &Path,
         articles: &mut Vec<Article>,
         all_tags: &mut HashSet<String>,
         all_categories: &mut HashSet<String>,
     ) -> Result<(), LoadError> {
-        let base_path = Path::new(content_dir);
-
-        for entry in WalkDir::new(content_dir).into_iter().filter_map(|e| e.ok()) {
+        for entry in Self::walk_articles(content_dir) {
             let path = entry.path();
 
             if path.is_file() && path.extension().is_some_and(|s| s == "md") {
                 // Calculate category from relative path
-                let category = Self::calculate_category(path, base_path);
+                let category = Self::calculate_category(path, content_dir);
 
                 if let Some(ref cat) = category {
                     all_categories.insert(cat.clone());
@@ -396,7 +699,7 @@ impl ArticleStore {
     fn process_article_file(
         path: &Path,
         category: Option<&str>,
-        content_root: &str,
+        content_root: &Path,
         articles: &mut Vec<Article>,
         all_tags: &mut HashSet<String>,
     ) -> Result<(), LoadError> {
@@ -426,16 +729,39 @@ impl ArticleStore {
         let data = parsed_content
             .data
             .ok_or_else(|| LoadError::MissingFrontMatter(path.to_string_lossy().to_string()))?;
-        let mut metadata: Metadata = from_value(data)?;
+        let defaults = category_defaults(content_root, path);
+        let mut metadata: Metadata = from_value(with_defaults(&defaults, data))?;
+        metadata.description_generated = false;
+        // Bodies of private notes are encrypted, and members-only ones must
+        // not leak through the description.
+        if metadata.description.trim().is_empty()
+            && !metadata.private
+            && !metadata.members_only
+            && let Some(description) =
+                first_paragraph(&parsed_content.content, GENERATED_DESCRIPTION_CHARS)
+        {
+            metadata.description = description;
+            metadata.description_generated = true;
+        }
 
         // Warn if body contains H1 headings
         Self::strip_h1(&parsed_content.content, &slug);
+        let mut includes: Vec<String> = parsed_content
+            .content
+            .lines()
+            .filter_map(Self::include_directive)
+            .filter_map(|name| Self::partial_path(content_root, name))
+            .chain(category_defaults_files(content_root, path))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        includes.sort();
+        includes.dedup();
 
         if let Some(cat) = category {
             metadata.category = Some(cat.to_string());
         }
 
-        if !metadata.draft {
+        if metadata.is_published() {
             for tag in &metadata.tags {
                 all_tags.insert(tag.clone());
             }
@@ -445,7 +771,7 @@ impl ArticleStore {
             .and_then(|m| m.modified())
             .unwrap_or(SystemTime::UNIX_EPOCH);
         let updated_at: DateTime<Utc> = last_modified.into();
-        let version_dir = format!("{}/{}/versions", content_root, slug);
+        let version_dir = content_root.join(&slug).join("versions");
         let version = fs::read_dir(&version_dir)
             .ok()
             .and_then(|rd| {
@@ -468,30 +794,120 @@ impl ArticleStore {
             updated_at,
             file_path: path.to_string_lossy().to_string(),
             last_modified,
+            includes,
             deleted: false,
         });
 
         Ok(())
     }
 
-    pub fn get_all_tags(&self) -> Vec<String> {
-        let mut tags: Vec<String> = self.tags.iter().cloned().collect();
+    /// All tags, sorted. Without `include_restricted`, tags that only occur
+    /// in restricted categories are left out.
+    pub fn get_all_tags(&self, include_restricted: bool) -> Vec<String> {
+        let mut tags: Vec<String> = if include_restricted || self.restricted_categories.is_empty() {
+            self.tags.iter().cloned().collect()
+        } else {
+            self.count_terms(false).0.into_keys().collect()
+        };
         tags.sort();
         tags
     }
 
-    pub fn get_all_categories(&self) -> Vec<String> {
-        let mut categories: Vec<String> = self.categories.iter().cloned().collect();
+    pub fn get_all_categories(&self, include_restricted: bool) -> Vec<String> {
+        let mut categories: Vec<String> = self
+            .categories
+            .iter()
+            .filter(|c| include_restricted || !self.is_restricted_category(c))
+            .cloned()
+            .collect();
         categories.sort();
         categories
     }
 
+    /// Published articles per tag, ordered by tag.
+    pub fn tag_counts(&self, include_restricted: bool) -> Vec<TermCount> {
+        if include_restricted || self.restricted_categories.is_empty() {
+            Self::term_counts(&self.tag_counts)
+        } else {
+            Self::term_counts(&self.count_terms(false).0)
+        }
+    }
+
+    /// Published articles per category, ordered by category.
+    pub fn category_counts(&self, include_restricted: bool) -> Vec<TermCount> {
+        if include_restricted || self.restricted_categories.is_empty() {
+            Self::term_counts(&self.category_counts)
+        } else {
+            Self::term_counts(&self.count_terms(false).1)
+        }
+    }
+
+    fn term_counts(counts: &BTreeMap<String, usize>) -> Vec<TermCount> {
+        counts
+            .iter()
+            .map(|(name, &count)| TermCount {
+                name: name.clone(),
+                count,
+            })
+            .collect()
+    }
+
+    /// Tag and category counts over published articles. The full counts
+    /// are cached by `rebuild_indexes`; the anonymous view is only needed
+    /// when restrictions are configured and is computed on demand.
+    fn count_terms(
+        &self,
+        include_restricted: bool,
+    ) -> (BTreeMap<String, usize>, BTreeMap<String, usize>) {
+        let mut tags = BTreeMap::new();
+        let mut categories = BTreeMap::new();
+        for article in self.articles.iter().filter(|a| {
+            !a.deleted
+                && a.metadata.is_published()
+                && (include_restricted || !self.is_restricted(a))
+        }) {
+            for tag in &article.metadata.tags {
+                *tags.entry(tag.clone()).or_default() += 1;
+            }
+            if let Some(ref category) = article.metadata.category {
+                *categories.entry(category.clone()).or_default() += 1;
+            }
+        }
+        (tags, categories)
+    }
+
+    /// Categories of published entries in `folder` or any folder below it,
+    /// read from the sorted category index. A trailing `/` is ignored.
+    pub fn categories_under(&self, folder: &str) -> HashSet<&str> {
+        let folder = folder.trim_matches('/');
+        self.category_counts
+            .range::<str, _>((Bound::Included(folder), Bound::Unbounded))
+            .map(|(category, _)| category.as_str())
+            .take_while(|category| category.starts_with(folder))
+            .filter(|category| {
+                category[folder.len()..].is_empty() || category[folder.len()..].starts_with('/')
+            })
+            .collect()
+    }
+
+    /// Finds an article by `category/slug` or, failing that, by its bare
+    /// slug.
     pub fn get_by_slug(&self, slug: &str) -> Option<&Article> {
         self.slug_map
             .get(slug)
+            .or_else(|| self.bare_slugs.get(slug))
             .and_then(|&idx| self.articles.get(idx))
     }
 
+    /// The entry stored at `category/slug`, looked up in the path index
+    /// without falling back to bare slugs. Deleted entries are left out.
+    pub fn get_by_path(&self, path: &str) -> Option<&Article> {
+        self.slug_map
+            .get(path)
+            .and_then(|&idx| self.articles.get(idx))
+            .filter(|a| !a.deleted)
+    }
+
     pub fn query<'a, F>(
         &'a self,
         filter: F,
@@ -509,6 +925,32 @@ impl ArticleStore {
             .take(limit)
     }
 
+    /// One page of the articles matching `filter` together with the total
+    /// number of matches, computed in a single pass.
+    pub fn query_paginated<F>(
+        &self,
+        filter: F,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<&Article>, usize)
+    where
+        F: Fn(&Article) -> bool,
+    {
+        let mut page = Vec::new();
+        let mut total = 0;
+        for article in self.articles.iter().filter(|a| !a.deleted) {
+            if !filter(article) {
+                continue;
+            }
+            if total >= offset && page.len() < limit {
+                page.push(article);
+            }
+            total += 1;
+        }
+        (page, total)
+    }
+
+    /// The body of `article` as readers get it, with partials included.
     pub fn load_content_for(&self, article: &Article) -> Result<String, LoadError> {
         {
             let cache = self.content_cache.lock().unwrap();
@@ -517,6 +959,18 @@ impl ArticleStore {
             }
         }
 
+        let source = self.load_source_for(article)?;
+        let content = self.expand_includes(&source, &article.slug).into_owned();
+        self.content_cache
+            .lock()
+            .unwrap()
+            .insert(article.file_path.clone(), content.clone());
+        Ok(content)
+    }
+
+    /// The body of `article` as written, include lines and all, for edits
+    /// that write it back. Not cached.
+    pub fn load_source_for(&self, article: &Article) -> Result<String, LoadError> {
         let file_content = fs::read_to_string(&article.file_path)?;
         let matter = Matter::<YAML>::new();
         let parsed_content = matter
@@ -527,23 +981,24 @@ impl ArticleStore {
                     article.file_path, e
                 ))
             })?;
-        let content = Self::strip_h1(&parsed_content.content, &article.slug);
-        self.content_cache
-            .lock()
-            .unwrap()
-            .insert(article.file_path.clone(), content.clone());
-        Ok(content)
+        Ok(Self::strip_h1(&parsed_content.content, &article.slug))
     }
 
+    /// Everything to feed the search index. Private notes are left out.
     pub fn load_full_articles(&self) -> Vec<ArticleContent> {
         let mut loaded = Vec::new();
 
-        for article in self.articles.iter().filter(|a| !a.deleted) {
+        for article in self
+            .articles
+            .iter()
+            .filter(|a| !a.deleted && !a.metadata.private)
+        {
             match self.load_content_for(article) {
                 Ok(content) => loaded.push(ArticleContent {
-                    slug: article.slug.clone(),
+                    slug: article.slug_with_category(),
                     metadata: article.metadata.clone(),
                     content,
+                    edit_url: None,
                 }),
                 Err(e) => {
                     tracing::warn!(
@@ -557,4 +1012,323 @@ impl ArticleStore {
 
         loaded
     }
+
+    pub fn stats(&self) -> StoreStats {
+        let tombstones = self.articles.iter().filter(|a| a.deleted).count();
+        let cache = self.content_cache.lock().unwrap();
+        StoreStats {
+            articles: self.articles.len() - tombstones,
+            tombstones,
+            content_cache_entries: cache.len(),
+            content_cache_bytes: cache.values().map(String::len).sum(),
+            slug_map_entries: self.slug_map.len(),
+            last_rebuild_ms: self.last_rebuild.as_millis() as u64,
+        }
+    }
+
+    /// Loads the bodies of the `limit` most recent published articles into
+    /// the content cache, so the first requests after startup are served
+    /// from memory. Returns how many were loaded.
+    pub fn warm_content_cache(&self, limit: usize) -> usize {
+        let now = Utc::now();
+        self.query(
+            |a| a.metadata.is_published() && a.metadata.date <= now,
+            0,
+            limit,
+        )
+        .filter(|article| match self.load_content_for(article) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!("Failed to warm content for {}: {:?}", article.slug, e);
+                false
+            }
+        })
+        .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::article::ArticleStatus;
+    use crate::services::front_matter::CATEGORY_DEFAULTS_FILE;
+    use tempfile::tempdir;
+
+    fn write_article(dir: &Path, slug: &str, day: u32, tags: &str) {
+        fs::write(
+            dir.join(format!("{}.md", slug)),
+            format!(
+                "---\ntitle: \"{slug}\"\nauthor: \"a\"\ndate: 2024-01-{day:02}T00:00:00Z\ndescription: \"\"\ntags: [{tags}]\ndraft: false\n---\n\nBody\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn query_paginated_returns_page_and_total() {
+        let dir = tempdir().unwrap();
+        for day in 1..=5 {
+            let tags = if day % 2 == 0 { "even" } else { "odd" };
+            write_article(dir.path(), &format!("post-{}", day), day, tags);
+        }
+        let store = ArticleStore::new(dir.path(), true).unwrap();
+
+        let odd = |a: &Article| a.metadata.tags.iter().any(|t| t == "odd");
+        let (page, total) = store.query_paginated(odd, 1, 1);
+        assert_eq!(total, 3);
+        assert_eq!(
+            page.iter().map(|a| a.slug.as_str()).collect::<Vec<_>>(),
+            ["post-3"]
+        );
+
+        let (page, total) = store.query_paginated(|_| true, 4, 10);
+        assert_eq!((page.len(), total), (1, 5));
+        let (page, total) = store.query_paginated(|_| true, 10, 10);
+        assert_eq!((page.len(), total), (0, 5));
+    }
+
+    #[test]
+    fn restricted_categories_cover_subcategories_only() {
+        let dir = tempdir().unwrap();
+        for category in ["work", "work/acme", "workshop"] {
+            let path = dir.path().join(category);
+            fs::create_dir_all(&path).unwrap();
+            write_article(&path, &category.replace('/', "-"), 1, "shared");
+        }
+        let mut store = ArticleStore::new(dir.path(), true).unwrap();
+        store.restrict_categories(&["work/".to_string()]);
+
+        let restricted: Vec<_> = store
+            .query(|a| store.is_restricted(a), 0, usize::MAX)
+            .map(|a| a.slug.as_str())
+            .collect();
+        assert_eq!(restricted.len(), 2);
+        assert!(!restricted.contains(&"workshop"));
+        assert_eq!(store.get_all_categories(false), ["workshop"]);
+        assert_eq!(store.tag_counts(false)[0].count, 1);
+        assert_eq!(store.tag_counts(true)[0].count, 3);
+    }
+
+    #[test]
+    fn folders_include_their_subfolders() {
+        let dir = tempdir().unwrap();
+        for category in ["rust", "rust/async", "rust/async/pin", "rustacean", "go"] {
+            let path = dir.path().join(category);
+            fs::create_dir_all(&path).unwrap();
+            write_article(&path, &category.replace('/', "-"), 1, "shared");
+        }
+        let store = ArticleStore::new(dir.path(), true).unwrap();
+
+        let mut under: Vec<_> = store.categories_under("rust/").into_iter().collect();
+        under.sort();
+        assert_eq!(under, ["rust", "rust/async", "rust/async/pin"]);
+        assert_eq!(store.categories_under("rust/async").len(), 2);
+        assert!(store.categories_under("ru").is_empty());
+    }
+
+    #[test]
+    fn drafts_drop_out_of_tags_and_categories() {
+        let dir = tempdir().unwrap();
+        let guides = dir.path().join("guides");
+        fs::create_dir(&guides).unwrap();
+        write_article(&guides, "tour", 1, "rust, solo");
+        write_article(dir.path(), "other", 2, "rust");
+        let mut store = ArticleStore::new(dir.path(), true).unwrap();
+        let path = guides.join("tour.md");
+        let path = path.to_str().unwrap();
+
+        for draft in [true, false, true] {
+            let text = fs::read_to_string(path).unwrap();
+            let text = text.replace(&format!("draft: {}", !draft), &format!("draft: {}", draft));
+            fs::write(path, text).unwrap();
+            store.update_single_article(path).unwrap();
+
+            let (tags, categories) = if draft {
+                (vec!["rust"], vec![])
+            } else {
+                (vec!["rust", "solo"], vec!["guides"])
+            };
+            assert_eq!(store.get_all_tags(true), tags);
+            assert_eq!(store.get_all_categories(true), categories);
+            assert_eq!(store.tag_counts(true)[0].count, if draft { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    fn equal_slugs_in_different_categories_do_not_collide() {
+        let dir = tempdir().unwrap();
+        for (category, day) in [("rust", 1), ("python", 2)] {
+            let path = dir.path().join(category);
+            fs::create_dir_all(&path).unwrap();
+            write_article(&path, "intro", day, category);
+        }
+        let mut store = ArticleStore::new(dir.path(), true).unwrap();
+        assert_eq!(store.query(|_| true, 0, usize::MAX).count(), 2);
+        let tags = |store: &ArticleStore, slug: &str| {
+            store.get_by_slug(slug).map(|a| a.metadata.tags.clone())
+        };
+        assert_eq!(tags(&store, "rust/intro").unwrap(), ["rust"]);
+        assert_eq!(tags(&store, "python/intro").unwrap(), ["python"]);
+        // The bare slug still resolves, to the newest article.
+        assert_eq!(tags(&store, "intro").unwrap(), ["python"]);
+        assert!(store.get_by_slug("go/intro").is_none());
+
+        // Editing one article leaves the other in place.
+        write_article(&dir.path().join("python"), "intro", 3, "edited");
+        store
+            .update_single_article(dir.path().join("python/intro.md").to_str().unwrap())
+            .unwrap();
+        assert_eq!(tags(&store, "python/intro").unwrap(), ["edited"]);
+        assert_eq!(tags(&store, "rust/intro").unwrap(), ["rust"]);
+
+        fs::remove_file(dir.path().join("python/intro.md")).unwrap();
+        assert!(store.incremental_update().unwrap());
+        assert!(store.get_by_slug("python/intro").is_none());
+        assert_eq!(tags(&store, "intro").unwrap(), ["rust"]);
+    }
+
+    #[test]
+    fn warmup_loads_the_latest_articles() {
+        let dir = tempdir().unwrap();
+        for day in 1..=3 {
+            write_article(dir.path(), &format!("post-{}", day), day, "");
+        }
+        let store = ArticleStore::new(dir.path(), true).unwrap();
+
+        assert_eq!(store.warm_content_cache(2), 2);
+        let cache = store.content_cache.lock().unwrap();
+        let mut warmed: Vec<&str> = cache
+            .keys()
+            .map(|path| path.rsplit('/').next().unwrap())
+            .collect();
+        warmed.sort();
+        assert_eq!(warmed, ["post-2.md", "post-3.md"]);
+    }
+
+    #[test]
+    fn snapshots_restore_entries_and_pick_up_changes() {
+        let dir = tempdir().unwrap();
+        let content = dir.path().join("content");
+        fs::create_dir(&content).unwrap();
+        let snapshot = dir.path().join("store.json");
+        for day in 1..=3 {
+            write_article(&content, &format!("post-{}", day), day, "old");
+        }
+        ArticleStore::new(&content, true)
+            .unwrap()
+            .save_snapshot(&snapshot)
+            .unwrap();
+
+        let restored = ArticleStore::from_snapshot(&snapshot, &content, true).unwrap();
+        assert_eq!(restored.query(|_| true, 0, usize::MAX).count(), 3);
+        assert_eq!(restored.get_all_tags(true), ["old"]);
+        assert!(ArticleStore::from_snapshot(&snapshot, dir.path(), true).is_none());
+        assert!(ArticleStore::from_snapshot(&snapshot, &content, false).is_none());
+
+        write_article(&content, "post-1", 1, "new");
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(content.join("post-1.md"))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        fs::remove_file(content.join("post-2.md")).unwrap();
+        write_article(&content, "post-4", 4, "old");
+
+        let restored = ArticleStore::from_snapshot(&snapshot, &content, true).unwrap();
+        let mut slugs: Vec<&str> = restored
+            .query(|_| true, 0, usize::MAX)
+            .map(|a| a.slug.as_str())
+            .collect();
+        slugs.sort();
+        assert_eq!(slugs, ["post-1", "post-3", "post-4"]);
+        assert_eq!(restored.get_all_tags(true), ["new", "old"]);
+    }
+
+    #[test]
+    fn partials_are_included_and_update_their_articles() {
+        let dir = tempdir().unwrap();
+        let partials = dir.path().join(PARTIALS_DIR);
+        fs::create_dir(&partials).unwrap();
+        fs::write(partials.join("cta.md"), "Subscribe!\n").unwrap();
+        fs::write(
+            dir.path().join("post.md"),
+            "---\ntitle: \"Post\"\nauthor: \"a\"\ndate: 2024-01-01T00:00:00Z\ndescription: \"\"\n---\n\nIntro\n{{< include \"cta\" >}}\n{{< include \"../post\" >}}\n",
+        )
+        .unwrap();
+
+        // Partials have no front matter and are not loaded as articles.
+        let mut store = ArticleStore::new(dir.path(), true).unwrap();
+        assert_eq!(store.query(|_| true, 0, usize::MAX).count(), 1);
+        let post = store.get_by_slug("post").unwrap().clone();
+        let content = store.load_content_for(&post).unwrap();
+        assert!(content.contains("Intro\nSubscribe!"), "{content}");
+        assert!(!content.contains("{{<"), "{content}");
+        let source = store.load_source_for(&post).unwrap();
+        assert!(source.contains("{{< include \"cta\" >}}"), "{source}");
+
+        fs::write(partials.join("cta.md"), "Follow along!\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(partials.join("cta.md"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(store.incremental_update().unwrap());
+        let post = store.get_by_slug("post").unwrap();
+        assert!(
+            store
+                .load_content_for(post)
+                .unwrap()
+                .contains("Follow along!")
+        );
+        assert!(!store.incremental_update().unwrap());
+    }
+
+    #[test]
+    fn category_defaults_are_inherited_and_update_their_articles() {
+        let dir = tempdir().unwrap();
+        let guides = dir.path().join("guides");
+        fs::create_dir(&guides).unwrap();
+        let defaults = guides.join(CATEGORY_DEFAULTS_FILE);
+        fs::write(
+            &defaults,
+            "author: Docs Team\ntags: [guide]\nstatus: draft\n",
+        )
+        .unwrap();
+        fs::write(
+            guides.join("setup.md"),
+            "---\ntitle: \"Setup\"\ndate: 2024-01-01T00:00:00Z\n---\n\nBody\n",
+        )
+        .unwrap();
+        fs::write(
+            guides.join("routing.md"),
+            "---\ntitle: \"Routing\"\ndate: 2024-01-02T00:00:00Z\ntags: [rust]\ndraft: false\n---\n\nBody\n",
+        )
+        .unwrap();
+
+        let mut store = ArticleStore::new(dir.path(), true).unwrap();
+        let setup = store.get_by_slug("setup").unwrap();
+        assert_eq!(setup.metadata.author, "Docs Team");
+        assert_eq!(setup.metadata.tags, ["guide"]);
+        assert_eq!(setup.metadata.status, ArticleStatus::Draft);
+        let routing = store.get_by_slug("routing").unwrap();
+        assert_eq!(routing.metadata.author, "Docs Team");
+        assert_eq!(routing.metadata.tags, ["rust"]);
+        assert_eq!(routing.metadata.status, ArticleStatus::Published);
+
+        fs::write(&defaults, "author: Ferris\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&defaults)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(store.incremental_update().unwrap());
+        let setup = store.get_by_slug("setup").unwrap();
+        assert_eq!(setup.metadata.author, "Ferris");
+        assert!(setup.metadata.tags.is_empty());
+        assert!(!store.incremental_update().unwrap());
+    }
 }
@@ -0,0 +1,95 @@
+use crate::db::DbPool;
+use crate::models::shortlink::Shortlink;
+use sha2::{Digest, Sha256};
+
+const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const MIN_CODE_LENGTH: usize = 6;
+const MAX_CODE_LENGTH: usize = 12;
+
+/// Base62 code derived from the slug, so the same article always gets the
+/// same link. Longer prefixes of the same digest resolve collisions.
+fn code_for(slug: &str, length: usize) -> String {
+    Sha256::digest(slug.as_bytes())
+        .iter()
+        .take(length)
+        .map(|b| ALPHABET[*b as usize % ALPHABET.len()] as char)
+        .collect()
+}
+
+async fn find_by_slug(pool: &DbPool, slug: &str) -> Result<Option<Shortlink>, sqlx::Error> {
+    sqlx::query_as("SELECT code, slug, clicks, created_at FROM shortlinks WHERE slug = ?")
+        .bind(slug)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Returns the article's shortlink, creating it on first use.
+pub async fn shortlink_for(pool: &DbPool, slug: &str) -> Result<Shortlink, sqlx::Error> {
+    for length in MIN_CODE_LENGTH..=MAX_CODE_LENGTH {
+        if let Some(existing) = find_by_slug(pool, slug).await? {
+            return Ok(existing);
+        }
+        let created: Option<Shortlink> = sqlx::query_as(
+            "INSERT INTO shortlinks (code, slug) VALUES (?, ?) ON CONFLICT DO NOTHING \
+             RETURNING code, slug, clicks, created_at",
+        )
+        .bind(code_for(slug, length))
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(created) = created {
+            return Ok(created);
+        }
+    }
+    find_by_slug(pool, slug)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Counts a click on `code` and returns the slug it points to.
+pub async fn follow_shortlink(pool: &DbPool, code: &str) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("UPDATE shortlinks SET clicks = clicks + 1 WHERE code = ? RETURNING slug")
+        .bind(code)
+        .fetch_optional(pool)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn codes_are_stable_and_count_clicks() {
+        let pool = memory_db().await;
+        let link = shortlink_for(&pool, "hello-world").await.unwrap();
+        assert_eq!(link.code.len(), MIN_CODE_LENGTH);
+        assert_eq!(link.code, code_for("hello-world", MIN_CODE_LENGTH));
+        assert_eq!(
+            shortlink_for(&pool, "hello-world").await.unwrap().code,
+            link.code
+        );
+
+        assert_eq!(
+            follow_shortlink(&pool, &link.code)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("hello-world")
+        );
+        assert_eq!(follow_shortlink(&pool, "nope").await.unwrap(), None);
+        assert_eq!(shortlink_for(&pool, "hello-world").await.unwrap().clicks, 1);
+    }
+
+    #[tokio::test]
+    async fn colliding_codes_grow_longer() {
+        let pool = memory_db().await;
+        sqlx::query("INSERT INTO shortlinks (code, slug) VALUES (?, 'other')")
+            .bind(code_for("hello-world", MIN_CODE_LENGTH))
+            .execute(&pool)
+            .await
+            .unwrap();
+        let link = shortlink_for(&pool, "hello-world").await.unwrap();
+        assert_eq!(link.code, code_for("hello-world", MIN_CODE_LENGTH + 1));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::db::DbPool;
+use chrono::{DateTime, Utc};
+
+/// Content was written through the API or changed on disk.
+pub const CONTENT_CHANGED: &str = "content_changed";
+/// A downstream deploy reported that it finished.
+pub const DEPLOYED: &str = "deployed";
+/// Time on the primary as of which a replica holds its content.
+pub const SYNCED: &str = "synced";
+
+/// Records that the event `name` happened now, replacing any earlier time.
+pub async fn record_event(pool: &DbPool, name: &str) -> Result<(), sqlx::Error> {
+    record_event_at(pool, name, Utc::now()).await
+}
+
+pub async fn record_event_at(
+    pool: &DbPool,
+    name: &str,
+    at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO site_events (name, occurred_at) VALUES (?, ?) \
+         ON CONFLICT (name) DO UPDATE SET occurred_at = excluded.occurred_at",
+    )
+    .bind(name)
+    .bind(at.to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// When the event `name` last happened, if ever.
+pub async fn last_occurred(
+    pool: &DbPool,
+    name: &str,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let at: Option<String> =
+        sqlx::query_scalar("SELECT occurred_at FROM site_events WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await?;
+    Ok(at
+        .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+        .map(|at| at.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn keeps_the_latest_time_of_each_event() {
+        let pool = memory_db().await;
+        assert_eq!(last_occurred(&pool, DEPLOYED).await.unwrap(), None);
+
+        record_event(&pool, CONTENT_CHANGED).await.unwrap();
+        let first = last_occurred(&pool, CONTENT_CHANGED)
+            .await
+            .unwrap()
+            .unwrap();
+        record_event(&pool, CONTENT_CHANGED).await.unwrap();
+        let second = last_occurred(&pool, CONTENT_CHANGED)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(second >= first);
+        assert_eq!(last_occurred(&pool, DEPLOYED).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use std::sync::RwLock;
+
+/// Timezone of the site, set from the `timezone` setting when the app is
+/// built. Dates written without an offset are read in it, and calendar days
+/// are counted in it.
+static SITE_TIMEZONE: RwLock<Tz> = RwLock::new(Tz::UTC);
+
+pub fn set_site_timezone(tz: Tz) {
+    *SITE_TIMEZONE.write().unwrap() = tz;
+}
+
+pub fn site_timezone() -> Tz {
+    *SITE_TIMEZONE.read().unwrap()
+}
+
+/// Formats accepted for dates without an offset, tried in order.
+const LOCAL_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parses a front matter date. RFC 3339 timestamps keep their offset; dates
+/// and times without one are taken as local time in `tz`, and a bare date
+/// as its midnight.
+pub fn parse_date(value: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let naive = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    // A time skipped by a DST change does not exist; one repeated by it is
+    // taken at its first occurrence.
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Deserializes a front matter date with [`parse_date`] in the site
+/// timezone.
+pub fn deserialize_date<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_date(&value, site_timezone())
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid date: {}", value)))
+}
+
+/// Calendar day of `date` in the site timezone.
+pub fn local_day(date: DateTime<Utc>) -> NaiveDate {
+    date.with_timezone(&site_timezone()).date_naive()
+}
+
+/// Today's date in the site timezone.
+pub fn today() -> NaiveDate {
+    local_day(Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_without_an_offset_are_read_in_the_given_timezone() {
+        let shanghai: Tz = "Asia/Shanghai".parse().unwrap();
+        let utc = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert_eq!(
+            parse_date("2024-05-01", shanghai),
+            Some(utc("2024-04-30T16:00:00Z"))
+        );
+        assert_eq!(
+            parse_date("2024-05-01 08:30", shanghai),
+            Some(utc("2024-05-01T00:30:00Z"))
+        );
+        assert_eq!(
+            parse_date("2024-05-01T08:30:00", shanghai),
+            Some(utc("2024-05-01T00:30:00Z"))
+        );
+        // An explicit offset wins over the site timezone.
+        assert_eq!(
+            parse_date("2024-05-01T08:30:00-04:00", shanghai),
+            Some(utc("2024-05-01T12:30:00Z"))
+        );
+        assert_eq!(parse_date("May 1st", shanghai), None);
+
+        // 02:30 does not exist on the day New York springs forward.
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        assert_eq!(parse_date("2024-03-10 02:30", new_york), None);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::config::SpellcheckConfig;
+use crate::models::taxonomy::Misspelling;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::LazyLock;
+
+/// Inline code, URLs, link targets and HTML tags, which are not prose.
+static NOT_PROSE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"`[^`]*`|[a-zA-Z][a-zA-Z0-9+.-]*://\S+|\]\([^)]*\)|<[^>]*>").unwrap()
+});
+
+/// How flags are written in a dictionary, from the `FLAG` line of its
+/// `.aff` file.
+#[derive(Clone, Copy)]
+enum FlagFormat {
+    Char,
+    Long,
+    Num,
+}
+
+impl FlagFormat {
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            FlagFormat::Char => flags.chars().map(String::from).collect(),
+            FlagFormat::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagFormat::Num => flags.split(',').map(|f| f.trim().to_string()).collect(),
+        }
+    }
+}
+
+/// One character of an affix condition.
+enum CharClass {
+    Any,
+    Set { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Set { chars, negated } => chars.contains(&c) != *negated,
+        }
+    }
+
+    fn parse(condition: &str) -> Vec<CharClass> {
+        let mut classes = Vec::new();
+        let mut chars = condition.chars();
+        while let Some(c) = chars.next() {
+            classes.push(match c {
+                '.' => CharClass::Any,
+                '[' => {
+                    let mut set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                    let negated = set.first() == Some(&'^');
+                    if negated {
+                        set.remove(0);
+                    }
+                    CharClass::Set {
+                        chars: set,
+                        negated,
+                    }
+                }
+                c => CharClass::Set {
+                    chars: vec![c],
+                    negated: false,
+                },
+            });
+        }
+        classes
+    }
+}
+
+/// A prefix or suffix rule: `strip` is removed from the stem and `add`
+/// put in its place, for stems matching `condition` at that end.
+struct Affix {
+    flag: String,
+    strip: String,
+    add: String,
+    condition: Vec<CharClass>,
+    cross: bool,
+}
+
+impl Affix {
+    /// Whether the stem, starting at the characters the condition covers,
+    /// satisfies it.
+    fn applies(&self, mut chars: impl Iterator<Item = char>) -> bool {
+        self.condition
+            .iter()
+            .all(|class| chars.next().is_some_and(|c| class.matches(c)))
+    }
+
+    fn suffix_stem(&self, word: &str) -> Option<String> {
+        let base = word.strip_suffix(self.add.as_str())?;
+        if base.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", base, self.strip);
+        let mut tail: Vec<char> = stem.chars().rev().take(self.condition.len()).collect();
+        tail.reverse();
+        self.applies(tail.into_iter()).then_some(stem)
+    }
+
+    fn prefix_stem(&self, word: &str) -> Option<String> {
+        let base = word.strip_prefix(self.add.as_str())?;
+        if base.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", self.strip, base);
+        self.applies(stem.chars()).then_some(stem)
+    }
+}
+
+/// The words of a Hunspell dictionary with its prefix and suffix rules.
+/// Compounding, replacement tables and the other refinements of the format
+/// are not supported, so a word the full engine accepts may be reported.
+pub struct Dictionary {
+    words: HashMap<String, HashSet<String>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+}
+
+impl Dictionary {
+    /// Reads `{path}.aff` and `{path}.dic`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let read = |ext: &str| {
+            fs::read_to_string(format!("{}.{}", path, ext))
+                .map_err(|e| format!("Failed to read dictionary {}.{}: {}", path, ext, e))
+        };
+        Ok(Self::parse(&read("aff")?, &read("dic")?))
+    }
+
+    fn parse(aff: &str, dic: &str) -> Self {
+        let mut format = FlagFormat::Char;
+        let mut prefixes = Vec::new();
+        let mut suffixes = Vec::new();
+        let mut cross: HashMap<String, bool> = HashMap::new();
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => format = FlagFormat::Long,
+                ["FLAG", "num", ..] => format = FlagFormat::Num,
+                ["PFX" | "SFX", flag, combines, count]
+                    if count.parse::<usize>().is_ok() && !cross.contains_key(*flag) =>
+                {
+                    cross.insert(flag.to_string(), *combines == "Y");
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, condition, ..] => {
+                    let none = |s: &str| {
+                        if s == "0" {
+                            String::new()
+                        } else {
+                            s.to_string()
+                        }
+                    };
+                    // Flags after `/` continue the word with further affixes.
+                    let add = add.split('/').next().unwrap_or_default();
+                    let affix = Affix {
+                        flag: flag.to_string(),
+                        strip: none(strip),
+                        add: none(add),
+                        condition: CharClass::parse(condition),
+                        cross: cross.get(*flag).copied().unwrap_or(false),
+                    };
+                    if *kind == "PFX" {
+                        prefixes.push(affix);
+                    } else {
+                        suffixes.push(affix);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut words: HashMap<String, HashSet<String>> = HashMap::new();
+        // The first line holds the approximate number of words.
+        for line in dic.lines().skip(1) {
+            let entry = line.split_whitespace().next().unwrap_or_default();
+            if entry.is_empty() {
+                continue;
+            }
+            let (word, flags) = entry.split_once('/').unwrap_or((entry, ""));
+            words
+                .entry(word.to_string())
+                .or_default()
+                .extend(format.split(flags));
+        }
+        Self {
+            words,
+            prefixes,
+            suffixes,
+        }
+    }
+
+    fn has(&self, stem: &str, flags: &[&str]) -> bool {
+        self.words
+            .get(stem)
+            .is_some_and(|own| flags.iter().all(|f| own.contains(*f)))
+    }
+
+    fn accepts_form(&self, word: &str) -> bool {
+        if self.words.contains_key(word) {
+            return true;
+        }
+        for suffix in &self.suffixes {
+            let Some(stem) = suffix.suffix_stem(word) else {
+                continue;
+            };
+            if self.has(&stem, &[&suffix.flag]) {
+                return true;
+            }
+            if suffix.cross {
+                for prefix in self.prefixes.iter().filter(|p| p.cross) {
+                    if let Some(root) = prefix.prefix_stem(&stem)
+                        && self.has(&root, &[&suffix.flag, &prefix.flag])
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        self.prefixes.iter().any(|prefix| {
+            prefix
+                .prefix_stem(word)
+                .is_some_and(|stem| self.has(&stem, &[&prefix.flag]))
+        })
+    }
+
+    /// Whether `word` is spelled correctly. Words may be capitalized at the
+    /// start of a sentence or written in capitals.
+    pub fn accepts(&self, word: &str) -> bool {
+        if self.accepts_form(word) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        if lower != word && self.accepts_form(&lower) {
+            return true;
+        }
+        let mut chars = lower.chars();
+        let capitalized: String = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+        capitalized != word && self.accepts_form(&capitalized)
+    }
+}
+
+/// Checks the prose of articles and notes against the configured
+/// dictionaries.
+pub struct SpellChecker {
+    dictionaries: HashMap<String, Dictionary>,
+    default_lang: String,
+    words: HashSet<String>,
+}
+
+impl SpellChecker {
+    pub fn from_config(config: &SpellcheckConfig) -> Result<Self, String> {
+        let dictionaries = config
+            .dictionaries
+            .iter()
+            .map(|(lang, path)| Ok((lang.to_lowercase(), Dictionary::load(path)?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Self {
+            dictionaries,
+            default_lang: config.default_lang.to_lowercase(),
+            words: config.words.iter().map(|w| w.to_lowercase()).collect(),
+        })
+    }
+
+    /// The dictionary for a language tag such as `en-US`, falling back to
+    /// its primary language, then to the default language when no tag is
+    /// given.
+    fn dictionary(&self, lang: Option<&str>) -> Option<&Dictionary> {
+        let lang = lang.unwrap_or(&self.default_lang).to_lowercase();
+        let primary = lang.split(['-', '_']).next().unwrap_or_default();
+        self.dictionaries
+            .get(&lang)
+            .or_else(|| self.dictionaries.get(primary))
+    }
+
+    /// Misspelled words in `file`, the full text of an article, by line
+    /// number in the file. Front matter and code are skipped. Nothing is
+    /// reported for languages without a dictionary.
+    pub fn check(&self, lang: Option<&str>, file: &str) -> Vec<Misspelling> {
+        let Some(dictionary) = self.dictionary(lang) else {
+            return Vec::new();
+        };
+        let mut misspellings = Vec::new();
+        let mut in_front_matter = false;
+        let mut fence: Option<&str> = None;
+        for (index, line) in file.lines().enumerate() {
+            let trimmed = line.trim();
+            if index == 0 && trimmed == "---" {
+                in_front_matter = true;
+                continue;
+            }
+            if in_front_matter {
+                in_front_matter = trimmed != "---";
+                continue;
+            }
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+                fence = Some(marker);
+                continue;
+            }
+
+            let prose = NOT_PROSE.replace_all(line, " ").replace('\u{2019}', "'");
+            let mut seen = HashSet::new();
+            for word in prose
+                .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+                .map(|w| w.trim_matches('\''))
+            {
+                if word.chars().count() < 2
+                    || word.chars().any(|c| c.is_numeric())
+                    || self.words.contains(&word.to_lowercase())
+                    || dictionary.accepts(word)
+                    || !seen.insert(word)
+                {
+                    continue;
+                }
+                misspellings.push(Misspelling {
+                    line: index + 1,
+                    word: word.to_string(),
+                });
+            }
+        }
+        misspellings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8\n\
+        SFX S Y 2\n\
+        SFX S 0 s [^y]\n\
+        SFX S y ies [^aeiou]y\n\
+        PFX U Y 1\n\
+        PFX U 0 un .\n";
+    const DIC: &str = "6\ncrab/S\nberry/S\nknown/U\nferris\nthe\nand\n";
+
+    #[test]
+    fn accepts_stems_affixed_forms_and_capitals() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        for word in ["crab", "crabs", "berries", "unknown", "The", "CRABS"] {
+            assert!(dictionary.accepts(word), "{word}");
+        }
+        for word in ["berrys", "uncrab", "ferriss", "crabbs"] {
+            assert!(!dictionary.accepts(word), "{word}");
+        }
+    }
+
+    #[test]
+    fn reports_misspellings_by_line_outside_code() {
+        let checker = SpellChecker {
+            dictionaries: HashMap::from([("en".to_string(), Dictionary::parse(AFF, DIC))]),
+            default_lang: "en".to_string(),
+            words: HashSet::from(["axum".to_string()]),
+        };
+        let file = "---\ntitle: Crabz\n---\n\nThe crabz and Axum berries\n\n```\nteh code\n```\n\
+            `teh` [the](https://teh.example) crabs\n";
+        let found = checker.check(None, file);
+        assert_eq!(
+            found,
+            [Misspelling {
+                line: 5,
+                word: "crabz".to_string()
+            }]
+        );
+        assert_eq!(checker.check(Some("en-GB"), file).len(), 1);
+        assert!(checker.check(Some("de"), file).is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::config::SponsorsConfig;
+use crate::models::sponsor::{Sponsor, SponsorList};
+use crate::server::app::AppState;
+use chrono::{DateTime, Duration, Utc};
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::sync::RwLock;
+
+/// How long a fetched list is served before a request fetches it again.
+pub const REFRESH_AFTER_SECS: i64 = 6 * 60 * 60;
+
+const SPONSORSHIPS_QUERY: &str = "query($login: String!, $after: String) {
+  user(login: $login) {
+    sponsorshipsAsMaintainer(first: 100, after: $after, activeOnly: true) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        createdAt
+        privacyLevel
+        tier { name monthlyPriceInDollars }
+        sponsorEntity {
+          ... on User { login name avatarUrl url }
+          ... on Organization { login name avatarUrl url }
+        }
+      }
+    }
+  }
+}";
+
+/// The last fetched [`SponsorList`], shared by requests and the
+/// `refresh_sponsors` job.
+#[derive(Default)]
+pub struct SponsorCache {
+    list: RwLock<Option<SponsorList>>,
+}
+
+impl SponsorCache {
+    pub fn get(&self) -> Option<SponsorList> {
+        self.list.read().unwrap().clone()
+    }
+
+    fn set(&self, list: SponsorList) {
+        *self.list.write().unwrap() = Some(list);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sponsorship {
+    created_at: DateTime<Utc>,
+    privacy_level: String,
+    tier: Option<Tier>,
+    sponsor_entity: Option<SponsorEntity>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Tier {
+    name: String,
+    monthly_price_in_dollars: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SponsorEntity {
+    login: String,
+    name: Option<String>,
+    avatar_url: Option<String>,
+    url: Option<String>,
+}
+
+/// One page of a `sponsorshipsAsMaintainer` response.
+#[derive(Debug)]
+struct SponsorPage {
+    /// The public sponsorships with their monthly amount in dollars.
+    sponsors: Vec<(u32, Sponsor)>,
+    /// Cursor of the next page, if there is one.
+    next: Option<String>,
+}
+
+fn parse_sponsorships(body: &Value) -> Result<SponsorPage, String> {
+    if let Some(errors) = body["errors"].as_array().filter(|e| !e.is_empty()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| e["message"].as_str())
+            .collect();
+        return Err(format!("GitHub answered: {}", messages.join("; ")));
+    }
+    let connection = &body["data"]["user"]["sponsorshipsAsMaintainer"];
+    if connection.is_null() {
+        return Err("GitHub returned no sponsorships".to_string());
+    }
+    let nodes: Vec<Sponsorship> =
+        serde_json::from_value(connection["nodes"].clone()).map_err(|e| e.to_string())?;
+    let sponsors = nodes
+        .into_iter()
+        .filter(|s| s.privacy_level == "PUBLIC")
+        .filter_map(|s| {
+            let entity = s.sponsor_entity?;
+            Some((
+                s.tier.as_ref().map_or(0, |t| t.monthly_price_in_dollars),
+                Sponsor {
+                    name: entity.name.unwrap_or_else(|| entity.login.clone()),
+                    login: Some(entity.login),
+                    url: entity.url,
+                    avatar_url: entity.avatar_url,
+                    tier: s.tier.map(|t| t.name),
+                    since: Some(s.created_at),
+                },
+            ))
+        })
+        .collect();
+    let page_info = &connection["pageInfo"];
+    let next = if page_info["hasNextPage"].as_bool() == Some(true) {
+        page_info["endCursor"].as_str().map(str::to_string)
+    } else {
+        None
+    };
+    Ok(SponsorPage { sponsors, next })
+}
+
+/// The public sponsors of `login`, largest tiers first, then the longest
+/// standing.
+async fn fetch_github_sponsors(
+    state: &AppState,
+    login: &str,
+    token: &str,
+) -> Result<Vec<Sponsor>, String> {
+    let url = format!(
+        "{}/graphql",
+        state.config.github_api_url.trim_end_matches('/')
+    );
+    let mut sponsors = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        if let Some(reset_at) = state.github_rate_limit.blocked_until(Utc::now()) {
+            return Err(format!(
+                "GitHub rate limit exceeded until {}",
+                reset_at.to_rfc3339()
+            ));
+        }
+        let request = state
+            .http
+            .post(&url)
+            .header(ACCEPT, "application/vnd.github+json")
+            .bearer_auth(token)
+            .json(&json!({
+                "query": SPONSORSHIPS_QUERY,
+                "variables": { "login": login, "after": after },
+            }));
+        let response = state.http.send(request).await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        state
+            .github_rate_limit
+            .record(status, response.headers(), Utc::now());
+        if !status.is_success() {
+            return Err(format!("GitHub answered {}", status));
+        }
+        let body: Value = response.json().await.map_err(|e| e.to_string())?;
+        let page = parse_sponsorships(&body)?;
+        sponsors.extend(page.sponsors);
+        match page.next {
+            Some(cursor) => after = Some(cursor),
+            None => break,
+        }
+    }
+    sponsors
+        .sort_by(|(a_amount, a), (b_amount, b)| b_amount.cmp(a_amount).then(a.since.cmp(&b.since)));
+    Ok(sponsors.into_iter().map(|(_, sponsor)| sponsor).collect())
+}
+
+/// The entries of the sponsors file, a YAML list of [`Sponsor`]s.
+pub fn read_sponsor_file(path: &str) -> Result<Vec<Sponsor>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    serde_yaml::from_str(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+async fn fetch_sponsors(state: &AppState, config: &SponsorsConfig) -> Result<SponsorList, String> {
+    let mut sponsors = Vec::new();
+    if let Some(login) = &config.github_login {
+        let token = std::env::var(&config.token_env)
+            .map_err(|_| format!("{} is not set", config.token_env))?;
+        sponsors.extend(fetch_github_sponsors(state, login, &token).await?);
+    }
+    if let Some(file) = &config.file {
+        sponsors.extend(read_sponsor_file(file)?);
+    }
+    Ok(SponsorList {
+        sponsors,
+        fetched_at: Utc::now(),
+    })
+}
+
+/// Fetches the sponsors again and caches them. Returns how many there are.
+pub async fn refresh_sponsors(state: &AppState) -> Result<usize, String> {
+    let config = state
+        .config
+        .sponsors
+        .as_ref()
+        .ok_or("[sponsors] is not configured")?;
+    let list = fetch_sponsors(state, config).await?;
+    let count = list.sponsors.len();
+    state.sponsors.set(list);
+    Ok(count)
+}
+
+/// The cached sponsors, fetched first when there are none or they are
+/// older than [`REFRESH_AFTER_SECS`]. If that fails, stale sponsors are
+/// served; the error is returned only when nothing is cached.
+pub async fn current_sponsors(state: &AppState) -> Result<SponsorList, String> {
+    let cached = state.sponsors.get();
+    if let Some(list) = &cached
+        && Utc::now() - list.fetched_at < Duration::seconds(REFRESH_AFTER_SECS)
+    {
+        return Ok(list.clone());
+    }
+    match refresh_sponsors(state).await {
+        Ok(_) => Ok(state.sponsors.get().expect("sponsors were just cached")),
+        Err(e) => match cached {
+            Some(list) => {
+                tracing::warn!("Serving stale sponsors: {}", e);
+                Ok(list)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_public_sponsorships_and_follows_pages() {
+        let body = json!({
+            "data": { "user": { "sponsorshipsAsMaintainer": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "Y3Vyc29y" },
+                "nodes": [
+                    {
+                        "createdAt": "2024-01-05T10:00:00Z",
+                        "privacyLevel": "PUBLIC",
+                        "tier": { "name": "$5 a month", "monthlyPriceInDollars": 5 },
+                        "sponsorEntity": {
+                            "login": "octocat",
+                            "name": null,
+                            "avatarUrl": "https://avatars.example/octocat",
+                            "url": "https://github.com/octocat"
+                        }
+                    },
+                    {
+                        "createdAt": "2024-02-01T00:00:00Z",
+                        "privacyLevel": "PRIVATE",
+                        "tier": null,
+                        "sponsorEntity": { "login": "shy" }
+                    }
+                ]
+            } } }
+        });
+        let page = parse_sponsorships(&body).unwrap();
+        assert_eq!(page.next.as_deref(), Some("Y3Vyc29y"));
+        assert_eq!(page.sponsors.len(), 1);
+        let (amount, sponsor) = &page.sponsors[0];
+        assert_eq!(*amount, 5);
+        assert_eq!(sponsor.name, "octocat");
+        assert_eq!(sponsor.tier.as_deref(), Some("$5 a month"));
+
+        let denied = json!({ "data": null, "errors": [{ "message": "Bad credentials" }] });
+        assert_eq!(
+            parse_sponsorships(&denied).unwrap_err(),
+            "GitHub answered: Bad credentials"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::db::DbPool;
+use crate::models::suggestion::Suggestion;
+
+const SUGGESTION_COLUMNS: &str = "id, article_slug, author_github_id, author_login, original, \
+                                  replacement, note, status, created_at";
+
+pub async fn add_suggestion(
+    pool: &DbPool,
+    article_slug: &str,
+    author_github_id: u64,
+    author_login: &str,
+    original: &str,
+    replacement: &str,
+    note: Option<&str>,
+) -> Result<Suggestion, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "INSERT INTO article_suggestions \
+         (article_slug, author_github_id, author_login, original, replacement, note) \
+         VALUES (?, ?, ?, ?, ?, ?) RETURNING {}",
+        SUGGESTION_COLUMNS
+    ))
+    .bind(article_slug)
+    .bind(author_github_id as i64)
+    .bind(author_login)
+    .bind(original)
+    .bind(replacement)
+    .bind(note)
+    .fetch_one(pool)
+    .await
+}
+
+/// Suggestions still waiting for a decision, oldest first.
+pub async fn pending_suggestions(pool: &DbPool) -> Result<Vec<Suggestion>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM article_suggestions WHERE status = 'pending' ORDER BY id",
+        SUGGESTION_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn pending_suggestion(pool: &DbPool, id: i64) -> Result<Option<Suggestion>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {} FROM article_suggestions WHERE id = ? AND status = 'pending'",
+        SUGGESTION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn count_pending(pool: &DbPool) -> Result<usize, sqlx::Error> {
+    let total: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM article_suggestions WHERE status = 'pending'")
+            .fetch_one(pool)
+            .await?;
+    Ok(total as usize)
+}
+
+/// Moves a pending suggestion to `status`. Returns `false` when it does not
+/// exist or was already resolved.
+pub async fn resolve_suggestion(pool: &DbPool, id: i64, status: &str) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query(
+        "UPDATE article_suggestions SET status = ? WHERE id = ? AND status = 'pending'",
+    )
+    .bind(status)
+    .bind(id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(updated > 0)
+}
+
+/// `body` with its one occurrence of `original` replaced. `None` when the
+/// text is missing or ambiguous, so a correction is never applied to the
+/// wrong place.
+pub fn apply_suggestion(body: &str, original: &str, replacement: &str) -> Option<String> {
+    let mut matches = body.match_indices(original);
+    let (start, _) = matches.next()?;
+    if original.is_empty() || matches.next().is_some() {
+        return None;
+    }
+    let mut patched = String::with_capacity(body.len() + replacement.len());
+    patched.push_str(&body[..start]);
+    patched.push_str(replacement);
+    patched.push_str(&body[start + original.len()..]);
+    Some(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[test]
+    fn applies_only_unambiguous_corrections() {
+        let body = "Ferris is a crab.\nFerris likes Rust.";
+        assert_eq!(
+            apply_suggestion(body, "a crab", "the crab").as_deref(),
+            Some("Ferris is the crab.\nFerris likes Rust.")
+        );
+        assert_eq!(apply_suggestion(body, "Ferris", "Corro"), None);
+        assert_eq!(apply_suggestion(body, "a lobster", "a crab"), None);
+        assert_eq!(apply_suggestion(body, "", "x"), None);
+    }
+
+    #[tokio::test]
+    async fn resolved_suggestions_leave_the_queue() {
+        let pool = memory_db().await;
+        let first = add_suggestion(&pool, "hello-world", 2, "bob", "teh", "the", None)
+            .await
+            .unwrap();
+        add_suggestion(&pool, "hello-world", 3, "carol", "a", "an", Some("typo"))
+            .await
+            .unwrap();
+        assert_eq!(count_pending(&pool).await.unwrap(), 2);
+
+        assert!(
+            resolve_suggestion(&pool, first.id, "dismissed")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !resolve_suggestion(&pool, first.id, "accepted")
+                .await
+                .unwrap()
+        );
+        assert!(pending_suggestion(&pool, first.id).await.unwrap().is_none());
+        let pending = pending_suggestions(&pool).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].note.as_deref(), Some("typo"));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::config::SummarizerConfig;
+use crate::server::http_client::HttpClient;
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+
+/// Article bodies are cut to this many characters before being sent, to stay
+/// well inside typical context windows.
+const MAX_INPUT_CHARS: usize = 12_000;
+
+#[derive(Deserialize)]
+struct ChatCompletion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: String,
+}
+
+/// Asks the configured chat-completions endpoint for a one-paragraph
+/// description of the article. `SUMMARIZER_API_KEY`, when set, is sent as a
+/// bearer token.
+pub async fn summarize(
+    http: &HttpClient,
+    config: &SummarizerConfig,
+    title: &str,
+    content: &str,
+) -> Result<String, String> {
+    let body: String = content.chars().take(MAX_INPUT_CHARS).collect();
+    let url = format!("{}/chat/completions", config.endpoint.trim_end_matches('/'));
+    let request = json!({
+        "model": config.model,
+        "temperature": 0.3,
+        "messages": [
+            {
+                "role": "system",
+                "content": format!(
+                    "You write meta descriptions for blog posts. Reply with a single plain-text \
+                     paragraph of at most {} words, in the language of the post, without quotes \
+                     or markdown.",
+                    config.max_words
+                ),
+            },
+            { "role": "user", "content": format!("# {}\n\n{}", title, body) },
+        ],
+    });
+
+    let mut builder = http.post(&url).json(&request);
+    if let Ok(key) = env::var("SUMMARIZER_API_KEY") {
+        builder = builder.bearer_auth(key);
+    }
+
+    let response = http
+        .send(builder)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Summarizer request failed: {}", e))?;
+    let completion: ChatCompletion = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected summarizer response: {}", e))?;
+
+    let summary = completion
+        .choices
+        .into_iter()
+        .next()
+        .map(|c| {
+            c.message
+                .content
+                .trim()
+                .trim_matches('"')
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default();
+    if summary.is_empty() {
+        return Err("Summarizer returned an empty summary".to_string());
+    }
+    Ok(summary)
+}
@@ -0,0 +1,479 @@
+use crate::models::article::{Article, ArticleContent};
+use crate::models::sync::{
+    ChangeFeed, ManifestEntry, SyncCollection, SyncConflict, SyncManifest, SyncRejection,
+    SyncReport, SyncedFile, UploadReport, UploadedFile,
+};
+use crate::server::app::{AppState, IndexJob, rebuild_store, record_content_change};
+use crate::services::article_service::{ArticleWrite, saved_versions};
+use crate::services::atomic_file::{is_temp_file, write_atomic};
+use crate::services::notification_service::{SIGNATURE_HEADER, verify};
+use crate::services::search::IndexCollection;
+use crate::services::service::ArticleStore;
+use crate::services::site_event_service::{SYNCED, last_occurred, record_event_at};
+use chrono::DateTime;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Environment variable holding the secret shared by a primary and its
+/// replicas. Replicas present it as a bearer token; the primary signs its
+/// change feed with it.
+pub const SYNC_SECRET: &str = "SYNC_SECRET";
+
+/// Where the synced collections live on this instance.
+pub struct SyncRoots {
+    pub articles: PathBuf,
+    pub notes: PathBuf,
+    pub pages: PathBuf,
+    pub versions: PathBuf,
+}
+
+impl SyncRoots {
+    pub async fn of(state: &AppState) -> Self {
+        Self {
+            articles: state.store.read().await.root().to_path_buf(),
+            notes: state.note_store.read().await.root().to_path_buf(),
+            pages: state.page_store.read().await.root().to_path_buf(),
+            versions: state.data_dir.join("articles"),
+        }
+    }
+
+    fn root(&self, collection: SyncCollection) -> &Path {
+        match collection {
+            SyncCollection::Articles => &self.articles,
+            SyncCollection::Notes => &self.notes,
+            SyncCollection::Pages => &self.pages,
+            SyncCollection::Versions => &self.versions,
+        }
+    }
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+fn is_synced(collection: SyncCollection, path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str());
+    match collection {
+        SyncCollection::Articles | SyncCollection::Notes | SyncCollection::Pages => {
+            extension == Some("md")
+        }
+        SyncCollection::Versions => matches!(extension, Some("md" | "message")),
+    }
+}
+
+/// Synced files under the root of `collection`, with their paths relative
+/// to it, `/`-separated.
+fn collection_files(roots: &SyncRoots, collection: SyncCollection) -> Vec<(String, PathBuf)> {
+    let root = roots.root(collection);
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| is_synced(collection, path) && !is_temp_file(path))
+        .filter_map(|path| {
+            let relative = path.strip_prefix(root).ok()?;
+            let relative: Vec<&str> = relative
+                .components()
+                .map(|c| c.as_os_str().to_str())
+                .collect::<Option<_>>()?;
+            Some((relative.join("/"), path))
+        })
+        .collect()
+}
+
+/// Resolves a path from a change feed under `root`, refusing anything that
+/// could point outside it.
+fn resolve(root: &Path, relative: &str) -> Option<PathBuf> {
+    let path = Path::new(relative);
+    let normal = path.components().all(|c| matches!(c, Component::Normal(_)));
+    (normal && !relative.is_empty()).then(|| root.join(path))
+}
+
+/// Files changed since `since`, in milliseconds since the epoch, plus the
+/// paths of every article, note and page there is now.
+pub fn change_feed(roots: &SyncRoots, since: u64) -> io::Result<ChangeFeed> {
+    let until = millis(SystemTime::now());
+    let mut feed = ChangeFeed {
+        until,
+        files: Vec::new(),
+        articles: Vec::new(),
+        notes: Vec::new(),
+        pages: Vec::new(),
+    };
+    for collection in [
+        SyncCollection::Articles,
+        SyncCollection::Notes,
+        SyncCollection::Pages,
+        SyncCollection::Versions,
+    ] {
+        for (relative, path) in collection_files(roots, collection) {
+            match collection {
+                SyncCollection::Articles => feed.articles.push(relative.clone()),
+                SyncCollection::Notes => feed.notes.push(relative.clone()),
+                SyncCollection::Pages => feed.pages.push(relative.clone()),
+                SyncCollection::Versions => {}
+            }
+            let modified = millis(fs::metadata(&path)?.modified()?);
+            if modified < since {
+                continue;
+            }
+            // Files that are not UTF-8 are not content this server reads.
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            feed.files.push(SyncedFile {
+                collection,
+                path: relative,
+                modified,
+                content,
+            });
+        }
+    }
+    Ok(feed)
+}
+
+/// Writes the files of `feed` that differ from the local copies and removes
+/// articles, notes and pages the primary no longer has.
+pub fn apply_feed(roots: &SyncRoots, feed: &ChangeFeed) -> io::Result<SyncReport> {
+    let mut report = SyncReport::default();
+    let mark = |report: &mut SyncReport, collection| {
+        if !report.changed.contains(&collection) {
+            report.changed.push(collection);
+        }
+    };
+
+    for file in &feed.files {
+        let Some(path) = resolve(roots.root(file.collection), &file.path) else {
+            tracing::warn!("Skipping synced file outside its collection: {}", file.path);
+            continue;
+        };
+        if fs::read(&path).is_ok_and(|local| local == file.content.as_bytes()) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&path, &file.content)?;
+        report.written += 1;
+        mark(&mut report, file.collection);
+    }
+
+    for (collection, kept) in [
+        (SyncCollection::Articles, &feed.articles),
+        (SyncCollection::Notes, &feed.notes),
+        (SyncCollection::Pages, &feed.pages),
+    ] {
+        for (relative, path) in collection_files(roots, collection) {
+            if !kept.contains(&relative) {
+                fs::remove_file(&path)?;
+                report.removed += 1;
+                mark(&mut report, collection);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Pulls the changes since the last pull from the configured primary,
+/// applies them and reloads the collections they touched.
+pub async fn pull(state: &Arc<AppState>) -> Result<SyncReport, String> {
+    let Some(sync) = &state.config.sync else {
+        return Err("no primary is configured".to_string());
+    };
+    let secret = env::var(SYNC_SECRET).map_err(|_| format!("{} is not set", SYNC_SECRET))?;
+    let since = last_occurred(&state.db, SYNCED)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_or(0, |at| at.timestamp_millis() as u64);
+
+    let url = format!(
+        "{}/api/sync/changes?since={}",
+        sync.primary.trim_end_matches('/'),
+        since
+    );
+    let response = state
+        .http
+        .send(state.http.get(&url).bearer_auth(&secret))
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch changes: {}", e))?;
+    let signature = response
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read changes: {}", e))?;
+    if !signature.is_some_and(|signature| verify(&secret, &body, &signature)) {
+        return Err("change feed is not signed with the sync secret".to_string());
+    }
+    let feed: ChangeFeed =
+        serde_json::from_slice(&body).map_err(|e| format!("Invalid change feed: {}", e))?;
+
+    let roots = SyncRoots::of(state).await;
+    let report =
+        apply_feed(&roots, &feed).map_err(|e| format!("Failed to apply changes: {}", e))?;
+    for collection in &report.changed {
+        let collection = match collection {
+            SyncCollection::Articles => IndexCollection::Articles,
+            SyncCollection::Notes => IndexCollection::Notes,
+            SyncCollection::Pages => IndexCollection::Pages,
+            SyncCollection::Versions => continue,
+        };
+        rebuild_store(state, collection)
+            .await
+            .map_err(|e| format!("Failed to reload synced content: {:?}", e))?;
+    }
+    if !report.changed.is_empty() {
+        record_content_change(state).await;
+    }
+
+    let until = DateTime::from_timestamp_millis(feed.until as i64).unwrap_or_default();
+    record_event_at(&state.db, SYNCED, until)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Change message of the versions saved for files uploaded by editing
+/// clients.
+const UPLOAD_MESSAGE: &str = "Synced from an editing client";
+
+/// Hex-encoded SHA-256 of a file, as sync manifests list it.
+pub fn content_hash(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+fn manifest_entry(
+    root: &Path,
+    data_dir: &Path,
+    article: &Article,
+    content: &[u8],
+) -> ManifestEntry {
+    let file_path = Path::new(&article.file_path);
+    let relative = file_path.strip_prefix(root).unwrap_or(file_path);
+    ManifestEntry {
+        path: relative
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/"),
+        hash: content_hash(content),
+        version: saved_versions(data_dir, &article.slug_with_category())
+            .last()
+            .copied()
+            .unwrap_or(0),
+    }
+}
+
+/// Hashes and versions of every article in `store`, drafts included.
+/// Files removed since the store last looked are left out.
+pub fn manifest(store: &ArticleStore, data_dir: &Path) -> io::Result<SyncManifest> {
+    let mut manifest = SyncManifest::default();
+    for article in store.query(|_| true, 0, usize::MAX) {
+        let content = match fs::read(&article.file_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let entry = manifest_entry(store.root(), data_dir, article, &content);
+        manifest
+            .articles
+            .insert(article.slug_with_category(), entry);
+    }
+    Ok(manifest)
+}
+
+/// Writes one uploaded file and loads it into `store`, saving a version
+/// of it. Undoes the write when the file is not a valid article. Returns
+/// the article with its body.
+fn upload_file(
+    state: &AppState,
+    store: &mut ArticleStore,
+    path: &Path,
+    content: &str,
+) -> Result<(Article, String), String> {
+    let mut write = ArticleWrite::begin(path).map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    write_atomic(path, content).map_err(|e| e.to_string())?;
+
+    let path = path.to_string_lossy();
+    let article = match store.update_single_article(&path) {
+        Ok(()) => store.query(|a| a.file_path == path, 0, 1).next().cloned(),
+        Err(e) => {
+            write.rollback();
+            return Err(format!("{:?}", e));
+        }
+    };
+    let Some(article) = article else {
+        write.rollback();
+        return Err("The file is not loaded as an article".to_string());
+    };
+    if let Err(e) = write.save_version(&state.data_dir, &article, Some(UPLOAD_MESSAGE)) {
+        let undone = write.rollback();
+        // Load what the rollback left, or drop the file if it was new.
+        if store.update_single_article(&path).is_err() {
+            store.remove_article(&path);
+        }
+        return Err(format!(
+            "Failed to save a version: {}; {}",
+            e,
+            undone.join(", ")
+        ));
+    }
+    let body = store
+        .load_content_for(&article)
+        .map_err(|e| format!("{:?}", e))?;
+    Ok((article, body))
+}
+
+/// Applies the files of an editing client's working copy. A file is only
+/// written when the server copy is still the one the client started from,
+/// so edits made elsewhere are reported as conflicts instead of lost. The
+/// store stays locked from each comparison through its write, so edits
+/// made through the API cannot slip in between.
+pub async fn apply_upload(state: &AppState, files: Vec<UploadedFile>) -> UploadReport {
+    let mut store = state.store.write().await;
+    let root = store.root().to_path_buf();
+    let mut report = UploadReport::default();
+    for file in files {
+        let reject = |message: &str| SyncRejection {
+            path: file.path.clone(),
+            message: message.to_string(),
+        };
+        let Some(path) =
+            resolve(&root, &file.path).filter(|path| path.extension().is_some_and(|e| e == "md"))
+        else {
+            report
+                .rejected
+                .push(reject("Not a Markdown file inside the content directory"));
+            continue;
+        };
+        let server_hash = match fs::read(&path) {
+            Ok(bytes) => Some(content_hash(&bytes)),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => {
+                report.rejected.push(reject(&e.to_string()));
+                continue;
+            }
+        };
+        if server_hash != file.base_hash {
+            report.conflicts.push(SyncConflict {
+                path: file.path,
+                base_hash: file.base_hash,
+                server_hash,
+            });
+            continue;
+        }
+        let (article, body) = match upload_file(state, &mut store, &path, &file.content) {
+            Ok(uploaded) => uploaded,
+            Err(message) => {
+                report.rejected.push(reject(&message));
+                continue;
+            }
+        };
+        report.applied.push(manifest_entry(
+            &root,
+            &state.data_dir,
+            &article,
+            file.content.as_bytes(),
+        ));
+        if let Some(tx) = &state.index_tx {
+            let _ = tx.send(IndexJob::for_article(ArticleContent {
+                slug: article.slug_with_category(),
+                metadata: article.metadata,
+                content: body,
+                edit_url: None,
+            }));
+        }
+    }
+    drop(store);
+    if !report.applied.is_empty() {
+        state.cache.invalidate_all();
+        record_content_change(state).await;
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn roots(dir: &TempDir) -> SyncRoots {
+        let roots = SyncRoots {
+            articles: dir.path().join("article"),
+            notes: dir.path().join("notes"),
+            pages: dir.path().join("pages"),
+            versions: dir.path().join("data/articles"),
+        };
+        for root in [&roots.articles, &roots.notes, &roots.pages, &roots.versions] {
+            fs::create_dir_all(root).unwrap();
+        }
+        roots
+    }
+
+    #[test]
+    fn feeds_bring_replicas_up_to_date() {
+        let (primary_dir, replica_dir) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let (primary, replica) = (roots(&primary_dir), roots(&replica_dir));
+        fs::create_dir_all(primary.articles.join("guides")).unwrap();
+        fs::write(primary.articles.join("guides/axum.md"), "Axum").unwrap();
+        fs::write(primary.notes.join("idea.md"), "Idea").unwrap();
+        fs::write(primary.notes.join("cover.png"), [0u8, 159]).unwrap();
+        fs::write(primary.pages.join("about.md"), "About").unwrap();
+        fs::create_dir_all(primary.versions.join("axum/versions")).unwrap();
+        fs::write(primary.versions.join("axum/versions/1.md"), "Axum").unwrap();
+        fs::write(primary.versions.join("axum/versions/1.message"), "First").unwrap();
+        fs::write(replica.articles.join("stale.md"), "Gone").unwrap();
+        fs::write(replica.pages.join("old.md"), "Gone").unwrap();
+
+        let feed = change_feed(&primary, 0).unwrap();
+        assert_eq!(feed.files.len(), 5);
+        assert_eq!(feed.articles, ["guides/axum.md"]);
+        assert_eq!(feed.pages, ["about.md"]);
+        let report = apply_feed(&replica, &feed).unwrap();
+        assert_eq!((report.written, report.removed), (5, 2));
+        assert_eq!(
+            fs::read_to_string(replica.articles.join("guides/axum.md")).unwrap(),
+            "Axum"
+        );
+        assert!(replica.versions.join("axum/versions/1.message").exists());
+        assert!(!replica.articles.join("stale.md").exists());
+        assert_eq!(
+            fs::read_to_string(replica.pages.join("about.md")).unwrap(),
+            "About"
+        );
+        assert!(!replica.pages.join("old.md").exists());
+
+        // Applying the same changes again touches nothing.
+        let report = apply_feed(&replica, &feed).unwrap();
+        assert_eq!(report, SyncReport::default());
+        assert!(
+            change_feed(&primary, feed.until + 60_000)
+                .unwrap()
+                .files
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn paths_cannot_leave_their_collection() {
+        let root = Path::new("/srv/article");
+        assert_eq!(resolve(root, "a/b.md"), Some(root.join("a/b.md")));
+        assert_eq!(resolve(root, "../secrets.md"), None);
+        assert_eq!(resolve(root, "/etc/passwd"), None);
+        assert_eq!(resolve(root, "a/./b.md"), Some(root.join("a/b.md")));
+        assert_eq!(resolve(root, ""), None);
+    }
+}
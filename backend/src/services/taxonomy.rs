@@ -0,0 +1,272 @@
+use crate::config::TaxonomyConfig;
+use crate::handlers::error::LoadError;
+use crate::models::article::{Article, ArticleTeaser};
+use crate::models::taxonomy::{TaxonomyViolation, TermDetail, TermPageMetadata};
+use gray_matter::Matter;
+use gray_matter::engine::YAML;
+use regex::Regex;
+use serde_yaml::from_value;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+/// The two kinds of terms a taxonomy page can describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermKind {
+    Tag,
+    Category,
+}
+
+impl TermKind {
+    fn dir(self) -> &'static str {
+        match self {
+            TermKind::Tag => "tags",
+            TermKind::Category => "categories",
+        }
+    }
+}
+
+/// Where the page describing `name` lives under `root`:
+/// `tags/{tag}.md` or `categories/{category}.md`, nested categories in
+/// subdirectories. `None` for names that would leave the directory.
+fn page_path(root: &Path, kind: TermKind, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name);
+    if name.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(root.join(kind.dir()).join(format!("{}.md", name)))
+}
+
+fn teaser(article: &Article) -> ArticleTeaser {
+    ArticleTeaser {
+        slug: article.slug_with_category(),
+        metadata: article.metadata.clone(),
+        edit_url: None,
+    }
+}
+
+/// Describes a term from `articles`, the published articles under it that
+/// the requester may see, newest first, merging in its taxonomy page from
+/// `root` when one exists. Front matter is optional; the body is the page's
+/// intro. `page_size` sets how many teasers the first page holds.
+pub fn term_detail(
+    root: &Path,
+    kind: TermKind,
+    name: &str,
+    articles: &[&Article],
+    page_size: usize,
+) -> Result<TermDetail, LoadError> {
+    let page = load_page(root, kind, name)?;
+    let (metadata, content) = page.unwrap_or_default();
+    let pinned = metadata
+        .pinned
+        .iter()
+        .filter_map(|slug| {
+            articles
+                .iter()
+                .find(|a| a.slug == *slug || a.slug_with_category() == *slug)
+        })
+        .map(|a| teaser(a))
+        .collect();
+
+    Ok(TermDetail {
+        name: name.to_string(),
+        count: articles.len(),
+        title: metadata.title,
+        description: metadata.description,
+        cover: metadata.cover,
+        content,
+        pinned,
+        articles: articles.iter().take(page_size).map(|a| teaser(a)).collect(),
+        total_pages: articles.len().div_ceil(page_size),
+    })
+}
+
+/// Reads the taxonomy page for `name`: its front matter and its body, when
+/// the body is not empty.
+fn load_page(
+    root: &Path,
+    kind: TermKind,
+    name: &str,
+) -> Result<Option<(TermPageMetadata, Option<String>)>, LoadError> {
+    let Some(path) = page_path(root, kind, name) else {
+        return Ok(None);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let parsed = Matter::<YAML>::new()
+        .parse::<serde_yaml::Value>(&text)
+        .map_err(|e| {
+            LoadError::MatterParse(format!(
+                "Failed to parse front matter in {}: {}",
+                path.to_string_lossy(),
+                e
+            ))
+        })?;
+    let metadata: TermPageMetadata = match parsed.data {
+        Some(data) => from_value(data)?,
+        None => TermPageMetadata::default(),
+    };
+    let content = parsed.content.trim();
+    Ok(Some((
+        metadata,
+        (!content.is_empty()).then(|| content.to_string()),
+    )))
+}
+
+/// Allowed values of one term kind: a list, patterns that must match the
+/// whole value, or neither to allow anything.
+#[derive(Debug, Default)]
+struct Allowed {
+    values: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Allowed {
+    fn new(values: &[String], patterns: &[String]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            values: values.to_vec(),
+            patterns: patterns
+                .iter()
+                .map(|p| Regex::new(&format!("^(?:{})$", p)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn allows(&self, value: &str) -> bool {
+        (self.values.is_empty() && self.patterns.is_empty())
+            || self.values.iter().any(|v| v == value)
+            || self.patterns.iter().any(|p| p.is_match(value))
+    }
+}
+
+/// The `[taxonomy]` rules, with their patterns compiled.
+#[derive(Debug, Default)]
+pub struct TaxonomyRules {
+    tags: Allowed,
+    categories: Allowed,
+    pub strict: bool,
+}
+
+impl TaxonomyRules {
+    pub fn new(config: &TaxonomyConfig) -> Result<Self, regex::Error> {
+        Ok(Self {
+            tags: Allowed::new(&config.tags, &config.tag_patterns)?,
+            categories: Allowed::new(&config.categories, &config.category_patterns)?,
+            strict: config.strict,
+        })
+    }
+
+    /// The tags and category the rules do not allow, tags first.
+    pub fn violations(&self, tags: &[String], category: Option<&str>) -> Vec<TaxonomyViolation> {
+        let mut violations: Vec<TaxonomyViolation> = tags
+            .iter()
+            .filter(|tag| !self.tags.allows(tag))
+            .map(|tag| TaxonomyViolation {
+                field: "tags",
+                value: tag.clone(),
+            })
+            .collect();
+        if let Some(category) = category
+            && !self.categories.allows(category)
+        {
+            violations.push(TaxonomyViolation {
+                field: "category",
+                value: category.to_string(),
+            });
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reads_taxonomy_pages_inside_the_directory() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("categories/guides")).unwrap();
+        fs::write(
+            dir.path().join("categories/guides/axum.md"),
+            "---\ntitle: \"Axum guides\"\ncover: images/axum.png\npinned: [guides/axum/intro]\n---\n\nStart here.\n",
+        )
+        .unwrap();
+
+        let (metadata, content) = load_page(dir.path(), TermKind::Category, "guides/axum")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            metadata,
+            TermPageMetadata {
+                title: Some("Axum guides".to_string()),
+                description: None,
+                cover: Some("images/axum.png".to_string()),
+                pinned: vec!["guides/axum/intro".to_string()],
+            }
+        );
+        assert_eq!(content.as_deref(), Some("Start here."));
+
+        // Missing pages, and names that would escape the directory, have
+        // no page.
+        for name in ["guides", "../categories/guides/axum", "/etc/passwd", ""] {
+            let page = load_page(dir.path(), TermKind::Category, name).unwrap();
+            assert!(page.is_none(), "{name}");
+        }
+        assert!(
+            load_page(dir.path(), TermKind::Tag, "guides/axum")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn rules_allow_listed_and_matching_terms() {
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert!(
+            TaxonomyRules::default()
+                .violations(&tags(&["anything"]), Some("any/where"))
+                .is_empty()
+        );
+
+        let rules = TaxonomyRules::new(&TaxonomyConfig {
+            tags: tags(&["rust", "web"]),
+            tag_patterns: tags(&["til-\\d+"]),
+            categories: tags(&["guides"]),
+            category_patterns: Vec::new(),
+            strict: true,
+        })
+        .unwrap();
+        assert!(rules.strict);
+        assert!(
+            rules
+                .violations(&tags(&["rust", "til-12"]), Some("guides"))
+                .is_empty()
+        );
+        let violations = rules.violations(&tags(&["rust", "Rust", "til-12x"]), Some("guides/axum"));
+        let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "tag Rust is not allowed",
+                "tag til-12x is not allowed",
+                "category guides/axum is not allowed"
+            ]
+        );
+
+        let invalid = TaxonomyConfig {
+            tag_patterns: tags(&["("]),
+            ..Default::default()
+        };
+        assert!(TaxonomyRules::new(&invalid).is_err());
+    }
+}
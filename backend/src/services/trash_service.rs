@@ -0,0 +1,251 @@
+use crate::models::article::Article;
+use crate::models::trash::{TrashItem, TrashPreview};
+use crate::services::atomic_file::write_atomic;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chrono::{DateTime, Duration, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Folder under the data directory holding deleted articles. Each
+/// deletion is kept under its own id as `{id}.md`, the file as it was,
+/// next to `{id}.json` with what the listing shows.
+pub const TRASH_DIR: &str = "trash";
+
+/// Path of the trash entry `id` with `extension`, `None` for ids that
+/// could reach outside the trash folder.
+fn trash_path(data_dir: &Path, id: &str, extension: &str) -> Option<PathBuf> {
+    if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+        return None;
+    }
+    Some(
+        data_dir
+            .join(TRASH_DIR)
+            .join(format!("{}.{}", id, extension)),
+    )
+}
+
+/// A fresh id for a deletion at `deleted_at`: its time, so ids sort in
+/// deletion order, and a random suffix for deletions in the same
+/// millisecond.
+fn trash_id(deleted_at: DateTime<Utc>) -> String {
+    let mut suffix = [0u8; 4];
+    OsRng.fill_bytes(&mut suffix);
+    format!(
+        "{}-{}",
+        deleted_at.format("%Y%m%d%H%M%S%3f"),
+        hex::encode(suffix)
+    )
+}
+
+fn read_item(path: &Path) -> io::Result<TrashItem> {
+    serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Moves the file of `article`, which lives under the content `root`, to
+/// the trash. Each deletion gets an entry of its own, so deleting an
+/// article restored earlier, or another one with the same slug, keeps the
+/// copies trashed before.
+pub fn trash_article(
+    data_dir: &Path,
+    root: &Path,
+    article: &Article,
+    deleted_by: &str,
+) -> io::Result<TrashItem> {
+    let file_path = Path::new(&article.file_path);
+    let deleted_at = Utc::now();
+    let id = trash_id(deleted_at);
+    let trash_dir = data_dir.join(TRASH_DIR);
+    let file = trash_dir.join(format!("{}.md", id));
+    let record = trash_dir.join(format!("{}.json", id));
+    let content = fs::read_to_string(file_path)?;
+    let item = TrashItem {
+        id,
+        slug: article.slug_with_category(),
+        title: article.metadata.title.clone(),
+        path: file_path
+            .strip_prefix(root)
+            .unwrap_or(file_path)
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/"),
+        deleted_at,
+        deleted_by: deleted_by.to_string(),
+        size: content.len() as u64,
+    };
+    fs::create_dir_all(&trash_dir)?;
+    write_atomic(&file, &content)?;
+    let json = serde_json::to_string_pretty(&item)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    write_atomic(&record, json)?;
+    fs::remove_file(file_path)?;
+    Ok(item)
+}
+
+/// Everything in the trash, most recently deleted first. Unreadable
+/// records are skipped with a warning.
+pub fn list_trash(data_dir: &Path) -> io::Result<Vec<TrashItem>> {
+    let entries = match fs::read_dir(data_dir.join(TRASH_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut items = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match read_item(&path) {
+            Ok(item) => items.push(item),
+            Err(e) => tracing::warn!("Skipping trash record {}: {}", path.display(), e),
+        }
+    }
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    Ok(items)
+}
+
+/// The trash entry `id` with its file, if it is in the trash.
+pub fn trashed(data_dir: &Path, id: &str) -> io::Result<Option<TrashPreview>> {
+    let (Some(file), Some(record)) = (
+        trash_path(data_dir, id, "md"),
+        trash_path(data_dir, id, "json"),
+    ) else {
+        return Ok(None);
+    };
+    let item = match read_item(&record) {
+        Ok(item) => item,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let content = fs::read_to_string(file)?;
+    Ok(Some(TrashPreview { item, content }))
+}
+
+/// Puts the article trashed as `id` back where it was under `root` and
+/// returns its path. Fails with [`ErrorKind::AlreadyExists`] when a file
+/// has taken its place since, leaving the trash as it was.
+pub fn restore_trashed(data_dir: &Path, root: &Path, id: &str) -> io::Result<Option<PathBuf>> {
+    let Some(preview) = trashed(data_dir, id)? else {
+        return Ok(None);
+    };
+    let target = root.join(&preview.item.path);
+    if !target.starts_with(root) || preview.item.path.split('/').any(|part| part == "..") {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} points outside the content directory", preview.item.path),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target)?
+        .write_all(preview.content.as_bytes())?;
+    purge_trashed(data_dir, id)?;
+    Ok(Some(target))
+}
+
+/// Deletes the trash entry `id` for good. Returns whether it was in the
+/// trash.
+pub fn purge_trashed(data_dir: &Path, id: &str) -> io::Result<bool> {
+    let (Some(file), Some(record)) = (
+        trash_path(data_dir, id, "md"),
+        trash_path(data_dir, id, "json"),
+    ) else {
+        return Ok(false);
+    };
+    let existed = match fs::remove_file(&record) {
+        Ok(()) => true,
+        Err(e) if e.kind() == ErrorKind::NotFound => false,
+        Err(e) => return Err(e),
+    };
+    match fs::remove_file(&file) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(existed),
+        Err(e) => Err(e),
+    }
+}
+
+/// Purges what was deleted more than `days` days before `now`. Returns
+/// the ids purged.
+pub fn purge_expired_trash(
+    data_dir: &Path,
+    days: u32,
+    now: DateTime<Utc>,
+) -> io::Result<Vec<String>> {
+    let cutoff = now - Duration::days(days.into());
+    let mut purged = Vec::new();
+    for item in list_trash(data_dir)? {
+        if item.deleted_at < cutoff && purge_trashed(data_dir, &item.id)? {
+            purged.push(item.id);
+        }
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::service::ArticleStore;
+    use tempfile::tempdir;
+
+    const SETUP: &str = "---\ntitle: Setup\nauthor: a\ndate: 2024-01-01T00:00:00Z\n---\n\nBody\n";
+
+    #[test]
+    fn trashed_articles_can_be_restored_or_expire() {
+        let content = tempdir().unwrap();
+        let data = tempdir().unwrap();
+        fs::create_dir(content.path().join("guides")).unwrap();
+        let file = content.path().join("guides").join("setup.md");
+        fs::write(&file, SETUP).unwrap();
+        let store = ArticleStore::new(content.path(), true).unwrap();
+        let article = store.get_by_slug("setup").unwrap();
+
+        let item = trash_article(data.path(), content.path(), article, "ferris").unwrap();
+        assert!(!file.exists());
+        assert_eq!(item.slug, "guides/setup");
+        assert_eq!(item.path, "guides/setup.md");
+        assert_eq!(item.size, SETUP.len() as u64);
+        assert_eq!(list_trash(data.path()).unwrap(), [item.clone()]);
+        let preview = trashed(data.path(), &item.id).unwrap().unwrap();
+        assert_eq!(preview.content, SETUP);
+        assert!(trashed(data.path(), "../setup").unwrap().is_none());
+
+        // A file created in its place since blocks the restore.
+        fs::write(&file, "new").unwrap();
+        let err = restore_trashed(data.path(), content.path(), &item.id).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        fs::remove_file(&file).unwrap();
+        let restored = restore_trashed(data.path(), content.path(), &item.id).unwrap();
+        assert_eq!(restored, Some(file.clone()));
+        assert!(list_trash(data.path()).unwrap().is_empty());
+
+        // Deleting it again keeps both copies.
+        let first = trash_article(data.path(), content.path(), article, "ferris").unwrap();
+        fs::write(&file, "second").unwrap();
+        let second = trash_article(data.path(), content.path(), article, "ferris").unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(list_trash(data.path()).unwrap().len(), 2);
+        assert_eq!(
+            trashed(data.path(), &first.id).unwrap().unwrap().content,
+            SETUP
+        );
+
+        let now = Utc::now();
+        assert!(
+            purge_expired_trash(data.path(), 30, now)
+                .unwrap()
+                .is_empty()
+        );
+        let mut purged = purge_expired_trash(data.path(), 30, now + Duration::days(31)).unwrap();
+        let mut ids = vec![first.id.clone(), second.id];
+        purged.sort();
+        ids.sort();
+        assert_eq!(purged, ids);
+        assert!(trashed(data.path(), &first.id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::db::DbPool;
+use crate::services::token::{generate_token, hash_token};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+/// A table whose rows admin deletions can put back.
+pub struct Table {
+    name: &'static str,
+    /// Column the rows removed by one deletion share.
+    key: &'static str,
+    columns: &'static [&'static str],
+}
+
+pub static GUESTBOOK_ENTRIES: Table = Table {
+    name: "guestbook_entries",
+    key: "id",
+    columns: &[
+        "id",
+        "author_github_id",
+        "author_login",
+        "content",
+        "approved",
+        "created_at",
+    ],
+};
+
+pub static REDIRECTS: Table = Table {
+    name: "redirects",
+    key: "id",
+    columns: &["id", "from_path", "to_url", "status", "created_at"],
+};
+
+pub static SAVED_SEARCHES: Table = Table {
+    name: "saved_searches",
+    key: "id",
+    columns: &[
+        "id",
+        "name",
+        "query",
+        "notify",
+        "last_comment_id",
+        "created_at",
+    ],
+};
+
+pub static SAVED_SEARCH_HITS: Table = Table {
+    name: "saved_search_hits",
+    key: "saved_search_id",
+    columns: &["saved_search_id", "slug"],
+};
+
+pub static MODERATION_RULES: Table = Table {
+    name: "moderation_rules",
+    key: "id",
+    columns: &["id", "pattern", "regex", "action", "created_at"],
+};
+
+static TABLES: [&Table; 5] = [
+    &GUESTBOOK_ENTRIES,
+    &REDIRECTS,
+    &SAVED_SEARCHES,
+    &SAVED_SEARCH_HITS,
+    &MODERATION_RULES,
+];
+
+/// Something a deletion removed, kept until its token expires.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Deleted {
+    /// A row, as the JSON object of its columns.
+    Row { table: String, row: String },
+    /// An article moved to the trash, by its trash id.
+    Trashed { trash_id: String },
+}
+
+/// What became of an undo request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Undo {
+    /// Everything was put back; holds the paths of the articles restored
+    /// from the trash.
+    Restored(Vec<PathBuf>),
+    /// The token is unknown, expired or was already used.
+    NotFound,
+    /// A deleted row clashes with one created since; nothing was restored
+    /// and the token stays valid.
+    Conflict,
+}
+
+/// Deletes the rows of each `(table, key)` in one transaction and returns
+/// a token that puts them back until `window_secs` have passed. The first
+/// target is the item being deleted, the others rows belonging to it;
+/// returns `None`, deleting nothing, when the item does not exist.
+pub async fn delete_undoably(
+    pool: &DbPool,
+    targets: &[(&'static Table, i64)],
+    window_secs: u64,
+) -> Result<Option<String>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let mut deleted = Vec::new();
+    for (i, (table, key)) in targets.iter().enumerate() {
+        let fields: Vec<String> = table
+            .columns
+            .iter()
+            .map(|column| format!("'{0}', {0}", column))
+            .collect();
+        let rows: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT json_object({}) FROM {} WHERE {} = ?",
+            fields.join(", "),
+            table.name,
+            table.key
+        ))
+        .bind(key)
+        .fetch_all(&mut *tx)
+        .await?;
+        if i == 0 && rows.is_empty() {
+            return Ok(None);
+        }
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE {} = ?",
+            table.name, table.key
+        ))
+        .bind(key)
+        .execute(&mut *tx)
+        .await?;
+        deleted.extend(rows.into_iter().map(|row| Deleted::Row {
+            table: table.name.to_string(),
+            row,
+        }));
+    }
+
+    let token = issue_token(&mut tx, &deleted, window_secs).await?;
+    tx.commit().await?;
+    Ok(Some(token))
+}
+
+/// A token that puts the article trashed as `trash_id` back until
+/// `window_secs` have passed.
+pub async fn undo_trashing(
+    pool: &DbPool,
+    trash_id: &str,
+    window_secs: u64,
+) -> Result<String, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let deleted = [Deleted::Trashed {
+        trash_id: trash_id.to_string(),
+    }];
+    let token = issue_token(&mut tx, &deleted, window_secs).await?;
+    tx.commit().await?;
+    Ok(token)
+}
+
+/// Stores `deleted` under a new token, clearing expired ones.
+async fn issue_token(
+    conn: &mut SqliteConnection,
+    deleted: &[Deleted],
+    window_secs: u64,
+) -> Result<String, sqlx::Error> {
+    sqlx::query("DELETE FROM undo_tokens WHERE expires_at <= datetime('now')")
+        .execute(&mut *conn)
+        .await?;
+    let snapshot = serde_json::to_string(deleted).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    let token = generate_token();
+    sqlx::query(
+        "INSERT INTO undo_tokens (token_hash, snapshot, expires_at) \
+         VALUES (?, ?, datetime('now', '+' || ? || ' seconds'))",
+    )
+    .bind(hash_token(&token))
+    .bind(snapshot)
+    .bind(window_secs as i64)
+    .execute(&mut *conn)
+    .await?;
+    Ok(token)
+}
+
+/// Puts back what was deleted with `token`: rows with their original ids,
+/// then trashed articles through `restore_trashed`, which returns where it
+/// put the article, `None` when it is no longer in the trash, or fails with
+/// [`ErrorKind::AlreadyExists`] when a file has taken its place. The token
+/// can be used once.
+pub async fn undo(
+    pool: &DbPool,
+    token: &str,
+    mut restore_trashed: impl FnMut(&str) -> io::Result<Option<PathBuf>>,
+) -> Result<Undo, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let snapshot: Option<String> = sqlx::query_scalar(
+        "DELETE FROM undo_tokens WHERE token_hash = ? AND expires_at > datetime('now') \
+         RETURNING snapshot",
+    )
+    .bind(hash_token(token))
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(snapshot) = snapshot else {
+        return Ok(Undo::NotFound);
+    };
+    let deleted: Vec<Deleted> =
+        serde_json::from_str(&snapshot).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+    let mut trashed = Vec::new();
+    for deleted in deleted {
+        let (table, row) = match deleted {
+            Deleted::Row { table, row } => (table, row),
+            Deleted::Trashed { trash_id } => {
+                trashed.push(trash_id);
+                continue;
+            }
+        };
+        let Some(table) = TABLES.iter().find(|t| t.name == table) else {
+            continue;
+        };
+        let values: Vec<String> = table
+            .columns
+            .iter()
+            .map(|column| format!("json_extract(?1, '$.{}')", column))
+            .collect();
+        let inserted = sqlx::query(&format!(
+            "INSERT INTO {} ({}) SELECT {} WHERE true ON CONFLICT DO NOTHING",
+            table.name,
+            table.columns.join(", "),
+            values.join(", ")
+        ))
+        .bind(&row)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            return Ok(Undo::Conflict);
+        }
+    }
+    // Files are put back last, once no row can clash anymore.
+    let mut restored = Vec::new();
+    for trash_id in trashed {
+        match restore_trashed(&trash_id) {
+            Ok(Some(path)) => restored.push(path),
+            Ok(None) => return Ok(Undo::NotFound),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => return Ok(Undo::Conflict),
+            Err(e) => return Err(sqlx::Error::Io(e)),
+        }
+    }
+    tx.commit().await?;
+    Ok(Undo::Restored(restored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+    use crate::services::redirect_service::{add_redirect, list_redirects};
+
+    fn no_trash(_: &str) -> io::Result<Option<PathBuf>> {
+        unreachable!("nothing was trashed")
+    }
+
+    #[tokio::test]
+    async fn deletions_are_restored_once_with_their_ids() {
+        let pool = memory_db().await;
+        let redirect = add_redirect(&pool, "/old", "/articles/new", 308)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            delete_undoably(&pool, &[(&REDIRECTS, redirect.id + 1)], 60)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let token = delete_undoably(&pool, &[(&REDIRECTS, redirect.id)], 60)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(list_redirects(&pool).await.unwrap().is_empty());
+        assert_eq!(
+            undo(&pool, &token, no_trash).await.unwrap(),
+            Undo::Restored(vec![])
+        );
+        let restored = list_redirects(&pool).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, redirect.id);
+        assert_eq!(restored[0].status, 308);
+        assert_eq!(restored[0].created_at, redirect.created_at);
+        assert_eq!(undo(&pool, &token, no_trash).await.unwrap(), Undo::NotFound);
+    }
+
+    #[tokio::test]
+    async fn clashing_or_expired_deletions_are_not_restored() {
+        let pool = memory_db().await;
+        let first = add_redirect(&pool, "/old", "/a", 301)
+            .await
+            .unwrap()
+            .unwrap();
+        let token = delete_undoably(&pool, &[(&REDIRECTS, first.id)], 60)
+            .await
+            .unwrap()
+            .unwrap();
+        add_redirect(&pool, "/old", "/b", 301)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(undo(&pool, &token, no_trash).await.unwrap(), Undo::Conflict);
+        assert_eq!(list_redirects(&pool).await.unwrap()[0].to_url, "/b");
+
+        let second = list_redirects(&pool).await.unwrap()[0].id;
+        let expired = delete_undoably(&pool, &[(&REDIRECTS, second)], 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            undo(&pool, &expired, no_trash).await.unwrap(),
+            Undo::NotFound
+        );
+        assert_eq!(
+            undo(&pool, &token, no_trash).await.unwrap(),
+            Undo::Restored(vec![])
+        );
+    }
+
+    #[tokio::test]
+    async fn trashed_articles_are_restored_through_the_callback() {
+        let pool = memory_db().await;
+        let token = undo_trashing(&pool, "20240101000000000-ab", 60)
+            .await
+            .unwrap();
+        let taken = |_: &str| Err(io::Error::from(ErrorKind::AlreadyExists));
+        assert_eq!(undo(&pool, &token, taken).await.unwrap(), Undo::Conflict);
+
+        let mut asked = Vec::new();
+        let restore = |id: &str| {
+            asked.push(id.to_string());
+            Ok(Some(PathBuf::from("article/setup.md")))
+        };
+        assert_eq!(
+            undo(&pool, &token, restore).await.unwrap(),
+            Undo::Restored(vec![PathBuf::from("article/setup.md")])
+        );
+        assert_eq!(asked, ["20240101000000000-ab"]);
+        assert_eq!(undo(&pool, &token, no_trash).await.unwrap(), Undo::NotFound);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::db::DbPool;
+use crate::models::user_preferences::UserPreferences;
+use crate::services::field_crypto::{FieldCipher, FieldCryptoError, open_field, seal_field};
+
+/// `github_id` and the profile fields of a saved row.
+type ProfileRow = (
+    i64,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+fn decode_error(e: FieldCryptoError) -> sqlx::Error {
+    sqlx::Error::Decode(Box::new(e))
+}
+
+/// The saved preferences of a user, if they have set any. Profile fields
+/// sealed with `cipher` are decrypted.
+pub async fn get_preferences(
+    pool: &DbPool,
+    cipher: Option<&FieldCipher>,
+    github_id: u64,
+) -> Result<Option<UserPreferences>, sqlx::Error> {
+    let prefs: Option<UserPreferences> = sqlx::query_as(
+        "SELECT display_name, bio, avatar, website, theme, language \
+         FROM user_preferences WHERE github_id = ?",
+    )
+    .bind(github_id as i64)
+    .fetch_optional(pool)
+    .await?;
+    let Some(prefs) = prefs else {
+        return Ok(None);
+    };
+    Ok(Some(UserPreferences {
+        display_name: open_field(cipher, prefs.display_name).map_err(decode_error)?,
+        bio: open_field(cipher, prefs.bio).map_err(decode_error)?,
+        avatar: open_field(cipher, prefs.avatar).map_err(decode_error)?,
+        website: open_field(cipher, prefs.website).map_err(decode_error)?,
+        ..prefs
+    }))
+}
+
+/// Replaces the saved preferences of a user. With a `cipher`, the profile
+/// fields are stored encrypted; theme and language stay readable.
+pub async fn save_preferences(
+    pool: &DbPool,
+    cipher: Option<&FieldCipher>,
+    github_id: u64,
+    prefs: &UserPreferences,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO user_preferences \
+         (github_id, display_name, bio, avatar, website, theme, language) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(github_id) DO UPDATE SET display_name = excluded.display_name, \
+         bio = excluded.bio, avatar = excluded.avatar, website = excluded.website, \
+         theme = excluded.theme, language = excluded.language, \
+         updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(github_id as i64)
+    .bind(seal_field(cipher, prefs.display_name.as_deref()))
+    .bind(seal_field(cipher, prefs.bio.as_deref()))
+    .bind(seal_field(cipher, prefs.avatar.as_deref()))
+    .bind(seal_field(cipher, prefs.website.as_deref()))
+    .bind(&prefs.theme)
+    .bind(&prefs.language)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Encrypts the profile fields saved before a key was configured. Returns
+/// the number of users whose preferences were rewritten.
+pub async fn seal_saved_preferences(
+    pool: &DbPool,
+    cipher: &FieldCipher,
+) -> Result<u64, sqlx::Error> {
+    let rows: Vec<ProfileRow> = sqlx::query_as(
+        "SELECT github_id, display_name, bio, avatar, website FROM user_preferences",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut sealed = 0;
+    for (github_id, display_name, bio, avatar, website) in rows {
+        let fields = [display_name, bio, avatar, website];
+        if fields
+            .iter()
+            .flatten()
+            .all(|value| FieldCipher::is_sealed(value))
+        {
+            continue;
+        }
+        let [display_name, bio, avatar, website] = fields.map(|value| {
+            value.map(|value| {
+                if FieldCipher::is_sealed(&value) {
+                    value
+                } else {
+                    cipher.seal(&value)
+                }
+            })
+        });
+        sqlx::query(
+            "UPDATE user_preferences SET display_name = ?, bio = ?, avatar = ?, website = ? \
+             WHERE github_id = ?",
+        )
+        .bind(display_name)
+        .bind(bio)
+        .bind(avatar)
+        .bind(website)
+        .bind(github_id)
+        .execute(pool)
+        .await?;
+        sealed += 1;
+    }
+    Ok(sealed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory_db;
+
+    #[tokio::test]
+    async fn profile_fields_are_stored_encrypted() {
+        let pool = memory_db().await;
+        let cipher = FieldCipher::new(&[3; 32]);
+        let prefs = UserPreferences {
+            display_name: Some("Ada".to_string()),
+            bio: Some("Analyst".to_string()),
+            theme: Some("dark".to_string()),
+            ..Default::default()
+        };
+        save_preferences(&pool, None, 7, &prefs).await.unwrap();
+        assert_eq!(seal_saved_preferences(&pool, &cipher).await.unwrap(), 1);
+        assert_eq!(seal_saved_preferences(&pool, &cipher).await.unwrap(), 0);
+
+        let (display_name, theme): (String, String) =
+            sqlx::query_as("SELECT display_name, theme FROM user_preferences WHERE github_id = 7")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert!(FieldCipher::is_sealed(&display_name));
+        assert_eq!(theme, "dark");
+
+        let read = get_preferences(&pool, Some(&cipher), 7)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.display_name.as_deref(), Some("Ada"));
+        assert_eq!(read.bio.as_deref(), Some("Analyst"));
+        assert!(get_preferences(&pool, None, 7).await.is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! `scribe setup`: asks for the secrets the server reads from the
+//! environment and writes them to a `.env` file.
+
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::rand_core::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Where GitHub exchanges OAuth codes, which also tells whether a client id
+/// and secret belong together.
+pub const GITHUB_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+/// Path of the OAuth callback, which the app's redirect URL must point to.
+pub const CALLBACK_PATH: &str = "/api/auth/github/callback";
+
+/// Random bytes in a generated cookie secret.
+const COOKIE_SECRET_BYTES: usize = 64;
+/// Random bytes in a generated admin token.
+const ADMIN_TOKEN_BYTES: usize = 32;
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// The `ADMIN_TOKEN_HASH` of `token`.
+pub fn hash_admin_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// The value of `key` in the contents of a `.env` file.
+fn env_value<'a>(env: &'a str, key: &str) -> Option<&'a str> {
+    env.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Sets each of `values` in the contents of a `.env` file, replacing the
+/// line of a key already there and appending the others. Other lines and
+/// comments are kept.
+pub fn merge_env(env: &str, values: &[(&str, String)]) -> String {
+    let mut lines: Vec<String> = env.lines().map(str::to_string).collect();
+    for (key, value) in values {
+        let line = format!("{}={}", key, value);
+        let existing = lines.iter_mut().find(|existing| {
+            existing
+                .split_once('=')
+                .is_some_and(|(name, _)| name.trim() == *key)
+        });
+        match existing {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    merged
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Whether GitHub accepts `client_id` and `client_secret`, found by
+/// exchanging a made-up code: GitHub checks the credentials first and
+/// only then complains about the code.
+pub async fn check_github_oauth(
+    http: &reqwest::Client,
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<(), String> {
+    let response = http
+        .post(token_url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("code", "scribe-setup-check"),
+        ])
+        .send()
+        .await
+        .map_err(|e| format!("could not reach GitHub: {}", e))?;
+    let body: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("unexpected answer from GitHub: {}", e))?;
+    match body.error.as_deref() {
+        Some("bad_verification_code") => Ok(()),
+        Some("incorrect_client_credentials") => {
+            Err("GitHub does not know this client id and secret".to_string())
+        }
+        Some(error) => Err(body.error_description.unwrap_or_else(|| error.to_string())),
+        None => Err("GitHub accepted a made-up code".to_string()),
+    }
+}
+
+/// Checks that `redirect_url`, the `github_redirect_url` of `config.toml`,
+/// points at the OAuth callback.
+pub fn check_redirect_url(redirect_url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(redirect_url)
+        .map_err(|e| format!("github_redirect_url is not a URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("github_redirect_url must be an http or https URL".to_string());
+    }
+    if url.path() != CALLBACK_PATH {
+        return Err(format!(
+            "github_redirect_url must end in {}, not {}",
+            CALLBACK_PATH,
+            url.path()
+        ));
+    }
+    Ok(())
+}
+
+/// Asks questions on `output` and reads the answers from `input`.
+struct Prompt<'a, R, W> {
+    input: &'a mut R,
+    output: &'a mut W,
+}
+
+impl<R: BufRead, W: Write> Prompt<'_, R, W> {
+    fn say(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.output, "{}", text)
+    }
+
+    fn ask(&mut self, question: &str) -> io::Result<String> {
+        write!(self.output, "{} ", question)?;
+        self.output.flush()?;
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "setup was cancelled",
+            ));
+        }
+        Ok(answer.trim().to_string())
+    }
+
+    fn ask_default(&mut self, question: &str, default: &str) -> io::Result<String> {
+        let answer = if default.is_empty() {
+            self.ask(&format!("{}:", question))?
+        } else {
+            self.ask(&format!("{} [{}]:", question, default))?
+        };
+        Ok(if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer
+        })
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> io::Result<bool> {
+        let hint = if default { "[Y/n]" } else { "[y/N]" };
+        let answer = self.ask(&format!("{} {}", question, hint))?;
+        Ok(match answer.to_lowercase().as_str() {
+            "y" | "yes" => true,
+            "n" | "no" => false,
+            _ => default,
+        })
+    }
+}
+
+/// Where `scribe setup` writes and what it checks against.
+pub struct SetupOptions<'a> {
+    pub env_path: &'a Path,
+    /// `github_redirect_url` from `config.toml`, if it could be read.
+    pub redirect_url: Option<&'a str>,
+    pub token_url: &'a str,
+    pub http: reqwest::Client,
+}
+
+/// Walks through the secrets: a cookie secret, the admin token, the
+/// author's GitHub login and, for comments, the GitHub OAuth app, whose
+/// credentials are checked with GitHub. Values already in the `.env` file
+/// are offered as defaults, and the file is rewritten with the answers.
+pub async fn run_setup(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    options: SetupOptions<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = match fs::read_to_string(options.env_path) {
+        Ok(env) => env,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut prompt = Prompt { input, output };
+    let mut values: Vec<(&str, String)> = Vec::new();
+
+    let replace_secret = match env_value(&existing, "COOKIE_SECRET") {
+        Some(_) => prompt.confirm(
+            "Replace the cookie secret? Everyone will be signed out.",
+            false,
+        )?,
+        None => true,
+    };
+    if replace_secret {
+        values.push(("COOKIE_SECRET", random_hex(COOKIE_SECRET_BYTES)));
+        prompt.say("Generated a new COOKIE_SECRET.")?;
+    }
+
+    let replace_token = match env_value(&existing, "ADMIN_TOKEN_HASH") {
+        Some(_) => prompt.confirm("Replace the admin token?", false)?,
+        None => true,
+    };
+    if replace_token {
+        let mut token = prompt.ask("Admin token (leave empty to generate one):")?;
+        if token.is_empty() {
+            token = random_hex(ADMIN_TOKEN_BYTES);
+            prompt.say(&format!(
+                "Your admin token is {}\nKeep it somewhere safe; only its hash is stored.",
+                token
+            ))?;
+        }
+        values.push(("ADMIN_TOKEN_HASH", hash_admin_token(&token)));
+    }
+
+    let author = loop {
+        let default = env_value(&existing, "AUTHOR_GITHUB_USERNAME").unwrap_or("");
+        let author = prompt.ask_default("Your GitHub username", default)?;
+        if !author.is_empty() {
+            break author;
+        }
+    };
+    values.push(("AUTHOR_GITHUB_USERNAME", author));
+
+    let has_oauth = env_value(&existing, "GITHUB_CLIENT_ID").is_some();
+    if prompt.confirm("Set up GitHub sign-in for comments?", has_oauth)? {
+        match options.redirect_url.map(check_redirect_url) {
+            Some(Ok(())) => {}
+            Some(Err(e)) => prompt.say(&format!("Warning: {}", e))?,
+            None => prompt.say(&format!(
+                "Warning: config.toml could not be read; github_redirect_url must end in {}",
+                CALLBACK_PATH
+            ))?,
+        }
+        if let Some(redirect_url) = options.redirect_url {
+            prompt.say(&format!(
+                "Register {} as the callback URL of your GitHub OAuth app.",
+                redirect_url
+            ))?;
+        }
+        loop {
+            let client_id = prompt.ask_default(
+                "GitHub client id",
+                env_value(&existing, "GITHUB_CLIENT_ID").unwrap_or(""),
+            )?;
+            let client_secret = prompt.ask_default(
+                "GitHub client secret",
+                env_value(&existing, "GITHUB_CLIENT_SECRET").unwrap_or(""),
+            )?;
+            match check_github_oauth(&options.http, options.token_url, &client_id, &client_secret)
+                .await
+            {
+                Ok(()) => prompt.say("GitHub accepted the client id and secret.")?,
+                Err(e) => {
+                    prompt.say(&format!("Could not verify them: {}", e))?;
+                    if !prompt.confirm("Keep them anyway?", false)? {
+                        continue;
+                    }
+                }
+            }
+            values.push(("GITHUB_CLIENT_ID", client_id));
+            values.push(("GITHUB_CLIENT_SECRET", client_secret));
+            break;
+        }
+    }
+
+    write_env(options.env_path, &merge_env(&existing, &values))?;
+    prompt.say(&format!("Wrote {}", options.env_path.display()))?;
+    Ok(())
+}
+
+/// Writes the `.env` file readable by its owner only, as it holds secrets.
+fn write_env(path: &Path, contents: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_replaces_known_keys_and_keeps_the_rest() {
+        let env = "# secrets\nCOOKIE_SECRET=old\nNOTES_ENCRYPTION_KEY=abc\n";
+        let merged = merge_env(
+            env,
+            &[
+                ("COOKIE_SECRET", "new".to_string()),
+                ("AUTHOR_GITHUB_USERNAME", "ferris".to_string()),
+            ],
+        );
+        assert_eq!(
+            merged,
+            "# secrets\nCOOKIE_SECRET=new\nNOTES_ENCRYPTION_KEY=abc\nAUTHOR_GITHUB_USERNAME=ferris\n"
+        );
+        assert_eq!(env_value(&merged, "NOTES_ENCRYPTION_KEY"), Some("abc"));
+        assert_eq!(env_value(&merged, "COOKIE"), None);
+    }
+
+    #[test]
+    fn redirect_urls_must_point_at_the_callback() {
+        assert!(check_redirect_url("https://blog.example.com/api/auth/github/callback").is_ok());
+        assert!(check_redirect_url("https://blog.example.com/callback").is_err());
+        assert!(check_redirect_url("ftp://blog.example.com/api/auth/github/callback").is_err());
+        assert!(check_redirect_url("not a url").is_err());
+    }
+}
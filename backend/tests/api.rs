@@ -0,0 +1,4497 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use common::{ADMIN_TOKEN, TestApp};
+use futures_util::StreamExt;
+use scribe_backend::config::ARTICLE_DIR;
+use scribe_backend::handlers::articles::publish_scheduled;
+use scribe_backend::handlers::saved_searches::check_saved_searches;
+use scribe_backend::models::link_preview::LinkPreview;
+use scribe_backend::models::user::User;
+use scribe_backend::server::app::reindex_all_content;
+use scribe_backend::server::load_shed::LoadClass;
+use scribe_backend::server::snapshots::refresh_snapshots;
+use scribe_backend::services::job_queue_service::due_jobs;
+use scribe_backend::services::notification_service::sign;
+use serde_json::json;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::time::timeout;
+use tower::ServiceExt;
+
+fn slugs(body: &serde_json::Value) -> Vec<String> {
+    body["articles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["slug"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn lists_published_fixture_content() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(res.status, StatusCode::OK);
+    let mut listed = slugs(&res.body);
+    listed.sort();
+    assert_eq!(listed, ["guides/axum-routing", "hello-world"]);
+
+    let res = app.get("/api/articles/unpublished").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app.get("/api/notes").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["articles"].as_array().unwrap().len(), 1);
+
+    let res = app.get("/api/tags").await;
+    assert_eq!(res.body, json!(["axum", "intro", "rust"]));
+
+    let res = app.get("/api/tags/counts").await;
+    assert_eq!(
+        res.body,
+        json!([
+            { "name": "axum", "count": 1 },
+            { "name": "intro", "count": 1 },
+            { "name": "rust", "count": 2 }
+        ])
+    );
+
+    let res = app.get("/api/categories/counts").await;
+    assert_eq!(res.body, json!([{ "name": "guides", "count": 1 }]));
+}
+
+#[tokio::test]
+async fn notes_have_their_own_tags_and_categories() {
+    let app = TestApp::spawn().await;
+    let entry = json!({ "date": "2024-05-01", "content": "Quiet day" });
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/notes/daily",
+            Some(entry),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/api/notes/tags").await;
+    assert_eq!(res.body, json!(["til"]));
+    let res = app.get("/api/notes/tags/counts").await;
+    assert_eq!(res.body, json!([{ "name": "til", "count": 1 }]));
+    let res = app.get("/api/notes/categories").await;
+    assert_eq!(res.body, json!(["journal"]));
+    let res = app.get("/api/notes/categories/counts").await;
+    assert_eq!(res.body, json!([{ "name": "journal", "count": 1 }]));
+
+    // The article taxonomy is unaffected.
+    let res = app.get("/api/categories").await;
+    assert_eq!(res.body, json!(["guides"]));
+}
+
+#[tokio::test]
+async fn author_can_create_and_update_articles() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Fresh Post", "content": "Brand new", "tags": ["news"] })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "fresh-post");
+    assert!(
+        app.dir
+            .path()
+            .join(ARTICLE_DIR)
+            .join("fresh-post.md")
+            .exists()
+    );
+
+    let res = app.get("/api/articles/fresh-post").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["metadata"]["title"], "Fresh Post");
+
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/fresh-post",
+            Some(json!({ "title": "Fresh Post (edited)", "content": "Edited" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/api/articles/fresh-post").await;
+    assert_eq!(res.body["metadata"]["title"], "Fresh Post (edited)");
+    assert_eq!(res.body["content"], "Edited");
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": " ", "content": "" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["details"]["title"].is_array());
+    assert!(res.body["details"]["content"].is_array());
+}
+
+const TAXONOMY_RULES: &str = "[taxonomy]\ntags = [\"rust\", \"axum\"]\ntag_patterns = [\"t.l\"]\ncategories = [\"guides\"]\n";
+
+#[tokio::test]
+async fn taxonomy_rules_warn_about_unknown_terms_and_lint_existing_content() {
+    let app = TestApp::spawn_with(TAXONOMY_RULES).await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/content/lint",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(
+        res.body,
+        json!([
+            { "slug": "hello-world", "violations": [{ "field": "tags", "value": "intro" }] },
+            { "slug": "unpublished", "violations": [{ "field": "tags", "value": "draft" }] }
+        ]),
+        "{}",
+        res.body
+    );
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Go Routing", "content": "Body", "tags": ["rust", "golang"] })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["warnings"], json!(["tag golang is not allowed"]));
+    assert!(app.dir.path().join("article/go-routing.md").exists());
+}
+
+#[tokio::test]
+async fn content_lint_reports_misspellings_by_line() {
+    let dir = TempDir::new().unwrap();
+    let dictionary = dir.path().join("fr");
+    let config = format!(
+        "[spellcheck]\ndictionaries = {{ fr = \"{}\" }}\nwords = [\"scribe\"]\n",
+        dictionary.display()
+    );
+    let builder = common::builder(&dir, &config).await;
+    std::fs::write(dictionary.with_extension("aff"), "SFX S Y 1\nSFX S 0 s .\n").unwrap();
+    std::fs::write(
+        dictionary.with_extension("dic"),
+        "4\nle/S\nchat/S\nnoir\nest\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("article/chats.md"),
+        "---\ntitle: \"Chats\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\n\
+         description: \"\"\nlang: fr-CA\n---\n\nLes chats noirs\n\n```\nfn main() {}\n```\n\n\
+         Le chat est noir `ok`\n",
+    )
+    .unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/content/lint",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(
+        res.body,
+        json!([{
+            "slug": "chats",
+            "violations": [],
+            "misspellings": [{ "line": 9, "word": "noirs" }],
+            "auto_description": true
+        }]),
+        "{}",
+        res.body
+    );
+}
+
+#[tokio::test]
+async fn strict_taxonomy_rules_refuse_unknown_terms() {
+    let app = TestApp::spawn_with(&format!("{}strict = true\n", TAXONOMY_RULES)).await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(
+                json!({ "title": "Misc", "content": "Body", "tags": ["til"], "category": "misc" }),
+            ),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        res.body["details"]["category"],
+        json!(["category misc is not allowed"])
+    );
+    assert!(!app.dir.path().join("article/misc").exists());
+
+    // Terms already on an article are not checked when they are left alone.
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(json!({ "title": "Hello World", "content": "Edited" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body.get("warnings").is_none());
+}
+
+#[tokio::test]
+async fn nearly_identical_titles_need_force_to_be_created() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let body = json!({ "title": "Hello, world!", "content": "Again" });
+
+    let res = app
+        .request(Method::POST, "/api/articles", Some(body.clone()), &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["error_code"], "ERR_SIMILAR_TITLE");
+    assert_eq!(
+        res.body["details"]["similar"],
+        json!([{ "slug": "hello-world", "title": "Hello World", "similarity": 1.0 }])
+    );
+    assert!(!app.dir.path().join("article/hello-world-1.md").exists());
+
+    let res = app
+        .request(Method::POST, "/api/articles?force=true", Some(body), &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "hello-world-1");
+}
+
+#[tokio::test]
+async fn article_meta_serves_counters_briefly_cached() {
+    let app = TestApp::spawn().await;
+    let meta = "/api/articles/hello-world/meta";
+    let res = app.get(meta).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.headers["cache-control"], "public, max-age=5");
+    assert_eq!(res.body["comment_count"], 0);
+    assert_eq!(res.body["views"], 0);
+    assert_eq!(
+        res.body["reactions"][0],
+        json!({ "emoji": "👍", "count": 0 })
+    );
+    assert!(res.body["updated_at"].is_string());
+
+    let cookie = app.visitor_cookie();
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/hello-world/reactions",
+            Some(json!({ "emoji": "👍" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // Counters are shared for a few seconds, then read again.
+    let res = app.get(meta).await;
+    assert_eq!(res.body["reactions"][0]["count"], 0);
+    app.state.meta_cache.invalidate_all();
+    let res = app.get(meta).await;
+    assert_eq!(res.body["reactions"][0]["count"], 1);
+
+    let res = app.get("/api/articles/unpublished/meta").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+const NOINDEX: &str = "---\ntitle: \"Scratchpad\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\ndescription: \"Not for search engines\"\nhttp_headers:\n  X-Robots-Tag: noindex\n  Content-Type: text/evil\n  Bad Name: x\n---\n\nScratch.\n";
+
+#[tokio::test]
+async fn front_matter_headers_are_sent_with_the_article() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("article/scratchpad.md"), NOINDEX).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    // The second request is answered from the response cache.
+    for _ in 0..2 {
+        let res = app.get("/api/articles/scratchpad").await;
+        assert_eq!(res.headers["x-robots-tag"], "noindex");
+        assert_eq!(res.headers["content-type"], "application/json");
+        assert_eq!(
+            res.body["metadata"]["http_headers"]["X-Robots-Tag"],
+            "noindex"
+        );
+    }
+
+    let res = app.get("/api/articles/scratchpad/text").await;
+    assert_eq!(res.headers["x-robots-tag"], "noindex");
+    assert_eq!(res.headers["content-type"], "text/plain; charset=utf-8");
+
+    let res = app
+        .get("/api/articles/scratchpad/download?format=print")
+        .await;
+    assert_eq!(res.headers["x-robots-tag"], "noindex");
+    assert!(
+        res.body
+            .as_str()
+            .unwrap()
+            .contains("<meta name=\"robots\" content=\"noindex\">")
+    );
+
+    let res = app.get("/api/articles/hello-world").await;
+    assert!(res.headers.get("x-robots-tag").is_none());
+    assert!(res.body["metadata"].get("http_headers").is_none());
+}
+
+#[tokio::test]
+async fn write_routes_require_a_signed_author_session() {
+    let app = TestApp::spawn().await;
+    let body = json!({ "title": "Nope", "content": "Nope" });
+
+    let res = app
+        .request(Method::POST, "/api/articles", Some(body.clone()), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let visitor = app.visitor_cookie();
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(body.clone()),
+            &[("cookie", visitor.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+
+    // An unsigned cookie claiming the author role is rejected.
+    let forged = format!(
+        "user_session={}",
+        json!({
+            "github_id": 1,
+            "github_login": "author",
+            "role": "Author",
+            "display_name": null,
+            "bio": null,
+            "avatar": null
+        })
+    );
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(body),
+            &[("cookie", forged.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/auth/me",
+            None,
+            &[("cookie", visitor.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["github_login"], "visitor");
+}
+
+#[tokio::test]
+async fn admin_routes_require_the_admin_token() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/admin/jobs").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/jobs",
+            None,
+            &[("authorization", "wrong")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/overview",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["search_index"]["enabled"], true);
+}
+
+#[tokio::test]
+async fn index_freshness_is_exported_as_metrics() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(Method::GET, "/api/admin/overview", None, &admin)
+        .await;
+    let index = &res.body["search_index"];
+    assert!(index["articles_indexed_at"].is_string());
+    assert!(index["notes_indexed_at"].is_string());
+    assert_eq!(index["pending_jobs"], 0);
+
+    assert_eq!(app.get("/metrics").await.status, StatusCode::UNAUTHORIZED);
+    let res = app.request(Method::GET, "/metrics", None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(
+        res.headers["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4")
+    );
+    let text = res.body.as_str().unwrap();
+    assert!(text.contains("# TYPE scribe_search_last_commit_timestamp_seconds gauge\n"));
+    assert!(text.contains("scribe_search_last_commit_timestamp_seconds{collection=\"articles\"} "));
+    assert!(text.contains("scribe_search_last_commit_timestamp_seconds{collection=\"notes\"} "));
+    assert!(text.contains("\nscribe_search_pending_index_jobs 0\n"));
+}
+
+#[tokio::test]
+async fn version_reports_the_build_features_and_content() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/version").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!res.body["git_commit"].as_str().unwrap().is_empty());
+    assert!(res.body["built_at"].is_string());
+    assert_eq!(res.body["features"]["search"], true);
+    assert_eq!(res.body["features"]["read_only"], false);
+    // Drafts are not counted.
+    assert_eq!(res.body["content"]["articles"], 2);
+    assert!(res.body["content"]["notes"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn missing_content_directories_are_created_when_bootstrapping() {
+    let dir = TempDir::new().unwrap();
+    let fresh = dir.path().join("fresh");
+    let missing = common::builder(&dir, "")
+        .await
+        .article_dir(fresh.join("article"))
+        .notes_dir(fresh.join("notes"))
+        .build()
+        .await;
+    let error = missing.err().unwrap().to_string();
+    assert!(error.contains("--init"), "{error}");
+    assert!(!fresh.exists());
+
+    let app = common::builder(&dir, "bootstrap_content = true")
+        .await
+        .article_dir(fresh.join("article"))
+        .notes_dir(fresh.join("notes"))
+        .build()
+        .await
+        .unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+    assert!(fresh.join("notes").is_dir());
+    let res = app.get("/api/articles/hello-scribe").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["metadata"]["title"], "Hello, Scribe");
+}
+
+/// Serves a `Date` header `offset` away from now. Returns the base URL.
+async fn spawn_clock(offset: chrono::Duration) -> String {
+    let app = axum::Router::new().route(
+        "/",
+        axum::routing::get(move || async move {
+            let date = (chrono::Utc::now() + offset).format("%a, %d %b %Y %H:%M:%S GMT");
+            ([(axum::http::header::DATE, date.to_string())], "")
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+fn check<'a>(body: &'a serde_json::Value, name: &str) -> &'a serde_json::Value {
+    body["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("no {name} check in {body}"))
+}
+
+#[tokio::test]
+async fn diagnostics_report_each_check_and_fail_on_clock_skew() {
+    let base = spawn_clock(chrono::Duration::zero()).await;
+    let app = TestApp::spawn_with(&format!("github_api_url = \"{base}\"")).await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    assert_eq!(
+        app.get("/api/admin/diagnostics").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let res = app
+        .request(Method::GET, "/api/admin/diagnostics", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["healthy"], true, "{}", res.body);
+    for name in [
+        "disk_space:articles",
+        "disk_space:data",
+        "writable:articles",
+        "writable:notes",
+        "writable:data",
+        "writable:search_index",
+        "database",
+        "clock_skew",
+    ] {
+        assert_eq!(check(&res.body, name)["status"], "pass", "{name}");
+    }
+    assert_eq!(check(&res.body, "watchers")["status"], "warn");
+    assert!(app.dir.path().join("data").is_dir());
+
+    let running = app.state.watchers.watching("articles");
+    app.state.watchers.stopped("notes");
+    let res = app
+        .request(Method::GET, "/api/admin/diagnostics", None, &admin)
+        .await;
+    let watchers = check(&res.body, "watchers");
+    assert_eq!(watchers["status"], "fail");
+    assert_eq!(watchers["detail"], "stopped: notes");
+    assert_eq!(res.body["healthy"], false);
+    drop(running);
+
+    let base = spawn_clock(chrono::Duration::minutes(-5)).await;
+    let app = TestApp::spawn_with(&format!("github_api_url = \"{base}\"")).await;
+    let res = app
+        .request(Method::GET, "/api/admin/diagnostics", None, &admin)
+        .await;
+    let skew = check(&res.body, "clock_skew");
+    assert_eq!(skew["status"], "fail");
+    assert!(skew["detail"].as_str().unwrap().starts_with("30"), "{skew}");
+    assert_eq!(res.body["healthy"], false);
+}
+
+#[tokio::test]
+async fn readiness_reports_watchers_that_failed() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/readyz").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["ready"], true);
+    assert_eq!(res.body["database"], true);
+
+    let _articles = app.state.watchers.watching("articles");
+    app.state
+        .watchers
+        .failed("notes", "failed to watch directory 'notes'");
+    app.state.watchers.restarting("notes");
+    let res = app.get("/readyz").await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.body["ready"], false);
+    assert_eq!(res.body["watchers"]["articles"]["running"], true);
+    let notes = &res.body["watchers"]["notes"];
+    assert_eq!(notes["running"], false);
+    assert_eq!(notes["restarts"], 1);
+    assert_eq!(notes["last_error"], "failed to watch directory 'notes'");
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/diagnostics",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(
+        check(&res.body, "watchers")["detail"],
+        "stopped: notes (failed to watch directory 'notes')"
+    );
+
+    // Back up after the restart.
+    let _notes = app.state.watchers.watching("notes");
+    let res = app.get("/readyz").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["watchers"]["notes"]["restarts"], 1);
+}
+
+#[tokio::test]
+async fn anonymous_reads_are_cached_until_invalidated() {
+    let app = TestApp::spawn().await;
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 2);
+
+    // Add a file and reload the store without touching the cache, as the
+    // file watcher would before it invalidates.
+    std::fs::write(
+        app.dir.path().join(ARTICLE_DIR).join("late-arrival.md"),
+        "---\ntitle: \"Late Arrival\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nBody\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 2);
+    app.state.cache.invalidate_all();
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 3);
+}
+
+#[tokio::test]
+async fn cache_classes_keep_responses_for_their_own_ttl() {
+    let app = TestApp::spawn_with(
+        "[response_cache.classes.listings]\nprefix = \"/api/articles\"\nttl_secs = 1\ncapacity = 10\n\n[response_cache.classes.search]\nprefix = \"/api/search\"\nttl_secs = 600\ncapacity = 10",
+    )
+    .await;
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 2);
+    app.get("/api/tags").await;
+    app.get("/api/tags").await;
+
+    std::fs::write(
+        app.dir.path().join(ARTICLE_DIR).join("late-arrival.md"),
+        "---\ntitle: \"Late Arrival\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nBody\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 2);
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 3);
+
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app.request(Method::GET, "/metrics", None, &admin).await;
+    let text = res.body.as_str().unwrap();
+    assert!(text.contains("# TYPE scribe_response_cache_hits_total counter\n"));
+    for line in [
+        "scribe_response_cache_hits_total{class=\"listings\"} 1\n",
+        "scribe_response_cache_misses_total{class=\"listings\"} 2\n",
+        "scribe_response_cache_hits_total{class=\"search\"} 0\n",
+        "scribe_response_cache_hits_total{class=\"default\"} 1\n",
+        "scribe_response_cache_misses_total{class=\"default\"} 1\n",
+    ] {
+        assert!(text.contains(line), "{} missing from\n{}", line, text);
+    }
+}
+
+#[tokio::test]
+async fn change_feed_lists_slugs_changed_since_a_marker() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/changes").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body["changed"],
+        json!({ "articles": ["axum-routing", "hello-world"], "notes": ["til"] })
+    );
+    let marker = res.body["marker"].as_str().unwrap().to_string();
+    let etag = format!("\"{}\"", marker);
+    assert_eq!(res.headers["etag"], etag.as_str());
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/changes",
+            None,
+            &[("if-none-match", etag.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+    let since = format!("/api/changes?since={}", marker);
+    let res = app.get(&since).await;
+    assert_eq!(res.body["marker"], marker.as_str());
+    assert_eq!(res.body["changed"], json!({ "articles": [], "notes": [] }));
+
+    let before_edits = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let articles = app.dir.path().join(ARTICLE_DIR);
+    let hello = articles.join("hello-world.md");
+    let edited = std::fs::read_to_string(&hello)
+        .unwrap()
+        .replace("Hello World", "Hello Again");
+    std::fs::write(&hello, edited).unwrap();
+    std::fs::remove_file(articles.join("guides/axum-routing.md")).unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+
+    let expected = json!({
+        "changed": { "articles": ["hello-world"], "notes": [] },
+        "removed": { "articles": ["axum-routing"], "notes": [] },
+    });
+    let res = app.get(&since).await;
+    assert_ne!(res.body["marker"], marker.as_str());
+    assert_eq!(res.body["changed"], expected["changed"]);
+    assert_eq!(res.body["removed"], expected["removed"]);
+    let res = app
+        .get(&format!("/api/changes?since={}", before_edits))
+        .await;
+    assert_eq!(res.body["changed"], expected["changed"]);
+    assert_eq!(res.body["removed"], expected["removed"]);
+
+    let res = app.get("/api/changes?since=yesterday").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn collections_list_visible_articles_in_curated_order() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/collections").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body[0]["name"], "start-here");
+    assert_eq!(res.body[0]["count"], 2);
+    assert_eq!(res.body[1]["name"], "empty");
+    assert_eq!(res.body[1]["count"], 0);
+
+    let res = app.get("/api/collections/start-here").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["title"], "Start here");
+    assert_eq!(res.body["description"], "The posts to read first.");
+    assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+
+    let res = app.get("/api/collections/missing").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_COLLECTION_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn glossary_lists_definitions_by_term() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/glossary").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!([
+            {
+                "slug": "crate",
+                "term": "crate",
+                "aliases": [],
+                "definition": "A compilation unit of Rust."
+            },
+            {
+                "slug": "ferris",
+                "term": "Ferris",
+                "aliases": ["Rustacean"],
+                "definition": "The crab **mascot** of Rust."
+            }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn presence_streams_editing_sessions_and_publications() {
+    let app = TestApp::spawn().await;
+    let req = Request::get("/api/presence").body(Body::empty()).unwrap();
+    let res = app.router.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["content-type"], "text/event-stream");
+    let mut frames = res.into_body().into_data_stream();
+    let mut next_frame = async || {
+        let frame = timeout(Duration::from_secs(5), frames.next())
+            .await
+            .expect("no presence event")
+            .unwrap()
+            .unwrap();
+        String::from_utf8(frame.to_vec()).unwrap()
+    };
+
+    let frame = next_frame().await;
+    assert!(frame.starts_with("event: editing\n"), "{}", frame);
+    assert!(frame.contains(r#""sessions":0"#), "{}", frame);
+
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Live Post", "content": "Just out" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let frame = next_frame().await;
+    assert!(frame.starts_with("event: published\n"), "{}", frame);
+    assert!(frame.contains(r#""slug":"live-post""#), "{}", frame);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/live-post/lock",
+            Some(json!({ "editor": "tab-1" })),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let frame = next_frame().await;
+    assert!(frame.starts_with("event: editing\n"), "{}", frame);
+    assert!(frame.contains(r#""sessions":1"#), "{}", frame);
+    assert!(!frame.contains("tab-1"), "{}", frame);
+}
+
+#[tokio::test]
+async fn article_pages_stream_as_ndjson_without_caching() {
+    let app = TestApp::spawn().await;
+    let uri = "/api/articles?include_content=true&stream=true&limit=50";
+    // The test client decodes a single-line body as plain JSON.
+    let lines = |body: &serde_json::Value| -> Vec<serde_json::Value> {
+        match body.as_str() {
+            Some(text) => text
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect(),
+            None => vec![body.clone()],
+        }
+    };
+
+    let res = app.get(uri).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/x-ndjson");
+    assert_eq!(res.headers["x-total-pages"], "1");
+    let articles = lines(&res.body);
+    assert_eq!(articles.len(), 2);
+    assert_eq!(articles[0]["slug"], "guides/axum-routing");
+    assert!(articles[1]["content"].as_str().unwrap().contains("ferris"));
+
+    // Streamed pages bypass the response cache.
+    std::fs::write(
+        app.dir.path().join(ARTICLE_DIR).join("late-arrival.md"),
+        "---\ntitle: \"Late Arrival\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nBody\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    assert_eq!(lines(&app.get(uri).await.body).len(), 3);
+
+    let res = app.get("/api/articles?stream=true&limit=1").await;
+    assert_eq!(res.headers["x-total-pages"], "3");
+    let teasers = lines(&res.body);
+    assert_eq!(teasers.len(), 1);
+    assert!(teasers[0].get("content").is_none());
+}
+
+#[tokio::test]
+async fn searches_article_content() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/search?q=ferris").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let results = res.body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["slug"], "hello-world");
+
+    let res = app.get("/api/search?q=").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn search_page_includes_matching_tags_and_categories() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/search/page?q=rust%20guides").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(res.body["results"].is_array());
+    assert_eq!(res.body["tags"], json!([{ "name": "rust", "count": 2 }]));
+    assert_eq!(
+        res.body["categories"],
+        json!([{ "name": "guides", "count": 1 }])
+    );
+
+    let res = app.get("/api/search/page?q=ferris").await;
+    assert_eq!(res.body["results"][0]["slug"], "hello-world");
+    assert_eq!(res.body["tags"], json!([]));
+
+    let res = app.get("/api/search/page?q=").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn search_hits_are_grouped_by_collection() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/search?q=fixture%20note&limit=1").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["results"].as_array().unwrap().len(), 1);
+    let groups = res.body["groups"].as_array().unwrap();
+    assert_eq!(groups[0]["collection"], "articles");
+    assert_eq!(groups[0]["count"], 1);
+    assert_eq!(groups[0]["hits"][0]["slug"], "hello-world");
+    assert_eq!(groups[1]["collection"], "notes");
+    assert_eq!(groups[1]["count"], 1);
+    assert_eq!(groups[1]["hits"][0]["slug"], "notes/til");
+
+    let res = app.get("/api/search?q=ferris").await;
+    assert_eq!(res.body["groups"][1]["count"], 0);
+    assert_eq!(res.body["groups"][1]["hits"], json!([]));
+}
+
+#[tokio::test]
+async fn admin_search_finds_drafts_and_archived_articles() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let cookie = app.author_cookie();
+    let archive = json!({ "title": "Hello World", "content": "Old news", "status": "archived" });
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(archive),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    app.state.index_tx.as_ref().unwrap().flush().await;
+
+    let res = app.get("/api/search?q=progress").await;
+    assert_eq!(res.body["results"], json!([]));
+    let res = app.get("/api/admin/search?q=progress").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = app
+        .request(Method::GET, "/api/admin/search?q=progress", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["total_found"], 1);
+    assert_eq!(res.body["results"][0]["slug"], "unpublished");
+    assert_eq!(res.body["results"][0]["status"], "draft");
+
+    let res = app
+        .request(Method::GET, "/api/admin/search?q=news", None, &admin)
+        .await;
+    assert_eq!(res.body["results"][0]["slug"], "hello-world");
+    assert_eq!(res.body["results"][0]["status"], "archived");
+
+    let res = app
+        .request(Method::GET, "/api/admin/search?q=%20", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn suggests_titles_in_the_opensearch_format() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/search/opensearch-suggestions?q=hel").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.headers["content-type"],
+        "application/x-suggestions+json"
+    );
+    assert_eq!(
+        res.body,
+        json!([
+            "hel",
+            ["Hello World"],
+            ["The first fixture article"],
+            ["http://localhost:3000/articles/hello-world"]
+        ])
+    );
+
+    let res = app
+        .get("/api/search/opensearch-suggestions?q=today%20i%20le")
+        .await;
+    assert_eq!(res.body[3], json!(["http://localhost:3000/notes/til"]));
+
+    let res = app.get("/api/search/opensearch-suggestions?q=").await;
+    assert_eq!(res.body, json!(["", [], [], []]));
+}
+
+#[tokio::test]
+async fn v1_responses_use_the_envelope() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/v1/articles?limit=1").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.headers.get("deprecation").is_none());
+    assert_eq!(res.body["meta"]["pagination"]["total_pages"], 2);
+    assert_eq!(res.body["data"]["articles"].as_array().unwrap().len(), 1);
+
+    let res = app.get("/api/v1/articles/missing").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error"]["code"], "ERR_ARTICLE_NOT_FOUND");
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(res.headers["deprecation"], "true");
+}
+
+#[tokio::test]
+async fn calendar_counts_published_articles_per_day() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/stats/calendar?year=2024").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(
+        res.body,
+        json!({
+            "year": 2024,
+            "days": [
+                { "date": "2024-01-01", "count": 1 },
+                { "date": "2024-02-01", "count": 1 }
+            ]
+        })
+    );
+
+    let res = app.get("/api/stats/calendar?year=2024&words=true").await;
+    assert!(res.body["days"][0]["words"].as_u64().unwrap() > 0);
+
+    let res = app.get("/api/stats/calendar?year=2023").await;
+    assert_eq!(res.body["days"], json!([]));
+
+    let res = app.get("/api/stats/calendar?year=0").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn translations_are_linked_in_front_matter() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/translations",
+            Some(json!({ "slug": "axum-routing", "lang": "de", "source_lang": "en" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!([{ "lang": "de", "slug": "axum-routing", "title": "Axum Routing" }])
+    );
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles/axum-routing/translations",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body[0]["slug"], "hello-world");
+    assert_eq!(res.body[0]["lang"], "en");
+
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.body["metadata"]["lang"], "en");
+    assert_eq!(res.body["metadata"]["translations"]["de"], "axum-routing");
+    let file =
+        std::fs::read_to_string(app.dir.path().join(ARTICLE_DIR).join("hello-world.md")).unwrap();
+    assert!(file.contains("de: axum-routing"));
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/translations",
+            Some(json!({ "slug": "missing", "lang": "fr" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/translations",
+            Some(json!({ "slug": "hello-world", "lang": "not a tag" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn tag_and_category_landing_pages_merge_taxonomy_pages() {
+    let app = TestApp::spawn_with("[pagination]\ndefault_limit = 1").await;
+
+    let res = app.get("/api/tags/rust").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let body = &res.body;
+    assert_eq!(body["name"], "rust");
+    assert_eq!(body["count"], 2);
+    assert_eq!(body["title"], "Rust");
+    assert_eq!(body["description"], "Posts about the Rust language");
+    assert_eq!(body["cover"], "images/ferris.png");
+    assert_eq!(body["content"], "Everything written here about **Rust**.");
+    // The pinned draft is left out.
+    assert_eq!(
+        slugs(&json!({ "articles": body["pinned"] })),
+        ["guides/axum-routing"]
+    );
+    assert_eq!(slugs(body), ["guides/axum-routing"]);
+    assert_eq!(body["total_pages"], 2);
+
+    let res = app.get("/api/tags/intro").await;
+    assert_eq!(res.body["count"], 1);
+    assert!(res.body.get("title").is_none());
+    assert_eq!(res.body["pinned"], json!([]));
+    assert_eq!(slugs(&res.body), ["hello-world"]);
+
+    let res = app.get("/api/categories/guides").await;
+    assert_eq!(res.body["title"], "Guides");
+    assert_eq!(res.body["description"], "Step-by-step walkthroughs");
+    assert_eq!(slugs(&res.body), ["guides/axum-routing"]);
+
+    // Only the draft uses this tag.
+    let res = app.get("/api/tags/draft").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_TAG_NOT_FOUND");
+    let res = app.get("/api/categories/guides/missing").await;
+    assert_eq!(res.body["error_code"], "ERR_CATEGORY_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn restricted_categories_are_hidden_from_anonymous_visitors() {
+    let app = TestApp::spawn_with("restricted_categories = [\"guides/\"]").await;
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(slugs(&res.body), ["hello-world"]);
+    let res = app.get("/api/articles/axum-routing").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/categories").await.body, json!([]));
+    assert_eq!(app.get("/api/tags").await.body, json!(["intro", "rust"]));
+    let res = app.get("/api/tags/counts").await;
+    assert_eq!(
+        res.body,
+        json!([{ "name": "intro", "count": 1 }, { "name": "rust", "count": 1 }])
+    );
+    let res = app.get("/api/search?q=extractors").await;
+    assert_eq!(res.body["results"], json!([]));
+    let sitemap = app.get("/sitemap.xml").await.body;
+    assert!(!sitemap.as_str().unwrap().contains("axum-routing"));
+
+    let cookie = app.visitor_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let res = app.request(Method::GET, "/api/articles", None, &auth).await;
+    assert_eq!(slugs(&res.body).len(), 2);
+    let res = app
+        .request(Method::GET, "/api/articles/axum-routing", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app
+        .request(Method::GET, "/api/search?q=extractors", None, &auth)
+        .await;
+    assert_eq!(res.body["results"][0]["slug"], "guides/axum-routing");
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(Method::GET, "/api/categories/counts", None, &admin)
+        .await;
+    assert_eq!(res.body, json!([{ "name": "guides", "count": 1 }]));
+}
+
+#[tokio::test]
+async fn registered_redirects_are_served_before_routing() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/redirects",
+            Some(json!({ "from": "/blog/hello/", "to": "/articles/hello-world" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["from_path"], "/blog/hello");
+    assert_eq!(res.body["status"], 301);
+    let id = res.body["id"].as_i64().unwrap();
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/redirects",
+            Some(json!({ "from": "/blog/hello", "to": "/elsewhere", "status": 308 })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["error_code"], "ERR_REDIRECT_EXISTS");
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/redirects",
+            Some(json!({ "from": "/api/admin/jobs", "to": "ftp://x", "status": 302 })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    for field in ["from", "to", "status"] {
+        assert!(res.body["details"][field].is_array(), "{}", res.body);
+    }
+
+    let res = app.get("/blog/hello?utm=feed").await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers["location"], "/articles/hello-world?utm=feed");
+
+    let res = app
+        .request(Method::GET, "/api/admin/redirects", None, &admin)
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+
+    let uri = format!("/api/admin/redirects/{}", id);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.body["error_code"], "ERR_REDIRECT_NOT_FOUND");
+    let res = app.get("/blog/hello").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn shortlinks_redirect_and_count_clicks() {
+    let app = TestApp::spawn_with("hostname = \"https://blog.example/\"").await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let uri = "/api/admin/articles/hello-world/shortlink";
+
+    let res = app.request(Method::POST, uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let code = res.body["code"].as_str().unwrap().to_string();
+    assert_eq!(res.body["url"], format!("https://blog.example/s/{}", code));
+
+    for _ in 0..2 {
+        let res = app.get(&format!("/s/{}", code)).await;
+        assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers["location"],
+            "https://blog.example/articles/hello-world"
+        );
+    }
+    let res = app.request(Method::POST, uri, None, &admin).await;
+    assert_eq!(res.body["code"], code);
+    assert_eq!(res.body["clicks"], 2);
+
+    let res = app.get("/s/unknown").await;
+    assert_eq!(res.body["error_code"], "ERR_SHORTLINK_NOT_FOUND");
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/missing/shortlink",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn article_views_record_scrubbed_referrers() {
+    let app = TestApp::spawn_with("hostname = \"https://blog.example\"").await;
+    let views = [
+        json!({ "referrer": "https://news.example/item?id=7", "utm_source": "HN", "utm_medium": "bob@example.com" }),
+        json!({ "referrer": "https://www.news.example/", "utm_source": "hn" }),
+        json!({ "referrer": "https://blog.example/articles/axum-routing" }),
+    ];
+    for body in views {
+        let res = app
+            .request(
+                Method::POST,
+                "/api/articles/hello-world/views",
+                Some(body),
+                &[],
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::NO_CONTENT);
+    }
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/unpublished/views",
+            Some(json!({})),
+            &[],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app.get("/api/admin/stats/referrers?slug=hello-world").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/stats/referrers?slug=hello-world",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.body["total_views"], 3);
+    assert_eq!(
+        res.body["sources"],
+        json!([
+            { "referrer": "news.example", "utm_source": "hn", "utm_medium": null, "utm_campaign": null, "views": 2 },
+            { "referrer": null, "utm_source": null, "utm_medium": null, "utm_campaign": null, "views": 1 }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn public_stats_hide_small_counts() {
+    let app = TestApp::spawn().await;
+    for (slug, views) in [("hello-world", 12), ("axum-routing", 3)] {
+        for _ in 0..views {
+            let uri = format!("/api/articles/{}/views", slug);
+            let res = app.request(Method::POST, &uri, Some(json!({})), &[]).await;
+            assert_eq!(res.status, StatusCode::NO_CONTENT);
+        }
+    }
+
+    let res = app.get("/api/stats/public").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["total_views"], 10);
+    assert_eq!(
+        res.body["month"],
+        chrono::Utc::now().format("%Y-%m").to_string()
+    );
+    assert_eq!(
+        res.body["most_read"],
+        json!([{ "slug": "hello-world", "title": "Hello World", "views": 10 }])
+    );
+}
+
+#[tokio::test]
+async fn comments_round_trip_through_export_and_import() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/comments/import",
+            Some(json!([
+                { "article_slug": "hello-world", "author_github_id": 7, "content": "<p>Hi</p>" },
+                { "article_slug": "missing", "author_github_id": 0, "content": "x", "created_at": "soon" }
+            ])),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.body["details"]["comments"].as_array().unwrap().len(), 3);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/comments/import",
+            Some(json!([
+                { "article_slug": "hello-world", "author_github_id": 7, "content": "<p>Hi<script>x</script></p>", "created_at": "2024-02-01T08:00:00Z" },
+                { "article_slug": "axum-routing", "author_github_id": 8, "content": "<p>Thanks</p>" }
+            ])),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body, json!({ "imported": 2, "skipped": 0 }));
+
+    let res = app
+        .request(Method::GET, "/api/admin/comments/export", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["content"], "<p>Hi</p>");
+    assert_eq!(res.body[0]["created_at"], "2024-02-01 08:00:00");
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/comments/export?format=csv",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.headers["content-type"], "text/csv; charset=utf-8");
+    let csv = res.body.as_str().unwrap().to_string();
+    assert!(csv.starts_with("id,article_slug,author_github_id,content,created_at,author_name\n"));
+
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/comments/import",
+            csv,
+            &[("authorization", ADMIN_TOKEN), ("content-type", "text/csv")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body, json!({ "imported": 0, "skipped": 2 }));
+}
+
+#[tokio::test]
+async fn analytics_move_between_instances_through_export_and_import() {
+    let old = TestApp::spawn().await;
+    let new = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let csv_import = [("authorization", ADMIN_TOKEN), ("content-type", "text/csv")];
+
+    let res = old
+        .request(
+            Method::POST,
+            "/api/admin/analytics/views/import",
+            Some(json!([
+                { "slug": "hello-world", "viewed_on": "yesterday" },
+                { "slug": " ", "viewed_on": "2024-07-01" }
+            ])),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.body["details"]["views"].as_array().unwrap().len(), 2);
+
+    let res = old
+        .request(
+            Method::POST,
+            "/api/admin/analytics/views/import",
+            Some(json!([
+                { "slug": "hello-world", "viewed_on": "2024-07-01", "utm_source": "News Letter" },
+                { "slug": "hello-world", "viewed_on": "2024-07-02", "referrer_host": "News.example" },
+                { "slug": "axum-routing", "viewed_on": "2024-07-03" }
+            ])),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body, json!({ "imported": 3, "skipped": 0 }));
+    old.request(
+        Method::POST,
+        "/api/admin/analytics/reactions/import",
+        Some(json!([
+            { "article_slug": "hello-world", "user_github_id": 7, "emoji": "👍", "created_at": "2024-07-02T08:00:00Z" }
+        ])),
+        &admin,
+    )
+    .await;
+    old.get("/api/search?q=axum").await;
+
+    let res = old
+        .request(
+            Method::GET,
+            "/api/admin/analytics/views/export?from=2024-07-02",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 2);
+    assert_eq!(res.body[0]["referrer_host"], "news.example");
+    let res = old
+        .request(
+            Method::GET,
+            "/api/admin/analytics/views/export?from=2024-07-03&to=2024-07-01",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    for (dataset, header) in [
+        (
+            "views",
+            "slug,viewed_on,referrer_host,utm_source,utm_medium,utm_campaign\n",
+        ),
+        (
+            "reactions",
+            "article_slug,user_github_id,emoji,created_at\n",
+        ),
+        ("searches", "query,count,timestamp\n"),
+    ] {
+        let uri = format!("/api/admin/analytics/{}/export?format=csv", dataset);
+        let res = old.request(Method::GET, &uri, None, &admin).await;
+        assert_eq!(res.headers["content-type"], "text/csv; charset=utf-8");
+        let csv = res.body.as_str().unwrap().to_string();
+        assert!(csv.starts_with(header), "{csv}");
+
+        let uri = format!("/api/admin/analytics/{}/import", dataset);
+        let res = new.request_raw(Method::POST, &uri, csv, &csv_import).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+        let uri = format!("/api/admin/analytics/{}/export", dataset);
+        let exported = old.request(Method::GET, &uri, None, &admin).await;
+        let imported = new.request(Method::GET, &uri, None, &admin).await;
+        assert_eq!(imported.body, exported.body, "{dataset}");
+    }
+
+    // Reactions already given are not counted twice.
+    let res = new
+        .request(
+            Method::POST,
+            "/api/admin/analytics/reactions/import",
+            Some(json!([
+                { "article_slug": "hello-world", "user_github_id": 7, "emoji": "👍" }
+            ])),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body, json!({ "imported": 0, "skipped": 1 }));
+    let res = new.get("/api/search/popular").await;
+    assert_eq!(res.body["searches"][0]["query"], "axum", "{}", res.body);
+}
+
+#[tokio::test]
+async fn disqus_exports_are_matched_to_articles() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/redirects",
+            Some(json!({ "from": "/2013/old-routing-post", "to": "/articles/axum-routing" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let export = r#"<?xml version="1.0" encoding="utf-8"?>
+<disqus xmlns="http://disqus.com" xmlns:dsq="http://disqus.com/disqus-internals">
+  <thread dsq:id="1"><link>https://old.example/2013/hello-world.html</link></thread>
+  <thread dsq:id="2"><link>https://old.example/2013/old-routing-post</link></thread>
+  <thread dsq:id="3"><link>https://old.example/about</link></thread>
+  <post dsq:id="10">
+    <message><![CDATA[<p>Hi<script>alert(1)</script></p>]]></message>
+    <createdAt>2013-10-29T19:16:29Z</createdAt>
+    <author><name>Bob</name></author>
+    <thread dsq:id="1"/>
+  </post>
+  <post dsq:id="11">
+    <message><![CDATA[<p>Routing!</p>]]></message>
+    <createdAt>2013-11-01T08:00:00Z</createdAt>
+    <author><username>carol</username></author>
+    <thread dsq:id="2"/>
+  </post>
+  <post dsq:id="12">
+    <message>About page</message>
+    <thread dsq:id="3"/>
+  </post>
+  <post dsq:id="13">
+    <message>gone</message>
+    <isDeleted>true</isDeleted>
+    <thread dsq:id="1"/>
+  </post>
+</disqus>"#;
+    let uri = "/api/admin/comments/import/disqus";
+    let headers = [
+        ("authorization", ADMIN_TOKEN),
+        ("content-type", "application/xml"),
+    ];
+    let res = app.request_raw(Method::POST, uri, export, &headers).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!({
+            "imported": 2,
+            "skipped": 0,
+            "ignored": 1,
+            "unmatched_threads": ["https://old.example/about"]
+        })
+    );
+    let res = app.request_raw(Method::POST, uri, export, &headers).await;
+    assert_eq!(res.body["skipped"], 2);
+
+    let res = app
+        .request(Method::GET, "/api/admin/comments/export", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["article_slug"], "hello-world");
+    assert_eq!(res.body[0]["content"], "<p>Hi</p>");
+    assert_eq!(res.body[0]["created_at"], "2013-10-29 19:16:29");
+    assert_eq!(res.body[0]["author_github_id"], 0);
+    assert_eq!(res.body[0]["author_name"], "Bob");
+    assert_eq!(res.body[1]["article_slug"], "axum-routing");
+    assert_eq!(res.body[1]["author_name"], "carol");
+
+    let res = app
+        .request_raw(Method::POST, uri, "<disqus>", &headers)
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn store_stats_count_entries_tombstones_and_cache() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let uri = "/api/admin/stores/stats";
+    let res = app.request(Method::GET, uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let articles = &res.body["articles"];
+    assert_eq!(articles["articles"], 3);
+    assert_eq!(articles["tombstones"], 0);
+    assert_eq!(articles["slug_map_entries"], 3);
+    // Indexing for search loaded every body.
+    assert_eq!(articles["content_cache_entries"], 3);
+    assert!(articles["content_cache_bytes"].as_u64().unwrap() > 0);
+    assert!(articles["last_rebuild_ms"].is_u64());
+    assert_eq!(res.body["notes"]["articles"], 1);
+
+    std::fs::remove_file(app.dir.path().join(ARTICLE_DIR).join("hello-world.md")).unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    let res = app.request(Method::GET, uri, None, &admin).await;
+    let articles = &res.body["articles"];
+    assert_eq!(articles["articles"], 2);
+    assert_eq!(articles["tombstones"], 1);
+    assert_eq!(articles["slug_map_entries"], 2);
+    assert_eq!(articles["content_cache_entries"], 2);
+}
+
+#[tokio::test]
+async fn stores_rebuild_from_disk_one_collection_at_a_time() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let rebuild = |collection| format!("/api/admin/stores/{}/rebuild", collection);
+    let store = &app.state.store;
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 2);
+
+    std::fs::remove_file(app.dir.path().join(ARTICLE_DIR).join("hello-world.md")).unwrap();
+    let res = app
+        .request(Method::POST, &rebuild("notes"), None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!({ "collection": "notes", "count": 1 }));
+    assert!(store.read().await.get_by_slug("hello-world").is_some());
+
+    let res = app
+        .request(Method::POST, &rebuild("articles"), None, &admin)
+        .await;
+    assert_eq!(res.body["collection"], "articles");
+    assert!(store.read().await.get_by_slug("hello-world").is_none());
+    assert_eq!(slugs(&app.get("/api/articles").await.body).len(), 1);
+
+    let res = app
+        .request(Method::POST, &rebuild("drafts"), None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn search_index_drift_is_reported_and_repaired() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let uri = "/api/admin/search/consistency";
+    let res = app.request(Method::GET, uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!({ "missing": [], "stale": [], "orphans": [] })
+    );
+
+    // Change the content behind the index's back, as a failed batch would.
+    let articles = app.dir.path().join(ARTICLE_DIR);
+    let hello = articles.join("hello-world.md");
+    let renamed = std::fs::read_to_string(&hello)
+        .unwrap()
+        .replace("Hello World", "Hello Again");
+    std::fs::write(&hello, renamed).unwrap();
+    std::fs::remove_file(articles.join("guides/axum-routing.md")).unwrap();
+    std::fs::write(
+        articles.join("late-arrival.md"),
+        "---\ntitle: \"Late Arrival\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nBody\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+
+    let drift = json!({
+        "missing": ["late-arrival"],
+        "stale": ["hello-world"],
+        "orphans": ["guides/axum-routing"]
+    });
+    let res = app.request(Method::GET, uri, None, &admin).await;
+    assert_eq!(res.body, drift);
+    let repair = "/api/admin/search/consistency/repair";
+    let res = app.request(Method::POST, repair, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body, drift);
+
+    let res = app.request(Method::GET, uri, None, &admin).await;
+    assert_eq!(
+        res.body,
+        json!({ "missing": [], "stale": [], "orphans": [] })
+    );
+    let res = app.get("/api/search?q=arrival").await;
+    assert_eq!(res.body["results"][0]["slug"], "late-arrival");
+}
+
+#[tokio::test]
+async fn search_index_snapshots_restore_on_another_instance() {
+    let source = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = source
+        .request(Method::POST, "/api/admin/search/snapshot", None, &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = source
+        .request(Method::POST, "/api/admin/search/snapshot", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/zip");
+
+    // Leave a mark in the source index that a rebuild would not produce.
+    let search = source.state.search_service.as_ref().unwrap();
+    search.remove_article("hello-world", 15_000_000).unwrap();
+    let archive = search.snapshot().unwrap();
+
+    let target = TestApp::spawn().await;
+    let restore = "/api/admin/search/restore";
+    let zip = [
+        ("authorization", ADMIN_TOKEN),
+        ("content-type", "application/zip"),
+    ];
+    let res = target
+        .request_raw(Method::POST, restore, "not a zip", &zip)
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_INVALID_SEARCH_SNAPSHOT");
+    let res = target.get("/api/search?q=ferris").await;
+    assert_eq!(res.body["results"][0]["slug"], "hello-world");
+
+    let documents = search.indexed_documents().unwrap().len();
+    let res = target
+        .request_raw(Method::POST, restore, archive, &zip)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body, json!({ "documents": documents }));
+    let res = target.get("/api/search?q=ferris").await;
+    assert_eq!(res.body["results"], json!([]));
+}
+
+#[tokio::test]
+async fn oversized_bodies_get_a_structured_413_per_route_group() {
+    let app = TestApp::spawn_with("[body_limits]\ndefault = 256\ncomments = 64").await;
+    let reactions = "/api/articles/hello-world/reactions";
+    let res = app
+        .request(Method::POST, reactions, Some(json!({ "emoji": "👍" })), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", res.body);
+
+    let oversized = json!({ "emoji": "👍".repeat(20) });
+    let res = app
+        .request(Method::POST, reactions, Some(oversized), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(res.body["error_code"], "ERR_PAYLOAD_TOO_LARGE");
+    assert_eq!(
+        res.body["message"],
+        "Request body exceeds the limit of 64 bytes"
+    );
+
+    // An announced length over the limit is rejected without reading.
+    let res = app
+        .request_raw(
+            Method::POST,
+            reactions,
+            "{}",
+            &[
+                ("content-type", "application/json"),
+                ("content-length", "65"),
+            ],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Routes outside the groups get the default limit, in the envelope too.
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let redirect = json!({ "from": "/old", "to": format!("/{}", "x".repeat(300)) });
+    let res = app
+        .request(
+            Method::POST,
+            "/api/v1/admin/redirects",
+            Some(redirect),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(res.body["error"]["code"], "ERR_PAYLOAD_TOO_LARGE");
+    assert_eq!(
+        res.body["error"]["message"],
+        "Request body exceeds the limit of 256 bytes"
+    );
+}
+
+#[tokio::test]
+async fn saturated_endpoints_shed_load_with_retry_after() {
+    let app = TestApp::spawn_with(
+        "[concurrency]\nsearch = 1\ncontent_listings = 1\nretry_after_secs = 5",
+    )
+    .await;
+    let limits = &app.state.load_limits;
+    let search = limits.try_acquire(LoadClass::Search).unwrap();
+    let res = app.get("/api/search?q=ferris").await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.headers["retry-after"], "5");
+    assert_eq!(res.body["error_code"], "ERR_SERVER_BUSY");
+    drop(search);
+    let res = app.get("/api/search?q=ferris").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // Only listings that include content count against their limit.
+    let _listing = limits.try_acquire(LoadClass::ContentListings).unwrap();
+    assert_eq!(app.get("/api/articles").await.status, StatusCode::OK);
+    let res = app.get("/api/notes?include_content=true").await;
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn articles_resolve_by_category_scoped_slug() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/articles/guides%2Faxum-routing").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "guides/axum-routing");
+    assert_eq!(
+        app.get("/api/articles/axum-routing").await.status,
+        StatusCode::OK
+    );
+    let res = app.get("/api/articles/other%2Faxum-routing").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn equal_slugs_in_two_categories_are_listed_and_searched_apart() {
+    let app = TestApp::spawn().await;
+    let recipes = app.dir.path().join(ARTICLE_DIR).join("recipes");
+    std::fs::create_dir(&recipes).unwrap();
+    std::fs::write(
+        recipes.join("axum-routing.md"),
+        "---\ntitle: \"Routing Recipes\"\nauthor: \"Scribe\"\ndate: 2024-03-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nRouters for every occasion.\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    reindex_all_content(&app.state).await;
+    app.state.cache.invalidate_all();
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(
+        slugs(&res.body),
+        ["recipes/axum-routing", "guides/axum-routing", "hello-world"]
+    );
+    async fn search(app: &TestApp) -> Vec<String> {
+        let res = app.get("/api/search?q=routers").await;
+        let mut hits: Vec<_> = res.body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["slug"].as_str().unwrap().to_string())
+            .collect();
+        hits.sort();
+        hits
+    }
+    assert_eq!(
+        search(&app).await,
+        ["guides/axum-routing", "recipes/axum-routing"]
+    );
+
+    // Deleting one leaves the other listed and searchable.
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::DELETE,
+            "/api/articles/recipes%2Faxum-routing",
+            None,
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    app.state.index_tx.as_ref().unwrap().flush().await;
+    assert_eq!(search(&app).await, ["guides/axum-routing"]);
+    let res = app.get("/api/articles").await;
+    assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+}
+
+#[tokio::test]
+async fn equal_slugs_in_two_categories_keep_their_own_versions_and_counters() {
+    let app = TestApp::spawn().await;
+    let recipes = app.dir.path().join(ARTICLE_DIR).join("recipes");
+    std::fs::create_dir(&recipes).unwrap();
+    std::fs::write(
+        recipes.join("axum-routing.md"),
+        "---\ntitle: \"Routing Recipes\"\nauthor: \"Scribe\"\ndate: 2024-03-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nRouters for every occasion.\n",
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    app.state.cache.invalidate_all();
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app.get("/api/articles/recipes%2Faxum-routing").await;
+    assert_eq!(res.body["slug"], "recipes/axum-routing");
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/recipes%2Faxum-routing",
+            Some(json!({ "title": "Routing Recipes", "content": "Edited" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let versions = |uri| app.request(Method::GET, uri, None, &auth);
+    let res = versions("/api/articles/recipes%2Faxum-routing/versions").await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    let res = versions("/api/articles/guides%2Faxum-routing/versions").await;
+    assert_eq!(res.body, json!([]));
+
+    // Reactions count once per article, whichever form the path used.
+    let visitor = app.visitor_cookie();
+    for uri in [
+        "/api/articles/guides%2Faxum-routing/reactions",
+        "/api/articles/recipes%2Faxum-routing/reactions",
+        "/api/articles/hello-world/reactions",
+    ] {
+        let res = app
+            .request(
+                Method::POST,
+                uri,
+                Some(json!({ "emoji": "👍" })),
+                &[("cookie", visitor.as_str())],
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert_eq!(res.body["reactions"][0]["count"], 1);
+    }
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/guides%2Faxum-routing/reactions",
+            Some(json!({ "emoji": "👍" })),
+            &[("cookie", visitor.as_str())],
+        )
+        .await;
+    assert_eq!(res.body["slug"], "guides/axum-routing");
+    assert_eq!(res.body["reactions"][0]["count"], 0);
+    let res = app.get("/api/articles/recipes%2Faxum-routing/meta").await;
+    assert_eq!(res.body["slug"], "recipes/axum-routing");
+    assert_eq!(res.body["reactions"][0]["count"], 1);
+}
+
+#[tokio::test]
+async fn failed_saves_roll_back_the_article_file() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    // A file where the version directory belongs makes saving a version fail.
+    let versions = app.dir.path().join("data/articles");
+    std::fs::create_dir_all(&versions).unwrap();
+    std::fs::write(versions.join("guides"), "").unwrap();
+    std::fs::write(versions.join("blocked-post"), "").unwrap();
+
+    let article = app
+        .dir
+        .path()
+        .join(ARTICLE_DIR)
+        .join("guides/axum-routing.md");
+    let original = std::fs::read_to_string(&article).unwrap();
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/axum-routing",
+            Some(json!({ "title": "Rewritten", "content": "Rewritten" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.body["error_code"], "ERR_PERSISTENCE_ROLLED_BACK");
+    let message = res.body["message"].as_str().unwrap();
+    assert!(
+        message.contains("restored the previous article file"),
+        "{message}"
+    );
+    assert_eq!(std::fs::read_to_string(&article).unwrap(), original);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Blocked Post", "content": "Never saved" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_PERSISTENCE_ROLLED_BACK");
+    assert!(
+        res.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("removed the new article file")
+    );
+    assert!(
+        !app.dir
+            .path()
+            .join(ARTICLE_DIR)
+            .join("blocked-post.md")
+            .exists()
+    );
+    assert_eq!(
+        app.get("/api/articles/blocked-post").await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn changing_the_category_moves_the_article_file() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let articles = app.dir.path().join(ARTICLE_DIR);
+    let update = json!({ "title": "Axum Routing", "content": "Moved", "category": "news" });
+
+    // A file the store does not know about is not overwritten.
+    std::fs::create_dir_all(articles.join("news")).unwrap();
+    std::fs::write(articles.join("news/axum-routing.md"), "stray").unwrap();
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/axum-routing",
+            Some(update.clone()),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["error_code"], "ERR_ARTICLE_EXISTS");
+    std::fs::remove_file(articles.join("news/axum-routing.md")).unwrap();
+
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/axum-routing",
+            Some(update),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(articles.join("news/axum-routing.md").exists());
+    // The emptied category directory is cleaned up.
+    assert!(!articles.join("guides").exists());
+
+    let res = app.get("/api/articles/news%2Faxum-routing").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["content"], "Moved");
+    // The saved versions move along with the article.
+    let versions = app.dir.path().join("data/articles");
+    assert!(versions.join("news/axum-routing/versions").is_dir());
+    assert!(!versions.join("guides/axum-routing").exists());
+    assert_eq!(
+        app.get("/api/articles/guides%2Faxum-routing").await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get("/api/articles/axum-routing").await.body["metadata"]["category"],
+        "news"
+    );
+}
+
+#[tokio::test]
+async fn api_edits_keep_hand_written_front_matter() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let path = app
+        .dir
+        .path()
+        .join(ARTICLE_DIR)
+        .join("guides/axum-routing.md");
+    let original = std::fs::read_to_string(&path).unwrap();
+    let annotated = original.replacen("---\n", "---\n# Reviewed in May\n", 1);
+    std::fs::write(&path, annotated).unwrap();
+
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/axum-routing",
+            Some(json!({ "title": "Routing in Axum", "content": "Edited" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let text = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[..8],
+        [
+            "---",
+            "# Reviewed in May",
+            "title: Routing in Axum",
+            "author: \"Scribe\"",
+            "date: 2024-02-01T00:00:00Z",
+            "description: \"Routing with axum\"",
+            "tags: [\"rust\", \"axum\"]",
+            "draft: false",
+        ]
+    );
+    assert!(lines[8].starts_with("last_updated: "), "{text}");
+}
+
+#[tokio::test]
+async fn draft_transitions_update_tags_listings_and_search() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    for draft in [true, false, true, false] {
+        let res = app
+            .request(
+                Method::PUT,
+                "/api/articles/axum-routing",
+                Some(json!({ "title": "Axum Routing", "content": "Routers", "draft": draft })),
+                &auth,
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+        let tags = app.get("/api/tags").await.body;
+        assert_eq!(tags.as_array().unwrap().contains(&json!("axum")), !draft);
+        let listed = app.get("/api/articles?limit=100").await.body;
+        let listed = listed["articles"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|a| a["slug"] == "guides/axum-routing");
+        assert_eq!(listed, !draft);
+
+        app.state.index_tx.as_ref().unwrap().flush().await;
+        let res = app.get("/api/search?q=routers").await;
+        let hits = res.body["results"].as_array().unwrap().len();
+        assert_eq!(hits, if draft { 0 } else { 1 }, "draft = {draft}");
+    }
+}
+
+#[tokio::test]
+async fn articles_move_through_the_editorial_workflow() {
+    let app = TestApp::spawn_with("notify_webhook = \"http://127.0.0.1:9/hook\"").await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let update =
+        |status: &str| json!({ "title": "Hello World", "content": "Hi", "status": status });
+
+    // Published articles cannot go back into review.
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(update("in_review")),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        res.body["details"]["status"][0],
+        "cannot change from published to in_review"
+    );
+
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(update("archived")),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/api/articles").await;
+    assert_eq!(slugs(&res.body), ["guides/axum-routing"]);
+    let text =
+        std::fs::read_to_string(app.dir.path().join(ARTICLE_DIR).join("hello-world.md")).unwrap();
+    assert!(
+        text.contains("status: archived") && !text.contains("draft:"),
+        "{text}"
+    );
+    let queued = due_jobs(&app.state.db, i64::MAX, 10).await.unwrap();
+    let event: serde_json::Value = serde_json::from_str(&queued[0].payload).unwrap();
+    assert_eq!(event["event"], "status_changed");
+    assert_eq!(
+        (&event["from"], &event["to"]),
+        (&json!("published"), &json!("archived"))
+    );
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles?status=archived",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    assert_eq!(res.body[0]["slug"], "hello-world");
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles?status=draft",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body[0]["slug"], "unpublished");
+
+    // Scheduled articles wait for the job once their date has passed.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Coming Up", "content": "Soon", "status": "scheduled" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        app.get("/api/articles/coming-up").await.status,
+        StatusCode::NOT_FOUND
+    );
+    let res = app
+        .request(Method::GET, "/api/admin/overview", None, &admin)
+        .await;
+    assert_eq!(res.body["scheduled_posts"][0]["slug"], "coming-up");
+    assert_eq!(publish_scheduled(&app.state).await.unwrap(), 1);
+    let res = app.get("/api/articles/coming-up").await;
+    assert_eq!(res.body["metadata"]["status"], "published");
+    assert_eq!(publish_scheduled(&app.state).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn archived_articles_stay_reachable_but_unlisted() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let archive = json!({ "title": "Hello World", "content": "Old news", "status": "archived" });
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(archive),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["archived"], true);
+    let res = app.get("/api/articles/axum-routing").await;
+    assert!(res.body.get("archived").is_none());
+    assert_eq!(
+        app.get("/api/articles/hello-world/changelog").await.status,
+        StatusCode::OK
+    );
+
+    for uri in ["/feed/updated.xml", "/sitemap.xml"] {
+        let body = app.get(uri).await.body.to_string();
+        assert!(!body.contains("hello-world"), "{uri}");
+    }
+    app.state.index_tx.as_ref().unwrap().flush().await;
+    let res = app.get("/api/search?q=news").await;
+    assert_eq!(res.body["results"], json!([]));
+
+    // Only the admin token can ask for archived articles in listings.
+    let res = app.get("/api/articles?include_archived=true").await;
+    assert!(!slugs(&res.body).contains(&"hello-world".to_string()));
+    let res = app
+        .request(
+            Method::GET,
+            "/api/articles?include_archived=true",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+}
+
+#[tokio::test]
+async fn recent_edits_list_drafts_and_notes_newest_first() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let cookie = app.author_cookie();
+    let edit = json!({ "title": "Unpublished", "content": "Still going", "status": "draft" });
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/unpublished",
+            Some(edit),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/api/admin/articles/recent-edits").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles/recent-edits",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let edits = res.body.as_array().unwrap();
+    assert_eq!(edits[0]["slug"], "unpublished");
+    assert_eq!(edits[0]["status"], "draft");
+    let mut slugs: Vec<_> = edits.iter().map(|e| e["slug"].as_str().unwrap()).collect();
+    slugs.sort();
+    assert_eq!(
+        slugs,
+        ["axum-routing", "hello-world", "notes/til", "unpublished"]
+    );
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles/recent-edits?limit=1",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn editor_locks_warn_other_sessions() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let lock = |editor: &str| json!({ "editor": editor });
+    let uri = "/api/admin/articles/hello-world/lock";
+
+    let res = app
+        .request(Method::POST, uri, Some(lock("laptop")), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(Method::POST, uri, Some(lock("laptop")), &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["locked_by"], "laptop");
+
+    let res = app
+        .request(Method::POST, uri, Some(lock("phone")), &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["error_code"], "ERR_ARTICLE_LOCKED");
+    assert_eq!(res.body["details"]["locked_by"], "laptop");
+    let res = app
+        .request(Method::POST, uri, Some(lock("laptop")), &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app
+        .request(Method::GET, "/api/admin/articles", None, &admin)
+        .await;
+    let articles = res.body.as_array().unwrap();
+    let hello = articles
+        .iter()
+        .find(|a| a["slug"] == "hello-world")
+        .unwrap();
+    assert_eq!(hello["locked_by"], "laptop");
+    assert!(hello["locked_until"].is_string());
+    let other = articles
+        .iter()
+        .find(|a| a["slug"] != "hello-world")
+        .unwrap();
+    assert!(other.get("locked_by").is_none());
+
+    let res = app
+        .request(Method::DELETE, &format!("{uri}?editor=phone"), None, &admin)
+        .await;
+    assert_eq!(res.body["released"], false);
+    let res = app
+        .request(
+            Method::DELETE,
+            &format!("{uri}?editor=laptop"),
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body["released"], true);
+    let res = app
+        .request(Method::POST, uri, Some(lock("phone")), &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/missing/lock",
+            Some(lock("phone")),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn edits_from_an_older_body_are_merged_word_by_word() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let base = "Welcome to the blog. Ferris says hi.";
+    for content in [base, "Welcome to the blog. Ferris says hello."] {
+        let update = json!({ "title": "Hello World", "content": content });
+        let res = app
+            .request(
+                Method::PUT,
+                "/api/articles/hello-world",
+                Some(update),
+                &auth,
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+
+    let uri = "/api/articles/hello-world/merge";
+    let edit = json!({ "base": base, "content": "Welcome to our blog. Ferris says hi." });
+    let res = app
+        .request(Method::POST, uri, Some(edit.clone()), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.request(Method::POST, uri, Some(edit), &auth).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["merged"], true);
+    assert_eq!(
+        res.body["content"].as_str().unwrap().trim(),
+        "Welcome to our blog. Ferris says hello."
+    );
+
+    let edit = json!({ "base": base, "content": "Welcome to the blog. Ferris says bye." });
+    let res = app.request(Method::POST, uri, Some(edit), &auth).await;
+    assert_eq!(res.body["merged"], false);
+    assert!(res.body.get("content").is_none());
+    assert_eq!(
+        res.body["conflicts"],
+        json!([{
+            "context": "Welcome to the blog. Ferris says",
+            "base": "hi.",
+            "yours": "bye.",
+            "current": "hello.",
+        }])
+    );
+}
+
+#[tokio::test]
+async fn page_sizes_are_capped_by_configuration() {
+    let app = TestApp::spawn_with("[pagination]\ndefault_limit = 1\nmax_limit = 2").await;
+
+    let res = app.get("/api/v1/articles").await;
+    assert_eq!(res.body["data"]["articles"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body["meta"]["pagination"]["limit"], 1);
+    assert_eq!(res.body["meta"]["pagination"]["max_limit"], 2);
+
+    let res = app
+        .get("/api/v1/articles?limit=100000&include_content=true")
+        .await;
+    assert_eq!(res.body["meta"]["pagination"]["limit"], 2);
+    assert_eq!(res.body["data"]["articles"].as_array().unwrap().len(), 2);
+
+    for uri in [
+        "/api/v1/notes?limit=100000",
+        "/api/v1/guestbook?limit=100000",
+        "/api/v1/search?q=rust&limit=100000",
+    ] {
+        let res = app.get(uri).await;
+        assert_eq!(res.status, StatusCode::OK, "{uri}: {}", res.body);
+        assert_eq!(res.body["meta"]["pagination"]["limit"], 2, "{uri}");
+        assert_eq!(res.body["meta"]["pagination"]["max_limit"], 2, "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn revised_articles_sort_first_and_fill_the_updated_feed() {
+    let app = TestApp::spawn().await;
+    let feed = app.get("/feed/updated.xml").await;
+    assert_eq!(feed.headers["content-type"], "application/rss+xml");
+    assert!(!feed.body.as_str().unwrap().contains("<item>"));
+
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(json!({ "title": "Hello World", "content": "Rewritten for 2025." })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+    let res = app.get("/api/articles?sort=updated").await;
+    assert_eq!(slugs(&res.body), ["hello-world", "guides/axum-routing"]);
+    let res = app.get("/api/articles?sort=sideways").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let feed = app.get("/feed/updated.xml").await;
+    let feed = feed.body.as_str().unwrap();
+    assert_eq!(feed.matches("<item>").count(), 1);
+    assert!(feed.contains("<link>http://localhost:3000/articles/hello-world</link>"));
+}
+
+#[tokio::test]
+async fn changelog_counts_changed_lines_per_revision() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/articles/hello-world/changelog").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["entries"], json!([]));
+    assert!(res.body["revised_at"].is_null());
+
+    let cookie = app.author_cookie();
+    let edits = [
+        (
+            json!("First line.\nSecond line."),
+            json!(null),
+            StatusCode::OK,
+        ),
+        (
+            json!("First line.\nA better second line.\nThird line."),
+            json!("Clarify"),
+            StatusCode::OK,
+        ),
+        (
+            json!("x"),
+            json!("m".repeat(501)),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+    ];
+    for (content, message, status) in edits {
+        let res = app
+            .request(
+                Method::PUT,
+                "/api/articles/hello-world",
+                Some(json!({ "title": "Hello World", "content": content, "message": message })),
+                &[("cookie", cookie.as_str())],
+            )
+            .await;
+        assert_eq!(res.status, status, "{}", res.body);
+    }
+
+    let res = app.get("/api/articles/hello-world/changelog").await;
+    let entries = res.body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["message"], "Clarify");
+    assert_eq!(entries[0]["lines_added"], 2);
+    assert_eq!(entries[0]["lines_removed"], 1);
+    assert!(entries[1]["lines_added"].is_null());
+    assert!(entries[1].get("message").is_none());
+    assert!(res.body["revised_at"].is_string());
+}
+
+#[tokio::test]
+async fn entries_link_to_their_source_in_the_repository() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/articles/hello-world").await;
+    assert!(res.body.get("edit_url").is_none());
+
+    let app = TestApp::spawn_with(
+        "[repository]\nurl = \"https://github.com/user/blog/\"\npath = \"content\"",
+    )
+    .await;
+    let res = app.get("/api/articles/axum-routing").await;
+    assert_eq!(
+        res.body["edit_url"],
+        "https://github.com/user/blog/edit/main/content/article/guides/axum-routing.md"
+    );
+    let res = app.get("/api/articles").await;
+    assert_eq!(
+        res.body["articles"][1]["edit_url"],
+        "https://github.com/user/blog/edit/main/content/article/hello-world.md"
+    );
+    let res = app.get("/api/notes/til").await;
+    assert_eq!(
+        res.body["edit_url"],
+        "https://github.com/user/blog/edit/main/content/notes/til.md"
+    );
+}
+
+#[tokio::test]
+async fn accepted_suggestions_patch_the_article_and_save_a_version() {
+    let app = TestApp::spawn_with("notify_webhook = \"http://127.0.0.1:9/hook\"").await;
+    let cookie = app.visitor_cookie();
+    let suggest = |original: &str, replacement: &str| json!({ "original": original, "replacement": replacement, "note": "typo" });
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/hello-world/suggestions",
+            Some(suggest("ferris", "Ferris")),
+            &[],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/hello-world/suggestions",
+            Some(suggest("lobster", "crab")),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_SUGGESTION_MISMATCH");
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let res = app
+            .request(
+                Method::POST,
+                "/api/articles/hello-world/suggestions",
+                Some(suggest("ferris", "Ferris")),
+                &[("cookie", cookie.as_str())],
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert_eq!(res.body["author_login"], "visitor");
+        ids.push(res.body["id"].as_i64().unwrap());
+    }
+    let queued = due_jobs(&app.state.db, i64::MAX, 10).await.unwrap();
+    assert_eq!(queued.len(), 2);
+    assert_eq!(queued[0].job_type, "webhook");
+
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(Method::GET, "/api/admin/suggestions", None, &admin)
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 2);
+
+    let accept = format!("/api/admin/suggestions/{}/accept", ids[0]);
+    let res = app.request(Method::POST, &accept, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/api/articles/hello-world").await;
+    let content = res.body["content"].as_str().unwrap();
+    assert!(content.contains("Ferris") && !content.contains("ferris"));
+    let res = app.get("/api/articles/hello-world/changelog").await;
+    assert_eq!(
+        res.body["entries"][0]["message"],
+        "Applied suggestion 1 from visitor"
+    );
+
+    // The second suggestion no longer applies and stays pending.
+    let accept = format!("/api/admin/suggestions/{}/accept", ids[1]);
+    let res = app.request(Method::POST, &accept, None, &admin).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    let dismiss = format!("/api/admin/suggestions/{}/dismiss", ids[1]);
+    let res = app.request(Method::POST, &dismiss, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.request(Method::POST, &dismiss, None, &admin).await;
+    assert_eq!(res.body["error_code"], "ERR_SUGGESTION_NOT_FOUND");
+    let res = app
+        .request(Method::GET, "/api/admin/suggestions", None, &admin)
+        .await;
+    assert_eq!(res.body, json!([]));
+}
+
+#[tokio::test]
+async fn preview_links_share_drafts_with_reviewers() {
+    let app = TestApp::spawn_with("notify_webhook = \"http://127.0.0.1:9/hook\"").await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    // Comments are rate limited per signed-in user across tests.
+    let reviewer = app.session_cookie(&User::new(1001, "reviewer".to_string(), false));
+    let reviewer = [("cookie", reviewer.as_str())];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/unpublished/previews",
+            Some(json!({ "reviewer": "Ada", "expires_in_hours": 24 })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["reviewer"], "Ada");
+    let id = res.body["id"].as_i64().unwrap();
+    let uri = format!("/api/previews/{}", res.body["token"].as_str().unwrap());
+
+    let res = app.get(&uri).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "unpublished");
+    assert_eq!(res.body["comments"], json!([]));
+    let v1_uri = uri.replace("/api/", "/api/v1/");
+    assert_eq!(app.get(&v1_uri).await.status, StatusCode::OK);
+
+    let res = app
+        .request(
+            Method::POST,
+            &format!("{}/comments", uri),
+            Some(json!({ "name": "Ada", "content": "Needs an *example*" })),
+            &reviewer,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["content"], "<p>Needs an <em>example</em></p>\n");
+    let res = app.get(&uri).await;
+    assert_eq!(res.body["comments"][0]["author_name"], "Ada");
+    let queued = due_jobs(&app.state.db, i64::MAX, 10).await.unwrap();
+    assert_eq!(queued.len(), 1);
+
+    // Review comments stay out of the public ones.
+    let res = app
+        .request(Method::GET, "/api/admin/comments/export", None, &admin)
+        .await;
+    assert_eq!(res.body, json!([]));
+
+    let res = app
+        .request(
+            Method::DELETE,
+            &format!("/api/admin/previews/{}", id),
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    // Revoked links stop working under every API version.
+    let res = app.get(&uri).await;
+    assert_eq!(res.body["error_code"], "ERR_PREVIEW_NOT_FOUND");
+    let res = app.get(&v1_uri).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error"]["code"], "ERR_PREVIEW_NOT_FOUND");
+    let res = app
+        .request(
+            Method::POST,
+            &format!("{}/comments", uri),
+            Some(json!({ "name": "Ada", "content": "Too late" })),
+            &reviewer,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    // The author keeps the review after the link is gone.
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles/unpublished/review-comments",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/articles/unpublished/previews",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body[0]["revoked"], true);
+}
+
+#[tokio::test]
+async fn api_keys_read_public_content_and_are_counted() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/keys",
+            Some(json!({ "name": "Reading list" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let id = res.body["id"].as_i64().unwrap();
+    let token = res.body["token"].as_str().unwrap().to_string();
+    let key = [("x-api-key", token.as_str())];
+
+    // Keys read what anonymous visitors can, cached or not.
+    for _ in 0..2 {
+        let res = app.request(Method::GET, "/api/articles", None, &key).await;
+        assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+    }
+    let res = app
+        .request(Method::DELETE, "/api/articles/hello-world", None, &key)
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.body["error_code"], "ERR_API_KEY_READ_ONLY");
+
+    let res = app
+        .request(Method::GET, "/api/admin/keys", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["request_count"], 2);
+    assert!(res.body[0].get("token").is_none());
+
+    let revoke = format!("/api/admin/keys/{id}");
+    let res = app.request(Method::DELETE, &revoke, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.request(Method::GET, "/api/articles", None, &key).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.body["error_code"], "ERR_INVALID_API_KEY");
+}
+
+#[tokio::test]
+async fn api_keys_are_held_to_their_daily_quota() {
+    let app = TestApp::spawn_with("[api_keys]\ndaily_quota = 2").await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/keys",
+            Some(json!({ "name": "Busy app" })),
+            &admin,
+        )
+        .await;
+    let id = res.body["id"].as_i64().unwrap();
+    let token = res.body["token"].as_str().unwrap().to_string();
+    let key = [("x-api-key", token.as_str())];
+
+    for _ in 0..2 {
+        let res = app.request(Method::GET, "/api/articles", None, &key).await;
+        assert_eq!(res.status, StatusCode::OK);
+    }
+    let res = app.request(Method::GET, "/api/articles", None, &key).await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.body["error_code"], "ERR_API_QUOTA_EXCEEDED");
+    assert!(res.headers.contains_key("retry-after"));
+
+    let usage = format!("/api/admin/keys/{id}/usage");
+    let res = app.request(Method::GET, &usage, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["daily_quota"], 2);
+    assert_eq!(res.body["today"], 2);
+    assert_eq!(res.body["days"].as_array().unwrap().len(), 1);
+
+    // A key's own quota wins over the configured one.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/keys",
+            Some(json!({ "name": "Trusted app", "daily_quota": 0 })),
+            &admin,
+        )
+        .await;
+    let token = res.body["token"].as_str().unwrap().to_string();
+    for _ in 0..3 {
+        let res = app
+            .request(Method::GET, "/api/articles", None, &[("x-api-key", &token)])
+            .await;
+        assert_eq!(res.status, StatusCode::OK);
+    }
+
+    let res = app
+        .request(Method::GET, "/api/admin/keys/999/usage", None, &admin)
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_API_KEY_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn read_only_mode_refuses_writes_but_serves_reads() {
+    let app = TestApp::spawn_with("read_only = true").await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let writes = [
+        (Method::POST, "/api/articles"),
+        (Method::PUT, "/api/articles/hello-world"),
+        (Method::POST, "/api/articles/hello-world/versions/1/restore"),
+        (Method::POST, "/api/articles/hello-world/suggestions"),
+        (Method::POST, "/api/guestbook"),
+        (Method::PUT, "/api/users/me/profile"),
+    ];
+    for (method, uri) in writes {
+        let res = app
+            .request(method.clone(), uri, Some(json!({})), &auth)
+            .await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN, "{method} {uri}");
+        assert_eq!(res.body["error_code"], "ERR_READ_ONLY", "{method} {uri}");
+    }
+    assert!(
+        app.dir
+            .path()
+            .join(ARTICLE_DIR)
+            .join("hello-world.md")
+            .exists()
+    );
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(slugs(&res.body), ["guides/axum-routing", "hello-world"]);
+
+    // Maintenance that does not touch content still works.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/keys",
+            Some(json!({ "name": "Mirror" })),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
+
+#[tokio::test]
+async fn deploy_hooks_are_queued_for_delivery() {
+    let app = TestApp::spawn_with(
+        "[deploy_hooks.netlify]\nurl = \"http://127.0.0.1:9/build\"\nsecret_env = \"NETLIFY_HOOK_SECRET\"",
+    )
+    .await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let trigger = "/api/admin/deploy-hooks/netlify/trigger";
+    let res = app.request(Method::POST, trigger, None, &[]).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.request(Method::POST, trigger, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["hook"], "netlify");
+
+    let queued = due_jobs(&app.state.db, i64::MAX, 10).await.unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].job_type, "deploy_hook");
+    assert!(queued[0].payload.contains("\"hook\":\"netlify\""));
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/deploy-hooks/vercel/trigger",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_DEPLOY_HOOK_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn overview_shows_whether_the_last_deploy_is_behind_content() {
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("SCRIBE_TEST_DEPLOY_SECRET", "hush") };
+    let app = TestApp::spawn_with(
+        "[deploy_hooks.site]\nurl = \"http://127.0.0.1:9/build\"\nsecret_env = \"SCRIBE_TEST_DEPLOY_SECRET\"\n\n[deploy_hooks.unsigned]\nurl = \"http://127.0.0.1:9/build\"",
+    )
+    .await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let publishing = || async {
+        let res = app
+            .request(Method::GET, "/api/admin/overview", None, &admin)
+            .await;
+        res.body["publishing"].clone()
+    };
+    assert_eq!(publishing().await["deploy_pending"], false);
+
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/hello-world",
+            Some(json!({ "title": "Hello, World", "content": "Edited" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let status = publishing().await;
+    assert!(status["content_changed_at"].is_string());
+    assert!(status["deployed_at"].is_null());
+    assert_eq!(status["deploy_pending"], true);
+
+    let report = json!({ "state": "ready" });
+    let signature = format!("sha256={}", sign("hush", report.to_string().as_bytes()));
+    for (uri, signature) in [
+        ("/api/deploy-hooks/site/callback", "sha256=00"),
+        ("/api/deploy-hooks/unsigned/callback", signature.as_str()),
+    ] {
+        let res = app
+            .request(
+                Method::POST,
+                uri,
+                Some(report.clone()),
+                &[("x-scribe-signature", signature)],
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{uri}");
+        assert_eq!(res.body["error_code"], "ERR_INVALID_SIGNATURE");
+    }
+    assert_eq!(publishing().await["deploy_pending"], true);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/deploy-hooks/site/callback",
+            Some(report),
+            &[("x-scribe-signature", signature.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let status = publishing().await;
+    assert!(status["deployed_at"].is_string());
+    assert_eq!(status["deploy_pending"], false);
+}
+
+#[tokio::test]
+async fn daily_notes_are_created_then_appended_to() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let entry = |content: &str, append: bool| json!({ "date": "2024-05-01", "content": content, "append": append });
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/notes/daily",
+            Some(entry("Fed ferris", false)),
+            &[],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.get("/api/notes/daily/2024-05-01").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    for (content, append) in [("Fed ferris", false), ("Walked ferris", true)] {
+        let res = app
+            .request(
+                Method::POST,
+                "/api/admin/notes/daily",
+                Some(entry(content, append)),
+                &admin,
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+        assert_eq!(res.body["slug"], "journal/2024-05-01");
+    }
+    let res = app.get("/api/notes/daily/2024-05-01").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["metadata"]["title"], "2024-05-01");
+    assert_eq!(res.body["content"], "Fed ferris\n\nWalked ferris");
+    assert!(app.dir.path().join("notes/journal/2024-05-01.md").exists());
+
+    // Without append the body is replaced.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/notes/daily",
+            Some(entry("Only this", false)),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body["content"], "Only this");
+
+    let res = app.get("/api/notes/daily/yesterday").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args([
+            "-c",
+            "user.name=Scribe",
+            "-c",
+            "user.email=scribe@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+#[tokio::test]
+async fn branches_are_checked_out_and_served_for_review() {
+    let repo = TempDir::new().unwrap();
+    std::fs::create_dir_all(repo.path().join("content/article")).unwrap();
+    std::fs::write(
+        repo.path().join("content/article/hello-world.md"),
+        "---\ntitle: \"Hello\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\n---\n\nOn main.\n",
+    )
+    .unwrap();
+    git(
+        repo.path(),
+        &["init", "--quiet", "--initial-branch", "main"],
+    );
+    git(repo.path(), &["add", "."]);
+    git(repo.path(), &["commit", "--quiet", "-m", "Initial"]);
+    git(repo.path(), &["checkout", "--quiet", "-b", "reorg/2024"]);
+    std::fs::rename(
+        repo.path().join("content/article/hello-world.md"),
+        repo.path().join("content/article/hello-again.md"),
+    )
+    .unwrap();
+    git(repo.path(), &["add", "-A"]);
+    git(repo.path(), &["commit", "--quiet", "-m", "Rename"]);
+    git(repo.path(), &["checkout", "--quiet", "main"]);
+
+    let config = format!(
+        "[preview_branches]\nremote = \"{}\"\npath = \"content\"\n",
+        repo.path().display()
+    );
+    let app = TestApp::spawn_with(&config).await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/preview-branches",
+            Some(json!({ "branch": "reorg/2024" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["articles"], 1);
+    assert_eq!(res.body["added"], json!(["hello-again"]));
+    assert!(
+        res.body["removed"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("hello-world"))
+    );
+
+    let res = app.get("/preview/reorg%2F2024/api/articles").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(
+            Method::GET,
+            "/preview/reorg%2F2024/api/articles/hello-again",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["content"], "On main.");
+    let res = app
+        .request(
+            Method::GET,
+            "/preview/reorg%2F2024/api/articles/hello-world",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/preview-branches",
+            Some(json!({ "branch": "missing" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_BRANCH_CHECKOUT_FAILED");
+
+    let res = app
+        .request(
+            Method::DELETE,
+            "/api/admin/preview-branches/reorg%2F2024",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let checkouts = app.dir.path().join("data/preview-branches");
+    assert_eq!(std::fs::read_dir(checkouts).unwrap().count(), 0);
+    let res = app
+        .request(
+            Method::GET,
+            "/preview/reorg%2F2024/api/articles",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_PREVIEW_BRANCH_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn comment_threads_can_be_locked_and_reopened() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let uri = "/api/admin/articles/hello-world/comments/lock";
+    let file = app.dir.path().join(ARTICLE_DIR).join("hello-world.md");
+
+    let res = app.request(Method::POST, uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!({ "slug": "hello-world", "comments": "closed" })
+    );
+    assert!(
+        std::fs::read_to_string(&file)
+            .unwrap()
+            .contains("comments: closed")
+    );
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.body["metadata"]["comments"], "closed");
+
+    let res = app.request(Method::DELETE, uri, None, &admin).await;
+    assert_eq!(res.body["comments"], "open");
+    assert!(
+        !std::fs::read_to_string(&file)
+            .unwrap()
+            .contains("comments:")
+    );
+    let res = app.get("/api/articles/hello-world").await;
+    assert!(res.body["metadata"].get("comments").is_none());
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/missing/comments/lock",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn comment_limits_ignore_client_supplied_ids() {
+    let app = TestApp::spawn().await;
+    let cookie = app.session_cookie(&User::new(1004, "chatty".to_string(), false));
+    let mut statuses = Vec::new();
+    for n in 0..6 {
+        let id = format!("fresh-{n}");
+        let res = app
+            .request(
+                Method::POST,
+                "/api/guestbook",
+                Some(json!({ "content": format!("Hello {n}") })),
+                &[("cookie", cookie.as_str()), ("x-user-id", id.as_str())],
+            )
+            .await;
+        statuses.push(res.status);
+    }
+    assert!(
+        statuses[..5]
+            .iter()
+            .all(|s| *s != StatusCode::TOO_MANY_REQUESTS)
+    );
+    assert_eq!(statuses[5], StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn pausing_comments_refuses_new_entries_but_serves_old_ones() {
+    let app = TestApp::spawn_with("comments_paused = true").await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let cookie = app.session_cookie(&User::new(1002, "paused".to_string(), false));
+    let auth = [("cookie", cookie.as_str())];
+    let sign = || {
+        app.request(
+            Method::POST,
+            "/api/guestbook",
+            Some(json!({ "content": "Hello" })),
+            &auth,
+        )
+    };
+
+    let res = sign().await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN, "{}", res.body);
+    assert_eq!(res.body["error_code"], "ERR_COMMENTS_PAUSED");
+    let res = app
+        .request(Method::POST, "/api/comments", None, &auth)
+        .await;
+    assert_eq!(res.body["error_code"], "ERR_COMMENTS_PAUSED");
+
+    let res = app
+        .request(Method::DELETE, "/api/admin/comments/pause", None, &admin)
+        .await;
+    assert_eq!(res.body, json!({ "paused": false }));
+    let res = sign().await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app
+        .request(Method::POST, "/api/admin/comments/pause", None, &admin)
+        .await;
+    assert_eq!(res.body, json!({ "paused": true }));
+    assert_eq!(sign().await.status, StatusCode::FORBIDDEN);
+    let res = app.get("/api/guestbook").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["entries"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn moderation_rules_reject_or_hold_guestbook_entries() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    for rule in [
+        json!({ "pattern": "darn", "action": "hold" }),
+        json!({ "pattern": "(?i)cheap\\s+pills", "regex": true, "action": "reject" }),
+    ] {
+        let res = app
+            .request(
+                Method::POST,
+                "/api/admin/moderation/rules",
+                Some(rule),
+                &admin,
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/moderation/rules",
+            Some(json!({ "pattern": "(", "regex": true, "action": "delete" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.body["details"]["pattern"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body["details"]["action"].as_array().unwrap().len(), 1);
+
+    let cookie = app.session_cookie(&User::new(1003, "moderation".to_string(), false));
+    let auth = [("cookie", cookie.as_str())];
+    let sign = |content: &str| {
+        app.request(
+            Method::POST,
+            "/api/guestbook",
+            Some(json!({ "content": content })),
+            &auth,
+        )
+    };
+    let res = sign("Buy CHEAP  pills").await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.body);
+    let res = sign("Darn good blog").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["approved"], false);
+    let res = sign("Good blog").await;
+    assert_eq!(res.body["approved"], true);
+
+    let res = app
+        .request(Method::GET, "/api/admin/moderation/rules", None, &admin)
+        .await;
+    let id = res.body[0]["id"].as_i64().unwrap();
+    let uri = format!("/api/admin/moderation/rules/{}", id);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.body["error_code"], "ERR_MODERATION_RULE_NOT_FOUND");
+    let res = sign("Darn good blog").await;
+    assert_eq!(res.body["approved"], true);
+}
+
+#[tokio::test]
+async fn saved_searches_report_new_matches_once() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let saved = json!({ "name": "Crabs", "query": "ferris", "notify": true });
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/saved-searches",
+            Some(saved.clone()),
+            &[],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/saved-searches",
+            Some(saved),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let id = res.body["id"].as_i64().unwrap();
+    // hello-world already matched when the search was saved.
+    assert!(check_saved_searches(&app.state).await.unwrap().is_empty());
+
+    let entry = json!({ "date": "2024-05-01", "content": "Saw ferris at the docks" });
+    let res = app
+        .request(Method::POST, "/api/admin/notes/daily", Some(entry), &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    sqlx::query(
+        "INSERT INTO comments (article_slug, author_github_id, content) \
+         VALUES ('hello-world', 1, '<p>Ferris is great</p>')",
+    )
+    .execute(&app.state.db)
+    .await
+    .unwrap();
+    app.state.index_tx.as_ref().unwrap().flush().await;
+
+    let alerts = check_saved_searches(&app.state).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].entries, ["notes/journal/2024-05-01"]);
+    assert_eq!(alerts[0].comments.len(), 1);
+    assert!(check_saved_searches(&app.state).await.unwrap().is_empty());
+
+    let res = app
+        .request(Method::GET, "/api/admin/saved-searches", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["name"], "Crabs");
+    let uri = format!("/api/admin/saved-searches/{id}");
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn notes_are_found_at_any_depth() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    let notes = dir.path().join("notes");
+    std::fs::create_dir_all(notes.join("programming/rust/async")).unwrap();
+    for (path, title) in [
+        ("programming/rust/async/pinning.md", "Pinning"),
+        ("programming/rust/ownership.md", "Ownership"),
+    ] {
+        let note = format!(
+            "---\ntitle: \"{title}\"\nauthor: \"Scribe\"\ndate: 2024-04-01T00:00:00Z\ndescription: \"\"\n---\n\nAbout {title}.\n"
+        );
+        std::fs::write(notes.join(path), note).unwrap();
+    }
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/notes/programming/rust/async/pinning").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "programming/rust/async/pinning");
+    assert_eq!(res.body["metadata"]["title"], "Pinning");
+    let res = app.get("/api/notes/programming/rust/ownership").await;
+    assert_eq!(res.body["metadata"]["title"], "Ownership");
+
+    let res = app.get("/api/notes/programming/pinning").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = app.get("/api/notes/til").await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admin_deletions_can_be_undone_once() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/redirects",
+            Some(json!({ "from": "/blog/hello", "to": "/articles/hello-world" })),
+            &admin,
+        )
+        .await;
+    let id = res.body["id"].as_i64().unwrap();
+
+    let uri = format!("/api/admin/redirects/{}", id);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["undo_expires_in"], 300);
+    let undo = format!(
+        "/api/admin/undo/{}",
+        res.body["undo_token"].as_str().unwrap()
+    );
+    assert_eq!(app.get("/blog/hello").await.status, StatusCode::NOT_FOUND);
+
+    let res = app.request(Method::POST, &undo, None, &[]).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.request(Method::POST, &undo, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/blog/hello").await;
+    assert_eq!(res.status, StatusCode::MOVED_PERMANENTLY);
+    let res = app
+        .request(Method::GET, "/api/admin/redirects", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["id"], id);
+
+    let res = app.request(Method::POST, &undo, None, &admin).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_UNDO_TOKEN_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn public_api_is_anonymous_cacheable_and_rate_limited() {
+    let app = TestApp::spawn_with("[public_api]\nrequests_per_minute = 2").await;
+    let client = [("x-forwarded-for", "203.0.113.7")];
+
+    let res = app
+        .request(
+            Method::OPTIONS,
+            "/api/public/articles",
+            None,
+            &[
+                ("origin", "https://app.example"),
+                ("access-control-request-method", "GET"),
+                ("access-control-request-headers", "accept"),
+            ],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    assert_eq!(res.headers["access-control-allow-origin"], "*");
+    assert_eq!(res.headers["access-control-max-age"], "86400");
+    assert_eq!(res.headers["access-control-allow-headers"], "accept");
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/public/articles/hello-world",
+            None,
+            &client,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.headers["access-control-allow-origin"], "*");
+    assert_eq!(res.headers["cache-control"], "public, max-age=300");
+    assert_eq!(res.headers["x-ratelimit-remaining"], "1");
+    assert!(!res.headers.contains_key("deprecation"));
+
+    let res = app
+        .request(Method::GET, "/api/public/tags", None, &client)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app
+        .request(Method::GET, "/api/public/tags", None, &client)
+        .await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.body["error_code"], "ERR_PUBLIC_API_RATE_LIMITED");
+    assert!(res.headers.contains_key("retry-after"));
+
+    let other = [("x-forwarded-for", "198.51.100.2")];
+    let res = app
+        .request(Method::GET, "/api/public/tags", None, &other)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    // The authoring API is not counted against the public quota.
+    let res = app.request(Method::GET, "/api/tags", None, &client).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(!res.headers.contains_key("access-control-allow-origin"));
+}
+
+#[tokio::test]
+async fn client_addresses_are_only_taken_from_trusted_proxies() {
+    let app = TestApp::spawn_with(
+        "trusted_proxies = [\"10.0.0.0/8\"]\n[public_api]\nrequests_per_minute = 1",
+    )
+    .await;
+    let status = |peer: &str, forwarded_for: &str| {
+        let mut req = Request::builder()
+            .uri("/api/public/tags")
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(axum::extract::ConnectInfo(
+            peer.parse::<std::net::SocketAddr>().unwrap(),
+        ));
+        let router = app.router.clone();
+        async move { router.oneshot(req).await.unwrap().status() }
+    };
+
+    // Behind the proxy the client is the rightmost hop it did not add, so
+    // made-up entries further left do not get a fresh quota.
+    assert_eq!(status("10.0.0.2:80", "1.1.1.1, 203.0.113.7").await, StatusCode::OK);
+    assert_eq!(
+        status("10.0.0.2:80", "2.2.2.2, 203.0.113.7").await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+        status("10.0.0.2:80", "203.0.113.8, 10.0.0.9").await,
+        StatusCode::OK
+    );
+    // A client connecting directly is known by its own address.
+    assert_eq!(status("198.51.100.2:80", "203.0.113.9").await, StatusCode::OK);
+    assert_eq!(
+        status("198.51.100.2:80", "203.0.113.10").await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[tokio::test]
+async fn route_groups_switch_layers_from_config() {
+    let app = TestApp::spawn_with(
+        r#"
+[public_api]
+requests_per_minute = 1
+
+[[route_layers]]
+prefix = "/api"
+compression = true
+
+[[route_layers]]
+prefix = "/api/search"
+cache = false
+
+[[route_layers]]
+prefix = "/api/public"
+rate_limit = false
+cors = false
+
+[[route_layers]]
+prefix = "/api/tags"
+rate_limit = true
+cors = true
+"#,
+    )
+    .await;
+    let hits = |app: &TestApp| app.state.cache.stats().iter().map(|c| c.hits).sum::<u64>();
+
+    app.get("/api/search?q=hello").await;
+    app.get("/api/search?q=hello").await;
+    assert_eq!(hits(&app), 0);
+    app.get("/api/articles").await;
+    app.get("/api/articles").await;
+    assert_eq!(hits(&app), 1);
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/v1/articles",
+            None,
+            &[("accept-encoding", "gzip")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-encoding"], "gzip");
+    let res = app.get("/api/articles").await;
+    assert!(!res.headers.contains_key("content-encoding"));
+
+    for _ in 0..2 {
+        let res = app.get("/api/public/tags").await;
+        assert_eq!(res.status, StatusCode::OK);
+        assert!(!res.headers.contains_key("access-control-allow-origin"));
+        assert!(!res.headers.contains_key("x-ratelimit-remaining"));
+    }
+
+    let res = app.get("/api/tags").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["access-control-allow-origin"], "*");
+    let res = app.get("/api/tags").await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.body["error_code"], "ERR_ROUTE_RATE_LIMITED");
+    assert!(res.headers.contains_key("retry-after"));
+    let res = app
+        .request(
+            Method::OPTIONS,
+            "/api/tags",
+            None,
+            &[("access-control-request-method", "GET")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    assert_eq!(res.headers["access-control-allow-origin"], "*");
+}
+
+#[tokio::test]
+async fn sitemap_is_served_from_a_snapshot_until_content_changes() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/sitemap.xml").await;
+    assert!(!res.headers.contains_key("etag"));
+
+    refresh_snapshots(&app.state).await;
+    assert!(app.dir.path().join("data/snapshots/sitemap.xml").exists());
+    let res = app.get("/sitemap.xml").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/xml");
+    assert!(res.body.as_str().unwrap().contains("hello-world"));
+    let etag = res.headers["etag"].to_str().unwrap().to_string();
+    let res = app
+        .request(
+            Method::GET,
+            "/sitemap.xml",
+            None,
+            &[("if-none-match", &etag)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+    let res = app.get("/feed/updated.xml").await;
+    assert!(res.headers.contains_key("etag"));
+
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Fresh Post", "content": "Brand new" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    // The stale snapshot is not served while the worker catches up.
+    let res = app
+        .request(
+            Method::GET,
+            "/sitemap.xml",
+            None,
+            &[("if-none-match", &etag)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.body.as_str().unwrap().contains("fresh-post"));
+
+    refresh_snapshots(&app.state).await;
+    let res = app.get("/sitemap.xml").await;
+    assert!(res.body.as_str().unwrap().contains("fresh-post"));
+    assert_ne!(res.headers["etag"], etag.as_str());
+}
+
+#[tokio::test]
+async fn home_sections_follow_the_configured_layout() {
+    let app = TestApp::spawn_with(
+        r#"
+[[home]]
+kind = "featured"
+slugs = ["hello-world", "unpublished", "missing"]
+
+[[home]]
+kind = "latest"
+limit = 1
+
+[[home]]
+kind = "pinned_notes"
+paths = ["til", "missing"]
+
+[[home]]
+kind = "popular_tags"
+limit = 2
+
+[[home]]
+kind = "recent_comments"
+limit = 2
+"#,
+    )
+    .await;
+    for slug in ["hello-world", "unpublished", "axum-routing"] {
+        sqlx::query(
+            "INSERT INTO comments (article_slug, author_github_id, content) VALUES (?, 1, 'Nice')",
+        )
+        .bind(slug)
+        .execute(&app.state.db)
+        .await
+        .unwrap();
+    }
+
+    let res = app.get("/api/home").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let sections = res.body["sections"].as_array().unwrap();
+    let kinds: Vec<&str> = sections
+        .iter()
+        .map(|s| s["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "featured",
+            "latest",
+            "pinned_notes",
+            "popular_tags",
+            "recent_comments"
+        ]
+    );
+    assert_eq!(slugs(&sections[0]), ["hello-world"]);
+    assert_eq!(sections[1]["articles"].as_array().unwrap().len(), 1);
+    assert_eq!(sections[2]["notes"][0]["slug"], "til");
+    assert_eq!(sections[2]["notes"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        sections[3]["tags"],
+        json!([{ "name": "rust", "count": 2 }, { "name": "axum", "count": 1 }])
+    );
+    let commented: Vec<&str> = sections[4]["comments"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["article_slug"].as_str().unwrap())
+        .collect();
+    assert_eq!(commented, ["axum-routing", "hello-world"]);
+}
+
+#[tokio::test]
+async fn pages_are_served_apart_from_articles() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/pages/about").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "about");
+    assert!(
+        res.body["content"]
+            .as_str()
+            .unwrap()
+            .contains("stand outside")
+    );
+
+    let res = app.get("/api/pages").await;
+    assert_eq!(res.body[0]["slug"], "about");
+    assert_eq!(res.body[0]["metadata"]["menu"]["order"], 1);
+
+    let res = app.get("/api/articles").await;
+    assert!(!res.body.to_string().contains("\"about\""));
+
+    let res = app.get("/api/search?q=stand%20outside").await;
+    assert_eq!(res.body["groups"][2]["collection"], "pages");
+    assert_eq!(res.body["groups"][2]["hits"][0]["slug"], "pages/about");
+
+    let sitemap = app.get("/sitemap.xml").await.body;
+    assert!(sitemap.as_str().unwrap().contains("/pages/about</loc>"));
+
+    let res = app.get("/api/pages/missing").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_PAGE_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn navigation_merges_config_items_with_page_menus() {
+    let app = TestApp::spawn_with(
+        r#"
+hostname = "https://blog.example"
+
+[[navigation]]
+title = "GitHub"
+url = "https://github.com/QiHuang02/scribe"
+order = 5
+
+[[navigation]]
+title = "Archive"
+url = "/archive"
+
+[[navigation.children]]
+title = "Tags"
+url = "https://blog.example/tags"
+"#,
+    )
+    .await;
+
+    let res = app.get("/api/navigation").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!([
+            {
+                "title": "Archive",
+                "url": "/archive",
+                "external": false,
+                "children": [{ "title": "Tags", "url": "https://blog.example/tags", "external": false }]
+            },
+            { "title": "About", "url": "/pages/about", "external": false },
+            { "title": "GitHub", "url": "https://github.com/QiHuang02/scribe", "external": true }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn flags_vary_by_requester() {
+    let app = TestApp::spawn_with(
+        r#"
+[flags.comments_ui]
+enabled = true
+
+[flags.new_editor]
+users = ["Author"]
+
+[flags.reading_list]
+signed_in = true
+
+[flags.unused]
+"#,
+    )
+    .await;
+
+    let res = app.get("/api/flags").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["flags"], json!(["comments_ui"]));
+
+    let visitor = app.visitor_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/flags",
+            None,
+            &[("cookie", visitor.as_str())],
+        )
+        .await;
+    assert_eq!(res.body["flags"], json!(["comments_ui", "reading_list"]));
+
+    let author = app.author_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/flags",
+            None,
+            &[("cookie", author.as_str())],
+        )
+        .await;
+    assert_eq!(
+        res.body["flags"],
+        json!(["comments_ui", "new_editor", "reading_list"])
+    );
+}
+
+#[tokio::test]
+async fn saved_language_picks_the_translation_served() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/translations",
+            Some(json!({ "slug": "axum-routing", "lang": "zh", "source_lang": "en" })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let visitor = app.visitor_cookie();
+    let auth = [("cookie", visitor.as_str())];
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/users/me/profile",
+            Some(json!({ "theme": "dark", "language": "zh" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app.request(Method::GET, "/api/auth/me", None, &auth).await;
+    assert_eq!(
+        res.body["preferences"],
+        json!({ "theme": "dark", "language": "zh" })
+    );
+
+    let res = app
+        .request(Method::GET, "/api/articles/hello-world", None, &auth)
+        .await;
+    assert_eq!(res.body["slug"], "guides/axum-routing");
+    assert_eq!(res.headers["content-language"], "zh");
+    let res = app
+        .request(
+            Method::GET,
+            "/api/articles/hello-world?lang=en",
+            None,
+            &auth,
+        )
+        .await;
+    assert_eq!(res.body["slug"], "hello-world");
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.body["slug"], "hello-world");
+
+    // Without a saved language, the browser's is used.
+    let author = app.author_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/auth/me",
+            None,
+            &[
+                ("cookie", author.as_str()),
+                ("accept-language", "zh-CN,en;q=0.8"),
+            ],
+        )
+        .await;
+    assert_eq!(
+        res.body["preferences"],
+        json!({ "theme": null, "language": "zh" })
+    );
+
+    // Values that would read back as sealed fields are refused.
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/users/me/profile",
+            Some(json!({ "bio": "enc1:x" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY, "{}", res.body);
+
+    let res = app
+        .request(Method::PUT, "/api/users/me/profile", Some(json!({})), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn account_data_can_be_exported_and_deleted() {
+    let app = TestApp::spawn().await;
+    let visitor = app.visitor_cookie();
+    let auth = [("cookie", visitor.as_str())];
+    for sql in [
+        "INSERT INTO comments (article_slug, author_github_id, content) VALUES ('hello-world', 2, 'Mine')",
+        "INSERT INTO comments (article_slug, author_github_id, content) VALUES ('hello-world', 1, 'Theirs')",
+        "INSERT INTO reactions (article_slug, user_github_id, emoji) VALUES ('hello-world', 2, '👍')",
+        "INSERT INTO guestbook_entries (author_github_id, author_login, content) VALUES (2, 'visitor', 'Hi')",
+        "INSERT INTO poll_votes (poll_id, option_index, voter_key) VALUES ('p', 0, 'user:2')",
+    ] {
+        sqlx::query(sql).execute(&app.state.db).await.unwrap();
+    }
+    app.request(
+        Method::PUT,
+        "/api/users/me/profile",
+        Some(json!({ "theme": "dark" })),
+        &auth,
+    )
+    .await;
+
+    let res = app
+        .request(Method::GET, "/api/users/me/export", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["user"]["github_login"], "visitor");
+    assert_eq!(res.body["preferences"]["theme"], "dark");
+    assert_eq!(res.body["comments"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body["comments"][0]["content"], "Mine");
+    assert_eq!(res.body["reactions"][0]["emoji"], "👍");
+    assert_eq!(res.body["guestbook_entries"][0]["content"], "Hi");
+    assert_eq!(res.body["poll_votes"][0]["poll_id"], "p");
+
+    let res = app
+        .request(Method::DELETE, "/api/users/me", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!({
+            "comments_anonymized": 1,
+            "suggestions_anonymized": 0,
+            "poll_votes_anonymized": 1,
+            "reactions_deleted": 1,
+            "guestbook_entries_deleted": 1,
+            "preferences_deleted": true
+        })
+    );
+    assert!(
+        res.headers["set-cookie"]
+            .to_str()
+            .unwrap()
+            .starts_with("user_session=")
+    );
+
+    let res = app
+        .request(Method::GET, "/api/users/me/export", None, &auth)
+        .await;
+    assert_eq!(res.body["preferences"], json!(null));
+    assert_eq!(res.body["comments"], json!([]));
+    assert_eq!(res.body["poll_votes"], json!([]));
+    let (anonymous,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM comments WHERE author_github_id = 0")
+            .fetch_one(&app.state.db)
+            .await
+            .unwrap();
+    assert_eq!(anonymous, 1);
+
+    let res = app
+        .request(Method::DELETE, "/api/users/me", None, &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn repeated_wrong_admin_tokens_lock_the_client_out() {
+    let app = TestApp::spawn_with("[admin_lockout]\nmax_failures = 2\nlockout_secs = 60").await;
+    let client = [("x-forwarded-for", "203.0.113.7")];
+    let wrong = [
+        ("x-forwarded-for", "203.0.113.7"),
+        ("authorization", "wrong"),
+    ];
+
+    for _ in 0..2 {
+        let res = app
+            .request(Method::GET, "/api/admin/overview", None, &wrong)
+            .await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN);
+    }
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/overview",
+            None,
+            &[client[0], ("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(res.body["error_code"], "ERR_ADMIN_LOCKED_OUT");
+    assert_eq!(res.headers["retry-after"], "60");
+
+    // Other clients are not affected.
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/overview",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+}
+
+#[tokio::test]
+async fn uploaded_assets_are_deduplicated_and_verified() {
+    let app = TestApp::spawn().await;
+    let upload = |content_type: &'static str| {
+        [
+            ("authorization", ADMIN_TOKEN),
+            ("content-type", content_type),
+        ]
+    };
+
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            "GIF89a",
+            &upload("image/gif"),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    assert_eq!(res.body["duplicate"], false);
+    let name = res.body["name"].as_str().unwrap().to_string();
+    assert!(name.ends_with(".gif"));
+
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            "GIF89a",
+            &upload("image/gif"),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["duplicate"], true);
+    assert_eq!(res.body["name"], name.as_str());
+
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            "#!/bin/sh",
+            &upload("text/x-sh"),
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_UNSUPPORTED_ASSET_TYPE");
+
+    let res = app.get(&format!("/api/assets/{}", name)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "image/gif");
+    assert_eq!(res.body, "GIF89a");
+    assert_eq!(
+        app.get("/api/assets/..%2Fconfig.toml").await.body["error_code"],
+        "ERR_ASSET_NOT_FOUND"
+    );
+
+    let verify = || {
+        app.request(
+            Method::GET,
+            "/api/admin/assets/verify",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+    };
+    let res = verify().await;
+    assert_eq!(res.body["checked"], 1);
+    assert_eq!(res.body["mismatches"], json!([]));
+
+    std::fs::write(app.state.data_dir.join("assets").join(&name), "GIF87a").unwrap();
+    let res = verify().await;
+    assert_eq!(res.body["mismatches"][0]["name"], name.as_str());
+}
+
+#[tokio::test]
+async fn assets_answer_range_and_conditional_requests() {
+    let app = TestApp::spawn().await;
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            "GIF89a-range",
+            &[
+                ("authorization", ADMIN_TOKEN),
+                ("content-type", "image/gif"),
+            ],
+        )
+        .await;
+    let uri = format!("/api/assets/{}", res.body["name"].as_str().unwrap());
+
+    let res = app
+        .request(Method::GET, &uri, None, &[("range", "bytes=0-5")])
+        .await;
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    assert_eq!(res.headers["content-range"], "bytes 0-5/12");
+    assert_eq!(res.headers["content-type"], "image/gif");
+    assert_eq!(res.body, "GIF89a");
+
+    let res = app.get(&uri).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["accept-ranges"], "bytes");
+    let etag = res.headers["etag"].to_str().unwrap().to_string();
+    let modified = res.headers["last-modified"].to_str().unwrap().to_string();
+
+    let res = app
+        .request(Method::GET, &uri, None, &[("if-none-match", &etag)])
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(res.headers["etag"], etag.as_str());
+    assert_eq!(res.body, "");
+    let res = app
+        .request(Method::GET, &uri, None, &[("if-modified-since", &modified)])
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+    let res = app
+        .request(Method::GET, &uri, None, &[("if-none-match", "\"other\"")])
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let uri = uri.replace("/api/", "/api/v1/");
+    for _ in 0..2 {
+        let res = app
+            .request(Method::GET, &uri, None, &[("range", "bytes=6-")])
+            .await;
+        assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.body, "-range");
+    }
+    let res = app
+        .request(Method::GET, &uri, None, &[("if-none-match", &etag)])
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn svg_assets_are_gzipped_for_clients_that_accept_it() {
+    let app = TestApp::spawn().await;
+    let svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">{}</svg>",
+        "<rect width=\"1\" height=\"1\"/>".repeat(20)
+    );
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            svg.clone(),
+            &[
+                ("authorization", ADMIN_TOKEN),
+                ("content-type", "image/svg+xml"),
+            ],
+        )
+        .await;
+    let name = res.body["name"].as_str().unwrap().to_string();
+    let uri = format!("/api/assets/{}", name);
+
+    let res = app
+        .request(Method::GET, &uri, None, &[("accept-encoding", "gzip")])
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-encoding"], "gzip");
+    assert_eq!(res.headers["content-type"], "image/svg+xml");
+    assert!(
+        res.headers["vary"]
+            .to_str()
+            .unwrap()
+            .contains("accept-encoding")
+    );
+    let etag = res.headers["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag, format!("W/\"{}\"", name));
+    let res = app
+        .request(
+            Method::GET,
+            &uri,
+            None,
+            &[("accept-encoding", "gzip"), ("if-none-match", &etag)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_MODIFIED);
+
+    // Ranges are served from the file as it is stored.
+    let res = app
+        .request(
+            Method::GET,
+            &uri,
+            None,
+            &[("accept-encoding", "gzip"), ("range", "bytes=0-3")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::PARTIAL_CONTENT);
+    assert!(res.headers.get("content-encoding").is_none());
+    assert_eq!(res.body, "<svg");
+
+    let res = app.get(&uri).await;
+    assert!(res.headers.get("content-encoding").is_none());
+    assert_eq!(res.headers["etag"], format!("\"{}\"", name).as_str());
+    assert_eq!(res.body, svg.as_str());
+
+    // Images are compressed already and keep their ranges.
+    let res = app
+        .request_raw(
+            Method::POST,
+            "/api/admin/assets",
+            "GIF89a".repeat(20),
+            &[
+                ("authorization", ADMIN_TOKEN),
+                ("content-type", "image/gif"),
+            ],
+        )
+        .await;
+    let uri = format!("/api/assets/{}", res.body["name"].as_str().unwrap());
+    let res = app
+        .request(Method::GET, &uri, None, &[("accept-encoding", "gzip")])
+        .await;
+    assert!(res.headers.get("content-encoding").is_none());
+    assert_eq!(res.headers["accept-ranges"], "bytes");
+}
+
+const EPISODE: &str = "---\ntitle: \"Episode one\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\ndescription: \"Pilot & intro\"\naudio:\n  file: https://cdn.example.com/ep1.mp3\n  duration: \"32:10\"\n  episode: 1\n---\n\nShow notes.\n";
+
+#[tokio::test]
+async fn audio_posts_make_a_podcast_feed() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(
+        &dir,
+        "[podcast]\ntitle = \"Scribe Radio\"\nauthor = \"Scribe\"\nowner_email = \"radio@example.com\"",
+    )
+    .await;
+    std::fs::write(dir.path().join("article/episode-one.md"), EPISODE).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/feed/podcast.xml").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/rss+xml");
+    let xml = res.body.as_str().unwrap();
+    assert!(xml.contains("<title>Scribe Radio</title>"));
+    assert!(xml.contains("<itunes:owner><itunes:name>Scribe</itunes:name><itunes:email>radio@example.com</itunes:email></itunes:owner>"));
+    assert!(xml.contains("<description>Pilot &amp; intro</description>"));
+    assert!(xml.contains(
+        "<enclosure url=\"https://cdn.example.com/ep1.mp3\" length=\"0\" type=\"audio/mpeg\"/>"
+    ));
+    assert!(xml.contains("<itunes:duration>1930</itunes:duration>"));
+    assert!(xml.contains("<itunes:episode>1</itunes:episode>"));
+    // Articles without audio are not episodes.
+    assert_eq!(xml.matches("<item>").count(), 1);
+
+    let res = app.get("/api/articles/episode-one").await;
+    assert_eq!(res.body["metadata"]["audio"]["duration"], 1930);
+}
+
+const GALLERY: &str = "---\ntitle: \"Harbour walk\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\nlast_updated: 2024-03-05T00:00:00Z\ndescription: \"Photos\"\ngallery:\n  - src: photos/dawn.jpg\n    caption: \"Harbour at dawn\"\n  - src: photos/dusk.jpg\n---\n\nA walk.\n";
+
+#[tokio::test]
+async fn gallery_posts_list_their_photos_and_flag_missing_ones() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("article/harbour-walk.md"), GALLERY).unwrap();
+    std::fs::create_dir(dir.path().join("article/photos")).unwrap();
+    std::fs::write(dir.path().join("article/photos/dawn.jpg"), "jpeg").unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/harbour-walk").await;
+    assert_eq!(
+        res.body["metadata"]["gallery"][0],
+        json!({ "src": "photos/dawn.jpg", "caption": "Harbour at dawn" })
+    );
+
+    let res = app.get("/feed/updated.xml").await;
+    let xml = res.body.as_str().unwrap();
+    assert_eq!(xml.matches("<media:content").count(), 2);
+    assert!(xml.contains("<media:description>Harbour at dawn</media:description>"));
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/content/lint",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    let lint = res
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["slug"] == "harbour-walk")
+        .unwrap();
+    assert_eq!(lint["missing_gallery_images"], json!(["photos/dusk.jpg"]));
+}
+
+const LINK_POST: &str = "---\ntitle: \"A good read\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\nlast_updated: 2024-03-05T00:00:00Z\ndescription: \"Worth it\"\nlink: https://example.com/essay\n---\n";
+
+#[tokio::test]
+async fn link_posts_point_feeds_at_the_linked_page() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("article/a-good-read.md"), LINK_POST).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/a-good-read").await;
+    assert_eq!(
+        res.body["metadata"]["link"],
+        json!({ "url": "https://example.com/essay" })
+    );
+
+    let res = app.get("/feed/updated.xml").await;
+    let xml = res.body.as_str().unwrap();
+    assert!(xml.contains("<title>→ A good read</title><link>https://example.com/essay</link>"));
+    assert!(xml.contains("/articles/a-good-read</comments>"));
+
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    // Previews never reach internal addresses.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "link": { "url": "http://127.0.0.1/" }, "fetch_link": true })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_GATEWAY);
+    assert_eq!(res.body["error_code"], "ERR_LINK_PREVIEW_FAILED");
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(
+                json!({ "title": "Local notes", "link": "http://127.0.0.1/", "fetch_link": true }),
+            ),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "local-notes");
+    assert_eq!(res.body["warnings"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn unfurl_needs_a_session_and_refuses_internal_urls() {
+    let app = TestApp::spawn().await;
+    let res = app
+        .get("/api/meta/unfurl?url=https://example.com/essay")
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let cookie = app.visitor_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let res = app
+        .request(
+            Method::GET,
+            "/api/meta/unfurl?url=http://169.254.169.254/latest",
+            None,
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    // Cached cards are served without fetching the page again.
+    let card = LinkPreview {
+        url: "https://example.com/essay".to_string(),
+        site_name: Some("Example".to_string()),
+        title: Some("An essay".to_string()),
+        ..Default::default()
+    };
+    app.state
+        .link_previews
+        .insert("https://example.com/essay".to_string(), card)
+        .await;
+    let res = app
+        .request(
+            Method::GET,
+            "/api/meta/unfurl?url=https://example.com/essay%23intro",
+            None,
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["title"], "An essay");
+    assert_eq!(res.body["site_name"], "Example");
+}
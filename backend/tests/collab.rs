@@ -0,0 +1,193 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{ADMIN_TOKEN, TestApp};
+use futures_util::{SinkExt, StreamExt};
+use scribe_backend::services::lock_service::live_locks;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Serves the app on a local port, as upgrades need a real connection.
+async fn serve(app: &TestApp) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = app.router.clone();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("ws://{}", addr)
+}
+
+async fn connect(base: &str, editor: &str) -> Result<Socket, StatusCode> {
+    let url = format!(
+        "{}/api/admin/articles/hello-world/ws?editor={}",
+        base, editor
+    );
+    let mut request = url.into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("authorization", ADMIN_TOKEN.parse().unwrap());
+    match tokio_tungstenite::connect_async(request).await {
+        Ok((socket, _)) => Ok(socket),
+        Err(tokio_tungstenite::tungstenite::Error::Http(res)) => Err(res.status()),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// The next message of type `kind`, skipping others.
+async fn next(socket: &mut Socket, kind: &str) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("no message")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            let message: Value = serde_json::from_str(&text).unwrap();
+            if message["type"] == kind {
+                return message;
+            }
+        }
+    }
+}
+
+async fn send(socket: &mut Socket, message: Value) {
+    socket
+        .send(Message::Text(message.to_string().into()))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn two_sessions_edit_a_draft_together() {
+    let app = TestApp::spawn().await;
+    let base = serve(&app).await;
+
+    let mut laptop = connect(&base, "laptop").await.unwrap();
+    let snapshot = next(&mut laptop, "snapshot").await;
+    assert_eq!(snapshot["revision"], 0);
+    assert!(
+        snapshot["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("Welcome to the fixture blog.")
+    );
+
+    let mut phone = connect(&base, "phone").await.unwrap();
+    let snapshot = next(&mut phone, "snapshot").await;
+    assert_eq!(snapshot["editors"], json!(["laptop", "phone"]));
+    // The laptop is told of its own join first.
+    assert_eq!(
+        next(&mut laptop, "presence").await["editors"],
+        json!(["laptop"])
+    );
+    assert_eq!(
+        next(&mut laptop, "presence").await["editors"],
+        json!(["laptop", "phone"])
+    );
+
+    send(
+        &mut phone,
+        json!({ "type": "patch", "from": 0, "to": 7, "text": "Hello" }),
+    )
+    .await;
+    let patch = next(&mut laptop, "patch").await;
+    assert_eq!(patch["revision"], 1);
+    assert_eq!(patch["editor"], "phone");
+    assert_eq!(patch["text"], "Hello");
+    assert_eq!(next(&mut phone, "patch").await["revision"], 1);
+
+    send(&mut laptop, json!({ "type": "sync" })).await;
+    let snapshot = next(&mut laptop, "snapshot").await;
+    assert!(
+        snapshot["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("Hello to the fixture blog.")
+    );
+    send(&mut laptop, json!({ "type": "rewrite" })).await;
+    assert!(next(&mut laptop, "error").await["message"].is_string());
+
+    // The draft is locked for everyone else while it is edited live.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/lock",
+            Some(json!({ "editor": "desktop" })),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["details"]["locked_by"], "live-session");
+
+    laptop.close(None).await.unwrap();
+    assert_eq!(
+        next(&mut phone, "presence").await["editors"],
+        json!(["phone"])
+    );
+    phone.close(None).await.unwrap();
+    for _ in 0..50 {
+        if live_locks(&app.state.db).await.unwrap().is_empty() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("the lock was not released");
+}
+
+#[tokio::test]
+async fn sessions_holding_the_lock_keep_the_draft() {
+    let app = TestApp::spawn().await;
+    let base = serve(&app).await;
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/hello-world/lock",
+            Some(json!({ "editor": "desktop" })),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    assert_eq!(
+        connect(&base, "laptop").await.unwrap_err(),
+        StatusCode::CONFLICT
+    );
+    // The session holding the lock moves over to live editing.
+    let mut desktop = connect(&base, "desktop").await.unwrap();
+    next(&mut desktop, "snapshot").await;
+}
+
+#[tokio::test]
+async fn browsers_pass_the_admin_token_as_a_subprotocol() {
+    let app = TestApp::spawn().await;
+    let base = serve(&app).await;
+    // The browser `WebSocket` API cannot set headers, only subprotocols:
+    // `new WebSocket(url, ["scribe-admin", token])`.
+    let open = |token: &str| {
+        let mut request = format!("{}/api/admin/articles/hello-world/ws?editor=browser", base)
+            .into_client_request()
+            .unwrap();
+        request.headers_mut().insert(
+            "sec-websocket-protocol",
+            format!("scribe-admin, {}", token).parse().unwrap(),
+        );
+        tokio_tungstenite::connect_async(request)
+    };
+
+    match open("wrong-token").await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(res)) => {
+            assert_eq!(res.status(), StatusCode::FORBIDDEN)
+        }
+        other => panic!("expected a refusal, got {:?}", other.map(|(_, res)| res)),
+    }
+
+    let (mut browser, res) = open(ADMIN_TOKEN).await.unwrap();
+    // Browsers drop connections that accept none of their subprotocols.
+    assert_eq!(res.headers()["sec-websocket-protocol"], "scribe-admin");
+    assert_eq!(next(&mut browser, "snapshot").await["revision"], 0);
+}
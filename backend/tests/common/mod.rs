@@ -0,0 +1,183 @@
+#![allow(dead_code)]
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use cookie::{Cookie, CookieJar, Key};
+use scribe_backend::config::Config;
+use scribe_backend::db::memory_db;
+use scribe_backend::models::user::User;
+use scribe_backend::server::app::AppState;
+use scribe_backend::{ScribeApp, ScribeAppBuilder};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::{Arc, Once};
+use tempfile::TempDir;
+use tower::ServiceExt;
+use walkdir::WalkDir;
+
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+const COOKIE_SECRET: &str = "integration-tests-cookie-secret-that-is-at-least-64-bytes-long!!";
+
+const BASE_CONFIG: &str = r#"
+log_level = "error"
+latest_articles_count = 10
+comments = true
+github_redirect_url = "http://localhost:3000/api/auth/github/callback"
+enable_full_text_search = true
+"#;
+
+static ENV: Once = Once::new();
+
+fn init_env() {
+    ENV.call_once(|| {
+        let hash = hex::encode(Sha256::digest(ADMIN_TOKEN.as_bytes()));
+        // SAFETY: runs once, before any app state is created.
+        unsafe {
+            std::env::set_var("ADMIN_TOKEN_HASH", hash);
+            std::env::set_var("AUTHOR_GITHUB_USERNAME", "author");
+        }
+    });
+}
+
+fn copy_fixtures(target: &Path) {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/content");
+    for entry in WalkDir::new(&source) {
+        let entry = entry.unwrap();
+        let dest = target.join(entry.path().strip_prefix(&source).unwrap());
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest).unwrap();
+        } else {
+            std::fs::copy(entry.path(), &dest).unwrap();
+        }
+    }
+}
+
+/// A [`ScribeApp`] builder over a fresh copy of the fixtures in `dir`, with
+/// extra `config.toml` lines appended to the defaults.
+pub async fn builder(dir: &TempDir, extra_config: &str) -> ScribeAppBuilder {
+    init_env();
+    copy_fixtures(dir.path());
+
+    let config: Config = toml::from_str(&format!("{}\n{}", BASE_CONFIG, extra_config)).unwrap();
+    config.validate().unwrap();
+    ScribeApp::builder(config)
+        .db(memory_db().await)
+        .article_dir(dir.path().join("article"))
+        .notes_dir(dir.path().join("notes"))
+        .pages_dir(dir.path().join("pages"))
+        .data_dir(dir.path().join("data"))
+        .taxonomy_dir(dir.path().join("taxonomy"))
+        .collections_file(dir.path().join("collections.yaml"))
+        .glossary_dir(dir.path().join("glossary"))
+        .search_index_dir(dir.path().join("search_index"))
+        .cookie_key(Key::derive_from(COOKIE_SECRET.as_bytes()))
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// A fully wired app over a temporary copy of the fixture content and an
+/// in-memory database. Requests go straight to the router without binding
+/// a socket, and nothing depends on the working directory, so tests run in
+/// parallel.
+pub struct TestApp {
+    pub router: Router,
+    pub state: Arc<AppState>,
+    pub dir: TempDir,
+}
+
+impl TestApp {
+    pub async fn spawn() -> Self {
+        Self::spawn_with("").await
+    }
+
+    /// Boots the app with extra `config.toml` lines appended to the defaults.
+    pub async fn spawn_with(extra_config: &str) -> Self {
+        let dir = TempDir::new().unwrap();
+        let app = builder(&dir, extra_config).await.build().await.unwrap();
+        Self {
+            router: app.router(),
+            state: app.state(),
+            dir,
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let body = match body {
+            Some(json) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(json.to_string())
+            }
+            None => Body::empty(),
+        };
+        self.send(builder.body(body).unwrap()).await
+    }
+
+    /// Sends a non-JSON body; `headers` should include its content type.
+    pub async fn request_raw(
+        &self,
+        method: Method,
+        uri: &str,
+        body: impl Into<Body>,
+        headers: &[(&str, &str)],
+    ) -> TestResponse {
+        let mut builder = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        self.send(builder.body(body.into()).unwrap()).await
+    }
+
+    async fn send(&self, req: Request<Body>) -> TestResponse {
+        let res = self.router.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let headers = res.headers().clone();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.request(Method::GET, uri, None, &[]).await
+    }
+
+    /// `Cookie` header value carrying a signed session for the given user.
+    pub fn session_cookie(&self, user: &User) -> String {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(&Key::derive_from(COOKIE_SECRET.as_bytes()))
+            .add(Cookie::new(
+                "user_session",
+                serde_json::to_string(user).unwrap(),
+            ));
+        let cookie = jar.get("user_session").unwrap();
+        format!("user_session={}", cookie.value())
+    }
+
+    pub fn author_cookie(&self) -> String {
+        self.session_cookie(&User::new(1, "author".to_string(), true))
+    }
+
+    pub fn visitor_cookie(&self) -> String {
+        self.session_cookie(&User::new(2, "visitor".to_string(), false))
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::{Duration, Utc};
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::services::audit_service::{ARTICLE_DELETED, record_audit};
+use serde_json::json;
+
+fn diff_uri(from: chrono::DateTime<Utc>, to: chrono::DateTime<Utc>) -> String {
+    format!(
+        "/api/admin/content/diff?from={}&to={}",
+        from.format("%Y-%m-%dT%H:%M:%SZ"),
+        to.format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+#[tokio::test]
+async fn summarizes_new_edited_and_deleted_articles() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let start = Utc::now() - Duration::hours(1);
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Fresh Post", "content": "Brand new" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    for (content, message) in [
+        ("Welcome.", "Shorten the intro"),
+        ("Welcome.\nNow with crabs.\n", "Add crabs"),
+    ] {
+        let res = app
+            .request(
+                Method::PUT,
+                "/api/articles/hello-world",
+                Some(json!({ "title": "Hello World", "content": content, "message": message })),
+                &auth,
+            )
+            .await;
+        assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    }
+    record_audit(
+        &app.state.db,
+        ARTICLE_DELETED,
+        "old-news",
+        true,
+        Some("Old News"),
+    )
+    .await
+    .unwrap();
+
+    let end = Utc::now() + Duration::minutes(1);
+    let res = app
+        .request(Method::GET, &diff_uri(start, end), None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let added = res.body["added"].as_array().unwrap();
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0]["slug"], "fresh-post");
+    assert_eq!(added[0]["status"], "published");
+
+    let edited = res.body["edited"].as_array().unwrap();
+    assert_eq!(edited.len(), 1);
+    assert_eq!(edited[0]["slug"], "hello-world");
+    assert_eq!(edited[0]["edits"], 2);
+    // The first saved version has nothing before it to compare against.
+    assert_eq!(edited[0]["lines_added"], 1);
+    assert_eq!(edited[0]["lines_removed"], 0);
+    assert_eq!(
+        edited[0]["messages"],
+        json!(["Shorten the intro", "Add crabs"])
+    );
+
+    assert_eq!(
+        res.body["deleted"],
+        json!([{
+            "slug": "old-news",
+            "title": "Old News",
+            "deleted_at": res.body["deleted"][0]["deleted_at"],
+        }])
+    );
+
+    // Nothing happened in the window before.
+    let res = app
+        .request(
+            Method::GET,
+            &diff_uri(start - Duration::days(1), start),
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    for list in ["added", "edited", "deleted"] {
+        assert_eq!(res.body[list], json!([]), "{}", list);
+    }
+}
+
+#[tokio::test]
+async fn windows_must_be_valid() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+    for uri in [
+        "/api/admin/content/diff?from=yesterday",
+        "/api/admin/content/diff?from=2024-02-01&to=2024-01-01",
+    ] {
+        let res = app.request(Method::GET, uri, None, &admin).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+    let res = app.get("/api/admin/content/diff?from=2024-01-01").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
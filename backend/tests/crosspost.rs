@@ -0,0 +1,257 @@
+mod common;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::services::crosspost_service::{deliver_crosspost, deliver_reminder};
+use scribe_backend::services::evergreen_service::suggest_evergreen_posts;
+use scribe_backend::services::job_queue_service::{complete_job, due_jobs};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+type Received = Arc<Mutex<Vec<(String, Value)>>>;
+
+fn record(received: &Received, headers: &HeaderMap, what: &str, body: Value) {
+    let auth = headers
+        .get("authorization")
+        .map_or("", |v| v.to_str().unwrap());
+    let key = headers
+        .get("idempotency-key")
+        .map_or("", |v| v.to_str().unwrap());
+    received.lock().unwrap().push((
+        format!("{} {} {}", what, auth, key).trim().to_string(),
+        body,
+    ));
+}
+
+/// Serves the Mastodon and Bluesky endpoints cross-posting uses, recording
+/// each request with its credentials, and returns the base URL.
+async fn spawn_social(received: Received) -> String {
+    let app = Router::new()
+        .route(
+            "/api/v1/statuses",
+            post(
+                |State(received): State<Received>, headers: HeaderMap, Json(body): Json<Value>| async move {
+                    record(&received, &headers, "status", body);
+                    Json(json!({ "id": "1", "url": "https://social.example/@blog/1" }))
+                },
+            ),
+        )
+        .route(
+            "/xrpc/com.atproto.server.createSession",
+            post(
+                |State(received): State<Received>, headers: HeaderMap, Json(body): Json<Value>| async move {
+                    record(&received, &headers, "session", body);
+                    Json(json!({ "accessJwt": "jwt", "did": "did:plc:blog" }))
+                },
+            ),
+        )
+        .route(
+            "/xrpc/com.atproto.repo.createRecord",
+            post(
+                |State(received): State<Received>, headers: HeaderMap, Json(body): Json<Value>| async move {
+                    record(&received, &headers, "record", body);
+                    Json(json!({ "uri": "at://did:plc:blog/app.bsky.feed.post/3kabc", "cid": "c" }))
+                },
+            ),
+        )
+        .with_state(received);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+async fn queued_crossposts(app: &TestApp) -> Vec<Value> {
+    queued_jobs(app, "crosspost").await
+}
+
+async fn queued_jobs(app: &TestApp, job_type: &str) -> Vec<Value> {
+    let jobs = due_jobs(&app.state.db, i64::MAX, 20).await.unwrap();
+    let mut payloads = Vec::new();
+    for job in jobs.iter().filter(|job| job.job_type == job_type) {
+        payloads.push(serde_json::from_str(&job.payload).unwrap());
+        complete_job(&app.state.db, job.id).await.unwrap();
+    }
+    payloads
+}
+
+#[tokio::test]
+async fn published_articles_are_cross_posted_once_per_account() {
+    let received = Received::default();
+    let base = spawn_social(Arc::clone(&received)).await;
+    let app = TestApp::spawn_with(&format!(
+        "hostname = \"https://blog.example\"\n\n[http]\nretries = 0\n\n[crosspost.mastodon]\ninstance = \"{base}\"\ntoken_env = \"SCRIBE_TEST_MASTODON_TOKEN\"\nvisibility = \"unlisted\"\n\n[crosspost.bluesky]\nhandle = \"blog.example\"\nservice = \"{base}\"\napp_password_env = \"SCRIBE_TEST_BLUESKY_PASSWORD\""
+    ))
+    .await;
+    unsafe { std::env::set_var("SCRIBE_TEST_MASTODON_TOKEN", "masto") };
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Kept quiet", "content": "Soon", "draft": true })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(queued_crossposts(&app).await.is_empty());
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Café notes", "content": "Hi" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let jobs = queued_crossposts(&app).await;
+    assert_eq!(jobs, [json!({ "slug": "cafe-notes" })]);
+
+    // Without its app password, Bluesky fails and Mastodon still gets the post.
+    let err = deliver_crosspost(Arc::clone(&app.state), jobs[0].clone())
+        .await
+        .unwrap_err();
+    assert!(
+        err.contains("SCRIBE_TEST_BLUESKY_PASSWORD is not set"),
+        "{}",
+        err
+    );
+    let res = app.get("/api/articles/cafe-notes").await;
+    assert_eq!(
+        res.body["metadata"]["syndication"],
+        json!({ "mastodon": "https://social.example/@blog/1" })
+    );
+
+    // The retry only posts where it failed.
+    unsafe { std::env::set_var("SCRIBE_TEST_BLUESKY_PASSWORD", "app-pass") };
+    deliver_crosspost(Arc::clone(&app.state), jobs[0].clone())
+        .await
+        .unwrap();
+    let res = app.get("/api/articles/cafe-notes").await;
+    assert_eq!(
+        res.body["metadata"]["syndication"],
+        json!({
+            "mastodon": "https://social.example/@blog/1",
+            "bluesky": "https://bsky.app/profile/blog.example/post/3kabc",
+        })
+    );
+
+    let received = received.lock().unwrap().clone();
+    let what: Vec<&str> = received.iter().map(|(what, _)| what.as_str()).collect();
+    assert_eq!(
+        what,
+        [
+            "status Bearer masto scribe-cafe-notes",
+            "session",
+            "record Bearer jwt"
+        ]
+    );
+    let url = "https://blog.example/articles/cafe-notes";
+    assert_eq!(
+        received[0].1,
+        json!({ "status": format!("Café notes\n\n{}", url), "visibility": "unlisted" })
+    );
+    assert_eq!(
+        received[1].1,
+        json!({ "identifier": "blog.example", "password": "app-pass" })
+    );
+    let post = &received[2].1;
+    assert_eq!(post["repo"], "did:plc:blog");
+    let facet = &post["record"]["facets"][0]["index"];
+    // "Café notes\n\n" is 13 bytes.
+    assert_eq!(
+        facet,
+        &json!({ "byteStart": 13, "byteEnd": 13 + url.len() })
+    );
+    assert_eq!(post["record"]["embed"]["external"]["uri"], url);
+}
+
+#[tokio::test]
+async fn evergreen_reminders_wait_for_approval() {
+    let received = Received::default();
+    let base = spawn_social(Arc::clone(&received)).await;
+    let app = TestApp::spawn_with(&format!(
+        "hostname = \"https://blog.example\"\n\n[http]\nretries = 0\n\n[crosspost.mastodon]\ninstance = \"{base}\"\ntoken_env = \"SCRIBE_TEST_EVERGREEN_TOKEN\"\n\n[crosspost.evergreen]\nmin_age_days = 30"
+    ))
+    .await;
+    unsafe { std::env::set_var("SCRIBE_TEST_EVERGREEN_TOKEN", "masto") };
+    for _ in 0..3 {
+        sqlx::query("INSERT INTO article_views (slug) VALUES ('hello-world')")
+            .execute(&app.state.db)
+            .await
+            .unwrap();
+    }
+
+    let picked = suggest_evergreen_posts(&app.state).await.unwrap();
+    assert_eq!(picked.len(), 1);
+    assert_eq!(picked[0].article_slug, "hello-world");
+    assert_eq!(picked[0].views, 3);
+    // Picked articles rest for `cooldown_days`.
+    assert!(
+        suggest_evergreen_posts(&app.state)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(Method::GET, "/api/admin/evergreen", None, &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(Method::GET, "/api/admin/evergreen", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    assert!(queued_jobs(&app, "crosspost_reminder").await.is_empty());
+
+    let id = picked[0].id;
+    let res = app
+        .request(
+            Method::POST,
+            &format!("/api/admin/evergreen/{}/approve", id),
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["status"], "approved");
+    let jobs = queued_jobs(&app, "crosspost_reminder").await;
+    assert_eq!(
+        jobs,
+        [json!({ "reminder": id, "slug": "hello-world", "service": "mastodon" })]
+    );
+    deliver_reminder(Arc::clone(&app.state), jobs[0].clone())
+        .await
+        .unwrap();
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(
+        received[0].0,
+        format!("status Bearer masto scribe-hello-world-reminder-{}", id)
+    );
+    let status = received[0].1["status"].as_str().unwrap();
+    assert!(
+        status.starts_with("From the archive: Hello World\n\n"),
+        "{}",
+        status
+    );
+
+    let res = app
+        .request(
+            Method::POST,
+            &format!("/api/admin/evergreen/{}/dismiss", id),
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_REMINDER_NOT_FOUND");
+}
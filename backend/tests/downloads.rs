@@ -0,0 +1,289 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use scribe_backend::services::asset_service::{ASSET_DIR, store_asset};
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn downloads_markdown_with_or_without_front_matter() {
+    let app = TestApp::spawn().await;
+
+    let res = app.get("/api/articles/hello-world/download").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "text/markdown; charset=utf-8");
+    assert_eq!(
+        res.headers["content-disposition"],
+        "attachment; filename=\"hello-world.md\""
+    );
+    let body = res.body.as_str().unwrap();
+    assert!(body.starts_with("---\ntitle: \"Hello World\""));
+
+    let res = app
+        .get("/api/articles/hello-world/download?format=md&front_matter=false")
+        .await;
+    let body = res.body.as_str().unwrap();
+    assert!(!body.contains("title:"));
+    assert!(body.contains("ferris the crab"));
+
+    let res = app.get("/api/articles/unpublished/download").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = app
+        .get("/api/articles/hello-world/download?format=docx")
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let res = app
+        .get("/api/articles/hello-world/download?format=pdf")
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_PDF_DISABLED");
+}
+
+#[tokio::test]
+async fn text_endpoint_returns_plain_text() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/articles/hello-world/text").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "text/plain; charset=utf-8");
+    let text = res.body.as_str().unwrap();
+    assert!(text.starts_with("Hello World\n\n"));
+    assert!(text.contains("ferris the crab"));
+    assert!(!text.contains("title:"));
+
+    let res = app.get("/api/articles/unpublished/text").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bundle_is_a_zip_download() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/articles/hello-world/bundle.zip").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/zip");
+    assert_eq!(
+        res.headers["content-disposition"],
+        "attachment; filename=\"hello-world.zip\""
+    );
+    assert!(res.body.as_str().unwrap().starts_with("PK"));
+
+    let res = app.get("/api/articles/unpublished/bundle.zip").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn print_format_is_a_standalone_page() {
+    let app = TestApp::spawn_with("hostname = \"https://blog.example\"").await;
+    let res = app
+        .get("/api/articles/hello-world/download?format=print")
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "text/html; charset=utf-8");
+    assert_eq!(
+        res.headers["content-disposition"],
+        "inline; filename=\"hello-world.html\""
+    );
+    let html = res.body.as_str().unwrap();
+    assert!(html.contains("<style>"));
+    assert!(html.contains("href=\"https://blog.example/articles/hello-world\""));
+    assert!(html.contains("<a href=\"https://blog.example/glossary#ferris\">ferris</a> the crab"));
+}
+
+#[tokio::test]
+async fn pdf_is_rendered_by_the_configured_command() {
+    // `cat` echoes the HTML document back in place of a real converter.
+    let app = TestApp::spawn_with("[pdf]\ncommand = [\"cat\"]").await;
+    let res = app
+        .get("/api/articles/hello-world/download?format=pdf")
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/pdf");
+    assert_eq!(
+        res.headers["content-disposition"],
+        "attachment; filename=\"hello-world.pdf\""
+    );
+    let html = res.body.as_str().unwrap();
+    assert!(html.contains("<h1>Hello World</h1>"));
+    assert!(html.contains("ferris</a> the crab"));
+
+    let app = TestApp::spawn_with("[pdf]\ncommand = [\"false\"]").await;
+    let res = app
+        .get("/api/articles/hello-world/download?format=pdf")
+        .await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.body["error_code"], "ERR_PDF_RENDER_FAILED");
+}
+
+const CITING: &str = "---\ntitle: \"Citing\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\ndescription: \"With references\"\nreferences:\n  - id: knuth84\n    title: Literate Programming\n    authors: [Donald E. Knuth]\n    year: 1984\n---\n\nAs argued [@sicp; @knuth84].\n";
+
+const CITING_BIB: &str = "@book{sicp,\n  title = {Structure and Interpretation of Computer Programs},\n  publisher = {MIT Press},\n}\n@misc{knuth84, title = {Shadowed by the front matter}}\n";
+
+#[tokio::test]
+async fn citations_use_front_matter_and_bibtex_references() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("article/citing.md"), CITING).unwrap();
+    std::fs::write(dir.path().join("article/citing.bib"), CITING_BIB).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/citing").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body["metadata"]["references"],
+        json!([
+            {
+                "id": "knuth84",
+                "title": "Literate Programming",
+                "authors": ["Donald E. Knuth"],
+                "year": 1984
+            },
+            {
+                "id": "sicp",
+                "title": "Structure and Interpretation of Computer Programs",
+                "venue": "MIT Press"
+            }
+        ])
+    );
+
+    let res = app.get("/api/articles/citing/download?format=print").await;
+    let html = res.body.as_str().unwrap();
+    assert!(html.contains(
+        "<sup class=\"citation\">[<a href=\"#ref-1\">1</a>, <a href=\"#ref-2\">2</a>]</sup>"
+    ));
+    assert!(html.contains(
+        "<li id=\"ref-1\">Structure and Interpretation of Computer Programs. <em>MIT Press</em>.</li>"
+    ));
+    assert!(html.contains("<li id=\"ref-2\">Donald E. Knuth. Literate Programming. 1984.</li>"));
+}
+
+const SNIPPETS: &str = "---\ntitle: \"Snippets\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\ndescription: \"With code\"\n---\n\nSetup:\n\n```toml\n[dependencies]\naxum = \"0.8\"\n```\n\nThen:\n\n```\ncargo run\n```\n";
+
+#[tokio::test]
+async fn snippets_list_fenced_code_blocks() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("article/snippets.md"), SNIPPETS).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/snippets/snippets").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body,
+        json!([
+            {
+                "anchor": "snippet-1",
+                "language": "toml",
+                "start_line": 4,
+                "end_line": 5,
+                "code": "[dependencies]\naxum = \"0.8\"\n"
+            },
+            {
+                "anchor": "snippet-2",
+                "language": null,
+                "start_line": 11,
+                "end_line": 11,
+                "code": "cargo run\n"
+            }
+        ])
+    );
+
+    let res = app.get("/api/articles/unpublished/snippets").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn attached_files_are_counted_and_may_need_a_sign_in() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    let asset_dir = dir.path().join("data").join(ASSET_DIR);
+    let pdf = store_asset(&asset_dir, b"%PDF-1.7 guide", "pdf").unwrap();
+    let zip = store_asset(&asset_dir, b"PK code", "zip").unwrap();
+    let article = format!(
+        "---\ntitle: \"Guide\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\n\
+         description: \"Lead magnet\"\ndownloads:\n  - name: guide.pdf\n    file: {}\n\
+         \x20   title: The guide\n  - name: code.zip\n    file: {}\n    login_required: true\n\
+         \x20 - name: missing.pdf\n    file: /api/assets/{}.pdf\n---\n\nGet the guide.\n",
+        pdf.url,
+        zip.url,
+        "0".repeat(64)
+    );
+    std::fs::write(dir.path().join("article/guide.md"), article).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/guide/downloads/guide.pdf").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.headers["content-type"], "application/pdf");
+    assert_eq!(
+        res.headers["content-disposition"],
+        "attachment; filename=\"guide.pdf\""
+    );
+    assert_eq!(res.body, "%PDF-1.7 guide");
+
+    let res = app.get("/api/articles/guide/downloads/code.zip").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let cookie = app.visitor_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/articles/guide/downloads/code.zip",
+            None,
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers["content-type"], "application/zip");
+    assert_eq!(res.headers["cache-control"], "private, no-store");
+
+    for name in ["missing.pdf", "other.pdf"] {
+        let res = app
+            .get(&format!("/api/articles/guide/downloads/{}", name))
+            .await;
+        assert_eq!(res.status, StatusCode::NOT_FOUND);
+        assert_eq!(res.body["error_code"], "ERR_ATTACHMENT_NOT_FOUND");
+    }
+
+    let res = app.get("/api/articles/guide/downloads").await;
+    assert_eq!(
+        res.body,
+        json!([
+            {
+                "name": "guide.pdf",
+                "title": "The guide",
+                "url": "/api/articles/guide/downloads/guide.pdf",
+                "content_type": "application/pdf",
+                "size": 14,
+                "downloads": 1
+            },
+            {
+                "name": "code.zip",
+                "url": "/api/articles/guide/downloads/code.zip",
+                "content_type": "application/zip",
+                "size": 7,
+                "login_required": true,
+                "downloads": 1
+            },
+            {
+                "name": "missing.pdf",
+                "url": "/api/articles/guide/downloads/missing.pdf",
+                "content_type": "application/pdf",
+                "size": null,
+                "downloads": 0
+            }
+        ])
+    );
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use common::TestApp;
+use scribe_backend::server::app::AppState;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+async fn article_count(State(state): State<Arc<AppState>>) -> String {
+    let store = state.store.read().await;
+    store.query(|_| true, 0, usize::MAX).count().to_string()
+}
+
+#[tokio::test]
+async fn mounts_under_a_host_router_with_custom_routes() {
+    let dir = TempDir::new().unwrap();
+    let scribe = common::builder(&dir, "")
+        .await
+        .routes(Router::new().route("/api/article-count", get(article_count)))
+        .build()
+        .await
+        .unwrap();
+    assert!(scribe.search_service().is_some());
+    let notes = scribe.note_store();
+    assert!(notes.read().await.get_by_slug("til").is_some());
+
+    let app = TestApp {
+        router: Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .nest_service("/blog", scribe.router()),
+        state: scribe.state(),
+        dir,
+    };
+
+    assert_eq!(app.get("/health").await.body, "ok");
+
+    let res = app.get("/blog/api/articles").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["articles"].as_array().unwrap().len(), 2);
+
+    // Custom routes share the state and the versioning layer.
+    assert_eq!(app.get("/blog/api/article-count").await.body, 3);
+    let res = app.get("/blog/api/v1/article-count").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.headers.get("deprecation").is_none());
+
+    let res = app.get("/api/articles").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+/// Collects the events posted to a Sentry-style store endpoint for project
+/// 42. Returns the base URL.
+async fn spawn_error_store(events: Arc<Mutex<Vec<(String, Value)>>>) -> String {
+    let app = Router::new()
+        .route(
+            "/api/42/store/",
+            post(
+                |State(events): State<Arc<Mutex<Vec<(String, Value)>>>>,
+                 headers: HeaderMap,
+                 Json(event): Json<Value>| async move {
+                    let auth = headers["x-sentry-auth"].to_str().unwrap().to_string();
+                    events.lock().unwrap().push((auth, event));
+                },
+            ),
+        )
+        .with_state(events);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("{}", addr)
+}
+
+#[tokio::test]
+async fn panics_become_structured_errors_and_are_reported() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let addr = spawn_error_store(Arc::clone(&events)).await;
+    let dir = TempDir::new().unwrap();
+    let config =
+        format!("[error_reporting]\ndsn = \"http://public-key@{addr}/42\"\nenvironment = \"test\"");
+    let scribe = common::builder(&dir, &config)
+        .await
+        .routes(Router::new().route(
+            "/api/explode",
+            get(|| async {
+                if true {
+                    panic!("boom");
+                }
+            }),
+        ))
+        .build()
+        .await
+        .unwrap();
+    let app = TestApp {
+        router: scribe.router(),
+        state: scribe.state(),
+        dir,
+    };
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/explode",
+            None,
+            &[("x-request-id", "req-7")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.body["error_code"], "ERR_INTERNAL_SERVER");
+    assert_eq!(res.body["request_id"], "req-7");
+    assert!(!res.body.to_string().contains("boom"));
+
+    let res = app.get("/api/v1/explode").await;
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(res.body["error"]["code"], "ERR_INTERNAL_SERVER");
+    assert!(res.body["meta"]["request_id"].is_string());
+
+    // The server keeps answering after a panic.
+    assert_eq!(app.get("/api/articles").await.status, StatusCode::OK);
+    assert_eq!(
+        app.state.error_log.recent()[1].request_id.as_deref(),
+        Some("req-7")
+    );
+
+    for _ in 0..100 {
+        if events.lock().unwrap().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    let (auth, event) = events
+        .iter()
+        .find(|(_, event)| event["tags"]["request_id"] == "req-7")
+        .unwrap();
+    assert!(auth.starts_with("Sentry sentry_version=7"));
+    assert!(auth.ends_with("sentry_key=public-key"));
+    assert_eq!(event["level"], "fatal");
+    assert_eq!(event["message"]["formatted"], "boom");
+    assert_eq!(event["environment"], "test");
+    assert_eq!(event["tags"]["kind"], "panic");
+    assert_eq!(event["request"]["method"], "GET");
+    assert!(
+        event["request"]["url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/api/explode")
+    );
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use axum::extract::{Path, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use common::TestApp;
+use scribe_backend::services::fediverse_service::refresh_fediverse_replies;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tempfile::TempDir;
+
+/// Serves the thread API of a Mastodon instance that is out of requests
+/// after the first, counting requests, and returns its base URL.
+async fn spawn_instance(hits: Arc<AtomicUsize>) -> String {
+    let app = Router::new()
+        .route(
+            "/api/v1/statuses/{id}/context",
+            get(
+                |State(hits): State<Arc<AtomicUsize>>, Path(id): Path<String>| async move {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    let account = json!({
+                        "acct": "reader@social.example",
+                        "display_name": "",
+                        "url": "https://social.example/@reader",
+                        "avatar": "https://social.example/avatar.png",
+                    });
+                    let context = json!({
+                        "ancestors": [],
+                        "descendants": [
+                            {
+                                "id": "201",
+                                "uri": "https://social.example/statuses/201",
+                                "url": "https://social.example/@reader/201",
+                                "in_reply_to_id": id,
+                                "visibility": "public",
+                                "content": "<p>Nice post<script>alert(1)</script></p>",
+                                "created_at": "2024-03-02T10:00:00.000Z",
+                                "account": account,
+                            },
+                            {
+                                "id": "202",
+                                "uri": "https://social.example/statuses/202",
+                                "url": null,
+                                "in_reply_to_id": id,
+                                "visibility": "direct",
+                                "content": "<p>Just for you</p>",
+                                "created_at": "2024-03-02T11:00:00.000Z",
+                                "account": account,
+                            }
+                        ],
+                    });
+                    (
+                        [
+                            ("x-ratelimit-remaining", "0"),
+                            ("x-ratelimit-reset", "2999-01-01T00:00:00Z"),
+                        ],
+                        Json(context),
+                    )
+                        .into_response()
+                },
+            ),
+        )
+        .with_state(hits);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn mastodon_replies_are_fetched_stored_and_rate_limited() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let base = spawn_instance(Arc::clone(&hits)).await;
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "").await;
+    std::fs::write(
+        dir.path().join("article/discussed.md"),
+        format!(
+            "---\ntitle: \"Discussed\"\nauthor: \"Scribe\"\ndate: 2024-03-01T00:00:00Z\ndescription: \"\"\nsyndication:\n  mastodon: {base}/@blog/109\n---\n\nBody.\n"
+        ),
+    )
+    .unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+
+    let res = app.get("/api/articles/discussed/fediverse-replies").await;
+    assert_eq!(res.status, 200, "{}", res.body);
+    assert_eq!(res.body["post_url"], format!("{base}/@blog/109"));
+    assert!(res.body["fetched_at"].is_string());
+    assert_eq!(
+        res.body["replies"],
+        json!([{
+            "id": "201",
+            "url": "https://social.example/@reader/201",
+            "in_reply_to_id": "109",
+            "author_acct": "reader@social.example",
+            "author_name": "reader@social.example",
+            "author_url": "https://social.example/@reader",
+            "author_avatar": "https://social.example/avatar.png",
+            "content": "<p>Nice post</p>",
+            "created_at": "2024-03-02 10:00:00",
+        }])
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // Fresh threads are served as stored.
+    let res = app.get("/api/articles/discussed/fediverse-replies").await;
+    assert_eq!(res.body["replies"].as_array().unwrap().len(), 1);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // The instance said it is out of requests, so the job leaves it alone.
+    assert_eq!(refresh_fediverse_replies(&app.state).await, Ok(0));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let res = app.get("/api/articles/hello-world/fediverse-replies").await;
+    assert_eq!(
+        res.body,
+        json!({ "post_url": null, "fetched_at": null, "replies": [] })
+    );
+    let res = app.get("/api/articles/missing/fediverse-replies").await;
+    assert_eq!(res.status, 404);
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use axum::Json;
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::handlers::users::fetch_github_profile;
+use scribe_backend::services::sponsor_service::refresh_sponsors;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct GitHub {
+    requests: Vec<Option<String>>,
+    exhausted: bool,
+}
+
+type Shared = Arc<Mutex<GitHub>>;
+
+/// Answers `GET /user` like GitHub: with an ETag that revalidates to 304,
+/// or with 403 and an empty quota once `exhausted`. Records the
+/// `If-None-Match` of each request. Returns the base URL.
+async fn spawn_github(github: Shared) -> String {
+    let app = Router::new()
+        .route(
+            "/user",
+            get(
+                |State(github): State<Shared>, headers: HeaderMap| async move {
+                    let mut github = github.lock().unwrap();
+                    let etag = headers
+                        .get(header::IF_NONE_MATCH)
+                        .map(|v| v.to_str().unwrap().to_string());
+                    github.requests.push(etag.clone());
+                    let reset = (chrono::Utc::now().timestamp() + 3600).to_string();
+                    let remaining = if github.exhausted { "0" } else { "59" };
+                    let quota = [
+                        ("x-ratelimit-limit", "60".to_string()),
+                        ("x-ratelimit-remaining", remaining.to_string()),
+                        ("x-ratelimit-reset", reset),
+                    ];
+                    if github.exhausted {
+                        (StatusCode::FORBIDDEN, quota, "rate limited").into_response()
+                    } else if etag.as_deref() == Some("\"v1\"") {
+                        (StatusCode::NOT_MODIFIED, quota).into_response()
+                    } else {
+                        let profile = json!({ "id": 1, "login": "octocat", "name": "The Octocat" });
+                        (quota, [(header::ETAG, "\"v1\"")], profile.to_string()).into_response()
+                    }
+                },
+            ),
+        )
+        .with_state(github);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn profiles_are_revalidated_and_served_from_cache_while_rate_limited() {
+    let github = Shared::default();
+    let base = spawn_github(Arc::clone(&github)).await;
+    let app = TestApp::spawn_with(&format!("github_api_url = \"{base}\"")).await;
+
+    let profile = fetch_github_profile(&app.state, "token-a").await.unwrap();
+    assert_eq!(profile.login, "octocat");
+    let profile = fetch_github_profile(&app.state, "token-a").await.unwrap();
+    assert_eq!(profile.name.as_deref(), Some("The Octocat"));
+    assert_eq!(
+        github.lock().unwrap().requests,
+        [None, Some("\"v1\"".to_string())]
+    );
+    assert_eq!(app.state.github_rate_limit.status().remaining, Some(59));
+
+    // Once GitHub refuses, nothing more is asked until the quota resets.
+    github.lock().unwrap().exhausted = true;
+    let err = fetch_github_profile(&app.state, "token-b")
+        .await
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("GitHubRateLimited"));
+    let err = fetch_github_profile(&app.state, "token-b")
+        .await
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("GitHubRateLimited"));
+    let profile = fetch_github_profile(&app.state, "token-a").await.unwrap();
+    assert_eq!(profile.login, "octocat");
+    assert_eq!(github.lock().unwrap().requests.len(), 3);
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/overview",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["github_rate_limit"]["limit"], 60);
+    assert_eq!(res.body["github_rate_limit"]["remaining"], 0);
+}
+
+/// Answers the sponsorships query over two pages, counting the requests.
+/// Returns the base URL.
+async fn spawn_sponsors(requests: Arc<Mutex<usize>>) -> String {
+    let app = Router::new()
+        .route(
+            "/graphql",
+            post(
+                |State(requests): State<Arc<Mutex<usize>>>,
+                 headers: HeaderMap,
+                 Json(query): Json<Value>| async move {
+                    *requests.lock().unwrap() += 1;
+                    assert_eq!(headers[header::AUTHORIZATION], "Bearer sponsors-token");
+                    assert_eq!(query["variables"]["login"], "author");
+                    let sponsorship = |login: &str, amount: u32, private: bool| {
+                        json!({
+                            "createdAt": "2024-01-01T00:00:00Z",
+                            "privacyLevel": if private { "PRIVATE" } else { "PUBLIC" },
+                            "tier": { "name": format!("${amount} a month"), "monthlyPriceInDollars": amount },
+                            "sponsorEntity": { "login": login, "name": null, "avatarUrl": null, "url": null }
+                        })
+                    };
+                    let (nodes, next) = if query["variables"]["after"].is_null() {
+                        (vec![sponsorship("small", 5, false), sponsorship("hidden", 50, true)], true)
+                    } else {
+                        (vec![sponsorship("large", 25, false)], false)
+                    };
+                    Json(json!({ "data": { "user": { "sponsorshipsAsMaintainer": {
+                        "pageInfo": { "hasNextPage": next, "endCursor": "page-2" },
+                        "nodes": nodes
+                    } } } }))
+                },
+            ),
+        )
+        .with_state(requests);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn sponsors_combine_github_and_the_sponsors_file_and_are_cached() {
+    let app = TestApp::spawn().await;
+    let res = app.get("/api/sponsors").await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_SPONSORS_DISABLED");
+
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("TEST_SPONSORS_TOKEN", "sponsors-token") };
+    let requests = Arc::new(Mutex::new(0));
+    let base = spawn_sponsors(Arc::clone(&requests)).await;
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(
+        file.path(),
+        "- name: Acme\n  url: https://acme.example\n  tier: Gold\n",
+    )
+    .unwrap();
+    let app = TestApp::spawn_with(&format!(
+        "github_api_url = \"{base}\"\n[sponsors]\ngithub_login = \"author\"\n\
+         token_env = \"TEST_SPONSORS_TOKEN\"\nfile = \"{}\"",
+        file.path().display()
+    ))
+    .await;
+
+    let res = app.get("/api/sponsors").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let names: Vec<&str> = res.body["sponsors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["large", "small", "Acme"]);
+    assert_eq!(res.body["sponsors"][0]["tier"], "$25 a month");
+    assert_eq!(res.body["sponsors"][2]["url"], "https://acme.example");
+    assert_eq!(*requests.lock().unwrap(), 2);
+
+    app.get("/api/sponsors").await;
+    assert_eq!(*requests.lock().unwrap(), 2);
+    assert_eq!(refresh_sponsors(&app.state).await.unwrap(), 3);
+    assert_eq!(*requests.lock().unwrap(), 4);
+}
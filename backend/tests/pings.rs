@@ -0,0 +1,185 @@
+mod common;
+
+use axum::Router;
+use axum::extract::{OriginalUri, State};
+use axum::http::{Method, StatusCode};
+use axum::routing::any;
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::services::job_queue_service::{complete_job, due_jobs};
+use scribe_backend::services::ping_service::deliver_ping;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+type Received = Arc<Mutex<Vec<String>>>;
+
+/// Serves endpoints that record each request as `METHOD uri body`. Those
+/// under `/down` answer 503. Returns the base URL.
+async fn spawn_endpoints(received: Received) -> String {
+    let app = Router::new()
+        .fallback(any(
+            |State(received): State<Received>,
+             method: Method,
+             OriginalUri(uri): OriginalUri,
+             body: String| async move {
+                let down = uri.path().starts_with("/down");
+                received
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {} {}", method, uri, body));
+                if down {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::NO_CONTENT
+                }
+            },
+        ))
+        .with_state(received);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+async fn run_queued_pings(app: &TestApp) -> usize {
+    let jobs = due_jobs(&app.state.db, i64::MAX, 20).await.unwrap();
+    for job in &jobs {
+        assert_eq!(job.job_type, "ping");
+        let payload: Value = serde_json::from_str(&job.payload).unwrap();
+        let _ = deliver_ping(Arc::clone(&app.state), payload).await;
+        complete_job(&app.state.db, job.id).await.unwrap();
+    }
+    jobs.len()
+}
+
+#[tokio::test]
+async fn publishing_pings_search_engines_and_the_websub_hub() {
+    let received = Received::default();
+    let base = spawn_endpoints(Arc::clone(&received)).await;
+    let app = TestApp::spawn_with(&format!(
+        "hostname = \"https://blog.example\"\n\n[http]\nretries = 1\nretry_backoff_ms = 10\n\n[pings]\nsitemap_endpoints = [\"{base}/ping\", \"{base}/down/ping\"]\nwebsub_hub = \"{base}/hub\"\nwebsub_topics = [\"https://blog.example/feed.xml\"]"
+    ))
+    .await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    // Drafts are not announced.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Pinged later", "content": "Soon", "draft": true })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(run_queued_pings(&app).await, 0);
+
+    // Publishing the draft announces it once.
+    let publish = json!({ "title": "Pinged later", "content": "Now", "draft": false });
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/pinged-later",
+            Some(publish.clone()),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(run_queued_pings(&app).await, 3);
+    let res = app
+        .request(
+            Method::PUT,
+            "/api/articles/pinged-later",
+            Some(publish),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(run_queued_pings(&app).await, 0);
+
+    let mut received = received.lock().unwrap().clone();
+    received.sort();
+    // The endpoint that is down is retried once before the job gives up.
+    assert_eq!(
+        received,
+        [
+            "GET /down/ping?sitemap=https%3A%2F%2Fblog.example%2Fsitemap.xml ",
+            "GET /down/ping?sitemap=https%3A%2F%2Fblog.example%2Fsitemap.xml ",
+            "GET /ping?sitemap=https%3A%2F%2Fblog.example%2Fsitemap.xml ",
+            "POST /hub hub.mode=publish&hub.url=https%3A%2F%2Fblog.example%2Ffeed.xml",
+        ]
+    );
+
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(Method::GET, "/api/admin/audit", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let entries = res.body.as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    let failed: Vec<_> = entries
+        .iter()
+        .filter(|e| e["succeeded"] == false)
+        .map(|e| e["target"].as_str().unwrap())
+        .collect();
+    assert_eq!(failed, [format!("{base}/down/ping")]);
+    assert!(
+        entries
+            .iter()
+            .all(|e| e["detail"].as_str().unwrap().contains("for pinged-later"))
+    );
+
+    let res = app
+        .request(
+            Method::GET,
+            "/api/admin/audit?action=websub_ping",
+            None,
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    assert_eq!(res.body[0]["target"], format!("{base}/hub"));
+
+    let res = app
+        .request(Method::GET, "/api/admin/audit", None, &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    // Articles published when they are created are announced right away.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Out at once", "content": "Hi" })),
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(run_queued_pings(&app).await, 3);
+}
+
+#[tokio::test]
+async fn pings_go_through_the_configured_proxy() {
+    let received = Received::default();
+    let proxy = spawn_endpoints(Arc::clone(&received)).await;
+    let app = TestApp::spawn_with(&format!(
+        "hostname = \"https://blog.example\"\n\n[http]\nproxy = \"{proxy}\"\n\n[pings]\nsitemap_endpoints = [\"http://search.invalid/ping\"]"
+    ))
+    .await;
+    let cookie = app.author_cookie();
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles",
+            Some(json!({ "title": "Behind a proxy", "content": "Hi" })),
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(run_queued_pings(&app).await, 1);
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        ["GET http://search.invalid/ping?sitemap=https%3A%2F%2Fblog.example%2Fsitemap.xml "]
+    );
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use scribe_backend::services::note_crypto::NoteCipher;
+use tempfile::TempDir;
+
+const DIARY: &str = "---\ntitle: \"Diary\"\nauthor: \"author\"\ndate: 2024-03-01T00:00:00Z\ndescription: \"Dear diary\"\nprivate: true\n---\n\nNothing happened today.\n";
+
+async fn spawn(cipher: Option<NoteCipher>) -> TestApp {
+    let dir = TempDir::new().unwrap();
+    let mut builder = common::builder(&dir, "").await;
+    std::fs::write(dir.path().join("notes/diary.md"), DIARY).unwrap();
+    if let Some(cipher) = cipher {
+        builder = builder.note_cipher(cipher);
+    }
+    let app = builder.build().await.unwrap();
+    TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    }
+}
+
+#[tokio::test]
+async fn private_notes_are_encrypted_and_author_only() {
+    let app = spawn(Some(NoteCipher::new(&[42; 32]))).await;
+
+    let on_disk = std::fs::read_to_string(app.dir.path().join("notes/diary.md")).unwrap();
+    assert!(on_disk.contains("private: true"));
+    assert!(!on_disk.contains("Nothing happened"));
+
+    let res = app.get("/api/notes").await;
+    let slugs: Vec<_> = res.body["articles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["slug"].as_str().unwrap())
+        .collect();
+    assert_eq!(slugs, ["til"]);
+    assert_eq!(
+        app.get("/api/notes/diary").await.status,
+        StatusCode::NOT_FOUND
+    );
+    let res = app.get("/api/search?q=happened").await;
+    assert_eq!(res.body["results"].as_array().unwrap().len(), 0);
+
+    assert_eq!(
+        app.get("/api/notes/private/diary").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    let visitor = app.visitor_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/notes/private/diary",
+            None,
+            &[("cookie", &visitor)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+
+    let author = app.author_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/notes/private",
+            None,
+            &[("cookie", &author)],
+        )
+        .await;
+    assert_eq!(res.body[0]["slug"], "diary");
+    let res = app
+        .request(
+            Method::GET,
+            "/api/notes/private/diary",
+            None,
+            &[("cookie", &author)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["content"], "Nothing happened today.");
+}
+
+#[tokio::test]
+async fn private_notes_need_a_key() {
+    let app = spawn(None).await;
+    assert_eq!(
+        app.get("/api/notes/diary").await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let author = app.author_cookie();
+    let res = app
+        .request(
+            Method::GET,
+            "/api/notes/private/diary",
+            None,
+            &[("cookie", &author)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_PRIVATE_NOTES_DISABLED");
+}
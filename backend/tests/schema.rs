@@ -0,0 +1,83 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::Value;
+
+/// Fetches the schema published as `name` and checks `body` against it.
+async fn assert_matches(app: &TestApp, name: &str, body: &Value) {
+    let schema = app.get(&format!("/api/schema/{}", name)).await;
+    assert_eq!(schema.status, StatusCode::OK, "schema {}", name);
+    let validator = jsonschema::validator_for(&schema.body).unwrap();
+    let errors: Vec<String> = validator
+        .iter_errors(body)
+        .map(|e| format!("{} at {}", e, e.instance_path))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "{} does not match {}: {:?}",
+        body,
+        name,
+        errors
+    );
+}
+
+#[tokio::test]
+async fn responses_match_their_published_schemas() {
+    let app = TestApp::spawn().await;
+
+    let listed = app.get("/api/schema").await;
+    assert!(listed.body["paginated-articles"].is_string());
+
+    for uri in [
+        "/api/articles",
+        "/api/articles?include_content=true",
+        "/api/articles?tag=rust&limit=1&page=2",
+        "/api/notes",
+    ] {
+        let res = app.get(uri).await;
+        assert_eq!(res.status, StatusCode::OK, "{}", uri);
+        assert!(
+            !res.body["articles"].as_array().unwrap().is_empty(),
+            "{}",
+            uri
+        );
+        assert_matches(&app, "paginated-articles", &res.body).await;
+    }
+    let teasers = app.get("/api/articles").await;
+    assert_matches(&app, "article-teaser", &teasers.body["articles"][0]).await;
+
+    let search = app.get("/api/search?q=rust").await;
+    assert_eq!(search.status, StatusCode::OK);
+    assert!(!search.body["results"].as_array().unwrap().is_empty());
+    assert_matches(&app, "search-results", &search.body).await;
+    let page = app.get("/api/search/page?q=rust").await;
+    assert_matches(&app, "search-page", &page.body).await;
+
+    let missing = app.get("/api/articles/no-such-article").await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+    assert_matches(&app, "error", &missing.body).await;
+
+    let unknown = app.get("/api/schema/no-such-schema").await;
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+    assert_eq!(unknown.body["error_code"], "ERR_SCHEMA_NOT_FOUND");
+    assert_matches(&app, "error", &unknown.body).await;
+}
+
+#[tokio::test]
+async fn schemas_reject_responses_that_break_the_contract() {
+    let app = TestApp::spawn().await;
+    let mut res = app.get("/api/articles").await;
+    res.body["articles"][0]["metadata"]
+        .as_object_mut()
+        .unwrap()
+        .remove("title");
+    let schema = app.get("/api/schema/paginated-articles").await;
+    let validator = jsonschema::validator_for(&schema.body).unwrap();
+    assert!(!validator.is_valid(&res.body));
+
+    let schema = app.get("/api/schema/error").await;
+    let validator = jsonschema::validator_for(&schema.body).unwrap();
+    let made_up = serde_json::json!({ "error_code": "ERR_MADE_UP", "message": "" });
+    assert!(!validator.is_valid(&made_up));
+}
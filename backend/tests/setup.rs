@@ -0,0 +1,96 @@
+use axum::routing::post;
+use axum::{Form, Json, Router};
+use scribe_backend::setup::{SetupOptions, hash_admin_token, run_setup};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Answers token exchanges like GitHub: a made-up code is only blamed once
+/// the client secret is `right`. Returns the token URL.
+async fn spawn_github() -> String {
+    let app = Router::new().route(
+        "/login/oauth/access_token",
+        post(|Form(form): Form<HashMap<String, String>>| async move {
+            let error = if form["client_secret"] == "right" {
+                "bad_verification_code"
+            } else {
+                "incorrect_client_credentials"
+            };
+            Json::<Value>(json!({ "error": error }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}/login/oauth/access_token", addr)
+}
+
+async fn setup(env_path: &Path, token_url: &str, answers: &str) -> String {
+    let mut output = Vec::new();
+    let options = SetupOptions {
+        env_path,
+        redirect_url: Some("http://localhost:3000/api/auth/github/callback"),
+        token_url,
+        http: reqwest::Client::new(),
+    };
+    run_setup(&mut answers.as_bytes(), &mut output, options)
+        .await
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+fn env(path: &Path) -> HashMap<String, String> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn setup_writes_the_secrets_and_checks_github_credentials() {
+    let token_url = spawn_github().await;
+    let dir = TempDir::new().unwrap();
+    let env_path = dir.path().join(".env");
+
+    // Generated admin token, then a wrong secret that is not kept.
+    let output = setup(
+        &env_path,
+        &token_url,
+        "\nferris\ny\nid\nwrong\nn\nid\nright\n",
+    )
+    .await;
+    assert!(output.contains("GitHub does not know this client id and secret"));
+    assert!(output.contains("GitHub accepted the client id and secret."));
+    // Answers are not echoed, so the token follows its prompt.
+    let token = output
+        .lines()
+        .find_map(|line| line.split_once("Your admin token is "))
+        .map(|(_, token)| token)
+        .unwrap();
+    let first = env(&env_path);
+    assert_eq!(first["ADMIN_TOKEN_HASH"], hash_admin_token(token));
+    assert_eq!(first["COOKIE_SECRET"].len(), 128);
+    assert_eq!(first["AUTHOR_GITHUB_USERNAME"], "ferris");
+    assert_eq!(first["GITHUB_CLIENT_ID"], "id");
+    assert_eq!(first["GITHUB_CLIENT_SECRET"], "right");
+
+    // Running it again keeps everything by default.
+    std::fs::write(
+        &env_path,
+        format!(
+            "{}NOTES_ENCRYPTION_KEY=abc\n",
+            std::fs::read_to_string(&env_path).unwrap()
+        ),
+    )
+    .unwrap();
+    setup(&env_path, &token_url, "\n\n\nn\n").await;
+    let second = env(&env_path);
+    assert_eq!(second["COOKIE_SECRET"], first["COOKIE_SECRET"]);
+    assert_eq!(second["ADMIN_TOKEN_HASH"], first["ADMIN_TOKEN_HASH"]);
+    assert_eq!(second["AUTHOR_GITHUB_USERNAME"], "ferris");
+    assert_eq!(second["GITHUB_CLIENT_SECRET"], "right");
+    assert_eq!(second["NOTES_ENCRYPTION_KEY"], "abc");
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use common::{ADMIN_TOKEN, TestApp};
+use serde_json::{Value, json};
+
+/// Serves a chat-completions endpoint that answers every request with the
+/// model name it was asked for, and returns its base URL.
+async fn spawn_llm() -> String {
+    let app = Router::new().route(
+        "/v1/chat/completions",
+        post(|Json(body): Json<Value>| async move {
+            Json(json!({
+                "choices": [{
+                    "message": { "content": format!("\"Summary from {}.\"", body["model"]) }
+                }]
+            }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{}/v1", addr)
+}
+
+async fn summarize(app: &TestApp, slug: &str, body: Value) -> common::TestResponse {
+    app.request(
+        Method::POST,
+        &format!("/api/admin/articles/{}/summarize", slug),
+        Some(body),
+        &[("authorization", ADMIN_TOKEN)],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn summaries_require_configuration() {
+    let app = TestApp::spawn().await;
+    let res = summarize(&app, "hello-world", json!({})).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_SUMMARIZER_DISABLED");
+}
+
+#[tokio::test]
+async fn generated_summaries_are_saved_after_confirmation() {
+    let endpoint = spawn_llm().await;
+    let app = TestApp::spawn_with(&format!(
+        "[summarizer]\nendpoint = \"{}\"\nmodel = \"tiny\"\n",
+        endpoint
+    ))
+    .await;
+
+    let res = summarize(&app, "hello-world", json!({})).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["error_code"], "ERR_DESCRIPTION_EXISTS");
+
+    let res = summarize(&app, "hello-world", json!({ "force": true })).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["description"], "Summary from \"tiny\".");
+    assert_eq!(res.body["saved"], false);
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(
+        res.body["metadata"]["description"],
+        "The first fixture article"
+    );
+
+    let res = summarize(
+        &app,
+        "hello-world",
+        json!({ "description": " ", "force": true }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let res = summarize(
+        &app,
+        "hello-world",
+        json!({ "description": "An edited summary.", "force": true }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["saved"], true);
+
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.body["metadata"]["description"], "An edited summary.");
+    assert!(res.body["content"].as_str().unwrap().contains("ferris"));
+    let file = std::fs::read_to_string(app.dir.path().join("article/hello-world.md")).unwrap();
+    assert!(file.contains("An edited summary."));
+}
+
+#[tokio::test]
+async fn missing_descriptions_are_derived_and_backfilled() {
+    let app = TestApp::spawn().await;
+    let path = app.dir.path().join("article").join("no-description.md");
+    std::fs::write(
+        &path,
+        "---\ntitle: \"No Description\"\nauthor: \"Scribe\"\ndate: 2024-02-01T00:00:00Z\n---\n\n\
+         ## Intro\n\nImported from an old blog without a summary.\n\nSecond paragraph.\n",
+    )
+    .unwrap();
+    app.state
+        .store
+        .write()
+        .await
+        .update_single_article(&path.to_string_lossy())
+        .unwrap();
+
+    let res = app.get("/api/articles/no-description").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(
+        res.body["metadata"]["description"],
+        "Imported from an old blog without a summary."
+    );
+    assert_eq!(res.body["metadata"]["description_generated"], true);
+    let hello = app.get("/api/articles/hello-world").await;
+    assert!(
+        hello.body["metadata"]
+            .get("description_generated")
+            .is_none()
+    );
+
+    let lint = app
+        .request(
+            Method::GET,
+            "/api/admin/content/lint",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    let flagged: Vec<&Value> = lint
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|l| l["auto_description"] == true)
+        .collect();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0]["slug"], "no-description");
+
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/descriptions/backfill",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["backfilled"][0]["slug"], "no-description");
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        written.contains("description: Imported from an old blog without a summary."),
+        "{}",
+        written
+    );
+    assert!(!written.contains("description_generated"));
+
+    let res = app.get("/api/articles/no-description").await;
+    assert!(res.body["metadata"].get("description_generated").is_none());
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/articles/descriptions/backfill",
+            None,
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.body["backfilled"], json!([]));
+}
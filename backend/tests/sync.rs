@@ -0,0 +1,67 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use scribe_backend::config::{ARTICLE_DIR, PAGES_DIR};
+use scribe_backend::services::sync_service::pull;
+
+const NEW_POST: &str = "---\ntitle: \"From the primary\"\nauthor: \"Scribe\"\ndate: 2024-06-01T00:00:00Z\ndescription: \"\"\ntags: []\n---\n\nWritten on the primary.\n";
+
+/// Serves `app` on a local port and returns its base URL.
+async fn serve(app: &TestApp) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = app.router.clone();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn replicas_pull_content_from_their_primary() {
+    // SAFETY: set before either app reads it; this is the only test here.
+    unsafe { std::env::set_var("SYNC_SECRET", "shared-secret") };
+    let primary = TestApp::spawn().await;
+    let url = serve(&primary).await;
+    let replica = TestApp::spawn_with(&format!(
+        "read_only = true\n\n[sync]\nprimary = \"{}\"",
+        url
+    ))
+    .await;
+
+    let res = primary.get("/api/sync/changes").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = primary
+        .request(
+            Method::GET,
+            "/api/sync/changes",
+            None,
+            &[("authorization", "Bearer wrong")],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let articles = primary.dir.path().join(ARTICLE_DIR);
+    std::fs::write(articles.join("from-primary.md"), NEW_POST).unwrap();
+    std::fs::remove_file(articles.join("hello-world.md")).unwrap();
+    let pages = primary.dir.path().join(PAGES_DIR);
+    std::fs::rename(pages.join("about.md"), pages.join("contact.md")).unwrap();
+
+    let report = pull(&replica.state).await.unwrap();
+    assert!(report.written >= 1, "{report:?}");
+    assert_eq!(report.removed, 2);
+    let res = replica.get("/api/articles/from-primary").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = replica.get("/api/articles/hello-world").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    // Drafts are copied too, and stay hidden on the replica.
+    let unpublished = replica.dir.path().join(ARTICLE_DIR).join("unpublished.md");
+    assert!(unpublished.exists());
+    let res = replica.get("/api/pages/contact").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = replica.get("/api/pages/about").await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    // Nothing changed since, so the next pull has nothing to do.
+    let report = pull(&replica.state).await.unwrap();
+    assert_eq!((report.written, report.removed), (0, 0));
+}
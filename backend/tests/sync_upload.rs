@@ -0,0 +1,112 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::services::sync_service::content_hash;
+use serde_json::json;
+use std::fs;
+
+const NEW_POST: &str = "---\ntitle: \"Written offline\"\nauthor: \"Scribe\"\ndate: 2024-06-01T00:00:00Z\ndescription: \"\"\ntags: []\n---\n\nWritten on a train.\n";
+
+#[tokio::test]
+async fn editing_clients_sync_against_the_manifest() {
+    let app = TestApp::spawn().await;
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(Method::GET, "/api/admin/sync/manifest", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let articles = &res.body["articles"];
+    assert_eq!(
+        articles["guides/axum-routing"]["path"],
+        "guides/axum-routing.md"
+    );
+    // Drafts are part of the working copy too.
+    assert!(articles["unpublished"].is_object());
+    let hello = &articles["hello-world"];
+    let base_hash = hello["hash"].as_str().unwrap().to_string();
+    assert_eq!(hello["version"], 0);
+
+    let edited = fs::read_to_string(app.dir.path().join("article/hello-world.md"))
+        .unwrap()
+        .replace("Welcome to the fixture blog.", "Welcome back.");
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/sync/upload",
+            Some(json!({ "files": [
+                { "path": "hello-world.md", "base_hash": base_hash, "content": edited },
+                { "path": "offline.md", "base_hash": null, "content": NEW_POST },
+                { "path": "unpublished.md", "base_hash": "stale", "content": NEW_POST },
+                { "path": "../escape.md", "base_hash": null, "content": NEW_POST },
+                { "path": "broken.md", "base_hash": null, "content": "no front matter" },
+            ] })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let applied = res.body["applied"].as_array().unwrap();
+    assert_eq!(applied.len(), 2, "{}", res.body);
+    assert_eq!(applied[0]["hash"], content_hash(edited.as_bytes()));
+    assert!(applied[0]["version"].as_u64().unwrap() > 0);
+    assert_eq!(applied[1]["path"], "offline.md");
+    assert_eq!(
+        res.body["conflicts"],
+        json!([{
+            "path": "unpublished.md",
+            "base_hash": "stale",
+            "server_hash": res.body["conflicts"][0]["server_hash"],
+        }])
+    );
+    let rejected: Vec<_> = res.body["rejected"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(rejected, ["../escape.md", "broken.md"]);
+    assert!(!app.dir.path().join("article/broken.md").exists());
+
+    let res = app.get("/api/articles/hello-world").await;
+    assert!(
+        res.body["content"]
+            .as_str()
+            .unwrap()
+            .contains("Welcome back.")
+    );
+    let res = app.get("/api/articles/offline").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["metadata"]["title"], "Written offline");
+
+    // Uploading from the old hash again is a conflict now.
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/sync/upload",
+            Some(json!({ "files": [
+                { "path": "hello-world.md", "base_hash": base_hash, "content": "stale" },
+            ] })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.body["conflicts"][0]["path"], "hello-world.md");
+}
+
+#[tokio::test]
+async fn syncing_needs_the_admin_token() {
+    let app = TestApp::spawn().await;
+    assert_eq!(
+        app.get("/api/admin/sync/manifest").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/sync/upload",
+            Some(json!({ "files": [] })),
+            &[("authorization", ADMIN_TOKEN)],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
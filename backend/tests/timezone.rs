@@ -0,0 +1,44 @@
+mod common;
+
+use common::TestApp;
+use scribe_backend::config::ARTICLE_DIR;
+
+fn article(date: &str, extra: &str) -> String {
+    format!(
+        "---\ntitle: \"Post\"\nauthor: \"Scribe\"\ndate: {date}\n{extra}description: \"\"\ntags: []\ndraft: false\n---\n\nBody\n"
+    )
+}
+
+/// Dates written without an offset are local to the configured timezone,
+/// so an early-morning post is not filed under the previous UTC day.
+#[tokio::test]
+async fn dates_are_local_to_the_site_timezone() {
+    let app = TestApp::spawn_with("timezone = \"Asia/Shanghai\"").await;
+    let articles = app.dir.path().join(ARTICLE_DIR);
+    std::fs::write(articles.join("early.md"), article("2024-05-01 07:00", "")).unwrap();
+    std::fs::write(articles.join("late.md"), article("2024-05-01T23:30:00", "")).unwrap();
+    std::fs::write(
+        articles.join("moved.md"),
+        article("2024-04-30T20:00:00Z", "display_date: 2024-06-01\n"),
+    )
+    .unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+
+    let res = app.get("/api/articles/early").await;
+    assert_eq!(res.body["metadata"]["date"], "2024-04-30T23:00:00Z");
+    let res = app.get("/api/articles/moved").await;
+    assert_eq!(res.body["metadata"]["display_date"], "2024-06-01");
+
+    let calendar = app.get("/api/stats/calendar?year=2024").await.body;
+    let count = |date: &str| {
+        calendar["days"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|day| day["date"] == date)
+            .map_or(0, |day| day["count"].as_u64().unwrap())
+    };
+    assert_eq!(count("2024-04-30"), 0, "{calendar}");
+    assert_eq!(count("2024-05-01"), 2);
+    assert_eq!(count("2024-06-01"), 1);
+}
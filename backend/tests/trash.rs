@@ -0,0 +1,147 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{ADMIN_TOKEN, TestApp};
+
+#[tokio::test]
+async fn deleted_articles_can_be_previewed_restored_and_purged() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(Method::DELETE, "/api/articles/hello-world", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let id = res.body["trash_id"].as_str().unwrap().to_string();
+    assert_eq!(
+        app.get("/api/articles/hello-world").await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let res = app
+        .request(Method::GET, "/api/admin/trash", None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let items = res.body.as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], id.as_str());
+    assert_eq!(items[0]["slug"], "hello-world");
+    assert_eq!(items[0]["title"], "Hello World");
+    assert_eq!(items[0]["deleted_by"], "author");
+    assert!(items[0]["size"].as_u64().unwrap() > 0);
+
+    let uri = format!("/api/admin/trash/{}", id);
+    let res = app.request(Method::GET, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert!(
+        res.body["content"]
+            .as_str()
+            .unwrap()
+            .contains("Welcome to the fixture blog.")
+    );
+
+    let res = app
+        .request(Method::POST, &format!("{}/restore", uri), None, &admin)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/api/articles/hello-world").await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["metadata"]["title"], "Hello World");
+
+    let res = app
+        .request(Method::DELETE, "/api/articles/hello-world", None, &auth)
+        .await;
+    let uri = format!(
+        "/api/admin/trash/{}",
+        res.body["trash_id"].as_str().unwrap()
+    );
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.request(Method::GET, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn deleting_a_slug_again_keeps_the_earlier_copy() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let auth = [("cookie", cookie.as_str())];
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let file = app.dir.path().join("article/hello-world.md");
+    let original = std::fs::read_to_string(&file).unwrap();
+
+    app.request(Method::DELETE, "/api/articles/hello-world", None, &auth)
+        .await;
+    std::fs::write(&file, original.replace("Hello World", "Hello Again")).unwrap();
+    app.state.store.write().await.incremental_update().unwrap();
+    let res = app
+        .request(Method::DELETE, "/api/articles/hello-world", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    let res = app
+        .request(Method::GET, "/api/admin/trash", None, &admin)
+        .await;
+    let mut titles: Vec<_> = res
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["title"].as_str().unwrap())
+        .collect();
+    titles.sort();
+    assert_eq!(titles, ["Hello Again", "Hello World"]);
+}
+
+#[tokio::test]
+async fn article_deletions_can_be_undone() {
+    let app = TestApp::spawn().await;
+    let cookie = app.author_cookie();
+    let admin = [("authorization", ADMIN_TOKEN)];
+
+    let res = app
+        .request(
+            Method::DELETE,
+            "/api/articles/guides%2Faxum-routing",
+            None,
+            &[("cookie", cookie.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    assert_eq!(res.body["slug"], "guides/axum-routing");
+    assert!(res.body["undo_expires_in"].as_u64().unwrap() > 0);
+    let undo = format!(
+        "/api/admin/undo/{}",
+        res.body["undo_token"].as_str().unwrap()
+    );
+
+    let res = app.request(Method::POST, &undo, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app.get("/api/articles/guides%2Faxum-routing").await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let res = app
+        .request(Method::GET, "/api/admin/trash", None, &admin)
+        .await;
+    assert_eq!(res.body, serde_json::json!([]));
+    app.state.index_tx.as_ref().unwrap().flush().await;
+    let res = app.get("/api/search?q=extractors").await;
+    assert_eq!(res.body["results"][0]["slug"], "guides/axum-routing");
+
+    let res = app.request(Method::POST, &undo, None, &admin).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn trash_needs_the_admin_token() {
+    let app = TestApp::spawn().await;
+    assert_eq!(
+        app.get("/api/admin/trash").await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    let res = app
+        .request(Method::DELETE, "/api/articles/hello-world", None, &[])
+        .await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
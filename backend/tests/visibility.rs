@@ -0,0 +1,302 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{ADMIN_TOKEN, TestApp};
+use scribe_backend::models::user::User;
+use serde_json::json;
+use tempfile::TempDir;
+
+const SCRATCH: &str = "---\ntitle: \"Scratch\"\nauthor: \"Scribe\"\ndate: 2024-03-02T00:00:00Z\ndescription: \"Hidden until it is done\"\ntags: [\"til\"]\ndraft: true\n---\n\nHalf a thought.\n";
+const CLUB: &str = "---\ntitle: \"Club Notes\"\nauthor: \"Scribe\"\ndate: 2024-03-04T00:00:00Z\ndescription: \"For members\"\nmembers_only: true\n---\n\nThe password is swordfish.\n";
+const DIARY: &str = "---\ntitle: \"Diary\"\nauthor: \"Scribe\"\ndate: 2024-03-03T00:00:00Z\ndescription: \"Hidden from everyone but the author\"\nprivate: true\n---\n\nNothing happened today.\n";
+
+/// The fixtures plus a draft and a private note, with the `guides`
+/// category restricted to signed-in readers.
+async fn spawn() -> TestApp {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "restricted_categories = [\"guides/\"]").await;
+    std::fs::write(dir.path().join("notes/scratch.md"), SCRATCH).unwrap();
+    std::fs::write(dir.path().join("notes/diary.md"), DIARY).unwrap();
+    let app = builder.build().await.unwrap();
+    TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    }
+}
+
+/// Surfaces that list content. None of them may show a draft or a private
+/// note, and only signed-in readers see the restricted article.
+const LISTINGS: &[&str] = &[
+    "/api/articles?include_content=true",
+    "/api/articles?include_content=true&stream=true",
+    "/api/v1/articles?include_content=true",
+    "/api/notes?include_content=true",
+    "/api/v1/notes?include_content=true",
+    "/api/search?q=hidden",
+    "/api/search?q=extractors",
+    "/sitemap.xml",
+];
+
+/// Surfaces that serve one entry, and whether a signed-in reader may open
+/// it.
+const DETAILS: &[(&str, bool)] = &[
+    ("/api/articles/unpublished", false),
+    ("/api/articles/unpublished/text", false),
+    ("/api/articles/unpublished/versions", false),
+    ("/api/articles/unpublished/changelog", false),
+    ("/api/articles/unpublished/meta", false),
+    ("/api/notes/scratch", false),
+    ("/api/notes/diary", false),
+    ("/api/articles/axum-routing", true),
+    ("/api/articles/axum-routing/text", true),
+    ("/api/articles/axum-routing/versions", true),
+    ("/api/articles/axum-routing/changelog", true),
+    ("/api/articles/axum-routing/meta", true),
+];
+
+#[tokio::test]
+async fn drafts_private_notes_and_restrictions_apply_on_every_surface() {
+    let app = spawn().await;
+    app.state.index_tx.as_ref().unwrap().flush().await;
+    let cookie = app.visitor_cookie();
+
+    for signed_in in [false, true] {
+        let headers: &[(&str, &str)] = if signed_in {
+            &[("cookie", cookie.as_str())]
+        } else {
+            &[]
+        };
+
+        for uri in LISTINGS {
+            let res = app.request(Method::GET, uri, None, headers).await;
+            assert_eq!(res.status, StatusCode::OK, "{uri}");
+            let body = res.body.to_string();
+            for hidden in ["unpublished", "scratch", "diary"] {
+                assert!(!body.contains(hidden), "{uri} shows {hidden}");
+            }
+            if !signed_in {
+                assert!(!body.contains("axum-routing"), "{uri} shows axum-routing");
+            }
+        }
+
+        for (uri, shown_when_signed_in) in DETAILS {
+            let res = app.request(Method::GET, uri, None, headers).await;
+            let expected = if signed_in && *shown_when_signed_in {
+                StatusCode::OK
+            } else {
+                StatusCode::NOT_FOUND
+            };
+            assert_eq!(res.status, expected, "{uri}, signed in: {signed_in}");
+        }
+
+        let res = app
+            .request(Method::GET, "/api/stats/calendar?year=2024", None, headers)
+            .await;
+        let days = res.body["days"].as_array().unwrap().len();
+        assert_eq!(days, if signed_in { 2 } else { 1 });
+    }
+
+    // Signed-in readers do get the restricted article in listings.
+    let auth = [("cookie", cookie.as_str())];
+    for uri in ["/api/articles", "/sitemap.xml"] {
+        let res = app.request(Method::GET, uri, None, &auth).await;
+        assert!(res.body.to_string().contains("axum-routing"), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn public_api_ignores_credentials() {
+    let app = spawn().await;
+    let cookie = app.visitor_cookie();
+    let auth = [("cookie", cookie.as_str())];
+
+    let res = app
+        .request(Method::GET, "/api/public/articles", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(!res.body.to_string().contains("axum-routing"));
+    let res = app
+        .request(
+            Method::GET,
+            "/api/public/articles/axum-routing",
+            None,
+            &auth,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    let res = app
+        .request(Method::GET, "/api/public/notes/scratch", None, &auth)
+        .await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn members_only_articles_need_an_allowlisted_user_or_an_access_code() {
+    let dir = TempDir::new().unwrap();
+    let builder = common::builder(&dir, "[members]\nallowlist = [\"Friend\"]").await;
+    std::fs::write(dir.path().join("article/club.md"), CLUB).unwrap();
+    std::fs::write(dir.path().join("pages/club.md"), CLUB).unwrap();
+    std::fs::write(dir.path().join("notes/club.md"), CLUB).unwrap();
+    let app = builder.build().await.unwrap();
+    let app = TestApp {
+        router: app.router(),
+        state: app.state(),
+        dir,
+    };
+    app.state.index_tx.as_ref().unwrap().flush().await;
+
+    let friend = app.session_cookie(&User::new(3, "friend".to_string(), false));
+    let visitor = app.visitor_cookie();
+    let is_unlocked = |cookie: Option<String>| {
+        let app = &app;
+        async move {
+            let headers = cookie
+                .iter()
+                .map(|c| ("cookie", c.as_str()))
+                .collect::<Vec<_>>();
+            let res = app
+                .request(Method::GET, "/api/articles/club", None, &headers)
+                .await;
+            assert_eq!(res.status, StatusCode::OK);
+            assert_eq!(res.body["metadata"]["title"], "Club Notes");
+            let unlocked = res.body["content"].as_str().unwrap().contains("swordfish");
+            assert_eq!(res.body["locked"].as_bool().unwrap_or(false), !unlocked);
+            unlocked
+        }
+    };
+    assert!(!is_unlocked(None).await);
+    assert!(!is_unlocked(Some(visitor.clone())).await);
+    assert!(is_unlocked(Some(friend.clone())).await);
+
+    // The body does not leak through other surfaces.
+    for uri in [
+        "/api/articles?include_content=true",
+        "/api/articles?include_content=true&stream=true",
+        "/api/notes?include_content=true",
+    ] {
+        let res = app.get(uri).await;
+        assert_eq!(res.status, StatusCode::OK, "{uri}");
+        assert!(!res.body.to_string().contains("swordfish"), "{uri}");
+    }
+    // A search hit would tell what the body says.
+    let res = app.get("/api/search?q=swordfish").await;
+    assert_eq!(res.body["total_found"], 0);
+    assert!(
+        app.get("/api/search?q=club")
+            .await
+            .body
+            .to_string()
+            .contains("club")
+    );
+    for uri in ["/api/articles/club/text", "/api/articles/club/versions"] {
+        let res = app.get(uri).await;
+        assert_eq!(res.status, StatusCode::FORBIDDEN, "{uri}");
+        assert_eq!(res.body["error_code"], "ERR_MEMBERS_ONLY", "{uri}");
+    }
+    let res = app
+        .request(
+            Method::GET,
+            "/api/articles/club/text",
+            None,
+            &[("cookie", friend.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    for uri in ["/api/pages/club", "/api/notes/club"] {
+        let res = app.get(uri).await;
+        assert_eq!(res.body["error_code"], "ERR_MEMBERS_ONLY", "{uri}");
+    }
+    let calendar = "/api/stats/calendar?year=2024&words=true";
+    let words = |body: &serde_json::Value| {
+        body["days"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|day| day["date"] == "2024-03-04")
+            .map(|day| day["words"].as_u64().unwrap())
+    };
+    assert_eq!(words(&app.get(calendar).await.body), Some(0));
+    let res = app
+        .request(Method::GET, calendar, None, &[("cookie", friend.as_str())])
+        .await;
+    assert!(words(&res.body).unwrap() > 0);
+    // Whether a suggestion matches would tell what the body says.
+    let suggestion = json!({ "original": "swordfish", "replacement": "marlin" });
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/club/suggestions",
+            Some(suggestion.clone()),
+            &[("cookie", visitor.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.body["error_code"], "ERR_MEMBERS_ONLY");
+    let res = app
+        .request(
+            Method::POST,
+            "/api/articles/club/suggestions",
+            Some(suggestion),
+            &[("cookie", friend.as_str())],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+
+    // Codes are issued by the admin and redeemed for a membership cookie.
+    let admin = [("authorization", ADMIN_TOKEN)];
+    let res = app
+        .request(
+            Method::POST,
+            "/api/admin/access-codes",
+            Some(json!({ "name": "workshop", "max_redemptions": 1 })),
+            &admin,
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    let id = res.body["id"].as_i64().unwrap();
+    let code = res.body["code"].as_str().unwrap().to_string();
+
+    let redeem = json!({ "code": code.to_lowercase() });
+    let res = app
+        .request(
+            Method::POST,
+            "/api/access-codes/redeem",
+            Some(redeem.clone()),
+            &[],
+        )
+        .await;
+    assert_eq!(res.status, StatusCode::OK);
+    let member = res.headers["set-cookie"]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    assert!(member.starts_with("member_access="));
+    assert!(is_unlocked(Some(member.clone())).await);
+
+    let res = app
+        .request(Method::POST, "/api/access-codes/redeem", Some(redeem), &[])
+        .await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["error_code"], "ERR_INVALID_ACCESS_CODE");
+
+    let res = app.get("/api/admin/access-codes").await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app
+        .request(Method::GET, "/api/admin/access-codes", None, &admin)
+        .await;
+    assert_eq!(res.body[0]["redemptions"], 1);
+    assert!(res.body[0].get("code").is_none());
+
+    // Revoking the code ends the membership.
+    let uri = format!("/api/admin/access-codes/{id}");
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(!is_unlocked(Some(member)).await);
+    let res = app.request(Method::DELETE, &uri, None, &admin).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.body["error_code"], "ERR_ACCESS_CODE_NOT_FOUND");
+}
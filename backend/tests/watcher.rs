@@ -0,0 +1,98 @@
+mod common;
+
+use scribe_backend::config::ARTICLE_DIR;
+use scribe_backend::services::audit_service::{ARTICLE_DELETED, recent_audit};
+use std::time::Duration;
+use tempfile::TempDir;
+
+const BULK_FILES: usize = 2_000;
+
+/// Writing many files at once, as a `git checkout` does, is picked up in
+/// full once the burst settles.
+#[tokio::test(flavor = "multi_thread")]
+async fn bulk_file_changes_are_loaded_once_they_settle() {
+    let dir = TempDir::new().unwrap();
+    let scribe = common::builder(&dir, "").await.build().await.unwrap();
+    scribe.spawn_background_tasks();
+    let store = scribe.article_store();
+    let initial = store.read().await.query(|_| true, 0, usize::MAX).count();
+    // Give the watcher time to start before the burst.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let bulk = dir.path().join(ARTICLE_DIR).join("bulk");
+    std::fs::create_dir(&bulk).unwrap();
+    for i in 0..BULK_FILES {
+        std::fs::write(
+            bulk.join(format!("post-{i}.md")),
+            format!(
+                "---\ntitle: \"Post {i}\"\nauthor: \"Scribe\"\ndate: 2024-05-01T00:00:00Z\ndescription: \"\"\ntags: []\ndraft: false\n---\n\nBody {i}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    let mut count = initial;
+    for _ in 0..300 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        count = store.read().await.query(|_| true, 0, usize::MAX).count();
+        if count == initial + BULK_FILES {
+            break;
+        }
+    }
+    assert_eq!(count, initial + BULK_FILES);
+}
+
+/// Editing `collections.yaml` replaces the reading lists without a restart.
+#[tokio::test(flavor = "multi_thread")]
+async fn collections_reload_when_their_file_changes() {
+    let dir = TempDir::new().unwrap();
+    let scribe = common::builder(&dir, "").await.build().await.unwrap();
+    scribe.spawn_background_tasks();
+    let state = scribe.state();
+    assert_eq!(state.collections.all().len(), 2);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    std::fs::write(
+        dir.path().join("collections.yaml"),
+        "- name: rust\n  title: Rust\n  articles: [axum-routing]\n",
+    )
+    .unwrap();
+
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if state.collections.get("rust").is_some() {
+            break;
+        }
+    }
+    assert_eq!(
+        state.collections.get("rust").unwrap().articles,
+        ["axum-routing"]
+    );
+    assert!(state.collections.get("start-here").is_none());
+}
+
+/// Removing an article's file records its deletion for the content diff.
+#[tokio::test(flavor = "multi_thread")]
+async fn removed_articles_are_recorded_as_deleted() {
+    let dir = TempDir::new().unwrap();
+    let scribe = common::builder(&dir, "").await.build().await.unwrap();
+    scribe.spawn_background_tasks();
+    let state = scribe.state();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    std::fs::remove_file(dir.path().join(ARTICLE_DIR).join("hello-world.md")).unwrap();
+
+    let mut deleted = Vec::new();
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        deleted = recent_audit(&state.db, Some(ARTICLE_DELETED), 10)
+            .await
+            .unwrap();
+        if !deleted.is_empty() {
+            break;
+        }
+    }
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].target, "hello-world");
+    assert_eq!(deleted[0].detail.as_deref(), Some("Hello World"));
+}
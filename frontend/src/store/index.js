@@ -43,7 +43,7 @@ export const useMainStore = defineStore('main', {
 
     async fetchArticle(slug) {
       const api = useApi()
-      await api.request(`/api/articles/${slug}`)
+      await api.request(`/api/articles/${encodeURIComponent(slug)}`)
       if (!api.error.value) {
         this.currentArticle = api.data.value
       } else {